@@ -59,15 +59,21 @@ class ModelInfo {
   final String siteName;
   final ModelStats stats;
 
+  /// Whether the model has elements to draw; a spatial-only file (project,
+  /// site, building and storeys) renders nothing
+  final bool hasGeometry;
+
   const ModelInfo({
     required this.projectName,
     required this.buildingName,
     required this.siteName,
     required this.stats,
+    required this.hasGeometry,
   });
 
   @override
-  int get hashCode => projectName.hashCode ^ buildingName.hashCode ^ siteName.hashCode ^ stats.hashCode;
+  int get hashCode =>
+      projectName.hashCode ^ buildingName.hashCode ^ siteName.hashCode ^ stats.hashCode ^ hasGeometry.hashCode;
 
   @override
   bool operator ==(Object other) =>
@@ -77,7 +83,8 @@ class ModelInfo {
           projectName == other.projectName &&
           buildingName == other.buildingName &&
           siteName == other.siteName &&
-          stats == other.stats;
+          stats == other.stats &&
+          hasGeometry == other.hasGeometry;
 }
 
 /// Model statistics
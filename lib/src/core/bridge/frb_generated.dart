@@ -2133,12 +2133,13 @@ class RustLibApiImpl extends RustLibApiImplPlatform implements RustLibApi {
   ModelInfo dco_decode_model_info(dynamic raw) {
     // Codec=Dco (DartCObject based), see doc to use other codecs
     final arr = raw as List<dynamic>;
-    if (arr.length != 4) throw Exception('unexpected arr length: expect 4 but see ${arr.length}');
+    if (arr.length != 5) throw Exception('unexpected arr length: expect 5 but see ${arr.length}');
     return ModelInfo(
       projectName: dco_decode_String(arr[0]),
       buildingName: dco_decode_String(arr[1]),
       siteName: dco_decode_String(arr[2]),
       stats: dco_decode_model_stats(arr[3]),
+      hasGeometry: dco_decode_bool(arr[4]),
     );
   }
 
@@ -2488,8 +2489,13 @@ class RustLibApiImpl extends RustLibApiImplPlatform implements RustLibApi {
     var var_buildingName = sse_decode_String(deserializer);
     var var_siteName = sse_decode_String(deserializer);
     var var_stats = sse_decode_model_stats(deserializer);
+    var var_hasGeometry = sse_decode_bool(deserializer);
     return ModelInfo(
-        projectName: var_projectName, buildingName: var_buildingName, siteName: var_siteName, stats: var_stats);
+        projectName: var_projectName,
+        buildingName: var_buildingName,
+        siteName: var_siteName,
+        stats: var_stats,
+        hasGeometry: var_hasGeometry);
   }
 
   @protected
@@ -2821,6 +2827,7 @@ class RustLibApiImpl extends RustLibApiImplPlatform implements RustLibApi {
     sse_encode_String(self.buildingName, serializer);
     sse_encode_String(self.siteName, serializer);
     sse_encode_model_stats(self.stats, serializer);
+    sse_encode_bool(self.hasGeometry, serializer);
   }
 
   @protected
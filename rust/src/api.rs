@@ -58,10 +58,18 @@ pub fn test_error_handling(should_fail: bool) -> Result<String, String> {
 // Phase 2 API: BIM File Parsing
 // ============================================================================
 
-use crate::bim::{BimModel, ElementInfo, GridLine, IfcFile, ModelInfo, ModelRegistry, RegisteredModelInfo};
-use crate::renderer::ray_aabb_intersect;
+use crate::bim::{
+    coordinates, default_palette, AttributeView, diff, set_tessellation_tolerance, ExportPrecision, GltfExport, tessellation_tolerance, BimModel, BoundsInfo, ElementInfo, ElementMesh, ExplodeMode, GridLine, IfcFile, IfcHeader, Legend, LoadOptions, LocalOrigin, MaterialInfo,
+    MaterialPalette, MaterialRegion, ModelDiff, ModelInfo, ModelMesh, ModelOutline, ModelRegistry, ModelValidation, PickMesh, PolylineMeasurement, RegisteredModel,
+    RegisteredModelInfo, TessellatedGeometry, TypeBudget, UpAxis,
+    WorldPoint,
+};
+use crate::frb_generated::StreamSink;
+use crate::renderer::{
+    box_fully_clipped, Annotation, section_plane_at_hit, section_plane_axis, Axis, SectionSweep,
+};
 use glam::Vec3;
-use std::sync::{LazyLock, Mutex};
+use std::sync::{Arc, LazyLock, Mutex};
 
 // Global model registry (supports multiple models)
 static MODEL_REGISTRY: LazyLock<Mutex<ModelRegistry>> =
@@ -71,15 +79,269 @@ static MODEL_REGISTRY: LazyLock<Mutex<ModelRegistry>> =
 static VISIBILITY: LazyLock<Mutex<std::collections::HashSet<String>>> =
     LazyLock::new(|| Mutex::new(std::collections::HashSet::new()));
 
+// Hidden presentation layers (by layer name)
+static HIDDEN_LAYERS: LazyLock<Mutex<std::collections::HashSet<String>>> =
+    LazyLock::new(|| Mutex::new(std::collections::HashSet::new()));
+
+// Hidden elements and element color overrides (by GlobalId)
+static HIDDEN_ELEMENTS: LazyLock<Mutex<std::collections::HashSet<String>>> =
+    LazyLock::new(|| Mutex::new(std::collections::HashSet::new()));
+static ELEMENT_COLORS: LazyLock<Mutex<std::collections::HashMap<String, [f32; 4]>>> =
+    LazyLock::new(|| Mutex::new(std::collections::HashMap::new()));
+
+// Level of detail per element type (share of triangles kept)
+static TYPE_LOD: LazyLock<Mutex<std::collections::HashMap<String, f32>>> =
+    LazyLock::new(|| Mutex::new(std::collections::HashMap::new()));
+
+// Projected size thresholds of automatic LOD (None when off)
+static AUTO_LOD: Mutex<Option<Vec<f32>>> = Mutex::new(None);
+
 // Currently selected element ID (for highlighting)
 static SELECTED_ELEMENT: Mutex<Option<i32>> = Mutex::new(None);
 
 // Grid visibility flag
 static GRID_VISIBLE: LazyLock<Mutex<bool>> = LazyLock::new(|| Mutex::new(true));
 
+// Up axis of the source coordinates of models loaded from now on
+static UP_AXIS: Mutex<UpAxis> = Mutex::new(UpAxis::Z);
+
+/// Whether loading models into the renderer fits the camera to them
+static AUTO_FRAME_ON_LOAD: Mutex<bool> = Mutex::new(true);
+
+/// Build a model from a parsed file with the current import settings
+fn build_model(ifc_file: &IfcFile) -> Result<BimModel, String> {
+    build_model_with_options(ifc_file, &LoadOptions::default())
+}
+
+/// Build the parts of a model `options` asks for with the current import
+/// settings
+fn build_model_with_options(ifc_file: &IfcFile, options: &LoadOptions) -> Result<BimModel, String> {
+    let mut model = BimModel::from_ifc_file_with_options(ifc_file, options, |_| {})?;
+    model.up_axis = *UP_AXIS.lock().unwrap();
+    Ok(model)
+}
+
+/// Run CPU-heavy work (parsing, tessellation) on the runtime's blocking
+/// thread pool, so a multi-second load doesn't stall other async tasks
+async fn run_blocking<T: Send + 'static>(
+    work: impl FnOnce() -> Result<T, String> + Send + 'static,
+) -> Result<T, String> {
+    tokio::task::spawn_blocking(work)
+        .await
+        .map_err(|e| format!("Background task failed: {}", e))?
+}
+
+/// Parse IFC content and build the model, reporting both phases
+fn parse_and_build_model(
+    content: &str,
+    options: &LoadOptions,
+    mut report: impl FnMut(LoadStage),
+) -> Result<BimModel, String> {
+    let ifc_file = IfcFile::parse_with_progress(content, |fraction| {
+        report(LoadStage { stage: LoadPhase::Parsing, fraction: fraction as f64 })
+    })?;
+
+    tracing::info!(
+        "Parsed IFC file: {} entities",
+        ifc_file.entity_count()
+    );
+    for warning in &ifc_file.parse_warnings {
+        tracing::warn!("{}", warning);
+    }
+
+    let mut model = BimModel::from_ifc_file_with_options(&ifc_file, options, |fraction| {
+        report(LoadStage { stage: LoadPhase::Tessellating, fraction: fraction as f64 })
+    })?;
+    model.up_axis = *UP_AXIS.lock().unwrap();
+    Ok(model)
+}
+
+/// Set the up axis of the models' source coordinates (Z for IFC)
+/// Applies to loaded models and future loads; geometry is presented Y-up.
+#[frb(sync)]
+pub fn set_up_axis(axis: UpAxis) -> Result<(), String> {
+    *UP_AXIS.lock().unwrap() = axis;
+
+    let has_models = {
+        let mut registry = MODEL_REGISTRY.lock().unwrap();
+        for id in registry.list_models() {
+            if let Some(reg_model) = registry.get_model_mut(&id) {
+                reg_model.model.up_axis = axis;
+                reg_model.geometry_changed();
+            }
+        }
+        !registry.is_empty()
+    };
+    let renderer_ready = RENDERER.lock().unwrap().is_some();
+    if renderer_ready && has_models {
+        reload_all_models_mesh()?;
+    }
+    Ok(())
+}
+
+/// Get the up axis assumed for the models' source coordinates
+#[frb(sync)]
+pub fn get_up_axis() -> UpAxis {
+    *UP_AXIS.lock().unwrap()
+}
+
+/// Set the tessellation quality: the largest distance (meters) allowed
+/// between curved geometry (revolved solids, pipes, circular profiles) and
+/// its triangles; smaller values give more segments on larger radii.
+/// Models loaded from IFC files are re-tessellated from their source; models
+/// parsed from content, imported meshes and salvaged geometry keep their
+/// geometry until loaded again.
+pub async fn set_tessellation_quality(tolerance: f32) -> Result<(), String> {
+    set_tessellation_tolerance(tolerance)?;
+
+    let sources: Vec<(String, String)> = MODEL_REGISTRY
+        .lock()
+        .unwrap()
+        .iter()
+        .filter(|(_, reg_model)| !reg_model.model.placed_bodies)
+        .filter_map(|(id, reg_model)| Some((id.clone(), reg_model.file_path.clone()?)))
+        .collect();
+    if sources.is_empty() {
+        return Ok(());
+    }
+
+    for (id, path) in sources {
+        let content = tokio::fs::read_to_string(&path)
+            .await
+            .map_err(|e| format!("Failed to read file: {}", e))?;
+        run_blocking(move || {
+            let ifc_file = IfcFile::parse(&content)?;
+            let mut registry = MODEL_REGISTRY.lock().unwrap();
+            if let Some(reg_model) = registry.get_model_mut(&id) {
+                reg_model.model.retessellate(&ifc_file);
+                reg_model.geometry_changed();
+            }
+            Ok(())
+        })
+        .await?;
+    }
+    if RENDERER.lock().unwrap().as_ref().is_some_and(|r| r.gpu.is_initialized()) {
+        reload_all_models_mesh()?;
+    }
+    Ok(())
+}
+
+/// Get the tessellation tolerance of curved geometry (meters)
+#[frb(sync)]
+pub fn get_tessellation_quality() -> f32 {
+    tessellation_tolerance()
+}
+
+/// Phase of loading an IFC file
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LoadPhase {
+    /// Reading STEP entities from the file
+    Parsing,
+    /// Extracting elements and tessellating their geometry
+    Tessellating,
+}
+
+/// Progress of a load: the current phase and how far along it is (0.0-1.0)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LoadStage {
+    pub stage: LoadPhase,
+    pub fraction: f64,
+}
+
+/// File metadata from an IFC header, for previews
+#[derive(Debug, Clone, PartialEq)]
+pub struct IfcHeaderInfo {
+    /// Schema identifiers, comma separated (e.g. "IFC4")
+    pub schema: String,
+    pub file_name: String,
+    pub description: Vec<String>,
+    pub time_stamp: String,
+    pub author: Vec<String>,
+    pub organization: Vec<String>,
+    /// Application that exported the file
+    pub originating_system: String,
+    pub preprocessor_version: String,
+}
+
+impl From<IfcHeader> for IfcHeaderInfo {
+    fn from(header: IfcHeader) -> Self {
+        let named = |names: Vec<String>| names.into_iter().filter(|n| !n.trim().is_empty()).collect();
+        Self {
+            schema: header.schema.join(", "),
+            file_name: header.file_name,
+            description: named(header.file_description),
+            time_stamp: header.time_stamp,
+            author: named(header.author),
+            organization: named(header.organization),
+            originating_system: header.originating_system,
+            preprocessor_version: header.preprocessor_version,
+        }
+    }
+}
+
+/// Most bytes read looking for the end of an IFC header
+const MAX_HEADER_BYTES: usize = 1 << 20;
+
+/// Read an IFC file's header (schema, author, timestamp, originating
+/// system) without loading the model
+/// Reading stops at the end of the HEADER section, so this is cheap even
+/// for large files.
+pub async fn read_ifc_header(path: String) -> Result<IfcHeaderInfo, String> {
+    use tokio::io::AsyncReadExt;
+
+    const END: &[u8] = b"ENDSEC;";
+    let mut file = tokio::fs::File::open(&path)
+        .await
+        .map_err(|e| format!("Failed to read file: {}", e))?;
+    let mut bytes = Vec::new();
+    let mut chunk = vec![0; 8192];
+    let header_len = loop {
+        let read = file.read(&mut chunk).await.map_err(|e| format!("Failed to read file: {}", e))?;
+        // The end marker may straddle two chunks
+        let from = bytes.len().saturating_sub(END.len() - 1);
+        bytes.extend_from_slice(&chunk[..read]);
+        if let Some(at) = bytes[from..].windows(END.len()).position(|w| w == END) {
+            break from + at + END.len();
+        }
+        if read == 0 || bytes.len() >= MAX_HEADER_BYTES {
+            return Err("No IFC header found".to_string());
+        }
+    };
+    let header = IfcFile::parse_header(&String::from_utf8_lossy(&bytes[..header_len]))?;
+    Ok(header.into())
+}
+
 /// Load an IFC file and parse it (backward compatible - loads as primary)
 /// This is async because file I/O can be slow
 pub async fn load_ifc_file(file_path: String) -> Result<ModelInfo, String> {
+    load_ifc_file_reporting(file_path, LoadOptions::default(), |_| {}).await
+}
+
+/// Load an IFC file as the primary model, extracting only what `options`
+/// asks for (e.g. no geometry for a property analysis)
+pub async fn load_ifc_file_with_options(file_path: String, options: LoadOptions) -> Result<ModelInfo, String> {
+    load_ifc_file_reporting(file_path, options, |_| {}).await
+}
+
+/// Load an IFC file as the primary model, reporting progress
+/// Emits `Parsing` stages, then `Tessellating` stages; each phase ends with
+/// fraction 1.0, so the spatial structure can be shown once parsing is done.
+pub async fn load_ifc_file_with_progress(
+    file_path: String,
+    sink: StreamSink<LoadStage>,
+) -> Result<ModelInfo, String> {
+    load_ifc_file_reporting(file_path, LoadOptions::default(), move |stage| {
+        // Keep loading if the listener went away
+        let _ = sink.add(stage);
+    })
+    .await
+}
+
+async fn load_ifc_file_reporting(
+    file_path: String,
+    options: LoadOptions,
+    report: impl FnMut(LoadStage) + Send + 'static,
+) -> Result<ModelInfo, String> {
     tracing::info!("Loading IFC file: {}", file_path);
 
     // Read file contents
@@ -87,16 +349,8 @@ pub async fn load_ifc_file(file_path: String) -> Result<ModelInfo, String> {
         .await
         .map_err(|e| format!("Failed to read file: {}", e))?;
 
-    // Parse IFC file
-    let ifc_file = IfcFile::parse(&content)?;
-
-    tracing::info!(
-        "Parsed IFC file: {} entities",
-        ifc_file.entity_count()
-    );
-
-    // Build BIM model from IFC
-    let model = BimModel::from_ifc_file(&ifc_file)?;
+    // Parse IFC file and build BIM model from it
+    let model = run_blocking(move || parse_and_build_model(&content, &options, report)).await?;
 
     // Get model info before storing
     let model_info = model.get_info();
@@ -116,6 +370,83 @@ pub async fn load_ifc_file(file_path: String) -> Result<ModelInfo, String> {
     Ok(model_info)
 }
 
+/// Load an IFC file as the primary model and stream its geometry element by
+/// element, tessellating each as it is sent, so the viewer can show elements
+/// as they arrive
+/// Ends with `ElementMesh::completion_marker()` (empty global_id) unless the
+/// listener goes away first; the model is registered just before it. Items
+/// periodically carry `running_bounds`, the bounds of the geometry so far,
+/// to re-fit the camera progressively.
+pub async fn load_and_stream_geometry(path: String, sink: StreamSink<ElementMesh>) -> Result<(), String> {
+    let content = tokio::fs::read_to_string(&path)
+        .await
+        .map_err(|e| format!("Failed to read file: {}", e))?;
+    let model = run_blocking(move || build_model(&IfcFile::parse(&content)?)).await?;
+
+    let mut marker = None;
+    for item in element_mesh_stream(&model) {
+        if item.is_completion_marker() {
+            marker = Some(item);
+            break;
+        }
+        if sink.add(item).is_err() {
+            break; // Listener went away
+        }
+        // Let other tasks run between elements
+        tokio::task::yield_now().await;
+    }
+
+    let name = std::path::Path::new(&path)
+        .file_stem()
+        .and_then(|s| s.to_str())
+        .unwrap_or("Untitled")
+        .to_string();
+    MODEL_REGISTRY.lock().unwrap().add_model(model, name, Some(path));
+    if let Some(marker) = marker {
+        let _ = sink.add(marker);
+    }
+    Ok(())
+}
+
+/// Streamed elements between running bounds updates
+const BOUNDS_UPDATE_INTERVAL: usize = 32;
+
+/// Items of a geometry stream: each element's mesh, tessellated when the
+/// stream reaches it, then the completion marker
+/// The first element and every `BOUNDS_UPDATE_INTERVAL`-th after it carry
+/// the bounds of the geometry tessellated so far, the marker those of all
+/// elements.
+fn element_mesh_stream(model: &BimModel) -> impl Iterator<Item = ElementMesh> + '_ {
+    let slots = model.element_slots();
+    let mut running: Option<crate::bim::BoundingBox> = None;
+    (0..=slots.len()).map(move |i| {
+        let Some(slot) = slots.get(i) else {
+            return ElementMesh {
+                running_bounds: running,
+                ..ElementMesh::completion_marker()
+            };
+        };
+        let mesh = model.slot_mesh(slot);
+        if let Some(bounds) = mesh.bounding_box().filter(|_| !mesh.indices.is_empty()) {
+            running = Some(running.map_or(bounds, |running| running.union(&bounds)));
+        }
+        let mut item = ElementMesh::new(slot.global_id.clone(), mesh);
+        if i % BOUNDS_UPDATE_INTERVAL == 0 {
+            item.running_bounds = running;
+        }
+        item
+    })
+}
+
+/// Parse IFC content and return its stats without loading it
+/// Touches no global state, so it is reentrant and safe to call from any thread
+#[frb(sync)]
+pub fn parse_ifc_stats_sync(content: String) -> Result<ModelInfo, String> {
+    let ifc_file = IfcFile::parse(&content)?;
+    let model = BimModel::from_ifc_file(&ifc_file)?;
+    Ok(model.get_info())
+}
+
 /// Get information about the currently loaded model (primary model)
 #[frb(sync)]
 pub fn get_model_info() -> Result<ModelInfo, String> {
@@ -127,6 +458,46 @@ pub fn get_model_info() -> Result<ModelInfo, String> {
     }
 }
 
+/// Get the problems parsing worked around in the current (primary) model's
+/// file, e.g. duplicate entity ids (of which the first definition is kept)
+#[frb(sync)]
+pub fn get_parse_warnings() -> Result<Vec<String>, String> {
+    let registry = MODEL_REGISTRY.lock().unwrap();
+    let reg_model = registry.get_primary_model().ok_or("No model loaded")?;
+    Ok(reg_model.model.parse_warnings.clone())
+}
+
+/// Get the outline of the current (primary) model: its spatial tree with
+/// each element's GlobalId, type and name
+/// Much smaller than the full model; fetch element details by GlobalId.
+#[frb(sync)]
+pub fn get_model_outline() -> Result<ModelOutline, String> {
+    let registry = MODEL_REGISTRY.lock().unwrap();
+    let reg_model = registry.get_primary_model().ok_or("No model loaded")?;
+    Ok(ModelOutline::of(&reg_model.model))
+}
+
+/// Get the bounding box of the current (primary) model
+/// Returns None if no model is loaded or it has no geometry
+#[frb(sync)]
+pub fn get_model_bounds() -> Option<BoundsInfo> {
+    let registry = MODEL_REGISTRY.lock().unwrap();
+    registry
+        .get_primary_model()
+        .and_then(|reg_model| reg_model.bounds)
+        .map(BoundsInfo::from)
+}
+
+/// Validate the generated geometry of the current (primary) model
+/// Reports degenerate triangles, NaN vertices, unreferenced vertices and
+/// non-manifold edges, plus the elements they belong to
+#[frb(sync)]
+pub fn validate_model() -> Result<ModelValidation, String> {
+    let registry = MODEL_REGISTRY.lock().unwrap();
+    let reg_model = registry.get_primary_model().ok_or("No model loaded")?;
+    Ok(reg_model.model.generate_meshes().validate())
+}
+
 /// Check if a model is currently loaded
 #[frb(sync)]
 pub fn is_model_loaded() -> bool {
@@ -157,16 +528,16 @@ pub fn unload_model() -> Result<(), String> {
 pub async fn parse_ifc_content(content: String) -> Result<ModelInfo, String> {
     tracing::info!("Parsing IFC content ({} bytes)", content.len());
 
-    // Parse IFC file
-    let ifc_file = IfcFile::parse(&content)?;
-
-    tracing::info!(
-        "Parsed IFC file: {} entities",
-        ifc_file.entity_count()
-    );
-
-    // Build BIM model from IFC
-    let model = BimModel::from_ifc_file(&ifc_file)?;
+    // Parse IFC file and build BIM model from IFC
+    let model = run_blocking(move || {
+        let ifc_file = IfcFile::parse(&content)?;
+        tracing::info!(
+            "Parsed IFC file: {} entities",
+            ifc_file.entity_count()
+        );
+        build_model(&ifc_file)
+    })
+    .await?;
 
     // Get model info before storing
     let model_info = model.get_info();
@@ -184,6 +555,15 @@ pub async fn parse_ifc_content(content: String) -> Result<ModelInfo, String> {
 
 /// Load a model with a specific ID
 pub async fn load_model(model_id: String, file_path: String) -> Result<ModelInfo, String> {
+    load_model_with_options(model_id, file_path, LoadOptions::default()).await
+}
+
+/// Load a model with a specific ID, extracting only what `options` asks for
+pub async fn load_model_with_options(
+    model_id: String,
+    file_path: String,
+    options: LoadOptions,
+) -> Result<ModelInfo, String> {
     tracing::info!("Loading model '{}' from: {}", model_id, file_path);
 
     // Read file contents
@@ -191,11 +571,8 @@ pub async fn load_model(model_id: String, file_path: String) -> Result<ModelInfo
         .await
         .map_err(|e| format!("Failed to read file: {}", e))?;
 
-    // Parse IFC file
-    let ifc_file = IfcFile::parse(&content)?;
-
-    // Build BIM model from IFC
-    let model = BimModel::from_ifc_file(&ifc_file)?;
+    // Parse IFC file and build BIM model from IFC
+    let model = run_blocking(move || build_model_with_options(&IfcFile::parse(&content)?, &options)).await?;
     let model_info = model.get_info();
 
     // Extract name from file path
@@ -213,6 +590,74 @@ pub async fn load_model(model_id: String, file_path: String) -> Result<ModelInfo
     Ok(model_info)
 }
 
+/// Load a glTF (`.gltf` or `.glb`) file as a model of generic elements,
+/// one per mesh node, and return its ID
+/// The meshes keep the positions the file gives them.
+pub async fn load_gltf(path: String) -> Result<String, String> {
+    tracing::info!("Loading glTF from: {}", path);
+    let file_path = path.clone();
+    let model = run_blocking(move || crate::bim::import_gltf(std::path::Path::new(&file_path))).await?;
+    let name = std::path::Path::new(&path)
+        .file_stem()
+        .and_then(|s| s.to_str())
+        .unwrap_or("Untitled")
+        .to_string();
+
+    let mut registry = MODEL_REGISTRY.lock().unwrap();
+    let model_id = registry.add_model(model, name, Some(path));
+    tracing::info!("glTF model '{}' loaded successfully", model_id);
+    Ok(model_id)
+}
+
+/// Load a Wavefront OBJ file as a model of generic elements, one per object
+/// or group, and return its ID
+/// For reference meshes (scans) shown beside the BIM model.
+pub async fn load_obj(path: String) -> Result<String, String> {
+    tracing::info!("Loading OBJ from: {}", path);
+    let file_path = path.clone();
+    let model = run_blocking(move || crate::bim::import_obj(std::path::Path::new(&file_path))).await?;
+    let name = std::path::Path::new(&path)
+        .file_stem()
+        .and_then(|s| s.to_str())
+        .unwrap_or("Untitled")
+        .to_string();
+
+    let mut registry = MODEL_REGISTRY.lock().unwrap();
+    let model_id = registry.add_model(model, name, Some(path));
+    tracing::info!("OBJ model '{}' loaded successfully", model_id);
+    Ok(model_id)
+}
+
+/// Load only the geometry of an IFC file, ignoring its schema and spatial
+/// structure, and return the model ID
+///
+/// A last resort for broken files the normal load finds nothing in: every
+/// shape representation that can be tessellated becomes a generic element,
+/// drawn where its placement (if any) puts it.
+pub async fn load_ifc_geometry_only(path: String) -> Result<String, String> {
+    tracing::info!("Salvaging geometry from: {}", path);
+    let content = tokio::fs::read_to_string(&path)
+        .await
+        .map_err(|e| format!("Failed to read file: {}", e))?;
+    let name = std::path::Path::new(&path)
+        .file_stem()
+        .and_then(|s| s.to_str())
+        .unwrap_or("Untitled")
+        .to_string();
+    let up_axis = *UP_AXIS.lock().unwrap();
+    let salvage_name = name.clone();
+    let model = run_blocking(move || {
+        let ifc_file = IfcFile::parse(&content)?;
+        crate::bim::salvage_geometry(&ifc_file, &salvage_name, up_axis)
+    })
+    .await?;
+
+    let mut registry = MODEL_REGISTRY.lock().unwrap();
+    let model_id = registry.add_model(model, name, Some(path));
+    tracing::info!("Salvaged model '{}' loaded successfully", model_id);
+    Ok(model_id)
+}
+
 /// Unload a specific model by ID
 #[frb(sync)]
 pub fn unload_model_by_id(model_id: String) -> Result<(), String> {
@@ -247,6 +692,69 @@ pub fn set_model_visible(model_id: String, visible: bool) -> Result<(), String>
     registry.set_model_visible(&model_id, visible)
 }
 
+/// Free a model's geometry and GPU buffers but keep its element data (for
+/// the tree and search); it is not drawn until `reload_geometry`
+///
+/// Only models loaded from a file can be unloaded, since their geometry is
+/// tessellated again from it.
+#[frb(sync)]
+pub fn unload_geometry(model_id: String) -> Result<(), String> {
+    {
+        let mut registry = MODEL_REGISTRY.lock().unwrap();
+        let reg_model = registry
+            .get_model_mut(&model_id)
+            .ok_or_else(|| format!("Model '{}' not found", model_id))?;
+        if reg_model.file_path.is_none() || reg_model.model.placed_bodies {
+            return Err(format!("Model '{}' has no IFC source file to reload geometry from", model_id));
+        }
+        reg_model.unload_geometry();
+    }
+    if RENDERER.lock().unwrap().as_ref().is_some_and(|r| r.gpu.is_initialized()) {
+        reload_all_models_mesh()?;
+    }
+    Ok(())
+}
+
+/// Tessellate a model's geometry again from its source file after
+/// `unload_geometry`, and show it
+pub async fn reload_geometry(model_id: String) -> Result<(), String> {
+    // The unloaded model holds no meshes, so copying it is cheap
+    let (path, model) = {
+        let registry = MODEL_REGISTRY.lock().unwrap();
+        let reg_model = registry
+            .get_model(&model_id)
+            .ok_or_else(|| format!("Model '{}' not found", model_id))?;
+        if reg_model.geometry_loaded {
+            return Ok(());
+        }
+        let path = reg_model
+            .file_path
+            .clone()
+            .ok_or_else(|| format!("Model '{}' has no source file to reload geometry from", model_id))?;
+        (path, reg_model.model.clone())
+    };
+    let content = tokio::fs::read_to_string(&path)
+        .await
+        .map_err(|e| format!("Failed to read file: {}", e))?;
+    run_blocking(move || {
+        // Tessellate without the registry locked, then swap the result in
+        let geometry = TessellatedGeometry::build(model, &IfcFile::parse(&content)?);
+        let mut registry = MODEL_REGISTRY.lock().unwrap();
+        let reg_model = registry
+            .get_model_mut(&model_id)
+            .ok_or_else(|| format!("Model '{}' not found", model_id))?;
+        if !reg_model.geometry_loaded {
+            reg_model.install_geometry(geometry);
+        }
+        Ok(())
+    })
+    .await?;
+    if RENDERER.lock().unwrap().as_ref().is_some_and(|r| r.gpu.is_initialized()) {
+        reload_all_models_mesh()?;
+    }
+    Ok(())
+}
+
 /// Set the primary model
 #[frb(sync)]
 pub fn set_primary_model(model_id: String) -> Result<(), String> {
@@ -254,6 +762,50 @@ pub fn set_primary_model(model_id: String) -> Result<(), String> {
     registry.set_primary_model(&model_id)
 }
 
+/// Compare two loaded revisions of a model, matching elements by GlobalId
+/// Returns the GlobalIds added in `b`, removed from `a`, and modified
+/// (geometry, placement, attributes or material) for the UI to color.
+#[frb(sync)]
+pub fn compare_models(a: String, b: String) -> Result<ModelDiff, String> {
+    let registry = MODEL_REGISTRY.lock().unwrap();
+    let model_a = registry.get_model(&a).ok_or_else(|| format!("Model '{}' not found", a))?;
+    let model_b = registry.get_model(&b).ok_or_else(|| format!("Model '{}' not found", b))?;
+    Ok(diff::compare_models(&model_a.model, &model_b.model))
+}
+
+/// Diff whose change status colors the view, if shown
+static DIFF_VIEW: Mutex<Option<ModelDiff>> = Mutex::new(None);
+
+/// Color loaded models by change status: added elements green, modified
+/// yellow, and removed ones red, ghosted from the old model, returning the
+/// legend of the statuses present
+/// Keep both revisions loaded and visible; the old one only shows its
+/// removed elements while the diff is displayed.
+#[frb(sync)]
+pub fn show_diff_colors(diff: ModelDiff) -> Result<Legend, String> {
+    let legend = Legend::of_diff(&diff);
+    *DIFF_VIEW.lock().unwrap() = Some(diff);
+    reload_all_models_mesh()?;
+    Ok(legend)
+}
+
+/// Stop coloring by change status and show the models normally
+#[frb(sync)]
+pub fn clear_diff_colors() -> Result<(), String> {
+    *DIFF_VIEW.lock().unwrap() = None;
+    if !MODEL_REGISTRY.lock().unwrap().is_empty() {
+        reload_all_models_mesh()?;
+    }
+    Ok(())
+}
+
+/// Recolor a model mesh by the displayed diff, if any
+fn apply_diff_view(mesh: &mut ModelMesh) {
+    if let Some(diff) = DIFF_VIEW.lock().unwrap().as_ref() {
+        mesh.apply_diff(diff);
+    }
+}
+
 /// Clear all models
 #[frb(sync)]
 pub fn clear_all_models() {
@@ -266,7 +818,10 @@ pub fn clear_all_models() {
 // Phase 3 API: 3D Rendering
 // ============================================================================
 
-use crate::renderer::Renderer;
+use crate::renderer::{
+    Camera, CameraPath, CameraState, DepthBias, ElementLod, NavMode, GpuCapabilities, LightingConfig, LodSelection, OverlaySampling, PointCloud, Projection,
+    Renderer, sequence_frame_name, ToneMapping, Viewpoint, DEFAULT_LOD_THRESHOLDS_PX, LOD_LEVEL_RATIOS,
+};
 
 // Global renderer instance
 static RENDERER: Mutex<Option<Renderer>> = Mutex::new(None);
@@ -285,6 +840,156 @@ pub async fn test_renderer_init() -> Result<String, String> {
     Ok("Renderer initialized successfully! wgpu backend is working.".to_string())
 }
 
+/// Outcome of one self-test stage
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SelfTestStatus {
+    Passed,
+    Failed,
+    /// No GPU adapter on this device
+    Unavailable,
+    /// Not run because an earlier stage did not pass
+    Skipped,
+}
+
+/// One stage of the self-test pipeline
+#[derive(Debug, Clone)]
+pub struct SelfTestStage {
+    /// "parse", "tessellate", "gpu" or "render"
+    pub name: String,
+    pub status: SelfTestStatus,
+    pub duration_ms: f64,
+    /// What was done, or why the stage did not pass
+    pub detail: String,
+}
+
+/// Result of run_self_test, for field diagnostics
+#[derive(Debug, Clone)]
+pub struct SelfTestReport {
+    /// True when no stage failed (a missing GPU is not a failure)
+    pub passed: bool,
+    pub stages: Vec<SelfTestStage>,
+}
+
+/// Small model run through the self-test: a storey with a slab placed by
+/// a local placement and a triangulated proxy
+const SELF_TEST_IFC: &str = "ISO-10303-21;
+HEADER;
+FILE_DESCRIPTION(('ViewDefinition [CoordinationView]'),'2;1');
+FILE_NAME('self_test.ifc','2024-01-01',(''),(''),'','','');
+FILE_SCHEMA(('IFC4'));
+ENDSEC;
+DATA;
+#1=IFCPROJECT('self-test-project',$,'Self Test',$,$,$,$,$,$);
+#2=IFCBUILDINGSTOREY('self-test-storey',$,'Ground Floor',$,$,$,$,$,.ELEMENT.,0.);
+#10=IFCCARTESIANPOINT((0.,0.,0.));
+#11=IFCAXIS2PLACEMENT3D(#10,$,$);
+#12=IFCLOCALPLACEMENT($,#11);
+#20=IFCSLAB('self-test-slab',$,'Slab',$,$,#12,$,$,.FLOOR.);
+#30=IFCCARTESIANPOINTLIST3D(((0.,0.,0.),(1.,0.,0.),(1.,1.,0.),(0.,0.,1.)));
+#31=IFCTRIANGULATEDFACESET(#30,$,$,((1,3,2),(1,2,4),(2,3,4),(3,1,4)),$);
+#32=IFCSHAPEREPRESENTATION($,'Body','Tessellation',(#31));
+#33=IFCPRODUCTDEFINITIONSHAPE($,$,(#32));
+#34=IFCBUILDINGELEMENTPROXY('self-test-proxy',$,'Proxy',$,$,#12,#33,$,$);
+#40=IFCRELCONTAINEDINSPATIALSTRUCTURE('self-test-rel',$,$,$,(#20,#34),#2);
+ENDSEC;
+END-ISO-10303-21;
+";
+
+/// Run the whole pipeline on an embedded model and time each stage: parse,
+/// tessellate, GPU initialization and one headless frame
+///
+/// Uses a renderer of its own, so the live scene is left untouched. Stages
+/// after a failure are skipped; without a GPU adapter the GPU stage is
+/// reported unavailable rather than failed.
+pub async fn run_self_test() -> SelfTestReport {
+    fn stage<T>(
+        stages: &mut Vec<SelfTestStage>,
+        name: &str,
+        started: std::time::Instant,
+        result: Result<(T, String), String>,
+        unavailable: bool,
+    ) -> Option<T> {
+        let duration_ms = started.elapsed().as_secs_f64() * 1000.0;
+        let (status, detail, value) = match result {
+            Ok((value, detail)) => (SelfTestStatus::Passed, detail, Some(value)),
+            Err(e) if unavailable => (SelfTestStatus::Unavailable, e, None),
+            Err(e) => (SelfTestStatus::Failed, e, None),
+        };
+        stages.push(SelfTestStage { name: name.to_string(), status, duration_ms, detail });
+        value
+    }
+
+    let mut stages = Vec::new();
+    let started = std::time::Instant::now();
+    let parsed = IfcFile::parse(SELF_TEST_IFC).map(|file| {
+        let detail = format!("{} entities", file.entity_count());
+        (file, detail)
+    });
+    let mesh = stage(&mut stages, "parse", started, parsed, false).and_then(|file| {
+        let started = std::time::Instant::now();
+        let tessellated = BimModel::from_ifc_file(&file).and_then(|model| {
+            let mesh = model.generate_meshes();
+            if mesh.indices.is_empty() {
+                return Err("No triangles generated".to_string());
+            }
+            let detail = format!("{} elements, {} triangles", mesh.elements.len(), mesh.indices.len() / 3);
+            Ok((mesh, detail))
+        });
+        stage(&mut stages, "tessellate", started, tessellated, false)
+    });
+
+    let renderer = match mesh {
+        Some(_) => {
+            let started = std::time::Instant::now();
+            let mut renderer = Renderer::new();
+            let initialized = renderer.initialize().await.map(|()| {
+                let detail = renderer.gpu.capabilities().map_or_else(String::new, |c| c.adapter_name);
+                (renderer, detail)
+            });
+            stage(&mut stages, "gpu", started, initialized, true)
+        }
+        None => None,
+    };
+
+    match (mesh, renderer) {
+        (Some(mesh), Some(mut renderer)) => {
+            let started = std::time::Instant::now();
+            let size = 64;
+            let rendered = (|| {
+                renderer.init_scene(size, size)?;
+                renderer.load_mesh(&mesh.vertices, &mesh.normals, &mesh.colors, &mesh.indices)?;
+                let bounds = mesh.bounds.ok_or(NO_RENDERABLE_GEOMETRY)?;
+                renderer.fit_camera_to_bounds(bounds.min, bounds.max);
+                let frame = renderer.render_frame()?;
+                if frame.len() != (size * size * 4) as usize {
+                    return Err(format!("Frame of {} bytes for {}x{}", frame.len(), size, size));
+                }
+                Ok(((), format!("{}x{} frame", size, size)))
+            })();
+            stage(&mut stages, "render", started, rendered, false);
+        }
+        _ => {
+            let ran: Vec<String> = stages.iter().map(|s| s.name.clone()).collect();
+            for name in ["parse", "tessellate", "gpu", "render"] {
+                if !ran.iter().any(|r| r == name) {
+                    stages.push(SelfTestStage {
+                        name: name.to_string(),
+                        status: SelfTestStatus::Skipped,
+                        duration_ms: 0.0,
+                        detail: "Earlier stage did not pass".to_string(),
+                    });
+                }
+            }
+        }
+    }
+
+    let passed = stages.iter().all(|s| s.status != SelfTestStatus::Failed);
+    for s in &stages {
+        tracing::info!("Self-test {}: {:?} in {:.1} ms ({})", s.name, s.status, s.duration_ms, s.detail);
+    }
+    SelfTestReport { passed, stages }
+}
+
 /// Initialize the 3D renderer with given dimensions
 pub async fn init_renderer(width: u32, height: u32) -> Result<String, String> {
     tracing::info!("Initializing renderer {}x{}", width, height);
@@ -306,6 +1011,36 @@ pub async fn init_renderer(width: u32, height: u32) -> Result<String, String> {
     Ok(format!("Renderer initialized at {}x{}", width, height))
 }
 
+/// Release the renderer's GPU resources, e.g. when the app goes to the
+/// background; camera, lighting and section settings are kept
+/// Rendering fails with an error until reinit_renderer is called.
+#[frb(sync)]
+pub fn shutdown_renderer() -> Result<(), String> {
+    let mut renderer = RENDERER.lock().unwrap();
+    let r = renderer.as_mut().ok_or("Renderer not initialized")?;
+    r.release();
+    Ok(())
+}
+
+/// Recreate the GPU resources released by shutdown_renderer and upload the
+/// loaded models again
+/// Drawing overlays are not restored; add them again if needed.
+pub async fn reinit_renderer() -> Result<String, String> {
+    // Out of the global while the GPU is set up, so the lock is not held
+    // across the await
+    let mut r = RENDERER.lock().unwrap().take().ok_or("Renderer not initialized")?;
+    let result = r.reinitialize().await;
+    let dimensions = r.get_dimensions();
+    *RENDERER.lock().unwrap() = Some(r);
+    result.map_err(|e| format!("GPU reinit failed: {}", e))?;
+
+    if !MODEL_REGISTRY.lock().unwrap().is_empty() {
+        reload_all_models_mesh()?;
+    }
+    let (width, height) = dimensions.unwrap_or_default();
+    Ok(format!("Renderer reinitialized at {}x{}", width, height))
+}
+
 /// Render a frame and return RGBA pixel data
 #[frb(sync)]
 pub fn render_frame() -> Result<Vec<u8>, String> {
@@ -314,39 +1049,202 @@ pub fn render_frame() -> Result<Vec<u8>, String> {
     r.render_frame()
 }
 
-/// Orbit the camera around the target
+/// Render a frame only if something changed since the last one
+/// Returns None when continuous rendering is off and the camera, lights,
+/// model and section are unchanged, so Flutter can skip both the render and
+/// the texture upload; always renders while continuous rendering is on.
 #[frb(sync)]
-pub fn orbit_camera(delta_x: f32, delta_y: f32) -> Result<(), String> {
+pub fn render_frame_if_changed() -> Result<Option<Vec<u8>>, String> {
+    let renderer = RENDERER.lock().unwrap();
+    let r = renderer.as_ref().ok_or("Renderer not initialized")?;
+    r.render_frame_if_changed()
+}
+
+/// Render every frame (true, the default) or only when something changed
+/// (false: low-power idle viewing; render_frame then returns the last frame)
+#[frb(sync)]
+pub fn set_continuous_rendering(enabled: bool) -> Result<(), String> {
     let mut renderer = RENDERER.lock().unwrap();
     let r = renderer.as_mut().ok_or("Renderer not initialized")?;
-    r.orbit_camera(delta_x, delta_y);
+    r.set_continuous_rendering(enabled);
     Ok(())
 }
 
-/// Zoom the camera in/out
+/// Force the next frame to render (e.g. after a change made outside the
+/// renderer)
 #[frb(sync)]
-pub fn zoom_camera(delta: f32) -> Result<(), String> {
+pub fn mark_dirty() -> Result<(), String> {
     let mut renderer = RENDERER.lock().unwrap();
     let r = renderer.as_mut().ok_or("Renderer not initialized")?;
-    r.zoom_camera(delta);
+    r.mark_dirty();
     Ok(())
 }
 
-/// Check if renderer is initialized
-#[frb(sync)]
-pub fn is_renderer_initialized() -> bool {
-    let renderer = RENDERER.lock().unwrap();
-    renderer.as_ref().map_or(false, |r| r.initialized)
+/// Render a frame without blocking, returning RGBA pixel data
+/// For Flutter's frame loop: calls made while a frame is still rendering
+/// share the next frame instead of queuing one each.
+pub async fn render_frame_async() -> Result<Vec<u8>, String> {
+    crate::renderer::render_frame_async(&RENDERER).await
 }
 
-/// Load the currently loaded BIM model into the renderer (primary model)
+/// Cancel pending render_frame_async calls (they return an error)
 #[frb(sync)]
-pub fn load_model_into_renderer() -> Result<String, String> {
-    // Get model mesh data from primary model
-    let registry = MODEL_REGISTRY.lock().unwrap();
+pub fn cancel_render_frames() -> Result<(), String> {
+    let mut renderer = RENDERER.lock().unwrap();
+    let r = renderer.as_mut().ok_or("Renderer not initialized")?;
+    r.cancel_frames();
+    Ok(())
+}
+
+/// Orbit the camera around the target (in walk and fly modes, look around
+/// in place instead)
+#[frb(sync)]
+pub fn orbit_camera(delta_x: f32, delta_y: f32) -> Result<(), String> {
+    let mut renderer = RENDERER.lock().unwrap();
+    let r = renderer.as_mut().ok_or("Renderer not initialized")?;
+    r.orbit_camera(delta_x, delta_y);
+    Ok(())
+}
+
+/// Set how drags and movement steer the camera: orbit around the target,
+/// walk at a constant eye height, or fly freely
+#[frb(sync)]
+pub fn set_nav_mode(mode: NavMode) -> Result<(), String> {
+    let mut renderer = RENDERER.lock().unwrap();
+    let r = renderer.as_mut().ok_or("Renderer not initialized")?;
+    r.camera.set_nav_mode(mode);
+    Ok(())
+}
+
+/// Get the navigation mode
+#[frb(sync)]
+pub fn get_nav_mode() -> Result<NavMode, String> {
+    let renderer = RENDERER.lock().unwrap();
+    let r = renderer.as_ref().ok_or("Renderer not initialized")?;
+    Ok(r.camera.nav_mode())
+}
+
+/// Move the camera in meters: forward along the view (horizontally when
+/// walking), right, and up (ignored when walking), e.g. from WASD keys or a
+/// virtual joystick
+#[frb(sync)]
+pub fn move_camera(forward: f32, right: f32, up: f32) -> Result<(), String> {
+    if ![forward, right, up].iter().all(|m| m.is_finite()) {
+        return Err(format!("Invalid camera movement: {}, {}, {}", forward, right, up));
+    }
+    let mut renderer = RENDERER.lock().unwrap();
+    let r = renderer.as_mut().ok_or("Renderer not initialized")?;
+    r.camera.move_forward(forward);
+    r.camera.strafe(right);
+    r.camera.move_up(up);
+    Ok(())
+}
+
+/// Turn the view in place (mouselook), whatever the navigation mode
+#[frb(sync)]
+pub fn look_camera(delta_x: f32, delta_y: f32) -> Result<(), String> {
+    let mut renderer = RENDERER.lock().unwrap();
+    let r = renderer.as_mut().ok_or("Renderer not initialized")?;
+    r.camera.look(delta_x, delta_y);
+    Ok(())
+}
+
+/// Zoom the camera in/out
+#[frb(sync)]
+pub fn zoom_camera(delta: f32) -> Result<(), String> {
+    let mut renderer = RENDERER.lock().unwrap();
+    let r = renderer.as_mut().ok_or("Renderer not initialized")?;
+    r.zoom_camera(delta);
+    Ok(())
+}
+
+/// Switch between perspective and orthographic projection
+#[frb(sync)]
+pub fn set_orthographic(enabled: bool) -> Result<(), String> {
+    let mut renderer = RENDERER.lock().unwrap();
+    let r = renderer.as_mut().ok_or("Renderer not initialized")?;
+    r.camera.set_projection(if enabled {
+        Projection::Orthographic
+    } else {
+        Projection::Perspective
+    });
+    Ok(())
+}
+
+/// World units (meters) covered by one pixel for the given viewport height
+/// Exact for orthographic; for perspective it holds at the orbit target
+#[frb(sync)]
+pub fn get_world_units_per_pixel(viewport_height: f32) -> Result<f32, String> {
+    let renderer = RENDERER.lock().unwrap();
+    let r = renderer.as_ref().ok_or("Renderer not initialized")?;
+    Ok(r.camera.world_units_per_pixel(viewport_height))
+}
+
+/// Scale bar near `target_pixels` long, rounded to a nice length (1, 2, 5 x 10^n)
+/// Returns (world_length, pixel_length)
+#[frb(sync)]
+pub fn get_scale_bar(target_pixels: f32, viewport_height: f32) -> Result<(f32, f32), String> {
+    let renderer = RENDERER.lock().unwrap();
+    let r = renderer.as_ref().ok_or("Renderer not initialized")?;
+    Ok(r.camera.scale_bar_length(target_pixels, viewport_height))
+}
+
+/// Project a point in local (render) coordinates to viewport pixels, for
+/// placing Flutter labels and tooltips over the 3D view
+/// Returns (x, y, depth) with y down and depth 0-1 (near to far), or None
+/// when the point is behind the camera or off screen.
+#[frb(sync)]
+pub fn project_point(
+    x: f32,
+    y: f32,
+    z: f32,
+    viewport_width: f32,
+    viewport_height: f32,
+) -> Result<Option<(f32, f32, f32)>, String> {
+    let renderer = RENDERER.lock().unwrap();
+    let r = renderer.as_ref().ok_or("Renderer not initialized")?;
+    Ok(r.camera.project_point(Vec3::new(x, y, z), (viewport_width, viewport_height)))
+}
+
+/// Check if renderer is initialized
+#[frb(sync)]
+pub fn is_renderer_initialized() -> bool {
+    let renderer = RENDERER.lock().unwrap();
+    renderer.as_ref().map_or(false, |r| r.initialized)
+}
+
+/// Error of loading or framing models with nothing to draw (spatial-only
+/// files, see `ModelInfo::has_geometry`), for the UI to show instead of an
+/// empty viewport
+const NO_RENDERABLE_GEOMETRY: &str = "No renderable geometry";
+
+/// Set whether loading models into the renderer zooms to their extents
+/// (on by default); when off the camera stays where it is
+#[frb(sync)]
+pub fn set_auto_frame_on_load(enabled: bool) {
+    *AUTO_FRAME_ON_LOAD.lock().unwrap() = enabled;
+}
+
+/// Whether loading models into the renderer zooms to their extents
+#[frb(sync)]
+pub fn get_auto_frame_on_load() -> bool {
+    *AUTO_FRAME_ON_LOAD.lock().unwrap()
+}
+
+/// Load the currently loaded BIM model into the renderer (primary model)
+/// Fails with "No renderable geometry" if the model has no elements (the
+/// scene is emptied and the camera left as it is). Fits the camera to the
+/// model unless auto-framing is off.
+#[frb(sync)]
+pub fn load_model_into_renderer() -> Result<String, String> {
+    // Get model mesh data from primary model
+    let registry = MODEL_REGISTRY.lock().unwrap();
     let reg_model = registry.get_primary_model().ok_or("No model loaded")?;
 
-    let mesh = reg_model.model.generate_meshes();
+    let mut mesh = reg_model.model.generate_meshes();
+
+    apply_explode(&reg_model.model, &mut mesh);
+    apply_diff_view(&mut mesh);
     let vertex_count = mesh.vertices.len() / 3;
     let triangle_count = mesh.indices.len() / 3;
 
@@ -354,10 +1252,10 @@ pub fn load_model_into_renderer() -> Result<String, String> {
     let mut renderer = RENDERER.lock().unwrap();
     let r = renderer.as_mut().ok_or("Renderer not initialized")?;
 
-    r.load_mesh(&mesh.vertices, &mesh.normals, &mesh.colors, &mesh.indices)?;
+    upload_scene_mesh(r, &mesh, *SELECTED_ELEMENT.lock().unwrap())?;
 
-    // Fit camera to bounds if available
-    if let Some(bounds) = mesh.bounds {
+    let bounds = mesh.bounds.ok_or(NO_RENDERABLE_GEOMETRY)?;
+    if get_auto_frame_on_load() {
         r.fit_camera_to_bounds(bounds.min, bounds.max);
     }
 
@@ -374,6 +1272,8 @@ pub fn load_model_into_renderer() -> Result<String, String> {
 }
 
 /// Load all visible models into the renderer
+/// Fails with "No renderable geometry" if none of them has elements. Fits
+/// the camera to their combined bounds unless auto-framing is off.
 #[frb(sync)]
 pub fn load_all_models_into_renderer() -> Result<String, String> {
     let registry = MODEL_REGISTRY.lock().unwrap();
@@ -382,48 +1282,9 @@ pub fn load_all_models_into_renderer() -> Result<String, String> {
         return Err("No models loaded".to_string());
     }
 
-    // Collect mesh data from all visible models
-    let mut all_vertices = Vec::new();
-    let mut all_normals = Vec::new();
-    let mut all_colors = Vec::new();
-    let mut all_indices = Vec::new();
-    let mut combined_bounds: Option<crate::bim::BoundingBox> = None;
-
-    for (_id, reg_model) in registry.iter_visible() {
-        let mesh = reg_model.model.generate_meshes();
-
-        // Offset indices by current vertex count
-        let vertex_offset = (all_vertices.len() / 3) as u32;
-        for idx in &mesh.indices {
-            all_indices.push(idx + vertex_offset);
-        }
-
-        all_vertices.extend(&mesh.vertices);
-        all_normals.extend(&mesh.normals);
-        all_colors.extend(&mesh.colors);
-
-        // Update combined bounds
-        if let Some(bounds) = mesh.bounds {
-            combined_bounds = Some(match combined_bounds {
-                None => bounds,
-                Some(existing) => existing.union(&bounds),
-            });
-        }
-    }
-
-    let vertex_count = all_vertices.len() / 3;
-    let triangle_count = all_indices.len() / 3;
-
-    // Upload to renderer
     let mut renderer = RENDERER.lock().unwrap();
     let r = renderer.as_mut().ok_or("Renderer not initialized")?;
-
-    r.load_mesh(&all_vertices, &all_normals, &all_colors, &all_indices)?;
-
-    // Fit camera to combined bounds
-    if let Some(bounds) = combined_bounds {
-        r.fit_camera_to_bounds(bounds.min, bounds.max);
-    }
+    let (vertex_count, triangle_count) = upload_visible_models(&registry, r)?;
 
     tracing::info!(
         "Loaded {} models: {} vertices, {} triangles",
@@ -440,6 +1301,126 @@ pub fn load_all_models_into_renderer() -> Result<String, String> {
     ))
 }
 
+/// Triangle range of each element, shifted to where the mesh starts in a
+/// combined index buffer
+fn element_triangle_ranges(
+    elements: &[ElementInfo],
+    triangle_offset: u32,
+) -> impl Iterator<Item = std::ops::Range<u32>> + '_ {
+    elements.iter().map(move |e| {
+        let start = triangle_offset + e.triangle_start;
+        start..start + e.triangle_count
+    })
+}
+
+/// Bounds (min, max) of the drawn vertices of each element, which explode
+/// may have moved away from the element's own bounds
+fn drawn_element_bounds(mesh: &ModelMesh) -> impl Iterator<Item = (Vec3, Vec3)> + '_ {
+    mesh.elements.iter().map(|element| {
+        let start = element.triangle_start as usize * 3;
+        let end = start + element.triangle_count as usize * 3;
+        mesh.indices[start..end]
+            .iter()
+            .map(|&i| Vec3::from_slice(&mesh.vertices[i as usize * 3..i as usize * 3 + 3]))
+            .fold((Vec3::MAX, Vec3::MIN), |(min, max), v| (min.min(v), max.max(v)))
+    })
+}
+
+/// Upload a scene mesh and its element ranges and bounds, with coarse levels
+/// of every element when automatic LOD is on
+///
+/// `selected` is drawn however small it gets on screen.
+fn upload_scene_mesh(r: &mut Renderer, mesh: &ModelMesh, selected: Option<i32>) -> Result<(), String> {
+    let auto_lod = AUTO_LOD.lock().unwrap().clone();
+    match auto_lod {
+        Some(thresholds) => {
+            let lod = mesh.lod_levels(&LOD_LEVEL_RATIOS[..thresholds.len()]);
+            let elements = mesh
+                .elements
+                .iter()
+                .zip(drawn_element_bounds(mesh))
+                .zip(lod.element_levels)
+                .map(|((element, (min, max)), coarse)| {
+                    let full = element.triangle_start..element.triangle_start + element.triangle_count;
+                    ElementLod {
+                        center: (min + max) / 2.0,
+                        radius: (max - min).max(Vec3::ZERO).length() / 2.0,
+                        levels: std::iter::once(full).chain(coarse).collect(),
+                    }
+                })
+                .collect();
+            r.load_mesh_with_lod(
+                &[mesh.vertices.as_slice(), &lod.vertices].concat(),
+                &[mesh.normals.as_slice(), &lod.normals].concat(),
+                &[mesh.colors.as_slice(), &lod.colors].concat(),
+                &[mesh.indices.as_slice(), &lod.indices].concat(),
+                mesh.indices.len() / 3,
+                LodSelection::new(elements, thresholds)?,
+            )?;
+        }
+        None => r.load_mesh(&mesh.vertices, &mesh.normals, &mesh.colors, &mesh.indices)?,
+    }
+    r.set_element_ranges(element_triangle_ranges(&mesh.elements, 0).collect())?;
+    r.set_element_bounds(drawn_element_bounds(mesh).map(|(min, max)| (min.into(), max.into())).collect())?;
+    r.set_pinned_elements(
+        mesh.elements
+            .iter()
+            .enumerate()
+            .filter(|(_, e)| Some(e.id) == selected)
+            .map(|(k, _)| k)
+            .collect(),
+    )
+}
+
+/// Switch each element between full detail and coarser copies by its size
+/// on screen, reloading the scene
+///
+/// `thresholds` are projected sizes in pixels, decreasing, below which the
+/// next coarser level is used (at most one per level; empty for defaults).
+#[frb(sync)]
+pub fn set_auto_lod(enabled: bool, thresholds: Vec<f32>) -> Result<(), String> {
+    let thresholds = if thresholds.is_empty() { DEFAULT_LOD_THRESHOLDS_PX.to_vec() } else { thresholds };
+    crate::renderer::lod::validate_thresholds(&thresholds)?;
+    *AUTO_LOD.lock().unwrap() = enabled.then_some(thresholds);
+    if !MODEL_REGISTRY.lock().unwrap().is_empty() && RENDERER.lock().unwrap().is_some() {
+        reload_all_models_mesh()?;
+    }
+    Ok(())
+}
+
+/// Skip elements whose bounds cover fewer than `min_pixels` across on
+/// screen (bolts, fixtures in dense models), checked every frame; None draws
+/// every element
+///
+/// The selected element is always drawn.
+#[frb(sync)]
+pub fn set_small_object_culling(min_pixels: Option<f32>) -> Result<(), String> {
+    let mut renderer = RENDERER.lock().unwrap();
+    let r = renderer.as_mut().ok_or("Renderer not initialized")?;
+    r.set_small_object_culling(min_pixels)
+}
+
+/// Upload the combined meshes of a registry's visible models, framing them
+/// when auto-framing is on
+/// Returns the vertex and triangle counts.
+fn upload_visible_models(registry: &ModelRegistry, r: &mut Renderer) -> Result<(usize, usize), String> {
+    let mut combined = ModelMesh::default();
+    for (_id, reg_model) in registry.iter_visible() {
+        let mut mesh = reg_model.model.generate_meshes();
+        apply_explode(&reg_model.model, &mut mesh);
+        apply_diff_view(&mut mesh);
+        combined.append(&mesh);
+    }
+
+    upload_scene_mesh(r, &combined, *SELECTED_ELEMENT.lock().unwrap())?;
+
+    let bounds = combined.bounds.ok_or(NO_RENDERABLE_GEOMETRY)?;
+    if get_auto_frame_on_load() {
+        r.fit_camera_to_bounds(bounds.min, bounds.max);
+    }
+    Ok((combined.vertices.len() / 3, combined.indices.len() / 3))
+}
+
 /// Fit camera to current model bounds (primary model)
 #[frb(sync)]
 pub fn fit_camera_to_model() -> Result<(), String> {
@@ -447,8 +1428,10 @@ pub fn fit_camera_to_model() -> Result<(), String> {
     let registry = MODEL_REGISTRY.lock().unwrap();
     let reg_model = registry.get_primary_model().ok_or("No model loaded")?;
 
-    let mesh = reg_model.model.generate_meshes();
-    let bounds = mesh.bounds.ok_or("Model has no bounds")?;
+    let mut mesh = reg_model.model.generate_meshes();
+
+    apply_explode(&reg_model.model, &mut mesh);
+    let bounds = mesh.bounds.ok_or(NO_RENDERABLE_GEOMETRY)?;
 
     // Update renderer camera
     let mut renderer = RENDERER.lock().unwrap();
@@ -463,7 +1446,13 @@ pub fn fit_camera_to_model() -> Result<(), String> {
 #[frb(sync)]
 pub fn fit_camera_to_all_models() -> Result<(), String> {
     let registry = MODEL_REGISTRY.lock().unwrap();
+    let mut renderer = RENDERER.lock().unwrap();
+    let r = renderer.as_mut().ok_or("Renderer not initialized")?;
+    fit_camera_to_visible_models(&registry, r)
+}
 
+/// Fit the camera to the combined bounds of the visible models
+fn fit_camera_to_visible_models(registry: &ModelRegistry, r: &mut Renderer) -> Result<(), String> {
     if registry.is_empty() {
         return Err("No models loaded".to_string());
     }
@@ -472,7 +1461,8 @@ pub fn fit_camera_to_all_models() -> Result<(), String> {
     let mut combined_bounds: Option<crate::bim::BoundingBox> = None;
 
     for (_id, reg_model) in registry.iter_visible() {
-        let mesh = reg_model.model.generate_meshes();
+        let mut mesh = reg_model.model.generate_meshes();
+        apply_explode(&reg_model.model, &mut mesh);
         if let Some(bounds) = mesh.bounds {
             combined_bounds = Some(match combined_bounds {
                 None => bounds,
@@ -481,14 +1471,241 @@ pub fn fit_camera_to_all_models() -> Result<(), String> {
         }
     }
 
-    let bounds = combined_bounds.ok_or("No visible models with bounds")?;
+    let bounds = combined_bounds.ok_or(NO_RENDERABLE_GEOMETRY)?;
+    r.fit_camera_to_bounds(bounds.min, bounds.max);
+    Ok(())
+}
 
-    // Update renderer camera
+/// Store the current camera as the home view, for `go_home`
+/// The home view stays as set when models are loaded or unloaded.
+#[frb(sync)]
+pub fn set_home_view() -> Result<(), String> {
     let mut renderer = RENDERER.lock().unwrap();
     let r = renderer.as_mut().ok_or("Renderer not initialized")?;
+    r.set_home_view();
+    Ok(())
+}
 
-    r.fit_camera_to_bounds(bounds.min, bounds.max);
+/// Move the camera back to the home view set with `set_home_view`
+#[frb(sync)]
+pub fn go_home() -> Result<(), String> {
+    let mut renderer = RENDERER.lock().unwrap();
+    let r = renderer.as_mut().ok_or("Renderer not initialized")?;
+    r.go_home()
+}
+
+/// Re-fit the camera to the visible models as they are now, regardless of
+/// the home view
+#[frb(sync)]
+pub fn reset_view() -> Result<(), String> {
+    fit_camera_to_all_models()
+}
 
+/// Frame rate of animated camera moves
+const CAMERA_ANIMATION_FPS: u32 = 60;
+
+/// Get the bounding box of an element by GlobalId (searches all loaded models)
+/// Bounds are in the assembled (not exploded) position
+#[frb(sync)]
+pub fn get_element_bounds(global_id: String) -> Option<BoundsInfo> {
+    let registry = MODEL_REGISTRY.lock().unwrap();
+    registry.element_bounds(&global_id).map(BoundsInfo::from)
+}
+
+/// Frame an element by GlobalId, e.g. when it is clicked in the model browser
+/// Moves the camera over `duration` seconds, or instantly when it is 0.
+/// Follows the element to its exploded position when the model is exploded.
+pub async fn zoom_to_element(global_id: String, duration: f32) -> Result<(), String> {
+    let bounds = {
+        let registry = MODEL_REGISTRY.lock().unwrap();
+        let cached = registry
+            .element_bounds(&global_id)
+            .ok_or_else(|| format!("Element not found: {}", global_id))?;
+        if EXPLODE.lock().unwrap().0 > 0.0 {
+            registry
+                .iter_visible()
+                .find_map(|(_, reg_model)| {
+                    let mut mesh = reg_model.model.generate_meshes();
+                    apply_explode(&reg_model.model, &mut mesh);
+                    mesh.elements.into_iter().find(|e| e.global_id == global_id)
+                })
+                .map(|e| e.bounds)
+                .unwrap_or(cached)
+        } else {
+            cached
+        }
+    };
+
+    let path = {
+        let mut renderer = RENDERER.lock().unwrap();
+        let r = renderer.as_mut().ok_or("Renderer not initialized")?;
+        if duration <= 0.0 {
+            r.fit_camera_to_bounds(bounds.min, bounds.max);
+            return Ok(());
+        }
+        CameraPath {
+            waypoints: vec![r.camera.state(), r.camera_state_fitting(bounds.min, bounds.max)],
+            durations: vec![duration],
+        }
+    };
+    play_walkthrough_frames(&path, CAMERA_ANIMATION_FPS, |_r, _t| Ok(true)).await
+}
+
+/// Current camera position and target (e.g. to record a walkthrough waypoint)
+#[frb(sync)]
+pub fn get_camera_state() -> Result<CameraState, String> {
+    let renderer = RENDERER.lock().unwrap();
+    let r = renderer.as_ref().ok_or("Renderer not initialized")?;
+    Ok(r.camera.state())
+}
+
+/// Move the camera to a saved position and target
+#[frb(sync)]
+pub fn set_camera_state(state: CameraState) -> Result<(), String> {
+    let mut renderer = RENDERER.lock().unwrap();
+    let r = renderer.as_mut().ok_or("Renderer not initialized")?;
+    r.camera.set_state(state);
+    Ok(())
+}
+
+/// Tilt the camera about its view direction by `radians` from a level
+/// horizon (positive turns the picture counter-clockwise)
+#[frb(sync)]
+pub fn set_camera_roll(radians: f32) -> Result<(), String> {
+    if !radians.is_finite() {
+        return Err(format!("Invalid camera roll: {}", radians));
+    }
+    let mut renderer = RENDERER.lock().unwrap();
+    let r = renderer.as_mut().ok_or("Renderer not initialized")?;
+    r.camera.set_roll(radians);
+    Ok(())
+}
+
+/// Current camera roll in radians (0 = level horizon)
+#[frb(sync)]
+pub fn get_camera_roll() -> Result<f32, String> {
+    let renderer = RENDERER.lock().unwrap();
+    let r = renderer.as_ref().ok_or("Renderer not initialized")?;
+    Ok(r.camera.roll())
+}
+
+/// Remove any camera roll, so world up points up on screen again
+#[frb(sync)]
+pub fn level_camera_horizon() -> Result<(), String> {
+    let mut renderer = RENDERER.lock().unwrap();
+    let r = renderer.as_mut().ok_or("Renderer not initialized")?;
+    r.camera.level_horizon();
+    Ok(())
+}
+
+/// Orbit around the model surface under a screen position (0-1 range) from
+/// now on, as in CAD "pivot on click"
+/// The camera shifts to put that point at the view center, keeping its view
+/// direction and distance. Returns false, leaving the camera as is, when
+/// nothing was hit.
+#[frb(sync)]
+pub fn set_orbit_pivot(x: f32, y: f32) -> Result<bool, String> {
+    let registry = MODEL_REGISTRY.lock().unwrap();
+    let mut renderer = RENDERER.lock().unwrap();
+    let r = renderer.as_mut().ok_or("Renderer not initialized")?;
+    let (ray_origin, ray_dir) = r.camera.screen_to_ray(x, y);
+
+    let hit = registry
+        .iter_visible()
+        .filter_map(|(_model_id, reg_model)| pick_mesh(reg_model).intersect(ray_origin, ray_dir).map(|(t, _)| t))
+        .min_by(f32::total_cmp);
+    let Some(t) = hit else {
+        return Ok(false);
+    };
+    r.camera.set_pivot((ray_origin + ray_dir * t).to_array());
+    Ok(true)
+}
+
+/// Play a camera walkthrough in real time, streaming the path time (seconds)
+/// of each frame after the camera has moved there
+/// The Flutter side renders frames as usual; closing the stream stops playback
+/// with the camera where it was.
+pub async fn play_walkthrough(path: CameraPath, fps: u32, sink: StreamSink<f64>) -> Result<(), String> {
+    play_walkthrough_frames(&path, fps, |_r, t| Ok(sink.add(t as f64).is_ok())).await
+}
+
+/// Play a camera walkthrough, streaming each rendered frame (RGBA) for
+/// display or recording
+pub async fn record_walkthrough(path: CameraPath, fps: u32, sink: StreamSink<Vec<u8>>) -> Result<(), String> {
+    play_walkthrough_frames(&path, fps, |r, _t| Ok(sink.add(r.render_frame()?).is_ok())).await
+}
+
+/// Step the renderer camera along a path at `fps`, calling `on_frame` after
+/// each move until it returns false (listener gone)
+async fn play_walkthrough_frames(
+    path: &CameraPath,
+    fps: u32,
+    mut on_frame: impl FnMut(&Renderer, f32) -> Result<bool, String>,
+) -> Result<(), String> {
+    path.validate()?;
+    if fps == 0 {
+        return Err("Frame rate must be positive".to_string());
+    }
+    let frame_interval = tokio::time::Duration::from_secs_f64(1.0 / fps as f64);
+
+    let times = path.frame_times(fps);
+    for (frame, &t) in times.iter().enumerate() {
+        // Lock per frame so other calls can run between frames
+        let keep_going = {
+            let mut renderer = RENDERER.lock().unwrap();
+            let r = renderer.as_mut().ok_or("Renderer not initialized")?;
+            let state = path.sample_state(t).ok_or("Invalid camera path")?;
+            r.camera.set_state(state);
+            on_frame(r, t)?
+        };
+        if !keep_going {
+            break; // Listener went away
+        }
+        if frame + 1 < times.len() {
+            tokio::time::sleep(frame_interval).await;
+        }
+    }
+
+    Ok(())
+}
+
+/// Render a camera path as `frame_count` numbered PNGs (`frame_00000.png`,
+/// ...) in `out_dir` for assembling into video, streaming the number of
+/// frames written so far
+///
+/// Frames are spread evenly from the first waypoint to the last and are the
+/// same for the same scene and path, whatever else was rendered before. The
+/// live camera is left where it was; closing the stream stops the export.
+pub async fn render_sequence(
+    path: CameraPath,
+    frame_count: u32,
+    out_dir: String,
+    sink: StreamSink<u32>,
+) -> Result<(), String> {
+    path.validate()?;
+    if frame_count == 0 {
+        return Err("Frame count must be positive".to_string());
+    }
+    let out_dir = std::path::PathBuf::from(out_dir);
+    tokio::fs::create_dir_all(&out_dir)
+        .await
+        .map_err(|e| format!("Failed to create {}: {}", out_dir.display(), e))?;
+
+    for frame in 0..frame_count {
+        // Lock per frame so other calls can run between frames
+        let png = {
+            let mut renderer = RENDERER.lock().unwrap();
+            let r = renderer.as_mut().ok_or("Renderer not initialized")?;
+            r.render_sequence_frame(&path, frame, frame_count)?
+        };
+        let file = out_dir.join(sequence_frame_name(frame));
+        tokio::fs::write(&file, png)
+            .await
+            .map_err(|e| format!("Failed to write {}: {}", file.display(), e))?;
+        if sink.add(frame + 1).is_err() {
+            break; // Listener went away
+        }
+    }
     Ok(())
 }
 
@@ -496,6 +1713,143 @@ pub fn fit_camera_to_all_models() -> Result<(), String> {
 // Phase 5 API: Element Selection
 // ============================================================================
 
+/// Everything under a tapped screen position
+#[derive(Debug, Clone, PartialEq)]
+pub struct PickInfo {
+    /// GlobalId of the element hit
+    pub global_id: String,
+    /// Surface point hit, in scene coordinates (as used by section planes
+    /// and annotations)
+    pub world_point: [f32; 3],
+    /// Unit normal of the surface hit, facing the camera
+    pub normal: [f32; 3],
+    /// Distance from the camera (ray origin) to the point
+    pub distance: f32,
+}
+
+/// Element, surface point and normal under a screen position (0-1 range,
+/// origin top-left), for placing section planes or annotations where the
+/// user taps. Searches all visible models; None if nothing was hit.
+#[frb(sync)]
+pub fn pick_info(x: f32, y: f32) -> Result<Option<PickInfo>, String> {
+    let registry = MODEL_REGISTRY.lock().unwrap();
+    if registry.is_empty() {
+        return Err("No model loaded".to_string());
+    }
+    let renderer = RENDERER.lock().unwrap();
+    let r = renderer.as_ref().ok_or("Renderer not initialized")?;
+    let (ray_origin, ray_dir) = r.camera.screen_to_ray(x, y);
+
+    let pick_meshes: Vec<Arc<PickMesh>> = registry.iter_visible().map(|(_, reg_model)| pick_mesh(reg_model)).collect();
+    Ok(closest_pick(pick_meshes.iter().map(Arc::as_ref), ray_origin, ray_dir))
+}
+
+/// Closest surface hit by a ray among pick meshes
+fn closest_pick<'a>(
+    pick_meshes: impl IntoIterator<Item = &'a PickMesh>,
+    ray_origin: Vec3,
+    ray_dir: Vec3,
+) -> Option<PickInfo> {
+    pick_meshes
+        .into_iter()
+        .filter_map(|pick_mesh| {
+            let (t, triangle) = pick_mesh.intersect(ray_origin, ray_dir)?;
+            Some(PickInfo {
+                global_id: pick_mesh.element_at(triangle)?.global_id.clone(),
+                world_point: (ray_origin + ray_dir * t).to_array(),
+                normal: pick_mesh.facing_normal(triangle, ray_dir)?.to_array(),
+                distance: t,
+            })
+        })
+        .min_by(|a, b| a.distance.total_cmp(&b.distance))
+}
+
+/// What a snapped pick landed on
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SnapType {
+    /// A corner of the element's mesh
+    Vertex,
+    /// The middle of a triangle edge
+    EdgeMidpoint,
+    /// The surface point hit (no corner or edge within the radius)
+    Surface,
+}
+
+/// Point a pick snapped to, in scene coordinates
+#[derive(Debug, Clone, PartialEq)]
+pub struct SnapResult {
+    pub world_point: [f32; 3],
+    pub snap_type: SnapType,
+}
+
+/// Pick like `pick_info`, then snap to the nearest vertex or edge midpoint
+/// of the element hit within `radius_px` viewport pixels of the screen
+/// position (0-1 range, origin top-left), for measurements that land on
+/// corners. Falls back to the surface point; None if nothing was hit.
+#[frb(sync)]
+pub fn snap_pick(x: f32, y: f32, radius_px: f32) -> Result<Option<SnapResult>, String> {
+    let registry = MODEL_REGISTRY.lock().unwrap();
+    if registry.is_empty() {
+        return Err("No model loaded".to_string());
+    }
+    let renderer = RENDERER.lock().unwrap();
+    let r = renderer.as_ref().ok_or("Renderer not initialized")?;
+    let scene = r.scene.as_ref().ok_or("Scene not initialized")?;
+    let viewport = (scene.width as f32, scene.height as f32);
+
+    let pick_meshes: Vec<Arc<PickMesh>> = registry.iter_visible().map(|(_, reg_model)| pick_mesh(reg_model)).collect();
+    Ok(closest_snap(pick_meshes.iter().map(Arc::as_ref), &r.camera, viewport, (x, y), radius_px))
+}
+
+/// Surface hit under a screen position, snapped to the closest vertex or
+/// edge midpoint of the element hit within `radius_px` on screen
+fn closest_snap<'a>(
+    pick_meshes: impl IntoIterator<Item = &'a PickMesh>,
+    camera: &Camera,
+    viewport: (f32, f32),
+    (x, y): (f32, f32),
+    radius_px: f32,
+) -> Option<SnapResult> {
+    let (ray_origin, ray_dir) = camera.screen_to_ray(x, y);
+    let (t, pick_mesh, element) = pick_meshes
+        .into_iter()
+        .filter_map(|pick_mesh| {
+            let (t, triangle) = pick_mesh.intersect(ray_origin, ray_dir)?;
+            Some((t, pick_mesh, pick_mesh.element_at(triangle)?))
+        })
+        .min_by(|a, b| a.0.total_cmp(&b.0))?;
+
+    let mesh = &pick_mesh.mesh;
+    let cursor = (x * viewport.0, y * viewport.1);
+    let vertex = |i: u32| Vec3::from_slice(&mesh.vertices[i as usize * 3..i as usize * 3 + 3]);
+    let start = (element.triangle_start as usize * 3).min(mesh.indices.len());
+    let end = (start + element.triangle_count as usize * 3).min(mesh.indices.len());
+    // Vertices first, so an edge midpoint only wins when strictly closer
+    let triangles = mesh.indices[start..end].chunks_exact(3);
+    let corners = triangles.clone().flatten().map(|&i| (vertex(i), SnapType::Vertex));
+    let midpoints = triangles.flat_map(|tri| {
+        [(tri[0], tri[1]), (tri[1], tri[2]), (tri[2], tri[0])]
+            .map(|(a, b)| ((vertex(a) + vertex(b)) * 0.5, SnapType::EdgeMidpoint))
+    });
+
+    let mut best: Option<(f32, Vec3, SnapType)> = None;
+    for (point, snap_type) in corners.chain(midpoints) {
+        let Some((px, py, _)) = camera.project_point(point, viewport) else {
+            continue;
+        };
+        let distance = ((px - cursor.0).powi(2) + (py - cursor.1).powi(2)).sqrt();
+        if distance <= radius_px && best.is_none_or(|(closest, _, _)| distance < closest) {
+            best = Some((distance, point, snap_type));
+        }
+    }
+
+    let (point, snap_type) = best.map_or((ray_origin + ray_dir * t, SnapType::Surface), |(_, p, s)| (p, s));
+    Some(SnapResult {
+        world_point: point.to_array(),
+        snap_type,
+    })
+}
+
 /// Pick element at screen coordinates (searches all visible models)
 /// screen_x and screen_y are normalized (0-1) with origin at top-left
 #[frb(sync)]
@@ -513,27 +1867,41 @@ pub fn pick_element(screen_x: f32, screen_y: f32) -> Result<Option<ElementInfo>,
     // Cast ray from screen position
     let (ray_origin, ray_dir) = r.camera.screen_to_ray(screen_x, screen_y);
 
-    // Find closest intersecting element across all visible models
-    let mut closest: Option<(f32, ElementInfo)> = None;
+    // Element of the closest surface hit across all visible models
+    let closest = registry
+        .iter_visible()
+        .filter_map(|(_model_id, reg_model)| {
+            let pick_mesh = pick_mesh(reg_model);
+            let (t, triangle) = pick_mesh.intersect(ray_origin, ray_dir)?;
+            Some((t, pick_mesh.element_at(triangle)?.clone()))
+        })
+        .min_by(|a, b| a.0.total_cmp(&b.0));
 
-    for (_model_id, reg_model) in registry.iter_visible() {
-        let mesh = reg_model.model.generate_meshes();
+    Ok(closest.map(|(_, e)| e))
+}
 
-        for element in &mesh.elements {
-            let box_min = Vec3::from_array(element.bounds.min);
-            let box_max = Vec3::from_array(element.bounds.max);
-
-            if let Some(t) = ray_aabb_intersect(ray_origin, ray_dir, box_min, box_max) {
-                match &closest {
-                    None => closest = Some((t, element.clone())),
-                    Some((closest_t, _)) if t < *closest_t => closest = Some((t, element.clone())),
-                    _ => {}
-                }
-            }
-        }
+/// GlobalIds of the elements of all visible models whose bounding boxes
+/// overlap the box `min`-`max`, e.g. to select everything in a dragged box
+/// Boxes are those of the assembled model (not exploded), moved by each
+/// model's transform.
+#[frb(sync)]
+pub fn elements_in_box(min: [f32; 3], max: [f32; 3]) -> Result<Vec<String>, String> {
+    if min.iter().chain(&max).any(|c| !c.is_finite()) || (0..3).any(|k| min[k] > max[k]) {
+        return Err(format!("Invalid box: {:?} to {:?}", min, max));
     }
+    Ok(MODEL_REGISTRY.lock().unwrap().elements_in_box(min, max))
+}
 
-    Ok(closest.map(|(_, e)| e))
+/// GlobalIds of the elements of all visible models whose bounding boxes
+/// come within `radius` of `point` ("what's near here")
+/// Boxes are those of the assembled model (not exploded), moved by each
+/// model's transform.
+#[frb(sync)]
+pub fn elements_near_point(point: [f32; 3], radius: f32) -> Result<Vec<String>, String> {
+    if point.iter().any(|c| !c.is_finite()) || !radius.is_finite() || radius < 0.0 {
+        return Err(format!("Invalid search: {} around {:?}", radius, point));
+    }
+    Ok(MODEL_REGISTRY.lock().unwrap().elements_near_point(point, radius))
 }
 
 /// Get all elements in the model (primary model)
@@ -541,7 +1909,8 @@ pub fn pick_element(screen_x: f32, screen_y: f32) -> Result<Option<ElementInfo>,
 pub fn get_all_elements() -> Result<Vec<ElementInfo>, String> {
     let registry = MODEL_REGISTRY.lock().unwrap();
     let reg_model = registry.get_primary_model().ok_or("No model loaded")?;
-    let mesh = reg_model.model.generate_meshes();
+    let mut mesh = reg_model.model.generate_meshes();
+    apply_explode(&reg_model.model, &mut mesh);
     Ok(mesh.elements)
 }
 
@@ -557,13 +1926,102 @@ pub fn get_all_elements_from_all_models() -> Result<Vec<ElementInfo>, String> {
     let mut all_elements = Vec::new();
 
     for (_model_id, reg_model) in registry.iter_visible() {
-        let mesh = reg_model.model.generate_meshes();
+        let mut mesh = reg_model.model.generate_meshes();
+        apply_explode(&reg_model.model, &mut mesh);
         all_elements.extend(mesh.elements);
     }
 
     Ok(all_elements)
 }
 
+/// Storey an element belongs to
+#[derive(Debug, Clone)]
+pub struct ElementStorey {
+    pub storey_id: i32,
+    pub storey_name: String,
+    pub elevation: Option<f64>,
+    /// True when the file has no containment link for the element and the
+    /// storey was picked by elevation (show as "probably on ...")
+    pub heuristic: bool,
+}
+
+/// Get the storey of an element by GlobalId (searches all loaded models)
+#[frb(sync)]
+pub fn get_element_storey(global_id: String) -> Option<ElementStorey> {
+    let registry = MODEL_REGISTRY.lock().unwrap();
+    registry.models().values().find_map(|reg_model| {
+        let (storey, heuristic) = reg_model.model.element_storey(&global_id)?;
+        Some(ElementStorey {
+            storey_id: storey.id,
+            storey_name: storey.name.clone(),
+            elevation: storey.elevation,
+            heuristic,
+        })
+    })
+}
+
+/// Get the GlobalIds of the elements connected to an element
+/// (IfcRelConnectsElements, e.g. wall joins)
+#[frb(sync)]
+pub fn get_connected_elements(global_id: String) -> Vec<String> {
+    let registry = MODEL_REGISTRY.lock().unwrap();
+    registry
+        .models()
+        .values()
+        .map(|reg_model| reg_model.model.connected_elements(&global_id))
+        .find(|connected| !connected.is_empty())
+        .unwrap_or_default()
+}
+
+/// Parsed source file kept for raw entity inspection, and its path
+/// (parsed on first inspection; a model's IFC file is not kept after loading)
+static INSPECTED_FILE: Mutex<Option<(String, Arc<IfcFile>)>> = Mutex::new(None);
+
+/// The parsed source file of a model, read again unless it is the one
+/// kept from the last call
+async fn source_ifc_file(path: String) -> Result<Arc<IfcFile>, String> {
+    let cached = INSPECTED_FILE
+        .lock()
+        .unwrap()
+        .as_ref()
+        .filter(|(cached_path, _)| *cached_path == path)
+        .map(|(_, file)| file.clone());
+    if let Some(file) = cached {
+        return Ok(file);
+    }
+    let content = tokio::fs::read_to_string(&path)
+        .await
+        .map_err(|e| format!("Failed to read file: {}", e))?;
+    let file = Arc::new(run_blocking(move || IfcFile::parse(&content)).await?);
+    *INSPECTED_FILE.lock().unwrap() = Some((path, file.clone()));
+    Ok(file)
+}
+
+/// Get the raw STEP attributes of an element (or any entity with a
+/// GlobalId), for inspecting what the file says
+/// Values are shown as in the file: strings quoted, references as `#id`,
+/// enumerations as `.VALUE.`, lists in parentheses. The source file is read
+/// again on the first call for a model, then kept until another model's
+/// file is inspected.
+pub async fn get_entity_attributes(global_id: String) -> Result<Vec<AttributeView>, String> {
+    let path = {
+        let registry = MODEL_REGISTRY.lock().unwrap();
+        let reg_model = registry
+            .iter()
+            .map(|(_, m)| m)
+            .find(|m| m.element_bounds.contains_key(&global_id))
+            .or_else(|| registry.get_primary_model())
+            .ok_or("No model loaded")?;
+        reg_model.file_path.clone().ok_or("Model has no IFC source file")?
+    };
+
+    let ifc_file = source_ifc_file(path).await?;
+    let entity = ifc_file
+        .get_entity_by_global_id(&global_id)
+        .ok_or_else(|| format!("Entity '{}' not found", global_id))?;
+    Ok(entity.attribute_views())
+}
+
 /// Get element count by type (primary model)
 #[frb(sync)]
 pub fn get_element_counts() -> Result<std::collections::HashMap<String, usize>, String> {
@@ -590,6 +2048,229 @@ pub fn set_element_type_visible(element_type: String, visible: bool) -> Result<(
     Ok(())
 }
 
+/// Get the presentation layers (CAD layers) of all loaded models, sorted
+#[frb(sync)]
+pub fn list_layers() -> Vec<String> {
+    let registry = MODEL_REGISTRY.lock().unwrap();
+    let layers: std::collections::BTreeSet<String> =
+        registry.iter().flat_map(|(_, m)| m.model.layers()).collect();
+    layers.into_iter().collect()
+}
+
+/// Show or hide the elements on a presentation layer, reloading the scene
+#[frb(sync)]
+pub fn set_layer_visible(layer: String, visible: bool) -> Result<(), String> {
+    {
+        let mut hidden = HIDDEN_LAYERS.lock().unwrap();
+        if visible {
+            hidden.remove(&layer);
+        } else {
+            hidden.insert(layer);
+        }
+    }
+    if !MODEL_REGISTRY.lock().unwrap().is_empty() && RENDERER.lock().unwrap().is_some() {
+        reload_all_models_mesh()?;
+    }
+    Ok(())
+}
+
+/// Check if a presentation layer is visible
+#[frb(sync)]
+pub fn is_layer_visible(layer: String) -> bool {
+    !HIDDEN_LAYERS.lock().unwrap().contains(&layer)
+}
+
+/// Drop the elements on hidden presentation layers from a model mesh
+fn apply_layer_visibility(model: &BimModel, mesh: &mut ModelMesh) {
+    mesh.hide_layers(model, &HIDDEN_LAYERS.lock().unwrap());
+}
+
+// ============================================================================
+// Element Handles
+// ============================================================================
+
+/// Compact handle of an element by GlobalId
+/// Handles are u32 numbers assigned when a model is loaded and kept for the
+/// session: reloading a file gives its elements the same handles. Use them
+/// instead of GlobalIds for element sets passed every frame.
+#[frb(sync)]
+pub fn handle_for_global_id(global_id: String) -> Option<u32> {
+    MODEL_REGISTRY.lock().unwrap().handle_for_global_id(&global_id)
+}
+
+/// GlobalId of the element a handle stands for
+#[frb(sync)]
+pub fn global_id_for_handle(handle: u32) -> Option<String> {
+    MODEL_REGISTRY.lock().unwrap().global_id_for_handle(handle).map(str::to_string)
+}
+
+/// GlobalIds of handles, failing on the first unknown one
+fn resolve_handles(registry: &ModelRegistry, handles: &[u32]) -> Result<Vec<String>, String> {
+    handles
+        .iter()
+        .map(|&handle| {
+            registry
+                .global_id_for_handle(handle)
+                .map(str::to_string)
+                .ok_or_else(|| format!("Unknown element handle: {}", handle))
+        })
+        .collect()
+}
+
+/// Reload the scene after an element override changed, if it is shown
+fn reload_for_overrides() -> Result<(), String> {
+    if !MODEL_REGISTRY.lock().unwrap().is_empty() && RENDERER.lock().unwrap().is_some() {
+        reload_all_models_mesh()?;
+    }
+    Ok(())
+}
+
+/// Select an element by handle for highlighting (None clears the selection)
+#[frb(sync)]
+pub fn set_selected_handle(handle: Option<u32>) -> Result<(), String> {
+    let element_id = match handle {
+        Some(handle) => {
+            let registry = MODEL_REGISTRY.lock().unwrap();
+            let global_id = resolve_handles(&registry, &[handle])?.remove(0);
+            let id = registry
+                .iter()
+                .flat_map(|(_, m)| m.model.products())
+                .find(|(_, p)| p.global_id == global_id)
+                .map(|(_, p)| p.id)
+                .ok_or_else(|| format!("Element {} is not loaded", global_id))?;
+            Some(id)
+        }
+        None => None,
+    };
+    set_selected_element(element_id)
+}
+
+/// Show or hide elements by handle, reloading the scene
+#[frb(sync)]
+pub fn set_elements_visible(handles: Vec<u32>, visible: bool) -> Result<(), String> {
+    let global_ids = resolve_handles(&MODEL_REGISTRY.lock().unwrap(), &handles)?;
+    {
+        let mut hidden = HIDDEN_ELEMENTS.lock().unwrap();
+        for global_id in global_ids {
+            if visible {
+                hidden.remove(&global_id);
+            } else {
+                hidden.insert(global_id);
+            }
+        }
+    }
+    reload_for_overrides()
+}
+
+/// Show every element hidden with set_elements_visible, reloading the scene
+#[frb(sync)]
+pub fn show_all_elements() -> Result<(), String> {
+    HIDDEN_ELEMENTS.lock().unwrap().clear();
+    reload_for_overrides()
+}
+
+/// Color elements by handle, overriding their type or material color until
+/// reset_element_colors; reloads the scene
+#[frb(sync)]
+pub fn set_handle_colors(handles: Vec<u32>, r: u8, g: u8, b: u8, a: u8) -> Result<(), String> {
+    let global_ids = resolve_handles(&MODEL_REGISTRY.lock().unwrap(), &handles)?;
+    let color = [r, g, b, a].map(|c| c as f32 / 255.0);
+    ELEMENT_COLORS
+        .lock()
+        .unwrap()
+        .extend(global_ids.into_iter().map(|global_id| (global_id, color)));
+    reload_for_overrides()
+}
+
+/// GlobalIds of the elements on a storey, in every loaded model that has it
+fn storey_global_ids(registry: &ModelRegistry, storey_id: i32) -> Result<Vec<String>, String> {
+    let models: Vec<&BimModel> = registry
+        .iter()
+        .map(|(_, m)| &m.model)
+        .filter(|m| m.storeys.iter().any(|s| s.id == storey_id))
+        .collect();
+    if models.is_empty() {
+        return Err(format!("Storey not found: #{}", storey_id));
+    }
+    Ok(models
+        .into_iter()
+        .flat_map(|m| m.storey_elements(storey_id))
+        .map(str::to_string)
+        .collect())
+}
+
+/// Color every element on a storey (see get_element_storey), overriding
+/// their type or material color until reset; reloads the scene
+#[frb(sync)]
+pub fn set_storey_color(storey_id: i32, r: u8, g: u8, b: u8, a: u8) -> Result<(), String> {
+    let global_ids = storey_global_ids(&MODEL_REGISTRY.lock().unwrap(), storey_id)?;
+    let color = [r, g, b, a].map(|c| c as f32 / 255.0);
+    ELEMENT_COLORS
+        .lock()
+        .unwrap()
+        .extend(global_ids.into_iter().map(|global_id| (global_id, color)));
+    reload_for_overrides()
+}
+
+/// Drop the color overrides of every element on a storey, reloading the
+/// scene
+#[frb(sync)]
+pub fn reset_storey_color(storey_id: i32) -> Result<(), String> {
+    let global_ids = storey_global_ids(&MODEL_REGISTRY.lock().unwrap(), storey_id)?;
+    {
+        let mut colors = ELEMENT_COLORS.lock().unwrap();
+        for global_id in &global_ids {
+            colors.remove(global_id);
+        }
+    }
+    reload_for_overrides()
+}
+
+/// Drop hidden elements and apply element color overrides to a model mesh
+fn apply_element_overrides(mesh: &mut ModelMesh) {
+    let hidden = HIDDEN_ELEMENTS.lock().unwrap();
+    if !hidden.is_empty() {
+        mesh.retain_elements(|e| !hidden.contains(&e.global_id));
+    }
+    mesh.set_element_colors(&ELEMENT_COLORS.lock().unwrap());
+}
+
+/// Simplify all elements of a type to `ratio` of their triangles (0 to 1,
+/// where 1 is full detail), reloading the scene
+#[frb(sync)]
+pub fn set_type_lod(ifc_type: String, ratio: f32) -> Result<(), String> {
+    if !(ratio > 0.0 && ratio <= 1.0) {
+        return Err(format!("LOD ratio must be in (0, 1], got {}", ratio));
+    }
+    {
+        let mut lod = TYPE_LOD.lock().unwrap();
+        if ratio == 1.0 {
+            lod.remove(&ifc_type);
+        } else {
+            lod.insert(ifc_type, ratio);
+        }
+    }
+    if !MODEL_REGISTRY.lock().unwrap().is_empty() && RENDERER.lock().unwrap().is_some() {
+        reload_all_models_mesh()?;
+    }
+    Ok(())
+}
+
+/// Restore full detail for all element types
+#[frb(sync)]
+pub fn reset_lod() -> Result<(), String> {
+    TYPE_LOD.lock().unwrap().clear();
+    if !MODEL_REGISTRY.lock().unwrap().is_empty() && RENDERER.lock().unwrap().is_some() {
+        reload_all_models_mesh()?;
+    }
+    Ok(())
+}
+
+/// Simplify the elements of types with a level of detail set
+fn apply_type_lod(mesh: &mut ModelMesh) {
+    mesh.apply_lod(&TYPE_LOD.lock().unwrap());
+}
+
 /// Check if an element type is visible
 #[frb(sync)]
 pub fn is_element_type_visible(element_type: String) -> bool {
@@ -735,17 +2416,63 @@ pub fn set_selected_element(element_id: Option<i32>) -> Result<(), String> {
     Ok(())
 }
 
+/// Current explode view setting (factor 0 = assembled model)
+static EXPLODE: Mutex<(f32, ExplodeMode)> = Mutex::new((0.0, ExplodeMode::Vertical));
+
+/// Apply the current explode setting to a model's generated mesh
+fn apply_explode(model: &BimModel, mesh: &mut ModelMesh) {
+    let (factor, mode) = *EXPLODE.lock().unwrap();
+    mesh.apply_explode(factor, mode, &model.storey_elevations());
+}
+
+/// Mesh and BVH of a model for ray casting, at the current explode setting
+fn pick_mesh(reg_model: &RegisteredModel) -> Arc<PickMesh> {
+    reg_model.pick_mesh(*EXPLODE.lock().unwrap())
+}
+
+/// Explode the model so elements separate for inspection
+/// factor: 0.0 = assembled, 1.0 = offsets equal to each element's distance
+/// from the model center (or storey elevation for ByStorey)
+#[frb(sync)]
+pub fn set_explode(factor: f32, mode: ExplodeMode) -> Result<(), String> {
+    if !factor.is_finite() || factor < 0.0 {
+        return Err(format!("Invalid explode factor: {}", factor));
+    }
+    *EXPLODE.lock().unwrap() = (factor, mode);
+
+    let renderer_ready = RENDERER.lock().unwrap().is_some();
+    let has_models = !MODEL_REGISTRY.lock().unwrap().is_empty();
+    if renderer_ready && has_models {
+        reload_all_models_mesh()?;
+    }
+    Ok(())
+}
+
+/// Get the current explode factor
+#[frb(sync)]
+pub fn get_explode_factor() -> f32 {
+    EXPLODE.lock().unwrap().0
+}
+
 /// Reload model mesh with current visibility and highlight settings (primary model)
 #[frb(sync)]
 pub fn reload_model_mesh() -> Result<String, String> {
     let registry = MODEL_REGISTRY.lock().unwrap();
     let reg_model = registry.get_primary_model().ok_or("No model loaded")?;
+    if !reg_model.geometry_loaded {
+        return Err("Primary model geometry is unloaded".to_string());
+    }
 
     let visibility = VISIBILITY.lock().unwrap();
     let selected = SELECTED_ELEMENT.lock().unwrap();
 
     // Generate mesh with visibility filter and highlight
-    let mesh = reg_model.model.generate_meshes_filtered(&visibility, *selected);
+    let mut mesh = reg_model.model.generate_meshes_filtered(&visibility, *selected);
+    apply_layer_visibility(&reg_model.model, &mut mesh);
+    apply_element_overrides(&mut mesh);
+    apply_type_lod(&mut mesh);
+    apply_explode(&reg_model.model, &mut mesh);
+    apply_diff_view(&mut mesh);
     let vertex_count = mesh.vertices.len() / 3;
     let triangle_count = mesh.indices.len() / 3;
 
@@ -753,7 +2480,7 @@ pub fn reload_model_mesh() -> Result<String, String> {
     let mut renderer = RENDERER.lock().unwrap();
     let r = renderer.as_mut().ok_or("Renderer not initialized")?;
 
-    r.load_mesh(&mesh.vertices, &mesh.normals, &mesh.colors, &mesh.indices)?;
+    upload_scene_mesh(r, &mesh, *selected)?;
 
     Ok(format!(
         "Mesh reloaded: {} vertices, {} triangles",
@@ -773,34 +2500,26 @@ pub fn reload_all_models_mesh() -> Result<String, String> {
     let visibility = VISIBILITY.lock().unwrap();
     let selected = SELECTED_ELEMENT.lock().unwrap();
 
-    // Collect mesh data from all visible models
-    let mut all_vertices = Vec::new();
-    let mut all_normals = Vec::new();
-    let mut all_colors = Vec::new();
-    let mut all_indices = Vec::new();
-
+    // Combine the meshes of all visible models
+    let mut combined = ModelMesh::default();
     for (_id, reg_model) in registry.iter_visible() {
-        let mesh = reg_model.model.generate_meshes_filtered(&visibility, *selected);
-
-        // Offset indices by current vertex count
-        let vertex_offset = (all_vertices.len() / 3) as u32;
-        for idx in &mesh.indices {
-            all_indices.push(idx + vertex_offset);
-        }
-
-        all_vertices.extend(&mesh.vertices);
-        all_normals.extend(&mesh.normals);
-        all_colors.extend(&mesh.colors);
+        let mut mesh = reg_model.model.generate_meshes_filtered(&visibility, *selected);
+        apply_layer_visibility(&reg_model.model, &mut mesh);
+        apply_element_overrides(&mut mesh);
+        apply_type_lod(&mut mesh);
+        apply_explode(&reg_model.model, &mut mesh);
+        apply_diff_view(&mut mesh);
+        combined.append(&mesh);
     }
 
-    let vertex_count = all_vertices.len() / 3;
-    let triangle_count = all_indices.len() / 3;
+    let vertex_count = combined.vertices.len() / 3;
+    let triangle_count = combined.indices.len() / 3;
 
     // Upload to renderer
     let mut renderer = RENDERER.lock().unwrap();
     let r = renderer.as_mut().ok_or("Renderer not initialized")?;
 
-    r.load_mesh(&all_vertices, &all_normals, &all_colors, &all_indices)?;
+    upload_scene_mesh(r, &combined, *selected)?;
 
     Ok(format!(
         "Reloaded {} models: {} vertices, {} triangles",
@@ -850,8 +2569,45 @@ pub fn set_ambient_color(r: f32, g: f32, b: f32) -> Result<(), String> {
     renderer.set_ambient_color(r, g, b)
 }
 
+/// Set all light settings at once (direction, color, intensity, ambient)
+#[frb(sync)]
+pub fn set_lighting(config: LightingConfig) -> Result<(), String> {
+    let mut renderer = RENDERER.lock().unwrap();
+    let r = renderer.as_mut().ok_or("Renderer not initialized")?;
+    r.set_lighting(&config)
+}
+
+/// Get the current light settings
+#[frb(sync)]
+pub fn get_lighting() -> Result<LightingConfig, String> {
+    let renderer = RENDERER.lock().unwrap();
+    let r = renderer.as_ref().ok_or("Renderer not initialized")?;
+    r.lighting()
+}
+
+/// Set the exposure applied to lit colors before tone mapping (0.0+)
+/// Default is 1.0. Exposure and tone mapping work on linear colors; the
+/// sRGB frame format applies gamma afterwards, so values here are not
+/// gamma-corrected (doubling exposure is less than twice as bright on screen).
+#[frb(sync)]
+pub fn set_exposure(value: f32) -> Result<(), String> {
+    let mut renderer = RENDERER.lock().unwrap();
+    let r = renderer.as_mut().ok_or("Renderer not initialized")?;
+    r.set_exposure(value)
+}
+
+/// Set the tone mapping curve for bright lighting (intensity > 1)
+/// Default is None, which clips to white; Reinhard and Aces roll off smoothly.
+#[frb(sync)]
+pub fn set_tonemap(mode: ToneMapping) -> Result<(), String> {
+    let mut renderer = RENDERER.lock().unwrap();
+    let r = renderer.as_mut().ok_or("Renderer not initialized")?;
+    r.set_tone_mapping(mode)
+}
+
 /// Set the render mode
-/// 0 = Shaded (default), 1 = Wireframe
+/// 0 = Shaded (default), 1 = Wireframe, 2 = Shaded with edges,
+/// 3 = Shaded with feature edges (creases and outlines only)
 #[frb(sync)]
 pub fn set_render_mode(mode: i32) -> Result<(), String> {
     let mut renderer = RENDERER.lock().unwrap();
@@ -859,13 +2615,15 @@ pub fn set_render_mode(mode: i32) -> Result<(), String> {
     let render_mode = match mode {
         0 => crate::renderer::RenderMode::Shaded,
         1 => crate::renderer::RenderMode::Wireframe,
+        2 => crate::renderer::RenderMode::ShadedWithEdges,
+        3 => crate::renderer::RenderMode::ShadedWithFeatureEdges,
         _ => return Err(format!("Invalid render mode: {}", mode)),
     };
     r.set_render_mode(render_mode)
 }
 
 /// Get the current render mode
-/// Returns: 0 = Shaded, 1 = Wireframe
+/// Returns: 0 = Shaded, 1 = Wireframe, 2 = Shaded with edges, 3 = Shaded with feature edges
 #[frb(sync)]
 pub fn get_render_mode() -> Result<i32, String> {
     let renderer = RENDERER.lock().unwrap();
@@ -873,9 +2631,60 @@ pub fn get_render_mode() -> Result<i32, String> {
     Ok(match r.get_render_mode()? {
         crate::renderer::RenderMode::Shaded => 0,
         crate::renderer::RenderMode::Wireframe => 1,
+        crate::renderer::RenderMode::ShadedWithEdges => 2,
+        crate::renderer::RenderMode::ShadedWithFeatureEdges => 3,
     })
 }
 
+/// Choose whether element colors are treated as sRGB (default) or passed to
+/// the shader unchanged (the old behavior: lighting in mixed color spaces)
+/// Loaded models are re-uploaded with the new setting.
+#[frb(sync)]
+pub fn set_srgb_vertex_colors(enabled: bool) -> Result<(), String> {
+    {
+        let mut renderer = RENDERER.lock().unwrap();
+        let r = renderer.as_mut().ok_or("Renderer not initialized")?;
+        r.set_srgb_vertex_colors(enabled)?;
+    }
+    if !MODEL_REGISTRY.lock().unwrap().is_empty() {
+        reload_all_models_mesh()?;
+    }
+    Ok(())
+}
+
+/// Turn occlusion culling (skipping elements hidden behind others) on or off
+/// Returns whether it is active: it needs a loaded model and device support.
+#[frb(sync)]
+pub fn set_occlusion_culling(enabled: bool) -> Result<bool, String> {
+    let mut renderer = RENDERER.lock().unwrap();
+    let r = renderer.as_mut().ok_or("Renderer not initialized")?;
+    r.set_occlusion_culling(enabled)
+}
+
+/// Get the material of an element by GlobalId (searches all loaded models)
+/// Elements without IFC styling report their type-based default color
+#[frb(sync)]
+pub fn get_element_material(global_id: String) -> Option<MaterialInfo> {
+    let registry = MODEL_REGISTRY.lock().unwrap();
+    let material = registry
+        .iter()
+        .find_map(|(_, reg_model)| reg_model.model.element_material(&global_id));
+    material
+}
+
+/// Get the material regions of an element by GlobalId (searches all loaded models)
+/// Each region is a triangle range of the element drawn with one material
+#[frb(sync)]
+pub fn get_element_material_regions(global_id: String) -> Vec<MaterialRegion> {
+    let registry = MODEL_REGISTRY.lock().unwrap();
+    let regions = registry
+        .iter()
+        .map(|(_, reg_model)| reg_model.model.element_material_regions(&global_id))
+        .find(|regions| !regions.is_empty())
+        .unwrap_or_default();
+    regions
+}
+
 /// Check if wireframe rendering is supported on this device
 #[frb(sync)]
 pub fn is_wireframe_supported() -> bool {
@@ -886,6 +2695,31 @@ pub fn is_wireframe_supported() -> bool {
         .unwrap_or(false)
 }
 
+/// Get the capabilities of the initialized GPU device
+/// Includes the limit tier that device creation succeeded with
+#[frb(sync)]
+pub fn get_gpu_capabilities() -> Option<GpuCapabilities> {
+    let renderer = RENDERER.lock().unwrap();
+    renderer.as_ref().and_then(|r| r.gpu.capabilities())
+}
+
+/// Get the most recent GPU error (validation, out-of-memory or uncaptured)
+/// Returns None if no error occurred or the renderer is not initialized
+#[frb(sync)]
+pub fn get_last_gpu_error() -> Option<String> {
+    let renderer = RENDERER.lock().unwrap();
+    renderer.as_ref().and_then(|r| r.gpu.last_error())
+}
+
+/// Clear the recorded GPU error
+#[frb(sync)]
+pub fn clear_gpu_error() {
+    let renderer = RENDERER.lock().unwrap();
+    if let Some(r) = renderer.as_ref() {
+        r.gpu.clear_last_error();
+    }
+}
+
 // ============================================================================
 // Phase 7: Measurements
 // ============================================================================
@@ -896,6 +2730,8 @@ pub enum MeasurementType {
     Distance,
     Area,
     Volume,
+    /// Path of picked surface points: length, segments and enclosed area
+    Polyline,
 }
 
 /// Measurement point in 3D space
@@ -916,9 +2752,51 @@ pub struct MeasurementResult {
 }
 
 /// Global measurement state
-static MEASUREMENT_POINTS: Mutex<Vec<MeasurementPoint>> = Mutex::new(Vec::new());
+/// Points are stored in full-precision world coordinates so georeferenced
+/// models keep millimeter accuracy far from the origin.
+static MEASUREMENT_POINTS: Mutex<Vec<WorldPoint>> = Mutex::new(Vec::new());
 static MEASUREMENT_TYPE: Mutex<Option<MeasurementType>> = Mutex::new(None);
 
+/// How the primary model's render coordinates map to its world coordinates
+///
+/// Render coordinates are Y-up, relative to the model's local origin and
+/// moved by its federation transform; world coordinates are in the model's
+/// source axes.
+struct MeasurementFrame {
+    origin: LocalOrigin,
+    up_axis: UpAxis,
+    transform: glam::Mat4,
+}
+
+impl MeasurementFrame {
+    /// Frame of the primary model (identity if none is loaded)
+    fn primary() -> Self {
+        let registry = MODEL_REGISTRY.lock().unwrap();
+        match registry.get_primary_model() {
+            Some(reg_model) => Self {
+                origin: reg_model.model.origin,
+                up_axis: reg_model.model.up_axis,
+                transform: glam::Mat4::from_cols_array(&reg_model.transform),
+            },
+            None => Self {
+                origin: LocalOrigin::default(),
+                up_axis: UpAxis::Y,
+                transform: glam::Mat4::IDENTITY,
+            },
+        }
+    }
+
+    fn to_world(&self, render: [f32; 3]) -> WorldPoint {
+        let local = self.transform.inverse().transform_point3(Vec3::from(render));
+        self.origin.to_world(self.up_axis.from_y_up(local.to_array()))
+    }
+
+    fn to_render(&self, world: WorldPoint) -> [f32; 3] {
+        let local = self.up_axis.to_y_up(self.origin.to_local(world));
+        self.transform.transform_point3(Vec3::from(local)).to_array()
+    }
+}
+
 /// Start a new measurement
 #[frb(sync)]
 pub fn start_measurement(measurement_type: String) -> Result<(), String> {
@@ -930,50 +2808,60 @@ pub fn start_measurement(measurement_type: String) -> Result<(), String> {
         "distance" => MeasurementType::Distance,
         "area" => MeasurementType::Area,
         "volume" => MeasurementType::Volume,
+        "polyline" => MeasurementType::Polyline,
         _ => return Err(format!("Invalid measurement type: {}", measurement_type)),
     });
 
     Ok(())
 }
 
-/// Add a measurement point
+/// Add a measurement point in render coordinates (e.g. a pick hit)
 /// Returns the current number of points
 #[frb(sync)]
 pub fn add_measurement_point(x: f32, y: f32, z: f32) -> Result<i32, String> {
+    let world = MeasurementFrame::primary().to_world([x, y, z]);
+    add_measurement_point_world(world[0], world[1], world[2])
+}
+
+/// Add a measurement point in world coordinates
+/// Returns the current number of points
+#[frb(sync)]
+pub fn add_measurement_point_world(x: f64, y: f64, z: f64) -> Result<i32, String> {
     let mut points = MEASUREMENT_POINTS.lock().unwrap();
-    points.push(MeasurementPoint { x, y, z });
+    points.push([x, y, z]);
     Ok(points.len() as i32)
 }
 
 /// Get the current measurement result
+/// Values are computed in world coordinates; returned points are in render
+/// coordinates
 #[frb(sync)]
 pub fn get_measurement_result() -> Result<MeasurementResult, String> {
+    let frame = MeasurementFrame::primary();
     let points = MEASUREMENT_POINTS.lock().unwrap();
     let mtype = MEASUREMENT_TYPE.lock().unwrap();
 
     let measurement_type = mtype.as_ref().ok_or("No measurement in progress")?;
 
+    let local_points = points
+        .iter()
+        .map(|&p| {
+            let [x, y, z] = frame.to_render(p);
+            MeasurementPoint { x, y, z }
+        })
+        .collect();
+
     match measurement_type {
         MeasurementType::Distance => {
             if points.len() < 2 {
                 return Err("Need at least 2 points for distance measurement".to_string());
             }
 
-            let mut total_distance = 0.0;
-            for i in 0..points.len() - 1 {
-                let p1 = &points[i];
-                let p2 = &points[i + 1];
-                let dx = p2.x - p1.x;
-                let dy = p2.y - p1.y;
-                let dz = p2.z - p1.z;
-                total_distance += ((dx * dx + dy * dy + dz * dz) as f64).sqrt();
-            }
-
             Ok(MeasurementResult {
                 measurement_type: "distance".to_string(),
-                value: total_distance,
+                value: coordinates::polyline_length(&points),
                 unit: "m".to_string(),
-                points: points.clone(),
+                points: local_points,
             })
         }
         MeasurementType::Area => {
@@ -981,19 +2869,12 @@ pub fn get_measurement_result() -> Result<MeasurementResult, String> {
                 return Err("Need at least 3 points for area measurement".to_string());
             }
 
-            // Calculate polygon area using shoelace formula (projected to XY plane)
-            let mut area = 0.0;
-            for i in 0..points.len() {
-                let j = (i + 1) % points.len();
-                area += (points[i].x * points[j].y - points[j].x * points[i].y) as f64;
-            }
-            area = (area / 2.0).abs();
-
+            // Polygon area projected to the XY plane
             Ok(MeasurementResult {
                 measurement_type: "area".to_string(),
-                value: area,
+                value: coordinates::polygon_area_xy(&points),
                 unit: "m²".to_string(),
-                points: points.clone(),
+                points: local_points,
             })
         }
         MeasurementType::Volume => {
@@ -1001,36 +2882,193 @@ pub fn get_measurement_result() -> Result<MeasurementResult, String> {
                 return Err("Need at least 4 points for volume measurement".to_string());
             }
 
-            // Calculate bounding box volume
-            let mut min_x = f32::MAX;
-            let mut max_x = f32::MIN;
-            let mut min_y = f32::MAX;
-            let mut max_y = f32::MIN;
-            let mut min_z = f32::MAX;
-            let mut max_z = f32::MIN;
-
-            for p in points.iter() {
-                min_x = min_x.min(p.x);
-                max_x = max_x.max(p.x);
-                min_y = min_y.min(p.y);
-                max_y = max_y.max(p.y);
-                min_z = min_z.min(p.z);
-                max_z = max_z.max(p.z);
-            }
-
-            let width = (max_x - min_x) as f64;
-            let depth = (max_y - min_y) as f64;
-            let height = (max_z - min_z) as f64;
-            let volume = width * depth * height;
-
+            // Bounding box volume
             Ok(MeasurementResult {
                 measurement_type: "volume".to_string(),
-                value: volume,
+                value: coordinates::bounding_volume(&points),
                 unit: "m³".to_string(),
-                points: points.clone(),
+                points: local_points,
+            })
+        }
+        MeasurementType::Polyline => {
+            if points.len() < 2 {
+                return Err("Need at least 2 points for polyline measurement".to_string());
+            }
+
+            Ok(MeasurementResult {
+                measurement_type: "polyline".to_string(),
+                value: coordinates::polyline_length(&points),
+                unit: "m".to_string(),
+                points: local_points,
+            })
+        }
+    }
+}
+
+/// Add the model surface point under a screen position (0-1 range) to a
+/// polyline measurement, starting one if none is in progress
+/// Returns the number of points, or None if nothing was hit.
+#[frb(sync)]
+pub fn add_measure_point(x: f32, y: f32) -> Result<Option<i32>, String> {
+    let hit = {
+        let registry = MODEL_REGISTRY.lock().unwrap();
+        if registry.is_empty() {
+            return Err("No model loaded".to_string());
+        }
+
+        let renderer = RENDERER.lock().unwrap();
+        let r = renderer.as_ref().ok_or("Renderer not initialized")?;
+        let (ray_origin, ray_dir) = r.camera.screen_to_ray(x, y);
+
+        // Closest surface hit across all visible models, in world coordinates
+        registry
+            .iter_visible()
+            .filter_map(|(_model_id, reg_model)| {
+                let (t, _) = pick_mesh(reg_model).intersect(ray_origin, ray_dir)?;
+                Some((t, reg_model.model.origin.to_world((ray_origin + ray_dir * t).to_array())))
             })
+            .min_by(|a, b| a.0.total_cmp(&b.0))
+            .map(|(_, world)| world)
+    };
+    let Some(world) = hit else {
+        return Ok(None);
+    };
+
+    {
+        let mut mtype = MEASUREMENT_TYPE.lock().unwrap();
+        if mtype.is_none() {
+            *mtype = Some(MeasurementType::Polyline);
+            MEASUREMENT_POINTS.lock().unwrap().clear();
         }
     }
+    add_measurement_point_world(world[0], world[1], world[2]).map(Some)
+}
+
+/// Finish the current measurement as a polyline: total and per-segment
+/// lengths, and the enclosed area when the points are roughly coplanar
+/// The measurement is cleared afterwards.
+#[frb(sync)]
+pub fn finish_measure() -> Result<PolylineMeasurement, String> {
+    let measurement = {
+        let points = MEASUREMENT_POINTS.lock().unwrap();
+        if points.len() < 2 {
+            return Err("Need at least 2 points for polyline measurement".to_string());
+        }
+        coordinates::measure_polyline(&points)
+    };
+    clear_measurement();
+    Ok(measurement)
+}
+
+/// Draw a measurement in the scene as a persistent line with point markers
+/// Points are local (render) coordinates, e.g. MeasurementResult.points;
+/// closed adds a segment back to the first point (for areas). Labels are
+/// left to Flutter. Returns the annotation index.
+#[frb(sync)]
+pub fn add_annotation(points: Vec<MeasurementPoint>, closed: bool) -> Result<u32, String> {
+    if points.len() < 2 {
+        return Err("Need at least 2 points for an annotation".to_string());
+    }
+    let mut renderer = RENDERER.lock().unwrap();
+    let r = renderer.as_mut().ok_or("Renderer not initialized")?;
+    let index = r.add_annotation(Annotation {
+        points: points.iter().map(|p| [p.x, p.y, p.z]).collect(),
+        closed,
+    })?;
+    Ok(index as u32)
+}
+
+/// Remove all measurement annotations from the scene
+#[frb(sync)]
+pub fn clear_annotations() -> Result<(), String> {
+    let mut renderer = RENDERER.lock().unwrap();
+    let r = renderer.as_mut().ok_or("Renderer not initialized")?;
+    r.clear_annotations()
+}
+
+/// Set the color of measurement annotations (RGBA, 0.0-1.0)
+/// Default is orange (1.0, 0.55, 0.0, 1.0)
+#[frb(sync)]
+pub fn set_annotation_color(r: f32, g: f32, b: f32, a: f32) -> Result<(), String> {
+    let mut renderer = RENDERER.lock().unwrap();
+    let renderer = renderer.as_mut().ok_or("Renderer not initialized")?;
+    renderer.set_annotation_color([r, g, b, a])
+}
+
+/// Set the depth bias of annotations drawn on model surfaces
+/// Negative values pull them towards the camera: constant in depth buffer
+/// units, slope_scale times the surface's depth slope. Default is (-4, -2.0),
+/// which keeps coplanar area fills stable at grazing angles.
+#[frb(sync)]
+pub fn set_overlay_depth_bias(constant: i32, slope_scale: f32) -> Result<(), String> {
+    if !slope_scale.is_finite() {
+        return Err(format!("Invalid slope scale: {}", slope_scale));
+    }
+    let mut renderer = RENDERER.lock().unwrap();
+    let r = renderer.as_mut().ok_or("Renderer not initialized")?;
+    r.set_overlay_depth_bias(DepthBias { constant, slope_scale })
+}
+
+/// Current depth bias of annotations
+#[frb(sync)]
+pub fn get_overlay_depth_bias() -> Result<DepthBias, String> {
+    let renderer = RENDERER.lock().unwrap();
+    let r = renderer.as_ref().ok_or("Renderer not initialized")?;
+    Ok(r.overlay_depth_bias())
+}
+
+/// Set the width of wireframe, edge and annotation lines in pixels
+/// Default is 1; pass the device pixel ratio (or a multiple) on high-DPI
+/// screens. Width must be greater than 0 and at most 32.
+#[frb(sync)]
+pub fn set_line_width(px: f32) -> Result<(), String> {
+    let mut renderer = RENDERER.lock().unwrap();
+    let r = renderer.as_mut().ok_or("Renderer not initialized")?;
+    r.set_line_width(px)
+}
+
+/// Current line width in pixels
+#[frb(sync)]
+pub fn get_line_width() -> Result<f32, String> {
+    let renderer = RENDERER.lock().unwrap();
+    let r = renderer.as_ref().ok_or("Renderer not initialized")?;
+    Ok(r.line_width())
+}
+
+/// Turn FXAA antialiasing on or off
+/// A cheap post-process that smooths jagged edges where MSAA is
+/// unavailable or too slow. Off by default; kept across re-initialization.
+#[frb(sync)]
+pub fn set_fxaa(enabled: bool) -> Result<(), String> {
+    let mut renderer = RENDERER.lock().unwrap();
+    let r = renderer.as_mut().ok_or("Renderer not initialized")?;
+    r.set_fxaa(enabled)
+}
+
+/// Whether FXAA antialiasing is on
+#[frb(sync)]
+pub fn get_fxaa() -> Result<bool, String> {
+    let renderer = RENDERER.lock().unwrap();
+    let r = renderer.as_ref().ok_or("Renderer not initialized")?;
+    Ok(r.fxaa())
+}
+
+/// Set MSAA antialiasing: 1 sample per pixel (off, the default) or 4
+/// Sharper than FXAA but slower on mobile GPUs; kept across
+/// re-initialization. Fails if the device cannot do it.
+#[frb(sync)]
+pub fn set_msaa_samples(samples: u32) -> Result<(), String> {
+    let mut renderer = RENDERER.lock().unwrap();
+    let r = renderer.as_mut().ok_or("Renderer not initialized")?;
+    r.set_msaa_samples(samples)
+}
+
+/// MSAA samples per pixel (1 = off)
+#[frb(sync)]
+pub fn get_msaa_samples() -> Result<u32, String> {
+    let renderer = RENDERER.lock().unwrap();
+    let r = renderer.as_ref().ok_or("Renderer not initialized")?;
+    Ok(r.msaa_samples())
 }
 
 /// Clear the current measurement
@@ -1149,6 +3187,19 @@ pub fn clear_section_plane() -> Result<(), String> {
     Ok(())
 }
 
+/// Outline the section cut: a line `width` pixels wide where the plane cuts
+/// surfaces, antialiased so the cut boundary does not look jagged in
+/// presentations. Width 0 turns it off (the default).
+#[frb(sync)]
+pub fn set_section_outline(width: f32, r: u8, g: u8, b: u8, a: u8) -> Result<(), String> {
+    if !width.is_finite() {
+        return Err(format!("Invalid outline width: {}", width));
+    }
+    let mut renderer = RENDERER.lock().unwrap();
+    let renderer_ref = renderer.as_mut().ok_or("Renderer not initialized")?;
+    renderer_ref.set_section_outline(width, [r, g, b, a].map(|c| c as f32 / 255.0))
+}
+
 /// Check if section plane is active
 #[frb(sync)]
 pub fn is_section_plane_active() -> bool {
@@ -1156,24 +3207,195 @@ pub fn is_section_plane_active() -> bool {
     plane.as_ref().map(|p| p.enabled).unwrap_or(false)
 }
 
-/// Set section plane from axis (X=0, Y=1, Z=2) and position
+/// Set section plane from axis (X=0, Y=1, Z=2) and position
+#[frb(sync)]
+pub fn set_section_plane_from_axis(axis: i32, position: f32) -> Result<(), String> {
+    let axis = Axis::from_index(axis).ok_or_else(|| format!("Invalid axis: {}", axis))?;
+    set_section_plane_axis(axis, position)
+}
+
+/// Set an axis-aligned section plane at the given position along the axis
+/// (e.g. Axis::Y at a storey height for a plan cut)
+#[frb(sync)]
+pub fn set_section_plane_axis(axis: Axis, position: f32) -> Result<(), String> {
+    let (origin, normal) = section_plane_axis(axis, position);
+    set_section_plane(origin[0], origin[1], origin[2], normal[0], normal[1], normal[2])
+}
+
+/// Set the section plane on the surface under a screen position (0-1 range)
+/// The plane is aligned to the picked face; returns false if nothing was hit
+#[frb(sync)]
+pub fn set_section_plane_from_pick(screen_x: f32, screen_y: f32) -> Result<bool, String> {
+    let plane = {
+        let registry = MODEL_REGISTRY.lock().unwrap();
+        if registry.is_empty() {
+            return Err("No model loaded".to_string());
+        }
+
+        let renderer = RENDERER.lock().unwrap();
+        let r = renderer.as_ref().ok_or("Renderer not initialized")?;
+        let (ray_origin, ray_dir) = r.camera.screen_to_ray(screen_x, screen_y);
+
+        // Closest hit across all visible models
+        registry
+            .iter_visible()
+            .filter_map(|(_model_id, reg_model)| {
+                let pick_mesh = pick_mesh(reg_model);
+                let hit = pick_mesh.intersect(ray_origin, ray_dir)?;
+                let mesh = &pick_mesh.mesh;
+                section_plane_at_hit(ray_origin, ray_dir, &mesh.vertices, &mesh.indices, hit)
+            })
+            .min_by(|a, b| {
+                let distance = |(origin, _): &([f32; 3], [f32; 3])| Vec3::from_array(*origin).distance(ray_origin);
+                distance(a).total_cmp(&distance(b))
+            })
+    };
+
+    match plane {
+        Some((origin, normal)) => {
+            set_section_plane(origin[0], origin[1], origin[2], normal[0], normal[1], normal[2])?;
+            Ok(true)
+        }
+        None => Ok(false),
+    }
+}
+
+/// Animate the section plane sweeping along an axis, streaming each rendered
+/// frame (RGBA) for display or recording
+/// from/to default to the extent of the loaded models along the axis; frames
+/// are spread evenly over duration_ms. The plane stays at `to` afterwards
+/// (or where the sweep stopped, if the stream was closed early).
+pub async fn animate_section_sweep(
+    axis: Axis,
+    from: Option<f32>,
+    to: Option<f32>,
+    duration_ms: u32,
+    steps: u32,
+    sink: StreamSink<Vec<u8>>,
+) -> Result<(), String> {
+    let sweep = match (from, to) {
+        (Some(from), Some(to)) => SectionSweep::new(axis, from, to, steps),
+        _ => {
+            let registry = MODEL_REGISTRY.lock().unwrap();
+            let bounds = registry.get_combined_bounds().ok_or("No model bounds available")?;
+            SectionSweep::across_bounds(axis, bounds.min, bounds.max, from, to, steps)
+        }
+    };
+    let frame_interval = tokio::time::Duration::from_millis((duration_ms / sweep.steps) as u64);
+
+    for step in 0..sweep.steps {
+        // Lock per frame so other calls can run between frames. The section
+        // state follows the renderer's plane at every step, so the two agree
+        // however the sweep ends (a failed frame, the listener leaving)
+        let frame = {
+            let mut section_plane = SECTION_PLANE.lock().unwrap();
+            let mut renderer = RENDERER.lock().unwrap();
+            let r = renderer.as_mut().ok_or("Renderer not initialized")?;
+            let (origin, normal) = sweep.plane(step);
+            r.set_section_plane(Some((origin, normal)))?;
+            *section_plane = Some(SectionPlane {
+                origin,
+                normal,
+                enabled: true,
+            });
+            r.render_frame()?
+        };
+        if sink.add(frame).is_err() {
+            break; // Listener went away
+        }
+        if step + 1 < sweep.steps {
+            tokio::time::sleep(frame_interval).await;
+        }
+    }
+
+    Ok(())
+}
+
+// ============================================================================
+// Phase 7: Clip Box
+// ============================================================================
+
+/// Axis-aligned clip box; everything outside it is hidden
+/// Composes with the section plane (a fragment must pass both)
+#[derive(Debug, Clone)]
+pub struct ClipBox {
+    pub min: [f32; 3],
+    pub max: [f32; 3],
+    pub enabled: bool,
+}
+
+/// Global clip box state
+static CLIP_BOX: Mutex<Option<ClipBox>> = Mutex::new(None);
+
+/// Clip everything outside the box between two corners
+/// Corners may be given in any order
+#[frb(sync)]
+pub fn set_clip_box(
+    min_x: f32,
+    min_y: f32,
+    min_z: f32,
+    max_x: f32,
+    max_y: f32,
+    max_z: f32,
+) -> Result<(), String> {
+    let a = [min_x, min_y, min_z];
+    let b = [max_x, max_y, max_z];
+    if a.iter().chain(&b).any(|c| !c.is_finite()) {
+        return Err("Clip box corners must be finite".to_string());
+    }
+    let min = [a[0].min(b[0]), a[1].min(b[1]), a[2].min(b[2])];
+    let max = [a[0].max(b[0]), a[1].max(b[1]), a[2].max(b[2])];
+
+    *CLIP_BOX.lock().unwrap() = Some(ClipBox {
+        min,
+        max,
+        enabled: true,
+    });
+
+    // Update renderer if initialized
+    let mut renderer = RENDERER.lock().unwrap();
+    if let Some(r) = renderer.as_mut() {
+        r.set_clip_box(Some((min, max)))?;
+    }
+
+    Ok(())
+}
+
+/// Remove the clip box (section planes are unaffected)
+#[frb(sync)]
+pub fn clear_clip_box() -> Result<(), String> {
+    *CLIP_BOX.lock().unwrap() = None;
+
+    let mut renderer = RENDERER.lock().unwrap();
+    if let Some(r) = renderer.as_mut() {
+        r.set_clip_box(None)?;
+    }
+
+    Ok(())
+}
+
+/// Get the current clip box, if any
+#[frb(sync)]
+pub fn get_clip_box() -> Option<ClipBox> {
+    CLIP_BOX.lock().unwrap().clone()
+}
+
+/// Section planes and clip box, for drawing manipulators over the view
+#[derive(Debug, Clone)]
+pub struct SectionState {
+    pub planes: Vec<SectionPlane>,
+    pub clip_box: Option<ClipBox>,
+}
+
+/// Get the current section planes and clip box
+/// Changes made by dragging a gizmo go back through `set_section_plane`,
+/// `set_section_plane_enabled` and `set_clip_box`.
 #[frb(sync)]
-pub fn set_section_plane_from_axis(axis: i32, position: f32) -> Result<(), String> {
-    let (normal_x, normal_y, normal_z) = match axis {
-        0 => (1.0, 0.0, 0.0), // X axis
-        1 => (0.0, 1.0, 0.0), // Y axis
-        2 => (0.0, 0.0, 1.0), // Z axis
-        _ => return Err(format!("Invalid axis: {}", axis)),
-    };
-
-    let (origin_x, origin_y, origin_z) = match axis {
-        0 => (position, 0.0, 0.0),
-        1 => (0.0, position, 0.0),
-        2 => (0.0, 0.0, position),
-        _ => unreachable!(),
-    };
-
-    set_section_plane(origin_x, origin_y, origin_z, normal_x, normal_y, normal_z)
+pub fn get_section_state() -> SectionState {
+    SectionState {
+        planes: SECTION_PLANE.lock().unwrap().iter().cloned().collect(),
+        clip_box: CLIP_BOX.lock().unwrap().clone(),
+    }
 }
 
 // ============================================================================
@@ -1193,45 +3415,103 @@ pub fn set_element_color(element_id: i32, r: u8, g: u8, b: u8) -> Result<(), Str
     )
 }
 
-/// Reset all element colors to defaults
+/// Reset all element colors to defaults, including those set by handle
 #[frb(sync)]
 pub fn reset_element_colors() -> Result<(), String> {
-    let mut renderer = RENDERER.lock().unwrap();
-    let r = renderer.as_mut().ok_or("Renderer not initialized")?;
-    r.reset_element_colors()
+    {
+        let mut renderer = RENDERER.lock().unwrap();
+        let r = renderer.as_mut().ok_or("Renderer not initialized")?;
+        r.reset_element_colors()?;
+    }
+    if std::mem::take(&mut *ELEMENT_COLORS.lock().unwrap()).is_empty() {
+        return Ok(());
+    }
+    reload_for_overrides()
 }
 
-/// Color elements by type
-/// Automatically assigns different colors to different element types
+/// Color elements by type, returning the legend (see get_current_legend)
+/// Elements without a material style take their color from the type palette
+/// (see set_type_color); this redraws all models with the current palette.
 #[frb(sync)]
-pub fn color_by_type() -> Result<(), String> {
-    let model = MODEL_REGISTRY.lock().unwrap();
-    if model.is_empty() {
-        return Err("No model loaded".to_string());
+pub fn color_by_type() -> Result<Legend, String> {
+    set_color_by_id(false)?;
+    Ok(get_current_legend())
+}
+
+/// Color each element without a material style by a stable hash of its
+/// GlobalId, so adjacent elements of the same type stand apart
+/// The colors are the same in every session; color_by_type switches back.
+/// The legend returned is empty: the colors stand for no categories.
+#[frb(sync)]
+pub fn color_by_element_id() -> Result<Legend, String> {
+    set_color_by_id(true)?;
+    Ok(get_current_legend())
+}
+
+/// Get the legend of the coloring shown: change statuses while a diff is
+/// displayed, none for id colors, otherwise the types of the visible
+/// models with their palette colors
+#[frb(sync)]
+pub fn get_current_legend() -> Legend {
+    if let Some(diff) = DIFF_VIEW.lock().unwrap().as_ref() {
+        return Legend::of_diff(diff);
     }
+    let registry = MODEL_REGISTRY.lock().unwrap();
+    let mut visible: Vec<_> = registry.iter_visible().collect();
+    if visible.iter().any(|(_, m)| m.model.color_by_id) {
+        return Legend::default();
+    }
+    visible.sort_by_key(|(id, _)| *id);
+    Legend::of_types(visible.into_iter().map(|(_, m)| &m.model))
+}
 
-    // Predefined color palette for different types (for future implementation)
-    let _type_colors: std::collections::HashMap<&str, [u8; 3]> = [
-        ("IfcWall", [200, 200, 200]),          // Light gray
-        ("IfcSlab", [150, 150, 150]),          // Medium gray
-        ("IfcBeam", [139, 69, 19]),            // Brown
-        ("IfcColumn", [160, 82, 45]),          // Sienna
-        ("IfcDoor", [210, 180, 140]),          // Tan
-        ("IfcWindow", [173, 216, 230]),        // Light blue
-        ("IfcStair", [188, 143, 143]),         // Rosy brown
-        ("IfcRoof", [178, 34, 34]),            // Firebrick
-        ("IfcSpace", [240, 255, 240]),         // Honeydew
-        ("IfcBuildingElementProxy", [192, 192, 192]), // Silver
-    ].iter().cloned().collect();
+/// Switch all loaded models between type and id colors and redraw
+fn set_color_by_id(enabled: bool) -> Result<(), String> {
+    {
+        let mut registry = MODEL_REGISTRY.lock().unwrap();
+        if registry.is_empty() {
+            return Err("No model loaded".to_string());
+        }
+        for (_, reg_model) in registry.iter_mut() {
+            reg_model.model.color_by_id = enabled;
+        }
+    }
+    if RENDERER.lock().unwrap().as_ref().is_some_and(|r| r.gpu.is_initialized()) {
+        reload_all_models_mesh()?;
+    }
+    Ok(())
+}
 
-    let mut renderer = RENDERER.lock().unwrap();
-    let _r = renderer.as_mut().ok_or("Renderer not initialized")?;
+/// Override the palette color of an IFC type (e.g. "IfcWall") in all loaded
+/// models; applies to elements without a material style
+#[frb(sync)]
+pub fn set_type_color(ifc_type: String, r: u8, g: u8, b: u8, a: u8) -> Result<(), String> {
+    let color = [r, g, b, a].map(|c| c as f32 / 255.0);
+    update_palettes(|palette| palette.set_color(&ifc_type, color))
+}
 
-    // TODO: Implement per-element coloring by iterating over all element types
-    // For now, just log that the operation would be applied
-    let total_elements: usize = model.models().values().map(|reg| reg.model.element_count).sum();
+/// Restore the built-in type palette in all loaded models
+#[frb(sync)]
+pub fn reset_type_colors() -> Result<(), String> {
+    update_palettes(|palette| *palette = default_palette())
+}
 
-    tracing::info!("Color-by-type requested for {} total elements (stub implementation)", total_elements);
+/// Apply a palette change to every loaded model and redraw
+fn update_palettes(update: impl Fn(&mut MaterialPalette)) -> Result<(), String> {
+    {
+        let mut registry = MODEL_REGISTRY.lock().unwrap();
+        if registry.is_empty() {
+            return Err("No model loaded".to_string());
+        }
+        for (_, reg_model) in registry.iter_mut() {
+            let mut palette = reg_model.model.palette.clone();
+            update(&mut palette);
+            reg_model.model.set_palette(palette);
+        }
+    }
+    if RENDERER.lock().unwrap().as_ref().is_some_and(|r| r.gpu.is_initialized()) {
+        reload_all_models_mesh()?;
+    }
     Ok(())
 }
 
@@ -1244,17 +3524,11 @@ pub async fn export_screenshot(path: String) -> Result<(), String> {
     let renderer = RENDERER.lock().unwrap();
     let r = renderer.as_ref().ok_or("Renderer not initialized")?;
 
-    // Render current frame and get image data
-    let image_data = r.render_frame()?;
-
-    // Save to file
-    match image::save_buffer(
-        &path,
-        &image_data,
-        r.scene.as_ref().ok_or("Scene not initialized")?.width,
-        r.scene.as_ref().ok_or("Scene not initialized")?.height,
-        image::ColorType::Rgba8,
-    ) {
+    // Render current frame and save it straight from the frame buffer
+    let (width, height) = r.get_dimensions().ok_or("Scene not initialized")?;
+    match r.with_frame(|image_data| {
+        image::save_buffer(&path, image_data, width, height, image::ColorType::Rgba8)
+    })? {
         Ok(_) => {
             tracing::info!("Screenshot saved to: {}", path);
             Ok(())
@@ -1263,6 +3537,197 @@ pub async fn export_screenshot(path: String) -> Result<(), String> {
     }
 }
 
+/// Export the elements currently on screen as glTF (`.glb` for a single
+/// binary file, otherwise `.gltf` plus a `.bin` buffer)
+/// Includes elements of visible models and types whose bounds are inside
+/// the camera frustum and not entirely cut away by the section plane or
+/// clip box; geometry is exported whole, as exploded on screen.
+/// Returns the number of exported elements.
+pub async fn export_visible_gltf(path: String) -> Result<u32, String> {
+    let export = {
+        let registry = MODEL_REGISTRY.lock().unwrap();
+        if registry.is_empty() {
+            return Err("No model loaded".to_string());
+        }
+        let renderer = RENDERER.lock().unwrap();
+        let r = renderer.as_ref().ok_or("Renderer not initialized")?;
+        let visibility = VISIBILITY.lock().unwrap();
+        let precision = *EXPORT_PRECISION.lock().unwrap();
+        visible_export(&registry, &visibility, &r.camera, r.section_plane(), r.clip_box(), precision)
+    };
+    export.write(std::path::Path::new(&path))?;
+    tracing::info!("Exported {} elements to: {}", export.element_count(), path);
+    Ok(export.element_count() as u32)
+}
+
+/// Precision of exported coordinates (full precision, file coordinates by
+/// default)
+static EXPORT_PRECISION: Mutex<ExportPrecision> = Mutex::new(ExportPrecision { decimals: None, relative_to_origin: false });
+
+/// Set the precision of coordinates in glTF exports
+/// decimals: places (of meters) positions are rounded to, e.g. 3 for
+/// millimeters, at most 9; None keeps full precision. With
+/// relative_to_origin, positions are written relative to each model's
+/// local origin (recorded in the node extras) so survey coordinates stay
+/// out of the file.
+#[frb(sync)]
+pub fn set_export_precision(precision: ExportPrecision) -> Result<(), String> {
+    precision.validate()?;
+    *EXPORT_PRECISION.lock().unwrap() = precision;
+    Ok(())
+}
+
+/// Get the precision of coordinates in glTF exports
+#[frb(sync)]
+pub fn get_export_precision() -> ExportPrecision {
+    *EXPORT_PRECISION.lock().unwrap()
+}
+
+/// Export elements by GlobalId as an IFC file, with the spatial structure,
+/// placements, geometry and properties they depend on
+/// The elements must all come from the same loaded IFC file, which is read
+/// again (see get_entity_attributes); entity ids are kept. Returns the number
+/// of entities written.
+pub async fn export_ifc_subset(global_ids: Vec<String>, path: String) -> Result<u32, String> {
+    if global_ids.is_empty() {
+        return Err("No elements to export".to_string());
+    }
+    let source = {
+        let registry = MODEL_REGISTRY.lock().unwrap();
+        let mut sources: Vec<Option<String>> = Vec::new();
+        for global_id in &global_ids {
+            let reg_model = registry
+                .iter()
+                .map(|(_, m)| m)
+                .find(|m| m.element_bounds.contains_key(global_id))
+                .ok_or_else(|| format!("Element not found: {}", global_id))?;
+            if !sources.contains(&reg_model.file_path) {
+                sources.push(reg_model.file_path.clone());
+            }
+        }
+        match sources.as_slice() {
+            [Some(source)] => source.clone(),
+            [None] => return Err("Model has no IFC source file".to_string()),
+            _ => return Err("Elements come from more than one file".to_string()),
+        }
+    };
+
+    let ifc_file = source_ifc_file(source).await?;
+    let file_name = std::path::Path::new(&path)
+        .file_name()
+        .and_then(|s| s.to_str())
+        .unwrap_or("subset.ifc")
+        .to_string();
+    let (text, count) = run_blocking(move || {
+        let mut subset = crate::bim::extract_subset(&ifc_file, &global_ids)?;
+        subset.header.file_name = file_name;
+        Ok((subset.to_step_string(), subset.entity_count()))
+    })
+    .await?;
+    tokio::fs::write(&path, text)
+        .await
+        .map_err(|e| format!("Failed to write file: {}", e))?;
+    tracing::info!("Exported {} entities to: {}", count, path);
+    Ok(count as u32)
+}
+
+/// Export every loaded element and its properties to a CSV file
+///
+/// One row per element (GlobalId, type, name, storey) plus a column per
+/// property name found, `<set name>.<property name>`; with a `pset_filter`,
+/// only properties of the set of that name. Returns the number of rows.
+pub async fn export_properties_csv(path: String, pset_filter: Option<String>) -> Result<u32, String> {
+    let (csv, rows) = {
+        let registry = MODEL_REGISTRY.lock().unwrap();
+        if registry.is_empty() {
+            return Err("No model loaded".to_string());
+        }
+        // In a stable order (the registry is unordered)
+        let mut registered: Vec<_> = registry.iter().collect();
+        registered.sort_by_key(|(id, _)| *id);
+        let models: Vec<&BimModel> = registered.into_iter().map(|(_, m)| &m.model).collect();
+        crate::bim::properties_csv(&models, pset_filter.as_deref())
+    };
+    std::fs::write(&path, csv).map_err(|e| format!("Failed to write CSV: {}", e))?;
+    tracing::info!("Exported properties of {} elements to: {}", rows, path);
+    Ok(rows as u32)
+}
+
+/// Elements of the visible models that are on screen, ready for export
+fn visible_export(
+    registry: &ModelRegistry,
+    hidden_types: &std::collections::HashSet<String>,
+    camera: &Camera,
+    section_plane: Option<([f32; 3], [f32; 3])>,
+    clip_box: Option<([f32; 3], [f32; 3])>,
+    precision: ExportPrecision,
+) -> GltfExport {
+    let frustum = camera.frustum();
+    let mut export = GltfExport::new();
+    export.set_decimals(precision.decimals);
+    for (_model_id, reg_model) in registry.iter_visible() {
+        if precision.relative_to_origin {
+            export.set_origin(reg_model.model.origin.y_up_offset(reg_model.model.up_axis));
+        }
+        let mut mesh = reg_model.model.generate_meshes_filtered(hidden_types, None);
+        apply_layer_visibility(&reg_model.model, &mut mesh);
+        apply_element_overrides(&mut mesh);
+        apply_explode(&reg_model.model, &mut mesh);
+        apply_diff_view(&mut mesh);
+        for element in &mesh.elements {
+            let (min, max) = (Vec3::from_array(element.bounds.min), Vec3::from_array(element.bounds.max));
+            if frustum.intersects_aabb(min, max) && !box_fully_clipped(min, max, section_plane, clip_box) {
+                export.add_element(element, &mesh.element_mesh(element));
+            }
+        }
+    }
+    export
+}
+
+/// Render a square PNG thumbnail of the primary model
+/// Works headlessly: uses the renderer's GPU when initialized, otherwise a
+/// temporary offscreen GPU context. The live view is not affected.
+pub async fn generate_thumbnail(size: u32) -> Result<Vec<u8>, String> {
+    let mesh = {
+        let registry = MODEL_REGISTRY.lock().unwrap();
+        let reg_model = registry.get_primary_model().ok_or("No model loaded")?;
+        reg_model.model.generate_meshes()
+    };
+    let bounds = mesh.bounds.map(|b| (b.min, b.max));
+    let render = |r: &Renderer| {
+        r.render_thumbnail(size, &mesh.vertices, &mesh.normals, &mesh.colors, &mesh.indices, bounds)
+    };
+
+    {
+        let renderer = RENDERER.lock().unwrap();
+        if let Some(r) = renderer.as_ref().filter(|r| r.gpu.is_initialized()) {
+            return render(r);
+        }
+    }
+
+    let mut headless = Renderer::new();
+    headless.initialize().await?;
+    render(&headless)
+}
+
+/// Save the current viewpoint (camera and lighting) as JSON
+#[frb(sync)]
+pub fn save_viewpoint() -> Result<String, String> {
+    let renderer = RENDERER.lock().unwrap();
+    let r = renderer.as_ref().ok_or("Renderer not initialized")?;
+    serde_json::to_string(&r.viewpoint()?).map_err(|e| format!("Failed to save viewpoint: {}", e))
+}
+
+/// Restore a viewpoint saved with save_viewpoint
+#[frb(sync)]
+pub fn restore_viewpoint(json: String) -> Result<(), String> {
+    let viewpoint: Viewpoint =
+        serde_json::from_str(&json).map_err(|e| format!("Invalid viewpoint: {}", e))?;
+    let mut renderer = RENDERER.lock().unwrap();
+    let r = renderer.as_mut().ok_or("Renderer not initialized")?;
+    r.set_viewpoint(&viewpoint)
+}
+
 /// Get current frame as RGBA bytes
 /// Returns width, height, and pixel data
 #[frb(sync)]
@@ -1299,6 +3764,57 @@ pub fn get_render_stats() -> Result<RenderStats, String> {
     })
 }
 
+/// Triangles, vertices and elements per element type across the loaded
+/// models (with geometry), most triangles first, for deciding what to
+/// simplify on constrained devices
+#[frb(sync)]
+pub fn get_geometry_budget() -> Result<Vec<TypeBudget>, String> {
+    let registry = MODEL_REGISTRY.lock().unwrap();
+    if registry.is_empty() {
+        return Err("No model loaded".to_string());
+    }
+    let pick_meshes: Vec<Arc<PickMesh>> = registry
+        .iter()
+        .filter(|(_, reg_model)| reg_model.geometry_loaded)
+        .map(|(_, reg_model)| pick_mesh(reg_model))
+        .collect();
+    Ok(crate::bim::geometry_budget(pick_meshes.iter().map(|p| &p.mesh)))
+}
+
+/// Estimated memory cost of the loaded models and the renderer, in bytes
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MemoryStats {
+    /// Element data: products, properties, storeys and materials
+    pub entity_bytes: u64,
+    /// Extracted geometry and the meshes cached for picking
+    pub geometry_bytes: u64,
+    /// Buffers and render targets uploaded to the GPU
+    pub gpu_buffer_bytes: u64,
+    pub total: u64,
+}
+
+/// Get the estimated memory cost of the loaded models and the renderer
+///
+/// Helps decide when to unload models in a federated session.
+#[frb(sync)]
+pub fn get_memory_stats() -> MemoryStats {
+    let registry = MODEL_REGISTRY.lock().unwrap();
+    let renderer = RENDERER.lock().unwrap();
+    memory_stats(&registry, renderer.as_ref())
+}
+
+fn memory_stats(registry: &ModelRegistry, renderer: Option<&Renderer>) -> MemoryStats {
+    let entity_bytes: usize = registry.iter().map(|(_, m)| m.model.entity_bytes()).sum();
+    let geometry_bytes: usize = registry.iter().map(|(_, m)| m.geometry_bytes()).sum();
+    let gpu_buffer_bytes = renderer.map_or(0, Renderer::gpu_buffer_bytes);
+    MemoryStats {
+        entity_bytes: entity_bytes as u64,
+        geometry_bytes: geometry_bytes as u64,
+        gpu_buffer_bytes,
+        total: (entity_bytes + geometry_bytes) as u64 + gpu_buffer_bytes,
+    }
+}
+
 // ============================================================================
 // Phase 6/7: 2D Drawing Overlay
 // ============================================================================
@@ -1325,31 +3841,67 @@ pub async fn upload_drawing_overlay(
     id: String,
     width: u32,
     height: u32,
-    _rgba_pixels: Vec<u8>,
+    rgba_pixels: Vec<u8>,
 ) -> Result<(), String> {
     tracing::info!("Uploading drawing overlay: {} ({}x{})", id, width, height);
 
-    // TODO: Store overlay in renderer
-    // This would require extending the Renderer struct to manage overlays
-    // For now, return success to generate the API binding
+    let mut renderer = RENDERER.lock().unwrap();
+    let r = renderer.as_mut().ok_or("Renderer not initialized")?;
+    r.add_overlay(&id, width, height, &rgba_pixels)
+}
+
+/// Add an overlay from an image file (PNG or JPEG)
+/// Images larger than the GPU's maximum texture size are downscaled
+pub async fn add_overlay_from_file(id: String, path: String) -> Result<(), String> {
+    tracing::info!("Loading drawing overlay {} from: {}", id, path);
 
-    Ok(())
+    let bytes = tokio::fs::read(&path)
+        .await
+        .map_err(|e| format!("Failed to read {}: {}", path, e))?;
+    add_overlay_from_bytes(id, bytes).await
+}
+
+/// Add an overlay from encoded image bytes (PNG or JPEG)
+/// Images larger than the GPU's maximum texture size are downscaled
+pub async fn add_overlay_from_bytes(id: String, data: Vec<u8>) -> Result<(), String> {
+    let mut renderer = RENDERER.lock().unwrap();
+    let r = renderer.as_mut().ok_or("Renderer not initialized")?;
+    r.add_overlay_from_bytes(&id, &data)
 }
 
 /// Set overlay transform (position, scale, rotation)
 #[frb(sync)]
 pub fn set_overlay_transform(
     id: String,
-    _position_x: f32,
-    _position_y: f32,
-    _position_z: f32,
-    _scale_x: f32,
-    _scale_y: f32,
-    _rotation: f32,
+    position_x: f32,
+    position_y: f32,
+    position_z: f32,
+    scale_x: f32,
+    scale_y: f32,
+    rotation: f32,
 ) -> Result<(), String> {
     tracing::info!("Set overlay transform: {}", id);
-    // TODO: Update overlay transform
-    Ok(())
+    let mut renderer = RENDERER.lock().unwrap();
+    let r = renderer.as_mut().ok_or("Renderer not initialized")?;
+    r.update_overlay(&id, |drawing| {
+        drawing.position = [position_x, position_y, position_z];
+        drawing.scale = [scale_x, scale_y];
+        drawing.rotation = rotation;
+    })
+}
+
+/// Position, scale and rotate an overlay from two points picked on its
+/// image (pixels from the top-left) and the two model points they should
+/// land on, e.g. two grid intersections
+#[frb(sync)]
+pub fn align_overlay_to_points(
+    overlay_id: String,
+    image_points: [(f32, f32); 2],
+    world_points: [[f32; 3]; 2],
+) -> Result<(), String> {
+    let mut renderer = RENDERER.lock().unwrap();
+    let r = renderer.as_mut().ok_or("Renderer not initialized")?;
+    r.update_overlay(&overlay_id, |drawing| drawing.align_to_points(image_points, world_points))?
 }
 
 /// Set overlay opacity (0.0 to 1.0)
@@ -1357,23 +3909,37 @@ pub fn set_overlay_transform(
 pub fn set_overlay_opacity(id: String, opacity: f32) -> Result<(), String> {
     let opacity = opacity.clamp(0.0, 1.0);
     tracing::info!("Set overlay opacity: {} = {}", id, opacity);
-    // TODO: Update overlay opacity
-    Ok(())
+    let mut renderer = RENDERER.lock().unwrap();
+    let r = renderer.as_mut().ok_or("Renderer not initialized")?;
+    r.update_overlay(&id, |drawing| drawing.opacity = opacity)
 }
 
 /// Set overlay visibility
 #[frb(sync)]
 pub fn set_overlay_visible(id: String, visible: bool) -> Result<(), String> {
     tracing::info!("Set overlay visible: {} = {}", id, visible);
-    // TODO: Update overlay visibility
-    Ok(())
+    let mut renderer = RENDERER.lock().unwrap();
+    let r = renderer.as_mut().ok_or("Renderer not initialized")?;
+    r.update_overlay(&id, |drawing| drawing.visible = visible)
+}
+
+/// Set how an overlay texture is sampled (filtering, mipmaps, anisotropy, tiling)
+/// Returns the settings actually applied - anisotropy is reduced to 1 when
+/// the device does not support it or filtering is nearest
+#[frb(sync)]
+pub fn set_overlay_sampling(id: String, settings: OverlaySampling) -> Result<OverlaySampling, String> {
+    let mut renderer = RENDERER.lock().unwrap();
+    let r = renderer.as_mut().ok_or("Renderer not initialized")?;
+    r.set_overlay_sampling(&id, settings)
 }
 
 /// Remove an overlay
 #[frb(sync)]
 pub fn remove_overlay(id: String) -> Result<(), String> {
     tracing::info!("Remove overlay: {}", id);
-    // TODO: Remove overlay from renderer
+    let mut renderer = RENDERER.lock().unwrap();
+    let r = renderer.as_mut().ok_or("Renderer not initialized")?;
+    r.remove_overlay(&id)?;
     Ok(())
 }
 
@@ -1408,6 +3974,706 @@ pub fn get_view_mode() -> String {
     }
 }
 
+// ============================================================================
+// Point Cloud Overlays
+// ============================================================================
+
+/// Load a point cloud (XYZ text or PLY) drawn with the model, returning its
+/// overlay ID (the file name, numbered if already taken)
+/// Points are taken as render coordinates (Y up, meters); align them with
+/// `set_point_cloud_transform`.
+pub async fn load_point_cloud(path: String) -> Result<String, String> {
+    tracing::info!("Loading point cloud from: {}", path);
+    let file_path = path.clone();
+    let cloud = run_blocking(move || PointCloud::load(std::path::Path::new(&file_path))).await?;
+    let name = std::path::Path::new(&path)
+        .file_stem()
+        .and_then(|s| s.to_str())
+        .unwrap_or("points")
+        .to_string();
+
+    let mut renderer = RENDERER.lock().unwrap();
+    let r = renderer.as_mut().ok_or("Renderer not initialized")?;
+    let id = (1..)
+        .map(|n| if n == 1 { name.clone() } else { format!("{}-{}", name, n) })
+        .find(|id| r.point_cloud(id).is_none())
+        .unwrap();
+    tracing::info!("Point cloud '{}': {} points", id, cloud.point_count());
+    r.add_point_cloud(&id, cloud)?;
+    Ok(id)
+}
+
+/// Align a point cloud with the model: a column-major 4x4 matrix (16
+/// values), as model transforms in the registry
+#[frb(sync)]
+pub fn set_point_cloud_transform(id: String, transform: Vec<f32>) -> Result<(), String> {
+    let transform: [f32; 16] = transform
+        .try_into()
+        .map_err(|t: Vec<f32>| format!("Transform needs 16 values, got {}", t.len()))?;
+    let mut renderer = RENDERER.lock().unwrap();
+    let r = renderer.as_mut().ok_or("Renderer not initialized")?;
+    r.set_point_cloud_transform(&id, transform)
+}
+
+/// Show or hide a point cloud
+#[frb(sync)]
+pub fn set_point_cloud_visible(id: String, visible: bool) -> Result<(), String> {
+    let mut renderer = RENDERER.lock().unwrap();
+    let r = renderer.as_mut().ok_or("Renderer not initialized")?;
+    r.set_point_cloud_visible(&id, visible)
+}
+
+/// Remove a point cloud
+#[frb(sync)]
+pub fn remove_point_cloud(id: String) -> Result<(), String> {
+    let mut renderer = RENDERER.lock().unwrap();
+    let r = renderer.as_mut().ok_or("Renderer not initialized")?;
+    r.remove_point_cloud(&id)
+}
+
+/// Get the number of points in a point cloud
+#[frb(sync)]
+pub fn get_point_cloud_point_count(id: String) -> Result<usize, String> {
+    let renderer = RENDERER.lock().unwrap();
+    let r = renderer.as_ref().ok_or("Renderer not initialized")?;
+    let cloud = r.point_cloud(&id).ok_or_else(|| format!("Point cloud '{}' not found", id))?;
+    Ok(cloud.point_count())
+}
+
+/// Set the size of point cloud points in pixels (at most 32)
+#[frb(sync)]
+pub fn set_point_size(px: f32) -> Result<(), String> {
+    let mut renderer = RENDERER.lock().unwrap();
+    let r = renderer.as_mut().ok_or("Renderer not initialized")?;
+    r.set_point_size(px)
+}
+
 // ============================================================================
 // Future Phases
 // ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Held by tests that load models into the global registry
+    static REGISTRY_TEST_LOCK: Mutex<()> = Mutex::new(());
+
+    #[test]
+    fn test_parse_ifc_stats_sync_from_two_threads() {
+        let architectural = include_str!("../../test/sample_architectural.ifc");
+        let building = include_str!("../../test/sample_building.ifc");
+
+        let handles: Vec<_> = [architectural, building]
+            .into_iter()
+            .map(|content| std::thread::spawn(move || parse_ifc_stats_sync(content.to_string())))
+            .collect();
+        let results: Vec<ModelInfo> = handles
+            .into_iter()
+            .map(|h| h.join().unwrap().unwrap())
+            .collect();
+
+        assert_eq!(results[0].stats.walls, 6);
+        assert_eq!(results[1].stats.walls, 3);
+
+        assert!(parse_ifc_stats_sync("not an ifc file".to_string()).is_err());
+    }
+
+    #[test]
+    fn test_read_ifc_header_skips_data_section() {
+        // The DATA section is not valid STEP, so parsing any entity would fail
+        let path = std::env::temp_dir().join(format!("header_only_{}.ifc", std::process::id()));
+        let header = "ISO-10303-21;
+HEADER;
+FILE_DESCRIPTION(('ViewDefinition [CoordinationView]'),'2;1');
+FILE_NAME('tower.ifc','2024-05-06T07:08:09',('Jane Doe'),(''),'IfcOpenShell','ArchiCAD 27',$);
+FILE_SCHEMA(('IFC2X3'));
+ENDSEC;
+DATA;
+";
+        std::fs::write(&path, format!("{}{}", header, "#1=NOT(VALID;\n".repeat(10_000))).unwrap();
+        assert!(IfcFile::parse(&std::fs::read_to_string(&path).unwrap()).is_err());
+
+        let runtime = tokio::runtime::Builder::new_current_thread().build().unwrap();
+        let info = runtime.block_on(read_ifc_header(path.to_string_lossy().into_owned()));
+        std::fs::remove_file(&path).unwrap();
+        let info = info.unwrap();
+        assert_eq!(info.schema, "IFC2X3");
+        assert_eq!(info.file_name, "tower.ifc");
+        assert_eq!(info.time_stamp, "2024-05-06T07:08:09");
+        assert_eq!(info.author, ["Jane Doe"]);
+        assert!(info.organization.is_empty());
+        assert_eq!(info.originating_system, "ArchiCAD 27");
+
+        assert!(runtime.block_on(read_ifc_header("/nonexistent/model.ifc".into())).is_err());
+    }
+
+    #[test]
+    fn test_runtime_makes_progress_during_large_parse() {
+        let walls: String = (0..20_000)
+            .map(|i| format!("#{}=IFCWALL('wall-{}',$,'Wall {}',$,$,$,$,$,$);\n", i + 1, i, i))
+            .collect();
+        let content = format!(
+            "ISO-10303-21;\nHEADER;\nFILE_SCHEMA(('IFC4'));\nENDSEC;\nDATA;\n{}ENDSEC;\nEND-ISO-10303-21;",
+            walls
+        );
+
+        // A single-threaded runtime: a parse on it would stop the ticker
+        let runtime = tokio::runtime::Builder::new_current_thread().build().unwrap();
+        let (model, ticks) = runtime.block_on(async {
+            let ticks = Arc::new(std::sync::atomic::AtomicUsize::new(0));
+            let counter = ticks.clone();
+            let ticker = tokio::spawn(async move {
+                loop {
+                    counter.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+                    tokio::task::yield_now().await;
+                }
+            });
+            let model = run_blocking(move || parse_and_build_model(&content, &LoadOptions::default(), |_| {})).await;
+            let ticks = ticks.load(std::sync::atomic::Ordering::Relaxed);
+            ticker.abort();
+            (model, ticks)
+        });
+        assert_eq!(model.unwrap().walls.len(), 20_000);
+        assert!(ticks > 1, "ticker ran {} times during the parse", ticks);
+    }
+
+    #[test]
+    fn test_load_reports_parsing_then_tessellating() {
+        let content = include_str!("../../test/sample_architectural.ifc");
+        let mut stages = Vec::new();
+        let model = parse_and_build_model(content, &LoadOptions::default(), |stage| stages.push(stage)).unwrap();
+        assert_eq!(model.walls.len(), 6);
+
+        let split = stages.iter().position(|s| s.stage == LoadPhase::Tessellating).unwrap();
+        let (parsing, tessellating) = stages.split_at(split);
+        assert!(parsing.iter().all(|s| s.stage == LoadPhase::Parsing));
+        assert!(tessellating.iter().all(|s| s.stage == LoadPhase::Tessellating));
+
+        for phase in [parsing, tessellating] {
+            assert!(phase.windows(2).all(|w| w[0].fraction <= w[1].fraction));
+            assert_eq!(phase.last().unwrap().fraction, 1.0);
+            assert_eq!(phase.iter().filter(|s| s.fraction == 1.0).count(), 1);
+        }
+        assert!(tessellating.len() > 1);
+    }
+
+    #[test]
+    fn test_zoom_to_element_frames_its_bounds() {
+        let _registry = REGISTRY_TEST_LOCK.lock().unwrap();
+        let content = include_str!("../../test/sample_architectural.ifc");
+        let model = BimModel::from_ifc_file(&IfcFile::parse(content).unwrap()).unwrap();
+        let wall = model.walls[0].product.global_id.clone();
+        MODEL_REGISTRY.lock().unwrap().add_model(model, "Sample".into(), None);
+        *RENDERER.lock().unwrap() = Some(Renderer::new());
+        let viewport = (800.0, 600.0);
+        RENDERER.lock().unwrap().as_mut().unwrap().camera.set_aspect_ratio(viewport.0 / viewport.1);
+
+        let runtime = tokio::runtime::Builder::new_current_thread().enable_time().build().unwrap();
+        let bounds = get_element_bounds(wall.clone()).unwrap();
+        for duration in [0.0, 0.05] {
+            RENDERER.lock().unwrap().as_mut().unwrap().camera.set_state(CameraState {
+                position: [50.0, 40.0, 30.0],
+                target: [20.0, 0.0, 0.0],
+            });
+            runtime.block_on(zoom_to_element(wall.clone(), duration)).unwrap();
+
+            let renderer = RENDERER.lock().unwrap();
+            let camera = &renderer.as_ref().unwrap().camera;
+            for k in 0..3 {
+                assert!((camera.state().target[k] - bounds.center[k]).abs() < 1e-4);
+            }
+            // Every corner of the element is in view
+            for corner in 0..8 {
+                let pick = |axis: usize| if corner & (1 << axis) == 0 { bounds.min[axis] } else { bounds.max[axis] };
+                let point = Vec3::new(pick(0), pick(1), pick(2));
+                assert!(camera.project_point(point, viewport).is_some(), "{:?} not in view", point);
+            }
+        }
+        assert!(runtime.block_on(zoom_to_element("missing".into(), 0.0)).is_err());
+
+        MODEL_REGISTRY.lock().unwrap().clear();
+        *RENDERER.lock().unwrap() = None;
+    }
+
+    #[test]
+    fn test_georeferenced_measurement_keeps_millimeters() {
+        let _registry = REGISTRY_TEST_LOCK.lock().unwrap();
+        // A triangle 10.001 m wide, placed next to a site two million meters out
+        let content = r#"ISO-10303-21;
+HEADER;
+FILE_SCHEMA(('IFC4'));
+ENDSEC;
+DATA;
+#1=IFCCARTESIANPOINT((2000000.,5000000.,10.));
+#2=IFCAXIS2PLACEMENT3D(#1,$,$);
+#3=IFCLOCALPLACEMENT($,#2);
+#4=IFCSITE('site-guid',$,'Site',$,$,#3,$,$,.ELEMENT.,$,$,$,$,$);
+#5=IFCCARTESIANPOINT((0.123,1.5,0.));
+#6=IFCAXIS2PLACEMENT3D(#5,$,$);
+#7=IFCLOCALPLACEMENT(#3,#6);
+#20=IFCCARTESIANPOINTLIST3D(((0.,0.,0.),(10.001,0.,0.),(0.,1.,0.)));
+#21=IFCTRIANGULATEDFACESET(#20,$,$,((1,2,3)),$);
+#22=IFCSHAPEREPRESENTATION($,'Body','Tessellation',(#21));
+#23=IFCPRODUCTDEFINITIONSHAPE($,$,(#22));
+#30=IFCBUILDINGELEMENTPROXY('proxy-guid',$,'Proxy',$,$,#7,#23,$,$);
+ENDSEC;
+END-ISO-10303-21;
+"#;
+        let model = BimModel::from_ifc_file(&IfcFile::parse(content).unwrap()).unwrap();
+        let rendered = model.generate_meshes().vertices;
+        MODEL_REGISTRY.lock().unwrap().clear();
+        MODEL_REGISTRY.lock().unwrap().add_model(model, "Georeferenced".into(), None);
+
+        // Picked vertices go through the model's origin and up axis
+        start_measurement("area".into()).unwrap();
+        for vertex in rendered.chunks_exact(3) {
+            add_measurement_point(vertex[0], vertex[1], vertex[2]).unwrap();
+        }
+        let result = get_measurement_result().unwrap();
+        assert!((result.value - 10.001 * 1.0 / 2.0).abs() < 1e-3, "area {}", result.value);
+        for (point, vertex) in result.points.iter().zip(rendered.chunks_exact(3)) {
+            assert_eq!([point.x, point.y, point.z], [vertex[0], vertex[1], vertex[2]]);
+        }
+
+        start_measurement("distance".into()).unwrap();
+        add_measurement_point(rendered[0], rendered[1], rendered[2]).unwrap();
+        add_measurement_point_world(2_000_010.124, 5_000_001.5, 10.0).unwrap();
+        let result = get_measurement_result().unwrap();
+        assert!((result.value - 10.001).abs() < 1e-3, "distance {}", result.value);
+        let end = &result.points[1];
+        for (k, coordinate) in [end.x, end.y, end.z].into_iter().enumerate() {
+            assert!((coordinate - rendered[3 + k]).abs() < 1e-5, "{:?}", end);
+        }
+
+        MODEL_REGISTRY.lock().unwrap().clear();
+    }
+
+    #[test]
+    fn test_loading_offset_models_frames_their_combined_bounds() {
+        let Some(mut renderer) = crate::renderer::test_renderer(64, 64) else {
+            eprintln!("No GPU adapter available, skipping");
+            return;
+        };
+        // Two models side by side, far from the origin
+        let mut registry = ModelRegistry::new();
+        for (name, x) in [("left", 100.0), ("right", 104.0)] {
+            let mut mesh = crate::bim::generate_box(2.0, 2.0, 2.0);
+            for p in mesh.vertices.chunks_exact_mut(3) {
+                p[0] += x;
+                p[2] += 50.0;
+            }
+            let mut model = BimModel::new();
+            model.add_placed_body(name.to_string(), None, mesh);
+            registry.add_model(model, name.to_string(), None);
+        }
+        let start = renderer.camera.state();
+        assert_eq!(start.target, [0.0, 0.0, 0.0]);
+
+        upload_visible_models(&registry, &mut renderer).unwrap();
+        let target = renderer.camera.state().target;
+        for (k, center) in [102.0, 0.0, 50.0].into_iter().enumerate() {
+            assert!((target[k] - center).abs() < 1e-4, "{:?}", target);
+        }
+
+        // Off: the camera stays put
+        set_auto_frame_on_load(false);
+        renderer.camera.set_state(start);
+        let result = upload_visible_models(&registry, &mut renderer);
+        set_auto_frame_on_load(true);
+        result.unwrap();
+        assert_eq!(renderer.camera.state().target, start.target);
+    }
+
+    #[test]
+    fn test_home_view_survives_model_change_while_reset_reframes() {
+        let Some(mut renderer) = crate::renderer::test_renderer(64, 64) else {
+            eprintln!("No GPU adapter available, skipping");
+            return;
+        };
+        let load = |registry: &mut ModelRegistry, name: &str, size: f32, x: f32| {
+            let mut mesh = crate::bim::generate_box(size, size, size);
+            mesh.vertices.chunks_exact_mut(3).for_each(|p| p[0] += x);
+            let mut model = BimModel::new();
+            model.add_placed_body(name.to_string(), None, mesh);
+            registry.clear();
+            registry.add_model(model, name.to_string(), None);
+        };
+        assert!(renderer.go_home().is_err());
+
+        let mut registry = ModelRegistry::new();
+        load(&mut registry, "small", 1.0, 0.0);
+        fit_camera_to_visible_models(&registry, &mut renderer).unwrap();
+        renderer.camera.orbit(0.4, 0.2);
+        renderer.set_home_view();
+        let home = renderer.camera.state();
+
+        // A different model replaces it; home still goes back to the bookmark
+        load(&mut registry, "large", 20.0, 50.0);
+        fit_camera_to_visible_models(&registry, &mut renderer).unwrap();
+        let fitted = renderer.camera.state();
+        assert_ne!(fitted, home);
+        renderer.go_home().unwrap();
+        assert_eq!(renderer.camera.state(), home);
+
+        // Reset frames the new model, not home
+        fit_camera_to_visible_models(&registry, &mut renderer).unwrap();
+        assert_eq!(renderer.camera.state(), fitted);
+        assert!((fitted.target[0] - 50.0).abs() < 1e-3, "{:?}", fitted);
+        assert_eq!(renderer.home_view(), Some(home));
+    }
+
+    #[test]
+    fn test_storey_color_applies_to_exactly_its_elements() {
+        // A wall linked to the ground floor, a column placed on each floor
+        // and a beam with no storey
+        let content = "ISO-10303-21;
+HEADER;
+FILE_SCHEMA(('IFC4'));
+ENDSEC;
+DATA;
+#1=IFCBUILDINGSTOREY('ground-guid',$,'Ground Floor',$,$,$,$,$,.ELEMENT.,0.);
+#2=IFCBUILDINGSTOREY('first-guid',$,'First Floor',$,$,$,$,$,.ELEMENT.,3.);
+#10=IFCCARTESIANPOINT((0.,0.,4.5));
+#11=IFCAXIS2PLACEMENT3D(#10,$,$);
+#12=IFCLOCALPLACEMENT($,#11);
+#13=IFCCARTESIANPOINT((5.,0.,0.5));
+#14=IFCAXIS2PLACEMENT3D(#13,$,$);
+#15=IFCLOCALPLACEMENT($,#14);
+#20=IFCWALL('linked-guid',$,'Linked Wall',$,$,#12,$,$,$);
+#21=IFCCOLUMN('upper-guid',$,'Upper Column',$,$,#12,$,$,$);
+#22=IFCCOLUMN('lower-guid',$,'Lower Column',$,$,#15,$,$,$);
+#23=IFCBEAM('unplaced-guid',$,'Unplaced Beam',$,$,$,$,$,$);
+#30=IFCRELCONTAINEDINSPATIALSTRUCTURE('rel-guid',$,$,$,(#20),#1);
+ENDSEC;
+END-ISO-10303-21;
+";
+        let model = BimModel::from_ifc_file(&IfcFile::parse(content).unwrap()).unwrap();
+        let mut registry = ModelRegistry::new();
+        registry.add_model(model, "Storeys".into(), None);
+        assert!(storey_global_ids(&registry, 99).is_err());
+
+        let red = [1.0, 0.0, 0.0, 1.0];
+        for (storey, expected) in [(1, vec!["linked-guid", "lower-guid"]), (2, vec!["upper-guid"])] {
+            let on_storey = storey_global_ids(&registry, storey).unwrap();
+            assert_eq!(on_storey, expected);
+            let colors: std::collections::HashMap<String, [f32; 4]> =
+                on_storey.iter().map(|g| (g.clone(), red)).collect();
+            let (_, reg_model) = registry.iter().next().unwrap();
+            let mut mesh = reg_model.model.generate_meshes();
+            mesh.set_element_colors(&colors);
+
+            assert_eq!(mesh.elements.len(), 4);
+            for element in &mesh.elements {
+                let start = element.triangle_start as usize * 3;
+                let indices = &mesh.indices[start..start + element.triangle_count as usize * 3];
+                let is_red = indices
+                    .iter()
+                    .all(|&i| mesh.colors[i as usize * 4..i as usize * 4 + 4] == red);
+                assert_eq!(is_red, on_storey.contains(&element.global_id), "{}", element.global_id);
+            }
+        }
+    }
+
+    #[test]
+    fn test_self_test_parses_and_tessellates() {
+        let runtime = tokio::runtime::Builder::new_current_thread().build().unwrap();
+        let report = runtime.block_on(run_self_test());
+        let names: Vec<&str> = report.stages.iter().map(|s| s.name.as_str()).collect();
+        assert_eq!(names, ["parse", "tessellate", "gpu", "render"]);
+        assert_eq!(report.stages[0].status, SelfTestStatus::Passed, "{}", report.stages[0].detail);
+        assert_eq!(report.stages[1].status, SelfTestStatus::Passed, "{}", report.stages[1].detail);
+        assert_eq!(report.stages[1].detail, "2 elements, 16 triangles");
+        // Without a GPU the rest is unavailable or skipped, never failed
+        assert!(report.passed, "{:?}", report);
+        if report.stages[2].status == SelfTestStatus::Passed {
+            assert_eq!(report.stages[3].status, SelfTestStatus::Passed);
+        }
+    }
+
+    #[test]
+    fn test_geometry_stream_emits_one_item_per_element() {
+        let content = include_str!("../../test/sample_architectural.ifc");
+        let model = BimModel::from_ifc_file(&IfcFile::parse(content).unwrap()).unwrap();
+        let mesh = model.generate_meshes();
+
+        let items: Vec<ElementMesh> = element_mesh_stream(&model).collect();
+        assert_eq!(items.len(), model.element_count + 1);
+        assert!(items.last().unwrap().is_completion_marker());
+        for (item, element) in items.iter().zip(&mesh.elements) {
+            assert_eq!(item.global_id, element.global_id);
+            assert_eq!(item.indices.len(), element.triangle_count as usize * 3);
+        }
+    }
+
+    #[test]
+    fn test_geometry_stream_running_bounds_reach_model_bounds() {
+        let content = include_str!("../../test/sample_architectural.ifc");
+        let model = BimModel::from_ifc_file(&IfcFile::parse(content).unwrap()).unwrap();
+        let mesh = model.generate_meshes();
+        let items: Vec<ElementMesh> = element_mesh_stream(&model).collect();
+
+        // Updates come periodically and only ever grow
+        let updates: Vec<crate::bim::BoundingBox> = items.iter().filter_map(|item| item.running_bounds).collect();
+        assert!(items[0].running_bounds.is_some());
+        assert_eq!(updates.len(), mesh.elements.len().div_ceil(BOUNDS_UPDATE_INTERVAL) + 1);
+        for pair in updates.windows(2) {
+            for k in 0..3 {
+                assert!(pair[1].min[k] <= pair[0].min[k] && pair[1].max[k] >= pair[0].max[k]);
+            }
+        }
+
+        // After every element, the running union is the whole model's
+        let full = mesh.bounds.unwrap();
+        let last = items.last().unwrap();
+        assert!(last.is_completion_marker());
+        let running = last.running_bounds.unwrap();
+        assert_eq!((running.min, running.max), (full.min, full.max));
+    }
+
+    #[test]
+    fn test_unloading_model_frees_geometry_memory() {
+        let content = include_str!("../../test/sample_architectural.ifc");
+        let mut registry = ModelRegistry::new();
+        assert_eq!(memory_stats(&registry, None), MemoryStats::default());
+
+        let id = registry.add_model(BimModel::from_ifc_file(&IfcFile::parse(content).unwrap()).unwrap(), "Sample".into(), None);
+        registry.get_model(&id).unwrap().pick_mesh((0.0, ExplodeMode::default()));
+        let loaded = memory_stats(&registry, None);
+        assert!(loaded.entity_bytes > 0);
+        assert!(loaded.geometry_bytes > 0);
+        assert_eq!(loaded.total, loaded.entity_bytes + loaded.geometry_bytes);
+
+        registry.remove_model(&id);
+        let unloaded = memory_stats(&registry, None);
+        assert_eq!(unloaded.geometry_bytes, 0);
+        assert_eq!(unloaded.total, 0);
+    }
+
+    #[test]
+    fn test_pick_info_on_test_cube_front_face() {
+        let (cube_vertices, indices) = crate::renderer::generate_test_cube();
+        let vertices: Vec<f32> = cube_vertices.iter().flat_map(|v| v.position).collect();
+        let element = ElementInfo {
+            id: 1,
+            element_type: "Cube".to_string(),
+            name: "Cube".to_string(),
+            global_id: "cube".to_string(),
+            bounds: crate::bim::BoundingBox { min: [-1.0; 3], max: [1.0; 3] },
+            triangle_start: 0,
+            triangle_count: (indices.len() / 3) as u32,
+        };
+        let bvh = crate::renderer::Bvh::build(&vertices, &indices);
+        let mesh = ModelMesh {
+            normals: vertices.clone(),
+            colors: Vec::new(),
+            bounds: Some(element.bounds),
+            elements: vec![element],
+            vertices,
+            indices,
+        };
+        let pick_mesh = PickMesh { mesh, bvh };
+
+        // The middle of the view, a little off-center, lands on the z = 1 face
+        let camera = Camera::new(Vec3::new(0.0, 0.0, 5.0), Vec3::ZERO);
+        let (origin, dir) = camera.screen_to_ray(0.55, 0.45);
+        let info = closest_pick([&pick_mesh], origin, dir).unwrap();
+        assert_eq!(info.global_id, "cube");
+        assert!((info.world_point[2] - 1.0).abs() < 1e-5);
+        assert!((info.distance - Vec3::from_array(info.world_point).distance(origin)).abs() < 1e-4);
+        assert_eq!(info.normal, [0.0, 0.0, 1.0]);
+        assert!(Vec3::from_array(info.normal).dot(camera.position().into()) > 0.0);
+
+        // Looking away from the cube
+        assert!(closest_pick([&pick_mesh], origin, -dir).is_none());
+    }
+
+    #[test]
+    fn test_msaa_section_cut_is_clean_and_picking_matches() {
+        let Some(mut renderer) = crate::renderer::test_renderer(64, 64) else {
+            eprintln!("No GPU adapter available, skipping");
+            return;
+        };
+        if !renderer.gpu.msaa_supported(4) {
+            eprintln!("No 4x MSAA on this adapter, skipping");
+            return;
+        }
+        // A red box and a blue box side by side, cut by a tilted plane
+        let boxes = [("left", -1.5, [1.0, 0.0, 0.0, 1.0]), ("right", 1.5, [0.0, 0.0, 1.0, 1.0])];
+        let meshes = boxes.map(|(_, x, color)| crate::bim::geometry::generate_box_with_normals([x, 0.0, 0.0], [2.0, 2.0, 2.0], color));
+        let triangles = (meshes[0].indices.len() / 3) as u32;
+        let merged = crate::bim::geometry::merge_meshes(meshes.to_vec());
+        let elements = boxes
+            .iter()
+            .enumerate()
+            .map(|(i, (global_id, x, _))| ElementInfo {
+                id: i as i32,
+                element_type: "Box".to_string(),
+                name: global_id.to_string(),
+                global_id: global_id.to_string(),
+                bounds: crate::bim::BoundingBox { min: [x - 1.0, -1.0, -1.0], max: [x + 1.0, 1.0, 1.0] },
+                triangle_start: i as u32 * triangles,
+                triangle_count: triangles,
+            })
+            .collect();
+        let pick_mesh = PickMesh {
+            bvh: crate::renderer::Bvh::build(&merged.vertices, &merged.indices),
+            mesh: ModelMesh {
+                vertices: merged.vertices.clone(),
+                normals: merged.normals.clone(),
+                colors: merged.colors.clone(),
+                indices: merged.indices.clone(),
+                bounds: None,
+                elements,
+            },
+        };
+        renderer.load_mesh(&merged.vertices, &merged.normals, &merged.colors, &merged.indices).unwrap();
+        renderer.update_camera([1.0, 4.0, 7.0], [0.0, 0.0, 0.0]);
+        renderer.set_section_plane(Some(([0.0, 0.3, 0.0], [0.15, -1.0, 0.1]))).unwrap();
+        let single = renderer.render_frame().unwrap();
+
+        renderer.set_msaa_samples(4).unwrap();
+        assert_eq!(renderer.msaa_samples(), 4);
+        assert!(renderer.set_msaa_samples(3).is_err());
+        let multi = renderer.render_frame().unwrap();
+        let pixel = |frame: &[u8], x: i32, y: i32| -> [u8; 4] {
+            let i = ((y.clamp(0, 63) * 64 + x.clamp(0, 63)) * 4) as usize;
+            frame[i..i + 4].try_into().unwrap()
+        };
+
+        let mut edge_pixels = 0;
+        let mut picked_ids = std::collections::HashSet::new();
+        for y in 0..64 {
+            for x in 0..64 {
+                let center = pixel(&single, x, y);
+                let uniform = (-1..=1).all(|dy| (-1..=1).all(|dx| pixel(&single, x + dx, y + dy) == center));
+                if !uniform {
+                    edge_pixels += (pixel(&multi, x, y) != center) as usize;
+                    continue;
+                }
+                // Away from edges, no samples dropped or kept across the cut
+                assert_eq!(pixel(&multi, x, y), center, "speckle at ({}, {})", x, y);
+
+                // Picking agrees with what the multisampled frame shows
+                let shown = match center {
+                    [r, 0, 0, _] if r > 0 => Some("left"),
+                    [0, 0, b, _] if b > 0 => Some("right"),
+                    _ => None,
+                };
+                if let Some(shown) = shown {
+                    let (origin, dir) = renderer.camera.screen_to_ray((x as f32 + 0.5) / 64.0, (y as f32 + 0.5) / 64.0);
+                    let picked = closest_pick([&pick_mesh], origin, dir).map(|info| info.global_id);
+                    assert_eq!(picked.as_deref(), Some(shown), "pick at ({}, {})", x, y);
+                    picked_ids.insert(shown);
+                }
+            }
+        }
+        // Edges are resolved from several samples
+        assert!(edge_pixels > 0);
+        assert_eq!(picked_ids.len(), 2);
+
+        renderer.set_msaa_samples(1).unwrap();
+        assert_eq!(renderer.render_frame().unwrap(), single);
+    }
+
+    #[test]
+    fn test_snap_pick_near_cube_corner_snaps_to_it() {
+        let (cube_vertices, indices) = crate::renderer::generate_test_cube();
+        let vertices: Vec<f32> = cube_vertices.iter().flat_map(|v| v.position).collect();
+        let element = ElementInfo {
+            id: 1,
+            element_type: "Cube".to_string(),
+            name: "Cube".to_string(),
+            global_id: "cube".to_string(),
+            bounds: crate::bim::BoundingBox { min: [-1.0; 3], max: [1.0; 3] },
+            triangle_start: 0,
+            triangle_count: (indices.len() / 3) as u32,
+        };
+        let bvh = crate::renderer::Bvh::build(&vertices, &indices);
+        let mesh = ModelMesh {
+            normals: vertices.clone(),
+            colors: Vec::new(),
+            bounds: Some(element.bounds),
+            elements: vec![element],
+            vertices,
+            indices,
+        };
+        let pick_mesh = PickMesh { mesh, bvh };
+
+        // A few pixels inside the front face's top-right corner (1, 1, 1)
+        let camera = Camera::new(Vec3::new(0.3, 0.4, 5.0), Vec3::ZERO);
+        let viewport = (800.0, 600.0);
+        let (cx, cy, _) = camera.project_point(Vec3::ONE, viewport).unwrap();
+        let tap = ((cx - 4.0) / viewport.0, (cy + 3.0) / viewport.1);
+        let snapped = closest_snap([&pick_mesh], &camera, viewport, tap, 10.0).unwrap();
+        assert_eq!(snapped.snap_type, SnapType::Vertex);
+        assert_eq!(snapped.world_point, [1.0, 1.0, 1.0]);
+
+        // Out of range of any corner or edge: the surface point
+        let surface = closest_snap([&pick_mesh], &camera, viewport, tap, 2.0).unwrap();
+        assert_eq!(surface.snap_type, SnapType::Surface);
+        assert!((surface.world_point[2] - 1.0).abs() < 1e-5);
+        assert_ne!(surface.world_point, [1.0, 1.0, 1.0]);
+
+        // Missing the cube snaps to nothing
+        assert!(closest_snap([&pick_mesh], &camera, viewport, (0.02, 0.02), 10.0).is_none());
+    }
+
+    #[test]
+    fn test_visible_export_skips_elements_outside_frustum_or_clipped() {
+        // Two 2.5 m walls side by side: x = -1.25..1.25 and 1.75..4.25
+        let content = "ISO-10303-21;
+HEADER;
+FILE_SCHEMA(('IFC2X3'));
+ENDSEC;
+DATA;
+#1=IFCWALL('2O2Fr$t4X7Zf8NOew3FLOH',$,'Wall A',$,$,$,$,$);
+#2=IFCWALL('2O2Fr$t4X7Zf8NOew3FLOI',$,'Wall B',$,$,$,$,$);
+ENDSEC;
+END-ISO-10303-21;";
+        let mut registry = ModelRegistry::new();
+        registry.add_model(BimModel::from_ifc_file(&IfcFile::parse(content).unwrap()).unwrap(), "Walls".to_string(), None);
+        let no_hidden = std::collections::HashSet::new();
+        let exported = |export: &GltfExport| -> Vec<String> {
+            let document = export.document(None);
+            let nodes = document["nodes"].as_array().unwrap();
+            nodes.iter().map(|n| n["name"].as_str().unwrap().to_string()).collect()
+        };
+
+        // Close up on wall A: wall B is off to the right
+        let mut camera = Camera::new(Vec3::new(0.0, 1.5, 2.0), Vec3::new(0.0, 1.5, 0.0));
+        camera.set_aspect_ratio(1.0);
+        let export = visible_export(&registry, &no_hidden, &camera, None, None, ExportPrecision::default());
+        assert_eq!(exported(&export), vec!["Wall A"]);
+
+        // Both in view, but the section plane keeps only x > 1.5
+        let camera = Camera::new(Vec3::new(1.5, 1.5, 15.0), Vec3::new(1.5, 1.5, 0.0));
+        assert_eq!(visible_export(&registry, &no_hidden, &camera, None, None, ExportPrecision::default()).element_count(), 2);
+        let section = Some(([1.5, 0.0, 0.0], [1.0, 0.0, 0.0]));
+        let export = visible_export(&registry, &no_hidden, &camera, section, None, ExportPrecision::default());
+        assert_eq!(exported(&export), vec!["Wall B"]);
+
+        // A clip box around wall A only
+        let clip_box = Some(([-2.0, 0.0, -1.0], [1.5, 3.0, 1.0]));
+        let export = visible_export(&registry, &no_hidden, &camera, None, clip_box, ExportPrecision::default());
+        assert_eq!(exported(&export), vec!["Wall A"]);
+    }
+    #[test]
+    fn test_section_state_reflects_setters() {
+        set_clip_box(4.0, 3.0, 2.0, -1.0, 0.0, 0.5).unwrap();
+        set_section_plane(0.0, 1.5, 0.0, 0.0, 2.0, 0.0).unwrap();
+        set_section_plane_enabled(false).unwrap();
+
+        let state = get_section_state();
+        let clip_box = state.clip_box.unwrap();
+        assert_eq!((clip_box.min, clip_box.max), ([-1.0, 0.0, 0.5], [4.0, 3.0, 2.0]));
+        assert_eq!(state.planes.len(), 1);
+        assert_eq!((state.planes[0].origin, state.planes[0].normal), ([0.0, 1.5, 0.0], [0.0, 1.0, 0.0]));
+        assert!(!state.planes[0].enabled);
+
+        clear_clip_box().unwrap();
+        clear_section_plane().unwrap();
+        let state = get_section_state();
+        assert!(state.planes.is_empty() && state.clip_box.is_none());
+    }
+}
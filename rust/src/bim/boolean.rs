@@ -0,0 +1,463 @@
+//! Boolean Geometry
+//!
+//! Half-space clipping for `IfcBooleanResult` / `IfcBooleanClippingResult`
+//! representations (sloped walls, beveled slabs): the base solid's mesh is cut
+//! by each half-space plane and the cut is capped to keep the mesh closed.
+//! Convex solids (opening voids) are subtracted as the union of the pieces
+//! outside each of their faces.
+
+use super::coordinates::axis_placement;
+use super::entities::{EntityId, IfcEntity, IfcValue};
+use super::geometry::{merge_meshes, Mesh, SubMesh};
+use super::ifc_parser::IfcFile;
+use super::tessellation::tessellate_item;
+use super::topology::face_normal;
+use glam::Vec3;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// Maximum nesting of boolean results followed (guards cycles)
+const MAX_BOOLEAN_DEPTH: usize = 32;
+
+/// Vertices within this distance of a clip plane count as lying on it
+const ON_PLANE_EPSILON: f32 = 1e-6;
+
+/// Cut points closer than this are merged when chaining the cap outline
+const CAP_WELD_EPSILON: f32 = 1e-5;
+
+/// Face planes closer than this (in normal and offset) are the same face,
+/// and vertices this far outside a face make a solid non-convex
+const CONVEX_EPSILON: f32 = 1e-4;
+
+/// A clipping plane; geometry on the side the normal points to is kept
+/// (same convention as the renderer's section plane)
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct ClipPlane {
+    pub origin: [f32; 3],
+    pub normal: [f32; 3],
+}
+
+impl ClipPlane {
+    /// Signed distance of a point from the plane (positive = kept side)
+    pub fn signed_distance(&self, point: Vec3) -> f32 {
+        (point - Vec3::from_array(self.origin)).dot(Vec3::from_array(self.normal).normalize_or_zero())
+    }
+
+}
+
+/// Clip a mesh against a plane, keeping the side the normal points to
+///
+/// Triangles crossing the plane are split, and the cut outline is capped with
+/// faces facing against the plane normal. Caps are fan-triangulated around
+/// their centroid, which is exact for convex cross-sections (all placeholder
+/// geometry). Submesh ranges are carried over; caps join the last submesh.
+pub fn clip_mesh(mesh: &Mesh, plane: &ClipPlane) -> Mesh {
+    let mut out = Mesh::new();
+    let mut segments: Vec<[Vec3; 2]> = Vec::new();
+    let mut cap_color = None;
+
+    let position = |i: u32| Vec3::from_slice(&mesh.vertices[i as usize * 3..i as usize * 3 + 3]);
+    let attribute = |data: &[f32], i: u32, n: usize| -> Vec<f32> {
+        data.get(i as usize * n..i as usize * n + n)
+            .map(<[f32]>::to_vec)
+            .unwrap_or_else(|| vec![0.0; n])
+    };
+
+    // Map each source triangle's output range so submeshes can be rebuilt
+    let mut output_starts = Vec::with_capacity(mesh.triangle_count() + 1);
+
+    for tri in mesh.indices.chunks_exact(3) {
+        output_starts.push(out.indices.len() as u32);
+
+        // A clipped triangle is a polygon of at most 4 (position, normal, color) corners
+        let corners: Vec<(Vec3, Vec<f32>, Vec<f32>)> = tri
+            .iter()
+            .map(|&i| (position(i), attribute(&mesh.normals, i, 3), attribute(&mesh.colors, i, 4)))
+            .collect();
+        let distances: Vec<f32> = corners
+            .iter()
+            .map(|c| plane.signed_distance(c.0))
+            .map(|d| if d.abs() <= ON_PLANE_EPSILON { 0.0 } else { d })
+            .collect();
+
+        let mut polygon = Vec::with_capacity(4);
+        let mut cut = Vec::with_capacity(2);
+        for k in 0..3 {
+            let (current, next) = (&corners[k], &corners[(k + 1) % 3]);
+            let (d0, d1) = (distances[k], distances[(k + 1) % 3]);
+            if d0 >= 0.0 {
+                polygon.push(current.clone());
+            }
+            if d0 == 0.0 && d1 == 0.0 && distances.iter().any(|&d| d > 0.0) {
+                // A kept triangle's edge lying on the plane bounds the cap
+                cut.extend([current.0, next.0]);
+            }
+            if (d0 > 0.0 && d1 < 0.0) || (d0 < 0.0 && d1 > 0.0) {
+                let t = d0 / (d0 - d1);
+                let lerp = |a: &[f32], b: &[f32]| -> Vec<f32> {
+                    a.iter().zip(b).map(|(x, y)| x + (y - x) * t).collect()
+                };
+                let point = current.0.lerp(next.0, t);
+                polygon.push((point, lerp(&current.1, &next.1), lerp(&current.2, &next.2)));
+                cut.push(point);
+                cap_color.get_or_insert_with(|| current.2.clone());
+            }
+        }
+        if let [a, b] = cut[..] {
+            segments.push([a, b]);
+        }
+
+        if polygon.len() < 3 {
+            continue;
+        }
+        let base = out.vertex_count() as u32;
+        for (p, normal, color) in &polygon {
+            out.add_vertex(p.x, p.y, p.z);
+            out.add_normal(normal[0], normal[1], normal[2]);
+            out.add_color(color[0], color[1], color[2], color[3]);
+        }
+        for k in 1..polygon.len() as u32 - 1 {
+            out.add_triangle(base, base + k, base + k + 1);
+        }
+    }
+    output_starts.push(out.indices.len() as u32);
+
+    let cap_color = cap_color.unwrap_or_else(|| vec![0.8, 0.8, 0.8, 1.0]);
+    add_caps(&mut out, &segments, plane, &cap_color);
+
+    // Rebuild submesh ranges over the clipped triangles
+    let submesh_count = mesh.submeshes.len();
+    out.submeshes = mesh
+        .submeshes
+        .iter()
+        .enumerate()
+        .filter_map(|(i, submesh)| {
+            let first = (submesh.index_offset / 3) as usize;
+            let last = first + (submesh.index_count / 3) as usize;
+            let start = *output_starts.get(first)?;
+            let end = if i + 1 == submesh_count {
+                out.indices.len() as u32
+            } else {
+                *output_starts.get(last)?
+            };
+            (end > start).then_some(SubMesh {
+                index_offset: start,
+                index_count: end - start,
+                material_id: submesh.material_id,
+            })
+        })
+        .collect();
+
+    out
+}
+
+/// Subtract a convex solid from a mesh, or None if `cutter` is not convex
+///
+/// The mesh is split into the part outside the cutter's first face, the part
+/// inside it but outside the second, and so on; their union is the
+/// difference. Each part is capped where it was cut, so the faces of the
+/// hole (reveals) are the caps that face into the cutter.
+pub fn subtract_convex(mesh: &Mesh, cutter: &Mesh) -> Option<Mesh> {
+    let faces = convex_faces(cutter)?;
+    let mut parts = Vec::new();
+    let mut inside = mesh.clone();
+    for face in &faces {
+        let outside = clip_mesh(&inside, face);
+        if outside.triangle_count() > 0 {
+            parts.push(outside);
+        }
+        let flipped = ClipPlane {
+            origin: face.origin,
+            normal: (-Vec3::from_array(face.normal)).to_array(),
+        };
+        inside = clip_mesh(&inside, &flipped);
+        if inside.triangle_count() == 0 {
+            break;
+        }
+    }
+    Some(merge_meshes(parts))
+}
+
+/// Distinct face planes of a closed mesh, normals pointing out, or None
+/// unless every vertex lies behind every face (a convex solid)
+fn convex_faces(mesh: &Mesh) -> Option<Vec<ClipPlane>> {
+    let mut faces: Vec<ClipPlane> = Vec::new();
+    for tri in mesh.indices.chunks_exact(3) {
+        let Some(normal) = face_normal(&mesh.vertices, [tri[0], tri[1], tri[2]]) else {
+            continue;
+        };
+        let origin = Vec3::from_slice(&mesh.vertices[tri[0] as usize * 3..tri[0] as usize * 3 + 3]);
+        let known = faces.iter().any(|f| {
+            Vec3::from_array(f.normal).dot(normal) > 1.0 - CONVEX_EPSILON && f.signed_distance(origin).abs() < CONVEX_EPSILON
+        });
+        if !known {
+            faces.push(ClipPlane {
+                origin: origin.to_array(),
+                normal: normal.to_array(),
+            });
+        }
+    }
+
+    let convex = mesh
+        .vertices
+        .chunks_exact(3)
+        .all(|p| faces.iter().all(|f| f.signed_distance(Vec3::from_slice(p)) <= CONVEX_EPSILON));
+    (faces.len() >= 4 && convex).then_some(faces)
+}
+
+/// Close the cut with faces facing against the plane normal
+fn add_caps(mesh: &mut Mesh, segments: &[[Vec3; 2]], plane: &ClipPlane, color: &[f32]) {
+    let normal = -Vec3::from_array(plane.normal).normalize_or_zero();
+
+    for outline in chain_segments(segments) {
+        if outline.len() < 3 {
+            continue;
+        }
+        let centroid = outline.iter().copied().sum::<Vec3>() / outline.len() as f32;
+
+        let base = mesh.vertex_count() as u32;
+        for p in std::iter::once(centroid).chain(outline.iter().copied()) {
+            mesh.add_vertex(p.x, p.y, p.z);
+            mesh.add_normal(normal.x, normal.y, normal.z);
+            mesh.add_color(color[0], color[1], color[2], color[3]);
+        }
+
+        let n = outline.len() as u32;
+        for k in 0..n {
+            let (a, b) = (base + 1 + k, base + 1 + (k + 1) % n);
+            let winding = (outline[(k as usize + 1) % n as usize] - centroid)
+                .cross(outline[k as usize] - centroid)
+                .dot(normal);
+            if winding >= 0.0 {
+                mesh.add_triangle(base, b, a);
+            } else {
+                mesh.add_triangle(base, a, b);
+            }
+        }
+    }
+}
+
+/// Chain cut segments into closed outlines by matching endpoints
+fn chain_segments(segments: &[[Vec3; 2]]) -> Vec<Vec<Vec3>> {
+    let key = |p: Vec3| (p / CAP_WELD_EPSILON).round().as_ivec3().to_array();
+
+    let mut by_point: HashMap<[i32; 3], Vec<usize>> = HashMap::new();
+    for (i, segment) in segments.iter().enumerate() {
+        for &p in segment {
+            by_point.entry(key(p)).or_default().push(i);
+        }
+    }
+
+    let mut used = vec![false; segments.len()];
+    let mut outlines = Vec::new();
+    for start in 0..segments.len() {
+        if used[start] {
+            continue;
+        }
+        used[start] = true;
+        let mut outline = vec![segments[start][0]];
+        let mut tip = segments[start][1];
+
+        while key(tip) != key(outline[0]) {
+            let next = by_point
+                .get(&key(tip))
+                .and_then(|candidates| candidates.iter().copied().find(|&i| !used[i]));
+            let Some(next) = next else {
+                break;
+            };
+            used[next] = true;
+            outline.push(tip);
+            let [a, b] = segments[next];
+            tip = if key(a) == key(tip) { b } else { a };
+        }
+        outlines.push(outline);
+    }
+    outlines
+}
+
+/// Tessellate an `IfcBooleanResult` / `IfcBooleanClippingResult` tree
+///
+/// The base solid is tessellated and clipped by each half-space subtracted
+/// from it, all in the representation's own coordinates.
+pub fn tessellate_boolean_result(ifc_file: &IfcFile, item: &IfcEntity) -> Option<Mesh> {
+    let (base, planes) = boolean_clip_planes(ifc_file, item.id)?;
+    let mut mesh = tessellate_item(ifc_file, base)?;
+    for plane in &planes {
+        mesh = clip_mesh(&mesh, plane);
+    }
+    (mesh.triangle_count() > 0).then_some(mesh)
+}
+
+/// Base solid and clipping planes of an `IfcBooleanResult` /
+/// `IfcBooleanClippingResult` tree
+///
+/// Follows DIFFERENCE operations down the first operand to the base solid and
+/// returns it with the half-space planes (in the representation's
+/// coordinates) that cut it. Other operations and second operands leave the
+/// base as is. `IfcPolygonalBoundedHalfSpace` is treated as unbounded.
+pub fn boolean_clip_planes(ifc_file: &IfcFile, item: EntityId) -> Option<(&IfcEntity, Vec<ClipPlane>)> {
+    let mut planes = Vec::new();
+    let mut current = ifc_file.get_entity(item)?;
+    if !is_boolean_result(current) {
+        return None;
+    }
+
+    for _ in 0..MAX_BOOLEAN_DEPTH {
+        match current.entity_type.as_str() {
+            // IFCBOOLEANRESULT(Operator, FirstOperand, SecondOperand)
+            "IFCBOOLEANRESULT" | "IFCBOOLEANCLIPPINGRESULT" => {
+                if matches!(current.get_attr(0), Some(IfcValue::Enum(op)) if op == "DIFFERENCE") {
+                    if let Some(plane) = current
+                        .get_entity_ref(2)
+                        .and_then(|operand| half_space_plane(ifc_file, operand))
+                    {
+                        planes.push(plane);
+                    }
+                }
+                current = ifc_file.get_entity(current.get_entity_ref(1)?)?;
+            }
+            _ => break,
+        }
+    }
+
+    // Nesting deeper than the limit (or a cycle) has no base solid
+    (!is_boolean_result(current)).then_some((current, planes))
+}
+
+fn is_boolean_result(entity: &IfcEntity) -> bool {
+    matches!(entity.entity_type.as_str(), "IFCBOOLEANRESULT" | "IFCBOOLEANCLIPPINGRESULT")
+}
+
+/// The kept side of a half-space solid subtracted from a base solid
+fn half_space_plane(ifc_file: &IfcFile, id: EntityId) -> Option<ClipPlane> {
+    let solid = ifc_file.get_entity(id)?;
+    if !matches!(
+        solid.entity_type.as_str(),
+        "IFCHALFSPACESOLID" | "IFCPOLYGONALBOUNDEDHALFSPACE" | "IFCBOXEDHALFSPACE"
+    ) {
+        return None;
+    }
+
+    // IFCHALFSPACESOLID(BaseSurface, AgreementFlag) with IFCPLANE(Position)
+    let surface = ifc_file.get_entity(solid.get_entity_ref(0)?)?;
+    if surface.entity_type != "IFCPLANE" {
+        return None;
+    }
+    let position = axis_placement(ifc_file, surface.get_entity_ref(0)?);
+    let (origin, axis) = (position.translation.as_vec3(), position.matrix3.z_axis.as_vec3());
+
+    // AgreementFlag TRUE: the normal points away from the half-space's
+    // material, so subtracting it keeps the side the normal points to
+    let agrees = !matches!(solid.get_attr(1), Some(IfcValue::Boolean(false)));
+    let normal = if agrees { axis } else { -axis };
+    Some(ClipPlane {
+        origin: origin.to_array(),
+        normal: normal.to_array(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bim::geometry::generate_box_with_normals;
+
+    #[test]
+    fn test_clip_box_with_half_space() {
+        let mesh = generate_box_with_normals([0.0; 3], [2.0, 2.0, 2.0], [1.0; 4]);
+
+        // Keep everything below y = 0.5
+        let plane = ClipPlane {
+            origin: [0.0, 0.5, 0.0],
+            normal: [0.0, -1.0, 0.0],
+        };
+        let clipped = clip_mesh(&mesh, &plane);
+        let bounds = clipped.bounding_box().unwrap();
+        assert_eq!(bounds.min, [-1.0, -1.0, -1.0]);
+        assert!((bounds.max[1] - 0.5).abs() < 1e-6);
+        assert_eq!([bounds.max[0], bounds.max[2]], [1.0, 1.0]);
+        assert!((clipped.volume() - 2.0 * 1.5 * 2.0).abs() < 1e-4);
+        assert!(clipped.validate().is_clean());
+
+        // A sloped cut through the top corner: a wedge is removed
+        let plane = ClipPlane {
+            origin: [0.0, 1.0, 0.0],
+            normal: [-1.0, -1.0, 0.0],
+        };
+        let clipped = clip_mesh(&mesh, &plane);
+        let bounds = clipped.bounding_box().unwrap();
+        assert_eq!(bounds.max, [1.0, 1.0, 1.0]);
+        // Removed wedge: right triangle with 1m legs, 2m deep
+        assert!((clipped.volume() - 7.0).abs() < 1e-4);
+        let above: Vec<f32> = clipped
+            .vertices
+            .chunks_exact(3)
+            .map(|p| p[0] + p[1])
+            .collect();
+        assert!(above.iter().all(|&s| s <= 1.0 + 1e-5));
+    }
+
+    #[test]
+    fn test_wall_with_rectangular_opening_has_hole() {
+        let wall = generate_box_with_normals([0.0, 1.5, 0.0], [4.0, 3.0, 0.2], [0.8, 0.8, 0.8, 1.0]);
+
+        // A 1m x 2m door opening reaching through the full thickness
+        let opening = generate_box_with_normals([0.0, 1.0, 0.0], [1.0, 2.0, 0.6], [1.0; 4]);
+        let cut = subtract_convex(&wall, &opening).unwrap();
+        let expected = 4.0 * 3.0 * 0.2 - 1.0 * 2.0 * 0.2;
+        assert!((cut.volume() - expected).abs() < 1e-4, "volume {}", cut.volume());
+        let (bounds, whole) = (cut.bounding_box().unwrap(), wall.bounding_box().unwrap());
+        assert_eq!((bounds.min, bounds.max), (whole.min, whole.max));
+
+        // No triangle covers the middle of the opening; the wall beside it is solid
+        let blocked = |x: f32, y: f32| {
+            let (origin, dir) = (Vec3::new(x, y, 1.0), -Vec3::Z);
+            cut.indices.chunks_exact(3).any(|tri| {
+                let p = |i: u32| Vec3::from_slice(&cut.vertices[i as usize * 3..i as usize * 3 + 3]);
+                crate::renderer::ray_triangle_intersect(origin, dir, p(tri[0]), p(tri[1]), p(tri[2])).is_some()
+            })
+        };
+        assert!(!blocked(0.0, 1.0));
+        assert!(blocked(1.5, 1.0) && blocked(0.0, 2.5));
+
+        // A cutter clear of the wall leaves it whole; a non-convex one is refused
+        let away = generate_box_with_normals([10.0, 1.0, 0.0], [1.0, 2.0, 0.6], [1.0; 4]);
+        assert!((subtract_convex(&wall, &away).unwrap().volume() - 4.0 * 3.0 * 0.2).abs() < 1e-4);
+        let l_shape = merge_meshes(vec![opening.clone(), away]);
+        assert!(subtract_convex(&wall, &l_shape).is_none());
+    }
+
+    #[test]
+    fn test_boolean_clipping_result_planes() {
+        let content = r#"ISO-10303-21;
+HEADER;
+FILE_DESCRIPTION(('ViewDefinition'),'2;1');
+FILE_NAME('clip.ifc','2024-01-01',(''),(''),'','','');
+FILE_SCHEMA(('IFC4'));
+ENDSEC;
+DATA;
+#1=IFCRECTANGLEPROFILEDEF(.AREA.,$,$,4.,0.2);
+#2=IFCEXTRUDEDAREASOLID(#1,$,$,3.);
+#3=IFCCARTESIANPOINT((0.,0.,2.5));
+#4=IFCDIRECTION((0.,0.,1.));
+#5=IFCAXIS2PLACEMENT3D(#3,#4,$);
+#6=IFCPLANE(#5);
+#7=IFCHALFSPACESOLID(#6,.F.);
+#8=IFCBOOLEANCLIPPINGRESULT(.DIFFERENCE.,#2,#7);
+ENDSEC;
+END-ISO-10303-21;
+"#;
+        let file = IfcFile::parse(content).unwrap();
+        let (base, planes) = boolean_clip_planes(&file, 8).unwrap();
+        assert_eq!(base.id, 2);
+        // AgreementFlag .F.: the half-space lies above z = 2.5, so keep below
+        assert_eq!(planes, vec![ClipPlane { origin: [0.0, 0.0, 2.5], normal: [0.0, 0.0, -1.0] }]);
+        assert!(boolean_clip_planes(&file, 2).is_none());
+
+        // The base extrusion is clipped in its own coordinates
+        let mesh = tessellate_boolean_result(&file, file.get_entity(8).unwrap()).unwrap();
+        let bounds = mesh.bounding_box().unwrap();
+        assert_eq!(bounds.min, [-2.0, -0.1, 0.0]);
+        assert_eq!(bounds.max, [2.0, 0.1, 2.5]);
+        assert!((mesh.volume() - 4.0 * 0.2 * 2.5).abs() < 1e-4);
+    }
+}
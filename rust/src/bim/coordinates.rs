@@ -0,0 +1,461 @@
+//! Coordinate Precision
+//!
+//! Georeferenced models often place elements millions of units away from the
+//! origin, where f32 can no longer represent millimeters. Positions are kept
+//! in f64 world coordinates and only converted to f32 relative to a
+//! model-local origin when building GPU buffers.
+
+use super::entities::{EntityId, IfcValue};
+use super::ifc_parser::IfcFile;
+use glam::{DAffine3, DMat3, DVec3};
+use serde::{Deserialize, Serialize};
+
+/// A position in full-precision world coordinates
+pub type WorldPoint = [f64; 3];
+
+/// Deepest IfcLocalPlacement nesting followed before giving up
+const MAX_PLACEMENT_DEPTH: usize = 64;
+
+/// Model-local origin that GPU (f32) coordinates are expressed relative to
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+pub struct LocalOrigin {
+    pub offset: WorldPoint,
+}
+
+impl LocalOrigin {
+    pub fn new(offset: WorldPoint) -> Self {
+        Self { offset }
+    }
+
+    /// Convert a world position to f32 coordinates relative to this origin
+    pub fn to_local(&self, point: WorldPoint) -> [f32; 3] {
+        [
+            (point[0] - self.offset[0]) as f32,
+            (point[1] - self.offset[1]) as f32,
+            (point[2] - self.offset[2]) as f32,
+        ]
+    }
+
+    /// Convert local f32 coordinates back to a world position
+    pub fn to_world(&self, local: [f32; 3]) -> WorldPoint {
+        [
+            self.offset[0] + local[0] as f64,
+            self.offset[1] + local[1] as f64,
+            self.offset[2] + local[2] as f64,
+        ]
+    }
+
+    /// The offset in Y-up render axes, for a model with `up_axis` source axes
+    pub fn y_up_offset(&self, up_axis: UpAxis) -> WorldPoint {
+        let [x, y, z] = self.offset;
+        match up_axis {
+            UpAxis::Y => [x, y, z],
+            UpAxis::Z => [x, z, -y],
+        }
+    }
+}
+
+/// Vertical axis of a model's source coordinates
+///
+/// IFC is Z-up; the renderer (like glTF and OBJ) is Y-up, right-handed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum UpAxis {
+    Y,
+    #[default]
+    Z,
+}
+
+impl UpAxis {
+    /// Convert a point or direction from source axes to Y-up render axes
+    pub fn to_y_up(self, p: [f32; 3]) -> [f32; 3] {
+        match self {
+            UpAxis::Y => p,
+            // Rotate -90° about X: +Z becomes +Y, +Y becomes -Z
+            UpAxis::Z => [p[0], p[2], -p[1]],
+        }
+    }
+
+    /// Index of the vertical coordinate in source axes
+    pub fn index(self) -> usize {
+        match self {
+            UpAxis::Y => 1,
+            UpAxis::Z => 2,
+        }
+    }
+
+    /// Convert a point or direction from Y-up render axes back to source axes
+    pub fn from_y_up(self, p: [f32; 3]) -> [f32; 3] {
+        match self {
+            UpAxis::Y => p,
+            UpAxis::Z => [p[0], -p[2], p[1]],
+        }
+    }
+}
+
+/// Resolve the world location of an IfcObjectPlacement (translation only)
+///
+/// Follows IFCLOCALPLACEMENT(PlacementRelTo, RelativePlacement) up the chain,
+/// summing each IFCAXIS2PLACEMENT3D/2D Location point. A chain with a cycle
+/// or a dangling parent has no location.
+pub fn placement_location(ifc_file: &IfcFile, placement: Option<EntityId>) -> Option<WorldPoint> {
+    let chain = ifc_file
+        .resolve_chain(placement?, MAX_PLACEMENT_DEPTH, |entity| {
+            (entity.entity_type == "IFCLOCALPLACEMENT")
+                .then(|| entity.get_entity_ref(0))
+                .flatten()
+        })
+        .ok()?;
+    let mut location = [0.0; 3];
+    let mut resolved = false;
+
+    for entity in chain.into_iter().filter(|e| e.entity_type == "IFCLOCALPLACEMENT") {
+        if let Some(point) = entity
+            .get_entity_ref(1)
+            .and_then(|axis| ifc_file.get_entity(axis))
+            .and_then(|axis| axis.get_entity_ref(0))
+            .and_then(|point| cartesian_point(ifc_file, point))
+        {
+            for (coord, delta) in location.iter_mut().zip(point) {
+                *coord += delta;
+            }
+            resolved = true;
+        }
+    }
+
+    resolved.then_some(location)
+}
+
+/// Resolve the full world transform of an IfcObjectPlacement in f64
+///
+/// Composes each IFCLOCALPLACEMENT's IFCAXIS2PLACEMENT3D/2D (location,
+/// axis and reference direction) with those of its parents. A chain with a
+/// cycle or a dangling parent, or that starts at anything but a local
+/// placement, has no transform.
+pub fn placement_matrix(ifc_file: &IfcFile, placement: Option<EntityId>) -> Option<DAffine3> {
+    let chain = ifc_file
+        .resolve_chain(placement?, MAX_PLACEMENT_DEPTH, |entity| {
+            (entity.entity_type == "IFCLOCALPLACEMENT")
+                .then(|| entity.get_entity_ref(0))
+                .flatten()
+        })
+        .ok()?;
+    if chain[0].entity_type != "IFCLOCALPLACEMENT" {
+        return None;
+    }
+
+    Some(
+        chain
+            .iter()
+            .filter(|e| e.entity_type == "IFCLOCALPLACEMENT")
+            .map(|e| e.get_entity_ref(1).map_or(DAffine3::IDENTITY, |axis| axis_placement(ifc_file, axis)))
+            .fold(DAffine3::IDENTITY, |child, relative| relative * child),
+    )
+}
+
+/// Transform of an IFCAXIS2PLACEMENT3D(Location, Axis, RefDirection) or
+/// IFCAXIS2PLACEMENT2D(Location, RefDirection)
+pub fn axis_placement(ifc_file: &IfcFile, id: EntityId) -> DAffine3 {
+    let Some(placement) = ifc_file.get_entity(id) else {
+        return DAffine3::IDENTITY;
+    };
+    let vector = |index: usize, default: DVec3| {
+        placement
+            .get_entity_ref(index)
+            .and_then(|d| direction(ifc_file, d))
+            .map_or(default, DVec3::from)
+    };
+    let location = placement
+        .get_entity_ref(0)
+        .and_then(|p| cartesian_point(ifc_file, p))
+        .map_or(DVec3::ZERO, DVec3::from);
+
+    let (z, x) = match placement.entity_type.as_str() {
+        "IFCAXIS2PLACEMENT2D" => (DVec3::Z, vector(1, DVec3::X)),
+        _ => (vector(1, DVec3::Z).try_normalize().unwrap_or(DVec3::Z), vector(2, DVec3::X)),
+    };
+    // RefDirection projected onto the plane normal to the axis
+    let x = x
+        .reject_from_normalized(z)
+        .try_normalize()
+        .unwrap_or_else(|| z.any_orthonormal_vector());
+    DAffine3::from_mat3_translation(DMat3::from_cols(x, z.cross(x), z), location)
+}
+
+/// Read an IFCCARTESIANPOINT((x, y, z)) as f64 (2D points get z = 0)
+pub fn cartesian_point(ifc_file: &IfcFile, id: EntityId) -> Option<WorldPoint> {
+    let entity = ifc_file.get_entity(id)?;
+    if entity.entity_type != "IFCCARTESIANPOINT" {
+        return None;
+    }
+
+    let mut point = [0.0; 3];
+    for (coord, value) in point.iter_mut().zip(entity.get_list(0)?) {
+        *coord = match value {
+            IfcValue::Real(v) => *v,
+            IfcValue::Integer(v) => *v as f64,
+            _ => return None,
+        };
+    }
+    Some(point)
+}
+
+/// Read an IFCDIRECTION((x, y, z)) as a unit vector (2D directions get z = 0)
+pub fn direction(ifc_file: &IfcFile, id: EntityId) -> Option<[f64; 3]> {
+    let entity = ifc_file.get_entity(id)?;
+    if entity.entity_type != "IFCDIRECTION" {
+        return None;
+    }
+
+    let mut ratios = [0.0; 3];
+    for (ratio, value) in ratios.iter_mut().zip(entity.get_list(0)?) {
+        *ratio = match value {
+            IfcValue::Real(v) => *v,
+            IfcValue::Integer(v) => *v as f64,
+            _ => return None,
+        };
+    }
+    let length = (ratios[0] * ratios[0] + ratios[1] * ratios[1] + ratios[2] * ratios[2]).sqrt();
+    (length > f64::EPSILON).then(|| ratios.map(|r| r / length))
+}
+
+/// Euclidean distance between two world points
+pub fn distance(a: WorldPoint, b: WorldPoint) -> f64 {
+    let dx = b[0] - a[0];
+    let dy = b[1] - a[1];
+    let dz = b[2] - a[2];
+    (dx * dx + dy * dy + dz * dz).sqrt()
+}
+
+/// Total length of a polyline through the points
+pub fn polyline_length(points: &[WorldPoint]) -> f64 {
+    points.windows(2).map(|w| distance(w[0], w[1])).sum()
+}
+
+/// Polygon area projected to the XY plane (shoelace formula)
+///
+/// Coordinates are taken relative to the first point so large world offsets
+/// do not cancel out the significant digits.
+pub fn polygon_area_xy(points: &[WorldPoint]) -> f64 {
+    let Some(&first) = points.first() else {
+        return 0.0;
+    };
+
+    let mut area = 0.0;
+    for i in 0..points.len() {
+        let j = (i + 1) % points.len();
+        let (xi, yi) = (points[i][0] - first[0], points[i][1] - first[1]);
+        let (xj, yj) = (points[j][0] - first[0], points[j][1] - first[1]);
+        area += xi * yj - xj * yi;
+    }
+    (area / 2.0).abs()
+}
+
+/// Points further than this (meters) from their best-fit plane make a
+/// polyline non-planar, so no enclosed area is reported
+pub const COPLANAR_TOLERANCE: f64 = 0.01;
+
+/// A polyline is closed when its last point is this close to its first
+pub const CLOSE_TOLERANCE: f64 = 0.001;
+
+/// Lengths (and, when planar, enclosed area) of a measured polyline
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PolylineMeasurement {
+    /// Sum of the segment lengths
+    pub total_length: f64,
+    /// Length of each segment between consecutive points
+    pub segment_lengths: Vec<f64>,
+    /// Whether the last point returns to the first
+    pub closed: bool,
+    /// Area enclosed by the points (treated as a closed polygon), if they
+    /// are roughly coplanar
+    pub area: Option<f64>,
+}
+
+/// Measure a polyline through world points
+///
+/// The area is the shoelace formula generalized to the polygon's own plane
+/// (half the Newell normal's length), so it works on walls and slopes as
+/// well as floors.
+pub fn measure_polyline(points: &[WorldPoint]) -> PolylineMeasurement {
+    let segment_lengths: Vec<f64> = points.windows(2).map(|w| distance(w[0], w[1])).collect();
+    let closed = points.len() > 2 && distance(points[0], points[points.len() - 1]) <= CLOSE_TOLERANCE;
+
+    // A closing point repeats the first corner
+    let corners = if closed { &points[..points.len() - 1] } else { points };
+
+    PolylineMeasurement {
+        total_length: segment_lengths.iter().sum(),
+        segment_lengths,
+        closed,
+        area: planar_polygon_area(corners),
+    }
+}
+
+/// Area of a polygon in its own plane, or None if it has fewer than three
+/// corners, no area, or corners off the plane
+fn planar_polygon_area(points: &[WorldPoint]) -> Option<f64> {
+    let &first = points.first()?;
+    if points.len() < 3 {
+        return None;
+    }
+
+    // Relative to the first point so large world offsets keep their precision
+    let relative: Vec<[f64; 3]> = points
+        .iter()
+        .map(|p| [p[0] - first[0], p[1] - first[1], p[2] - first[2]])
+        .collect();
+
+    // Newell normal: twice the area vector of the polygon
+    let mut normal = [0.0; 3];
+    for i in 0..relative.len() {
+        let (a, b) = (relative[i], relative[(i + 1) % relative.len()]);
+        normal[0] += (a[1] - b[1]) * (a[2] + b[2]);
+        normal[1] += (a[2] - b[2]) * (a[0] + b[0]);
+        normal[2] += (a[0] - b[0]) * (a[1] + b[1]);
+    }
+    let length = (normal[0] * normal[0] + normal[1] * normal[1] + normal[2] * normal[2]).sqrt();
+    if length < f64::EPSILON {
+        return None;
+    }
+
+    // Distance of each point from the plane through the centroid
+    let n = normal.map(|c| c / length);
+    let count = relative.len() as f64;
+    let centroid_offset = relative.iter().map(|p| p[0] * n[0] + p[1] * n[1] + p[2] * n[2]).sum::<f64>() / count;
+    let planar = relative
+        .iter()
+        .all(|p| (p[0] * n[0] + p[1] * n[1] + p[2] * n[2] - centroid_offset).abs() <= COPLANAR_TOLERANCE);
+
+    planar.then_some(length / 2.0)
+}
+
+/// Volume of the axis-aligned box enclosing the points
+pub fn bounding_volume(points: &[WorldPoint]) -> f64 {
+    if points.is_empty() {
+        return 0.0;
+    }
+
+    let mut min = [f64::MAX; 3];
+    let mut max = [f64::MIN; 3];
+    for p in points {
+        for k in 0..3 {
+            min[k] = min[k].min(p[k]);
+            max[k] = max[k].max(p[k]);
+        }
+    }
+    (max[0] - min[0]) * (max[1] - min[1]) * (max[2] - min[2])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_georeferenced_distance_keeps_millimeters() {
+        let a = [2_000_000.123, 0.0, 0.0];
+        let b = [2_000_010.124, 0.0, 0.0];
+        assert!((distance(a, b) - 10.001).abs() < 1e-3);
+
+        // Round-tripping through a local origin keeps the precision too
+        let origin = LocalOrigin::new([2_000_000.0, 0.0, 0.0]);
+        let local_a = origin.to_local(a);
+        let local_b = origin.to_local(b);
+        let measured = distance(origin.to_world(local_a), origin.to_world(local_b));
+        assert!((measured - 10.001).abs() < 1e-3);
+    }
+
+    #[test]
+    fn test_placement_chain_is_summed_in_f64() {
+        let content = r#"ISO-10303-21;
+HEADER;
+FILE_DESCRIPTION(('ViewDefinition'),'2;1');
+FILE_NAME('geo.ifc','2024-01-01',(''),(''),'','','');
+FILE_SCHEMA(('IFC4'));
+ENDSEC;
+DATA;
+#1=IFCCARTESIANPOINT((2000000.,5000000.,10.));
+#2=IFCAXIS2PLACEMENT3D(#1,$,$);
+#3=IFCLOCALPLACEMENT($,#2);
+#4=IFCCARTESIANPOINT((0.123,1.5,0.));
+#5=IFCAXIS2PLACEMENT3D(#4,$,$);
+#6=IFCLOCALPLACEMENT(#3,#5);
+ENDSEC;
+END-ISO-10303-21;
+"#;
+        let file = IfcFile::parse(content).unwrap();
+        let location = placement_location(&file, Some(6)).unwrap();
+        assert!((location[0] - 2_000_000.123).abs() < 1e-9);
+        assert!((location[1] - 5_000_001.5).abs() < 1e-9);
+        assert!((location[2] - 10.0).abs() < 1e-9);
+        assert_eq!(placement_location(&file, None), None);
+    }
+
+    #[test]
+    fn test_placement_matrix_composes_rotated_parent() {
+        // The child's X offset runs along the parent's Y (rotated 90° about Z)
+        let content = "ISO-10303-21;
+HEADER;
+FILE_SCHEMA(('IFC4'));
+ENDSEC;
+DATA;
+#1=IFCCARTESIANPOINT((100.,0.,3.));
+#2=IFCDIRECTION((0.,0.,1.));
+#3=IFCDIRECTION((0.,1.,0.));
+#4=IFCAXIS2PLACEMENT3D(#1,#2,#3);
+#5=IFCLOCALPLACEMENT($,#4);
+#6=IFCCARTESIANPOINT((2.,0.,0.));
+#7=IFCAXIS2PLACEMENT3D(#6,$,$);
+#8=IFCLOCALPLACEMENT(#5,#7);
+ENDSEC;
+END-ISO-10303-21;
+";
+        let file = IfcFile::parse(content).unwrap();
+        let matrix = placement_matrix(&file, Some(8)).unwrap();
+        let point = matrix.transform_point3(DVec3::new(1.0, 0.0, 0.0));
+        assert!((point - DVec3::new(100.0, 3.0, 3.0)).length() < 1e-9, "{}", point);
+        assert_eq!(placement_matrix(&file, Some(4)), None);
+        assert_eq!(placement_matrix(&file, None), None);
+    }
+
+    #[test]
+    fn test_z_up_point_maps_to_y_up() {
+        // 3m up and 2m north in IFC becomes 3m up and 2m "into" the screen
+        assert_eq!(UpAxis::Z.to_y_up([1.0, 2.0, 3.0]), [1.0, 3.0, -2.0]);
+        assert_eq!(UpAxis::Z.from_y_up([1.0, 3.0, -2.0]), [1.0, 2.0, 3.0]);
+        assert_eq!(UpAxis::Y.to_y_up([1.0, 2.0, 3.0]), [1.0, 2.0, 3.0]);
+    }
+
+    #[test]
+    fn test_closed_square_polyline_perimeter_and_area() {
+        // A 3 m square on a sloped plane far from the origin, back to the start
+        let base = [2_000_000.0, 1_000_000.0, 50.0];
+        let corner = |u: f64, v: f64| [base[0] + u, base[1] + v * 0.6, base[2] + v * 0.8];
+        let points = [
+            corner(0.0, 0.0),
+            corner(3.0, 0.0),
+            corner(3.0, 3.0),
+            corner(0.0, 3.0),
+            corner(0.0, 0.0),
+        ];
+
+        let measurement = measure_polyline(&points);
+        assert!(measurement.closed);
+        assert_eq!(measurement.segment_lengths.len(), 4);
+        for length in &measurement.segment_lengths {
+            assert!((length - 3.0).abs() < 1e-6);
+        }
+        assert!((measurement.total_length - 12.0).abs() < 1e-6);
+        assert!((measurement.area.unwrap() - 9.0).abs() < 1e-6);
+
+        // Lifting one corner off the plane drops the area
+        let mut bent = points;
+        bent[2][2] += 0.5;
+        assert_eq!(measure_polyline(&bent).area, None);
+
+        // Open and too-short polylines
+        let open = measure_polyline(&points[..3]);
+        assert!(!open.closed);
+        assert!((open.total_length - 6.0).abs() < 1e-6);
+        assert_eq!(measure_polyline(&points[..2]).area, None);
+    }
+}
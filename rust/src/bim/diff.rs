@@ -0,0 +1,235 @@
+//! Model Comparison
+//!
+//! Compares two revisions of a model element by element, matching elements
+//! by GlobalId. Geometry changes are detected from the element's placement
+//! and a hash of its tessellated body; attribute changes from its type,
+//! names, properties and material. A diff can be shown by recoloring the
+//! generated meshes of both revisions by change status.
+
+use super::coordinates::WorldPoint;
+use super::entities::IfcProduct;
+use super::geometry::Mesh;
+use super::material::MaterialInfo;
+use super::model::{BimModel, ModelMesh};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::hash::{Hash, Hasher};
+
+/// Coordinates closer than this are considered unchanged
+const GEOMETRY_TOLERANCE: f64 = 1e-4;
+
+/// Color of elements added in the new revision (RGBA)
+pub const ADDED_COLOR: [f32; 4] = [0.2, 0.8, 0.2, 1.0];
+
+/// Color of elements modified between revisions (RGBA)
+pub const MODIFIED_COLOR: [f32; 4] = [0.95, 0.85, 0.1, 1.0];
+
+/// Color of elements removed from the old revision, ghosted (RGBA)
+pub const REMOVED_COLOR: [f32; 4] = [0.9, 0.15, 0.15, 0.35];
+
+/// Elements that differ between two models, as sorted GlobalId lists
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ModelDiff {
+    /// In the second model only
+    pub added: Vec<String>,
+    /// In the first model only
+    pub removed: Vec<String>,
+    /// In both, with different geometry or attributes
+    pub modified: Vec<String>,
+}
+
+impl ModelDiff {
+    /// Whether the models have the same elements
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.modified.is_empty()
+    }
+}
+
+impl ModelMesh {
+    /// Recolor elements by their change status in `diff`
+    ///
+    /// A mesh containing removed elements is taken to be the old revision:
+    /// only its removed elements are kept (ghosted), so that elements still
+    /// present are drawn once, from the new revision.
+    pub fn apply_diff(&mut self, diff: &ModelDiff) {
+        let removed: HashSet<&str> = diff.removed.iter().map(String::as_str).collect();
+        if self.elements.iter().any(|e| removed.contains(e.global_id.as_str())) {
+            self.retain_elements(|e| removed.contains(e.global_id.as_str()));
+        }
+
+        let added: HashSet<&str> = diff.added.iter().map(String::as_str).collect();
+        let modified: HashSet<&str> = diff.modified.iter().map(String::as_str).collect();
+        for element in &self.elements {
+            let id = element.global_id.as_str();
+            let color = if added.contains(id) {
+                ADDED_COLOR
+            } else if modified.contains(id) {
+                MODIFIED_COLOR
+            } else if removed.contains(id) {
+                REMOVED_COLOR
+            } else {
+                continue;
+            };
+            let start = (element.triangle_start as usize * 3).min(self.indices.len());
+            let end = (start + element.triangle_count as usize * 3).min(self.indices.len());
+            for &index in &self.indices[start..end] {
+                let v = index as usize * 4;
+                if let Some(c) = self.colors.get_mut(v..v + 4) {
+                    c.copy_from_slice(&color);
+                }
+            }
+        }
+    }
+}
+
+/// What is compared for one element
+#[derive(Debug, PartialEq)]
+struct ElementSignature<'a> {
+    element_type: &'static str,
+    name: &'a Option<String>,
+    description: &'a Option<String>,
+    object_type: &'a Option<String>,
+    properties: &'a HashMap<String, String>,
+    material: Option<&'a MaterialInfo>,
+    geometry: u64,
+}
+
+fn signatures(model: &BimModel) -> HashMap<&str, ElementSignature<'_>> {
+    model
+        .products()
+        .into_iter()
+        .map(|(element_type, product)| {
+            let signature = ElementSignature {
+                element_type,
+                name: &product.name,
+                description: &product.description,
+                object_type: &product.object_type,
+                properties: &product.properties,
+                material: model.materials.get(&product.global_id),
+                geometry: geometry_hash(product, model.body_meshes.get(&product.global_id), model.origin.offset),
+            };
+            (product.global_id.as_str(), signature)
+        })
+        .collect()
+}
+
+/// Hash of an element's placement and body mesh, at `GEOMETRY_TOLERANCE`
+/// (the body in world coordinates, so a model origin that moved between
+/// versions changes nothing)
+fn geometry_hash(product: &IfcProduct, body: Option<&Mesh>, origin: WorldPoint) -> u64 {
+    let quantize = |v: f64| (v / GEOMETRY_TOLERANCE).round() as i64;
+    let mut hasher = std::collections::hash_map::DefaultHasher::new();
+    product.location.map(|l| l.map(quantize)).hash(&mut hasher);
+    if let Some(mesh) = body {
+        for p in mesh.vertices.chunks_exact(3) {
+            for (&v, offset) in p.iter().zip(origin) {
+                quantize(v as f64 + offset).hash(&mut hasher);
+            }
+        }
+        mesh.indices.hash(&mut hasher);
+    }
+    hasher.finish()
+}
+
+/// Compare two models: elements of `b` that are new, gone or changed
+/// relative to `a`
+pub fn compare_models(a: &BimModel, b: &BimModel) -> ModelDiff {
+    let before = signatures(a);
+    let after = signatures(b);
+
+    let mut diff = ModelDiff::default();
+    for (global_id, signature) in &after {
+        match before.get(global_id) {
+            None => diff.added.push(global_id.to_string()),
+            Some(old) if old != signature => diff.modified.push(global_id.to_string()),
+            Some(_) => {}
+        }
+    }
+    let after_ids: HashSet<&str> = after.keys().copied().collect();
+    diff.removed = before
+        .keys()
+        .filter(|id| !after_ids.contains(*id))
+        .map(|id| id.to_string())
+        .collect();
+
+    diff.added.sort();
+    diff.removed.sort();
+    diff.modified.sort();
+    diff
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bim::IfcFile;
+
+    const REVISION_A: &str = "ISO-10303-21;
+HEADER;
+FILE_SCHEMA(('IFC4'));
+ENDSEC;
+DATA;
+#1=IFCWALL('0aaaaaaaaaaaaaaaaaaaaa',$,'Wall A',$,$,$,$,$);
+#2=IFCWALL('0bbbbbbbbbbbbbbbbbbbbb',$,'Wall B',$,$,$,$,$);
+#3=IFCSLAB('0ccccccccccccccccccccc',$,'Floor',$,$,$,$,$);
+ENDSEC;
+END-ISO-10303-21;";
+
+    #[test]
+    fn test_added_and_removed_elements() {
+        let a = BimModel::from_ifc_file(&IfcFile::parse(REVISION_A).unwrap()).unwrap();
+        assert!(compare_models(&a, &a).is_empty());
+
+        // Wall B removed, a column added
+        let revision_b = REVISION_A
+            .replace("#2=IFCWALL('0bbbbbbbbbbbbbbbbbbbbb',$,'Wall B',$,$,$,$,$);", "")
+            .replace("ENDSEC;\nEND", "#4=IFCCOLUMN('0ddddddddddddddddddddd',$,'Column',$,$,$,$,$);\nENDSEC;\nEND");
+        let b = BimModel::from_ifc_file(&IfcFile::parse(&revision_b).unwrap()).unwrap();
+
+        let diff = compare_models(&a, &b);
+        assert_eq!(diff.added, vec!["0ddddddddddddddddddddd".to_string()]);
+        assert_eq!(diff.removed, vec!["0bbbbbbbbbbbbbbbbbbbbb".to_string()]);
+        assert!(diff.modified.is_empty());
+
+        // A renamed element is modified
+        let renamed = REVISION_A.replace("'Floor'", "'Ground Floor'");
+        let c = BimModel::from_ifc_file(&IfcFile::parse(&renamed).unwrap()).unwrap();
+        let diff = compare_models(&a, &c);
+        assert_eq!(diff.modified, vec!["0ccccccccccccccccccccc".to_string()]);
+        assert!(diff.added.is_empty() && diff.removed.is_empty());
+    }
+
+    #[test]
+    fn test_diff_colors_added_green_and_keep_only_removed_from_old() {
+        let a = BimModel::from_ifc_file(&IfcFile::parse(REVISION_A).unwrap()).unwrap();
+        let revision_b = REVISION_A
+            .replace("#2=IFCWALL('0bbbbbbbbbbbbbbbbbbbbb',$,'Wall B',$,$,$,$,$);", "")
+            .replace("ENDSEC;\nEND", "#4=IFCCOLUMN('0ddddddddddddddddddddd',$,'Column',$,$,$,$,$);\nENDSEC;\nEND");
+        let b = BimModel::from_ifc_file(&IfcFile::parse(&revision_b).unwrap()).unwrap();
+        let diff = compare_models(&a, &b);
+
+        let vertex_colors = |mesh: &ModelMesh, global_id: &str| -> Vec<[f32; 4]> {
+            let element = mesh.elements.iter().find(|e| e.global_id == global_id).unwrap();
+            let start = element.triangle_start as usize * 3;
+            mesh.indices[start..start + element.triangle_count as usize * 3]
+                .iter()
+                .map(|&i| mesh.colors[i as usize * 4..i as usize * 4 + 4].try_into().unwrap())
+                .collect()
+        };
+
+        // New revision: the added column is green, the rest keeps its colors
+        let mut new_mesh = b.generate_meshes();
+        let wall_before = vertex_colors(&new_mesh, "0aaaaaaaaaaaaaaaaaaaaa");
+        new_mesh.apply_diff(&diff);
+        assert_eq!(new_mesh.elements.len(), 3);
+        assert!(vertex_colors(&new_mesh, "0ddddddddddddddddddddd").iter().all(|c| *c == ADDED_COLOR));
+        assert_eq!(vertex_colors(&new_mesh, "0aaaaaaaaaaaaaaaaaaaaa"), wall_before);
+
+        // Old revision: only the removed wall remains, ghosted red
+        let mut old_mesh = a.generate_meshes();
+        old_mesh.apply_diff(&diff);
+        assert_eq!(old_mesh.elements.len(), 1);
+        assert_eq!(old_mesh.elements[0].triangle_start, 0);
+        assert_eq!(old_mesh.indices.len(), old_mesh.elements[0].triangle_count as usize * 3);
+        assert!(vertex_colors(&old_mesh, "0bbbbbbbbbbbbbbbbbbbbb").iter().all(|c| *c == REMOVED_COLOR));
+    }
+}
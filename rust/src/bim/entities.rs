@@ -18,10 +18,13 @@ pub struct IfcEntity {
 }
 
 /// IFC Value - Represents any value in IFC files
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 #[serde(untagged)]
 pub enum IfcValue {
+    #[default]
     Null,
+    /// `*`: the value is derived by the schema, not stored
+    Derived,
     Integer(i64),
     Real(f64),
     String(String),
@@ -40,6 +43,12 @@ pub struct IfcProduct {
     pub description: Option<String>,
     pub object_type: Option<String>,
     pub properties: HashMap<String, String>,
+    /// World location of the ObjectPlacement (full precision)
+    #[serde(default)]
+    pub location: Option<[f64; 3]>,
+    /// Presentation layer (IfcPresentationLayerAssignment) of its geometry
+    #[serde(default)]
+    pub layer: Option<String>,
 }
 
 /// IFC Wall
@@ -86,6 +95,16 @@ pub struct IfcWindow {
     pub overall_width: Option<f64>,
 }
 
+/// IFC Opening Element (void cut into a host element)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IfcOpeningElement {
+    pub product: IfcProduct,
+    /// GlobalId of the element the opening is cut from (IfcRelVoidsElement)
+    pub host_global_id: Option<String>,
+    /// Door or window filling the opening (IfcRelFillsElement)
+    pub filling: Option<EntityId>,
+}
+
 /// IFC Roof
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct IfcRoof {
@@ -178,6 +197,15 @@ pub struct IfcBuildingStorey {
     pub elevation: Option<f64>,
 }
 
+/// The storey an element belongs to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StoreyAssignment {
+    pub storey_id: EntityId,
+    /// Guessed from the element's elevation because the file has no
+    /// IfcRelContainedInSpatialStructure link for it
+    pub heuristic: bool,
+}
+
 /// IFC Building
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct IfcBuilding {
@@ -261,10 +289,79 @@ impl IfcEntity {
             _ => None,
         }
     }
+
+    /// Get the entity references contained in a list attribute
+    /// (non-reference items are skipped, missing lists yield an empty Vec)
+    pub fn get_ref_list(&self, index: usize) -> Vec<EntityId> {
+        self.get_list(index)
+            .map(|list| {
+                list.iter()
+                    .filter_map(|v| match v {
+                        IfcValue::EntityRef(id) => Some(*id),
+                        _ => None,
+                    })
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    /// The attributes as strings, for inspecting the raw entity
+    pub fn attribute_views(&self) -> Vec<AttributeView> {
+        self.attributes
+            .iter()
+            .enumerate()
+            .map(|(index, value)| AttributeView {
+                index: index as u32,
+                kind: value.kind().to_string(),
+                value: value.to_step(),
+            })
+            .collect()
+    }
 }
 
-impl Default for IfcValue {
-    fn default() -> Self {
-        IfcValue::Null
+impl IfcValue {
+    /// Name of the value's kind ("String", "EntityRef", ...)
+    pub fn kind(&self) -> &'static str {
+        match self {
+            IfcValue::Null => "Null",
+            IfcValue::Derived => "Derived",
+            IfcValue::Integer(_) => "Integer",
+            IfcValue::Real(_) => "Real",
+            IfcValue::String(_) => "String",
+            IfcValue::Enum(_) => "Enum",
+            IfcValue::Boolean(_) => "Boolean",
+            IfcValue::EntityRef(_) => "EntityRef",
+            IfcValue::List(_) => "List",
+        }
+    }
+
+    /// The value as written in a STEP file: `'text'`, `.ENUM.`, `#12`,
+    /// `(1.,#3)`, `$` or `*` (typed values lose their type name when parsed)
+    pub fn to_step(&self) -> String {
+        match self {
+            IfcValue::Null => "$".to_string(),
+            IfcValue::Derived => "*".to_string(),
+            IfcValue::Integer(i) => i.to_string(),
+            IfcValue::Real(r) if r.fract() == 0.0 => format!("{}.", r),
+            IfcValue::Real(r) => r.to_string(),
+            IfcValue::String(s) => format!("'{}'", s.replace('\'', "''")),
+            IfcValue::Enum(e) => format!(".{}.", e),
+            IfcValue::Boolean(b) => if *b { ".T." } else { ".F." }.to_string(),
+            IfcValue::EntityRef(id) => format!("#{}", id),
+            IfcValue::List(items) => {
+                format!("({})", items.iter().map(IfcValue::to_step).collect::<Vec<_>>().join(","))
+            }
+        }
     }
 }
+
+/// One attribute of a raw entity, for an inspector
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AttributeView {
+    /// Position in the entity's attribute list
+    pub index: u32,
+    /// Kind of value (see `IfcValue::kind`)
+    pub kind: String,
+    /// The value in STEP syntax (see `IfcValue::to_step`)
+    pub value: String,
+}
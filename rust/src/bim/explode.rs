@@ -0,0 +1,151 @@
+//! Exploded Views
+//!
+//! Separates elements of a generated model mesh for inspection by offsetting
+//! each element's vertices away from the model center (or by storey level).
+
+use super::geometry::BoundingBox;
+use super::model::{ElementInfo, ModelMesh};
+use glam::Vec3;
+
+/// How elements are pushed apart in an exploded view
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ExplodeMode {
+    /// Spread along the vertical (Y) axis from the model center height
+    #[default]
+    Vertical,
+    /// Spread outwards in all directions from the model center
+    Radial,
+    /// Lift whole storeys apart by their elevation above the lowest storey
+    ByStorey,
+}
+
+/// Offset of each element for an explode factor (0 = assembled model)
+///
+/// `storey_elevations` are used by `ByStorey`: each element belongs to the
+/// highest storey at or below its base. Without storeys, each element's own
+/// base height is used instead.
+pub fn explode_offsets(
+    elements: &[ElementInfo],
+    model_bounds: &BoundingBox,
+    storey_elevations: &[f32],
+    factor: f32,
+    mode: ExplodeMode,
+) -> Vec<[f32; 3]> {
+    if factor == 0.0 {
+        return vec![[0.0; 3]; elements.len()];
+    }
+
+    let model_center = Vec3::from_array(model_bounds.center());
+    let mut levels: Vec<f32> = storey_elevations.to_vec();
+    levels.sort_by(f32::total_cmp);
+
+    elements
+        .iter()
+        .map(|element| {
+            let center = Vec3::from_array(element.bounds.center());
+            let offset = match mode {
+                ExplodeMode::Vertical => Vec3::new(0.0, center.y - model_center.y, 0.0),
+                ExplodeMode::Radial => center - model_center,
+                ExplodeMode::ByStorey => {
+                    let base = element.bounds.min[1];
+                    let elevation = match levels.first() {
+                        Some(&lowest) => {
+                            let storey = levels
+                                .iter()
+                                .rev()
+                                .find(|&&level| level <= base + 1e-3)
+                                .copied()
+                                .unwrap_or(lowest);
+                            storey - lowest
+                        }
+                        None => base - model_bounds.min[1],
+                    };
+                    Vec3::new(0.0, elevation, 0.0)
+                }
+            };
+            (offset * factor).to_array()
+        })
+        .collect()
+}
+
+impl ModelMesh {
+    /// Offset every element's vertices (and bounds) for an exploded view
+    pub fn apply_explode(&mut self, factor: f32, mode: ExplodeMode, storey_elevations: &[f32]) {
+        let Some(bounds) = self.bounds else {
+            return;
+        };
+        if factor == 0.0 {
+            return;
+        }
+
+        let offsets = explode_offsets(&self.elements, &bounds, storey_elevations, factor, mode);
+        let mut moved = vec![false; self.vertices.len() / 3];
+
+        for (element, offset) in self.elements.iter_mut().zip(&offsets) {
+            let start = element.triangle_start as usize * 3;
+            let end = (start + element.triangle_count as usize * 3).min(self.indices.len());
+
+            for &index in &self.indices[start..end] {
+                let v = index as usize;
+                if v >= moved.len() || moved[v] {
+                    continue;
+                }
+                moved[v] = true;
+                for (coord, delta) in self.vertices[v * 3..v * 3 + 3].iter_mut().zip(offset) {
+                    *coord += delta;
+                }
+            }
+
+            for (k, delta) in offset.iter().enumerate() {
+                element.bounds.min[k] += delta;
+                element.bounds.max[k] += delta;
+            }
+        }
+
+        self.bounds = self
+            .elements
+            .iter()
+            .map(|e| e.bounds)
+            .reduce(|a, b| a.union(&b))
+            .or(self.bounds);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bim::model::BimModel;
+
+    #[test]
+    fn test_factor_zero_leaves_positions_unchanged() {
+        let model = BimModel::new();
+        let original = model.generate_meshes();
+
+        for mode in [ExplodeMode::Vertical, ExplodeMode::Radial, ExplodeMode::ByStorey] {
+            let mut mesh = original.clone();
+            mesh.apply_explode(0.0, mode, &[0.0, 3.0]);
+            assert_eq!(mesh.vertices, original.vertices);
+        }
+    }
+
+    #[test]
+    fn test_explode_moves_elements_apart() {
+        // Default building: floor slab, four walls and a roof
+        let original = BimModel::new().generate_meshes();
+        let roof_before = original.elements.iter().find(|e| e.name == "Roof").unwrap().bounds;
+
+        let mut mesh = original.clone();
+        mesh.apply_explode(1.0, ExplodeMode::Vertical, &[]);
+        let roof_after = mesh.elements.iter().find(|e| e.name == "Roof").unwrap().bounds;
+        assert!(roof_after.min[1] > roof_before.min[1]);
+        assert_eq!(mesh.vertices.len(), original.vertices.len());
+
+        // Storeys: everything based at or above 3.0 lifts by (3.0 - 0.0) * 2
+        let mut mesh = original.clone();
+        mesh.apply_explode(2.0, ExplodeMode::ByStorey, &[3.0, 0.0]);
+        let roof_after = mesh.elements.iter().find(|e| e.name == "Roof").unwrap().bounds;
+        assert!((roof_after.min[1] - roof_before.min[1] - 6.0).abs() < 1e-5);
+        let slab_after = mesh.elements.iter().find(|e| e.name == "Floor").unwrap().bounds;
+        assert_eq!(slab_after.min, original.elements[0].bounds.min);
+    }
+}
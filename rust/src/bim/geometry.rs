@@ -2,7 +2,10 @@
 //!
 //! Converts IFC geometry representations to triangle meshes.
 
+use super::topology::{face_normal, MeshTopology};
 use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::sync::OnceLock;
 
 /// 3D Point
 pub type Point3D = [f32; 3];
@@ -24,8 +27,53 @@ pub struct Mesh {
 
     /// Vertex colors (r, g, b, a)
     pub colors: Vec<f32>,
+
+    /// Material regions (empty = the whole mesh uses its vertex colors as is)
+    #[serde(default)]
+    pub submeshes: Vec<SubMesh>,
+
+    /// Adjacency, built on first use
+    #[serde(skip)]
+    topology: OnceLock<MeshTopology>,
+}
+
+/// A range of a mesh's indices drawn with one material
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SubMesh {
+    /// First index of the range
+    pub index_offset: u32,
+    /// Number of indices (a multiple of 3)
+    pub index_count: u32,
+    /// Position of the material in the element's material region list
+    pub material_id: u32,
+}
+
+/// Problems found in a triangle mesh by [`validate_mesh`]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MeshReport {
+    /// Triangles with zero area, repeated or out-of-range indices
+    pub degenerate_tris: usize,
+    /// Vertices with a NaN or infinite coordinate
+    pub nan_vertices: usize,
+    /// Vertices not referenced by any triangle
+    pub unreferenced_vertices: usize,
+    /// Edges shared by more than two triangles
+    pub non_manifold_edges: usize,
+}
+
+impl MeshReport {
+    /// Whether no problems were found
+    pub fn is_clean(&self) -> bool {
+        *self == Self::default()
+    }
 }
 
+/// Triangles with an area at or below this are considered degenerate
+const DEGENERATE_AREA_EPSILON: f32 = 1e-12;
+
+/// Default dihedral angle (degrees) above which an edge counts as a feature edge
+pub const DEFAULT_FEATURE_ANGLE_DEG: f32 = 30.0;
+
 /// Bounding box
 #[derive(Debug, Clone, Copy, Serialize, Deserialize)]
 pub struct BoundingBox {
@@ -41,6 +89,8 @@ impl Mesh {
             indices: Vec::new(),
             normals: Vec::new(),
             colors: Vec::new(),
+            submeshes: Vec::new(),
+            topology: OnceLock::new(),
         }
     }
 
@@ -54,6 +104,14 @@ impl Mesh {
         self.indices.len() / 3
     }
 
+    /// Bytes held by the vertex and index arrays
+    pub fn heap_bytes(&self) -> usize {
+        let floats = self.vertices.capacity() + self.normals.capacity() + self.colors.capacity();
+        floats * size_of::<f32>()
+            + self.indices.capacity() * size_of::<u32>()
+            + self.submeshes.capacity() * size_of::<SubMesh>()
+    }
+
     /// Calculate bounding box
     pub fn bounding_box(&self) -> Option<BoundingBox> {
         if self.vertices.is_empty() {
@@ -82,6 +140,7 @@ impl Mesh {
 
     /// Add a vertex
     pub fn add_vertex(&mut self, x: f32, y: f32, z: f32) {
+        self.invalidate_topology();
         self.vertices.push(x);
         self.vertices.push(y);
         self.vertices.push(z);
@@ -104,10 +163,533 @@ impl Mesh {
 
     /// Add a triangle
     pub fn add_triangle(&mut self, i0: u32, i1: u32, i2: u32) {
+        self.invalidate_topology();
         self.indices.push(i0);
         self.indices.push(i1);
         self.indices.push(i2);
     }
+
+    /// Make the whole mesh one submesh drawn with material `material_id`
+    pub fn set_material(&mut self, material_id: u32) {
+        self.submeshes = vec![SubMesh {
+            index_offset: 0,
+            index_count: self.indices.len() as u32,
+            material_id,
+        }];
+    }
+
+    /// Color each submesh's vertices with its material (`colors` indexed by
+    /// material id; ids past the end take the first)
+    ///
+    /// A mesh without submeshes becomes one submesh of material 0. Vertices
+    /// must not be shared across submeshes, which holds for meshes merged
+    /// from separately tessellated items.
+    pub fn assign_materials(&mut self, colors: &[[f32; 4]]) {
+        if colors.is_empty() {
+            return;
+        }
+        if self.submeshes.is_empty() {
+            self.set_material(0);
+        }
+
+        for submesh in &self.submeshes {
+            let color = colors.get(submesh.material_id as usize).unwrap_or(&colors[0]);
+            let start = submesh.index_offset as usize;
+            let end = start + submesh.index_count as usize;
+            for &index in &self.indices[start..end] {
+                let v = index as usize * 4;
+                if let Some(c) = self.colors.get_mut(v..v + 4) {
+                    c.copy_from_slice(color);
+                }
+            }
+        }
+    }
+
+    /// Enclosed volume (divergence theorem; meaningful for closed,
+    /// consistently wound meshes)
+    pub fn volume(&self) -> f32 {
+        let position = |i: u32| {
+            let p = &self.vertices[i as usize * 3..i as usize * 3 + 3];
+            glam::Vec3::new(p[0], p[1], p[2])
+        };
+        let signed: f32 = self
+            .indices
+            .chunks_exact(3)
+            .map(|tri| position(tri[0]).dot(position(tri[1]).cross(position(tri[2]))))
+            .sum();
+        signed / 6.0
+    }
+
+    /// Check the mesh for degenerate or invalid geometry (see [`validate_mesh`])
+    pub fn validate(&self) -> MeshReport {
+        validate_mesh(&self.vertices, &self.indices)
+    }
+
+    /// Remove degenerate triangles and triangles touching NaN vertices, then
+    /// drop vertices no longer referenced
+    ///
+    /// Submesh ranges are shrunk to match. Returns the number of triangles removed.
+    pub fn repair(&mut self) -> usize {
+        let vertex_count = self.vertex_count();
+        let finite: Vec<bool> = self
+            .vertices
+            .chunks_exact(3)
+            .map(|p| p.iter().all(|c| c.is_finite()))
+            .collect();
+
+        // Keep triangles that are valid, tracking how many survive per submesh
+        let mut kept_indices = Vec::with_capacity(self.indices.len());
+        let mut kept_per_triangle = Vec::with_capacity(self.triangle_count());
+        for tri in self.indices.chunks_exact(3) {
+            let keep = tri.iter().all(|&i| (i as usize) < vertex_count && finite[i as usize])
+                && !is_degenerate_triangle(&self.vertices, [tri[0], tri[1], tri[2]]);
+            if keep {
+                kept_indices.extend_from_slice(tri);
+            }
+            kept_per_triangle.push(keep);
+        }
+        let removed = kept_per_triangle.iter().filter(|&&k| !k).count();
+
+        let mut offset = 0;
+        for submesh in &mut self.submeshes {
+            let first = submesh.index_offset as usize / 3;
+            let last = (first + submesh.index_count as usize / 3).min(kept_per_triangle.len());
+            let kept = kept_per_triangle[first.min(last)..last].iter().filter(|&&k| k).count() as u32;
+            submesh.index_offset = offset;
+            submesh.index_count = kept * 3;
+            offset += kept * 3;
+        }
+        self.submeshes.retain(|s| s.index_count > 0);
+
+        // Compact the vertex arrays to the referenced vertices
+        let mut remap = vec![u32::MAX; vertex_count];
+        let mut next = 0u32;
+        for &i in &kept_indices {
+            if remap[i as usize] == u32::MAX {
+                remap[i as usize] = next;
+                next += 1;
+            }
+        }
+        let compact = |data: &[f32], stride: usize| -> Vec<f32> {
+            let mut out = vec![0.0; next as usize * stride];
+            for (old, &new) in remap.iter().enumerate() {
+                if new != u32::MAX {
+                    if let Some(src) = data.get(old * stride..old * stride + stride) {
+                        out[new as usize * stride..new as usize * stride + stride].copy_from_slice(src);
+                    }
+                }
+            }
+            out
+        };
+        self.vertices = compact(&self.vertices, 3);
+        if !self.normals.is_empty() {
+            self.normals = compact(&self.normals, 3);
+        }
+        if !self.colors.is_empty() {
+            self.colors = compact(&self.colors, 4);
+        }
+        self.indices = kept_indices.into_iter().map(|i| remap[i as usize]).collect();
+        self.invalidate_topology();
+
+        removed
+    }
+
+    /// Get the mesh adjacency, building and caching it on first use
+    pub fn topology(&self) -> &MeshTopology {
+        self.topology
+            .get_or_init(|| MeshTopology::new(&self.vertices, &self.indices))
+    }
+
+    /// Drop the cached adjacency
+    ///
+    /// Needed after editing `vertices` or `indices` directly; the `add_*`
+    /// and `repair` methods do this themselves.
+    pub fn invalidate_topology(&mut self) {
+        self.topology = OnceLock::new();
+    }
+
+    /// Decimate to about `ratio` (0-1) of the triangles by collapsing the
+    /// shortest edges first
+    ///
+    /// Corners at the same position are welded so collapses don't open
+    /// cracks, and a collapse that would flip a triangle is skipped. Each
+    /// collapse merges one end of an edge into the other, so surviving
+    /// corners keep their positions. The result is flat shaded, keeps each
+    /// corner's color and has no material regions.
+    pub fn simplify(&self, ratio: f32) -> Mesh {
+        let vertex_count = self.vertex_count() as u32;
+        let position = |i: u32| glam::Vec3::from_slice(&self.vertices[i as usize * 3..i as usize * 3 + 3]);
+        let faces: Vec<[u32; 3]> = self
+            .indices
+            .chunks_exact(3)
+            .map(|t| [t[0], t[1], t[2]])
+            .filter(|tri| tri.iter().all(|&i| i < vertex_count))
+            .collect();
+        let target = (faces.len() as f32 * ratio.clamp(0.0, 1.0)).ceil() as usize;
+
+        // Corners welded by position; collapsed vertices point at the vertex
+        // they were merged into
+        let mut first_at: HashMap<[u32; 3], u32> = HashMap::new();
+        let weld: Vec<u32> = (0..vertex_count)
+            .map(|i| *first_at.entry(position(i).to_array().map(f32::to_bits)).or_insert(i))
+            .collect();
+        let mut merged_into: Vec<u32> = (0..vertex_count).collect();
+        fn root(merged_into: &[u32], mut v: u32) -> u32 {
+            while merged_into[v as usize] != v {
+                v = merged_into[v as usize];
+            }
+            v
+        }
+        let corners = |merged_into: &[u32], t: usize| faces[t].map(|c| root(merged_into, weld[c as usize]));
+        let degenerate = |c: [u32; 3]| c[0] == c[1] || c[1] == c[2] || c[2] == c[0];
+
+        let mut around: Vec<Vec<usize>> = vec![Vec::new(); vertex_count as usize];
+        let mut alive: Vec<bool> = (0..faces.len()).map(|t| !degenerate(corners(&merged_into, t))).collect();
+        let mut alive_count = alive.iter().filter(|&&a| a).count();
+        // Shortest edges first (lengths are non-negative, so their bits sort
+        // like the lengths)
+        let mut edges = std::collections::BinaryHeap::new();
+        for t in (0..faces.len()).filter(|&t| alive[t]) {
+            let c = corners(&merged_into, t);
+            for (k, &v) in c.iter().enumerate() {
+                around[v as usize].push(t);
+                let (a, b) = (v, c[(k + 1) % 3]);
+                edges.push(std::cmp::Reverse((position(a).distance(position(b)).to_bits(), a.min(b), a.max(b))));
+            }
+        }
+
+        while alive_count > target {
+            let Some(std::cmp::Reverse((length, a, b))) = edges.pop() else {
+                break;
+            };
+            let (keep, gone) = (root(&merged_into, a), root(&merged_into, b));
+            if keep == gone {
+                continue;
+            }
+            let current = position(keep).distance(position(gone)).to_bits();
+            if current != length {
+                // Ends were merged elsewhere since this edge was queued
+                edges.push(std::cmp::Reverse((current, keep.min(gone), keep.max(gone))));
+                continue;
+            }
+            let flips = around[gone as usize].iter().filter(|&&t| alive[t]).any(|&t| {
+                let before = corners(&merged_into, t);
+                if before.contains(&keep) {
+                    return false; // Collapses away
+                }
+                let after = before.map(|v| if v == gone { keep } else { v });
+                let normal = |c: [u32; 3]| (position(c[1]) - position(c[0])).cross(position(c[2]) - position(c[0]));
+                normal(before).dot(normal(after)) <= 0.0
+            });
+            if flips {
+                continue;
+            }
+
+            merged_into[gone as usize] = keep;
+            for t in std::mem::take(&mut around[gone as usize]) {
+                if !alive[t] {
+                    continue;
+                }
+                if degenerate(corners(&merged_into, t)) {
+                    alive[t] = false;
+                    alive_count -= 1;
+                } else {
+                    around[keep as usize].push(t);
+                }
+            }
+        }
+
+        let mut mesh = Mesh::new();
+        let has_colors = self.colors.len() == self.vertices.len() / 3 * 4;
+        for t in (0..faces.len()).filter(|&t| alive[t]) {
+            let c = corners(&merged_into, t);
+            let normal = face_normal(&self.vertices, c).unwrap_or(glam::Vec3::Z);
+            for (k, &v) in c.iter().enumerate() {
+                let p = position(v);
+                mesh.add_vertex(p.x, p.y, p.z);
+                mesh.add_normal(normal.x, normal.y, normal.z);
+                if has_colors {
+                    let i = faces[t][k] as usize * 4;
+                    mesh.colors.extend_from_slice(&self.colors[i..i + 4]);
+                }
+            }
+            let base = mesh.vertex_count() as u32 - 3;
+            mesh.add_triangle(base, base + 1, base + 2);
+        }
+        mesh
+    }
+
+    /// Triangles connected to `seed_triangle` across shared edges, sorted
+    /// (powers "select connected surface")
+    pub fn connected_region(&self, seed_triangle: u32) -> Vec<u32> {
+        self.topology().connected_region(&self.indices, seed_triangle)
+    }
+
+    /// Make triangle winding consistent across each connected surface
+    ///
+    /// Walks each surface across its shared edges, flipping neighbors that
+    /// run a shared edge the same way as the triangle they were reached
+    /// from (consistent neighbors run it the opposite way). Closed surfaces
+    /// are then turned to enclose a positive volume, so their fronts face
+    /// outward; open ones keep the winding most of their triangles had.
+    /// Vertex normals on flipped triangles that point against the faces
+    /// around them are reversed. Edges shared by more than two triangles are
+    /// not crossed. Returns the number of triangles flipped.
+    pub fn fix_winding(&mut self) -> usize {
+        let faces: Vec<[u32; 3]> = self.indices.chunks_exact(3).map(|t| [t[0], t[1], t[2]]).collect();
+        let mut flip = vec![false; faces.len()];
+        {
+            let topology = self.topology();
+            let welded = |t: usize| faces[t].map(|v| topology.canonical(v).unwrap_or(v));
+            let runs = |c: [u32; 3], a: u32, b: u32| (0..3).any(|k| c[k] == a && c[(k + 1) % 3] == b);
+            let mut visited = vec![false; faces.len()];
+
+            for seed in 0..faces.len() {
+                if visited[seed] || face_normal(&self.vertices, faces[seed]).is_none() {
+                    continue;
+                }
+                visited[seed] = true;
+                let mut surface = vec![seed];
+                let mut queue = VecDeque::from([seed]);
+                let mut closed = true;
+                while let Some(t) = queue.pop_front() {
+                    let corners = welded(t);
+                    for k in 0..3 {
+                        let shared = topology.edge_faces(faces[t][k], faces[t][(k + 1) % 3]);
+                        let other = match *shared {
+                            [a, b] if a as usize == t => b as usize,
+                            [a, b] if b as usize == t => a as usize,
+                            _ => {
+                                closed = false;
+                                continue;
+                            }
+                        };
+                        if visited[other] {
+                            continue;
+                        }
+                        visited[other] = true;
+                        flip[other] = flip[t] ^ runs(welded(other), corners[k], corners[(k + 1) % 3]);
+                        surface.push(other);
+                        queue.push_back(other);
+                    }
+                }
+
+                let reverse = if closed {
+                    let position = |i: u32| glam::Vec3::from_slice(&self.vertices[i as usize * 3..i as usize * 3 + 3]);
+                    let volume: f32 = surface
+                        .iter()
+                        .map(|&t| {
+                            let [a, b, c] = faces[t].map(position);
+                            let signed = a.dot(b.cross(c));
+                            if flip[t] { -signed } else { signed }
+                        })
+                        .sum();
+                    volume < 0.0
+                } else {
+                    surface.iter().filter(|&&t| flip[t]).count() * 2 > surface.len()
+                };
+                if reverse {
+                    for &t in &surface {
+                        flip[t] = !flip[t];
+                    }
+                }
+            }
+        }
+
+        for (t, _) in flip.iter().enumerate().filter(|(_, &f)| f) {
+            self.indices.swap(t * 3 + 1, t * 3 + 2);
+        }
+        if self.normals.len() == self.vertices.len() {
+            let mut around = vec![glam::Vec3::ZERO; self.vertex_count()];
+            for tri in self.indices.chunks_exact(3) {
+                if let Some(normal) = face_normal(&self.vertices, [tri[0], tri[1], tri[2]]) {
+                    for &v in tri {
+                        around[v as usize] += normal;
+                    }
+                }
+            }
+            let mut reversed = vec![false; self.vertex_count()];
+            for (t, _) in flip.iter().enumerate().filter(|(_, &f)| f) {
+                for &v in &self.indices[t * 3..t * 3 + 3] {
+                    let n = &mut self.normals[v as usize * 3..v as usize * 3 + 3];
+                    if !reversed[v as usize] && glam::Vec3::from_slice(n).dot(around[v as usize]) < 0.0 {
+                        reversed[v as usize] = true;
+                        n.iter_mut().for_each(|c| *c = -*c);
+                    }
+                }
+            }
+        }
+        // Winding doesn't change which triangles share an edge, so the cached
+        // topology stays valid
+        flip.iter().filter(|&&f| f).count()
+    }
+
+    /// Get the feature edges of the mesh (see [`feature_edges`])
+    pub fn feature_edges(&self, angle_deg: f32) -> Vec<[u32; 2]> {
+        feature_edges_with_topology(&self.vertices, &self.indices, self.topology(), angle_deg)
+    }
+
+    /// Recompute vertex normals, smoothing across edges sharper than
+    /// `threshold_deg` only
+    ///
+    /// Each triangle corner averages the area-weighted normals of the
+    /// triangles around its position that lie within the threshold of its own
+    /// face. Corners of a vertex that end up in different smoothing groups get
+    /// their own copy of the vertex, so a cube gets crisp corners while a
+    /// finely tessellated cylinder stays smooth.
+    pub fn compute_normals_with_angle(&mut self, threshold_deg: f32) {
+        let cos_threshold = threshold_deg.to_radians().cos();
+        let position = |i: u32| glam::Vec3::from_slice(&self.vertices[i as usize * 3..i as usize * 3 + 3]);
+        let faces: Vec<[u32; 3]> = self.indices.chunks_exact(3).map(|t| [t[0], t[1], t[2]]).collect();
+        let unit_normals: Vec<Option<glam::Vec3>> =
+            faces.iter().map(|&tri| face_normal(&self.vertices, tri)).collect();
+        // Unnormalized cross product: larger faces weigh more
+        let weighted_normals: Vec<glam::Vec3> = faces
+            .iter()
+            .zip(&unit_normals)
+            .map(|(tri, unit)| match unit {
+                Some(_) => (position(tri[1]) - position(tri[0])).cross(position(tri[2]) - position(tri[0])),
+                None => glam::Vec3::ZERO,
+            })
+            .collect();
+
+        let topology = self.topology();
+        let has_colors = self.colors.len() == self.vertices.len() / 3 * 4;
+        let mut groups: HashMap<(u32, Vec<u32>), u32> = HashMap::new();
+        let mut vertices = Vec::new();
+        let mut normals = Vec::new();
+        let mut colors = Vec::new();
+        let mut indices = Vec::with_capacity(self.indices.len());
+
+        for (face, tri) in faces.iter().enumerate() {
+            for &v in tri {
+                // Degenerate triangles join every group around the vertex
+                let group: Vec<u32> = topology
+                    .vertex_faces(v)
+                    .iter()
+                    .copied()
+                    .filter(|&other| match (unit_normals[face], unit_normals[other as usize]) {
+                        (Some(a), Some(b)) => a.dot(b) >= cos_threshold,
+                        (None, _) => true,
+                        (Some(_), None) => false,
+                    })
+                    .collect();
+                let normal = group
+                    .iter()
+                    .map(|&g| weighted_normals[g as usize])
+                    .sum::<glam::Vec3>()
+                    .normalize_or_zero();
+
+                let next = (vertices.len() / 3) as u32;
+                let index = *groups.entry((v, group)).or_insert_with(|| {
+                    vertices.extend_from_slice(&self.vertices[v as usize * 3..v as usize * 3 + 3]);
+                    normals.extend(normal.to_array());
+                    if has_colors {
+                        colors.extend_from_slice(&self.colors[v as usize * 4..v as usize * 4 + 4]);
+                    }
+                    next
+                });
+                indices.push(index);
+            }
+        }
+
+        self.vertices = vertices;
+        self.normals = normals;
+        self.colors = colors;
+        self.indices = indices;
+        self.invalidate_topology();
+    }
+}
+
+/// Check a triangle mesh for degenerate triangles, NaN vertices,
+/// unreferenced vertices and non-manifold edges
+pub fn validate_mesh(positions: &[f32], indices: &[u32]) -> MeshReport {
+    let vertex_count = positions.len() / 3;
+    let mut referenced = vec![false; vertex_count];
+    let mut edge_uses: HashMap<[u32; 2], u32> = HashMap::new();
+    let mut report = MeshReport {
+        nan_vertices: positions
+            .chunks_exact(3)
+            .filter(|p| p.iter().any(|c| !c.is_finite()))
+            .count(),
+        ..Default::default()
+    };
+
+    for tri in indices.chunks_exact(3) {
+        let tri = [tri[0], tri[1], tri[2]];
+        for &i in &tri {
+            if let Some(r) = referenced.get_mut(i as usize) {
+                *r = true;
+            }
+        }
+        if is_degenerate_triangle(positions, tri) {
+            report.degenerate_tris += 1;
+            continue;
+        }
+        for (a, b) in [(tri[0], tri[1]), (tri[1], tri[2]), (tri[2], tri[0])] {
+            *edge_uses.entry([a.min(b), a.max(b)]).or_insert(0) += 1;
+        }
+    }
+
+    report.unreferenced_vertices = referenced.iter().filter(|&&r| !r).count();
+    report.non_manifold_edges = edge_uses.values().filter(|&&uses| uses > 2).count();
+    report
+}
+
+/// Whether a triangle has zero area or repeated / out-of-range indices
+///
+/// Triangles touching NaN vertices are not counted here (see `nan_vertices`).
+fn is_degenerate_triangle(positions: &[f32], tri: [u32; 3]) -> bool {
+    if tri[0] == tri[1] || tri[1] == tri[2] || tri[0] == tri[2] {
+        return true;
+    }
+    let position = |i: u32| -> Option<glam::Vec3> {
+        let p = positions.get(i as usize * 3..i as usize * 3 + 3)?;
+        Some(glam::Vec3::new(p[0], p[1], p[2]))
+    };
+    let (Some(a), Some(b), Some(c)) = (position(tri[0]), position(tri[1]), position(tri[2])) else {
+        return true;
+    };
+
+    let area = (b - a).cross(c - a).length() * 0.5;
+    // NaN areas compare false, leaving NaN triangles to the NaN vertex count
+    area <= DEGENERATE_AREA_EPSILON
+}
+
+/// Compute feature edges of a triangle mesh
+///
+/// An edge is kept when the faces on either side meet at a dihedral angle
+/// above `angle_deg`, or when it is a boundary / non-manifold edge. Vertices
+/// are welded by position first, so meshes with split per-face vertices
+/// (flat shading) still find their neighbours. Returns vertex index pairs
+/// suitable for a line list.
+pub fn feature_edges(positions: &[f32], indices: &[u32], angle_deg: f32) -> Vec<[u32; 2]> {
+    let topology = MeshTopology::new(positions, indices);
+    feature_edges_with_topology(positions, indices, &topology, angle_deg)
+}
+
+fn feature_edges_with_topology(
+    positions: &[f32],
+    indices: &[u32],
+    topology: &MeshTopology,
+    angle_deg: f32,
+) -> Vec<[u32; 2]> {
+    let normal = |face: u32| {
+        let tri = &indices[face as usize * 3..face as usize * 3 + 3];
+        face_normal(positions, [tri[0], tri[1], tri[2]]).unwrap_or_default()
+    };
+
+    let cos_threshold = angle_deg.to_radians().cos();
+    let mut result: Vec<[u32; 2]> = topology
+        .edges()
+        .filter(|(_, faces)| match faces {
+            [f0, f1] => normal(*f0).dot(normal(*f1)) < cos_threshold,
+            _ => true, // Boundary or non-manifold
+        })
+        .map(|(edge, _)| edge)
+        .collect();
+    result.sort_unstable();
+    result
 }
 
 impl Default for Mesh {
@@ -157,58 +739,91 @@ impl BoundingBox {
     }
 }
 
-/// Get color for IFC element type
-pub fn color_for_element_type(element_type: &str) -> [f32; 4] {
-    match element_type.to_uppercase().as_str() {
-        // === ARCHITECTURAL ===
-        // Walls - light gray/beige
-        s if s.contains("WALL") => [0.85, 0.82, 0.75, 1.0],
-        // Slabs/floors - darker gray
-        s if s.contains("SLAB") || s.contains("FLOOR") => [0.6, 0.6, 0.65, 1.0],
-        // Doors - brown
-        s if s.contains("DOOR") => [0.6, 0.45, 0.3, 1.0],
-        // Windows - light blue (glass)
-        s if s.contains("WINDOW") => [0.7, 0.85, 0.95, 0.7],
-        // Roofs - terracotta
-        s if s.contains("ROOF") => [0.75, 0.5, 0.4, 1.0],
-        // Stairs - concrete gray
-        s if s.contains("STAIR") => [0.65, 0.65, 0.65, 1.0],
-        // Railings - dark gray
-        s if s.contains("RAILING") => [0.4, 0.4, 0.4, 1.0],
-        // Furniture - wood tone
-        s if s.contains("FURNITURE") => [0.65, 0.5, 0.35, 1.0],
-
-        // === STRUCTURAL ===
-        // Columns - steel blue
-        s if s.contains("COLUMN") => [0.5, 0.55, 0.7, 1.0],
-        // Beams - steel gray
-        s if s.contains("BEAM") => [0.55, 0.55, 0.6, 1.0],
-        // Footings - concrete
-        s if s.contains("FOOTING") || s.contains("FOUNDATION") => [0.5, 0.5, 0.5, 1.0],
-
-        // === MEP (Mechanical/Electrical/Plumbing) ===
-        // Pipes - copper/green for water
-        s if s.contains("PIPE") => [0.2, 0.7, 0.5, 1.0],
-        // Ducts - silver/metal
-        s if s.contains("DUCT") => [0.7, 0.75, 0.8, 1.0],
-        // Flow terminals (vents, outlets) - light metal
-        s if s.contains("FLOWTERMINAL") || s.contains("TERMINAL") => [0.6, 0.65, 0.7, 1.0],
-
-        // === ELECTRICAL ===
-        // Cable carriers/trays - orange
-        s if s.contains("CABLE") || s.contains("CONDUIT") => [0.9, 0.5, 0.2, 1.0],
-        // Electrical equipment - yellow
-        s if s.contains("ELECTRIC") => [0.9, 0.8, 0.2, 1.0],
-
-        // === GENERIC ===
-        // Building element proxy - purple tint
-        s if s.contains("PROXY") => [0.6, 0.5, 0.7, 1.0],
-
-        // Default - neutral gray
-        _ => [0.7, 0.7, 0.7, 1.0],
+/// Bounding box summary for the UI (camera framing, scale bars, grid extents)
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct BoundsInfo {
+    pub min: Point3D,
+    pub max: Point3D,
+    pub center: Point3D,
+    pub size: Vector3D,
+}
+
+impl From<BoundingBox> for BoundsInfo {
+    fn from(bounds: BoundingBox) -> Self {
+        Self {
+            min: bounds.min,
+            max: bounds.max,
+            center: bounds.center(),
+            size: bounds.size(),
+        }
     }
 }
 
+/// Built-in colors by IFC type, matched in order against the upper-cased type
+/// name (so `WALL` also covers `IFCWALLSTANDARDCASE`)
+pub const DEFAULT_TYPE_COLORS: &[(&str, [f32; 4])] = &[
+    // === ARCHITECTURAL ===
+    // Walls - off-white
+    ("WALL", [0.92, 0.9, 0.85, 1.0]),
+    // Slabs/floors - gray
+    ("SLAB", [0.6, 0.6, 0.62, 1.0]),
+    ("FLOOR", [0.6, 0.6, 0.62, 1.0]),
+    // Doors - wood brown
+    ("DOOR", [0.55, 0.38, 0.22, 1.0]),
+    // Windows - translucent cyan (glass)
+    ("WINDOW", [0.6, 0.9, 0.95, 0.4]),
+    // Roofs - terracotta
+    ("ROOF", [0.75, 0.5, 0.4, 1.0]),
+    // Stairs - concrete gray
+    ("STAIR", [0.65, 0.65, 0.65, 1.0]),
+    // Railings - dark gray
+    ("RAILING", [0.4, 0.4, 0.4, 1.0]),
+    // Furniture - wood tone
+    ("FURNITURE", [0.65, 0.5, 0.35, 1.0]),
+    // Spaces - translucent pastel green
+    ("SPACE", [0.75, 0.92, 0.8, 0.3]),
+
+    // === STRUCTURAL ===
+    // Columns and beams - steel blue
+    ("COLUMN", [0.45, 0.55, 0.7, 1.0]),
+    ("BEAM", [0.45, 0.55, 0.7, 1.0]),
+    // Footings - concrete
+    ("FOOTING", [0.5, 0.5, 0.5, 1.0]),
+    ("FOUNDATION", [0.5, 0.5, 0.5, 1.0]),
+
+    // === MEP (Mechanical/Electrical/Plumbing) ===
+    // Pipes - copper/green for water
+    ("PIPE", [0.2, 0.7, 0.5, 1.0]),
+    // Ducts - silver/metal
+    ("DUCT", [0.7, 0.75, 0.8, 1.0]),
+    // Flow terminals (vents, outlets) - light metal
+    ("TERMINAL", [0.6, 0.65, 0.7, 1.0]),
+
+    // === ELECTRICAL ===
+    // Cable carriers/trays - orange
+    ("CABLE", [0.9, 0.5, 0.2, 1.0]),
+    ("CONDUIT", [0.9, 0.5, 0.2, 1.0]),
+    // Electrical equipment - yellow
+    ("ELECTRIC", [0.9, 0.8, 0.2, 1.0]),
+
+    // === GENERIC ===
+    // Building element proxy - purple tint
+    ("PROXY", [0.6, 0.5, 0.7, 1.0]),
+];
+
+/// Color for types not in the palette - neutral gray
+pub const DEFAULT_ELEMENT_COLOR: [f32; 4] = [0.7, 0.7, 0.7, 1.0];
+
+/// Get the built-in color for an IFC element type
+pub fn color_for_element_type(element_type: &str) -> [f32; 4] {
+    let upper = element_type.to_uppercase();
+    DEFAULT_TYPE_COLORS
+        .iter()
+        .find(|(key, _)| upper.contains(key))
+        .map(|(_, color)| *color)
+        .unwrap_or(DEFAULT_ELEMENT_COLOR)
+}
+
 /// Generate a box mesh with proper normals per face
 pub fn generate_box_with_normals(
     center: [f32; 3],
@@ -312,6 +927,12 @@ pub fn merge_meshes(meshes: Vec<Mesh>) -> Mesh {
 
     for mesh in meshes {
         let base = result.vertex_count() as u32;
+        let index_base = result.indices.len() as u32;
+
+        result.submeshes.extend(mesh.submeshes.iter().map(|s| SubMesh {
+            index_offset: s.index_offset + index_base,
+            ..*s
+        }));
 
         // Add vertices
         result.vertices.extend(&mesh.vertices);
@@ -389,6 +1010,79 @@ mod tests {
         assert_eq!(mesh.triangle_count(), 12);
     }
 
+    #[test]
+    fn test_cube_feature_edges() {
+        let mesh = generate_box_with_normals([0.0, 0.0, 0.0], [2.0, 2.0, 2.0], [0.7, 0.7, 0.7, 1.0]);
+
+        // 12 triangles have 36 edges (18 unique), only the 12 cube edges are features
+        let edges = mesh.feature_edges(30.0);
+        assert_eq!(edges.len(), 12);
+        assert!(edges.len() < mesh.triangle_count() * 3);
+
+        // Every feature edge is axis-aligned (no face diagonals)
+        for [a, b] in edges {
+            let pa = &mesh.vertices[a as usize * 3..a as usize * 3 + 3];
+            let pb = &mesh.vertices[b as usize * 3..b as usize * 3 + 3];
+            let differing = (0..3).filter(|&k| pa[k] != pb[k]).count();
+            assert_eq!(differing, 1);
+        }
+
+        // A single open quad only has its 4 boundary edges
+        let mut quad = Mesh::new();
+        quad.add_vertex(0.0, 0.0, 0.0);
+        quad.add_vertex(1.0, 0.0, 0.0);
+        quad.add_vertex(1.0, 1.0, 0.0);
+        quad.add_vertex(0.0, 1.0, 0.0);
+        quad.add_triangle(0, 1, 2);
+        quad.add_triangle(2, 3, 0);
+        assert_eq!(quad.feature_edges(30.0).len(), 4);
+    }
+
+    #[test]
+    fn test_normals_with_angle_split_cube_but_keep_cylinder_smooth() {
+        // Shared-corner cube: every corner splits into one copy per face
+        let mut cube = generate_box(2.0, 2.0, 2.0);
+        cube.compute_normals_with_angle(30.0);
+        assert_eq!(cube.vertex_count(), 24);
+        assert_eq!(cube.triangle_count(), 12);
+        for n in cube.normals.chunks_exact(3) {
+            assert_eq!(n.iter().filter(|c| c.abs() > 0.999).count(), 1, "{:?}", n);
+        }
+
+        // Capped cylinder with shared ring vertices, 32 segments (11.25° apart)
+        let segments = 32u32;
+        let mut cylinder = Mesh::new();
+        for y in [0.0, 2.0] {
+            for k in 0..segments {
+                let angle = k as f32 / segments as f32 * std::f32::consts::TAU;
+                cylinder.add_vertex(angle.cos(), y, angle.sin());
+            }
+        }
+        cylinder.add_vertex(0.0, 0.0, 0.0);
+        cylinder.add_vertex(0.0, 2.0, 0.0);
+        let (bottom, top) = (2 * segments, 2 * segments + 1);
+        for k in 0..segments {
+            let next = (k + 1) % segments;
+            cylinder.add_triangle(k, segments + k, segments + next);
+            cylinder.add_triangle(segments + next, next, k);
+            cylinder.add_triangle(bottom, k, next);
+            cylinder.add_triangle(top, segments + next, segments + k);
+        }
+        cylinder.compute_normals_with_angle(30.0);
+
+        // Side vertices stay shared, only the 90° rims split from the caps
+        assert_eq!(cylinder.vertex_count(), (4 * segments + 2) as usize);
+        for (p, n) in cylinder.vertices.chunks_exact(3).zip(cylinder.normals.chunks_exact(3)) {
+            if n[1].abs() < 0.5 {
+                // Smooth side: radial normal (a face normal would be 5.6° off)
+                assert!(n[0] * p[0] + n[2] * p[2] > 0.999, "{:?} {:?}", p, n);
+                assert!(n[1].abs() < 1e-4);
+            } else {
+                assert!((n[1].abs() - 1.0).abs() < 1e-4);
+            }
+        }
+    }
+
     #[test]
     fn test_bounding_box() {
         let mesh = generate_box(2.0, 2.0, 2.0);
@@ -396,4 +1090,83 @@ mod tests {
         assert_eq!(bbox.center(), [0.0, 0.0, 0.0]);
         assert_eq!(bbox.size(), [2.0, 2.0, 2.0]);
     }
+
+    #[test]
+    fn test_fix_winding_corrects_flipped_face() {
+        let reference = generate_box_with_normals([0.0, 0.0, 0.0], [2.0, 2.0, 2.0], [1.0; 4]);
+        assert!((reference.volume() - 8.0).abs() < 1e-5);
+
+        // One face (two triangles) wound and lit inside out
+        let mut mesh = reference.clone();
+        for t in [4, 5] {
+            mesh.indices.swap(t * 3 + 1, t * 3 + 2);
+        }
+        let flipped: Vec<u32> = mesh.indices[12..18].to_vec();
+        for &v in &flipped {
+            mesh.normals[v as usize * 3..v as usize * 3 + 3].iter_mut().for_each(|c| *c = -*c);
+        }
+        assert!(mesh.volume() < 8.0 - 1e-3);
+
+        assert_eq!(mesh.fix_winding(), 2);
+        assert_eq!(mesh.indices, reference.indices);
+        assert_eq!(mesh.normals, reference.normals);
+        assert!((mesh.volume() - 8.0).abs() < 1e-5);
+        assert_eq!(mesh.fix_winding(), 0);
+
+        // A box wound inside out throughout is turned to face outward
+        let mut inverted = reference.clone();
+        for tri in inverted.indices.chunks_exact_mut(3) {
+            tri.swap(1, 2);
+        }
+        assert_eq!(inverted.fix_winding(), 12);
+        assert!((inverted.volume() - 8.0).abs() < 1e-5);
+    }
+
+    #[test]
+    fn test_validate_and_repair_degenerate_and_nan() {
+        let mut mesh = generate_box_with_normals([0.0, 0.0, 0.0], [1.0, 1.0, 1.0], [0.7, 0.7, 0.7, 1.0]);
+        assert!(mesh.validate().is_clean());
+        mesh.assign_materials(&[[1.0, 0.0, 0.0, 1.0]]);
+
+        // A zero-area triangle (collinear points)
+        let base = mesh.vertex_count() as u32;
+        mesh.add_vertex(0.0, 0.0, 0.0);
+        mesh.add_vertex(1.0, 0.0, 0.0);
+        mesh.add_vertex(2.0, 0.0, 0.0);
+        // A triangle with a NaN vertex
+        mesh.add_vertex(f32::NAN, 0.0, 0.0);
+        mesh.add_vertex(0.0, 1.0, 0.0);
+        mesh.add_vertex(0.0, 0.0, 1.0);
+        // A stray vertex no triangle uses
+        mesh.add_vertex(5.0, 5.0, 5.0);
+        for _ in 0..7 {
+            mesh.add_normal(0.0, 0.0, 1.0);
+            mesh.add_color(0.7, 0.7, 0.7, 1.0);
+        }
+        mesh.add_triangle(base, base + 1, base + 2);
+        mesh.add_triangle(base + 3, base + 4, base + 5);
+
+        let report = mesh.validate();
+        assert_eq!(report.degenerate_tris, 1);
+        assert_eq!(report.nan_vertices, 1);
+        assert_eq!(report.unreferenced_vertices, 1);
+        assert_eq!(report.non_manifold_edges, 0);
+
+        assert_eq!(mesh.repair(), 2);
+        assert!(mesh.validate().is_clean());
+        assert_eq!(mesh.vertex_count(), 24);
+        assert_eq!(mesh.triangle_count(), 12);
+        assert_eq!(mesh.colors.len(), 24 * 4);
+        assert_eq!(mesh.submeshes[0].index_count, 36);
+    }
+
+    #[test]
+    fn test_non_manifold_edge() {
+        // Three triangles sharing the edge 0-1
+        let positions = [
+            0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 1.0, 0.0, 0.0, -1.0, 0.0, 0.0, 0.0, 1.0,
+        ];
+        let indices = [0, 1, 2, 1, 0, 3, 0, 1, 4];
+        assert_eq!(validate_mesh(&positions, &indices).non_manifold_edges, 1);
+    }
 }
@@ -0,0 +1,342 @@
+//! glTF Export
+//!
+//! Writes element meshes as a glTF 2.0 asset: one node and mesh per element,
+//! named after the element, with positions, normals and vertex colors in a
+//! single binary buffer. Geometry is exported as generated for rendering
+//! (Y-up, meters), which matches glTF's conventions.
+//!
+//! Positions can be written relative to an origin (so survey coordinates
+//! stay out of the file) and rounded to a number of decimals, which also
+//! makes the buffer compress much better.
+
+use super::model::{ElementInfo, ElementMesh};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::path::Path;
+
+/// glTF component type of 32-bit floats
+const FLOAT: u32 = 5126;
+/// glTF component type of 32-bit unsigned integers
+const UNSIGNED_INT: u32 = 5125;
+/// Buffer view targets
+const ARRAY_BUFFER: u32 = 34962;
+const ELEMENT_ARRAY_BUFFER: u32 = 34963;
+
+/// Materials shared by all primitives; vertex colors carry the element color
+const OPAQUE_MATERIAL: usize = 0;
+const BLENDED_MATERIAL: usize = 1;
+
+/// Most decimals positions can be rounded to (nanometers, well below f32
+/// precision at building scale)
+pub const MAX_EXPORT_DECIMALS: u32 = 9;
+
+/// Coordinate precision of exported positions
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ExportPrecision {
+    /// Decimal places (of meters) positions are rounded to, e.g. 3 for
+    /// millimeters; None keeps full precision
+    pub decimals: Option<u32>,
+    /// Write positions relative to each model's local origin rather than in
+    /// file coordinates
+    pub relative_to_origin: bool,
+}
+
+impl ExportPrecision {
+    /// Check the settings: at most `MAX_EXPORT_DECIMALS` decimals
+    pub fn validate(&self) -> Result<(), String> {
+        match self.decimals {
+            Some(decimals) if decimals > MAX_EXPORT_DECIMALS => Err(format!(
+                "Invalid export precision: {} decimals (at most {})",
+                decimals, MAX_EXPORT_DECIMALS
+            )),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// A glTF asset being assembled from element meshes
+#[derive(Debug, Default)]
+pub struct GltfExport {
+    nodes: Vec<Value>,
+    meshes: Vec<Value>,
+    accessors: Vec<Value>,
+    buffer_views: Vec<Value>,
+    buffer: Vec<u8>,
+    /// Decimals positions are rounded to (None for full precision)
+    decimals: Option<u32>,
+    /// Subtracted from the positions of elements added
+    origin: [f64; 3],
+}
+
+impl GltfExport {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Round the positions of elements added from now on to `decimals`
+    /// places (None for full precision)
+    pub fn set_decimals(&mut self, decimals: Option<u32>) {
+        self.decimals = decimals;
+    }
+
+    /// Write the positions of elements added from now on relative to
+    /// `origin` (in the meshes' Y-up axes); recorded in each node's extras
+    pub fn set_origin(&mut self, origin: [f64; 3]) {
+        self.origin = origin;
+    }
+
+    /// A position as written: relative to the origin, rounded
+    fn export_position(&self, p: &[f32]) -> [f32; 3] {
+        let scale = self.decimals.map(|d| 10f64.powi(d as i32));
+        [0, 1, 2].map(|k| {
+            let relative = p[k] as f64 - self.origin[k];
+            match scale {
+                Some(scale) => ((relative * scale).round() / scale) as f32,
+                None => relative as f32,
+            }
+        })
+    }
+
+    /// Add an element as a node with its own mesh (skipped if it has no
+    /// triangles)
+    pub fn add_element(&mut self, info: &ElementInfo, mesh: &ElementMesh) {
+        let vertex_count = mesh.vertices.len() / 3;
+        if mesh.indices.is_empty() || vertex_count == 0 {
+            return;
+        }
+
+        let positions: Vec<f32> = mesh.vertices.chunks_exact(3).flat_map(|p| self.export_position(p)).collect();
+        let (min, max) = positions.chunks_exact(3).fold(
+            ([f32::MAX; 3], [f32::MIN; 3]),
+            |(mut min, mut max), v| {
+                for k in 0..3 {
+                    min[k] = min[k].min(v[k]);
+                    max[k] = max[k].max(v[k]);
+                }
+                (min, max)
+            },
+        );
+        let mut attributes = serde_json::Map::new();
+        let position = self.add_accessor(&positions, vertex_count, "VEC3", Some((min, max)));
+        attributes.insert("POSITION".to_string(), json!(position));
+        if mesh.normals.len() == mesh.vertices.len() {
+            let normal = self.add_accessor(&mesh.normals, vertex_count, "VEC3", None);
+            attributes.insert("NORMAL".to_string(), json!(normal));
+        }
+        let translucent = if mesh.colors.len() == vertex_count * 4 {
+            let color = self.add_accessor(&mesh.colors, vertex_count, "VEC4", None);
+            attributes.insert("COLOR_0".to_string(), json!(color));
+            mesh.colors.chunks_exact(4).any(|c| c[3] < 1.0)
+        } else {
+            false
+        };
+        let indices = self.add_indices(&mesh.indices);
+
+        let name = if info.name.is_empty() { &info.global_id } else { &info.name };
+        let material = if translucent { BLENDED_MATERIAL } else { OPAQUE_MATERIAL };
+        self.meshes.push(json!({
+            "name": name,
+            "primitives": [{ "attributes": attributes, "indices": indices, "material": material }],
+        }));
+        let mut extras = json!({ "globalId": info.global_id, "type": info.element_type });
+        if self.origin != [0.0; 3] {
+            extras["origin"] = json!(self.origin);
+        }
+        self.nodes.push(json!({
+            "name": name,
+            "mesh": self.meshes.len() - 1,
+            "extras": extras,
+        }));
+    }
+
+    /// Number of elements added
+    pub fn element_count(&self) -> usize {
+        self.nodes.len()
+    }
+
+    fn add_accessor(&mut self, data: &[f32], count: usize, kind: &str, bounds: Option<([f32; 3], [f32; 3])>) -> usize {
+        let view = self.add_buffer_view(bytemuck::cast_slice(data), ARRAY_BUFFER);
+        let mut accessor = json!({
+            "bufferView": view,
+            "componentType": FLOAT,
+            "count": count,
+            "type": kind,
+        });
+        if let Some((min, max)) = bounds {
+            accessor["min"] = json!(min);
+            accessor["max"] = json!(max);
+        }
+        self.accessors.push(accessor);
+        self.accessors.len() - 1
+    }
+
+    fn add_indices(&mut self, indices: &[u32]) -> usize {
+        let view = self.add_buffer_view(bytemuck::cast_slice(indices), ELEMENT_ARRAY_BUFFER);
+        self.accessors.push(json!({
+            "bufferView": view,
+            "componentType": UNSIGNED_INT,
+            "count": indices.len(),
+            "type": "SCALAR",
+        }));
+        self.accessors.len() - 1
+    }
+
+    fn add_buffer_view(&mut self, bytes: &[u8], target: u32) -> usize {
+        // Every view holds 4-byte components, so offsets stay aligned
+        self.buffer_views.push(json!({
+            "buffer": 0,
+            "byteOffset": self.buffer.len(),
+            "byteLength": bytes.len(),
+            "target": target,
+        }));
+        self.buffer.extend_from_slice(bytes);
+        self.buffer_views.len() - 1
+    }
+
+    /// The glTF JSON document; `buffer_uri` names an external buffer file
+    /// (None for a GLB, whose buffer is embedded)
+    pub fn document(&self, buffer_uri: Option<&str>) -> Value {
+        let mut buffer = json!({ "byteLength": self.buffer.len() });
+        if let Some(uri) = buffer_uri {
+            buffer["uri"] = json!(uri);
+        }
+        let mut document = json!({
+            "asset": { "version": "2.0", "generator": "flutter_bim" },
+            "scene": 0,
+            "scenes": [{ "nodes": (0..self.nodes.len()).collect::<Vec<_>>() }],
+            "nodes": self.nodes,
+            "meshes": self.meshes,
+            "materials": [
+                { "name": "Opaque", "pbrMetallicRoughness": { "metallicFactor": 0.0 } },
+                { "name": "Translucent", "pbrMetallicRoughness": { "metallicFactor": 0.0 }, "alphaMode": "BLEND" },
+            ],
+        });
+        // glTF forbids empty arrays, so an empty asset has no buffer at all
+        if !self.buffer.is_empty() {
+            document["accessors"] = json!(self.accessors);
+            document["bufferViews"] = json!(self.buffer_views);
+            document["buffers"] = json!([buffer]);
+        }
+        document
+    }
+
+    /// The asset as a self-contained binary glTF (GLB) file
+    pub fn to_glb(&self) -> Vec<u8> {
+        let mut json = self.document(None).to_string().into_bytes();
+        json.resize(json.len().next_multiple_of(4), b' ');
+        let mut bin = self.buffer.clone();
+        bin.resize(bin.len().next_multiple_of(4), 0);
+
+        let chunks = [(json, 0x4E4F_534A_u32), (bin, 0x004E_4942_u32)];
+        let length: usize = 12 + chunks.iter().filter(|c| !c.0.is_empty()).map(|c| 8 + c.0.len()).sum::<usize>();
+        let mut glb = Vec::with_capacity(length);
+        glb.extend_from_slice(b"glTF");
+        glb.extend_from_slice(&2u32.to_le_bytes());
+        glb.extend_from_slice(&(length as u32).to_le_bytes());
+        for (data, kind) in chunks.iter().filter(|c| !c.0.is_empty()) {
+            glb.extend_from_slice(&(data.len() as u32).to_le_bytes());
+            glb.extend_from_slice(&kind.to_le_bytes());
+            glb.extend_from_slice(data);
+        }
+        glb
+    }
+
+    /// Write the asset: a GLB for `.glb` paths, otherwise glTF JSON with the
+    /// buffer in a `.bin` file of the same name beside it
+    pub fn write(&self, path: &Path) -> Result<(), String> {
+        let is_glb = path.extension().is_some_and(|e| e.eq_ignore_ascii_case("glb"));
+        if is_glb {
+            return std::fs::write(path, self.to_glb()).map_err(|e| format!("Failed to write glTF: {}", e));
+        }
+
+        let bin_path = path.with_extension("bin");
+        let bin_name = bin_path
+            .file_name()
+            .and_then(|n| n.to_str())
+            .ok_or("Invalid glTF path")?;
+        let document = serde_json::to_string_pretty(&self.document(Some(bin_name)))
+            .map_err(|e| format!("Failed to write glTF: {}", e))?;
+        std::fs::write(path, document).map_err(|e| format!("Failed to write glTF: {}", e))?;
+        if !self.buffer.is_empty() {
+            std::fs::write(&bin_path, &self.buffer).map_err(|e| format!("Failed to write glTF buffer: {}", e))?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bim::BoundingBox;
+
+    fn triangle(global_id: &str, alpha: f32) -> (ElementInfo, ElementMesh) {
+        let info = ElementInfo {
+            id: 1,
+            element_type: "IfcWall".to_string(),
+            name: String::new(),
+            global_id: global_id.to_string(),
+            bounds: BoundingBox { min: [0.0; 3], max: [1.0, 1.0, 0.0] },
+            triangle_start: 0,
+            triangle_count: 1,
+        };
+        let mesh = ElementMesh {
+            global_id: global_id.to_string(),
+            vertices: vec![0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 1.0, 0.0],
+            normals: [0.0, 0.0, 1.0].repeat(3),
+            colors: [0.5, 0.5, 0.5, alpha].repeat(3),
+            indices: vec![0, 1, 2],
+            running_bounds: None,
+        };
+        (info, mesh)
+    }
+
+    #[test]
+    fn test_glb_layout_and_document() {
+        let mut export = GltfExport::new();
+        for (info, mesh) in [triangle("a", 1.0), triangle("b", 0.4)] {
+            export.add_element(&info, &mesh);
+        }
+        assert_eq!(export.element_count(), 2);
+
+        let document = export.document(None);
+        assert_eq!(document["nodes"][0]["name"], "a");
+        assert_eq!(document["nodes"][1]["extras"]["globalId"], "b");
+        assert_eq!(document["meshes"][0]["primitives"][0]["material"], OPAQUE_MATERIAL);
+        assert_eq!(document["meshes"][1]["primitives"][0]["material"], BLENDED_MATERIAL);
+        assert_eq!(document["accessors"][0]["max"], json!([1.0, 1.0, 0.0]));
+        // Positions, normals and colors as floats plus 3 indices, per element
+        assert_eq!(document["buffers"][0]["byteLength"], 2 * (4 * (9 + 9 + 12) + 4 * 3));
+
+        let glb = export.to_glb();
+        assert_eq!(&glb[..4], b"glTF");
+        assert_eq!(u32::from_le_bytes(glb[8..12].try_into().unwrap()) as usize, glb.len());
+        let json_length = u32::from_le_bytes(glb[12..16].try_into().unwrap()) as usize;
+        assert_eq!(json_length % 4, 0);
+        let parsed: Value = serde_json::from_slice(&glb[20..20 + json_length]).unwrap();
+        assert_eq!(parsed, document);
+    }
+
+    #[test]
+    fn test_quantized_positions_rounded_to_millimeters() {
+        let (info, mut mesh) = triangle("a", 1.0);
+        mesh.vertices = vec![1000.1235, 2.0004, -3.98765, 1001.5, 2.25, -3.0, 1000.0, 3.333333, -4.0];
+        let mut export = GltfExport::new();
+        export.set_decimals(Some(3));
+        export.set_origin([1000.0, 0.0, 0.0]);
+        export.add_element(&info, &mesh);
+
+        let positions: &[f32] = bytemuck::cast_slice(&export.buffer[..36]);
+        let expected = [0.123, 2.0, -3.988, 1.5, 2.25, -3.0, 0.0, 3.333, -4.0];
+        for (p, e) in positions.iter().zip(expected) {
+            assert_eq!(*p, e as f32);
+            let millimeters = *p as f64 * 1000.0;
+            assert!((millimeters - millimeters.round()).abs() < 1e-3, "{} is not whole millimeters", p);
+        }
+        let document = export.document(None);
+        assert_eq!(document["accessors"][0]["min"], json!([0.0f32, 2.0f32, -4.0f32]));
+        assert_eq!(document["nodes"][0]["extras"]["origin"], json!([1000.0, 0.0, 0.0]));
+
+        assert!(ExportPrecision { decimals: Some(3), relative_to_origin: true }.validate().is_ok());
+        assert!(ExportPrecision { decimals: Some(MAX_EXPORT_DECIMALS + 1), ..Default::default() }.validate().is_err());
+    }
+}
@@ -0,0 +1,421 @@
+//! glTF Import
+//!
+//! Reads glTF 2.0 assets (`.gltf` JSON or binary `.glb`) into a model of
+//! generic elements, so meshes from other tools can be viewed beside IFC
+//! models. Every node with a mesh becomes one proxy element named after the
+//! node, its triangles transformed to world space by the node hierarchy.
+//! Buffers may be embedded in the GLB, external files next to the asset, or
+//! base64 `data:` URIs.
+//!
+//! glTF is Y-up and in meters, so the geometry is used as is and placed
+//! where it is rather than on the placeholder layout.
+
+use super::geometry::{Mesh, DEFAULT_FEATURE_ANGLE_DEG};
+use super::material::MaterialInfo;
+use super::model::BimModel;
+use glam::{Mat3, Mat4, Quat, Vec3};
+use serde_json::Value;
+use std::path::Path;
+
+/// First bytes of a GLB file ("glTF")
+const GLB_MAGIC: &[u8; 4] = b"glTF";
+/// GLB chunk types
+const JSON_CHUNK: u32 = 0x4E4F_534A;
+const BIN_CHUNK: u32 = 0x004E_4942;
+/// Primitive mode of triangle lists (the default)
+const TRIANGLES: u64 = 4;
+
+/// Import a `.gltf` or `.glb` file; external buffers are looked up next to it
+pub fn import_gltf(path: &Path) -> Result<BimModel, String> {
+    let data = std::fs::read(path).map_err(|e| format!("Failed to read glTF: {}", e))?;
+    let name = path.file_stem().and_then(|s| s.to_str()).unwrap_or("gltf");
+    parse_gltf(&data, path.parent(), name)
+}
+
+/// Build a model from glTF or GLB bytes
+///
+/// `base_dir` is where external buffers are read from (None to allow only
+/// embedded buffers). Elements get the GlobalId stored by our glTF export,
+/// or `<name>:<node index>`.
+pub fn parse_gltf(data: &[u8], base_dir: Option<&Path>, name: &str) -> Result<BimModel, String> {
+    let (json, bin) = if data.starts_with(GLB_MAGIC) { split_glb(data)? } else { (data, None) };
+    let document: Value = serde_json::from_slice(json).map_err(|e| format!("Invalid glTF JSON: {}", e))?;
+    let buffers = load_buffers(&document, bin, base_dir)?;
+    let gltf = Gltf { document: &document, buffers };
+
+    let mut model = BimModel::new();
+    let mut pending: Vec<(usize, Mat4)> = gltf.root_nodes().into_iter().rev().map(|n| (n, Mat4::IDENTITY)).collect();
+    while let Some((index, parent)) = pending.pop() {
+        let node = gltf.get("nodes", index)?;
+        let transform = parent * node_transform(node);
+        let children = node["children"].as_array().into_iter().flatten().filter_map(Value::as_u64);
+        pending.extend(children.rev().map(|child| (child as usize, transform)));
+
+        let Some(mesh_index) = node["mesh"].as_u64() else {
+            continue;
+        };
+        let (mesh, color) = gltf.node_mesh(mesh_index as usize, transform)?;
+        if mesh.indices.is_empty() {
+            continue;
+        }
+        let global_id = node["extras"]["globalId"]
+            .as_str()
+            .map_or_else(|| format!("{}:{}", name, index), str::to_string);
+        let node_name = node["name"].as_str().or_else(|| gltf.get("meshes", mesh_index as usize).ok()?["name"].as_str());
+        model.materials.insert(
+            global_id.clone(),
+            MaterialInfo {
+                name: None,
+                color: [color[0], color[1], color[2]],
+                transparency: 1.0 - color[3],
+            },
+        );
+        model.add_placed_body(global_id, node_name.map(str::to_string), mesh);
+    }
+    Ok(model)
+}
+
+/// JSON and binary chunks of a GLB file
+fn split_glb(data: &[u8]) -> Result<(&[u8], Option<&[u8]>), String> {
+    let word = |at: usize| -> Option<u32> { Some(u32::from_le_bytes(data.get(at..at + 4)?.try_into().ok()?)) };
+    let length = (word(8).ok_or("Truncated GLB header")? as usize).min(data.len());
+    let (mut json, mut bin) = (None, None);
+    let mut at = 12;
+    while at + 8 <= length {
+        let (chunk_length, kind) = (word(at).unwrap() as usize, word(at + 4).unwrap());
+        let chunk = data.get(at + 8..at + 8 + chunk_length).ok_or("Truncated GLB chunk")?;
+        match kind {
+            JSON_CHUNK => json = json.or(Some(chunk)),
+            BIN_CHUNK => bin = bin.or(Some(chunk)),
+            _ => {}
+        }
+        at += 8 + chunk_length;
+    }
+    Ok((json.ok_or("GLB has no JSON chunk")?, bin))
+}
+
+/// Contents of every buffer: the GLB chunk, a data URI or an external file
+fn load_buffers(document: &Value, bin: Option<&[u8]>, base_dir: Option<&Path>) -> Result<Vec<Vec<u8>>, String> {
+    let buffers = document["buffers"].as_array().map(Vec::as_slice).unwrap_or_default();
+    buffers
+        .iter()
+        .enumerate()
+        .map(|(i, buffer)| match buffer["uri"].as_str() {
+            None if i == 0 => bin.map(<[u8]>::to_vec).ok_or_else(|| "Buffer 0 has no data".to_string()),
+            None => Err(format!("Buffer {} has no data", i)),
+            Some(uri) if uri.starts_with("data:") => {
+                let (_, encoded) = uri.split_once(";base64,").ok_or("Only base64 data URIs are supported")?;
+                decode_base64(encoded)
+            }
+            Some(uri) => {
+                let dir = base_dir.ok_or_else(|| format!("External buffer '{}' without a base directory", uri))?;
+                std::fs::read(dir.join(uri)).map_err(|e| format!("Failed to read glTF buffer '{}': {}", uri, e))
+            }
+        })
+        .collect()
+}
+
+/// Decode standard base64 (padding optional)
+fn decode_base64(encoded: &str) -> Result<Vec<u8>, String> {
+    let value = |c: u8| -> Result<u32, String> {
+        Ok(match c {
+            b'A'..=b'Z' => c - b'A',
+            b'a'..=b'z' => c - b'a' + 26,
+            b'0'..=b'9' => c - b'0' + 52,
+            b'+' => 62,
+            b'/' => 63,
+            _ => return Err(format!("Invalid base64 character '{}'", c as char)),
+        } as u32)
+    };
+    let digits = encoded.trim_end_matches('=').as_bytes();
+    let mut bytes = Vec::with_capacity(digits.len() * 3 / 4);
+    for group in digits.chunks(4) {
+        if group.len() == 1 {
+            return Err("Truncated base64 data".to_string());
+        }
+        let mut bits = 0u32;
+        for (k, &c) in group.iter().enumerate() {
+            bits |= value(c)? << (18 - 6 * k);
+        }
+        bytes.extend_from_slice(&bits.to_be_bytes()[1..group.len()]);
+    }
+    Ok(bytes)
+}
+
+/// Local transform of a node: its matrix, or translation, rotation and scale
+fn node_transform(node: &Value) -> Mat4 {
+    let floats = |key: &str| -> Option<Vec<f32>> {
+        node[key].as_array()?.iter().map(|v| v.as_f64().map(|f| f as f32)).collect()
+    };
+    if let Some(matrix) = floats("matrix").filter(|m| m.len() == 16) {
+        return Mat4::from_cols_slice(&matrix);
+    }
+    let vec3 = |key: &str, default: Vec3| floats(key).filter(|v| v.len() == 3).map_or(default, |v| Vec3::from_slice(&v));
+    let rotation = floats("rotation")
+        .filter(|r| r.len() == 4)
+        .map_or(Quat::IDENTITY, |r| Quat::from_slice(&r).normalize());
+    Mat4::from_scale_rotation_translation(vec3("scale", Vec3::ONE), rotation, vec3("translation", Vec3::ZERO))
+}
+
+/// A parsed asset and its buffers
+struct Gltf<'a> {
+    document: &'a Value,
+    buffers: Vec<Vec<u8>>,
+}
+
+impl Gltf<'_> {
+    /// Item `index` of a top-level array
+    fn get(&self, array: &str, index: usize) -> Result<&Value, String> {
+        self.document[array]
+            .get(index)
+            .ok_or_else(|| format!("glTF {} {} not found", array, index))
+    }
+
+    /// Root nodes of the default scene, or every node no other node has as
+    /// a child when the asset has no scenes
+    fn root_nodes(&self) -> Vec<usize> {
+        let indices = |v: &Value| -> Vec<usize> {
+            v.as_array().into_iter().flatten().filter_map(Value::as_u64).map(|i| i as usize).collect()
+        };
+        let scene = self.document["scene"].as_u64().unwrap_or(0) as usize;
+        if let Some(scene) = self.document["scenes"].get(scene) {
+            return indices(&scene["nodes"]);
+        }
+        let nodes = self.document["nodes"].as_array().map(Vec::as_slice).unwrap_or_default();
+        let children: Vec<usize> = nodes.iter().flat_map(|n| indices(&n["children"])).collect();
+        (0..nodes.len()).filter(|i| !children.contains(i)).collect()
+    }
+
+    /// Triangles of a mesh in world space, and the color of its first
+    /// primitive (material base color times its first vertex color)
+    fn node_mesh(&self, mesh_index: usize, transform: Mat4) -> Result<(Mesh, [f32; 4]), String> {
+        let normal_matrix = Mat3::from_mat4(transform).inverse().transpose();
+        // Mirroring transforms turn the winding around
+        let mirrored = transform.determinant() < 0.0;
+        let mut mesh = Mesh::new();
+        let mut color = None;
+        let mut missing_normals = false;
+
+        let primitives = self.get("meshes", mesh_index)?["primitives"].as_array().cloned().unwrap_or_default();
+        for primitive in &primitives {
+            if primitive["mode"].as_u64().unwrap_or(TRIANGLES) != TRIANGLES {
+                continue;
+            }
+            let attributes = &primitive["attributes"];
+            let Some(position) = attributes["POSITION"].as_u64() else {
+                continue;
+            };
+            let positions = self.read_accessor::<3>(position as usize)?;
+            let normals = match attributes["NORMAL"].as_u64() {
+                Some(normal) => Some(self.read_accessor::<3>(normal as usize)?),
+                None => None,
+            };
+            if color.is_none() {
+                let base = primitive["material"]
+                    .as_u64()
+                    .and_then(|m| self.get("materials", m as usize).ok())
+                    .map(|m| &m["pbrMetallicRoughness"]["baseColorFactor"])
+                    .and_then(Value::as_array)
+                    .and_then(|f| f.iter().map(|v| v.as_f64().map(|x| x as f32)).collect::<Option<Vec<f32>>>())
+                    .filter(|f| f.len() == 4)
+                    .map_or([1.0; 4], |f| [f[0], f[1], f[2], f[3]]);
+                let tint = match attributes["COLOR_0"].as_u64() {
+                    Some(c) => self.read_accessor::<4>(c as usize)?.first().copied().unwrap_or([1.0; 4]),
+                    None => [1.0; 4],
+                };
+                color = Some([0, 1, 2, 3].map(|k| base[k] * tint[k]));
+            }
+
+            let count = positions.len() as u32;
+            let indices = match primitive["indices"].as_u64() {
+                Some(accessor) => self.read_indices(accessor as usize)?,
+                None => (0..count).collect(),
+            };
+            if indices.iter().any(|&i| i >= count) {
+                return Err(format!("glTF mesh {} has an index out of range", mesh_index));
+            }
+
+            let offset = mesh.vertex_count() as u32;
+            for (k, p) in positions.iter().enumerate() {
+                let world = transform.transform_point3(Vec3::new(p[0], p[1], p[2]));
+                mesh.add_vertex(world.x, world.y, world.z);
+                let normal = normals.as_ref().and_then(|n| n.get(k)).map_or(Vec3::ZERO, |n| {
+                    (normal_matrix * Vec3::new(n[0], n[1], n[2])).normalize_or_zero()
+                });
+                mesh.add_normal(normal.x, normal.y, normal.z);
+                mesh.add_color(1.0, 1.0, 1.0, 1.0);
+            }
+            missing_normals |= normals.is_none();
+            for tri in indices.chunks_exact(3) {
+                if mirrored {
+                    mesh.add_triangle(offset + tri[0], offset + tri[2], offset + tri[1]);
+                } else {
+                    mesh.add_triangle(offset + tri[0], offset + tri[1], offset + tri[2]);
+                }
+            }
+        }
+        if missing_normals {
+            mesh.compute_normals_with_angle(DEFAULT_FEATURE_ANGLE_DEG);
+        }
+        Ok((mesh, color.unwrap_or([1.0; 4])))
+    }
+
+    /// Elements of an accessor as floats (integer components normalized as
+    /// the accessor says), padded to `N` components with 1.0 (for RGB colors)
+    fn read_accessor<const N: usize>(&self, index: usize) -> Result<Vec<[f32; N]>, String> {
+        let normalized = self.get("accessors", index)?["normalized"].as_bool().unwrap_or(false);
+        let (values, components) = self.read_raw(index, normalized)?;
+        Ok(values
+            .chunks_exact(components)
+            .map(|c| std::array::from_fn(|k| c.get(k).map_or(1.0, |&v| v as f32)))
+            .collect())
+    }
+
+    /// Index accessor as u32
+    fn read_indices(&self, index: usize) -> Result<Vec<u32>, String> {
+        let accessor = self.get("accessors", index)?;
+        if accessor["normalized"].as_bool() == Some(true) {
+            return Err(format!("glTF accessor {} is not an index list", index));
+        }
+        let (values, _) = self.read_raw(index, false)?;
+        Ok(values.into_iter().map(|v| v as u32).collect())
+    }
+
+    /// Every component of an accessor, and the components per element
+    fn read_raw(&self, index: usize, normalized: bool) -> Result<(Vec<f64>, usize), String> {
+        let accessor = self.get("accessors", index)?;
+        if !accessor["sparse"].is_null() {
+            return Err(format!("glTF accessor {} is sparse (not supported)", index));
+        }
+        let components = match accessor["type"].as_str() {
+            Some("SCALAR") => 1,
+            Some("VEC2") => 2,
+            Some("VEC3") => 3,
+            Some("VEC4") => 4,
+            Some("MAT2") => 4,
+            Some("MAT3") => 9,
+            Some("MAT4") => 16,
+            other => return Err(format!("glTF accessor {} has unknown type {:?}", index, other)),
+        };
+        let component_type = accessor["componentType"].as_u64().unwrap_or(0);
+        let size = match component_type {
+            5120 | 5121 => 1,
+            5122 | 5123 => 2,
+            5125 | 5126 => 4,
+            other => return Err(format!("glTF accessor {} has unknown component type {}", index, other)),
+        };
+        let count = accessor["count"].as_u64().unwrap_or(0) as usize;
+        // No buffer view: all zeros
+        let Some(view_index) = accessor["bufferView"].as_u64() else {
+            return Ok((vec![0.0; count * components], components));
+        };
+        let view = self.get("bufferViews", view_index as usize)?;
+        let buffer = self
+            .buffers
+            .get(view["buffer"].as_u64().unwrap_or(0) as usize)
+            .ok_or_else(|| format!("glTF buffer view {} has no buffer", view_index))?;
+        let start = view["byteOffset"].as_u64().unwrap_or(0) as usize + accessor["byteOffset"].as_u64().unwrap_or(0) as usize;
+        let stride = view["byteStride"].as_u64().map_or(components * size, |s| s as usize);
+
+        let mut values = Vec::with_capacity(count * components);
+        for element in 0..count {
+            for k in 0..components {
+                let at = start + element * stride + k * size;
+                let bytes = buffer
+                    .get(at..at + size)
+                    .ok_or_else(|| format!("glTF accessor {} runs past its buffer", index))?;
+                let value = match component_type {
+                    5120 => (bytes[0] as i8) as f64,
+                    5121 => bytes[0] as f64,
+                    5122 => i16::from_le_bytes([bytes[0], bytes[1]]) as f64,
+                    5123 => u16::from_le_bytes([bytes[0], bytes[1]]) as f64,
+                    5125 => u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]) as f64,
+                    _ => f32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]) as f64,
+                };
+                values.push(match (normalized, component_type) {
+                    (true, 5120) => (value / 127.0).max(-1.0),
+                    (true, 5121) => value / 255.0,
+                    (true, 5122) => (value / 32767.0).max(-1.0),
+                    (true, 5123) => value / 65535.0,
+                    _ => value,
+                });
+            }
+        }
+        Ok((values, components))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bim::{GltfExport, IfcFile};
+
+    /// A 2 x 1 m quad (4 positions, 6 u16 indices) in a base64 data URI,
+    /// on a child node moved 10 m along X by its parent
+    const QUAD_GLTF: &str = r#"{
+  "asset": { "version": "2.0" },
+  "scene": 0,
+  "scenes": [{ "nodes": [0] }],
+  "nodes": [
+    { "name": "Group", "translation": [10, 0, 0], "children": [1] },
+    { "name": "Panel", "mesh": 0 }
+  ],
+  "meshes": [{ "primitives": [{ "attributes": { "POSITION": 0 }, "indices": 1, "material": 0 }] }],
+  "materials": [{ "pbrMetallicRoughness": { "baseColorFactor": [1, 0, 0, 1] } }],
+  "accessors": [
+    { "bufferView": 0, "componentType": 5126, "count": 4, "type": "VEC3" },
+    { "bufferView": 1, "componentType": 5123, "count": 6, "type": "SCALAR" }
+  ],
+  "bufferViews": [
+    { "buffer": 0, "byteOffset": 0, "byteLength": 48 },
+    { "buffer": 0, "byteOffset": 48, "byteLength": 12 }
+  ],
+  "buffers": [{
+    "byteLength": 60,
+    "uri": "data:application/octet-stream;base64,AAAAAAAAAAAAAAAAAAAAQAAAAAAAAAAAAAAAQAAAgD8AAAAAAAAAAAAAgD8AAAAAAAABAAIAAAACAAMA"
+  }]
+}"#;
+
+    #[test]
+    fn test_imported_gltf_keeps_vertex_and_index_counts() {
+        assert_eq!(decode_base64("TWE=").unwrap(), b"Ma");
+        assert_eq!(decode_base64("TWFu").unwrap(), b"Man");
+
+        let model = parse_gltf(QUAD_GLTF.as_bytes(), None, "quad").unwrap();
+        assert_eq!(model.proxies.len(), 1);
+        let panel = &model.proxies[0].product;
+        assert_eq!((panel.global_id.as_str(), panel.name.as_deref()), ("quad:1", Some("Panel")));
+        let body = &model.body_meshes["quad:1"];
+        assert_eq!((body.vertex_count(), body.indices.len()), (4, 6));
+        assert_eq!(model.materials["quad:1"].color, [1.0, 0.0, 0.0]);
+
+        // Drawn where the file puts it, in its material color
+        let mesh = model.generate_meshes();
+        assert_eq!((mesh.vertices.len() / 3, mesh.indices.len()), (4, 6));
+        let bounds = mesh.bounds.unwrap();
+        assert_eq!((bounds.min, bounds.max), ([10.0, 0.0, 0.0], [12.0, 1.0, 0.0]));
+        assert_eq!(&mesh.colors[..4], &[1.0, 0.0, 0.0, 1.0]);
+
+        // Our own export, with an external buffer and as a GLB
+        let content = include_str!("../../../test/sample_architectural.ifc");
+        let source = BimModel::from_ifc_file(&IfcFile::parse(content).unwrap()).unwrap().generate_meshes();
+        let mut export = GltfExport::new();
+        for element in &source.elements {
+            export.add_element(element, &source.element_mesh(element));
+        }
+        let dir = std::env::temp_dir().join(format!("gltf_import_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        for file in ["model.gltf", "model.glb"] {
+            export.write(&dir.join(file)).unwrap();
+            let imported = import_gltf(&dir.join(file)).unwrap();
+            let mesh = imported.generate_meshes();
+            assert_eq!(imported.element_count, source.elements.len(), "{}", file);
+            assert_eq!(mesh.vertices.len(), source.vertices.len(), "{}", file);
+            assert_eq!(mesh.indices.len(), source.indices.len(), "{}", file);
+            let ids: Vec<&str> = mesh.elements.iter().map(|e| e.global_id.as_str()).collect();
+            let expected: Vec<&str> = source.elements.iter().map(|e| e.global_id.as_str()).collect();
+            assert_eq!(ids, expected, "{}", file);
+        }
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,111 @@
+//! Element Handles
+//!
+//! Compact `u32` stand-ins for GlobalIds, for passing element sets across
+//! FFI every frame (selection, visibility, coloring) without 22-character
+//! strings. Each GlobalId gets a handle the first time a model containing
+//! it is registered, numbered from 1 in the model's element order; handles
+//! are never reused or reassigned, so unloading and reloading a file gives
+//! its elements the same handles again. 0 is never a handle (background in
+//! id buffers).
+
+use super::model::BimModel;
+use std::collections::HashMap;
+
+/// Two-way map between GlobalIds and their handles
+#[derive(Debug, Clone, Default)]
+pub struct ElementHandles {
+    /// Handle of each GlobalId
+    handles: HashMap<String, u32>,
+    /// GlobalId of handle k + 1
+    global_ids: Vec<String>,
+}
+
+impl ElementHandles {
+    /// Handle of a GlobalId, assigning the next one if it has none
+    pub fn assign(&mut self, global_id: &str) -> u32 {
+        if let Some(&handle) = self.handles.get(global_id) {
+            return handle;
+        }
+        self.global_ids.push(global_id.to_string());
+        let handle = self.global_ids.len() as u32;
+        self.handles.insert(global_id.to_string(), handle);
+        handle
+    }
+
+    /// Assign handles to every element of a model, in its element order
+    pub fn assign_model(&mut self, model: &BimModel) {
+        for (_, product) in model.products() {
+            self.assign(&product.global_id);
+        }
+    }
+
+    /// Handle of a GlobalId, if it has one
+    pub fn handle(&self, global_id: &str) -> Option<u32> {
+        self.handles.get(global_id).copied()
+    }
+
+    /// GlobalId a handle stands for
+    pub fn global_id(&self, handle: u32) -> Option<&str> {
+        let index = handle.checked_sub(1)? as usize;
+        self.global_ids.get(index).map(String::as_str)
+    }
+
+    /// Number of handles assigned
+    pub fn len(&self) -> usize {
+        self.global_ids.len()
+    }
+
+    /// Whether no handles have been assigned
+    pub fn is_empty(&self) -> bool {
+        self.global_ids.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::bim::{BimModel, IfcFile, ModelRegistry};
+
+    fn load(registry: &mut ModelRegistry) -> String {
+        let content = include_str!("../../../test/sample_architectural.ifc");
+        let model = BimModel::from_ifc_file(&IfcFile::parse(content).unwrap()).unwrap();
+        registry.add_model(model, "sample".to_string(), None)
+    }
+
+    #[test]
+    fn test_handles_stable_across_reload() {
+        let mut registry = ModelRegistry::new();
+        let id = load(&mut registry);
+        let global_ids: Vec<String> = registry
+            .get_model(&id)
+            .unwrap()
+            .model
+            .products()
+            .into_iter()
+            .map(|(_, p)| p.global_id.clone())
+            .collect();
+        assert!(!global_ids.is_empty());
+        let handles: Vec<u32> = global_ids.iter().map(|g| registry.handle_for_global_id(g).unwrap()).collect();
+        assert_eq!(handles, (1..=global_ids.len() as u32).collect::<Vec<u32>>());
+        for (handle, global_id) in handles.iter().zip(&global_ids) {
+            assert_eq!(registry.global_id_for_handle(*handle), Some(global_id.as_str()));
+        }
+        assert_eq!(registry.global_id_for_handle(0), None);
+        assert_eq!(registry.handle_for_global_id("not-an-element"), None);
+
+        // Reloaded, after other models came and went
+        registry.clear();
+        let mut other = BimModel::new();
+        other.add_placed_body("other".to_string(), None, crate::bim::generate_box(1.0, 1.0, 1.0));
+        registry.add_model(other, "other".to_string(), None);
+        load(&mut registry);
+        let reloaded: Vec<u32> = global_ids.iter().map(|g| registry.handle_for_global_id(g).unwrap()).collect();
+        assert_eq!(reloaded, handles);
+        assert_eq!(registry.handle_for_global_id("other"), Some(handles.len() as u32 + 1));
+
+        // And the same in a fresh session
+        let mut fresh = ModelRegistry::new();
+        load(&mut fresh);
+        let fresh: Vec<u32> = global_ids.iter().map(|g| fresh.handle_for_global_id(g).unwrap()).collect();
+        assert_eq!(fresh, handles);
+    }
+}
@@ -7,13 +7,15 @@ use super::entities::{EntityId, IfcEntity, IfcValue};
 use nom::{
     branch::alt,
     bytes::complete::{tag, take_until, take_while, take_while1},
-    character::complete::{char, digit1, multispace0, one_of},
+    character::complete::{char, digit0, digit1, multispace0, one_of},
     combinator::{map, opt, recognize},
-    multi::{many0, separated_list0},
-    sequence::{delimited, preceded, terminated, tuple},
+    multi::separated_list0,
+    sequence::{delimited, tuple},
     IResult,
 };
-use std::collections::HashMap;
+use std::collections::hash_map::Entry;
+use std::collections::{HashMap, HashSet};
+use std::sync::OnceLock;
 
 /// Parse result type
 type ParseResult<'a, T> = IResult<&'a str, T>;
@@ -23,10 +25,15 @@ type ParseResult<'a, T> = IResult<&'a str, T>;
 pub struct IfcFile {
     pub header: IfcHeader,
     pub entities: HashMap<EntityId, IfcEntity>,
+    /// Problems in the file that parsing worked around (e.g. an entity id
+    /// defined twice, of which the first definition is kept)
+    pub parse_warnings: Vec<String>,
+    /// Radians per plane angle unit, read on first use
+    plane_angle_unit: OnceLock<f64>,
 }
 
 /// IFC Header information
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default)]
 pub struct IfcHeader {
     pub file_description: Vec<String>,
     pub file_name: String,
@@ -36,6 +43,42 @@ pub struct IfcHeader {
     pub preprocessor_version: String,
     pub originating_system: String,
     pub authorization: String,
+    /// Schema identifiers, e.g. "IFC4" or "IFC2X3"
+    pub schema: Vec<String>,
+}
+
+/// IFC schema version of a file, for reading attributes whose position
+/// changed between versions
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IfcSchema {
+    Ifc2x3,
+    Ifc4,
+    Ifc4x3,
+    /// No or unrecognized FILE_SCHEMA (read as IFC4)
+    Unknown,
+}
+
+impl IfcSchema {
+    /// Schema of a FILE_SCHEMA identifier, e.g. "IFC2X3" or "IFC4X3_ADD2"
+    pub fn from_identifier(identifier: &str) -> Self {
+        let identifier = identifier.trim().to_ascii_uppercase();
+        if identifier.starts_with("IFC2X3") {
+            Self::Ifc2x3
+        } else if identifier.starts_with("IFC4X3") {
+            Self::Ifc4x3
+        } else if identifier.starts_with("IFC4") {
+            Self::Ifc4
+        } else {
+            Self::Unknown
+        }
+    }
+}
+
+impl IfcHeader {
+    /// Schema version of the file (its first FILE_SCHEMA identifier)
+    pub fn schema_version(&self) -> IfcSchema {
+        self.schema.first().map_or(IfcSchema::Unknown, |s| IfcSchema::from_identifier(s))
+    }
 }
 
 impl IfcFile {
@@ -44,72 +87,223 @@ impl IfcFile {
         Self {
             header: IfcHeader::default(),
             entities: HashMap::new(),
+            parse_warnings: Vec::new(),
+            plane_angle_unit: OnceLock::new(),
+        }
+    }
+
+    /// Create a file from already parsed entities
+    pub fn from_entities(header: IfcHeader, entities: HashMap<EntityId, IfcEntity>) -> Self {
+        Self {
+            header,
+            entities,
+            ..Self::new()
         }
     }
 
     /// Parse IFC file from string
     pub fn parse(input: &str) -> Result<Self, String> {
+        Self::parse_with_progress(input, |_| {})
+    }
+
+    /// Parse IFC file from string, reporting the fraction of the input
+    /// consumed (0.0-1.0) as entities are read
+    ///
+    /// `1.0` is reported once, when parsing succeeds.
+    pub fn parse_with_progress(input: &str, mut progress: impl FnMut(f32)) -> Result<Self, String> {
         // Normalize line endings (handle both Windows \r\n and Unix \n)
         let normalized = input.replace("\r\n", "\n");
 
-        match parse_ifc_file(&normalized) {
-            Ok((_, ifc_file)) => Ok(ifc_file),
+        match parse_ifc_file(&normalized, &mut progress) {
+            Ok((_, ifc_file)) => {
+                progress(1.0);
+                Ok(ifc_file)
+            }
             Err(e) => Err(format!("Failed to parse IFC file: {:?}", e)),
         }
     }
 
+    /// Parse only the header of an IFC file, ignoring everything after the
+    /// HEADER section (which may be missing or incomplete)
+    pub fn parse_header(input: &str) -> Result<IfcHeader, String> {
+        let normalized = input.replace("\r\n", "\n");
+        let (input, _) = parse_iso_header(&normalized).map_err(|e| format!("Failed to parse IFC header: {:?}", e))?;
+        parse_header_section(input)
+            .map(|(_, header)| header)
+            .map_err(|e| format!("Failed to parse IFC header: {:?}", e))
+    }
+
     /// Get entity by ID
     pub fn get_entity(&self, id: EntityId) -> Option<&IfcEntity> {
         self.entities.get(&id)
     }
 
-    /// Get all entities of a specific type
+    /// Get all entities of a specific type, in `EntityId` order
+    ///
+    /// The order is stable across runs, so everything extracted from these
+    /// lists (element order, exports, draw order) is reproducible.
     pub fn get_entities_by_type(&self, entity_type: &str) -> Vec<&IfcEntity> {
-        self.entities
+        let mut entities: Vec<&IfcEntity> = self
+            .entities
             .values()
             .filter(|e| e.entity_type.eq_ignore_ascii_case(entity_type))
-            .collect()
+            .collect();
+        entities.sort_unstable_by_key(|e| e.id);
+        entities
+    }
+
+    /// Get the entity with a GlobalId (the first attribute of every rooted
+    /// entity: elements, spatial structure, property sets, relationships)
+    pub fn get_entity_by_global_id(&self, global_id: &str) -> Option<&IfcEntity> {
+        self.entities
+            .values()
+            .filter(|e| matches!(e.attributes.first(), Some(IfcValue::String(id)) if id == global_id))
+            .min_by_key(|e| e.id)
+    }
+
+    /// Radians per plane angle unit of the file
+    ///
+    /// Read from the IFCUNITASSIGNMENT: an IFCSIUNIT plane angle unit is the
+    /// radian, an IFCCONVERSIONBASEDUNIT (e.g. DEGREE) carries its factor to
+    /// radians. Files that declare none use radians, the IFC default.
+    pub fn plane_angle_unit(&self) -> f64 {
+        *self.plane_angle_unit.get_or_init(|| {
+            let unit = self
+                .get_entities_by_type("IFCUNITASSIGNMENT")
+                .into_iter()
+                // IFCUNITASSIGNMENT(Units)
+                .flat_map(|assignment| assignment.get_ref_list(0))
+                .filter_map(|id| self.get_entity(id))
+                // IFCSIUNIT and IFCCONVERSIONBASEDUNIT both have UnitType second
+                .find(|unit| matches!(unit.get_attr(1), Some(IfcValue::Enum(t)) if t == "PLANEANGLEUNIT"));
+            match unit {
+                // IFCCONVERSIONBASEDUNIT(Dimensions, UnitType, Name,
+                // ConversionFactor: IFCMEASUREWITHUNIT(ValueComponent, UnitComponent))
+                Some(unit) if unit.entity_type == "IFCCONVERSIONBASEDUNIT" => unit
+                    .get_entity_ref(3)
+                    .and_then(|factor| self.get_entity(factor))
+                    .and_then(|factor| factor.get_real(0))
+                    .filter(|factor| *factor > 0.0)
+                    .unwrap_or(1.0),
+                _ => 1.0,
+            }
+        })
     }
 
     /// Get total entity count
     pub fn entity_count(&self) -> usize {
         self.entities.len()
     }
+
+    /// Follow a chain of references from `start` (placements up to their
+    /// parents, mapped items to their sources), returning the entities
+    /// visited in order, `start` first
+    ///
+    /// `follow` gives the next entity of the chain, or `None` where it
+    /// ends. Fails on a reference back into the chain (a cycle in a
+    /// malformed file), a reference to a missing entity, or a chain longer
+    /// than `max_depth` entities, instead of looping or truncating.
+    pub fn resolve_chain(
+        &self,
+        start: EntityId,
+        max_depth: usize,
+        mut follow: impl FnMut(&IfcEntity) -> Option<EntityId>,
+    ) -> Result<Vec<&IfcEntity>, String> {
+        let mut chain = Vec::new();
+        let mut visited = HashSet::new();
+        let mut next = Some(start);
+        while let Some(id) = next {
+            if !visited.insert(id) {
+                return Err(format!("Reference cycle through #{}", id));
+            }
+            if chain.len() == max_depth {
+                return Err(format!("Reference chain from #{} longer than {}", start, max_depth));
+            }
+            let entity = self
+                .get_entity(id)
+                .ok_or_else(|| format!("Unresolved reference #{}", id))?;
+            next = follow(entity);
+            chain.push(entity);
+        }
+        Ok(chain)
+    }
+
+    /// Add the entities of another file (an addendum or another part of a
+    /// split export), keeping this file's header
+    ///
+    /// The other file's ids are shifted past this file's highest id so none
+    /// collide, and its references are shifted with them. Returns the new id
+    /// of each of its entities (old id → new id). Its parse warnings are
+    /// appended. Fails, leaving this file unchanged, if the shifted ids
+    /// would overflow.
+    pub fn merge(&mut self, other: IfcFile) -> Result<HashMap<EntityId, EntityId>, String> {
+        let offset = self.entities.keys().copied().max().unwrap_or(0).max(0);
+        let highest = other.entities.keys().copied().max().unwrap_or(0);
+        if highest.checked_add(offset).is_none() {
+            return Err(format!("Entity ids overflow when merging: #{} shifted by {}", highest, offset));
+        }
+
+        let mut remap = HashMap::with_capacity(other.entities.len());
+        for (id, mut entity) in other.entities {
+            entity.id = id + offset;
+            for attribute in &mut entity.attributes {
+                shift_references(attribute, offset);
+            }
+            remap.insert(id, entity.id);
+            self.entities.insert(entity.id, entity);
+        }
+        self.parse_warnings.extend(other.parse_warnings);
+        self.plane_angle_unit = OnceLock::new();
+        Ok(remap)
+    }
+}
+
+/// Shift every entity reference in a value by `offset` (saturating, so
+/// references past the id range stay unresolvable)
+fn shift_references(value: &mut IfcValue, offset: EntityId) {
+    match value {
+        IfcValue::EntityRef(id) => *id = id.saturating_add(offset),
+        IfcValue::List(items) => items.iter_mut().for_each(|item| shift_references(item, offset)),
+        _ => {}
+    }
 }
 
-impl Default for IfcHeader {
+impl Default for IfcFile {
     fn default() -> Self {
-        Self {
-            file_description: Vec::new(),
-            file_name: String::new(),
-            time_stamp: String::new(),
-            author: Vec::new(),
-            organization: Vec::new(),
-            preprocessor_version: String::new(),
-            originating_system: String::new(),
-            authorization: String::new(),
-        }
+        Self::new()
     }
 }
 
 /// Parse complete IFC file
-fn parse_ifc_file(input: &str) -> ParseResult<IfcFile> {
+fn parse_ifc_file<'a>(input: &'a str, progress: &mut dyn FnMut(f32)) -> ParseResult<'a, IfcFile> {
+    let total = input.len().max(1);
     let (input, _) = parse_iso_header(input)?;
     let (input, header) = parse_header_section(input)?;
-    let (input, entities) = parse_data_section(input)?;
+    let (input, entities) = parse_data_section(input, &mut |remaining| {
+        // Stay below 1.0 until the whole file has parsed
+        progress(((total - remaining) as f32 / total as f32).min(0.99))
+    })?;
     let (input, _) = parse_iso_footer(input)?;
 
-    Ok((
-        input,
-        IfcFile {
-            header,
-            entities: entities.into_iter().map(|e| (e.id, e)).collect(),
-        },
-    ))
+    let mut file = IfcFile::from_entities(header, HashMap::with_capacity(entities.len()));
+    for entity in entities {
+        match file.entities.entry(entity.id) {
+            Entry::Vacant(slot) => {
+                slot.insert(entity);
+            }
+            Entry::Occupied(first) => file.parse_warnings.push(format!(
+                "Duplicate entity id #{}: kept {}, ignored {}",
+                entity.id,
+                first.get().entity_type,
+                entity.entity_type
+            )),
+        }
+    }
+    Ok((input, file))
 }
 
 /// Parse ISO 10303-21 header
-fn parse_iso_header(input: &str) -> ParseResult<()> {
+fn parse_iso_header(input: &str) -> ParseResult<'_, ()> {
     let (input, _) = multispace0(input)?;
     let (input, _) = tag("ISO-10303-21;")(input)?;
     let (input, _) = multispace0(input)?;
@@ -117,32 +311,100 @@ fn parse_iso_header(input: &str) -> ParseResult<()> {
 }
 
 /// Parse ISO 10303-21 footer
-fn parse_iso_footer(input: &str) -> ParseResult<()> {
+fn parse_iso_footer(input: &str) -> ParseResult<'_, ()> {
     let (input, _) = multispace0(input)?;
     let (input, _) = tag("END-ISO-10303-21;")(input)?;
     Ok((input, ()))
 }
 
 /// Parse HEADER section
-fn parse_header_section(input: &str) -> ParseResult<IfcHeader> {
-    let (input, _) = tag("HEADER;")(input)?;
-    let (input, _) = multispace0(input)?;
+///
+/// FILE_DESCRIPTION, FILE_NAME and FILE_SCHEMA fill the header; other
+/// entries, and anything unreadable, are skipped.
+fn parse_header_section(input: &str) -> ParseResult<'_, IfcHeader> {
+    let (mut input, _) = tag("HEADER;")(input)?;
+    let mut header = IfcHeader::default();
+
+    while let Ok((rest, (name, attributes))) = parse_header_entry(input) {
+        input = rest;
+        let text = |i: usize| match attributes.get(i) {
+            Some(IfcValue::String(s)) => s.clone(),
+            _ => String::new(),
+        };
+        let texts = |i: usize| match attributes.get(i) {
+            Some(IfcValue::List(values)) => values
+                .iter()
+                .filter_map(|v| match v {
+                    IfcValue::String(s) => Some(s.clone()),
+                    _ => None,
+                })
+                .collect(),
+            _ => Vec::new(),
+        };
+        match name.as_str() {
+            // FILE_DESCRIPTION(Description, ImplementationLevel)
+            "FILE_DESCRIPTION" => header.file_description = texts(0),
+            // FILE_NAME(Name, TimeStamp, Author, Organization,
+            // PreprocessorVersion, OriginatingSystem, Authorization)
+            "FILE_NAME" => {
+                header.file_name = text(0);
+                header.time_stamp = text(1);
+                header.author = texts(2);
+                header.organization = texts(3);
+                header.preprocessor_version = text(4);
+                header.originating_system = text(5);
+                header.authorization = text(6);
+            }
+            // FILE_SCHEMA(Schemas)
+            "FILE_SCHEMA" => header.schema = texts(0),
+            _ => {}
+        }
+    }
 
-    // For now, skip header parsing and use default
-    // TODO: Implement full header parsing
     let (input, _) = take_until("ENDSEC;")(input)?;
     let (input, _) = tag("ENDSEC;")(input)?;
     let (input, _) = multispace0(input)?;
 
-    Ok((input, IfcHeader::default()))
+    Ok((input, header))
 }
 
-/// Parse DATA section
-fn parse_data_section(input: &str) -> ParseResult<Vec<IfcEntity>> {
-    let (input, _) = tag("DATA;")(input)?;
+/// Parse a header entry: FILE_NAME(...);
+fn parse_header_entry(input: &str) -> ParseResult<'_, (String, Vec<IfcValue>)> {
     let (input, _) = multispace0(input)?;
+    let (input, name) = parse_entity_type(input)?;
+    let (input, attributes) = parse_attribute_list(input)?;
+    let (input, _) = char(';')(input)?;
+    Ok((input, (name, attributes)))
+}
 
-    let (input, entities) = many0(parse_entity_instance)(input)?;
+/// Parse DATA section
+/// Entities parsed between progress reports
+const PROGRESS_INTERVAL: usize = 1000;
+
+/// Parse the DATA section, reporting the remaining input length every
+/// `PROGRESS_INTERVAL` entities
+fn parse_data_section<'a>(
+    input: &'a str,
+    progress: &mut dyn FnMut(usize),
+) -> ParseResult<'a, Vec<IfcEntity>> {
+    let (input, _) = tag("DATA;")(input)?;
+    let (mut input, _) = multispace0(input)?;
+
+    // Same as many0(parse_entity_instance), with progress
+    let mut entities = Vec::new();
+    loop {
+        match parse_entity_instance(input) {
+            Ok((rest, entity)) if rest.len() < input.len() => {
+                input = rest;
+                entities.push(entity);
+                if entities.len() % PROGRESS_INTERVAL == 0 {
+                    progress(input.len());
+                }
+            }
+            Ok(_) | Err(nom::Err::Error(_)) => break,
+            Err(e) => return Err(e),
+        }
+    }
 
     let (input, _) = multispace0(input)?;
     let (input, _) = tag("ENDSEC;")(input)?;
@@ -151,7 +413,7 @@ fn parse_data_section(input: &str) -> ParseResult<Vec<IfcEntity>> {
 }
 
 /// Parse a single entity instance: #123=IFCWALL(...);
-fn parse_entity_instance(input: &str) -> ParseResult<IfcEntity> {
+fn parse_entity_instance(input: &str) -> ParseResult<'_, IfcEntity> {
     let (input, _) = multispace0(input)?;
     let (input, id) = parse_entity_id(input)?;
     let (input, _) = char('=')(input)?;
@@ -171,7 +433,7 @@ fn parse_entity_instance(input: &str) -> ParseResult<IfcEntity> {
 }
 
 /// Parse entity ID: #123
-fn parse_entity_id(input: &str) -> ParseResult<EntityId> {
+fn parse_entity_id(input: &str) -> ParseResult<'_, EntityId> {
     let (input, _) = char('#')(input)?;
     let (input, id_str) = digit1(input)?;
     let id = id_str.parse::<EntityId>().map_err(|_| {
@@ -181,13 +443,13 @@ fn parse_entity_id(input: &str) -> ParseResult<EntityId> {
 }
 
 /// Parse entity type: IFCWALL
-fn parse_entity_type(input: &str) -> ParseResult<String> {
+fn parse_entity_type(input: &str) -> ParseResult<'_, String> {
     let (input, type_str) = take_while1(|c: char| c.is_alphanumeric() || c == '_')(input)?;
     Ok((input, type_str.to_uppercase()))
 }
 
 /// Parse attribute list: (attr1,attr2,attr3)
-fn parse_attribute_list(input: &str) -> ParseResult<Vec<IfcValue>> {
+fn parse_attribute_list(input: &str) -> ParseResult<'_, Vec<IfcValue>> {
     delimited(
         char('('),
         separated_list0(char(','), parse_value),
@@ -196,10 +458,11 @@ fn parse_attribute_list(input: &str) -> ParseResult<Vec<IfcValue>> {
 }
 
 /// Parse a single value
-fn parse_value(input: &str) -> ParseResult<IfcValue> {
+fn parse_value(input: &str) -> ParseResult<'_, IfcValue> {
     let (input, _) = multispace0(input)?;
     let result = alt((
         map(tag("$"), |_| IfcValue::Null),
+        map(tag("*"), |_| IfcValue::Derived),
         map(parse_entity_ref, IfcValue::EntityRef),
         map(parse_string, IfcValue::String),
         map(parse_float, IfcValue::Real),
@@ -207,26 +470,44 @@ fn parse_value(input: &str) -> ParseResult<IfcValue> {
         map(parse_boolean, IfcValue::Boolean), // Must come before parse_enum
         map(parse_enum, IfcValue::Enum),
         map(parse_list, IfcValue::List),
+        parse_typed_value,
     ))(input)?;
     let (_input, _) = multispace0(input)?;
     Ok(result)
 }
 
 /// Parse entity reference: #123
-fn parse_entity_ref(input: &str) -> ParseResult<EntityId> {
+fn parse_entity_ref(input: &str) -> ParseResult<'_, EntityId> {
     parse_entity_id(input)
 }
 
-/// Parse string: 'hello'
-fn parse_string(input: &str) -> ParseResult<String> {
-    let (input, _) = char('\'')(input)?;
-    let (input, content) = take_while(|c| c != '\'')(input)?;
-    let (input, _) = char('\'')(input)?;
-    Ok((input, content.to_string()))
+/// Parse string: 'hello' (a quote inside is doubled: 'O''Brien')
+fn parse_string(input: &str) -> ParseResult<'_, String> {
+    let (mut input, _) = char('\'')(input)?;
+    let mut content = String::new();
+    loop {
+        let (rest, part) = take_while(|c| c != '\'')(input)?;
+        content.push_str(part);
+        let (rest, _) = char('\'')(rest)?;
+        match rest.strip_prefix('\'') {
+            Some(rest) => {
+                content.push('\'');
+                input = rest;
+            }
+            None => return Ok((rest, content)),
+        }
+    }
+}
+
+/// Parse a typed value: IFCLABEL('Level 1') or IFCBOOLEAN(.T.)
+/// The type name is dropped; only the value is kept.
+fn parse_typed_value(input: &str) -> ParseResult<'_, IfcValue> {
+    let (input, _) = take_while1(|c: char| c.is_alphanumeric() || c == '_')(input)?;
+    delimited(char('('), parse_value, char(')'))(input)
 }
 
 /// Parse integer: 123 or -456
-fn parse_integer(input: &str) -> ParseResult<i64> {
+fn parse_integer(input: &str) -> ParseResult<'_, i64> {
     let (input, sign) = opt(one_of("+-"))(input)?;
     let (input, digits) = digit1(input)?;
 
@@ -249,12 +530,12 @@ fn parse_integer(input: &str) -> ParseResult<i64> {
     Ok((input, value))
 }
 
-/// Parse float: 123.456 or -0.5 or 1.5E-3
-fn parse_float(input: &str) -> ParseResult<f64> {
+/// Parse float: 123.456 or -0.5 or 1.5E-3 or 2000000. (STEP allows a bare trailing dot)
+fn parse_float(input: &str) -> ParseResult<'_, f64> {
     let (input, sign) = opt(one_of("+-"))(input)?;
     let (input, num_str) = recognize(tuple((
         digit1,
-        opt(tuple((char('.'), digit1))),
+        opt(tuple((char('.'), digit0))),
         opt(tuple((one_of("eE"), opt(one_of("+-")), digit1))),
     )))(input)?;
 
@@ -270,7 +551,7 @@ fn parse_float(input: &str) -> ParseResult<f64> {
 }
 
 /// Parse enumeration: .ENUMVALUE.
-fn parse_enum(input: &str) -> ParseResult<String> {
+fn parse_enum(input: &str) -> ParseResult<'_, String> {
     let (input, _) = char('.')(input)?;
     let (input, value) = take_while1(|c: char| c.is_alphanumeric() || c == '_')(input)?;
     let (input, _) = char('.')(input)?;
@@ -278,7 +559,7 @@ fn parse_enum(input: &str) -> ParseResult<String> {
 }
 
 /// Parse boolean: .T. or .F.
-fn parse_boolean(input: &str) -> ParseResult<bool> {
+fn parse_boolean(input: &str) -> ParseResult<'_, bool> {
     alt((
         map(tag(".T."), |_| true),
         map(tag(".F."), |_| false),
@@ -288,7 +569,7 @@ fn parse_boolean(input: &str) -> ParseResult<bool> {
 }
 
 /// Parse list: (val1,val2,val3)
-fn parse_list(input: &str) -> ParseResult<Vec<IfcValue>> {
+fn parse_list(input: &str) -> ParseResult<'_, Vec<IfcValue>> {
     delimited(
         char('('),
         separated_list0(char(','), parse_value),
@@ -300,6 +581,38 @@ fn parse_list(input: &str) -> ParseResult<Vec<IfcValue>> {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_resolve_chain_detects_cycle() {
+        // #3 places relative to #1, which places relative to #3
+        let file = IfcFile::parse(
+            "ISO-10303-21;
+HEADER;
+FILE_SCHEMA(('IFC4'));
+ENDSEC;
+DATA;
+#1=IFCLOCALPLACEMENT(#3,$);
+#2=IFCLOCALPLACEMENT(#1,$);
+#3=IFCLOCALPLACEMENT(#2,$);
+#4=IFCLOCALPLACEMENT($,$);
+#5=IFCLOCALPLACEMENT(#4,$);
+#6=IFCLOCALPLACEMENT(#99,$);
+ENDSEC;
+END-ISO-10303-21;
+",
+        )
+        .unwrap();
+        let parent = |e: &IfcEntity| e.get_entity_ref(0);
+
+        let err = file.resolve_chain(2, 64, parent).unwrap_err();
+        assert!(err.contains("cycle"), "{}", err);
+
+        let ids: Vec<EntityId> = file.resolve_chain(5, 64, parent).unwrap().iter().map(|e| e.id).collect();
+        assert_eq!(ids, [5, 4]);
+        assert!(file.resolve_chain(5, 1, parent).is_err());
+        assert!(file.resolve_chain(6, 64, parent).is_err());
+        assert!(file.resolve_chain(42, 64, parent).is_err());
+    }
+
     #[test]
     fn test_parse_entity_id() {
         assert_eq!(parse_entity_id("#123"), Ok(("", 123)));
@@ -318,6 +631,45 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_parse_quoted_and_typed_values() {
+        assert_eq!(parse_string("'O''Brien, Ltd'"), Ok(("", "O'Brien, Ltd".to_string())));
+        let (_, list) = parse_list("(IFCLABEL('Level 1'),IFCBOOLEAN(.T.),IFCLENGTHMEASURE(2.5))").unwrap();
+        assert!(matches!(&list[0], IfcValue::String(s) if s == "Level 1"));
+        assert!(matches!(list[1], IfcValue::Boolean(true)));
+        assert!(matches!(list[2], IfcValue::Real(v) if v == 2.5));
+    }
+
+    #[test]
+    fn test_parse_header_fields() {
+        let header = IfcFile::parse_header(
+            "ISO-10303-21;
+HEADER;
+FILE_DESCRIPTION(('ViewDefinition [CoordinationView]'),'2;1');
+FILE_NAME('house.ifc','2024-03-01T10:00:00',('Jane Doe','John Roe'),('Acme'),'IfcOpenShell','Revit 2024',$);
+FILE_SCHEMA(('IFC4'));
+ENDSEC;
+DATA;
+this is not parsed",
+        )
+        .unwrap();
+        assert_eq!(header.file_description, ["ViewDefinition [CoordinationView]"]);
+        assert_eq!(header.file_name, "house.ifc");
+        assert_eq!(header.time_stamp, "2024-03-01T10:00:00");
+        assert_eq!(header.author, ["Jane Doe", "John Roe"]);
+        assert_eq!(header.organization, ["Acme"]);
+        assert_eq!(header.originating_system, "Revit 2024");
+        assert_eq!(header.authorization, "");
+        assert_eq!(header.schema, ["IFC4"]);
+        assert_eq!(header.schema_version(), IfcSchema::Ifc4);
+        assert_eq!(IfcSchema::from_identifier("IFC2X3"), IfcSchema::Ifc2x3);
+        assert_eq!(IfcSchema::from_identifier("ifc4x3_add2"), IfcSchema::Ifc4x3);
+        assert_eq!(IfcHeader::default().schema_version(), IfcSchema::Unknown);
+
+        let file = IfcFile::parse(include_str!("../../../test/sample_building.ifc")).unwrap();
+        assert!(!file.header.schema.is_empty());
+    }
+
     #[test]
     fn test_parse_integer() {
         assert_eq!(parse_integer("123"), Ok(("", 123)));
@@ -350,4 +702,106 @@ mod tests {
         let (_, list) = result.unwrap();
         assert_eq!(list.len(), 3);
     }
+
+    #[test]
+    fn test_duplicate_entity_id_keeps_first_with_warning() {
+        let content = "ISO-10303-21;
+HEADER;
+FILE_SCHEMA(('IFC4'));
+ENDSEC;
+DATA;
+#1=IFCWALL('wall-guid',$,'Wall',$,$,$,$,$,$);
+#2=IFCSLAB('slab-guid',$,'Slab',$,$,$,$,$,$);
+#1=IFCCOLUMN('column-guid',$,'Column',$,$,$,$,$,$);
+ENDSEC;
+END-ISO-10303-21;
+";
+        let file = IfcFile::parse(content).unwrap();
+        assert_eq!(file.entity_count(), 2);
+        assert_eq!(file.get_entity(1).unwrap().entity_type, "IFCWALL");
+        assert_eq!(file.parse_warnings, ["Duplicate entity id #1: kept IFCWALL, ignored IFCCOLUMN"]);
+
+        let model = crate::bim::BimModel::from_ifc_file(&file).unwrap();
+        assert_eq!(model.parse_warnings, file.parse_warnings);
+        assert!(IfcFile::parse(&content.replace("#1=IFCCOLUMN", "#3=IFCCOLUMN")).unwrap().parse_warnings.is_empty());
+    }
+
+    #[test]
+    fn test_merge_shifts_overlapping_ids_and_references() {
+        let base = "ISO-10303-21;
+HEADER;
+FILE_SCHEMA(('IFC4'));
+ENDSEC;
+DATA;
+#1=IFCCARTESIANPOINT((0.,0.,0.));
+#2=IFCAXIS2PLACEMENT3D(#1,$,$);
+#3=IFCWALL('base-wall',$,'Base',$,$,#2,$,$,$);
+ENDSEC;
+END-ISO-10303-21;
+";
+        let delta = "ISO-10303-21;
+HEADER;
+FILE_SCHEMA(('IFC4'));
+ENDSEC;
+DATA;
+#1=IFCCARTESIANPOINT((5.,0.,0.));
+#2=IFCCARTESIANPOINT((6.,0.,0.));
+#3=IFCPOLYLINE((#1,#2));
+#4=IFCWALL('delta-wall',$,'Delta',$,$,$,#3,$,$);
+#4=IFCSLAB('dup-slab',$,$,$,$,$,$,$,$);
+ENDSEC;
+END-ISO-10303-21;
+";
+        let mut file = IfcFile::parse(base).unwrap();
+        let before = file.clone();
+        let delta = IfcFile::parse(delta).unwrap();
+        let delta_count = delta.entity_count();
+        let remap = file.merge(delta).unwrap();
+
+        assert_eq!(remap, HashMap::from([(1, 4), (2, 5), (3, 6), (4, 7)]));
+        assert_eq!(file.entity_count(), before.entity_count() + delta_count);
+        // Base entities untouched
+        for (id, entity) in &before.entities {
+            assert_eq!(file.get_entity(*id).unwrap().attribute_views(), entity.attribute_views());
+        }
+        // Delta references follow their targets, nested ones included
+        let polyline = file.get_entity(6).unwrap();
+        assert_eq!(polyline.get_ref_list(0), [4, 5]);
+        let wall = file.get_entity_by_global_id("delta-wall").unwrap();
+        assert_eq!((wall.id, wall.get_entity_ref(6)), (7, Some(6)));
+        assert_eq!(file.get_entity(4).unwrap().attribute_views()[0].value, "(5.,0.,0.)");
+        assert_eq!(file.parse_warnings.len(), 1);
+
+        let mut huge = IfcFile::new();
+        huge.entities.insert(EntityId::MAX, IfcEntity { id: EntityId::MAX, entity_type: "IFCWALL".into(), attributes: vec![] });
+        assert!(file.merge(huge).is_err());
+        assert_eq!(file.entity_count(), before.entity_count() + delta_count);
+    }
+
+    #[test]
+    fn test_wall_attributes_read_back_as_step() {
+        let content = "ISO-10303-21;
+HEADER;
+FILE_SCHEMA(('IFC4'));
+ENDSEC;
+DATA;
+#5=IFCWALL('2O2Fr$t4X7Zf8NOew3FLOH',#2,'O''Neil Wall',$,$,#6,#7,'tag',.STANDARD.);
+#8=IFCCARTESIANPOINT((0.,1.5,-2.25E-1));
+ENDSEC;
+END-ISO-10303-21;
+";
+        let file = IfcFile::parse(content).unwrap();
+        let wall = file.get_entity_by_global_id("2O2Fr$t4X7Zf8NOew3FLOH").unwrap();
+        assert_eq!(wall.id, 5);
+        let values: Vec<String> = wall.attribute_views().into_iter().map(|a| a.value).collect();
+        assert_eq!(
+            values,
+            ["'2O2Fr$t4X7Zf8NOew3FLOH'", "#2", "'O''Neil Wall'", "$", "$", "#6", "#7", "'tag'", ".STANDARD."]
+        );
+        let views = wall.attribute_views();
+        assert_eq!((views[1].index, views[1].kind.as_str()), (1, "EntityRef"));
+        assert_eq!(views[8].kind, "Enum");
+        assert_eq!(file.get_entity(8).unwrap().attribute_views()[0].value, "(0.,1.5,-0.225)");
+        assert!(file.get_entity_by_global_id("missing").is_none());
+    }
 }
@@ -0,0 +1,112 @@
+//! Color Legends
+//!
+//! The key to a thematic coloring, built where the colors are chosen so the
+//! UI shows exactly what was drawn: one entry per category for discrete
+//! colorings (types, change status), or samples of a continuous ramp at
+//! regular intervals.
+
+use super::diff::{ModelDiff, ADDED_COLOR, MODIFIED_COLOR, REMOVED_COLOR};
+use super::model::BimModel;
+use serde::{Deserialize, Serialize};
+
+/// Labels and colors (RGBA, 0.0-1.0) of a coloring, in display order
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Legend {
+    pub entries: Vec<(String, [f32; 4])>,
+}
+
+impl Legend {
+    /// Legend of coloring by type: each element type present in `models`,
+    /// sorted, with its palette color
+    ///
+    /// Elements with a material style of their own keep it, so only
+    /// elements without one are drawn in these colors.
+    pub fn of_types<'a>(models: impl IntoIterator<Item = &'a BimModel>) -> Self {
+        let mut entries: Vec<(String, [f32; 4])> = Vec::new();
+        for model in models {
+            for (element_type, _) in model.products() {
+                if !entries.iter().any(|(label, _)| label == element_type) {
+                    entries.push((element_type.to_string(), model.palette.color_for(element_type)));
+                }
+            }
+        }
+        entries.sort_by(|a, b| a.0.cmp(&b.0));
+        Self { entries }
+    }
+
+    /// Legend of coloring by change status, for the statuses in `diff`
+    pub fn of_diff(diff: &ModelDiff) -> Self {
+        let statuses = [
+            ("Added", &diff.added, ADDED_COLOR),
+            ("Modified", &diff.modified, MODIFIED_COLOR),
+            ("Removed", &diff.removed, REMOVED_COLOR),
+        ];
+        Self {
+            entries: statuses
+                .into_iter()
+                .filter(|(_, ids, _)| !ids.is_empty())
+                .map(|(label, _, color)| (label.to_string(), color))
+                .collect(),
+        }
+    }
+
+    /// Legend of a continuous ramp from `min` to `max`, sampled at `samples`
+    /// evenly spaced values (at least the two ends) labeled with the value
+    pub fn ramp(min: f64, max: f64, samples: usize, color_at: impl Fn(f64) -> [f32; 4]) -> Self {
+        let samples = samples.max(2);
+        let entries = (0..samples)
+            .map(|k| {
+                let value = min + (max - min) * k as f64 / (samples - 1) as f64;
+                (format_value(value), color_at(value))
+            })
+            .collect();
+        Self { entries }
+    }
+
+    /// Number of entries
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Whether the coloring has no legend
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+/// A ramp value with at most two decimals and no trailing zeros
+fn format_value(value: f64) -> String {
+    let text = format!("{:.2}", value);
+    let text = text.trim_end_matches('0').trim_end_matches('.');
+    if text == "-0" { "0".to_string() } else { text.to_string() }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bim::IfcFile;
+
+    #[test]
+    fn test_type_legend_lists_each_present_type() {
+        let content = include_str!("../../../test/sample_architectural.ifc");
+        let model = BimModel::from_ifc_file(&IfcFile::parse(content).unwrap()).unwrap();
+        let legend = Legend::of_types([&model]);
+        let mut present: Vec<&str> = model.products().into_iter().map(|(t, _)| t).collect();
+        present.sort();
+        present.dedup();
+        let labels: Vec<&str> = legend.entries.iter().map(|(label, _)| label.as_str()).collect();
+        assert_eq!(labels, present);
+        assert!(labels.contains(&"Wall"));
+        for (label, color) in &legend.entries {
+            assert_eq!(*color, model.palette.color_for(label));
+        }
+
+        let diff = ModelDiff { added: vec!["a".to_string()], ..Default::default() };
+        assert_eq!(Legend::of_diff(&diff).entries, [("Added".to_string(), ADDED_COLOR)]);
+
+        let ramp = Legend::ramp(0.0, 1.5, 4, |v| [v as f32, 0.0, 0.0, 1.0]);
+        let labels: Vec<&str> = ramp.entries.iter().map(|(label, _)| label.as_str()).collect();
+        assert_eq!(labels, ["0", "0.5", "1", "1.5"]);
+        assert_eq!(ramp.entries[3].1, [1.5, 0.0, 0.0, 1.0]);
+    }
+}
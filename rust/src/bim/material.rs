@@ -0,0 +1,616 @@
+//! Material Extraction
+//!
+//! Resolves element colors and material names from IFC presentation data:
+//! - Surface colors via `IfcStyledItem` → `IfcSurfaceStyle` → `IfcColourRgb`
+//! - Material names via `IfcRelAssociatesMaterial`
+//! - Material colors via `IfcMaterialDefinitionRepresentation`
+
+use super::entities::{EntityId, IfcEntity};
+use super::geometry::{DEFAULT_ELEMENT_COLOR, DEFAULT_TYPE_COLORS};
+use super::ifc_parser::IfcFile;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+
+/// Resolved material information for an element
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MaterialInfo {
+    /// Material name (from IfcMaterial / layer set), if associated
+    pub name: Option<String>,
+    /// Surface color (RGB, 0.0-1.0)
+    pub color: [f32; 3],
+    /// Transparency (0.0 = opaque, 1.0 = fully transparent)
+    pub transparency: f32,
+}
+
+impl MaterialInfo {
+    /// Get color as RGBA (alpha = 1 - transparency)
+    pub fn rgba(&self) -> [f32; 4] {
+        [
+            self.color[0],
+            self.color[1],
+            self.color[2],
+            1.0 - self.transparency,
+        ]
+    }
+}
+
+/// A triangle range of an element's mesh drawn with one material
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MaterialRegion {
+    pub material: MaterialInfo,
+    /// First triangle, relative to the element's `triangle_start`
+    pub triangle_start: u32,
+    pub triangle_count: u32,
+}
+
+/// Fraction of the hue circle between consecutive hash values (the golden
+/// ratio conjugate), which keeps nearby values far apart in hue
+const GOLDEN_RATIO_CONJUGATE: f64 = 0.618_033_988_749_895;
+
+/// Saturation and value of element id colors: soft but distinct
+const ID_COLOR_SATURATION: f32 = 0.55;
+const ID_COLOR_VALUE: f32 = 0.9;
+
+/// A stable color for an element, from a hash of its GlobalId
+///
+/// The same id gives the same color in every session (FNV-1a, not the
+/// randomly seeded std hasher). The hash steps around the hue circle by the
+/// golden ratio, so different ids land on clearly different hues.
+pub fn element_id_color(global_id: &str) -> [f32; 4] {
+    let hash = global_id
+        .bytes()
+        .fold(0x811c_9dc5_u32, |hash, byte| (hash ^ byte as u32).wrapping_mul(0x0100_0193));
+    let hue = (hash as f64 * GOLDEN_RATIO_CONJUGATE).fract() as f32 * 6.0;
+
+    // HSV to RGB
+    let chroma = ID_COLOR_VALUE * ID_COLOR_SATURATION;
+    let x = chroma * (1.0 - (hue % 2.0 - 1.0).abs());
+    let (r, g, b) = match hue as u32 {
+        0 => (chroma, x, 0.0),
+        1 => (x, chroma, 0.0),
+        2 => (0.0, chroma, x),
+        3 => (0.0, x, chroma),
+        4 => (x, 0.0, chroma),
+        _ => (chroma, 0.0, x),
+    };
+    let m = ID_COLOR_VALUE - chroma;
+    [r + m, g + m, b + m, 1.0]
+}
+
+/// Fallback colors by IFC type for elements without a material style
+///
+/// Entries are matched in order against the upper-cased type name, so
+/// overrides (kept in front) win over the built-in colors.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MaterialPalette {
+    pub colors: Vec<(String, [f32; 4])>,
+}
+
+impl Default for MaterialPalette {
+    fn default() -> Self {
+        default_palette()
+    }
+}
+
+impl MaterialPalette {
+    /// Color for an element type (RGBA, 0.0-1.0)
+    pub fn color_for(&self, element_type: &str) -> [f32; 4] {
+        let upper = element_type.to_uppercase();
+        self.colors
+            .iter()
+            .find(|(key, _)| upper.contains(key.as_str()))
+            .map(|(_, color)| *color)
+            .unwrap_or(DEFAULT_ELEMENT_COLOR)
+    }
+
+    /// Override the color of a type, given as `IfcWall` or `WALL`
+    pub fn set_color(&mut self, ifc_type: &str, color: [f32; 4]) {
+        let upper = ifc_type.to_uppercase();
+        let key = upper.strip_prefix("IFC").unwrap_or(&upper).to_string();
+        self.colors.retain(|(k, _)| *k != key);
+        self.colors.insert(0, (key, color));
+    }
+}
+
+/// The built-in palette: the type colors of [`DEFAULT_TYPE_COLORS`]
+pub fn default_palette() -> MaterialPalette {
+    MaterialPalette {
+        colors: DEFAULT_TYPE_COLORS
+            .iter()
+            .map(|(key, color)| (key.to_string(), *color))
+            .collect(),
+    }
+}
+
+/// Surface color resolved from a presentation style
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct SurfaceColor {
+    rgb: [f32; 3],
+    transparency: f32,
+}
+
+/// Resolves materials for product entities of a parsed IFC file
+///
+/// Builds lookup tables once so that resolving each element is cheap.
+pub struct MaterialResolver<'a> {
+    ifc_file: &'a IfcFile,
+    /// Representation item ID → style color (from IfcStyledItem)
+    item_colors: HashMap<EntityId, SurfaceColor>,
+    /// IfcMaterial ID → color (from IfcMaterialDefinitionRepresentation)
+    material_colors: HashMap<EntityId, SurfaceColor>,
+    /// Product ID → relating material select (from IfcRelAssociatesMaterial)
+    associations: HashMap<EntityId, EntityId>,
+}
+
+impl<'a> MaterialResolver<'a> {
+    /// Build the lookup tables for an IFC file
+    pub fn new(ifc_file: &'a IfcFile) -> Self {
+        let mut resolver = Self {
+            ifc_file,
+            item_colors: HashMap::new(),
+            material_colors: HashMap::new(),
+            associations: HashMap::new(),
+        };
+
+        // IFCSTYLEDITEM(Item, Styles, Name)
+        for styled_item in ifc_file.get_entities_by_type("IFCSTYLEDITEM") {
+            if let (Some(item), Some(color)) = (
+                styled_item.get_entity_ref(0),
+                resolver.styled_item_color(styled_item),
+            ) {
+                resolver.item_colors.entry(item).or_insert(color);
+            }
+        }
+
+        // IFCMATERIALDEFINITIONREPRESENTATION(Name, Description, Representations, RepresentedMaterial)
+        for definition in ifc_file.get_entities_by_type("IFCMATERIALDEFINITIONREPRESENTATION") {
+            let Some(material) = definition.get_entity_ref(3) else {
+                continue;
+            };
+            // IFCSTYLEDREPRESENTATION(ContextOfItems, Identifier, Type, Items)
+            let color = definition
+                .get_ref_list(2)
+                .into_iter()
+                .filter_map(|id| ifc_file.get_entity(id))
+                .flat_map(|rep| rep.get_ref_list(3))
+                .filter_map(|id| ifc_file.get_entity(id))
+                .find_map(|item| resolver.styled_item_color(item));
+            if let Some(color) = color {
+                resolver.material_colors.insert(material, color);
+            }
+        }
+
+        // IFCRELASSOCIATESMATERIAL(GlobalId, OwnerHistory, Name, Description, RelatedObjects, RelatingMaterial)
+        for rel in ifc_file.get_entities_by_type("IFCRELASSOCIATESMATERIAL") {
+            if let Some(material) = rel.get_entity_ref(5) {
+                for object in rel.get_ref_list(4) {
+                    resolver.associations.entry(object).or_insert(material);
+                }
+            }
+        }
+
+        resolver
+    }
+
+    /// Resolve the material of a product entity
+    ///
+    /// Priority: the element's own styled geometry, then the color of its
+    /// associated material, then `default_color` (the type-based color).
+    /// Returns None only when neither a style nor a material is present.
+    pub fn resolve(&self, product: &IfcEntity, default_color: [f32; 4]) -> Option<MaterialInfo> {
+        let name = self
+            .associations
+            .get(&product.id)
+            .and_then(|select| self.material_select_name(*select));
+
+        match self.surface_color(product) {
+            Some(color) => Some(MaterialInfo {
+                name,
+                color: color.rgb,
+                transparency: color.transparency,
+            }),
+            None if name.is_some() => Some(MaterialInfo {
+                name,
+                color: [default_color[0], default_color[1], default_color[2]],
+                transparency: 1.0 - default_color[3],
+            }),
+            None => None,
+        }
+    }
+
+    /// Whether a product gets its color from the file (its style or its
+    /// associated material) rather than the fallback color
+    pub fn has_color(&self, product: &IfcEntity) -> bool {
+        self.surface_color(product).is_some()
+    }
+
+    /// Style color of a product, else the color of an associated material
+    fn surface_color(&self, product: &IfcEntity) -> Option<SurfaceColor> {
+        let materials = self
+            .associations
+            .get(&product.id)
+            .map(|select| self.materials_of(*select))
+            .unwrap_or_default();
+
+        self.product_style_color(product)
+            .or_else(|| materials.iter().find_map(|m| self.material_colors.get(m).copied()))
+    }
+
+    /// Resolve one material per styled item of a product's representation
+    ///
+    /// Elements with fewer than two styled items get no regions; their single
+    /// material comes from [`MaterialResolver::resolve`]. Regions are in the
+    /// order of [`MaterialResolver::styled_items`].
+    pub fn resolve_regions(&self, product: &IfcEntity) -> Vec<MaterialInfo> {
+        let colors = self.product_style_colors(product);
+        if colors.len() < 2 {
+            return Vec::new();
+        }
+
+        let name = self
+            .associations
+            .get(&product.id)
+            .and_then(|select| self.material_select_name(*select));
+
+        colors
+            .into_iter()
+            .map(|(_, color)| MaterialInfo {
+                name: name.clone(),
+                color: color.rgb,
+                transparency: color.transparency,
+            })
+            .collect()
+    }
+
+    /// Styled representation items of a product, in representation order
+    /// (items of mapped representations where they are mapped)
+    pub fn styled_items(&self, product: &IfcEntity) -> Vec<EntityId> {
+        self.product_style_colors(product).into_iter().map(|(item, _)| item).collect()
+    }
+
+    /// Find the style color of the first styled item in a product's representation
+    fn product_style_color(&self, product: &IfcEntity) -> Option<SurfaceColor> {
+        self.product_style_colors(product).into_iter().next().map(|(_, color)| color)
+    }
+
+    /// Collect the styled items of a product's representation with their colors
+    fn product_style_colors(&self, product: &IfcEntity) -> Vec<(EntityId, SurfaceColor)> {
+        let mut colors = Vec::new();
+        // IfcProduct attribute 6 = Representation (IfcProductDefinitionShape)
+        let Some(shape) = product
+            .get_entity_ref(6)
+            .and_then(|id| self.ifc_file.get_entity(id))
+        else {
+            return colors;
+        };
+        let mut visited = HashSet::new();
+
+        // IFCPRODUCTDEFINITIONSHAPE(Name, Description, Representations)
+        for rep in shape.get_ref_list(2) {
+            self.representation_colors(rep, &mut visited, &mut colors);
+        }
+        colors
+    }
+
+    /// Collect the style colors among the items of an IfcShapeRepresentation,
+    /// following IfcMappedItem sources
+    fn representation_colors(
+        &self,
+        representation: EntityId,
+        visited: &mut HashSet<EntityId>,
+        colors: &mut Vec<(EntityId, SurfaceColor)>,
+    ) {
+        if !visited.insert(representation) {
+            return;
+        }
+        let Some(rep) = self.ifc_file.get_entity(representation) else {
+            return;
+        };
+
+        // IFCSHAPEREPRESENTATION(ContextOfItems, Identifier, Type, Items)
+        for item_id in rep.get_ref_list(3) {
+            if let Some(color) = self.item_colors.get(&item_id) {
+                colors.push((item_id, *color));
+                continue;
+            }
+
+            let Some(item) = self.ifc_file.get_entity(item_id) else {
+                continue;
+            };
+            // IFCMAPPEDITEM(MappingSource, MappingTarget)
+            // IFCREPRESENTATIONMAP(MappingOrigin, MappedRepresentation)
+            if item.entity_type == "IFCMAPPEDITEM" {
+                if let Some(mapped) = item
+                    .get_entity_ref(0)
+                    .and_then(|id| self.ifc_file.get_entity(id))
+                    .and_then(|map| map.get_entity_ref(1))
+                {
+                    self.representation_colors(mapped, visited, colors);
+                }
+            }
+        }
+    }
+
+    /// Resolve the color of an IfcStyledItem
+    fn styled_item_color(&self, styled_item: &IfcEntity) -> Option<SurfaceColor> {
+        styled_item
+            .get_ref_list(1)
+            .into_iter()
+            .find_map(|style| self.style_color(style, 0))
+    }
+
+    /// Resolve a presentation style select to a surface color
+    fn style_color(&self, style_id: EntityId, depth: usize) -> Option<SurfaceColor> {
+        if depth > 4 {
+            return None;
+        }
+        let style = self.ifc_file.get_entity(style_id)?;
+
+        match style.entity_type.as_str() {
+            // IFC2X3: IFCPRESENTATIONSTYLEASSIGNMENT(Styles)
+            "IFCPRESENTATIONSTYLEASSIGNMENT" => style
+                .get_ref_list(0)
+                .into_iter()
+                .find_map(|s| self.style_color(s, depth + 1)),
+            // IFCSURFACESTYLE(Name, Side, Styles)
+            "IFCSURFACESTYLE" => style
+                .get_ref_list(2)
+                .into_iter()
+                .find_map(|s| self.style_color(s, depth + 1)),
+            // IFCSURFACESTYLESHADING(SurfaceColour[, Transparency])
+            // IFCSURFACESTYLERENDERING(SurfaceColour, Transparency, ...)
+            "IFCSURFACESTYLESHADING" | "IFCSURFACESTYLERENDERING" => {
+                let rgb = self.colour_rgb(style.get_entity_ref(0)?)?;
+                let transparency = style.get_real(1).unwrap_or(0.0).clamp(0.0, 1.0) as f32;
+                Some(SurfaceColor { rgb, transparency })
+            }
+            _ => None,
+        }
+    }
+
+    /// Read an IFCCOLOURRGB(Name, Red, Green, Blue)
+    fn colour_rgb(&self, colour_id: EntityId) -> Option<[f32; 3]> {
+        let colour = self.ifc_file.get_entity(colour_id)?;
+        if colour.entity_type != "IFCCOLOURRGB" {
+            return None;
+        }
+        Some([
+            colour.get_real(1)?.clamp(0.0, 1.0) as f32,
+            colour.get_real(2)?.clamp(0.0, 1.0) as f32,
+            colour.get_real(3)?.clamp(0.0, 1.0) as f32,
+        ])
+    }
+
+    /// Collect the IfcMaterial IDs referenced by a material select
+    fn materials_of(&self, select: EntityId) -> Vec<EntityId> {
+        let Some(entity) = self.ifc_file.get_entity(select) else {
+            return Vec::new();
+        };
+
+        match entity.entity_type.as_str() {
+            "IFCMATERIAL" => vec![entity.id],
+            // IFCMATERIALLIST(Materials)
+            "IFCMATERIALLIST" => entity.get_ref_list(0),
+            // IFCMATERIALLAYERSETUSAGE(ForLayerSet, ...)
+            // IFCMATERIALPROFILESETUSAGE(ForProfileSet, ...)
+            "IFCMATERIALLAYERSETUSAGE" | "IFCMATERIALPROFILESETUSAGE" => entity
+                .get_entity_ref(0)
+                .map(|set| self.materials_of(set))
+                .unwrap_or_default(),
+            // IFCMATERIALLAYERSET(MaterialLayers, LayerSetName)
+            // IFCMATERIALLAYER(Material, LayerThickness, ...)
+            "IFCMATERIALLAYERSET" => entity
+                .get_ref_list(0)
+                .into_iter()
+                .filter_map(|id| self.ifc_file.get_entity(id))
+                .filter_map(|layer| layer.get_entity_ref(0))
+                .collect(),
+            // IFCMATERIALPROFILESET(Name, Description, MaterialProfiles, ...)
+            // IFCMATERIALCONSTITUENTSET(Name, Description, MaterialConstituents)
+            // IFCMATERIALPROFILE / IFCMATERIALCONSTITUENT(Name, Description, Material, ...)
+            "IFCMATERIALPROFILESET" | "IFCMATERIALCONSTITUENTSET" => entity
+                .get_ref_list(2)
+                .into_iter()
+                .filter_map(|id| self.ifc_file.get_entity(id))
+                .filter_map(|part| part.get_entity_ref(2))
+                .collect(),
+            _ => Vec::new(),
+        }
+    }
+
+    /// Display name for a material select
+    fn material_select_name(&self, select: EntityId) -> Option<String> {
+        let entity = self.ifc_file.get_entity(select)?;
+
+        let set_name = match entity.entity_type.as_str() {
+            "IFCMATERIALLAYERSET" => entity.get_string(1),
+            "IFCMATERIALLAYERSETUSAGE" => entity
+                .get_entity_ref(0)
+                .and_then(|id| self.ifc_file.get_entity(id))
+                .and_then(|set| set.get_string(1)),
+            "IFCMATERIALPROFILESET" | "IFCMATERIALCONSTITUENTSET" => entity.get_string(0),
+            _ => None,
+        };
+
+        set_name.filter(|n| !n.is_empty()).or_else(|| {
+            // IFCMATERIAL(Name, ...)
+            self.materials_of(select)
+                .into_iter()
+                .filter_map(|id| self.ifc_file.get_entity(id))
+                .find_map(|m| m.get_string(0))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bim::model::BimModel;
+
+    const STYLED_WALL: &str = "ISO-10303-21;
+HEADER;
+FILE_SCHEMA(('IFC2X3'));
+ENDSEC;
+DATA;
+#1=IFCWALL('2O2Fr$t4X7Zf8NOew3FLOH',$,'Red Wall',$,$,$,#10,$);
+#2=IFCWALL('2O2Fr$t4X7Zf8NOew3FLOI',$,'Plain Wall',$,$,$,$,$);
+#10=IFCPRODUCTDEFINITIONSHAPE($,$,(#11));
+#11=IFCSHAPEREPRESENTATION($,'Body','SweptSolid',(#12));
+#12=IFCEXTRUDEDAREASOLID($,$,$,3.0);
+#20=IFCSTYLEDITEM(#12,(#21),$);
+#21=IFCPRESENTATIONSTYLEASSIGNMENT((#22));
+#22=IFCSURFACESTYLE('Red',.BOTH.,(#23));
+#23=IFCSURFACESTYLERENDERING(#24,0.25,$,$,$,$,$,$,.FLAT.);
+#24=IFCCOLOURRGB($,0.8,0.2,0.1);
+#30=IFCMATERIAL('Concrete');
+#31=IFCRELASSOCIATESMATERIAL('3O2Fr$t4X7Zf8NOew3FLOH',$,$,$,(#1,#2),#30);
+ENDSEC;
+END-ISO-10303-21;";
+
+    #[test]
+    fn test_styled_item_color_and_material_name() {
+        let ifc = IfcFile::parse(STYLED_WALL).unwrap();
+        let resolver = MaterialResolver::new(&ifc);
+
+        let wall = ifc.get_entity(1).unwrap();
+        let material = resolver.resolve(wall, [0.5, 0.5, 0.5, 1.0]).unwrap();
+        assert_eq!(material.name.as_deref(), Some("Concrete"));
+        assert_eq!(material.color, [0.8, 0.2, 0.1]);
+        assert_eq!(material.transparency, 0.25);
+    }
+
+    #[test]
+    fn test_unstyled_element_falls_back_to_type_color() {
+        let ifc = IfcFile::parse(STYLED_WALL).unwrap();
+        let model = BimModel::from_ifc_file(&ifc).unwrap();
+
+        let plain = model.element_material("2O2Fr$t4X7Zf8NOew3FLOI").unwrap();
+        assert_eq!(plain.name.as_deref(), Some("Concrete"));
+        let wall_color = crate::bim::color_for_element_type("WALL");
+        assert_eq!(plain.color, [wall_color[0], wall_color[1], wall_color[2]]);
+    }
+
+    #[test]
+    fn test_element_id_color_is_stable_and_distinct() {
+        let a = element_id_color("2O2Fr$t4X7Zf8NOew3FLOI");
+        assert_eq!(a, element_id_color("2O2Fr$t4X7Zf8NOew3FLOI"));
+        assert_ne!(a, element_id_color("2O2Fr$t4X7Zf8NOew3FLOJ"));
+        assert!(a.iter().all(|c| (0.0..=1.0).contains(c)));
+
+        // Unstyled elements take it when coloring by id; styled ones keep theirs
+        let ifc = IfcFile::parse(STYLED_WALL).unwrap();
+        let mut model = BimModel::from_ifc_file(&ifc).unwrap();
+        model.color_by_id = true;
+        let mesh = model.generate_meshes();
+        let color_of = |global_id: &str| {
+            let element = mesh.elements.iter().find(|e| e.global_id == global_id).unwrap();
+            mesh.element_mesh(element).colors[..4].to_vec()
+        };
+        assert_eq!(color_of("2O2Fr$t4X7Zf8NOew3FLOI"), a);
+        assert_eq!(color_of("2O2Fr$t4X7Zf8NOew3FLOH")[..3], [0.8, 0.2, 0.1]);
+    }
+
+    #[test]
+    fn test_unstyled_wall_gets_palette_color() {
+        let ifc = IfcFile::parse(STYLED_WALL).unwrap();
+        let mut model = BimModel::from_ifc_file(&ifc).unwrap();
+        let palette_wall = default_palette().color_for("IfcWallStandardCase");
+        assert_eq!(palette_wall, [0.92, 0.9, 0.85, 1.0]);
+
+        // The named-but-uncolored wall takes the palette's wall color
+        let plain = model.element_material("2O2Fr$t4X7Zf8NOew3FLOI").unwrap();
+        assert_eq!(plain.color, [palette_wall[0], palette_wall[1], palette_wall[2]]);
+        assert_eq!(plain.transparency, 0.0);
+
+        // Overriding the palette recolors it, but not the styled wall
+        let mut palette = model.palette.clone();
+        palette.set_color("IfcWall", [0.0, 1.0, 0.0, 1.0]);
+        model.set_palette(palette);
+        let plain = model.element_material("2O2Fr$t4X7Zf8NOew3FLOI").unwrap();
+        assert_eq!(plain.color, [0.0, 1.0, 0.0]);
+        assert_eq!(plain.name.as_deref(), Some("Concrete"));
+        let styled = model.element_material("2O2Fr$t4X7Zf8NOew3FLOH").unwrap();
+        assert_eq!(styled.color, [0.8, 0.2, 0.1]);
+    }
+
+    #[test]
+    fn test_style_color_applied_to_vertices() {
+        let ifc = IfcFile::parse(STYLED_WALL).unwrap();
+        let model = BimModel::from_ifc_file(&ifc).unwrap();
+        let mesh = model.generate_meshes();
+
+        // Every vertex of the red wall carries the style color
+        let wall = mesh
+            .elements
+            .iter()
+            .find(|e| e.global_id == "2O2Fr$t4X7Zf8NOew3FLOH")
+            .unwrap();
+        let start = wall.triangle_start as usize * 3;
+        let end = start + wall.triangle_count as usize * 3;
+        for &index in &mesh.indices[start..end] {
+            let c = &mesh.colors[index as usize * 4..index as usize * 4 + 4];
+            assert_eq!(c, &[0.8, 0.2, 0.1, 0.75]);
+        }
+    }
+
+    const TWO_MATERIAL_WALL: &str = "ISO-10303-21;
+HEADER;
+FILE_SCHEMA(('IFC4'));
+ENDSEC;
+DATA;
+#1=IFCWALL('2O2Fr$t4X7Zf8NOew3FLOJ',$,'Painted Wall',$,$,$,#10,$);
+#10=IFCPRODUCTDEFINITIONSHAPE($,$,(#11));
+#11=IFCSHAPEREPRESENTATION($,'Body','SweptSolid',(#12,#13));
+#12=IFCEXTRUDEDAREASOLID(#14,$,$,3.0);
+#13=IFCEXTRUDEDAREASOLID(#15,$,$,3.0);
+#14=IFCRECTANGLEPROFILEDEF(.AREA.,$,$,4.0,0.2);
+#15=IFCRECTANGLEPROFILEDEF(.AREA.,$,#16,4.0,0.01);
+#16=IFCAXIS2PLACEMENT2D(#17,$);
+#17=IFCCARTESIANPOINT((0.0,0.105));
+#20=IFCSTYLEDITEM(#12,(#22),$);
+#21=IFCSTYLEDITEM(#13,(#24),$);
+#22=IFCSURFACESTYLE('Concrete',.BOTH.,(#23));
+#23=IFCSURFACESTYLESHADING(#26,$);
+#24=IFCSURFACESTYLE('Paint',.BOTH.,(#25));
+#25=IFCSURFACESTYLESHADING(#27,$);
+#26=IFCCOLOURRGB($,0.5,0.5,0.5);
+#27=IFCCOLOURRGB($,0.9,0.1,0.1);
+ENDSEC;
+END-ISO-10303-21;";
+
+    #[test]
+    fn test_two_material_element_produces_two_submeshes() {
+        let ifc = IfcFile::parse(TWO_MATERIAL_WALL).unwrap();
+        let model = BimModel::from_ifc_file(&ifc).unwrap();
+
+        // One region per styled item, covering exactly that item's box
+        let regions = model.element_material_regions("2O2Fr$t4X7Zf8NOew3FLOJ");
+        assert_eq!(regions.len(), 2);
+        assert_eq!(regions[0].material.color, [0.5, 0.5, 0.5]);
+        assert_eq!(regions[1].material.color, [0.9, 0.1, 0.1]);
+        assert_eq!((regions[0].triangle_start, regions[0].triangle_count), (0, 12));
+        assert_eq!((regions[1].triangle_start, regions[1].triangle_count), (12, 12));
+
+        // Each submesh's vertices carry its own material color, and the paint
+        // region is the thin layer on the wall's face (source y 0.1..0.11,
+        // render z = -y)
+        let mesh = model.generate_meshes();
+        let wall = &mesh.elements[0];
+        for region in &regions {
+            let start = (wall.triangle_start + region.triangle_start) as usize * 3;
+            let end = start + region.triangle_count as usize * 3;
+            for &index in &mesh.indices[start..end] {
+                let c = &mesh.colors[index as usize * 4..index as usize * 4 + 3];
+                assert_eq!(c, &region.material.color);
+                let z = mesh.vertices[index as usize * 3 + 2];
+                let layer = if region.material.color == [0.9, 0.1, 0.1] { -0.1101..=-0.0999 } else { -0.1001..=0.1001 };
+                assert!(layer.contains(&z), "z {}", z);
+            }
+        }
+
+        // Single-material elements keep one region
+        let ifc = IfcFile::parse(STYLED_WALL).unwrap();
+        let model = BimModel::from_ifc_file(&ifc).unwrap();
+        assert_eq!(model.element_material_regions("2O2Fr$t4X7Zf8NOew3FLOH").len(), 1);
+    }
+}
@@ -3,14 +3,46 @@
 //! This module handles loading and parsing IFC (Industry Foundation Classes) files.
 //! IFC files use the STEP format (ISO 10303-21) for data representation.
 
+pub mod boolean;
+pub mod coordinates;
+pub mod diff;
 pub mod entities;
+pub mod explode;
 pub mod geometry;
+pub mod gltf;
+pub mod gltf_import;
+pub mod handles;
 pub mod ifc_parser;
+pub mod legend;
+pub mod material;
 pub mod model;
 pub mod model_registry;
+pub mod obj_import;
+pub mod outline;
+pub mod properties;
+pub mod salvage;
+pub mod step_writer;
+pub mod tessellation;
+pub mod topology;
 
+pub use boolean::ClipPlane;
+pub use coordinates::{LocalOrigin, PolylineMeasurement, UpAxis, WorldPoint};
+pub use diff::ModelDiff;
 pub use entities::*;
+pub use explode::*;
 pub use geometry::*;
+pub use gltf::{ExportPrecision, GltfExport, MAX_EXPORT_DECIMALS};
+pub use gltf_import::{import_gltf, parse_gltf};
+pub use handles::ElementHandles;
 pub use ifc_parser::*;
+pub use legend::Legend;
+pub use material::*;
 pub use model::*;
 pub use model_registry::*;
+pub use obj_import::{import_obj, parse_obj};
+pub use outline::{ElementOutline, ModelOutline, StoreyOutline};
+pub use properties::properties_csv;
+pub use salvage::salvage_geometry;
+pub use step_writer::extract_subset;
+pub use tessellation::{set_tessellation_tolerance, tessellate_item, tessellation_tolerance};
+pub use topology::MeshTopology;
@@ -2,11 +2,21 @@
 //!
 //! High-level API for working with loaded IFC models.
 
+use super::boolean::subtract_convex;
+use super::coordinates::{placement_location, placement_matrix, LocalOrigin, UpAxis};
 use super::entities::*;
-use super::geometry::{color_for_element_type, generate_box_with_normals, merge_meshes, BoundingBox};
+use super::geometry::{
+    generate_box_with_normals, merge_meshes,
+    validate_mesh, BoundingBox, Mesh, MeshReport, SubMesh,
+};
 use super::ifc_parser::IfcFile;
+use super::tessellation::{bake_placement, convert_to_y_up, tessellate_item};
+use super::material::{element_id_color, MaterialInfo, MaterialPalette, MaterialRegion, MaterialResolver};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+
+/// Elements with fewer triangles keep full detail at every coarse level
+const MIN_LOD_TRIANGLES: usize = 32;
 
 /// BIM Model - High-level representation of a loaded IFC file
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -22,6 +32,9 @@ pub struct BimModel {
     pub windows: Vec<IfcWindow>,
     pub roofs: Vec<IfcRoof>,
     pub stairs: Vec<IfcStair>,
+    // Voids cut into hosts (not rendered as elements themselves)
+    #[serde(default)]
+    pub openings: Vec<IfcOpeningElement>,
     // Structural elements
     pub columns: Vec<IfcColumn>,
     pub beams: Vec<IfcBeam>,
@@ -38,6 +51,47 @@ pub struct BimModel {
     pub grids: Vec<IfcGrid>,
     pub grid_axes: Vec<IfcGridAxis>,
     pub grid_lines: Vec<GridLine>,
+    // Materials resolved per element (keyed by GlobalId)
+    pub materials: HashMap<String, MaterialInfo>,
+    // Elements whose material has a name but no color (colored from the palette)
+    #[serde(default)]
+    pub palette_colored: HashSet<String>,
+    // Fallback colors by type for elements without a material style
+    #[serde(default)]
+    pub palette: MaterialPalette,
+    // Color elements without a material color by their GlobalId instead
+    #[serde(default)]
+    pub color_by_id: bool,
+    // Per-styled-item materials of multi-material elements (keyed by GlobalId)
+    #[serde(default)]
+    pub material_regions: HashMap<String, Vec<MaterialInfo>>,
+    // Tessellated explicit geometry in source axes, placed in the model's
+    // world relative to `origin` (keyed by GlobalId)
+    #[serde(default)]
+    pub body_meshes: HashMap<String, Mesh>,
+    // Vertical axis of the source coordinates (converted to Y-up for rendering)
+    #[serde(default)]
+    pub up_axis: UpAxis,
+    // Storey of each element (keyed by GlobalId), explicit or by elevation
+    #[serde(default)]
+    pub storey_assignments: HashMap<String, StoreyAssignment>,
+    // Elements physically connected to each element (keyed by GlobalId, sorted)
+    #[serde(default)]
+    pub connections: HashMap<String, Vec<String>>,
+    // Body meshes are imported in render coordinates rather than tessellated
+    // from an IFC source (glTF/OBJ imports)
+    #[serde(default)]
+    pub placed_bodies: bool,
+    // Offset that local (f32) render coordinates are relative to
+    #[serde(default)]
+    pub origin: LocalOrigin,
+    // Loaded without geometry (`LoadOptions::extract_geometry` off): the
+    // elements have metadata only and nothing is drawn
+    #[serde(default)]
+    pub geometry_skipped: bool,
+    // Problems in the source file that parsing worked around
+    #[serde(default)]
+    pub parse_warnings: Vec<String>,
     pub element_count: usize,
 }
 
@@ -64,6 +118,48 @@ pub struct ModelInfo {
     pub building_name: String,
     pub site_name: String,
     pub stats: ModelStats,
+    /// Whether the model has elements to draw; a spatial-only file (project,
+    /// site, building and storeys) renders nothing
+    pub has_geometry: bool,
+}
+
+/// What to extract when building a model, to skip work a caller doesn't
+/// need (e.g. only walls and their properties for an analysis)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LoadOptions {
+    /// Element types to extract, by display type name (`"Wall"`, `"Door"`,
+    /// as in `ElementInfo::element_type`, case-insensitive); all when `None`
+    pub element_types: Option<Vec<String>>,
+    /// Whether to read property sets and quantities
+    pub extract_properties: bool,
+    /// Whether to tessellate geometry; without it the model has metadata
+    /// only and generates no meshes
+    pub extract_geometry: bool,
+}
+
+impl Default for LoadOptions {
+    fn default() -> Self {
+        Self {
+            element_types: None,
+            extract_properties: true,
+            extract_geometry: true,
+        }
+    }
+}
+
+impl LoadOptions {
+    /// Elements of a display type: extracted, or none if not asked for
+    fn extract<T>(&self, element_type: &str, extract: impl FnOnce() -> Vec<T>) -> Vec<T> {
+        let included = self
+            .element_types
+            .as_ref()
+            .is_none_or(|types| types.iter().any(|t| t.eq_ignore_ascii_case(element_type)));
+        if included {
+            extract()
+        } else {
+            Vec::new()
+        }
+    }
 }
 
 impl BimModel {
@@ -81,6 +177,7 @@ impl BimModel {
             windows: Vec::new(),
             roofs: Vec::new(),
             stairs: Vec::new(),
+            openings: Vec::new(),
             // Structural
             columns: Vec::new(),
             beams: Vec::new(),
@@ -97,13 +194,49 @@ impl BimModel {
             grids: Vec::new(),
             grid_axes: Vec::new(),
             grid_lines: Vec::new(),
+            materials: HashMap::new(),
+            palette_colored: HashSet::new(),
+            palette: MaterialPalette::default(),
+            color_by_id: false,
+            material_regions: HashMap::new(),
+            body_meshes: HashMap::new(),
+            up_axis: UpAxis::default(),
+            storey_assignments: HashMap::new(),
+            connections: HashMap::new(),
+            placed_bodies: false,
+            origin: LocalOrigin::default(),
+            geometry_skipped: false,
+            parse_warnings: Vec::new(),
             element_count: 0,
         }
     }
 
     /// Load model from IFC file
     pub fn from_ifc_file(ifc_file: &IfcFile) -> Result<Self, String> {
+        Self::from_ifc_file_with_progress(ifc_file, |_| {})
+    }
+
+    /// Load model from IFC file, reporting the fraction of elements
+    /// tessellated (0.0-1.0)
+    ///
+    /// `1.0` is reported once, when the model is complete.
+    pub fn from_ifc_file_with_progress(
+        ifc_file: &IfcFile,
+        progress: impl FnMut(f32),
+    ) -> Result<Self, String> {
+        Self::from_ifc_file_with_options(ifc_file, &LoadOptions::default(), progress)
+    }
+
+    /// Load the parts of an IFC file `options` asks for, reporting progress
+    /// as `from_ifc_file_with_progress` does
+    pub fn from_ifc_file_with_options(
+        ifc_file: &IfcFile,
+        options: &LoadOptions,
+        mut progress: impl FnMut(f32),
+    ) -> Result<Self, String> {
         let mut model = BimModel::new();
+        model.parse_warnings = ifc_file.parse_warnings.clone();
+        model.geometry_skipped = !options.extract_geometry;
 
         // Extract project
         model.project = Self::extract_project(ifc_file);
@@ -118,34 +251,66 @@ impl BimModel {
         model.storeys = Self::extract_storeys(ifc_file);
 
         // Architectural elements
-        model.walls = Self::extract_walls(ifc_file);
-        model.slabs = Self::extract_slabs(ifc_file);
-        model.doors = Self::extract_doors(ifc_file);
-        model.windows = Self::extract_windows(ifc_file);
-        model.roofs = Self::extract_roofs(ifc_file);
-        model.stairs = Self::extract_stairs(ifc_file);
+        model.walls = options.extract("Wall", || Self::extract_walls(ifc_file));
+        model.slabs = options.extract("Slab", || Self::extract_slabs(ifc_file));
+        model.doors = options.extract("Door", || Self::extract_doors(ifc_file));
+        model.windows = options.extract("Window", || Self::extract_windows(ifc_file));
+        model.roofs = options.extract("Roof", || Self::extract_roofs(ifc_file));
+        model.stairs = options.extract("Stair", || Self::extract_stairs(ifc_file));
+        if options.extract_geometry {
+            model.openings = Self::extract_openings(ifc_file);
+        }
 
         // Structural elements
-        model.columns = Self::extract_columns(ifc_file);
-        model.beams = Self::extract_beams(ifc_file);
-        model.footings = Self::extract_footings(ifc_file);
+        model.columns = options.extract("Column", || Self::extract_columns(ifc_file));
+        model.beams = options.extract("Beam", || Self::extract_beams(ifc_file));
+        model.footings = options.extract("Footing", || Self::extract_footings(ifc_file));
 
         // MEP elements
-        model.pipes = Self::extract_pipes(ifc_file);
-        model.ducts = Self::extract_ducts(ifc_file);
-        model.flow_terminals = Self::extract_flow_terminals(ifc_file);
+        model.pipes = options.extract("Pipe", || Self::extract_pipes(ifc_file));
+        model.ducts = options.extract("Duct", || Self::extract_ducts(ifc_file));
+        model.flow_terminals = options.extract("FlowTerminal", || Self::extract_flow_terminals(ifc_file));
 
         // Electrical
-        model.cable_carriers = Self::extract_cable_carriers(ifc_file);
+        model.cable_carriers = options.extract("CableCarrier", || Self::extract_cable_carriers(ifc_file));
 
         // Generic
-        model.proxies = Self::extract_proxies(ifc_file);
+        model.proxies = options.extract("Proxy", || Self::extract_proxies(ifc_file));
 
         // Grids
         model.grids = Self::extract_grids(ifc_file);
         model.grid_axes = Self::extract_grid_axes(ifc_file);
         model.grid_lines = Self::generate_grid_lines(&model);
 
+        // Materials (styled item colors, associated material names)
+        (model.materials, model.palette_colored) = Self::extract_materials(ifc_file, &model);
+
+        // Local origin for georeferenced coordinates
+        model.origin = Self::extract_origin(ifc_file, &model);
+
+        if options.extract_geometry {
+            model.material_regions = Self::extract_material_regions(ifc_file, &model);
+
+            // Explicit geometry (triangulated face sets, faceted surface
+            // models, extruded, revolved and swept disk solids, and their
+            // boolean clipping)
+            model.body_meshes = Self::extract_body_meshes(ifc_file, &model, &mut progress);
+        }
+
+        // Spatial containment, guessed from elevation where links are missing
+        model.storey_assignments = Self::extract_storey_assignments(ifc_file, &model);
+
+        // Element connections (wall joins, beam-column connections)
+        model.connections = Self::extract_connections(ifc_file, &model);
+
+        // Presentation layers (CAD layers) of element geometry
+        Self::assign_layers(ifc_file, &mut model);
+
+        // Property sets and element quantities
+        if options.extract_properties {
+            Self::assign_properties(ifc_file, &mut model);
+        }
+
         model.element_count = model.walls.len()
             + model.slabs.len()
             + model.columns.len()
@@ -161,9 +326,16 @@ impl BimModel {
             + model.cable_carriers.len()
             + model.proxies.len();
 
+        progress(1.0);
         Ok(model)
     }
 
+    /// Tessellate the explicit geometry again, e.g. at a new tessellation
+    /// tolerance; `ifc_file` must be the file the model was loaded from
+    pub fn retessellate(&mut self, ifc_file: &IfcFile) {
+        self.body_meshes = Self::extract_body_meshes(ifc_file, self, &mut |_| {});
+    }
+
     /// Get model information
     pub fn get_info(&self) -> ModelInfo {
         ModelInfo {
@@ -192,11 +364,339 @@ impl BimModel {
                 windows: self.windows.len(),
                 storeys: self.storeys.len(),
             },
+            has_geometry: self.has_geometry(),
+        }
+    }
+
+    /// Whether there is anything to draw: every element is drawn (from its
+    /// body geometry or as a placeholder), so any element will do
+    pub fn has_geometry(&self) -> bool {
+        self.element_count > 0 && !self.geometry_skipped
+    }
+
+    /// Whether nothing was loaded into the model: no elements and no
+    /// spatial structure
+    fn is_blank(&self) -> bool {
+        !self.has_geometry() && self.project.is_none() && self.site.is_none() && self.storeys.is_empty()
+    }
+
+    /// Get all products with their display type name
+    pub fn products(&self) -> Vec<(&'static str, &IfcProduct)> {
+        let mut products = Vec::with_capacity(self.element_count);
+        products.extend(self.walls.iter().map(|e| ("Wall", &e.product)));
+        products.extend(self.slabs.iter().map(|e| ("Slab", &e.product)));
+        products.extend(self.columns.iter().map(|e| ("Column", &e.product)));
+        products.extend(self.beams.iter().map(|e| ("Beam", &e.product)));
+        products.extend(self.doors.iter().map(|e| ("Door", &e.product)));
+        products.extend(self.windows.iter().map(|e| ("Window", &e.product)));
+        products.extend(self.roofs.iter().map(|e| ("Roof", &e.product)));
+        products.extend(self.stairs.iter().map(|e| ("Stair", &e.product)));
+        products.extend(self.footings.iter().map(|e| ("Footing", &e.product)));
+        products.extend(self.pipes.iter().map(|e| ("Pipe", &e.product)));
+        products.extend(self.ducts.iter().map(|e| ("Duct", &e.product)));
+        products.extend(self.flow_terminals.iter().map(|e| ("FlowTerminal", &e.product)));
+        products.extend(self.cable_carriers.iter().map(|e| ("CableCarrier", &e.product)));
+        products.extend(self.proxies.iter().map(|e| ("Proxy", &e.product)));
+        products
+    }
+
+    /// Every product, mutably, in the order of `products`
+    fn products_mut(&mut self) -> Vec<&mut IfcProduct> {
+        let mut products = Vec::with_capacity(self.element_count);
+        products.extend(self.walls.iter_mut().map(|e| &mut e.product));
+        products.extend(self.slabs.iter_mut().map(|e| &mut e.product));
+        products.extend(self.columns.iter_mut().map(|e| &mut e.product));
+        products.extend(self.beams.iter_mut().map(|e| &mut e.product));
+        products.extend(self.doors.iter_mut().map(|e| &mut e.product));
+        products.extend(self.windows.iter_mut().map(|e| &mut e.product));
+        products.extend(self.roofs.iter_mut().map(|e| &mut e.product));
+        products.extend(self.stairs.iter_mut().map(|e| &mut e.product));
+        products.extend(self.footings.iter_mut().map(|e| &mut e.product));
+        products.extend(self.pipes.iter_mut().map(|e| &mut e.product));
+        products.extend(self.ducts.iter_mut().map(|e| &mut e.product));
+        products.extend(self.flow_terminals.iter_mut().map(|e| &mut e.product));
+        products.extend(self.cable_carriers.iter_mut().map(|e| &mut e.product));
+        products.extend(self.proxies.iter_mut().map(|e| &mut e.product));
+        products
+    }
+
+    /// Estimated memory held by the element data (products with their
+    /// properties, storeys, materials), in bytes
+    ///
+    /// The parsed IFC file itself is not kept once the model is built.
+    pub fn entity_bytes(&self) -> usize {
+        let text = |s: &Option<String>| s.as_ref().map_or(0, String::capacity);
+        let product_bytes: usize = self
+            .products()
+            .into_iter()
+            .map(|(_, p)| {
+                let properties: usize = p
+                    .properties
+                    .iter()
+                    .map(|(k, v)| 2 * size_of::<String>() + k.capacity() + v.capacity())
+                    .sum();
+                // Every element type wraps its product with a little more
+                size_of::<IfcWall>()
+                    + p.global_id.capacity()
+                    + text(&p.name)
+                    + text(&p.description)
+                    + text(&p.object_type)
+                    + text(&p.layer)
+                    + properties
+            })
+            .sum();
+        let storey_bytes: usize = self
+            .storeys
+            .iter()
+            .map(|s| size_of::<IfcBuildingStorey>() + s.name.capacity())
+            .sum();
+        let material_bytes: usize = self
+            .materials
+            .keys()
+            .map(|k| size_of::<String>() + k.capacity() + size_of::<MaterialInfo>())
+            .sum();
+        product_bytes + storey_bytes + material_bytes + self.storey_assignments.len() * size_of::<StoreyAssignment>()
+    }
+
+    /// Estimated memory held by the extracted geometry (tessellated bodies,
+    /// material regions, grid lines), in bytes
+    pub fn geometry_bytes(&self) -> usize {
+        let meshes: usize = self.body_meshes.iter().map(|(k, m)| k.capacity() + size_of::<Mesh>() + m.heap_bytes()).sum();
+        let regions: usize = self
+            .material_regions
+            .values()
+            .map(|r| size_of::<String>() + r.capacity() * size_of::<MaterialInfo>())
+            .sum();
+        meshes + regions + self.grid_lines.capacity() * size_of::<GridLine>()
+    }
+
+    /// Names of the presentation layers elements are on, sorted
+    pub fn layers(&self) -> Vec<String> {
+        let layers: std::collections::BTreeSet<&String> =
+            self.products().into_iter().filter_map(|(_, p)| p.layer.as_ref()).collect();
+        layers.into_iter().cloned().collect()
+    }
+
+    /// Get the elevations of all storeys that define one
+    pub fn storey_elevations(&self) -> Vec<f32> {
+        self.storeys
+            .iter()
+            .filter_map(|s| s.elevation)
+            .map(|e| e as f32)
+            .collect()
+    }
+
+    /// Get the storey an element belongs to, and whether the assignment was
+    /// guessed from its elevation
+    pub fn element_storey(&self, global_id: &str) -> Option<(&IfcBuildingStorey, bool)> {
+        let assignment = self.storey_assignments.get(global_id)?;
+        let storey = self.storeys.iter().find(|s| s.id == assignment.storey_id)?;
+        Some((storey, assignment.heuristic))
+    }
+
+    /// GlobalIds of the elements on a storey (contained in it, or guessed
+    /// from their elevation), in element order
+    pub fn storey_elements(&self, storey_id: EntityId) -> Vec<&str> {
+        self.products()
+            .into_iter()
+            .filter(|(_, p)| self.storey_assignments.get(&p.global_id).is_some_and(|a| a.storey_id == storey_id))
+            .map(|(_, p)| p.global_id.as_str())
+            .collect()
+    }
+
+    /// Add an imported mesh (render coordinates, Y up) as a generic element
+    /// drawn where it is
+    pub fn add_placed_body(&mut self, global_id: String, name: Option<String>, mesh: Mesh) {
+        self.up_axis = UpAxis::Y;
+        self.placed_bodies = true;
+        self.body_meshes.insert(global_id.clone(), mesh);
+        self.proxies.push(IfcBuildingElementProxy {
+            product: IfcProduct {
+                id: self.proxies.len() as EntityId + 1,
+                global_id,
+                name,
+                description: None,
+                object_type: None,
+                properties: HashMap::new(),
+                location: None,
+                layer: None,
+            },
+            predefined_type: None,
+        });
+        self.element_count += 1;
+    }
+
+    /// Get the GlobalIds of the elements connected to an element
+    pub fn connected_elements(&self, global_id: &str) -> Vec<String> {
+        self.connections.get(global_id).cloned().unwrap_or_default()
+    }
+
+    /// Get the material of an element, falling back to the type-based default
+    /// color when the file defines no style for it
+    pub fn element_material(&self, global_id: &str) -> Option<MaterialInfo> {
+        if let Some(material) = self.materials.get(global_id) {
+            return Some(material.clone());
+        }
+
+        let (element_type, _) = self
+            .products()
+            .into_iter()
+            .find(|(_, p)| p.global_id == global_id)?;
+        let color = self.palette.color_for(element_type);
+        Some(MaterialInfo {
+            name: None,
+            color: [color[0], color[1], color[2]],
+            transparency: 1.0 - color[3],
+        })
+    }
+
+    /// Replace the fallback palette, recoloring elements that take their
+    /// color from it
+    pub fn set_palette(&mut self, palette: MaterialPalette) {
+        let recolor: Vec<(String, [f32; 4])> = self
+            .products()
+            .into_iter()
+            .filter(|(_, p)| self.palette_colored.contains(&p.global_id))
+            .map(|(element_type, p)| (p.global_id.clone(), palette.color_for(element_type)))
+            .collect();
+        for (global_id, color) in recolor {
+            if let Some(material) = self.materials.get_mut(&global_id) {
+                material.color = [color[0], color[1], color[2]];
+                material.transparency = 1.0 - color[3];
+            }
+        }
+        self.palette = palette;
+    }
+
+    /// Get the material regions of an element's mesh: one per run of
+    /// triangles tessellated from the same styled item
+    ///
+    /// Single-material elements return one region covering the whole mesh.
+    pub fn element_material_regions(&self, global_id: &str) -> Vec<MaterialRegion> {
+        if self.geometry_skipped {
+            return Vec::new();
+        }
+        let Some(material) = self.element_material(global_id) else {
+            return Vec::new();
+        };
+        let regions = self.material_regions.get(global_id);
+        let submeshes = self
+            .element_mesh(global_id, [0.0; 3], [1.0; 3], material.rgba())
+            .submeshes;
+
+        let mut merged: Vec<SubMesh> = Vec::new();
+        for submesh in submeshes.into_iter().filter(|s| s.index_count > 0) {
+            match merged.last_mut() {
+                Some(last)
+                    if last.material_id == submesh.material_id
+                        && last.index_offset + last.index_count == submesh.index_offset =>
+                {
+                    last.index_count += submesh.index_count;
+                }
+                _ => merged.push(submesh),
+            }
         }
+        merged
+            .into_iter()
+            .map(|submesh| MaterialRegion {
+                material: regions
+                    .and_then(|r| r.get(submesh.material_id as usize))
+                    .unwrap_or(&material)
+                    .clone(),
+                triangle_start: submesh.index_offset / 3,
+                triangle_count: submesh.index_count / 3,
+            })
+            .collect()
+    }
+
+    /// Bounds of an element's tessellated body (render axes), if it has one
+    fn body_bounds(&self, global_id: &str) -> Option<BoundingBox> {
+        let bounds = self.body_meshes.get(global_id)?.bounding_box()?;
+        let (a, b) = (self.up_axis.to_y_up(bounds.min), self.up_axis.to_y_up(bounds.max));
+        Some(BoundingBox::from_min_max([0, 1, 2].map(|k| a[k].min(b[k])), [0, 1, 2].map(|k| a[k].max(b[k]))))
+    }
+
+    /// Generate an element's mesh with one submesh per material region
+    ///
+    /// Elements with tessellated geometry use it where it was placed (in
+    /// render axes); others get a placeholder box at `center`.
+    fn element_mesh(&self, global_id: &str, center: [f32; 3], size: [f32; 3], color: [f32; 4]) -> Mesh {
+        if let Some(body) = self.body_meshes.get(global_id) {
+            let mut mesh = body.clone();
+            convert_to_y_up(&mut mesh, self.up_axis);
+            mesh.assign_materials(&self.region_colors(global_id, color));
+            return mesh;
+        }
+
+        let mut mesh = generate_box_with_normals(center, size, color);
+        mesh.assign_materials(&self.region_colors(global_id, color));
+        mesh
+    }
+
+    /// Colors of an element's material regions (just `color` for one material)
+    fn region_colors(&self, global_id: &str, color: [f32; 4]) -> Vec<[f32; 4]> {
+        match self.material_regions.get(global_id) {
+            Some(regions) => regions.iter().map(MaterialInfo::rgba).collect(),
+            None => vec![color],
+        }
+    }
+
+    /// Get the vertex color for an element (material color, or the type
+    /// default or id color when the file gives it no color)
+    fn element_color(&self, global_id: &str, element_type: &str) -> [f32; 4] {
+        let styled = self.materials.contains_key(global_id) && !self.palette_colored.contains(global_id);
+        if self.color_by_id && !styled {
+            return element_id_color(global_id);
+        }
+        self.materials
+            .get(global_id)
+            .map(|m| m.rgba())
+            .unwrap_or_else(|| self.palette.color_for(element_type))
     }
 
     // Extraction helper methods
 
+    fn extract_materials(
+        ifc_file: &IfcFile,
+        model: &BimModel,
+    ) -> (HashMap<String, MaterialInfo>, HashSet<String>) {
+        let resolver = MaterialResolver::new(ifc_file);
+        let mut materials = HashMap::new();
+        let mut palette_colored = HashSet::new();
+
+        for (element_type, product) in model.products() {
+            let Some(entity) = ifc_file.get_entity(product.id) else {
+                continue;
+            };
+            let Some(material) = resolver.resolve(entity, model.palette.color_for(element_type)) else {
+                continue;
+            };
+            if !resolver.has_color(entity) {
+                palette_colored.insert(product.global_id.clone());
+            }
+            materials.insert(product.global_id.clone(), material);
+        }
+
+        (materials, palette_colored)
+    }
+
+    fn extract_material_regions(
+        ifc_file: &IfcFile,
+        model: &BimModel,
+    ) -> HashMap<String, Vec<MaterialInfo>> {
+        let resolver = MaterialResolver::new(ifc_file);
+
+        model
+            .products()
+            .into_iter()
+            .filter_map(|(_, product)| {
+                let entity = ifc_file.get_entity(product.id)?;
+                let regions = resolver.resolve_regions(entity);
+                (!regions.is_empty()).then(|| (product.global_id.clone(), regions))
+            })
+            .collect()
+    }
+
     fn extract_project(ifc_file: &IfcFile) -> Option<IfcProject> {
         let entities = ifc_file.get_entities_by_type("IFCPROJECT");
         entities.first().map(|e| IfcProject {
@@ -219,6 +719,19 @@ impl BimModel {
         })
     }
 
+    fn extract_origin(ifc_file: &IfcFile, model: &BimModel) -> LocalOrigin {
+        // Prefer the site placement; otherwise the first placed element
+        let site_location = ifc_file
+            .get_entities_by_type("IFCSITE")
+            .first()
+            .and_then(|e| placement_location(ifc_file, e.get_entity_ref(5)));
+
+        site_location
+            .or_else(|| model.products().iter().find_map(|(_, p)| p.location))
+            .map(LocalOrigin::new)
+            .unwrap_or_default()
+    }
+
     fn extract_building(ifc_file: &IfcFile) -> Option<IfcBuilding> {
         let entities = ifc_file.get_entities_by_type("IFCBUILDING");
         entities.first().map(|e| IfcBuilding {
@@ -235,7 +748,7 @@ impl BimModel {
             .map(|e| IfcBuildingStorey {
                 id: e.id,
                 name: e.get_string(2).unwrap_or_default(),
-                elevation: e.get_real(8),
+                elevation: e.get_real(9),
             })
             .collect()
     }
@@ -252,6 +765,8 @@ impl BimModel {
                     description: e.get_string(3),
                     object_type: e.get_string(4),
                     properties: HashMap::new(),
+                    location: placement_location(ifc_file, e.get_entity_ref(5)),
+                    layer: None,
                 };
                 IfcWall {
                     product,
@@ -273,6 +788,8 @@ impl BimModel {
                     description: e.get_string(3),
                     object_type: e.get_string(4),
                     properties: HashMap::new(),
+                    location: placement_location(ifc_file, e.get_entity_ref(5)),
+                    layer: None,
                 };
                 IfcSlab {
                     product,
@@ -294,6 +811,8 @@ impl BimModel {
                     description: e.get_string(3),
                     object_type: e.get_string(4),
                     properties: HashMap::new(),
+                    location: placement_location(ifc_file, e.get_entity_ref(5)),
+                    layer: None,
                 };
                 IfcColumn {
                     product,
@@ -315,6 +834,8 @@ impl BimModel {
                     description: e.get_string(3),
                     object_type: e.get_string(4),
                     properties: HashMap::new(),
+                    location: placement_location(ifc_file, e.get_entity_ref(5)),
+                    layer: None,
                 };
                 IfcBeam {
                     product,
@@ -336,11 +857,13 @@ impl BimModel {
                     description: e.get_string(3),
                     object_type: e.get_string(4),
                     properties: HashMap::new(),
+                    location: placement_location(ifc_file, e.get_entity_ref(5)),
+                    layer: None,
                 };
                 IfcDoor {
                     product,
-                    overall_height: e.get_real(5),
-                    overall_width: e.get_real(6),
+                    overall_height: e.get_real(OVERALL_HEIGHT_INDEX),
+                    overall_width: e.get_real(OVERALL_WIDTH_INDEX),
                 }
             })
             .collect()
@@ -358,16 +881,354 @@ impl BimModel {
                     description: e.get_string(3),
                     object_type: e.get_string(4),
                     properties: HashMap::new(),
+                    location: placement_location(ifc_file, e.get_entity_ref(5)),
+                    layer: None,
                 };
                 IfcWindow {
                     product,
-                    overall_height: e.get_real(5),
-                    overall_width: e.get_real(6),
+                    overall_height: e.get_real(OVERALL_HEIGHT_INDEX),
+                    overall_width: e.get_real(OVERALL_WIDTH_INDEX),
+                }
+            })
+            .collect()
+    }
+
+    fn extract_openings(ifc_file: &IfcFile) -> Vec<IfcOpeningElement> {
+        // IFCRELVOIDSELEMENT(..., RelatingBuildingElement, RelatedOpeningElement)
+        let mut hosts: HashMap<EntityId, String> = HashMap::new();
+        for rel in ifc_file.get_entities_by_type("IFCRELVOIDSELEMENT") {
+            if let (Some(host), Some(opening)) = (rel.get_entity_ref(4), rel.get_entity_ref(5)) {
+                if let Some(global_id) = ifc_file.get_entity(host).and_then(|h| h.get_string(0)) {
+                    hosts.insert(opening, global_id);
+                }
+            }
+        }
+
+        // IFCRELFILLSELEMENT(..., RelatingOpeningElement, RelatedBuildingElement)
+        let mut fillings: HashMap<EntityId, EntityId> = HashMap::new();
+        for rel in ifc_file.get_entities_by_type("IFCRELFILLSELEMENT") {
+            if let (Some(opening), Some(element)) = (rel.get_entity_ref(4), rel.get_entity_ref(5)) {
+                fillings.insert(opening, element);
+            }
+        }
+
+        ifc_file
+            .get_entities_by_type("IFCOPENINGELEMENT")
+            .into_iter()
+            .map(|e| {
+                let product = IfcProduct {
+                    id: e.id,
+                    global_id: e.get_string(0).unwrap_or_default(),
+                    name: e.get_string(2),
+                    description: e.get_string(3),
+                    object_type: e.get_string(4),
+                    properties: HashMap::new(),
+                    location: placement_location(ifc_file, e.get_entity_ref(5)),
+                    layer: None,
+                };
+                IfcOpeningElement {
+                    product,
+                    host_global_id: hosts.get(&e.id).cloned(),
+                    filling: fillings.get(&e.id).copied(),
+                }
+            })
+            .collect()
+    }
+
+    /// Set the presentation layer of every product
+    ///
+    /// IFCPRESENTATIONLAYERASSIGNMENT(Name, Description, AssignedItems,
+    /// Identifier) assigns shape representations or representation items
+    /// (or, from some exporters, products) to a named layer. A product is on
+    /// the first layer found for itself, one of its representations or one
+    /// of their items.
+    fn assign_layers(ifc_file: &IfcFile, model: &mut BimModel) {
+        let mut layer_of: HashMap<EntityId, String> = HashMap::new();
+        for layer_type in ["IFCPRESENTATIONLAYERASSIGNMENT", "IFCPRESENTATIONLAYERWITHSTYLE"] {
+            for assignment in ifc_file.get_entities_by_type(layer_type) {
+                let Some(name) = assignment.get_string(0) else {
+                    continue;
+                };
+                for item in assignment.get_ref_list(2) {
+                    layer_of.entry(item).or_insert_with(|| name.clone());
                 }
+            }
+        }
+        if layer_of.is_empty() {
+            return;
+        }
+
+        for product in model.products_mut() {
+            let Some(entity) = ifc_file.get_entity(product.id) else {
+                continue;
+            };
+            // IFCPRODUCTDEFINITIONSHAPE(Name, Description, Representations)
+            let representations = entity
+                .get_entity_ref(6)
+                .and_then(|id| ifc_file.get_entity(id))
+                .map(|shape| shape.get_ref_list(2))
+                .unwrap_or_default();
+            // IFCSHAPEREPRESENTATION(ContextOfItems, Identifier, Type, Items)
+            let items = representations
+                .iter()
+                .filter_map(|&rep| ifc_file.get_entity(rep))
+                .flat_map(|rep| rep.get_ref_list(3));
+            product.layer = std::iter::once(product.id)
+                .chain(representations.iter().copied())
+                .chain(items)
+                .find_map(|id| layer_of.get(&id).cloned());
+        }
+    }
+
+    /// Fill every product's properties from its property sets and element
+    /// quantities, keyed `<set name>.<property name>`
+    ///
+    /// IFCRELDEFINESBYPROPERTIES(..., RelatedObjects, RelatingPropertyDefinition)
+    /// links products to an IFCPROPERTYSET(..., Name, Description,
+    /// HasProperties) of single values or an IFCELEMENTQUANTITY(..., Name,
+    /// Description, MethodOfMeasurement, Quantities).
+    fn assign_properties(ifc_file: &IfcFile, model: &mut BimModel) {
+        let mut properties: HashMap<EntityId, HashMap<String, String>> = HashMap::new();
+        for rel in ifc_file.get_entities_by_type("IFCRELDEFINESBYPROPERTIES") {
+            let Some(definition) = rel.get_entity_ref(5).and_then(|id| ifc_file.get_entity(id)) else {
+                continue;
+            };
+            let Some(set_name) = definition.get_string(2) else {
+                continue;
+            };
+            let members = match definition.entity_type.as_str() {
+                "IFCPROPERTYSET" => definition.get_ref_list(4),
+                "IFCELEMENTQUANTITY" => definition.get_ref_list(5),
+                _ => continue,
+            };
+            let values: Vec<(String, String)> = members
+                .into_iter()
+                .filter_map(|id| ifc_file.get_entity(id))
+                .filter_map(|member| {
+                    let value = match member.entity_type.as_str() {
+                        // IFCPROPERTYSINGLEVALUE(Name, Description, NominalValue, Unit)
+                        "IFCPROPERTYSINGLEVALUE" => member.get_attr(2),
+                        // IFCQUANTITYLENGTH/AREA/VOLUME/COUNT/WEIGHT/TIME
+                        // (Name, Description, Unit, Value, ...)
+                        t if t.starts_with("IFCQUANTITY") => member.get_attr(3),
+                        _ => None,
+                    };
+                    Some((format!("{}.{}", set_name, member.get_string(0)?), property_text(value?)?))
+                })
+                .collect();
+            for product in rel.get_ref_list(4) {
+                properties.entry(product).or_default().extend(values.iter().cloned());
+            }
+        }
+        if properties.is_empty() {
+            return;
+        }
+        for product in model.products_mut() {
+            if let Some(values) = properties.remove(&product.id) {
+                product.properties.extend(values);
+            }
+        }
+    }
+
+    /// Geometric items of a product's shape representations
+    fn representation_items<'a>(ifc_file: &'a IfcFile, product: &IfcEntity) -> Vec<&'a IfcEntity> {
+        // IFCPRODUCTDEFINITIONSHAPE(Name, Description, Representations)
+        let Some(shape) = product.get_entity_ref(6).and_then(|id| ifc_file.get_entity(id)) else {
+            return Vec::new();
+        };
+        shape
+            .get_ref_list(2)
+            .into_iter()
+            .filter_map(|rep| ifc_file.get_entity(rep))
+            // IFCSHAPEREPRESENTATION(ContextOfItems, Identifier, Type, Items)
+            .flat_map(|rep| rep.get_ref_list(3))
+            .filter_map(|item| ifc_file.get_entity(item))
+            .collect()
+    }
+
+    /// Tessellate every product's representation, reporting the fraction done
+    ///
+    /// Each item is one submesh, of the material region of its styled item
+    /// (unstyled items take the first, the element's own material). Meshes
+    /// are placed in the model's world and have the openings that void them
+    /// (IfcRelVoidsElement) cut out.
+    fn extract_body_meshes(
+        ifc_file: &IfcFile,
+        model: &BimModel,
+        progress: &mut dyn FnMut(f32),
+    ) -> HashMap<String, Mesh> {
+        let mut openings: HashMap<&str, Vec<&IfcOpeningElement>> = HashMap::new();
+        for opening in &model.openings {
+            if let Some(host) = &opening.host_global_id {
+                openings.entry(host.as_str()).or_default().push(opening);
+            }
+        }
+
+        let resolver = MaterialResolver::new(ifc_file);
+        let products = model.products();
+        let total = products.len().max(1) as f32;
+        products
+            .into_iter()
+            .enumerate()
+            .filter_map(|(i, (_, product))| {
+                progress((i as f32 / total).min(0.99));
+                let entity = ifc_file.get_entity(product.id)?;
+                let styled_items = if model.material_regions.contains_key(&product.global_id) {
+                    resolver.styled_items(entity)
+                } else {
+                    Vec::new()
+                };
+                let meshes: Vec<Mesh> = Self::representation_items(ifc_file, entity)
+                    .into_iter()
+                    .filter_map(|item| {
+                        let mut mesh = tessellate_item(ifc_file, item)?;
+                        let region = styled_items.iter().position(|&id| id == item.id).unwrap_or(0);
+                        mesh.set_material(region as u32);
+                        Some(mesh)
+                    })
+                    .collect();
+                if meshes.is_empty() {
+                    return None;
+                }
+                let mut mesh = merge_meshes(meshes);
+                if let Some(placement) = placement_matrix(ifc_file, entity.get_entity_ref(5)) {
+                    bake_placement(&mut mesh, &placement, model.origin.offset);
+                }
+                let hosted = openings.get(product.global_id.as_str()).map_or(&[][..], Vec::as_slice);
+                for cutter in hosted.iter().flat_map(|o| Self::opening_solids(ifc_file, model, o)) {
+                    if let Some(cut) = subtract_convex(&mesh, &cutter) {
+                        mesh = cut;
+                    }
+                }
+                Some((product.global_id.clone(), mesh))
+            })
+            .collect()
+    }
+
+    /// Solids of an opening, placed in the model's world like its host's body
+    ///
+    /// Each representation item is one solid. An opening without a placement
+    /// has no known position relative to its host and cuts nothing.
+    fn opening_solids(ifc_file: &IfcFile, model: &BimModel, opening: &IfcOpeningElement) -> Vec<Mesh> {
+        let Some(entity) = ifc_file.get_entity(opening.product.id) else {
+            return Vec::new();
+        };
+        let Some(placement) = placement_matrix(ifc_file, entity.get_entity_ref(5)) else {
+            return Vec::new();
+        };
+        Self::representation_items(ifc_file, entity)
+            .into_iter()
+            .filter_map(|item| tessellate_item(ifc_file, item))
+            .map(|mut mesh| {
+                bake_placement(&mut mesh, &placement, model.origin.offset);
+                mesh
             })
             .collect()
     }
 
+    /// Storey of every element
+    ///
+    /// Explicit IFCRELCONTAINEDINSPATIALSTRUCTURE links to a storey win.
+    /// Other elements go to the highest storey at or below their center
+    /// elevation (placement plus body bounds center, along the up axis), or
+    /// the lowest storey when they sit below all of them; these are marked
+    /// heuristic. Elements with no placement or body geometry stay unassigned.
+    fn extract_storey_assignments(ifc_file: &IfcFile, model: &BimModel) -> HashMap<String, StoreyAssignment> {
+        let storey_ids: HashSet<EntityId> = model.storeys.iter().map(|s| s.id).collect();
+        let product_ids: HashMap<EntityId, &str> = model
+            .products()
+            .into_iter()
+            .map(|(_, p)| (p.id, p.global_id.as_str()))
+            .collect();
+
+        // IFCRELCONTAINEDINSPATIALSTRUCTURE(..., RelatedElements, RelatingStructure)
+        let mut assignments = HashMap::new();
+        for rel in ifc_file.get_entities_by_type("IFCRELCONTAINEDINSPATIALSTRUCTURE") {
+            let Some(storey_id) = rel.get_entity_ref(5).filter(|id| storey_ids.contains(id)) else {
+                continue;
+            };
+            for element in rel.get_ref_list(4) {
+                if let Some(global_id) = product_ids.get(&element) {
+                    let assignment = StoreyAssignment { storey_id, heuristic: false };
+                    assignments.insert(global_id.to_string(), assignment);
+                }
+            }
+        }
+
+        let mut levels: Vec<(f64, EntityId)> = model
+            .storeys
+            .iter()
+            .filter_map(|s| Some((s.elevation?, s.id)))
+            .collect();
+        levels.sort_by(|a, b| a.0.total_cmp(&b.0));
+        let Some(&(_, lowest)) = levels.first() else {
+            return assignments;
+        };
+
+        let up = model.up_axis.index();
+        for (_, product) in model.products() {
+            if assignments.contains_key(&product.global_id) {
+                continue;
+            }
+            // Bodies are placed already, relative to the model origin
+            let body_center = model
+                .body_meshes
+                .get(&product.global_id)
+                .and_then(|mesh| mesh.bounding_box())
+                .map(|b| (b.min[up] + b.max[up]) as f64 / 2.0 + model.origin.offset[up]);
+            let Some(center) = body_center.or(product.location.map(|l| l[up])) else {
+                continue;
+            };
+
+            let storey_id = levels
+                .iter()
+                .rev()
+                .find(|(elevation, _)| *elevation <= center)
+                .map_or(lowest, |&(_, id)| id);
+            assignments.insert(
+                product.global_id.clone(),
+                StoreyAssignment { storey_id, heuristic: true },
+            );
+        }
+
+        assignments
+    }
+
+    /// Connected elements of every element, both ways round
+    ///
+    /// Read from IFCRELCONNECTSELEMENTS and its subtype
+    /// IFCRELCONNECTSPATHELEMENTS (wall joins); connections to elements that
+    /// are not extracted products are left out.
+    fn extract_connections(ifc_file: &IfcFile, model: &BimModel) -> HashMap<String, Vec<String>> {
+        let product_ids: HashMap<EntityId, &str> = model
+            .products()
+            .into_iter()
+            .map(|(_, p)| (p.id, p.global_id.as_str()))
+            .collect();
+
+        // IFCRELCONNECTSELEMENTS(..., ConnectionGeometry, RelatingElement, RelatedElement, ...)
+        let mut connections: HashMap<String, Vec<String>> = HashMap::new();
+        for rel_type in ["IFCRELCONNECTSELEMENTS", "IFCRELCONNECTSPATHELEMENTS"] {
+            for rel in ifc_file.get_entities_by_type(rel_type) {
+                let relating = rel.get_entity_ref(5).and_then(|id| product_ids.get(&id));
+                let related = rel.get_entity_ref(6).and_then(|id| product_ids.get(&id));
+                let (Some(&a), Some(&b)) = (relating, related) else {
+                    continue;
+                };
+                if a == b {
+                    continue;
+                }
+                connections.entry(a.to_string()).or_default().push(b.to_string());
+                connections.entry(b.to_string()).or_default().push(a.to_string());
+            }
+        }
+        for connected in connections.values_mut() {
+            connected.sort();
+            connected.dedup();
+        }
+        connections
+    }
+
     fn extract_roofs(ifc_file: &IfcFile) -> Vec<IfcRoof> {
         ifc_file
             .get_entities_by_type("IFCROOF")
@@ -380,6 +1241,8 @@ impl BimModel {
                     description: e.get_string(3),
                     object_type: e.get_string(4),
                     properties: HashMap::new(),
+                    location: placement_location(ifc_file, e.get_entity_ref(5)),
+                    layer: None,
                 };
                 IfcRoof {
                     product,
@@ -401,6 +1264,8 @@ impl BimModel {
                     description: e.get_string(3),
                     object_type: e.get_string(4),
                     properties: HashMap::new(),
+                    location: placement_location(ifc_file, e.get_entity_ref(5)),
+                    layer: None,
                 };
                 IfcStair {
                     product,
@@ -422,6 +1287,8 @@ impl BimModel {
                     description: e.get_string(3),
                     object_type: e.get_string(4),
                     properties: HashMap::new(),
+                    location: placement_location(ifc_file, e.get_entity_ref(5)),
+                    layer: None,
                 };
                 IfcFooting {
                     product,
@@ -443,6 +1310,8 @@ impl BimModel {
                     description: e.get_string(3),
                     object_type: e.get_string(4),
                     properties: HashMap::new(),
+                    location: placement_location(ifc_file, e.get_entity_ref(5)),
+                    layer: None,
                 };
                 IfcPipeSegment {
                     product,
@@ -464,6 +1333,8 @@ impl BimModel {
                     description: e.get_string(3),
                     object_type: e.get_string(4),
                     properties: HashMap::new(),
+                    location: placement_location(ifc_file, e.get_entity_ref(5)),
+                    layer: None,
                 };
                 IfcDuctSegment {
                     product,
@@ -485,6 +1356,8 @@ impl BimModel {
                     description: e.get_string(3),
                     object_type: e.get_string(4),
                     properties: HashMap::new(),
+                    location: placement_location(ifc_file, e.get_entity_ref(5)),
+                    layer: None,
                 };
                 IfcFlowTerminal {
                     product,
@@ -506,6 +1379,8 @@ impl BimModel {
                     description: e.get_string(3),
                     object_type: e.get_string(4),
                     properties: HashMap::new(),
+                    location: placement_location(ifc_file, e.get_entity_ref(5)),
+                    layer: None,
                 };
                 IfcCableCarrierSegment {
                     product,
@@ -527,6 +1402,8 @@ impl BimModel {
                     description: e.get_string(3),
                     object_type: e.get_string(4),
                     properties: HashMap::new(),
+                    location: placement_location(ifc_file, e.get_entity_ref(5)),
+                    layer: None,
                 };
                 IfcBuildingElementProxy {
                     product,
@@ -651,8 +1528,7 @@ impl BimModel {
         }
 
         // If no grids defined, generate default structural grid
-        if grid_lines.is_empty() && bounds.is_some() {
-            let bounds = bounds.unwrap();
+        if let (true, Some(bounds)) = (grid_lines.is_empty(), bounds) {
             let span_x = bounds.max[0] - bounds.min[0];
             let span_y = bounds.max[1] - bounds.min[1];
 
@@ -697,6 +1573,27 @@ impl Default for BimModel {
     }
 }
 
+/// Position of OverallHeight in IFCDOOR and IFCWINDOW
+///
+/// It and OverallWidth follow Tag (7) in every schema: IFC4 and IFC4X3 add
+/// PredefinedType and the operation (doors) or partitioning (windows) type
+/// after them rather than before, so the positions agree.
+const OVERALL_HEIGHT_INDEX: usize = 8;
+
+/// Position of OverallWidth in IFCDOOR and IFCWINDOW
+const OVERALL_WIDTH_INDEX: usize = 9;
+
+/// Text of a property value; None when unset or not a plain value
+fn property_text(value: &IfcValue) -> Option<String> {
+    match value {
+        IfcValue::String(s) | IfcValue::Enum(s) => Some(s.clone()),
+        IfcValue::Integer(i) => Some(i.to_string()),
+        IfcValue::Real(r) => Some(r.to_string()),
+        IfcValue::Boolean(b) => Some(if *b { "TRUE" } else { "FALSE" }.to_string()),
+        IfcValue::Null | IfcValue::Derived | IfcValue::EntityRef(_) | IfcValue::List(_) => None,
+    }
+}
+
 /// Element information for selection/properties
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ElementInfo {
@@ -709,8 +1606,47 @@ pub struct ElementInfo {
     pub triangle_count: u32,
 }
 
+/// Where `BimModel::generate_meshes` puts an element, worked out without
+/// tessellating it; `BimModel::slot_mesh` tessellates it
+#[derive(Debug, Clone)]
+pub struct ElementSlot {
+    pub id: i32,
+    pub element_type: &'static str,
+    pub name: String,
+    pub global_id: String,
+    /// Bounds of the element's geometry (render axes)
+    pub bounds: BoundingBox,
+    center: [f32; 3],
+    size: [f32; 3],
+    color: [f32; 4],
+}
+
+impl ElementSlot {
+    /// Bounds of a placeholder box
+    fn placeholder_bounds(center: [f32; 3], size: [f32; 3]) -> BoundingBox {
+        let half = [size[0] / 2.0, size[1] / 2.0, size[2] / 2.0];
+        BoundingBox {
+            min: [center[0] - half[0], center[1] - half[1], center[2] - half[2]],
+            max: [center[0] + half[0], center[1] + half[1], center[2] + half[2]],
+        }
+    }
+
+    /// Element info for the slot's triangles in a merged mesh
+    pub fn info(&self, triangle_start: u32, triangle_count: u32) -> ElementInfo {
+        ElementInfo {
+            id: self.id,
+            element_type: self.element_type.to_string(),
+            name: self.name.clone(),
+            global_id: self.global_id.clone(),
+            bounds: self.bounds,
+            triangle_start,
+            triangle_count,
+        }
+    }
+}
+
 /// Generated mesh data for rendering
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct ModelMesh {
     pub vertices: Vec<f32>,
     pub indices: Vec<u32>,
@@ -720,6 +1656,319 @@ pub struct ModelMesh {
     pub elements: Vec<ElementInfo>,
 }
 
+/// Geometry generated for one element type, for performance budgeting
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TypeBudget {
+    pub ifc_type: String,
+    pub element_count: u32,
+    pub triangle_count: u32,
+    /// Distinct vertices the type's triangles use
+    pub vertex_count: u32,
+}
+
+/// Geometry per element type across meshes, most triangles first
+pub fn geometry_budget<'a>(meshes: impl IntoIterator<Item = &'a ModelMesh>) -> Vec<TypeBudget> {
+    let mut budgets: HashMap<&str, TypeBudget> = HashMap::new();
+    for mesh in meshes {
+        for element in &mesh.elements {
+            let start = (element.triangle_start as usize * 3).min(mesh.indices.len());
+            let end = (start + element.triangle_count as usize * 3).min(mesh.indices.len());
+            let vertices: HashSet<u32> = mesh.indices[start..end].iter().copied().collect();
+            let budget = budgets.entry(&element.element_type).or_insert_with(|| TypeBudget {
+                ifc_type: element.element_type.clone(),
+                element_count: 0,
+                triangle_count: 0,
+                vertex_count: 0,
+            });
+            budget.element_count += 1;
+            budget.triangle_count += ((end - start) / 3) as u32;
+            budget.vertex_count += vertices.len() as u32;
+        }
+    }
+    let mut budgets: Vec<TypeBudget> = budgets.into_values().collect();
+    budgets.sort_by(|a, b| b.triangle_count.cmp(&a.triangle_count).then_with(|| a.ifc_type.cmp(&b.ifc_type)));
+    budgets
+}
+
+/// Geometry of a single element, with its own compact vertex buffers
+///
+/// When streamed, an item with an empty `global_id` and no geometry marks
+/// the end of the stream.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ElementMesh {
+    pub global_id: String,
+    pub vertices: Vec<f32>,
+    pub normals: Vec<f32>,
+    pub colors: Vec<f32>,
+    pub indices: Vec<u32>,
+    /// When streamed: union of the bounds of the elements streamed so far,
+    /// on every few items and on the end marker (the whole model's bounds
+    /// there), to re-fit the camera as geometry arrives
+    #[serde(default)]
+    pub running_bounds: Option<BoundingBox>,
+}
+
+impl ElementMesh {
+    /// An element's tessellated mesh
+    pub fn new(global_id: String, mesh: Mesh) -> Self {
+        Self {
+            global_id,
+            vertices: mesh.vertices,
+            normals: mesh.normals,
+            colors: mesh.colors,
+            indices: mesh.indices,
+            running_bounds: None,
+        }
+    }
+
+    /// The end-of-stream marker
+    pub fn completion_marker() -> Self {
+        Self::default()
+    }
+
+    /// Whether this is the end-of-stream marker rather than an element
+    pub fn is_completion_marker(&self) -> bool {
+        self.global_id.is_empty() && self.indices.is_empty()
+    }
+}
+
+/// Geometry validation summary of a generated model mesh
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ModelValidation {
+    /// Problems across the whole mesh
+    pub report: MeshReport,
+    /// GlobalIds of elements whose triangles have problems
+    pub elements_with_issues: Vec<String>,
+}
+
+/// Coarse levels of a model mesh's elements (from `ModelMesh::lod_levels`),
+/// to be appended to the mesh's own arrays
+#[derive(Debug, Clone, Default)]
+pub struct LodMesh {
+    /// Vertex data, numbered after the model mesh's vertices
+    pub vertices: Vec<f32>,
+    pub normals: Vec<f32>,
+    pub colors: Vec<f32>,
+    pub indices: Vec<u32>,
+    /// Per element, the triangle range of each coarse level (finest first)
+    /// in the model mesh's triangles followed by these
+    pub element_levels: Vec<Vec<std::ops::Range<u32>>>,
+}
+
+impl ModelMesh {
+    /// Add another mesh's geometry and elements after this one's (e.g. to
+    /// draw several models as one)
+    pub fn append(&mut self, other: &ModelMesh) {
+        let vertex_offset = (self.vertices.len() / 3) as u32;
+        let triangle_offset = (self.indices.len() / 3) as u32;
+        self.indices.extend(other.indices.iter().map(|i| i + vertex_offset));
+        self.vertices.extend_from_slice(&other.vertices);
+        self.normals.extend_from_slice(&other.normals);
+        self.colors.extend_from_slice(&other.colors);
+        self.elements.extend(other.elements.iter().map(|e| ElementInfo {
+            triangle_start: e.triangle_start + triangle_offset,
+            ..e.clone()
+        }));
+        self.bounds = match (self.bounds, other.bounds) {
+            (Some(a), Some(b)) => Some(a.union(&b)),
+            (a, b) => a.or(b),
+        };
+    }
+
+    /// Drop the triangles of elements for which `keep` is false
+    ///
+    /// Vertices are left in place; only indices and element ranges change.
+    pub fn retain_elements(&mut self, keep: impl Fn(&ElementInfo) -> bool) {
+        let mut indices = Vec::new();
+        let mut elements = Vec::new();
+        for element in self.elements.drain(..) {
+            if !keep(&element) {
+                continue;
+            }
+            let start = (element.triangle_start as usize * 3).min(self.indices.len());
+            let end = (start + element.triangle_count as usize * 3).min(self.indices.len());
+            let triangle_start = (indices.len() / 3) as u32;
+            indices.extend_from_slice(&self.indices[start..end]);
+            elements.push(ElementInfo {
+                triangle_start,
+                ..element
+            });
+        }
+        self.indices = indices;
+        self.elements = elements;
+    }
+
+    /// Recolor the elements given by GlobalId (RGBA, 0.0-1.0)
+    pub fn set_element_colors(&mut self, colors: &HashMap<String, [f32; 4]>) {
+        if colors.is_empty() {
+            return;
+        }
+        for element in &self.elements {
+            let Some(color) = colors.get(&element.global_id) else {
+                continue;
+            };
+            let start = (element.triangle_start as usize * 3).min(self.indices.len());
+            let end = (start + element.triangle_count as usize * 3).min(self.indices.len());
+            for &index in &self.indices[start..end] {
+                let v = index as usize * 4;
+                if let Some(c) = self.colors.get_mut(v..v + 4) {
+                    c.copy_from_slice(color);
+                }
+            }
+        }
+    }
+
+    /// Bytes held by the vertex, index and element arrays
+    pub fn heap_bytes(&self) -> usize {
+        let floats = self.vertices.capacity() + self.normals.capacity() + self.colors.capacity();
+        floats * size_of::<f32>()
+            + self.indices.capacity() * size_of::<u32>()
+            + self
+                .elements
+                .iter()
+                .map(|e| size_of::<ElementInfo>() + e.global_id.capacity() + e.name.capacity())
+                .sum::<usize>()
+    }
+
+    /// Decimate the elements of the given types to their ratio of triangles
+    /// (see `Mesh::simplify`), keyed by element type
+    ///
+    /// Simplified elements get new vertices at the end of the vertex
+    /// arrays; other elements keep theirs.
+    pub fn apply_lod(&mut self, ratios: &HashMap<String, f32>) {
+        if ratios.is_empty() {
+            return;
+        }
+        let mut indices = Vec::with_capacity(self.indices.len());
+        for k in 0..self.elements.len() {
+            let element = &self.elements[k];
+            let start = (element.triangle_start as usize * 3).min(self.indices.len());
+            let end = (start + element.triangle_count as usize * 3).min(self.indices.len());
+            let triangle_start = (indices.len() / 3) as u32;
+            match ratios.get(&element.element_type) {
+                Some(&ratio) => {
+                    let simplified = self.simplified_element(element, ratio);
+                    let base = (self.vertices.len() / 3) as u32;
+                    self.vertices.extend_from_slice(&simplified.vertices);
+                    self.normals.extend_from_slice(&simplified.normals);
+                    self.colors.extend_from_slice(&simplified.colors);
+                    indices.extend(simplified.indices.iter().map(|i| i + base));
+                }
+                None => indices.extend_from_slice(&self.indices[start..end]),
+            }
+            let element = &mut self.elements[k];
+            element.triangle_start = triangle_start;
+            element.triangle_count = (indices.len() / 3) as u32 - triangle_start;
+        }
+        self.indices = indices;
+    }
+
+    /// Coarser copies of every element at each of `ratios` of its triangles,
+    /// for switching detail by distance (see `LodMesh`)
+    pub fn lod_levels(&self, ratios: &[f32]) -> LodMesh {
+        let mut lod = LodMesh::default();
+        for element in &self.elements {
+            let full = element.triangle_start..element.triangle_start + element.triangle_count;
+            let mut levels = Vec::with_capacity(ratios.len());
+            for &ratio in ratios {
+                if (element.triangle_count as usize) < MIN_LOD_TRIANGLES {
+                    levels.push(full.clone());
+                    continue;
+                }
+                let simplified = self.simplified_element(element, ratio);
+                let base = (self.vertices.len() + lod.vertices.len()) as u32 / 3;
+                let start = self.indices.len() as u32 / 3 + lod.indices.len() as u32 / 3;
+                lod.vertices.extend_from_slice(&simplified.vertices);
+                lod.normals.extend_from_slice(&simplified.normals);
+                lod.colors.extend_from_slice(&simplified.colors);
+                lod.indices.extend(simplified.indices.iter().map(|i| i + base));
+                levels.push(start..self.indices.len() as u32 / 3 + lod.indices.len() as u32 / 3);
+            }
+            lod.element_levels.push(levels);
+        }
+        lod
+    }
+
+    /// One element's triangles decimated to `ratio` (see `Mesh::simplify`)
+    fn simplified_element(&self, element: &ElementInfo, ratio: f32) -> Mesh {
+        let part = self.element_mesh(element);
+        let mut mesh = Mesh::new();
+        mesh.vertices = part.vertices;
+        mesh.normals = part.normals;
+        mesh.colors = part.colors;
+        mesh.indices = part.indices;
+        mesh.simplify(ratio)
+    }
+
+    /// Drop the elements of `model` on any of the `hidden` layers
+    pub fn hide_layers(&mut self, model: &BimModel, hidden: &HashSet<String>) {
+        if hidden.is_empty() {
+            return;
+        }
+        let hidden_elements: HashSet<&str> = model
+            .products()
+            .into_iter()
+            .filter(|(_, p)| p.layer.as_ref().is_some_and(|layer| hidden.contains(layer)))
+            .map(|(_, p)| p.global_id.as_str())
+            .collect();
+        self.retain_elements(|e| !hidden_elements.contains(e.global_id.as_str()));
+    }
+
+    /// Extract one element's triangles into a standalone mesh
+    ///
+    /// Only the vertices the element uses are copied, renumbered from zero.
+    pub fn element_mesh(&self, element: &ElementInfo) -> ElementMesh {
+        let start = (element.triangle_start as usize * 3).min(self.indices.len());
+        let end = (start + element.triangle_count as usize * 3).min(self.indices.len());
+
+        let mut remap: HashMap<u32, u32> = HashMap::new();
+        let mut mesh = ElementMesh {
+            global_id: element.global_id.clone(),
+            ..Default::default()
+        };
+        for &index in &self.indices[start..end] {
+            let next = remap.len() as u32;
+            let local = *remap.entry(index).or_insert_with(|| {
+                let v = index as usize;
+                mesh.vertices.extend_from_slice(&self.vertices[v * 3..v * 3 + 3]);
+                mesh.normals.extend_from_slice(&self.normals[v * 3..v * 3 + 3]);
+                mesh.colors.extend_from_slice(&self.colors[v * 4..v * 4 + 4]);
+                next
+            });
+            mesh.indices.push(local);
+        }
+        mesh
+    }
+
+    /// Check the mesh for degenerate triangles, NaN vertices and other
+    /// problems, and report which elements they belong to
+    pub fn validate(&self) -> ModelValidation {
+        let elements_with_issues = self
+            .elements
+            .iter()
+            .filter(|element| {
+                let start = (element.triangle_start as usize * 3).min(self.indices.len());
+                let end = (start + element.triangle_count as usize * 3).min(self.indices.len());
+                let report = validate_mesh(&self.vertices, &self.indices[start..end]);
+                report.degenerate_tris > 0 || report.non_manifold_edges > 0 || {
+                    // Only count NaN vertices this element actually uses
+                    self.indices[start..end].iter().any(|&i| {
+                        self.vertices
+                            .get(i as usize * 3..i as usize * 3 + 3)
+                            .is_some_and(|p| p.iter().any(|c| !c.is_finite()))
+                    })
+                }
+            })
+            .map(|element| element.global_id.clone())
+            .collect();
+
+        ModelValidation {
+            report: validate_mesh(&self.vertices, &self.indices),
+            elements_with_issues,
+        }
+    }
+}
+
 impl BimModel {
     /// Generate meshes from the BIM model for rendering
     /// This creates placeholder box geometry for each element
@@ -727,293 +1976,155 @@ impl BimModel {
         let mut meshes = Vec::new();
         let mut elements = Vec::new();
         let mut current_triangle = 0u32;
-        let y_offset = 0.0f32;
+        for slot in self.element_slots() {
+            let mesh = self.slot_mesh(&slot);
+            let triangles = (mesh.indices.len() / 3) as u32;
+            elements.push(slot.info(current_triangle, triangles));
+            current_triangle += triangles;
+            meshes.push(mesh);
+        }
 
-        // Helper to add element info
-        fn add_element(
-            elements: &mut Vec<ElementInfo>,
-            current_triangle: &mut u32,
-            mesh_triangles: u32,
-            id: i32,
-            element_type: &str,
-            name: &str,
-            global_id: &str,
-            center: [f32; 3],
-            size: [f32; 3],
-        ) {
-            let half = [size[0] / 2.0, size[1] / 2.0, size[2] / 2.0];
-            elements.push(ElementInfo {
-                id,
-                element_type: element_type.to_string(),
-                name: name.to_string(),
-                global_id: global_id.to_string(),
-                bounds: BoundingBox {
-                    min: [center[0] - half[0], center[1] - half[1], center[2] - half[2]],
-                    max: [center[0] + half[0], center[1] + half[1], center[2] + half[2]],
-                },
-                triangle_start: *current_triangle,
-                triangle_count: mesh_triangles,
-            });
-            *current_triangle += mesh_triangles;
+        // Merge all meshes
+        let merged = merge_meshes(meshes);
+        let bounds = merged.bounding_box();
+        fit_element_bounds(&mut elements, &merged);
+
+        ModelMesh {
+            vertices: merged.vertices,
+            indices: merged.indices,
+            normals: merged.normals,
+            colors: merged.colors,
+            bounds,
+            elements,
         }
+    }
 
+    /// Lay out the elements of `generate_meshes`, in order, without
+    /// tessellating them
+    pub fn element_slots(&self) -> Vec<ElementSlot> {
+        if self.geometry_skipped {
+            return Vec::new();
+        }
+        let mut slots = Vec::with_capacity(self.element_count);
+        let y_offset = 0.0f32;
         // Generate wall meshes
         for (i, wall) in self.walls.iter().enumerate() {
-            let color = color_for_element_type("WALL");
             let center = [i as f32 * 3.0, 1.5 + y_offset, 0.0];
             let size = [2.5, 3.0, 0.2];
-            let mesh = generate_box_with_normals(center, size, color);
-            let triangles = (mesh.indices.len() / 3) as u32;
-            add_element(
-                &mut elements, &mut current_triangle, triangles,
-                wall.product.id, "Wall",
-                wall.product.name.as_deref().unwrap_or("Wall"),
-                &wall.product.global_id,
-                center, size,
-            );
-            meshes.push(mesh);
+            slots.push(self.element_slot(&wall.product, "Wall", "Wall", "WALL", center, size));
         }
 
         // Generate slab meshes (floors)
         for (i, slab) in self.slabs.iter().enumerate() {
-            let color = color_for_element_type("SLAB");
             let center = [0.0, y_offset + i as f32 * 3.5, 0.0];
             let size = [10.0, 0.3, 8.0];
-            let mesh = generate_box_with_normals(center, size, color);
-            let triangles = (mesh.indices.len() / 3) as u32;
-            add_element(
-                &mut elements, &mut current_triangle, triangles,
-                slab.product.id, "Slab",
-                slab.product.name.as_deref().unwrap_or("Slab"),
-                &slab.product.global_id,
-                center, size,
-            );
-            meshes.push(mesh);
+            slots.push(self.element_slot(&slab.product, "Slab", "Slab", "SLAB", center, size));
         }
 
         // Generate column meshes
         for (i, column) in self.columns.iter().enumerate() {
-            let color = color_for_element_type("COLUMN");
             let x = (i % 4) as f32 * 3.0 - 4.5;
             let z = (i / 4) as f32 * 3.0 - 3.0;
             let center = [x, 1.5 + y_offset, z];
             let size = [0.4, 3.0, 0.4];
-            let mesh = generate_box_with_normals(center, size, color);
-            let triangles = (mesh.indices.len() / 3) as u32;
-            add_element(
-                &mut elements, &mut current_triangle, triangles,
-                column.product.id, "Column",
-                column.product.name.as_deref().unwrap_or("Column"),
-                &column.product.global_id,
-                center, size,
-            );
-            meshes.push(mesh);
+            slots.push(self.element_slot(&column.product, "Column", "Column", "COLUMN", center, size));
         }
 
         // Generate beam meshes
         for (i, beam) in self.beams.iter().enumerate() {
-            let color = color_for_element_type("BEAM");
             let center = [0.0, 2.8 + y_offset, i as f32 * 2.0 - 2.0];
             let size = [8.0, 0.4, 0.3];
-            let mesh = generate_box_with_normals(center, size, color);
-            let triangles = (mesh.indices.len() / 3) as u32;
-            add_element(
-                &mut elements, &mut current_triangle, triangles,
-                beam.product.id, "Beam",
-                beam.product.name.as_deref().unwrap_or("Beam"),
-                &beam.product.global_id,
-                center, size,
-            );
-            meshes.push(mesh);
+            slots.push(self.element_slot(&beam.product, "Beam", "Beam", "BEAM", center, size));
         }
 
         // Generate door meshes
         for (i, door) in self.doors.iter().enumerate() {
-            let color = color_for_element_type("DOOR");
             let height = door.overall_height.unwrap_or(2.1) as f32;
             let width = door.overall_width.unwrap_or(0.9) as f32;
             let center = [i as f32 * 3.0 + 1.0, height / 2.0 + y_offset, 0.1];
             let size = [width, height, 0.1];
-            let mesh = generate_box_with_normals(center, size, color);
-            let triangles = (mesh.indices.len() / 3) as u32;
-            add_element(
-                &mut elements, &mut current_triangle, triangles,
-                door.product.id, "Door",
-                door.product.name.as_deref().unwrap_or("Door"),
-                &door.product.global_id,
-                center, size,
-            );
-            meshes.push(mesh);
+            slots.push(self.element_slot(&door.product, "Door", "Door", "DOOR", center, size));
         }
 
         // Generate window meshes
         for (i, window) in self.windows.iter().enumerate() {
-            let color = color_for_element_type("WINDOW");
             let height = window.overall_height.unwrap_or(1.2) as f32;
             let width = window.overall_width.unwrap_or(1.0) as f32;
             let center = [i as f32 * 3.0 + 1.5, 1.5 + y_offset, 0.1];
             let size = [width, height, 0.05];
-            let mesh = generate_box_with_normals(center, size, color);
-            let triangles = (mesh.indices.len() / 3) as u32;
-            add_element(
-                &mut elements, &mut current_triangle, triangles,
-                window.product.id, "Window",
-                window.product.name.as_deref().unwrap_or("Window"),
-                &window.product.global_id,
-                center, size,
-            );
-            meshes.push(mesh);
+            slots.push(self.element_slot(&window.product, "Window", "Window", "WINDOW", center, size));
         }
 
         // Generate roof meshes
         for (i, roof) in self.roofs.iter().enumerate() {
-            let color = color_for_element_type("ROOF");
             let center = [0.0, 3.15 + y_offset + i as f32 * 0.5, 0.0];
             let size = [10.0, 0.3, 8.0];
-            let mesh = generate_box_with_normals(center, size, color);
-            let triangles = (mesh.indices.len() / 3) as u32;
-            add_element(
-                &mut elements, &mut current_triangle, triangles,
-                roof.product.id, "Roof",
-                roof.product.name.as_deref().unwrap_or("Roof"),
-                &roof.product.global_id,
-                center, size,
-            );
-            meshes.push(mesh);
+            slots.push(self.element_slot(&roof.product, "Roof", "Roof", "ROOF", center, size));
         }
 
         // Generate stair meshes
         for (i, stair) in self.stairs.iter().enumerate() {
-            let color = color_for_element_type("STAIR");
             let center = [3.0 + i as f32 * 2.0, 1.5 + y_offset, 2.0];
             let size = [1.5, 3.0, 3.0];
-            let mesh = generate_box_with_normals(center, size, color);
-            let triangles = (mesh.indices.len() / 3) as u32;
-            add_element(
-                &mut elements, &mut current_triangle, triangles,
-                stair.product.id, "Stair",
-                stair.product.name.as_deref().unwrap_or("Stair"),
-                &stair.product.global_id,
-                center, size,
-            );
-            meshes.push(mesh);
+            slots.push(self.element_slot(&stair.product, "Stair", "Stair", "STAIR", center, size));
         }
 
         // Generate footing meshes (foundations)
         for (i, footing) in self.footings.iter().enumerate() {
-            let color = color_for_element_type("FOOTING");
             let x = (i % 4) as f32 * 3.0 - 4.5;
             let z = (i / 4) as f32 * 3.0 - 3.0;
             let center = [x, -0.5 + y_offset, z];
             let size = [1.0, 0.6, 1.0];
-            let mesh = generate_box_with_normals(center, size, color);
-            let triangles = (mesh.indices.len() / 3) as u32;
-            add_element(
-                &mut elements, &mut current_triangle, triangles,
-                footing.product.id, "Footing",
-                footing.product.name.as_deref().unwrap_or("Footing"),
-                &footing.product.global_id,
-                center, size,
-            );
-            meshes.push(mesh);
+            slots.push(self.element_slot(&footing.product, "Footing", "Footing", "FOOTING", center, size));
         }
 
         // Generate pipe meshes (MEP - shown as thin horizontal boxes)
         for (i, pipe) in self.pipes.iter().enumerate() {
-            let color = color_for_element_type("PIPE");
             let y_pos = 2.5 + (i / 3) as f32 * 0.3;
             let z_pos = (i % 3) as f32 * 2.0 - 2.0;
             let center = [0.0, y_pos + y_offset, z_pos];
             let size = [8.0, 0.1, 0.1]; // Thin horizontal pipe
-            let mesh = generate_box_with_normals(center, size, color);
-            let triangles = (mesh.indices.len() / 3) as u32;
-            add_element(
-                &mut elements, &mut current_triangle, triangles,
-                pipe.product.id, "Pipe",
-                pipe.product.name.as_deref().unwrap_or("Pipe"),
-                &pipe.product.global_id,
-                center, size,
-            );
-            meshes.push(mesh);
+            slots.push(self.element_slot(&pipe.product, "Pipe", "Pipe", "PIPE", center, size));
         }
 
         // Generate duct meshes (MEP - shown as rectangular boxes)
         for (i, duct) in self.ducts.iter().enumerate() {
-            let color = color_for_element_type("DUCT");
             let z_pos = (i % 2) as f32 * 4.0 - 2.0;
             let center = [0.0, 2.7 + y_offset, z_pos];
             let size = [8.0, 0.4, 0.6]; // Rectangular duct
-            let mesh = generate_box_with_normals(center, size, color);
-            let triangles = (mesh.indices.len() / 3) as u32;
-            add_element(
-                &mut elements, &mut current_triangle, triangles,
-                duct.product.id, "Duct",
-                duct.product.name.as_deref().unwrap_or("Duct"),
-                &duct.product.global_id,
-                center, size,
-            );
-            meshes.push(mesh);
+            slots.push(self.element_slot(&duct.product, "Duct", "Duct", "DUCT", center, size));
         }
 
         // Generate flow terminal meshes (vents, outlets)
         for (i, terminal) in self.flow_terminals.iter().enumerate() {
-            let color = color_for_element_type("FLOWTERMINAL");
             let x = (i % 4) as f32 * 2.5 - 3.75;
             let z = (i / 4) as f32 * 3.0 - 1.5;
             let center = [x, 2.9 + y_offset, z];
             let size = [0.4, 0.1, 0.4]; // Small square vent
-            let mesh = generate_box_with_normals(center, size, color);
-            let triangles = (mesh.indices.len() / 3) as u32;
-            add_element(
-                &mut elements, &mut current_triangle, triangles,
-                terminal.product.id, "FlowTerminal",
-                terminal.product.name.as_deref().unwrap_or("Vent"),
-                &terminal.product.global_id,
-                center, size,
-            );
-            meshes.push(mesh);
+            slots.push(self.element_slot(&terminal.product, "FlowTerminal", "Vent", "FLOWTERMINAL", center, size));
         }
 
         // Generate cable carrier meshes (electrical)
         for (i, carrier) in self.cable_carriers.iter().enumerate() {
-            let color = color_for_element_type("CABLE");
             let y_pos = 2.8 + (i / 2) as f32 * 0.2;
             let z_pos = (i % 2) as f32 * 6.0 - 3.0;
             let center = [0.0, y_pos + y_offset, z_pos];
             let size = [8.0, 0.08, 0.15]; // Cable tray
-            let mesh = generate_box_with_normals(center, size, color);
-            let triangles = (mesh.indices.len() / 3) as u32;
-            add_element(
-                &mut elements, &mut current_triangle, triangles,
-                carrier.product.id, "CableCarrier",
-                carrier.product.name.as_deref().unwrap_or("Cable Tray"),
-                &carrier.product.global_id,
-                center, size,
-            );
-            meshes.push(mesh);
+            slots.push(self.element_slot(&carrier.product, "CableCarrier", "Cable Tray", "CABLE", center, size));
         }
 
         // Generate proxy meshes (generic elements)
         for (i, proxy) in self.proxies.iter().enumerate() {
-            let color = color_for_element_type("PROXY");
             let x = (i % 3) as f32 * 2.0 - 2.0;
             let z = (i / 3) as f32 * 2.0 - 2.0;
             let center = [x, 1.0 + y_offset, z];
             let size = [0.5, 0.5, 0.5];
-            let mesh = generate_box_with_normals(center, size, color);
-            let triangles = (mesh.indices.len() / 3) as u32;
-            add_element(
-                &mut elements, &mut current_triangle, triangles,
-                proxy.product.id, "Proxy",
-                proxy.product.name.as_deref().unwrap_or("Element"),
-                &proxy.product.global_id,
-                center, size,
-            );
-            meshes.push(mesh);
+            slots.push(self.element_slot(&proxy.product, "Proxy", "Element", "PROXY", center, size));
         }
 
-        // If no elements, create a default building shape
-        if meshes.is_empty() {
+        // A blank model shows a default building shape; a loaded file without
+        // elements shows nothing
+        if slots.is_empty() && self.is_blank() {
             let default_elements = [
                 ([0.0, 0.0, 0.0], [10.0, 0.3, 8.0], "SLAB", "Floor"),
                 ([-4.9, 1.5, 0.0], [0.2, 3.0, 8.0], "WALL", "Left Wall"),
@@ -1023,32 +2134,57 @@ impl BimModel {
                 ([0.0, 3.15, 0.0], [10.0, 0.3, 8.0], "ROOF", "Roof"),
             ];
 
-            for (i, (center, size, elem_type, name)) in default_elements.iter().enumerate() {
-                let mesh = generate_box_with_normals(*center, *size, color_for_element_type(elem_type));
-                let triangles = (mesh.indices.len() / 3) as u32;
-                add_element(
-                    &mut elements, &mut current_triangle, triangles,
-                    i as i32, elem_type, name, &format!("default_{}", i),
-                    *center, *size,
-                );
-                meshes.push(mesh);
+            for (i, (center, size, elem_type, name)) in default_elements.into_iter().enumerate() {
+                slots.push(ElementSlot {
+                    id: i as i32,
+                    element_type: elem_type,
+                    name: name.to_string(),
+                    global_id: format!("default_{}", i),
+                    bounds: ElementSlot::placeholder_bounds(center, size),
+                    center,
+                    size,
+                    color: self.palette.color_for(elem_type),
+                });
             }
         }
 
-        // Merge all meshes
-        let merged = merge_meshes(meshes);
-        let bounds = merged.bounding_box();
+        slots
+    }
 
-        ModelMesh {
-            vertices: merged.vertices,
-            indices: merged.indices,
-            normals: merged.normals,
-            colors: merged.colors,
+    /// Lay out one product: where its tessellated body was placed, or as a
+    /// placeholder box at `center`
+    fn element_slot(
+        &self,
+        product: &IfcProduct,
+        element_type: &'static str,
+        default_name: &str,
+        color_type: &str,
+        center: [f32; 3],
+        size: [f32; 3],
+    ) -> ElementSlot {
+        let global_id = &product.global_id;
+        let (bounds, center, size) = match self.body_bounds(global_id) {
+            Some(bounds) => (bounds, bounds.center(), bounds.size()),
+            None => (ElementSlot::placeholder_bounds(center, size), center, size),
+        };
+        ElementSlot {
+            id: product.id,
+            element_type,
+            name: product.name.as_deref().unwrap_or(default_name).to_string(),
+            global_id: global_id.clone(),
             bounds,
-            elements,
+            center,
+            size,
+            color: self.element_color(global_id, color_type),
         }
     }
 
+    /// Tessellate one element laid out by `element_slots`
+    pub fn slot_mesh(&self, slot: &ElementSlot) -> Mesh {
+        self.element_mesh(&slot.global_id, slot.center, slot.size, slot.color)
+    }
+
+
     /// Get element by ID
     pub fn get_element_info(&self, element_id: i32) -> Option<ElementInfo> {
         let mesh = self.generate_meshes();
@@ -1063,6 +2199,9 @@ impl BimModel {
     ) -> ModelMesh {
         use super::geometry::Mesh;
 
+        if self.geometry_skipped {
+            return ModelMesh::default();
+        }
         let mut meshes = Vec::new();
         let mut elements = Vec::new();
         let mut current_triangle = 0u32;
@@ -1072,6 +2211,7 @@ impl BimModel {
         let highlight_color: [f32; 4] = [0.2, 0.9, 0.9, 1.0];
 
         // Helper to add element info
+        #[allow(clippy::too_many_arguments)]
         fn add_element(
             elements: &mut Vec<ElementInfo>,
             current_triangle: &mut u32,
@@ -1112,10 +2252,10 @@ impl BimModel {
         // Generate wall meshes
         if !hidden_types.contains("Wall") {
             for (i, wall) in self.walls.iter().enumerate() {
-                let color = color_for_element_type("WALL");
+                let color = self.element_color(&wall.product.global_id, "WALL");
                 let center = [i as f32 * 3.0, 1.5 + y_offset, 0.0];
                 let size = [2.5, 3.0, 0.2];
-                let mut mesh = generate_box_with_normals(center, size, color);
+                let mut mesh = self.element_mesh(&wall.product.global_id, center, size, color);
 
                 if selected_id == Some(wall.product.id) {
                     apply_highlight(&mut mesh, highlight_color);
@@ -1136,10 +2276,10 @@ impl BimModel {
         // Generate slab meshes (floors)
         if !hidden_types.contains("Slab") {
             for (i, slab) in self.slabs.iter().enumerate() {
-                let color = color_for_element_type("SLAB");
+                let color = self.element_color(&slab.product.global_id, "SLAB");
                 let center = [0.0, y_offset + i as f32 * 3.5, 0.0];
                 let size = [10.0, 0.3, 8.0];
-                let mut mesh = generate_box_with_normals(center, size, color);
+                let mut mesh = self.element_mesh(&slab.product.global_id, center, size, color);
 
                 if selected_id == Some(slab.product.id) {
                     apply_highlight(&mut mesh, highlight_color);
@@ -1160,12 +2300,12 @@ impl BimModel {
         // Generate column meshes
         if !hidden_types.contains("Column") {
             for (i, column) in self.columns.iter().enumerate() {
-                let color = color_for_element_type("COLUMN");
+                let color = self.element_color(&column.product.global_id, "COLUMN");
                 let x = (i % 4) as f32 * 3.0 - 4.5;
                 let z = (i / 4) as f32 * 3.0 - 3.0;
                 let center = [x, 1.5 + y_offset, z];
                 let size = [0.4, 3.0, 0.4];
-                let mut mesh = generate_box_with_normals(center, size, color);
+                let mut mesh = self.element_mesh(&column.product.global_id, center, size, color);
 
                 if selected_id == Some(column.product.id) {
                     apply_highlight(&mut mesh, highlight_color);
@@ -1186,10 +2326,10 @@ impl BimModel {
         // Generate beam meshes
         if !hidden_types.contains("Beam") {
             for (i, beam) in self.beams.iter().enumerate() {
-                let color = color_for_element_type("BEAM");
+                let color = self.element_color(&beam.product.global_id, "BEAM");
                 let center = [0.0, 2.8 + y_offset, i as f32 * 2.0 - 2.0];
                 let size = [8.0, 0.4, 0.3];
-                let mut mesh = generate_box_with_normals(center, size, color);
+                let mut mesh = self.element_mesh(&beam.product.global_id, center, size, color);
 
                 if selected_id == Some(beam.product.id) {
                     apply_highlight(&mut mesh, highlight_color);
@@ -1210,12 +2350,12 @@ impl BimModel {
         // Generate door meshes
         if !hidden_types.contains("Door") {
             for (i, door) in self.doors.iter().enumerate() {
-                let color = color_for_element_type("DOOR");
+                let color = self.element_color(&door.product.global_id, "DOOR");
                 let height = door.overall_height.unwrap_or(2.1) as f32;
                 let width = door.overall_width.unwrap_or(0.9) as f32;
                 let center = [i as f32 * 3.0 + 1.0, height / 2.0 + y_offset, 0.1];
                 let size = [width, height, 0.1];
-                let mut mesh = generate_box_with_normals(center, size, color);
+                let mut mesh = self.element_mesh(&door.product.global_id, center, size, color);
 
                 if selected_id == Some(door.product.id) {
                     apply_highlight(&mut mesh, highlight_color);
@@ -1236,12 +2376,12 @@ impl BimModel {
         // Generate window meshes
         if !hidden_types.contains("Window") {
             for (i, window) in self.windows.iter().enumerate() {
-                let color = color_for_element_type("WINDOW");
+                let color = self.element_color(&window.product.global_id, "WINDOW");
                 let height = window.overall_height.unwrap_or(1.2) as f32;
                 let width = window.overall_width.unwrap_or(1.0) as f32;
                 let center = [i as f32 * 3.0 + 1.5, 1.5 + y_offset, 0.1];
                 let size = [width, height, 0.05];
-                let mut mesh = generate_box_with_normals(center, size, color);
+                let mut mesh = self.element_mesh(&window.product.global_id, center, size, color);
 
                 if selected_id == Some(window.product.id) {
                     apply_highlight(&mut mesh, highlight_color);
@@ -1259,8 +2399,9 @@ impl BimModel {
             }
         }
 
-        // If no elements, create a default building shape
-        if meshes.is_empty() {
+        // A blank model shows a default building shape; a loaded file without
+        // elements shows nothing
+        if meshes.is_empty() && self.is_blank() {
             let default_elements = [
                 ([0.0, 0.0, 0.0], [10.0, 0.3, 8.0], "SLAB", "Floor", "Slab"),
                 ([-4.9, 1.5, 0.0], [0.2, 3.0, 8.0], "WALL", "Left Wall", "Wall"),
@@ -1274,7 +2415,7 @@ impl BimModel {
                 if hidden_types.contains(*type_name) {
                     continue;
                 }
-                let mut mesh = generate_box_with_normals(*center, *size, color_for_element_type(elem_type));
+                let mut mesh = generate_box_with_normals(*center, *size, self.palette.color_for(elem_type));
 
                 if selected_id == Some(i as i32) {
                     apply_highlight(&mut mesh, highlight_color);
@@ -1293,6 +2434,7 @@ impl BimModel {
         // Merge all meshes
         let merged = merge_meshes(meshes);
         let bounds = merged.bounding_box();
+        fit_element_bounds(&mut elements, &merged);
 
         ModelMesh {
             vertices: merged.vertices,
@@ -1304,3 +2446,541 @@ impl BimModel {
         }
     }
 }
+
+/// Shrink-wrap each element's bounds to the triangles it actually has
+/// (tessellated or clipped geometry differs from the placeholder box)
+fn fit_element_bounds(elements: &mut [ElementInfo], mesh: &Mesh) {
+    for element in elements {
+        let start = (element.triangle_start as usize * 3).min(mesh.indices.len());
+        let end = (start + element.triangle_count as usize * 3).min(mesh.indices.len());
+        let bounds = mesh.indices[start..end]
+            .iter()
+            .filter_map(|&i| mesh.vertices.get(i as usize * 3..i as usize * 3 + 3))
+            .map(|p| BoundingBox {
+                min: [p[0], p[1], p[2]],
+                max: [p[0], p[1], p[2]],
+            })
+            .reduce(|a, b| a.union(&b));
+        if let Some(bounds) = bounds {
+            element.bounds = bounds;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_element_slots_match_generated_mesh() {
+        for content in [
+            include_str!("../../../test/sample_architectural.ifc"),
+            include_str!("../../../test/sample_mep.ifc"),
+        ] {
+            let model = BimModel::from_ifc_file(&IfcFile::parse(content).unwrap()).unwrap();
+            let mesh = model.generate_meshes();
+            let slots = model.element_slots();
+            assert_eq!(slots.len(), mesh.elements.len());
+
+            for (slot, element) in slots.iter().zip(&mesh.elements) {
+                assert_eq!(slot.global_id, element.global_id);
+                assert_eq!((slot.bounds.min, slot.bounds.max), (element.bounds.min, element.bounds.max));
+                assert_eq!(model.slot_mesh(slot).indices.len() as u32, element.triangle_count * 3);
+            }
+            let bounds = slots.iter().map(|s| s.bounds).reduce(|a, b| a.union(&b)).unwrap();
+            assert_eq!(Some((bounds.min, bounds.max)), mesh.bounds.map(|b| (b.min, b.max)));
+        }
+    }
+
+    #[test]
+    fn test_load_options_skip_geometry_and_types() {
+        let ifc_file = IfcFile::parse(include_str!("../../../test/sample_architectural.ifc")).unwrap();
+        let full = BimModel::from_ifc_file(&ifc_file).unwrap();
+
+        let options = LoadOptions {
+            extract_geometry: false,
+            ..LoadOptions::default()
+        };
+        let model = BimModel::from_ifc_file_with_options(&ifc_file, &options, |_| {}).unwrap();
+        // Metadata as in a full load
+        assert_eq!(model.element_count, full.element_count);
+        assert_eq!(model.storeys.len(), full.storeys.len());
+        let info = model.get_info();
+        assert_eq!(info.project_name, full.get_info().project_name);
+        assert_eq!(info.stats.walls, full.get_info().stats.walls);
+        // But nothing to draw
+        assert!(!info.has_geometry);
+        assert!(model.body_meshes.is_empty());
+        let mesh = model.generate_meshes();
+        assert!(mesh.vertices.is_empty() && mesh.elements.is_empty() && mesh.bounds.is_none());
+        assert!(model.generate_meshes_filtered(&HashSet::new(), None).elements.is_empty());
+
+        // Only the types asked for
+        let options = LoadOptions {
+            element_types: Some(vec!["wall".to_string()]),
+            ..LoadOptions::default()
+        };
+        let walls = BimModel::from_ifc_file_with_options(&ifc_file, &options, |_| {}).unwrap();
+        assert_eq!(walls.element_count, full.walls.len());
+        assert!(walls.products().iter().all(|(t, _)| *t == "Wall"));
+        assert_eq!(walls.generate_meshes().elements.len(), full.walls.len());
+    }
+
+    #[test]
+    fn test_element_order_is_stable_across_builds() {
+        let content = include_str!("../../../test/sample_building.ifc");
+        let order = || -> Vec<(EntityId, String)> {
+            // Each parse gets its own entity HashMap (and hash seed)
+            let model = BimModel::from_ifc_file(&IfcFile::parse(content).unwrap()).unwrap();
+            let elements: Vec<String> = model.generate_meshes().elements.into_iter().map(|e| e.global_id).collect();
+            let products: Vec<(EntityId, String)> =
+                model.products().into_iter().map(|(_, p)| (p.id, p.global_id.clone())).collect();
+            assert_eq!(elements, products.iter().map(|(_, g)| g.clone()).collect::<Vec<_>>());
+            products
+        };
+
+        let first = order();
+        assert!(first.len() > 1);
+        for _ in 0..3 {
+            assert_eq!(order(), first);
+        }
+        // Within each element type, entities come in EntityId order
+        let model = BimModel::from_ifc_file(&IfcFile::parse(content).unwrap()).unwrap();
+        assert!(model.walls.windows(2).all(|w| w[0].product.id < w[1].product.id));
+    }
+
+    const WALL_WITH_OPENING: &str = r#"ISO-10303-21;
+HEADER;
+FILE_DESCRIPTION(('ViewDefinition'),'2;1');
+FILE_NAME('opening.ifc','2024-01-01',(''),(''),'','','');
+FILE_SCHEMA(('IFC4'));
+ENDSEC;
+DATA;
+#1=IFCWALL('wall-guid',$,'Wall',$,$,#20,#10,$,$);
+#2=IFCOPENINGELEMENT('opening-guid',$,'Opening',$,$,#23,#3,$,$);
+#3=IFCPRODUCTDEFINITIONSHAPE($,$,(#4));
+#4=IFCSHAPEREPRESENTATION($,'Body','SweptSolid',(#5));
+#5=IFCEXTRUDEDAREASOLID(#6,$,$,2.);
+#6=IFCRECTANGLEPROFILEDEF(.AREA.,$,$,1.,0.6);
+#7=IFCRELVOIDSELEMENT('rel-guid',$,$,$,#1,#2);
+#8=IFCOPENINGELEMENT('unplaced-guid',$,'Opening',$,$,$,#3,$,$);
+#9=IFCRELVOIDSELEMENT('rel2-guid',$,$,$,#1,#8);
+#10=IFCPRODUCTDEFINITIONSHAPE($,$,(#11));
+#11=IFCSHAPEREPRESENTATION($,'Body','SweptSolid',(#12));
+#12=IFCEXTRUDEDAREASOLID(#13,$,$,3.);
+#13=IFCRECTANGLEPROFILEDEF(.AREA.,$,$,4.,0.2);
+#20=IFCLOCALPLACEMENT($,#21);
+#21=IFCAXIS2PLACEMENT3D(#22,$,$);
+#22=IFCCARTESIANPOINT((5.,0.,0.));
+#23=IFCLOCALPLACEMENT(#20,#24);
+#24=IFCAXIS2PLACEMENT3D(#25,$,$);
+#25=IFCCARTESIANPOINT((1.,0.,0.));
+ENDSEC;
+END-ISO-10303-21;
+"#;
+
+    #[test]
+    fn test_opening_is_cut_from_host_wall() {
+        let file = IfcFile::parse(WALL_WITH_OPENING).unwrap();
+        let model = BimModel::from_ifc_file(&file).unwrap();
+
+        assert_eq!(model.openings.len(), 2);
+        assert!(model.openings.iter().all(|o| o.host_global_id.as_deref() == Some("wall-guid")));
+        // Openings are voids, not elements of their own
+        assert_eq!(model.element_count, 1);
+
+        // A 1m x 2m hole, 1m along the wall from its placement; the opening
+        // without a placement cuts nothing
+        let body = &model.body_meshes["wall-guid"];
+        let expected = 4.0 * 0.2 * 3.0 - 1.0 * 0.2 * 2.0;
+        assert!((body.volume() - expected).abs() < 1e-4, "volume {}", body.volume());
+
+        // Seen through the wall (render axes: Y up, wall along X at z = 0,
+        // relative to the wall's placement as the model origin)
+        assert_eq!(model.origin.offset, [5.0, 0.0, 0.0]);
+        let mesh = model.generate_meshes();
+        let wall = &mesh.elements[0];
+        let blocked = |x: f32, y: f32| {
+            let (origin, dir) = (glam::Vec3::new(x, y, 1.0), -glam::Vec3::Z);
+            mesh.indices.chunks_exact(3).any(|tri| {
+                let p = |i: u32| glam::Vec3::from_slice(&mesh.vertices[i as usize * 3..i as usize * 3 + 3]);
+                crate::renderer::ray_triangle_intersect(origin, dir, p(tri[0]), p(tri[1]), p(tri[2])).is_some()
+            })
+        };
+        assert!(!blocked(1.0, 1.0));
+        assert!(blocked(-1.0, 1.0) && blocked(1.0, 2.5));
+        assert_eq!(wall.bounds.min, [-2.0, 0.0, -0.1]);
+        assert_eq!(wall.bounds.max, [2.0, 3.0, 0.1]);
+
+        let regions = model.element_material_regions("wall-guid");
+        assert_eq!(regions.len(), 1);
+        assert_eq!(regions[0].triangle_count, wall.triangle_count);
+    }
+
+    #[test]
+    fn test_boolean_clipped_wall_is_cut() {
+        let content = r#"ISO-10303-21;
+HEADER;
+FILE_DESCRIPTION(('ViewDefinition'),'2;1');
+FILE_NAME('clip.ifc','2024-01-01',(''),(''),'','','');
+FILE_SCHEMA(('IFC4'));
+ENDSEC;
+DATA;
+#1=IFCWALL('wall-guid',$,'Wall',$,$,$,#2,$,$);
+#2=IFCPRODUCTDEFINITIONSHAPE($,$,(#3));
+#3=IFCSHAPEREPRESENTATION($,'Body','Clipping',(#12));
+#4=IFCRECTANGLEPROFILEDEF(.AREA.,$,$,4.,0.2);
+#5=IFCEXTRUDEDAREASOLID(#4,$,$,4.);
+#6=IFCCARTESIANPOINT((0.,0.,3.));
+#7=IFCDIRECTION((0.,0.,1.));
+#8=IFCAXIS2PLACEMENT3D(#6,#7,$);
+#9=IFCPLANE(#8);
+#10=IFCHALFSPACESOLID(#9,.F.);
+#12=IFCBOOLEANCLIPPINGRESULT(.DIFFERENCE.,#5,#10);
+ENDSEC;
+END-ISO-10303-21;
+"#;
+        let file = IfcFile::parse(content).unwrap();
+        let model = BimModel::from_ifc_file(&file).unwrap();
+
+        // The 4m high extrusion is cut at 3m (Z up -> Y up)
+        let mesh = model.generate_meshes();
+        let bounds = mesh.bounds.unwrap();
+        assert!((bounds.max[1] - 3.0).abs() < 1e-5, "top {}", bounds.max[1]);
+        assert_eq!(bounds.min[1], 0.0);
+        assert_eq!([bounds.min[0], bounds.max[0]], [-2.0, 2.0]);
+        let volume = model.body_meshes["wall-guid"].volume();
+        assert!((volume - 4.0 * 0.2 * 3.0).abs() < 1e-4, "volume {}", volume);
+    }
+
+    #[test]
+    fn test_body_mesh_has_placement_baked_in() {
+        // A triangle placed 10, 20 across from a storey placement 3 up
+        let content = r#"ISO-10303-21;
+HEADER;
+FILE_SCHEMA(('IFC4'));
+ENDSEC;
+DATA;
+#1=IFCCARTESIANPOINT((0.,0.,0.));
+#2=IFCAXIS2PLACEMENT3D(#1,$,$);
+#3=IFCLOCALPLACEMENT($,#2);
+#4=IFCSITE('site-guid',$,'Site',$,$,#3,$,$,.ELEMENT.,$,$,$,$,$);
+#5=IFCCARTESIANPOINT((0.,0.,3.));
+#6=IFCAXIS2PLACEMENT3D(#5,$,$);
+#7=IFCLOCALPLACEMENT(#3,#6);
+#8=IFCCARTESIANPOINT((10.,20.,0.));
+#9=IFCAXIS2PLACEMENT3D(#8,$,$);
+#10=IFCLOCALPLACEMENT(#7,#9);
+#20=IFCCARTESIANPOINTLIST3D(((0.,0.,0.),(1.,0.,0.),(0.,1.,0.)));
+#21=IFCTRIANGULATEDFACESET(#20,$,$,((1,2,3)),$);
+#22=IFCSHAPEREPRESENTATION($,'Body','Tessellation',(#21));
+#23=IFCPRODUCTDEFINITIONSHAPE($,$,(#22));
+#30=IFCBUILDINGELEMENTPROXY('proxy-guid',$,'Proxy',$,$,#10,#23,$,$);
+ENDSEC;
+END-ISO-10303-21;
+"#;
+        let model = BimModel::from_ifc_file(&IfcFile::parse(content).unwrap()).unwrap();
+        assert_eq!(model.origin, LocalOrigin::default());
+        let body = &model.body_meshes["proxy-guid"];
+        assert_eq!(body.vertices, vec![10.0, 20.0, 3.0, 11.0, 20.0, 3.0, 10.0, 21.0, 3.0]);
+
+        // Drawn where it was placed (Z up -> Y up), not on the placeholder grid
+        let mesh = model.generate_meshes();
+        assert_eq!(mesh.vertices, vec![10.0, 3.0, -20.0, 11.0, 3.0, -20.0, 10.0, 3.0, -21.0]);
+        let element = &mesh.elements[0];
+        assert_eq!(element.bounds.min, [10.0, 3.0, -21.0]);
+        assert_eq!(element.bounds.max, [11.0, 3.0, -20.0]);
+    }
+
+    #[test]
+    fn test_unlinked_elements_are_assigned_to_storeys_by_elevation() {
+        let content = r#"ISO-10303-21;
+HEADER;
+FILE_DESCRIPTION(('ViewDefinition'),'2;1');
+FILE_NAME('storeys.ifc','2024-01-01',(''),(''),'','','');
+FILE_SCHEMA(('IFC4'));
+ENDSEC;
+DATA;
+#1=IFCBUILDINGSTOREY('ground-guid',$,'Ground Floor',$,$,$,$,$,.ELEMENT.,0.);
+#2=IFCBUILDINGSTOREY('first-guid',$,'First Floor',$,$,$,$,$,.ELEMENT.,3.);
+#10=IFCCARTESIANPOINT((0.,0.,4.5));
+#11=IFCAXIS2PLACEMENT3D(#10,$,$);
+#12=IFCLOCALPLACEMENT($,#11);
+#13=IFCCARTESIANPOINT((5.,0.,0.5));
+#14=IFCAXIS2PLACEMENT3D(#13,$,$);
+#15=IFCLOCALPLACEMENT($,#14);
+#20=IFCWALL('linked-guid',$,'Linked Wall',$,$,#12,$,$,$);
+#21=IFCCOLUMN('upper-guid',$,'Upper Column',$,$,#12,$,$,$);
+#22=IFCCOLUMN('lower-guid',$,'Lower Column',$,$,#15,$,$,$);
+#23=IFCBEAM('unplaced-guid',$,'Unplaced Beam',$,$,$,$,$,$);
+#30=IFCRELCONTAINEDINSPATIALSTRUCTURE('rel-guid',$,$,$,(#20),#1);
+ENDSEC;
+END-ISO-10303-21;
+"#;
+        let file = IfcFile::parse(content).unwrap();
+        let model = BimModel::from_ifc_file(&file).unwrap();
+        let mut elevations = model.storey_elevations();
+        elevations.sort_by(f32::total_cmp);
+        assert_eq!(elevations, vec![0.0, 3.0]);
+
+        // The explicit link wins even though the wall is placed upstairs
+        let (storey, heuristic) = model.element_storey("linked-guid").unwrap();
+        assert_eq!((storey.name.as_str(), heuristic), ("Ground Floor", false));
+
+        let (storey, heuristic) = model.element_storey("upper-guid").unwrap();
+        assert_eq!((storey.name.as_str(), heuristic), ("First Floor", true));
+        let (storey, heuristic) = model.element_storey("lower-guid").unwrap();
+        assert_eq!((storey.name.as_str(), heuristic), ("Ground Floor", true));
+
+        // Nothing to go on without a placement or geometry
+        assert!(model.element_storey("unplaced-guid").is_none());
+    }
+
+    #[test]
+    fn test_connected_elements_report_each_other() {
+        let content = r#"ISO-10303-21;
+HEADER;
+FILE_DESCRIPTION(('ViewDefinition'),'2;1');
+FILE_NAME('connections.ifc','2024-01-01',(''),(''),'','','');
+FILE_SCHEMA(('IFC4'));
+ENDSEC;
+DATA;
+#1=IFCWALL('wall-a',$,'Wall A',$,$,$,$,$,$);
+#2=IFCWALL('wall-b',$,'Wall B',$,$,$,$,$,$);
+#3=IFCCOLUMN('column-guid',$,'Column',$,$,$,$,$,$);
+#4=IFCBEAM('beam-guid',$,'Beam',$,$,$,$,$,$);
+#5=IFCSLAB('slab-guid',$,'Slab',$,$,$,$,$,$);
+#10=IFCRELCONNECTSPATHELEMENTS('rel-a',$,$,$,$,#1,#2,(),(),.ATEND.,.ATSTART.);
+#11=IFCRELCONNECTSELEMENTS('rel-b',$,$,$,$,#4,#3);
+#12=IFCRELCONNECTSELEMENTS('rel-c',$,$,$,$,#3,#4);
+ENDSEC;
+END-ISO-10303-21;
+"#;
+        let model = BimModel::from_ifc_file(&IfcFile::parse(content).unwrap()).unwrap();
+        assert_eq!(model.connected_elements("wall-a"), ["wall-b"]);
+        assert_eq!(model.connected_elements("wall-b"), ["wall-a"]);
+        // Listed once, whichever side of the relationship it is on
+        assert_eq!(model.connected_elements("column-guid"), ["beam-guid"]);
+        assert_eq!(model.connected_elements("beam-guid"), ["column-guid"]);
+        assert!(model.connected_elements("slab-guid").is_empty());
+    }
+
+    #[test]
+    fn test_elements_on_layers_toggle_independently() {
+        let content = r#"ISO-10303-21;
+HEADER;
+FILE_DESCRIPTION(('ViewDefinition'),'2;1');
+FILE_NAME('layers.ifc','2024-01-01',(''),(''),'','','');
+FILE_SCHEMA(('IFC4'));
+ENDSEC;
+DATA;
+#1=IFCWALL('wall-guid',$,'Wall',$,$,$,#3,$,$);
+#2=IFCCOLUMN('column-guid',$,'Column',$,$,$,$,$,$);
+#3=IFCPRODUCTDEFINITIONSHAPE($,$,(#4));
+#4=IFCSHAPEREPRESENTATION($,'Body','SweptSolid',(#5));
+#5=IFCEXTRUDEDAREASOLID(#6,$,$,2.);
+#6=IFCRECTANGLEPROFILEDEF(.AREA.,$,$,1.,0.2);
+#7=IFCBEAM('beam-guid',$,'Beam',$,$,$,$,$,$);
+#10=IFCPRESENTATIONLAYERASSIGNMENT('A-WALL',$,(#4),$);
+#11=IFCPRESENTATIONLAYERASSIGNMENT('S-COLS',$,(#2),$);
+ENDSEC;
+END-ISO-10303-21;
+"#;
+        let model = BimModel::from_ifc_file(&IfcFile::parse(content).unwrap()).unwrap();
+        assert_eq!(model.layers(), vec!["A-WALL".to_string(), "S-COLS".to_string()]);
+        assert_eq!(model.walls[0].product.layer.as_deref(), Some("A-WALL"));
+        assert_eq!(model.columns[0].product.layer.as_deref(), Some("S-COLS"));
+        assert_eq!(model.beams[0].product.layer, None);
+
+        let shown = |hidden: &[&str]| -> Vec<String> {
+            let hidden: HashSet<String> = hidden.iter().map(|l| l.to_string()).collect();
+            let mut mesh = model.generate_meshes();
+            mesh.hide_layers(&model, &hidden);
+            assert_eq!(
+                mesh.indices.len() / 3,
+                mesh.elements.iter().map(|e| e.triangle_count as usize).sum::<usize>()
+            );
+            mesh.elements.into_iter().map(|e| e.global_id).collect()
+        };
+        assert_eq!(shown(&[]), ["wall-guid", "column-guid", "beam-guid"]);
+        assert_eq!(shown(&["A-WALL"]), ["column-guid", "beam-guid"]);
+        assert_eq!(shown(&["S-COLS"]), ["wall-guid", "beam-guid"]);
+        assert_eq!(shown(&["A-WALL", "S-COLS"]), ["beam-guid"]);
+    }
+
+    #[test]
+    fn test_geometry_budget_sums_to_model_totals() {
+        let content = include_str!("../../../test/sample_architectural.ifc");
+        let model = BimModel::from_ifc_file(&IfcFile::parse(content).unwrap()).unwrap();
+        let mesh = model.generate_meshes();
+        let budget = geometry_budget([&mesh]);
+
+        let total = |f: fn(&TypeBudget) -> u32| budget.iter().map(f).sum::<u32>() as usize;
+        assert_eq!(total(|b| b.triangle_count), mesh.indices.len() / 3);
+        assert_eq!(total(|b| b.vertex_count), mesh.vertices.len() / 3);
+        assert_eq!(total(|b| b.element_count), mesh.elements.len());
+        assert!(budget.windows(2).all(|w| w[0].triangle_count >= w[1].triangle_count));
+        let walls = budget.iter().find(|b| b.ifc_type == "Wall").unwrap();
+        assert_eq!(walls.element_count as usize, model.walls.len());
+
+        // Two copies of the model: every count doubles
+        let doubled = geometry_budget([&mesh, &mesh]);
+        assert!(doubled.iter().zip(&budget).all(|(d, b)| d.triangle_count == 2 * b.triangle_count));
+    }
+
+    #[test]
+    fn test_furniture_lod_halves_its_triangles_only() {
+        // A finely tessellated, gently curved table top and a wall box
+        let n = 20u32;
+        let mut top = Mesh::new();
+        for j in 0..=n {
+            for i in 0..=n {
+                let (x, z) = (i as f32 / n as f32, j as f32 / n as f32);
+                top.add_vertex(x, 0.8 + 0.02 * (x * 3.0).sin() * (z * 2.0).cos(), z);
+                top.add_normal(0.0, 1.0, 0.0);
+                top.add_color(0.6, 0.4, 0.2, 1.0);
+            }
+        }
+        for j in 0..n {
+            for i in 0..n {
+                let k = j * (n + 1) + i;
+                top.add_triangle(k, k + n + 1, k + 1);
+                top.add_triangle(k + 1, k + n + 1, k + n + 2);
+            }
+        }
+        let wall = generate_box_with_normals([3.0, 1.5, 0.0], [2.5, 3.0, 0.2], [0.8, 0.8, 0.8, 1.0]);
+        let parts = [("Furniture", &top), ("Wall", &wall)];
+
+        let mut mesh = ModelMesh {
+            vertices: Vec::new(),
+            indices: Vec::new(),
+            normals: Vec::new(),
+            colors: Vec::new(),
+            bounds: None,
+            elements: Vec::new(),
+        };
+        for (id, (element_type, part)) in parts.iter().enumerate() {
+            let base = (mesh.vertices.len() / 3) as u32;
+            mesh.elements.push(ElementInfo {
+                id: id as i32,
+                element_type: element_type.to_string(),
+                name: element_type.to_string(),
+                global_id: element_type.to_string(),
+                bounds: part.bounding_box().unwrap(),
+                triangle_start: (mesh.indices.len() / 3) as u32,
+                triangle_count: part.triangle_count() as u32,
+            });
+            mesh.vertices.extend_from_slice(&part.vertices);
+            mesh.normals.extend_from_slice(&part.normals);
+            mesh.colors.extend_from_slice(&part.colors);
+            mesh.indices.extend(part.indices.iter().map(|i| i + base));
+        }
+        let wall_triangles = mesh.element_mesh(&mesh.elements[1]);
+
+        mesh.apply_lod(&HashMap::from([("Furniture".to_string(), 0.5)]));
+        let furniture = mesh.elements[0].triangle_count as f32;
+        let original = top.triangle_count() as f32;
+        assert!((furniture / original - 0.5).abs() < 0.05, "{} of {} triangles", furniture, original);
+        assert_eq!(mesh.indices.len() / 3, mesh.elements.iter().map(|e| e.triangle_count as usize).sum::<usize>());
+        assert_eq!(mesh.validate().report.degenerate_tris, 0);
+
+        // The wall is untouched
+        assert_eq!(mesh.elements[1].triangle_count as usize, wall.triangle_count());
+        let after = mesh.element_mesh(&mesh.elements[1]);
+        assert_eq!(after.vertices, wall_triangles.vertices);
+        assert_eq!(after.indices, wall_triangles.indices);
+    }
+
+    #[test]
+    fn test_element_mesh_is_compact() {
+        let mesh = BimModel::new().generate_meshes();
+        let roof = mesh.elements.iter().find(|e| e.name == "Roof").unwrap();
+        let element = mesh.element_mesh(roof);
+
+        assert_eq!(element.global_id, roof.global_id);
+        assert_eq!(element.indices.len(), roof.triangle_count as usize * 3);
+        // A box: 24 flat-shaded vertices, all referenced
+        assert_eq!(element.vertices.len(), 24 * 3);
+        assert_eq!(element.colors.len(), 24 * 4);
+        assert!(element.indices.iter().all(|&i| i < 24));
+        assert!(!element.is_completion_marker());
+        assert!(ElementMesh::completion_marker().is_completion_marker());
+    }
+
+    #[test]
+    fn test_spatial_only_model_reports_no_geometry() {
+        let content = r#"ISO-10303-21;
+HEADER;
+FILE_DESCRIPTION(('ViewDefinition'),'2;1');
+FILE_NAME('spatial.ifc','2024-01-01',(''),(''),'','','');
+FILE_SCHEMA(('IFC4'));
+ENDSEC;
+DATA;
+#1=IFCPROJECT('project-guid',$,'Project',$,$,$,$,$,$);
+#2=IFCSITE('site-guid',$,'Site',$,$,$,$,$,.ELEMENT.,$,$,$,$,$);
+#3=IFCBUILDING('building-guid',$,'Building',$,$,$,$,$,.ELEMENT.,$,$,$);
+#4=IFCBUILDINGSTOREY('storey-guid',$,'Ground Floor',$,$,$,$,$,.ELEMENT.,0.);
+#5=IFCRELAGGREGATES('agg-1',$,$,$,#1,(#2));
+#6=IFCRELAGGREGATES('agg-2',$,$,$,#2,(#3));
+#7=IFCRELAGGREGATES('agg-3',$,$,$,#3,(#4));
+ENDSEC;
+END-ISO-10303-21;
+"#;
+        let model = BimModel::from_ifc_file(&IfcFile::parse(content).unwrap()).unwrap();
+        let info = model.get_info();
+        assert_eq!(info.project_name, "Project");
+        assert_eq!(info.stats.storeys, 1);
+        assert!(!info.has_geometry);
+        assert!(model.generate_meshes().bounds.is_none());
+
+        let content = include_str!("../../../test/sample_building.ifc");
+        assert!(BimModel::from_ifc_file(&IfcFile::parse(content).unwrap()).unwrap().get_info().has_geometry);
+    }
+
+    #[test]
+    fn test_door_and_window_sizes_read_in_each_schema() {
+        let file = |schema: &str, door: &str, window: &str| {
+            format!(
+                "ISO-10303-21;
+HEADER;
+FILE_DESCRIPTION(('ViewDefinition'),'2;1');
+FILE_NAME('openings.ifc','2024-01-01',(''),(''),'','','');
+FILE_SCHEMA(('{}'));
+ENDSEC;
+DATA;
+#1=IFCPROJECT('project-guid',$,'Project',$,$,$,$,$,$);
+#10={}
+#11={}
+ENDSEC;
+END-ISO-10303-21;
+",
+                schema, door, window
+            )
+        };
+        let sizes = |content: &str| {
+            let model = BimModel::from_ifc_file(&IfcFile::parse(content).unwrap()).unwrap();
+            let (door, window) = (&model.doors[0], &model.windows[0]);
+            [door.overall_height, door.overall_width, window.overall_height, window.overall_width]
+        };
+
+        let ifc2x3 = file(
+            "IFC2X3",
+            "IFCDOOR('door-guid',$,'Door',$,$,$,$,'D1',2.1,0.9);",
+            "IFCWINDOW('window-guid',$,'Window',$,$,$,$,'W1',1.2,1.5);",
+        );
+        assert_eq!(sizes(&ifc2x3), [Some(2.1), Some(0.9), Some(1.2), Some(1.5)]);
+
+        // IFC4 appends the predefined and operation/partitioning types
+        let ifc4 = file(
+            "IFC4",
+            "IFCDOOR('door-guid',$,'Door',$,$,$,$,'D1',2.1,0.9,.DOOR.,.SINGLE_SWING_LEFT.,$);",
+            "IFCWINDOW('window-guid',$,'Window',$,$,$,$,'W1',1.2,1.5,.WINDOW.,.SINGLE_PANEL.,$);",
+        );
+        assert_eq!(sizes(&ifc4), [Some(2.1), Some(0.9), Some(1.2), Some(1.5)]);
+
+        let content = include_str!("../../../test/sample_architectural.ifc");
+        let model = BimModel::from_ifc_file(&IfcFile::parse(content).unwrap()).unwrap();
+        let main = model.doors.iter().find(|d| d.product.name.as_deref() == Some("Door-Main")).unwrap();
+        assert_eq!((main.overall_height, main.overall_width), (Some(2.4), Some(1.2)));
+    }
+}
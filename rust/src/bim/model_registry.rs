@@ -3,9 +3,14 @@
 //! Manages multiple BIM models for federated model support.
 //! Enables loading, unloading, and visibility control of multiple IFC files.
 
-use super::model::{BimModel, ModelInfo};
-use super::geometry::BoundingBox;
+use super::model::{BimModel, ElementInfo, ModelInfo, ModelMesh};
+use super::explode::ExplodeMode;
+use super::geometry::{BoundingBox, Mesh};
+use super::handles::ElementHandles;
+use super::ifc_parser::IfcFile;
+use crate::renderer::Bvh;
 use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
 
 /// Unique identifier for a loaded model
 pub type ModelId = String;
@@ -21,23 +26,204 @@ pub struct RegisteredModel {
     pub file_path: Option<String>,
     /// Whether this model is visible
     pub visible: bool,
+    /// Whether the model's geometry is in memory (false after
+    /// `unload_geometry`: its metadata stays, but it is not drawn)
+    pub geometry_loaded: bool,
     /// Transform matrix (4x4, column-major) for model positioning
     pub transform: [f32; 16],
     /// Cached bounding box
     pub bounds: Option<BoundingBox>,
+    /// Cached bounding box of each element (keyed by GlobalId)
+    pub element_bounds: HashMap<String, BoundingBox>,
+    /// Mesh and BVH for ray casting, built on first use
+    pick_cache: PickCache,
+}
+
+/// Generated mesh of a model with a BVH over its triangles, for ray casting
+#[derive(Debug)]
+pub struct PickMesh {
+    pub mesh: ModelMesh,
+    pub bvh: Bvh,
+}
+
+impl PickMesh {
+    /// Closest triangle hit by a ray: distance and triangle index
+    pub fn intersect(&self, ray_origin: glam::Vec3, ray_dir: glam::Vec3) -> Option<(f32, usize)> {
+        self.bvh.intersect(ray_origin, ray_dir, &self.mesh.vertices, &self.mesh.indices)
+    }
+
+    /// Unit normal of a triangle, facing against `towards` (e.g. the ray
+    /// direction, so that it points back at the viewer)
+    pub fn facing_normal(&self, triangle: usize, towards: glam::Vec3) -> Option<glam::Vec3> {
+        let corner = |k: usize| -> Option<glam::Vec3> {
+            let i = *self.mesh.indices.get(triangle * 3 + k)? as usize * 3;
+            Some(glam::Vec3::from_slice(self.mesh.vertices.get(i..i + 3)?))
+        };
+        let (a, b, c) = (corner(0)?, corner(1)?, corner(2)?);
+        let normal = (b - a).cross(c - a).try_normalize()?;
+        Some(if normal.dot(towards) > 0.0 { -normal } else { normal })
+    }
+
+    /// Element a triangle belongs to
+    pub fn element_at(&self, triangle: usize) -> Option<&ElementInfo> {
+        let triangle = triangle as u32;
+        self.mesh
+            .elements
+            .iter()
+            .find(|e| (e.triangle_start..e.triangle_start + e.triangle_count).contains(&triangle))
+    }
+}
+
+/// A model's geometry tessellated away from the registry, swapped in with
+/// `RegisteredModel::install_geometry`, so the registry stays unlocked
+/// while tessellating
+#[derive(Debug)]
+pub struct TessellatedGeometry {
+    body_meshes: HashMap<String, Mesh>,
+    bounds: Option<BoundingBox>,
+    element_bounds: HashMap<String, BoundingBox>,
+}
+
+impl TessellatedGeometry {
+    /// Tessellate a copy of a model's element data from `ifc_file`, the
+    /// file the model was loaded from
+    pub fn build(mut model: BimModel, ifc_file: &IfcFile) -> Self {
+        model.retessellate(ifc_file);
+        let (bounds, element_bounds) = cached_bounds(&model);
+        Self {
+            body_meshes: model.body_meshes,
+            bounds,
+            element_bounds,
+        }
+    }
+}
+
+/// Bounds of a model and of each of its elements, from the element layout
+/// (placeholder boxes and tessellated bodies' bounds), without generating
+/// the model's mesh
+fn cached_bounds(model: &BimModel) -> (Option<BoundingBox>, HashMap<String, BoundingBox>) {
+    let slots = model.element_slots();
+    let bounds = slots.iter().map(|slot| slot.bounds).reduce(|a, b| a.union(&b));
+    let element_bounds = slots.into_iter().map(|slot| (slot.global_id, slot.bounds)).collect();
+    (bounds, element_bounds)
+}
+
+/// Explode factor and mode a mesh was generated at
+type ExplodeSetting = (f32, ExplodeMode);
+
+/// Pick mesh with the explode setting it was generated at; cloning a model
+/// starts with an empty cache
+#[derive(Debug, Default)]
+struct PickCache(Mutex<Option<(ExplodeSetting, Arc<PickMesh>)>>);
+
+impl Clone for PickCache {
+    fn clone(&self) -> Self {
+        Self::default()
+    }
 }
 
 impl RegisteredModel {
     /// Create a new registered model with default settings
     pub fn new(model: BimModel, name: String, file_path: Option<String>) -> Self {
-        Self {
+        let mut registered = Self {
             model,
             name,
             file_path,
             visible: true,
+            geometry_loaded: true,
             transform: Self::identity_matrix(),
             bounds: None,
+            element_bounds: HashMap::new(),
+            pick_cache: PickCache::default(),
+        };
+        registered.geometry_changed();
+        registered
+    }
+
+    /// Recompute the cached bounds and drop the pick mesh after the model's
+    /// geometry changed
+    pub fn geometry_changed(&mut self) {
+        (self.bounds, self.element_bounds) = cached_bounds(&self.model);
+        self.pick_cache = PickCache::default();
+    }
+
+    /// Estimated memory held by the model's geometry and the caches built
+    /// from it (element bounds, pick mesh), in bytes
+    pub fn geometry_bytes(&self) -> usize {
+        let bounds: usize = self
+            .element_bounds
+            .keys()
+            .map(|k| size_of::<String>() + k.capacity() + size_of::<BoundingBox>())
+            .sum();
+        let pick_mesh = self.pick_cache.0.lock().unwrap().as_ref().map_or(0, |(_, pick)| {
+            pick.mesh.heap_bytes() + pick.bvh.heap_bytes()
+        });
+        self.model.geometry_bytes() + bounds + pick_mesh
+    }
+
+    /// Whether the model is visible with its geometry loaded
+    pub fn is_drawn(&self) -> bool {
+        self.visible && self.geometry_loaded
+    }
+
+    /// Free the tessellated geometry and pick mesh, keeping the element data
+    /// and cached bounds; the model is no longer drawn
+    pub fn unload_geometry(&mut self) {
+        self.model.body_meshes = HashMap::new();
+        self.pick_cache = PickCache::default();
+        self.geometry_loaded = false;
+    }
+
+    /// Tessellate the geometry again after `unload_geometry`; `ifc_file`
+    /// must be the file the model was loaded from
+    pub fn reload_geometry(&mut self, ifc_file: &IfcFile) {
+        self.install_geometry(TessellatedGeometry::build(self.model.clone(), ifc_file));
+    }
+
+    /// Show geometry tessellated with `TessellatedGeometry::build` from
+    /// this model's element data
+    pub fn install_geometry(&mut self, geometry: TessellatedGeometry) {
+        self.model.body_meshes = geometry.body_meshes;
+        self.bounds = geometry.bounds;
+        self.element_bounds = geometry.element_bounds;
+        self.pick_cache = PickCache::default();
+        self.geometry_loaded = true;
+    }
+
+    /// Mesh and BVH for ray casting at an explode setting
+    ///
+    /// Built on first use and kept until the setting changes or
+    /// `geometry_changed` is called.
+    pub fn pick_mesh(&self, explode: ExplodeSetting) -> Arc<PickMesh> {
+        let mut cache = self.pick_cache.0.lock().unwrap();
+        if let Some((key, pick_mesh)) = cache.as_ref() {
+            if *key == explode {
+                return pick_mesh.clone();
+            }
         }
+
+        let (factor, mode) = explode;
+        let mut mesh = self.model.generate_meshes();
+        mesh.apply_explode(factor, mode, &self.model.storey_elevations());
+        let bvh = Bvh::build(&mesh.vertices, &mesh.indices);
+        let pick_mesh = Arc::new(PickMesh { mesh, bvh });
+        *cache = Some((explode, pick_mesh.clone()));
+        pick_mesh
+    }
+
+    /// Cached bounds (min, max) of each element, moved by the model
+    /// transform (the box around the transformed corners)
+    pub fn transformed_element_bounds(&self) -> impl Iterator<Item = (&str, glam::Vec3, glam::Vec3)> + '_ {
+        let transform = glam::Mat4::from_cols_array(&self.transform);
+        self.element_bounds.iter().map(move |(global_id, bounds)| {
+            let (min, max) = (0..8)
+                .map(|corner| {
+                    let pick = |k: usize| if corner & (1 << k) == 0 { bounds.min[k] } else { bounds.max[k] };
+                    transform.transform_point3(glam::Vec3::new(pick(0), pick(1), pick(2)))
+                })
+                .fold((glam::Vec3::MAX, glam::Vec3::MIN), |(min, max), p| (min.min(p), max.max(p)));
+            (global_id.as_str(), min, max)
+        })
     }
 
     /// Identity transform matrix
@@ -60,6 +246,8 @@ pub struct ModelRegistry {
     primary_model: Option<ModelId>,
     /// Counter for generating unique IDs
     next_id: u32,
+    /// Element handles, kept for the session so reloads get the same ones
+    handles: ElementHandles,
 }
 
 impl ModelRegistry {
@@ -69,6 +257,7 @@ impl ModelRegistry {
             models: HashMap::new(),
             primary_model: None,
             next_id: 1,
+            handles: ElementHandles::default(),
         }
     }
 
@@ -83,6 +272,7 @@ impl ModelRegistry {
     /// Returns the assigned model ID
     pub fn add_model(&mut self, model: BimModel, name: String, file_path: Option<String>) -> ModelId {
         let id = self.generate_id();
+        self.handles.assign_model(&model);
         let registered = RegisteredModel::new(model, name, file_path);
 
         // If this is the first model, make it primary
@@ -96,6 +286,7 @@ impl ModelRegistry {
 
     /// Add a model with a specific ID (for backward compatibility)
     pub fn add_model_with_id(&mut self, id: ModelId, model: BimModel, name: String, file_path: Option<String>) -> ModelId {
+        self.handles.assign_model(&model);
         let registered = RegisteredModel::new(model, name, file_path);
 
         // If this is the first model, make it primary
@@ -194,7 +385,7 @@ impl ModelRegistry {
     pub fn list_visible_models(&self) -> Vec<ModelId> {
         self.models
             .iter()
-            .filter(|(_, m)| m.visible)
+            .filter(|(_, m)| m.is_drawn())
             .map(|(id, _)| id.clone())
             .collect()
     }
@@ -214,24 +405,34 @@ impl ModelRegistry {
         self.models.contains_key(id)
     }
 
-    /// Clear all models
+    /// Clear all models (element handles are kept)
     pub fn clear(&mut self) {
         self.models.clear();
         self.primary_model = None;
     }
 
+    /// Handle of an element by GlobalId (see [`ElementHandles`])
+    pub fn handle_for_global_id(&self, global_id: &str) -> Option<u32> {
+        self.handles.handle(global_id)
+    }
+
+    /// GlobalId of the element a handle stands for
+    pub fn global_id_for_handle(&self, handle: u32) -> Option<&str> {
+        self.handles.global_id(handle)
+    }
+
     /// Get combined bounding box of all visible models
     pub fn get_combined_bounds(&self) -> Option<BoundingBox> {
         let mut combined: Option<BoundingBox> = None;
 
         for model in self.models.values() {
-            if !model.visible {
+            if !model.is_drawn() {
                 continue;
             }
 
             if let Some(bounds) = &model.bounds {
                 combined = Some(match combined {
-                    None => bounds.clone(),
+                    None => *bounds,
                     Some(existing) => existing.union(bounds),
                 });
             }
@@ -240,14 +441,54 @@ impl ModelRegistry {
         combined
     }
 
+    /// Get the cached bounds of an element by GlobalId (searches all models)
+    pub fn element_bounds(&self, global_id: &str) -> Option<BoundingBox> {
+        self.models
+            .values()
+            .find_map(|m| m.element_bounds.get(global_id).copied())
+    }
+
+    /// GlobalIds of the elements of drawn models whose bounds overlap the box
+    /// `min`-`max` (model transforms applied), sorted
+    pub fn elements_in_box(&self, min: [f32; 3], max: [f32; 3]) -> Vec<String> {
+        let (min, max) = (glam::Vec3::from(min), glam::Vec3::from(max));
+        self.elements_where(|element_min, element_max| element_min.cmple(max).all() && element_max.cmpge(min).all())
+    }
+
+    /// GlobalIds of the elements of drawn models whose bounds come within
+    /// `radius` of `point` (model transforms applied), sorted
+    pub fn elements_near_point(&self, point: [f32; 3], radius: f32) -> Vec<String> {
+        let point = glam::Vec3::from(point);
+        self.elements_where(|min, max| point.clamp(min, max).distance_squared(point) <= radius * radius)
+    }
+
+    /// GlobalIds of the elements of drawn models whose transformed bounds
+    /// pass `test`, sorted
+    fn elements_where(&self, test: impl Fn(glam::Vec3, glam::Vec3) -> bool) -> Vec<String> {
+        let mut found: Vec<String> = self
+            .iter_visible()
+            .flat_map(|(_, model)| model.transformed_element_bounds())
+            .filter(|&(_, min, max)| test(min, max))
+            .map(|(global_id, _, _)| global_id.to_string())
+            .collect();
+        found.sort();
+        found.dedup();
+        found
+    }
+
     /// Iterate over all registered models
     pub fn iter(&self) -> impl Iterator<Item = (&ModelId, &RegisteredModel)> {
         self.models.iter()
     }
 
-    /// Iterate over all visible models
+    /// Iterate mutably over all registered models
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = (&ModelId, &mut RegisteredModel)> {
+        self.models.iter_mut()
+    }
+
+    /// Iterate over all visible models (with their geometry loaded)
     pub fn iter_visible(&self) -> impl Iterator<Item = (&ModelId, &RegisteredModel)> {
-        self.models.iter().filter(|(_, m)| m.visible)
+        self.models.iter().filter(|(_, m)| m.is_drawn())
     }
 
     /// Get all models (for iteration)
@@ -287,6 +528,7 @@ impl ModelRegistry {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::bim::{BoundsInfo, IfcFile};
 
     #[test]
     fn test_add_remove_model() {
@@ -303,6 +545,169 @@ mod tests {
         assert!(!registry.has_model(&id));
     }
 
+    #[test]
+    fn test_model_bounds() {
+        let ifc = IfcFile::parse(
+            "ISO-10303-21;
+HEADER;
+FILE_SCHEMA(('IFC2X3'));
+ENDSEC;
+DATA;
+#1=IFCWALL('2O2Fr$t4X7Zf8NOew3FLOH',$,'Wall A',$,$,$,$,$);
+#2=IFCWALL('2O2Fr$t4X7Zf8NOew3FLOI',$,'Wall B',$,$,$,$,$);
+ENDSEC;
+END-ISO-10303-21;",
+        )
+        .unwrap();
+        let model = BimModel::from_ifc_file(&ifc).unwrap();
+
+        // Two 2.5 x 3.0 x 0.2 walls, 3 units apart along X
+        let mut registry = ModelRegistry::new();
+        let id = registry.add_model(model, "Walls".to_string(), None);
+        let bounds = registry.get_model(&id).unwrap().bounds.unwrap();
+        assert_eq!(bounds.min, [-1.25, 0.0, -0.1]);
+        assert_eq!(bounds.max, [4.25, 3.0, 0.1]);
+
+        let info = BoundsInfo::from(bounds);
+        assert_eq!(info.center, [1.5, 1.5, 0.0]);
+        assert_eq!(info.size, [5.5, 3.0, 0.2]);
+
+        let combined = registry.get_combined_bounds().unwrap();
+        assert_eq!(combined.min, bounds.min);
+
+        // Walls are laid out in extraction order, which is not fixed
+        let mut walls: Vec<BoundingBox> = ["2O2Fr$t4X7Zf8NOew3FLOH", "2O2Fr$t4X7Zf8NOew3FLOI"]
+            .into_iter()
+            .map(|id| registry.element_bounds(id).unwrap())
+            .collect();
+        walls.sort_by(|a, b| a.min[0].total_cmp(&b.min[0]));
+        assert_eq!((walls[0].min, walls[0].max), ([-1.25, 0.0, -0.1], [1.25, 3.0, 0.1]));
+        assert_eq!((walls[1].min, walls[1].max), ([1.75, 0.0, -0.1], [4.25, 3.0, 0.1]));
+        assert!(registry.element_bounds("missing").is_none());
+    }
+
+    #[test]
+    fn test_box_around_one_element_finds_only_it() {
+        let ifc = IfcFile::parse(
+            "ISO-10303-21;
+HEADER;
+FILE_SCHEMA(('IFC2X3'));
+ENDSEC;
+DATA;
+#1=IFCWALL('wall-a',$,'Wall A',$,$,$,$,$);
+#2=IFCWALL('wall-b',$,'Wall B',$,$,$,$,$);
+ENDSEC;
+END-ISO-10303-21;",
+        )
+        .unwrap();
+        let model = BimModel::from_ifc_file(&ifc).unwrap();
+        let mut registry = ModelRegistry::new();
+        let id = registry.add_model(model, "Walls".to_string(), None);
+        let mut walls: Vec<(&str, BoundingBox)> =
+            ["wall-a", "wall-b"].map(|id| (id, registry.element_bounds(id).unwrap())).to_vec();
+        walls.sort_by(|a, b| a.1.min[0].total_cmp(&b.1.min[0]));
+        let (left, left_bounds) = walls[0];
+
+        // Around the left wall, and the gap next to it
+        let (min, max) = (left_bounds.min.map(|c| c - 0.1), left_bounds.max.map(|c| c + 0.1));
+        assert_eq!(registry.elements_in_box(min, max), [left]);
+        assert_eq!(registry.elements_in_box([-10.0; 3], [10.0; 3]), ["wall-a", "wall-b"]);
+        assert!(registry.elements_in_box([1.3, 0.0, -1.0], [1.7, 3.0, 1.0]).is_empty());
+
+        // Near the left wall's end, then reaching the other wall
+        let end = [left_bounds.max[0] + 0.2, 1.0, 0.0];
+        assert_eq!(registry.elements_near_point(end, 0.25), [left]);
+        assert_eq!(registry.elements_near_point(end, 0.4), ["wall-a", "wall-b"]);
+
+        // A model moved 100 along X finds its elements there
+        let mut moved = [0.0; 16];
+        for k in 0..4 {
+            moved[k * 5] = 1.0;
+        }
+        moved[12] = 100.0;
+        registry.set_model_transform(&id, moved).unwrap();
+        assert!(registry.elements_in_box(min, max).is_empty());
+        let shifted = |p: [f32; 3]| [p[0] + 100.0, p[1], p[2]];
+        assert_eq!(registry.elements_in_box(shifted(min), shifted(max)), [left]);
+        registry.set_model_visible(&id, false).unwrap();
+        assert!(registry.elements_near_point(end, 1000.0).is_empty());
+    }
+
+    #[test]
+    fn test_pick_mesh_hits_element_and_is_cached() {
+        let ifc = IfcFile::parse(
+            "ISO-10303-21;
+HEADER;
+FILE_SCHEMA(('IFC2X3'));
+ENDSEC;
+DATA;
+#1=IFCWALL('2O2Fr$t4X7Zf8NOew3FLOH',$,'Wall A',$,$,$,$,$);
+#2=IFCWALL('2O2Fr$t4X7Zf8NOew3FLOI',$,'Wall B',$,$,$,$,$);
+ENDSEC;
+END-ISO-10303-21;",
+        )
+        .unwrap();
+        let mut registered = RegisteredModel::new(BimModel::from_ifc_file(&ifc).unwrap(), "Walls".to_string(), None);
+
+        // Looking down -Z at the wall spanning x = 1.75..4.25
+        let assembled = (0.0, ExplodeMode::Vertical);
+        let pick_mesh = registered.pick_mesh(assembled);
+        let (t, triangle) = pick_mesh.intersect(glam::Vec3::new(3.0, 1.5, 10.0), glam::Vec3::NEG_Z).unwrap();
+        assert!((t - 9.9).abs() < 1e-4);
+        let element = pick_mesh.element_at(triangle).unwrap();
+        assert_eq!(element.bounds.min[0], 1.75);
+        assert!(pick_mesh.intersect(glam::Vec3::new(1.5, 1.5, 10.0), glam::Vec3::NEG_Z).is_none());
+
+        // Reused until the explode setting or geometry changes
+        assert!(Arc::ptr_eq(&pick_mesh, &registered.pick_mesh(assembled)));
+        assert!(!Arc::ptr_eq(&pick_mesh, &registered.pick_mesh((1.0, ExplodeMode::Radial))));
+        let pick_mesh = registered.pick_mesh(assembled);
+        registered.geometry_changed();
+        assert!(!Arc::ptr_eq(&pick_mesh, &registered.pick_mesh(assembled)));
+    }
+
+    #[test]
+    fn test_unload_geometry_keeps_model_listed() {
+        let ifc_file = IfcFile::parse(
+            "ISO-10303-21;
+HEADER;
+FILE_SCHEMA(('IFC4'));
+ENDSEC;
+DATA;
+#1=IFCCARTESIANPOINTLIST3D(((0.,0.,0.),(1.,0.,0.),(1.,1.,0.),(0.,1.,0.)));
+#2=IFCTRIANGULATEDFACESET(#1,$,.F.,((1,2,3),(3,4,1)),$);
+#3=IFCSHAPEREPRESENTATION($,'Body','Tessellation',(#2));
+#4=IFCPRODUCTDEFINITIONSHAPE($,$,(#3));
+#5=IFCSLAB('2O2Fr$t4X7Zf8NOew3FLOH',$,'Slab',$,$,$,#4,$,$);
+#6=IFCWALL('2O2Fr$t4X7Zf8NOew3FLOI',$,'Wall',$,$,$,$,$);
+ENDSEC;
+END-ISO-10303-21;",
+        )
+        .unwrap();
+        let mut registry = ModelRegistry::new();
+        let id = registry.add_model(BimModel::from_ifc_file(&ifc_file).unwrap(), "Slab".into(), None);
+        let model = registry.get_model_mut(&id).unwrap();
+        assert_eq!(model.model.body_meshes.len(), 1);
+        let loaded = model.geometry_bytes();
+
+        model.unload_geometry();
+        let unloaded = model.geometry_bytes();
+        assert!(unloaded < loaded);
+        assert!(model.model.body_meshes.is_empty());
+        // Metadata stays for the tree and search, but nothing is drawn
+        assert_eq!((model.model.slabs.len(), model.model.walls.len()), (1, 1));
+        assert_eq!(model.element_bounds.len(), 2);
+        assert_eq!(registry.list_models(), vec![id.clone()]);
+        assert!(registry.list_visible_models().is_empty());
+        assert_eq!(registry.iter_visible().count(), 0);
+
+        let model = registry.get_model_mut(&id).unwrap();
+        model.reload_geometry(&ifc_file);
+        assert!(model.geometry_bytes() > unloaded);
+        assert_eq!(model.model.body_meshes.len(), 1);
+        assert_eq!(registry.list_visible_models(), vec![id]);
+    }
+
     #[test]
     fn test_primary_model() {
         let mut registry = ModelRegistry::new();
@@ -0,0 +1,198 @@
+//! OBJ Import
+//!
+//! Reads Wavefront OBJ meshes (scans, reference geometry) into a model of
+//! generic elements: each `o` object or `g` group with faces becomes one
+//! element named after it. Only geometry is read (`v`, `vn`, `f`); texture
+//! coordinates, materials and lines are ignored. Polygons are triangulated by
+//! ear clipping, and groups without normals get them computed.
+//!
+//! Coordinates are taken as Y-up, as most tools write OBJ.
+
+use super::geometry::{Mesh, DEFAULT_FEATURE_ANGLE_DEG};
+use super::model::BimModel;
+use super::tessellation::triangulate_polygon;
+use glam::Vec3;
+use std::collections::HashMap;
+use std::path::Path;
+
+/// Import an `.obj` file
+pub fn import_obj(path: &Path) -> Result<BimModel, String> {
+    let text = std::fs::read_to_string(path).map_err(|e| format!("Failed to read OBJ: {}", e))?;
+    let name = path.file_stem().and_then(|s| s.to_str()).unwrap_or("obj");
+    parse_obj(&text, name)
+}
+
+/// Build a model from OBJ text; elements get the GlobalId
+/// `<name>:<element index>`
+pub fn parse_obj(text: &str, name: &str) -> Result<BimModel, String> {
+    let mut positions: Vec<Vec3> = Vec::new();
+    let mut normals: Vec<Vec3> = Vec::new();
+    let mut groups: Vec<ObjGroup> = vec![ObjGroup::default()];
+
+    for (line_number, line) in text.lines().enumerate() {
+        let error = |message: &str| format!("OBJ line {}: {}", line_number + 1, message);
+        let mut tokens = line.split_whitespace();
+        match tokens.next() {
+            Some("v") => positions.push(parse_vec3(tokens).ok_or_else(|| error("invalid vertex"))?),
+            Some("vn") => normals.push(parse_vec3(tokens).ok_or_else(|| error("invalid normal"))?),
+            Some("o" | "g") => {
+                // An unnamed group keeps the name of the one before
+                let group_name = tokens.collect::<Vec<_>>().join(" ");
+                let name = if group_name.is_empty() { groups.last().unwrap().name.clone() } else { Some(group_name) };
+                groups.push(ObjGroup { name, ..Default::default() });
+            }
+            Some("f") => {
+                let corners = tokens
+                    .map(|token| parse_corner(token, positions.len(), normals.len()))
+                    .collect::<Option<Vec<_>>>()
+                    .ok_or_else(|| error("invalid face"))?;
+                if corners.len() < 3 {
+                    return Err(error("face with fewer than 3 vertices"));
+                }
+                groups.last_mut().unwrap().add_face(&corners, &positions, &normals);
+            }
+            _ => {}
+        }
+    }
+
+    let mut model = BimModel::new();
+    for (index, group) in groups.into_iter().filter(|g| !g.mesh.indices.is_empty()).enumerate() {
+        let mut mesh = group.mesh;
+        if group.missing_normals {
+            mesh.compute_normals_with_angle(DEFAULT_FEATURE_ANGLE_DEG);
+        }
+        model.add_placed_body(format!("{}:{}", name, index), group.name, mesh);
+    }
+    Ok(model)
+}
+
+/// An object or group being read
+#[derive(Default)]
+struct ObjGroup {
+    name: Option<String>,
+    mesh: Mesh,
+    /// Mesh vertex of each (position, normal) pair used so far
+    vertices: HashMap<(usize, Option<usize>), u32>,
+    /// Some face corner had no normal
+    missing_normals: bool,
+}
+
+impl ObjGroup {
+    fn add_face(&mut self, corners: &[(usize, Option<usize>)], positions: &[Vec3], normals: &[Vec3]) {
+        let indices: Vec<u32> = corners
+            .iter()
+            .map(|&(position, normal)| {
+                self.missing_normals |= normal.is_none();
+                *self.vertices.entry((position, normal)).or_insert_with(|| {
+                    let p = positions[position];
+                    let n = normal.map_or(Vec3::ZERO, |n| normals[n].normalize_or_zero());
+                    self.mesh.add_vertex(p.x, p.y, p.z);
+                    self.mesh.add_normal(n.x, n.y, n.z);
+                    self.mesh.add_color(1.0, 1.0, 1.0, 1.0);
+                    (self.mesh.vertex_count() - 1) as u32
+                })
+            })
+            .collect();
+
+        if indices.len() == 3 {
+            self.mesh.add_triangle(indices[0], indices[1], indices[2]);
+            return;
+        }
+        let polygon: Vec<Vec3> = corners.iter().map(|&(position, _)| positions[position]).collect();
+        for [a, b, c] in triangulate_polygon(&polygon) {
+            self.mesh.add_triangle(indices[a], indices[b], indices[c]);
+        }
+    }
+}
+
+fn parse_vec3<'a>(mut tokens: impl Iterator<Item = &'a str>) -> Option<Vec3> {
+    let mut coordinate = || tokens.next()?.parse::<f32>().ok();
+    Some(Vec3::new(coordinate()?, coordinate()?, coordinate()?))
+}
+
+/// Position and normal of a face corner (`v`, `v/vt`, `v//vn` or
+/// `v/vt/vn`; negative indices count back from the last one read)
+fn parse_corner(token: &str, position_count: usize, normal_count: usize) -> Option<(usize, Option<usize>)> {
+    let resolve = |index: &str, count: usize| -> Option<usize> {
+        let index: i64 = index.parse().ok()?;
+        let resolved = if index < 0 { count as i64 + index } else { index - 1 };
+        (0..count as i64).contains(&resolved).then_some(resolved as usize)
+    };
+    let mut parts = token.split('/');
+    let position = resolve(parts.next()?, position_count)?;
+    let normal = match parts.nth(1) {
+        Some(normal) if !normal.is_empty() => Some(resolve(normal, normal_count)?),
+        _ => None,
+    };
+    Some((position, normal))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::renderer::test_renderer;
+
+    /// A unit cube of quads without normals, then a triangle with normals
+    /// and relative indices
+    const CUBE_OBJ: &str = "# cube
+mtllib cube.mtl
+o Cube
+v 0 0 0
+v 1 0 0
+v 1 1 0
+v 0 1 0
+v 0 0 1
+v 1 0 1
+v 1 1 1
+v 0 1 1
+vt 0 0
+usemtl Grey
+f 1/1 4/1 3/1 2/1
+f 5 6 7 8
+f 1 2 6 5
+f 2 3 7 6
+f 3 4 8 7
+f 4 1 5 8
+g Marker
+v 3 0 0
+v 4 0 0
+v 3 1 0
+vn 0 0 1
+f -3//1 -2//1 -1//1
+";
+
+    #[test]
+    fn test_obj_cube_becomes_renderable_model() {
+        let model = parse_obj(CUBE_OBJ, "cube").unwrap();
+        assert_eq!(model.element_count, 2);
+        let names: Vec<Option<&str>> = model.proxies.iter().map(|p| p.product.name.as_deref()).collect();
+        assert_eq!(names, [Some("Cube"), Some("Marker")]);
+
+        // Quads split in two; shared corners split by the computed normals
+        let cube = &model.body_meshes["cube:0"];
+        assert_eq!(cube.triangle_count(), 12);
+        assert_eq!(cube.vertex_count(), 24);
+        assert!(cube.validate().is_clean());
+        assert!((cube.volume() - 1.0).abs() < 1e-5, "volume {}", cube.volume());
+        let marker = &model.body_meshes["cube:1"];
+        assert_eq!((marker.vertex_count(), marker.triangle_count()), (3, 1));
+        assert_eq!(&marker.normals[..3], &[0.0, 0.0, 1.0]);
+
+        // Drawn where the file puts it
+        let mesh = model.generate_meshes();
+        assert_eq!(mesh.elements.len(), 2);
+        assert_eq!(mesh.indices.len() / 3, 13);
+        let bounds = mesh.bounds.unwrap();
+        assert_eq!((bounds.min, bounds.max), ([0.0; 3], [4.0, 1.0, 1.0]));
+        assert!(parse_obj("v 0 0 0\nf 1 2 3\n", "bad").is_err());
+
+        let Some(mut renderer) = test_renderer(64, 64) else {
+            eprintln!("No GPU adapter available, skipping");
+            return;
+        };
+        renderer.load_mesh(&mesh.vertices, &mesh.normals, &mesh.colors, &mesh.indices).unwrap();
+        renderer.update_camera([2.0, 3.0, 6.0], [2.0, 0.5, 0.5]);
+        let pixels = renderer.render_frame().unwrap();
+        assert!(pixels.chunks_exact(4).any(|p| p[..3] != pixels[..3]), "model not drawn");
+    }
+}
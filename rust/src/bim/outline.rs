@@ -0,0 +1,133 @@
+//! Model Outline
+//!
+//! A light view of a model for the first transfer to the UI: the spatial
+//! tree (project, site, building, storeys) with the GlobalId, type and name
+//! of every element and nothing else. Geometry, properties and materials are
+//! fetched per element when needed, so the outline of a 100k-element model
+//! stays a few megabytes.
+
+use super::entities::EntityId;
+use super::model::BimModel;
+use serde::{Deserialize, Serialize};
+
+/// An element in the outline
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ElementOutline {
+    pub global_id: String,
+    pub element_type: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+}
+
+/// A storey and the elements on it
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StoreyOutline {
+    pub id: EntityId,
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub elevation: Option<f64>,
+    pub elements: Vec<ElementOutline>,
+}
+
+/// Spatial tree of a model with its elements' ids, types and names
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ModelOutline {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub project: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub site: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub building: Option<String>,
+    /// Storeys in file order
+    pub storeys: Vec<StoreyOutline>,
+    /// Elements on no storey
+    pub unassigned: Vec<ElementOutline>,
+}
+
+impl ModelOutline {
+    /// Outline of a model; elements keep the order of `BimModel::products`
+    /// within each storey
+    pub fn of(model: &BimModel) -> Self {
+        let mut storeys: Vec<StoreyOutline> = model
+            .storeys
+            .iter()
+            .map(|s| StoreyOutline {
+                id: s.id,
+                name: s.name.clone(),
+                elevation: s.elevation,
+                elements: Vec::new(),
+            })
+            .collect();
+        let mut unassigned = Vec::new();
+        for (element_type, product) in model.products() {
+            let element = ElementOutline {
+                global_id: product.global_id.clone(),
+                element_type: element_type.to_string(),
+                name: product.name.clone(),
+            };
+            let storey = model
+                .storey_assignments
+                .get(&product.global_id)
+                .and_then(|a| storeys.iter_mut().find(|s| s.id == a.storey_id));
+            match storey {
+                Some(storey) => storey.elements.push(element),
+                None => unassigned.push(element),
+            }
+        }
+
+        Self {
+            project: model.project.as_ref().map(|p| p.name.clone()),
+            site: model.site.as_ref().map(|s| s.name.clone()),
+            building: model.building.as_ref().map(|b| b.name.clone()),
+            storeys,
+            unassigned,
+        }
+    }
+
+    /// Number of elements in the outline
+    pub fn element_count(&self) -> usize {
+        self.storeys.iter().map(|s| s.elements.len()).sum::<usize>() + self.unassigned.len()
+    }
+
+    /// Compact JSON of the outline
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).expect("outline serializes")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bim::IfcFile;
+    use std::collections::HashSet;
+
+    #[test]
+    fn test_outline_lists_every_element_without_geometry() {
+        let content = include_str!("../../../test/sample_architectural.ifc");
+        let model = BimModel::from_ifc_file(&IfcFile::parse(content).unwrap()).unwrap();
+        let outline = ModelOutline::of(&model);
+
+        let ids: HashSet<&str> = outline
+            .storeys
+            .iter()
+            .flat_map(|s| &s.elements)
+            .chain(&outline.unassigned)
+            .map(|e| e.global_id.as_str())
+            .collect();
+        let expected: HashSet<&str> = model.products().into_iter().map(|(_, p)| p.global_id.as_str()).collect();
+        assert!(!expected.is_empty());
+        assert_eq!(ids, expected);
+        assert_eq!(outline.element_count(), model.element_count);
+        assert_eq!(outline.storeys.len(), model.storeys.len());
+
+        // Ids, types and names only
+        let json = outline.to_json();
+        for field in ["vertices", "indices", "normals", "properties", "location"] {
+            assert!(!json.contains(field), "outline carries {}", field);
+        }
+        let full = serde_json::to_string(&model).unwrap();
+        assert!(json.len() * 2 < full.len(), "outline {} vs model {} bytes", json.len(), full.len());
+        let parsed: ModelOutline = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed, outline);
+    }
+}
@@ -0,0 +1,120 @@
+//! Property Export
+//!
+//! Writes elements and their properties as CSV for spreadsheets: one row per
+//! element with its GlobalId, type, name and storey, then one column per
+//! property name found on any element (`<set name>.<property name>`, sorted),
+//! left blank where an element lacks it. Fields are quoted as in RFC 4180.
+
+use super::model::BimModel;
+use std::collections::BTreeSet;
+
+/// Columns every row starts with
+const ELEMENT_COLUMNS: [&str; 4] = ["GlobalId", "Type", "Name", "Storey"];
+
+/// Elements of `models` and their properties as CSV
+///
+/// With a `pset_filter`, only properties of the set of that name are
+/// exported. Returns the text and the number of element rows.
+pub fn properties_csv(models: &[&BimModel], pset_filter: Option<&str>) -> (String, usize) {
+    let prefix = pset_filter.map(|pset| format!("{}.", pset));
+    let included = |key: &str| prefix.as_ref().is_none_or(|p| key.starts_with(p.as_str()));
+    let columns: BTreeSet<&str> = models
+        .iter()
+        .flat_map(|model| model.products())
+        .flat_map(|(_, product)| product.properties.keys())
+        .map(String::as_str)
+        .filter(|key| included(key))
+        .collect();
+
+    let mut csv = String::new();
+    let header = ELEMENT_COLUMNS.iter().copied().chain(columns.iter().copied());
+    push_row(&mut csv, header);
+    let mut rows = 0;
+    for model in models {
+        for (element_type, product) in model.products() {
+            let storey = model.element_storey(&product.global_id).map(|(storey, _)| storey.name.as_str());
+            let element = [
+                product.global_id.as_str(),
+                element_type,
+                product.name.as_deref().unwrap_or(""),
+                storey.unwrap_or(""),
+            ];
+            let values = columns
+                .iter()
+                .map(|&key| product.properties.get(key).map_or("", String::as_str));
+            push_row(&mut csv, element.into_iter().chain(values));
+            rows += 1;
+        }
+    }
+    (csv, rows)
+}
+
+fn push_row<'a>(csv: &mut String, fields: impl Iterator<Item = &'a str>) {
+    for (i, field) in fields.enumerate() {
+        if i > 0 {
+            csv.push(',');
+        }
+        if field.contains([',', '"', '\n', '\r']) {
+            csv.push('"');
+            csv.push_str(&field.replace('"', "\"\""));
+            csv.push('"');
+        } else {
+            csv.push_str(field);
+        }
+    }
+    csv.push_str("\r\n");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bim::IfcFile;
+
+    const WALLS_WITH_PSETS: &str = r#"ISO-10303-21;
+HEADER;
+FILE_DESCRIPTION(('ViewDefinition'),'2;1');
+FILE_NAME('psets.ifc','2024-01-01',(''),(''),'','','');
+FILE_SCHEMA(('IFC4'));
+ENDSEC;
+DATA;
+#1=IFCBUILDINGSTOREY('storey-guid',$,'Level 1',$,$,$,$,$,.ELEMENT.,0.);
+#2=IFCWALL('wall-a',$,'Wall A',$,$,$,$,$,$);
+#3=IFCWALL('wall-b',$,'Wall, "B"',$,$,$,$,$,$);
+#10=IFCPROPERTYSINGLEVALUE('IsExternal',$,IFCBOOLEAN(.T.),$);
+#11=IFCPROPERTYSINGLEVALUE('Reference',$,IFCIDENTIFIER('W-01, "ext"'),$);
+#12=IFCPROPERTYSET('pset-a',$,'Pset_WallCommon',$,(#10,#11));
+#13=IFCRELDEFINESBYPROPERTIES('rel-a',$,$,$,(#2),#12);
+#20=IFCPROPERTYSINGLEVALUE('FireRating',$,IFCLABEL('EI 60'),$);
+#21=IFCPROPERTYSET('pset-b',$,'Pset_WallCommon',$,(#20));
+#22=IFCQUANTITYLENGTH('Length',$,$,4.5,$);
+#23=IFCELEMENTQUANTITY('qto-b',$,'Qto_WallBaseQuantities',$,$,(#22));
+#24=IFCRELDEFINESBYPROPERTIES('rel-b',$,$,$,(#3),#21);
+#25=IFCRELDEFINESBYPROPERTIES('rel-c',$,$,$,(#3),#23);
+#30=IFCRELCONTAINEDINSPATIALSTRUCTURE('rel-d',$,$,$,(#2,#3),#1);
+ENDSEC;
+END-ISO-10303-21;
+"#;
+
+    #[test]
+    fn test_csv_header_is_union_of_properties_and_rows_align() {
+        let model = BimModel::from_ifc_file(&IfcFile::parse(WALLS_WITH_PSETS).unwrap()).unwrap();
+        let (csv, rows) = properties_csv(&[&model], None);
+        assert_eq!(rows, 2);
+        let lines: Vec<&str> = csv.split_terminator("\r\n").collect();
+        assert_eq!(
+            lines,
+            [
+                "GlobalId,Type,Name,Storey,Pset_WallCommon.FireRating,Pset_WallCommon.IsExternal,\
+                 Pset_WallCommon.Reference,Qto_WallBaseQuantities.Length",
+                r#"wall-a,Wall,Wall A,Level 1,,TRUE,"W-01, ""ext""","#,
+                r#"wall-b,Wall,"Wall, ""B""",Level 1,EI 60,,,4.5"#,
+            ]
+        );
+
+        let (csv, _) = properties_csv(&[&model], Some("Qto_WallBaseQuantities"));
+        let lines: Vec<&str> = csv.split_terminator("\r\n").collect();
+        assert_eq!(lines[0], "GlobalId,Type,Name,Storey,Qto_WallBaseQuantities.Length");
+        assert!(lines[1].ends_with("Level 1,"));
+        assert!(lines[2].ends_with("Level 1,4.5"));
+    }
+}
@@ -0,0 +1,149 @@
+//! Geometry Salvage
+//!
+//! Last-resort reading of broken files the structured extraction gets
+//! nothing from: valid STEP with geometry, but missing spatial structure,
+//! unknown product types or a schema we don't recognize. Every shape
+//! representation is tessellated regardless of what (if anything) owns it,
+//! and becomes one generic element of a flat model. Each product definition
+//! shape is one element; representations outside any are elements of their
+//! own.
+//!
+//! An owning product gives its GlobalId, name and placement; other elements
+//! get the GlobalId `<name>:<representation id>`.
+
+use super::coordinates::{placement_matrix, UpAxis};
+use super::entities::{EntityId, IfcEntity};
+use super::geometry::{merge_meshes, Mesh};
+use super::ifc_parser::IfcFile;
+use super::model::BimModel;
+use super::tessellation::{bake_placement, convert_to_y_up, tessellate_item};
+use std::collections::{HashMap, HashSet};
+
+/// Build a flat model of every tessellatable shape representation in a
+/// file, with `up_axis` as its source axes
+///
+/// Fails when no geometry could be tessellated at all.
+pub fn salvage_geometry(ifc_file: &IfcFile, name: &str, up_axis: UpAxis) -> Result<BimModel, String> {
+    // Entities pointing at a product definition shape (IfcProduct:
+    // ObjectPlacement at 5, Representation at 6), lowest id first
+    let mut owners: HashMap<EntityId, &IfcEntity> = HashMap::new();
+    let mut entities: Vec<&IfcEntity> = ifc_file.entities.values().collect();
+    entities.sort_unstable_by_key(|e| e.id);
+    for entity in &entities {
+        if let Some(shape) = entity.get_entity_ref(6).and_then(|id| ifc_file.get_entity(id)) {
+            if shape.entity_type == "IFCPRODUCTDEFINITIONSHAPE" {
+                owners.entry(shape.id).or_insert(entity);
+            }
+        }
+    }
+
+    // IFCPRODUCTDEFINITIONSHAPE(Name, Description, Representations)
+    let mut groups: Vec<(EntityId, Option<&IfcEntity>, Vec<EntityId>)> = ifc_file
+        .get_entities_by_type("IFCPRODUCTDEFINITIONSHAPE")
+        .into_iter()
+        .map(|shape| (shape.id, owners.get(&shape.id).copied(), shape.get_ref_list(2)))
+        .collect();
+    let grouped: HashSet<EntityId> = groups.iter().flat_map(|(_, _, reps)| reps.iter().copied()).collect();
+    groups.extend(
+        ifc_file
+            .get_entities_by_type("IFCSHAPEREPRESENTATION")
+            .into_iter()
+            .filter(|rep| !grouped.contains(&rep.id))
+            .map(|rep| (rep.id, None, vec![rep.id])),
+    );
+
+    let mut model = BimModel::new();
+    let mut used_ids: HashSet<String> = HashSet::new();
+    for (id, owner, representations) in groups {
+        // IFCSHAPEREPRESENTATION(ContextOfItems, Identifier, Type, Items)
+        let meshes: Vec<Mesh> = representations
+            .into_iter()
+            .filter_map(|rep| ifc_file.get_entity(rep))
+            .flat_map(|rep| rep.get_ref_list(3))
+            .filter_map(|item| ifc_file.get_entity(item))
+            .filter_map(|item| tessellate_item(ifc_file, item))
+            .collect();
+        if meshes.is_empty() {
+            continue;
+        }
+        let mut mesh = merge_meshes(meshes);
+        if let Some(placement) = owner.and_then(|o| placement_matrix(ifc_file, o.get_entity_ref(5))) {
+            bake_placement(&mut mesh, &placement, [0.0; 3]);
+        }
+        convert_to_y_up(&mut mesh, up_axis);
+
+        let global_id = owner
+            .and_then(|o| o.get_string(0))
+            .filter(|g| !g.is_empty() && !used_ids.contains(g))
+            .unwrap_or_else(|| format!("{}:{}", name, id));
+        used_ids.insert(global_id.clone());
+        model.add_placed_body(global_id, owner.and_then(|o| o.get_string(2)), mesh);
+    }
+
+    if model.element_count == 0 {
+        return Err("No geometry found to salvage".to_string());
+    }
+    model.parse_warnings = ifc_file.parse_warnings.clone();
+    Ok(model)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A faceted box owned by an unknown product type with a placement, and
+    /// a loose triangle mesh, with no project or spatial structure
+    const BROKEN_IFC: &str = "ISO-10303-21;
+HEADER;
+FILE_DESCRIPTION((''),'2;1');
+FILE_NAME('broken.ifc','2024-01-01',(''),(''),'','','');
+FILE_SCHEMA(('SOMETHING_ELSE'));
+ENDSEC;
+DATA;
+#1=IFCCARTESIANPOINT((0.,0.,0.));
+#2=IFCCARTESIANPOINT((1.,0.,0.));
+#3=IFCCARTESIANPOINT((1.,1.,0.));
+#4=IFCCARTESIANPOINT((0.,1.,0.));
+#5=IFCPOLYLOOP((#1,#2,#3,#4));
+#6=IFCFACEOUTERBOUND(#5,.T.);
+#7=IFCFACE((#6));
+#8=IFCCLOSEDSHELL((#7));
+#9=IFCFACETEDBREP(#8);
+#10=IFCSHAPEREPRESENTATION($,'Body','Brep',(#9));
+#11=IFCPRODUCTDEFINITIONSHAPE($,$,(#10));
+#12=IFCCARTESIANPOINT((10.,0.,0.));
+#13=IFCAXIS2PLACEMENT3D(#12,$,$);
+#14=IFCLOCALPLACEMENT($,#13);
+#15=IFCCUSTOMTHING('thing-guid',$,'Thing',$,$,#14,#11,$);
+#20=IFCCARTESIANPOINTLIST3D(((0.,0.,5.),(1.,0.,5.),(0.,1.,5.)));
+#21=IFCTRIANGULATEDFACESET(#20,$,$,((1,2,3)),$);
+#22=IFCSHAPEREPRESENTATION($,'Body','Tessellation',(#21));
+#30=IFCSHAPEREPRESENTATION($,'Axis','Curve2D',(#1));
+ENDSEC;
+END-ISO-10303-21;
+";
+
+    #[test]
+    fn test_geometry_salvaged_without_spatial_structure() {
+        let ifc_file = IfcFile::parse(BROKEN_IFC).unwrap();
+        let structured = BimModel::from_ifc_file(&ifc_file).unwrap();
+        assert!(!structured.has_geometry());
+
+        let model = salvage_geometry(&ifc_file, "broken", UpAxis::Z).unwrap();
+        assert_eq!(model.element_count, 2);
+        let ids: Vec<&str> = model.proxies.iter().map(|p| p.product.global_id.as_str()).collect();
+        assert_eq!(ids, ["thing-guid", "broken:22"]);
+        assert_eq!(model.proxies[0].product.name.as_deref(), Some("Thing"));
+
+        // Drawn where the file puts it: the face moved by its placement,
+        // the triangle 5 up, both turned Y-up
+        let mesh = model.generate_meshes();
+        assert_eq!(mesh.elements.len(), 2);
+        assert_eq!(mesh.indices.len() / 3, 3);
+        let bounds = mesh.bounds.unwrap();
+        assert_eq!((bounds.min, bounds.max), ([0.0, 0.0, -1.0], [11.0, 5.0, 0.0]));
+
+        let empty = IfcFile::parse("ISO-10303-21;\nHEADER;\nENDSEC;\nDATA;\nENDSEC;\nEND-ISO-10303-21;\n").unwrap();
+        assert!(salvage_geometry(&empty, "empty", UpAxis::Z).is_err());
+    }
+}
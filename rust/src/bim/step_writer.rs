@@ -0,0 +1,319 @@
+//! STEP Writer
+//!
+//! Writes an `IfcFile` back out as ISO 10303-21 (`IfcFile::to_step_string`),
+//! and cuts a subset of a file down to chosen elements with everything they
+//! depend on, for sending part of a model to clients that need IFC.
+//!
+//! Values are written as parsed: typed values (`IFCLABEL('x')`) lose their
+//! type name, so a written file reads back the same here but is not always
+//! schema-valid for other tools.
+
+use super::entities::{EntityId, IfcEntity, IfcValue};
+use super::ifc_parser::{IfcFile, IfcHeader};
+use std::collections::{HashMap, HashSet};
+use std::fmt::Write;
+
+impl IfcFile {
+    /// The file as ISO 10303-21 text, entities in id order; parsing it
+    /// again gives the same header and entities
+    pub fn to_step_string(&self) -> String {
+        let mut out = String::from("ISO-10303-21;\nHEADER;\n");
+        write_header(&mut out, &self.header);
+        out.push_str("ENDSEC;\nDATA;\n");
+
+        let mut entities: Vec<&IfcEntity> = self.entities.values().collect();
+        entities.sort_unstable_by_key(|e| e.id);
+        for entity in entities {
+            let attributes: Vec<String> = entity.attributes.iter().map(IfcValue::to_step).collect();
+            let _ = writeln!(out, "#{}={}({});", entity.id, entity.entity_type, attributes.join(","));
+        }
+
+        out.push_str("ENDSEC;\nEND-ISO-10303-21;\n");
+        out
+    }
+}
+
+/// FILE_DESCRIPTION, FILE_NAME and FILE_SCHEMA
+fn write_header(out: &mut String, header: &IfcHeader) {
+    let text = |s: &str| IfcValue::String(s.to_string()).to_step();
+    // Lists of strings may not be empty
+    let texts = |list: &[String]| match list {
+        [] => "('')".to_string(),
+        _ => format!("({})", list.iter().map(|s| text(s)).collect::<Vec<_>>().join(",")),
+    };
+    let _ = writeln!(out, "FILE_DESCRIPTION({},'2;1');", texts(&header.file_description));
+    let _ = writeln!(
+        out,
+        "FILE_NAME({},{},{},{},{},{},{});",
+        text(&header.file_name),
+        text(&header.time_stamp),
+        texts(&header.author),
+        texts(&header.organization),
+        text(&header.preprocessor_version),
+        text(&header.originating_system),
+        text(&header.authorization),
+    );
+    let schema = match header.schema.as_slice() {
+        [] => vec!["IFC4".to_string()],
+        schema => schema.to_vec(),
+    };
+    let _ = writeln!(out, "FILE_SCHEMA({});", texts(&schema));
+}
+
+/// Cut a file down to the elements with the given GlobalIds and what they
+/// depend on, keeping the header and entity ids
+///
+/// Kept are everything the elements reference (placements, representations
+/// and their contexts, owner history), and the relationships that attach
+/// things to them: spatial containment and decomposition up to the project,
+/// property sets, types, materials, styles and layers. Relationships keep
+/// only the related objects that are in the subset, so other elements are
+/// not pulled in through them. Fails if a GlobalId is not in the file.
+pub fn extract_subset(ifc_file: &IfcFile, global_ids: &[String]) -> Result<IfcFile, String> {
+    let mut by_global_id: HashMap<&str, EntityId> = HashMap::new();
+    for entity in ifc_file.entities.values() {
+        if let Some(IfcValue::String(global_id)) = entity.attributes.first() {
+            by_global_id
+                .entry(global_id.as_str())
+                .and_modify(|id| *id = (*id).min(entity.id))
+                .or_insert(entity.id);
+        }
+    }
+
+    let mut retained = HashSet::new();
+    let mut pending = Vec::new();
+    for global_id in global_ids {
+        let id = by_global_id
+            .get(global_id.as_str())
+            .ok_or_else(|| format!("Element not found: {}", global_id))?;
+        pending.push(*id);
+    }
+    close_references(ifc_file, &mut retained, pending);
+
+    // Relationships pointing at the subset, until no more attach
+    let mut relations: Vec<&IfcEntity> = ifc_file.entities.values().filter(|e| is_attaching(e)).collect();
+    relations.sort_unstable_by_key(|e| e.id);
+    let mut attached = HashSet::new();
+    loop {
+        let mut pending = Vec::new();
+        for relation in &relations {
+            if attached.contains(&relation.id) || !attaching_refs(relation).iter().any(|id| retained.contains(id)) {
+                continue;
+            }
+            attached.insert(relation.id);
+            retained.insert(relation.id);
+            // The other side of the relationship (the storey, property set)
+            for attribute in &relation.attributes {
+                match attribute {
+                    IfcValue::List(_) if relation.entity_type != "IFCSTYLEDITEM" => {}
+                    value => collect_refs(value, &mut pending),
+                }
+            }
+        }
+        if pending.is_empty() {
+            break;
+        }
+        close_references(ifc_file, &mut retained, pending);
+    }
+
+    let entities = retained
+        .iter()
+        .filter_map(|&id| ifc_file.get_entity(id))
+        .map(|entity| {
+            let mut entity = entity.clone();
+            if attached.contains(&entity.id) {
+                for attribute in &mut entity.attributes {
+                    if let IfcValue::List(items) = attribute {
+                        items.retain(|item| !matches!(item, IfcValue::EntityRef(id) if !retained.contains(id)));
+                    }
+                }
+            }
+            (entity.id, entity)
+        })
+        .collect();
+    Ok(IfcFile::from_entities(ifc_file.header.clone(), entities))
+}
+
+/// Entities that attach to others by pointing at them: relationships and
+/// layer assignments (by their lists of related objects) and styled items
+/// (by the item they style)
+fn is_attaching(entity: &IfcEntity) -> bool {
+    entity.entity_type.starts_with("IFCREL")
+        || entity.entity_type == "IFCPRESENTATIONLAYERASSIGNMENT"
+        || entity.entity_type == "IFCSTYLEDITEM"
+}
+
+/// The references by which an attaching entity attaches
+fn attaching_refs(entity: &IfcEntity) -> Vec<EntityId> {
+    if entity.entity_type == "IFCSTYLEDITEM" {
+        // IFCSTYLEDITEM(Item, Styles, Name)
+        return entity.get_entity_ref(0).into_iter().collect();
+    }
+    let mut refs = Vec::new();
+    for attribute in &entity.attributes {
+        if let IfcValue::List(items) = attribute {
+            refs.extend(items.iter().filter_map(|item| match item {
+                IfcValue::EntityRef(id) => Some(*id),
+                _ => None,
+            }));
+        }
+    }
+    refs
+}
+
+/// Add entities and everything they reference, transitively
+fn close_references(ifc_file: &IfcFile, retained: &mut HashSet<EntityId>, mut pending: Vec<EntityId>) {
+    while let Some(id) = pending.pop() {
+        if !retained.insert(id) {
+            continue;
+        }
+        if let Some(entity) = ifc_file.get_entity(id) {
+            for attribute in &entity.attributes {
+                collect_refs(attribute, &mut pending);
+            }
+        }
+    }
+}
+
+/// Every entity reference in a value, nested lists included
+fn collect_refs(value: &IfcValue, refs: &mut Vec<EntityId>) {
+    match value {
+        IfcValue::EntityRef(id) => refs.push(*id),
+        IfcValue::List(items) => items.iter().for_each(|item| collect_refs(item, refs)),
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Project, site, building and storey with two walls and a slab on it;
+    /// one wall with a property set, both walls with placements under the
+    /// storey's
+    const BUILDING: &str = "ISO-10303-21;
+HEADER;
+FILE_DESCRIPTION(('ViewDefinition [CoordinationView]'),'2;1');
+FILE_NAME('building.ifc','2024-01-01T00:00:00',('Jane O''Neil'),('Studio'),'pre','Modeler','');
+FILE_SCHEMA(('IFC4'));
+ENDSEC;
+DATA;
+#1=IFCPROJECT('project-guid',$,'Project',$,$,$,$,$,$);
+#2=IFCSITE('site-guid',$,'Site',$,$,$,$,$,.ELEMENT.,$,$,$,$,$);
+#3=IFCBUILDING('building-guid',$,'Building',$,$,$,$,$,.ELEMENT.,$,$,$);
+#4=IFCBUILDINGSTOREY('storey-guid',$,'Ground',$,$,#12,$,$,.ELEMENT.,0.);
+#5=IFCRELAGGREGATES('agg-1',$,$,$,#1,(#2));
+#6=IFCRELAGGREGATES('agg-2',$,$,$,#2,(#3));
+#7=IFCRELAGGREGATES('agg-3',$,$,$,#3,(#4));
+#10=IFCCARTESIANPOINT((0.,0.,0.));
+#11=IFCAXIS2PLACEMENT3D(#10,$,$);
+#12=IFCLOCALPLACEMENT($,#11);
+#13=IFCCARTESIANPOINT((5.,0.,0.));
+#14=IFCAXIS2PLACEMENT3D(#13,$,$);
+#15=IFCLOCALPLACEMENT(#12,#14);
+#16=IFCCARTESIANPOINT((0.,5.,0.));
+#17=IFCAXIS2PLACEMENT3D(#16,$,$);
+#18=IFCLOCALPLACEMENT(#12,#17);
+#20=IFCWALL('wall-a',$,'Wall A',$,$,#15,$,$,$);
+#21=IFCWALL('wall-b',$,'Wall B',$,$,#18,$,$,$);
+#22=IFCSLAB('slab-guid',$,'Slab',$,$,#12,$,$,.FLOOR.);
+#30=IFCRELCONTAINEDINSPATIALSTRUCTURE('contained',$,$,$,(#20,#21,#22),#4);
+#40=IFCPROPERTYSINGLEVALUE('FireRating',$,IFCLABEL('REI60'),$);
+#41=IFCPROPERTYSET('pset-guid',$,'Pset_WallCommon',$,(#40));
+#42=IFCRELDEFINESBYPROPERTIES('props',$,$,$,(#20),#41);
+#43=IFCPROPERTYSINGLEVALUE('FireRating',$,IFCLABEL('REI30'),$);
+#44=IFCPROPERTYSET('pset-b-guid',$,'Pset_WallCommon',$,(#43));
+#45=IFCRELDEFINESBYPROPERTIES('props-b',$,$,$,(#21),#44);
+ENDSEC;
+END-ISO-10303-21;
+";
+
+    #[test]
+    fn test_subset_round_trips_with_its_dependencies() {
+        let file = IfcFile::parse(BUILDING).unwrap();
+        let requested = ["wall-a".to_string(), "slab-guid".to_string()];
+        let subset = extract_subset(&file, &requested).unwrap();
+        let text = subset.to_step_string();
+        let reparsed = IfcFile::parse(&text).unwrap();
+        assert!(reparsed.parse_warnings.is_empty());
+        assert_eq!(reparsed.header.file_name, "building.ifc");
+        assert_eq!(reparsed.header.author, ["Jane O'Neil"]);
+        assert_eq!(reparsed.header.schema, ["IFC4"]);
+
+        // The elements, their placements and properties, and the spatial
+        // structure up to the project; nothing of the other wall
+        let mut ids: Vec<EntityId> = reparsed.entities.keys().copied().collect();
+        ids.sort_unstable();
+        assert_eq!(
+            ids,
+            [1, 2, 3, 4, 5, 6, 7, 10, 11, 12, 13, 14, 15, 20, 22, 30, 40, 41, 42]
+        );
+        let containment = reparsed.get_entity(30).unwrap();
+        assert_eq!(containment.get_ref_list(4), [20, 22]);
+        assert_eq!(containment.get_entity_ref(5), Some(4));
+        // Every reference resolves
+        for entity in reparsed.entities.values() {
+            let mut refs = Vec::new();
+            entity.attributes.iter().for_each(|a| collect_refs(a, &mut refs));
+            assert!(refs.iter().all(|id| reparsed.get_entity(*id).is_some()), "#{}", entity.id);
+        }
+        // And the same again after another round
+        assert_eq!(reparsed.to_step_string(), text);
+
+        let model = crate::bim::BimModel::from_ifc_file(&reparsed).unwrap();
+        assert_eq!(model.element_count, 2);
+        assert_eq!(model.walls[0].product.properties["Pset_WallCommon.FireRating"], "REI60");
+        assert_eq!(model.element_storey("wall-a").unwrap().0.name, "Ground");
+
+        assert!(extract_subset(&file, &["missing".to_string()]).is_err());
+    }
+
+    #[test]
+    fn test_serialized_file_reparses_to_same_entities() {
+        let content = "ISO-10303-21;
+HEADER;
+FILE_DESCRIPTION(('ViewDefinition [ReferenceView]'),'2;1');
+FILE_NAME('values.ifc','2024-01-01T00:00:00',('Ann'),('Office'),'pre','Modeler','');
+FILE_SCHEMA(('IFC2X3'));
+ENDSEC;
+DATA;
+#1=IFCCARTESIANPOINT((0.,-2.5,1.5E-3));
+#2=IFCDIRECTION((1.E20,-4.,0.25));
+#3=IFCAXIS2PLACEMENT3D(#1,#2,$);
+#4=IFCPROPERTYSINGLEVALUE('Note',$,IFCTEXT('It''s ''quoted'', (with) #signs; and = too'),$);
+#5=IFCINDEXEDPOLYGONALFACE(((1,2,3),(3,4,1)),());
+#6=IFCWALL('wall-guid',$,'Wall',$,$,#3,$,$,.STANDARD.);
+#7=IFCSIUNIT(*,.LENGTHUNIT.,.MILLI.,.METRE.);
+#8=IFCPROPERTYSINGLEVALUE('IsExternal',$,IFCBOOLEAN(.T.),$);
+#9=IFCPROPERTYSINGLEVALUE('LoadBearing',$,.F.,$);
+ENDSEC;
+END-ISO-10303-21;
+";
+        let file = IfcFile::parse(content).unwrap();
+        assert!(file.parse_warnings.is_empty());
+        assert!(matches!(file.get_entity(7).unwrap().attributes[0], IfcValue::Derived));
+
+        let text = file.to_step_string();
+        let reparsed = IfcFile::parse(&text).unwrap();
+        assert!(reparsed.parse_warnings.is_empty(), "{:?}", reparsed.parse_warnings);
+        assert_eq!(reparsed.header.file_description, file.header.file_description);
+        assert_eq!(reparsed.header.file_name, "values.ifc");
+        assert_eq!(reparsed.header.schema, ["IFC2X3"]);
+
+        let written = |f: &IfcFile| {
+            let mut entities: Vec<(EntityId, String, Vec<String>)> = f
+                .entities
+                .values()
+                .map(|e| (e.id, e.entity_type.clone(), e.attributes.iter().map(IfcValue::to_step).collect()))
+                .collect();
+            entities.sort_unstable_by_key(|e| e.0);
+            entities
+        };
+        assert_eq!(written(&reparsed), written(&file));
+        assert_eq!(
+            reparsed.get_entity(4).unwrap().get_string(2).as_deref(),
+            Some("It's 'quoted', (with) #signs; and = too")
+        );
+        assert_eq!(reparsed.to_step_string(), text);
+    }
+}
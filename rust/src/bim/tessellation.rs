@@ -0,0 +1,1051 @@
+//! Tessellation
+//!
+//! Converts explicit IFC geometry representation items to triangle meshes in
+//! the representation's own coordinates (source axes, normally Z up).
+
+use super::boolean::tessellate_boolean_result;
+use super::coordinates::{axis_placement, cartesian_point, direction, UpAxis, WorldPoint};
+use super::entities::{IfcEntity, IfcValue};
+use super::geometry::Mesh;
+use super::ifc_parser::IfcFile;
+use super::topology::face_normal;
+use glam::{Affine3A, DAffine3, DVec3, Quat, Vec2, Vec3, Vec3A};
+use std::f32::consts::TAU;
+use std::sync::Mutex;
+
+/// Vertex color of tessellated meshes (recolored by the element's material)
+const TESSELLATION_COLOR: [f32; 4] = [1.0, 1.0, 1.0, 1.0];
+
+/// Default maximum distance between a curve and its tessellated chords, in
+/// model length units (meters)
+pub const DEFAULT_TESSELLATION_TOLERANCE: f32 = 0.005;
+
+/// Fewest segments of a full circle, however small or coarse
+const MIN_CIRCLE_SEGMENTS: u32 = 8;
+
+/// Most segments of a full circle, however large or fine
+const MAX_CIRCLE_SEGMENTS: u32 = 256;
+
+// Chord tolerance used by the curved-geometry tessellators
+static TESSELLATION_TOLERANCE: Mutex<f32> = Mutex::new(DEFAULT_TESSELLATION_TOLERANCE);
+
+/// Current chord tolerance of curved geometry
+pub fn tessellation_tolerance() -> f32 {
+    *TESSELLATION_TOLERANCE.lock().unwrap()
+}
+
+/// Set the chord tolerance of curved geometry tessellated from now on
+pub fn set_tessellation_tolerance(tolerance: f32) -> Result<(), String> {
+    if !tolerance.is_finite() || tolerance <= 0.0 {
+        return Err(format!("Invalid tessellation tolerance: {}", tolerance));
+    }
+    *TESSELLATION_TOLERANCE.lock().unwrap() = tolerance;
+    Ok(())
+}
+
+/// Segments of a full circle of `radius` whose chords stay within
+/// `tolerance` of it
+///
+/// A chord spanning angle θ deviates r(1 - cos(θ/2)) from the circle, so the
+/// segment count grows with the radius (clamped to a sane range).
+pub fn circle_segments(radius: f32, tolerance: f32) -> u32 {
+    if tolerance <= 0.0 || tolerance >= radius {
+        return MIN_CIRCLE_SEGMENTS;
+    }
+    let max_angle = 2.0 * (1.0 - tolerance / radius).acos();
+    ((TAU / max_angle).ceil() as u32).clamp(MIN_CIRCLE_SEGMENTS, MAX_CIRCLE_SEGMENTS)
+}
+
+/// Segments of an arc of `radius` spanning `angle` radians, at `tolerance`
+pub fn arc_segments(radius: f32, angle: f32, tolerance: f32) -> u32 {
+    let share = (angle.abs() / TAU).min(1.0);
+    ((circle_segments(radius, tolerance) as f32 * share).ceil() as u32).max(1)
+}
+
+/// Tessellate a representation item, or None if its type is not supported
+pub fn tessellate_item(ifc_file: &IfcFile, item: &IfcEntity) -> Option<Mesh> {
+    let faces = match item.entity_type.as_str() {
+        // Ready-made triangle mesh: the fastest path, so checked first
+        "IFCTRIANGULATEDFACESET" => return triangulated_face_set(ifc_file, item),
+        "IFCEXTRUDEDAREASOLID" => return extruded_area_solid(ifc_file, item),
+        "IFCBOOLEANRESULT" | "IFCBOOLEANCLIPPINGRESULT" => return tessellate_boolean_result(ifc_file, item),
+        "IFCREVOLVEDAREASOLID" => return revolved_area_solid(ifc_file, item, tessellation_tolerance()),
+        "IFCSWEPTDISKSOLID" => return swept_disk_solid(ifc_file, item),
+        // IFCFACEBASEDSURFACEMODEL(FbsmFaces: IfcConnectedFaceSet list)
+        "IFCFACEBASEDSURFACEMODEL" => item
+            .get_ref_list(0)
+            .into_iter()
+            .filter_map(|set| ifc_file.get_entity(set))
+            .flat_map(|set| set.get_ref_list(0))
+            .collect::<Vec<_>>(),
+        // IFCSHELLBASEDSURFACEMODEL(SbsmBoundary: IfcOpenShell/IfcClosedShell list)
+        "IFCSHELLBASEDSURFACEMODEL" => item
+            .get_ref_list(0)
+            .into_iter()
+            .filter_map(|shell| ifc_file.get_entity(shell))
+            .flat_map(|shell| shell.get_ref_list(0))
+            .collect(),
+        // IFCFACETEDBREP(Outer: IfcClosedShell)
+        "IFCFACETEDBREP" => ifc_file.get_entity(item.get_entity_ref(0)?)?.get_ref_list(0),
+        _ => return None,
+    };
+
+    let mut mesh = Mesh::new();
+    for face in faces.into_iter().filter_map(|f| ifc_file.get_entity(f)) {
+        if let Some(outline) = face_outline(ifc_file, face) {
+            add_polygon(&mut mesh, &outline);
+        }
+    }
+    // Exporters often get face orientation wrong
+    mesh.fix_winding();
+    (mesh.triangle_count() > 0).then_some(mesh)
+}
+
+/// Read an IFCTRIANGULATEDFACESET straight into a mesh
+///
+/// IFCTRIANGULATEDFACESET(Coordinates, Normals, Closed, CoordIndex, PnIndex):
+/// indices are 1-based and, when PnIndex is given, refer into it rather than
+/// directly into the coordinate list. Normals correspond to the indices; if
+/// absent, vertex normals are averaged from the adjacent faces.
+fn triangulated_face_set(ifc_file: &IfcFile, item: &IfcEntity) -> Option<Mesh> {
+    // IFCCARTESIANPOINTLIST3D(CoordList)
+    let coordinates = ifc_file.get_entity(item.get_entity_ref(0)?)?;
+    let points = real_tuples(coordinates.get_list(0)?);
+    let normals = item.get_list(1).map(Vec::as_slice).map(real_tuples).unwrap_or_default();
+    let pn_index: Vec<usize> = item.get_list(4).map(Vec::as_slice).map(integers).unwrap_or_default();
+
+    // Vertex k (0-based) of the mesh is the k-th point of the index space
+    let vertex_count = if pn_index.is_empty() { points.len() } else { pn_index.len() };
+    let point_of = |k: usize| -> Option<[f32; 3]> {
+        let point = if pn_index.is_empty() { k } else { pn_index.get(k)?.checked_sub(1)? };
+        points.get(point).copied()
+    };
+
+    let mut mesh = Mesh::new();
+    for k in 0..vertex_count {
+        let [x, y, z] = point_of(k)?;
+        mesh.add_vertex(x, y, z);
+        let [r, g, b, a] = TESSELLATION_COLOR;
+        mesh.add_color(r, g, b, a);
+    }
+    for triangle in item.get_list(3)? {
+        let IfcValue::List(triangle) = triangle else {
+            continue;
+        };
+        let corners: Vec<u32> = integers(triangle)
+            .into_iter()
+            .filter_map(|i| i.checked_sub(1))
+            .filter(|&i| i < vertex_count)
+            .map(|i| i as u32)
+            .collect();
+        if let [a, b, c] = corners[..] {
+            mesh.add_triangle(a, b, c);
+        }
+    }
+    if mesh.triangle_count() == 0 {
+        return None;
+    }
+
+    if normals.len() == vertex_count {
+        for [x, y, z] in normals {
+            mesh.add_normal(x, y, z);
+        }
+    } else {
+        let mut accumulated = vec![Vec3::ZERO; vertex_count];
+        for tri in mesh.indices.chunks_exact(3) {
+            let p = |i: u32| Vec3::from_slice(&mesh.vertices[i as usize * 3..i as usize * 3 + 3]);
+            // Unnormalized cross product: larger faces weigh more
+            let normal = (p(tri[1]) - p(tri[0])).cross(p(tri[2]) - p(tri[0]));
+            for &i in tri {
+                accumulated[i as usize] += normal;
+            }
+        }
+        for normal in accumulated {
+            let n = normal.normalize_or_zero();
+            mesh.add_normal(n.x, n.y, n.z);
+        }
+    }
+    Some(mesh)
+}
+
+/// Tessellate an IFCEXTRUDEDAREASOLID(SweptArea, Position, ExtrudedDirection, Depth)
+///
+/// The profile is swept by Depth along ExtrudedDirection (Z when unset),
+/// with flat sides and both ends capped.
+fn extruded_area_solid(ifc_file: &IfcFile, item: &IfcEntity) -> Option<Mesh> {
+    let mut outline = profile_outline(ifc_file, ifc_file.get_entity(item.get_entity_ref(0)?)?)?;
+    let extruded_direction = item
+        .get_entity_ref(2)
+        .and_then(|d| direction(ifc_file, d))
+        .map_or(Vec3::Z, |d| Vec3::new(d[0] as f32, d[1] as f32, d[2] as f32));
+    let sweep = extruded_direction.normalize_or_zero() * item.get_real(3)? as f32;
+    // A direction within the profile's plane sweeps no volume
+    if outline.len() < 3 || sweep.z.abs() <= f32::EPSILON {
+        return None;
+    }
+
+    // Outline wound counter-clockwise seen against the sweep, so side
+    // normals (edge x sweep) point outwards
+    let signed_area: f32 = (0..outline.len())
+        .map(|i| outline[i].perp_dot(outline[(i + 1) % outline.len()]))
+        .sum();
+    if signed_area * sweep.z < 0.0 {
+        outline.reverse();
+    }
+
+    let mut mesh = Mesh::new();
+    let base: Vec<Vec3> = outline.iter().map(|p| p.extend(0.0)).collect();
+    let n = base.len();
+    for j in 0..n {
+        let (a, b) = (base[j], base[(j + 1) % n]);
+        let normal = (b - a).cross(sweep).normalize_or_zero();
+        add_quad(&mut mesh, [(a, normal), (b, normal), (b + sweep, normal), (a + sweep, normal)]);
+    }
+    let top: Vec<Vec3> = base.iter().map(|&p| p + sweep).collect();
+    add_cap(&mut mesh, base, -sweep);
+    add_cap(&mut mesh, top, sweep);
+
+    if let Some(position) = item.get_entity_ref(1) {
+        transform_mesh(&mut mesh, &single_precision(&axis_placement(ifc_file, position)));
+    }
+    (mesh.triangle_count() > 0).then_some(mesh)
+}
+
+/// Tessellate an IFCREVOLVEDAREASOLID(SweptArea, Position, Axis, Angle)
+///
+/// The angle is in the file's plane angle unit. The segment count follows
+/// from the profile's largest distance to the axis.
+fn revolved_area_solid(ifc_file: &IfcFile, item: &IfcEntity, tolerance: f32) -> Option<Mesh> {
+    let outline = profile_outline(ifc_file, ifc_file.get_entity(item.get_entity_ref(0)?)?)?;
+
+    // IFCAXIS1PLACEMENT(Location, Axis) in the profile's plane
+    let axis = ifc_file.get_entity(item.get_entity_ref(2)?)?;
+    let axis_origin = axis
+        .get_entity_ref(0)
+        .and_then(|p| cartesian_point(ifc_file, p))
+        .map_or(Vec3::ZERO, |p| Vec3::new(p[0] as f32, p[1] as f32, p[2] as f32));
+    let axis_direction = axis
+        .get_entity_ref(1)
+        .and_then(|d| direction(ifc_file, d))
+        .map_or(Vec3::Z, |d| Vec3::new(d[0] as f32, d[1] as f32, d[2] as f32));
+
+    let angle = (item.get_real(3)? * ifc_file.plane_angle_unit()) as f32;
+
+    let axis_unit = axis_direction.normalize_or_zero();
+    let radius = outline
+        .iter()
+        .map(|p| (p.extend(0.0) - axis_origin).reject_from(axis_unit).length())
+        .fold(0.0, f32::max);
+    let segments_per_revolution = circle_segments(radius, tolerance);
+
+    let mut mesh = revolve_profile(&outline, axis_origin, axis_direction, angle, segments_per_revolution);
+    if let Some(position) = item.get_entity_ref(1) {
+        transform_mesh(&mut mesh, &single_precision(&axis_placement(ifc_file, position)));
+    }
+    (mesh.triangle_count() > 0).then_some(mesh)
+}
+
+/// Sweep a closed 2D profile (in the XY plane) around an axis
+///
+/// The number of segments is `segments_per_revolution` scaled by the share
+/// of a full turn (at least one). Side faces are smooth around the axis and
+/// sharp between profile edges; partial revolutions get end caps.
+pub fn revolve_profile(
+    outline: &[Vec2],
+    axis_origin: Vec3,
+    axis_direction: Vec3,
+    angle: f32,
+    segments_per_revolution: u32,
+) -> Mesh {
+    let mut mesh = Mesh::new();
+    let axis = axis_direction.normalize_or_zero();
+    if outline.len() < 3 || axis == Vec3::ZERO || angle.abs() <= f32::EPSILON {
+        return mesh;
+    }
+
+    // Counter-clockwise outline so edge normals (dy, -dx) point outwards
+    let mut outline = outline.to_vec();
+    let signed_area: f32 = (0..outline.len())
+        .map(|i| outline[i].perp_dot(outline[(i + 1) % outline.len()]))
+        .sum();
+    if signed_area < 0.0 {
+        outline.reverse();
+    }
+
+    let angle = angle.clamp(-TAU, TAU);
+    let full_turn = (angle.abs() - TAU).abs() <= 1e-4;
+    let segments = ((segments_per_revolution as f32 * angle.abs() / TAU).ceil() as u32).max(1);
+    let rotation = |step: u32| Quat::from_axis_angle(axis, angle * step as f32 / segments as f32);
+    let revolve = |p: Vec2, step: u32| rotation(step) * (p.extend(0.0) - axis_origin) + axis_origin;
+
+    let n = outline.len();
+    for j in 0..n {
+        let (a, b) = (outline[j], outline[(j + 1) % n]);
+        let edge = b - a;
+        let edge_normal = Vec3::new(edge.y, -edge.x, 0.0).normalize_or_zero();
+
+        for step in 0..segments {
+            let corners = [
+                (revolve(a, step), rotation(step) * edge_normal),
+                (revolve(b, step), rotation(step) * edge_normal),
+                (revolve(b, step + 1), rotation(step + 1) * edge_normal),
+                (revolve(a, step + 1), rotation(step + 1) * edge_normal),
+            ];
+            add_quad(&mut mesh, corners);
+        }
+    }
+
+    if !full_turn {
+        // Caps face against the sweep at the start and along it at the end
+        let centroid = outline.iter().copied().sum::<Vec2>() / n as f32;
+        let tangent = axis.cross(centroid.extend(0.0) - axis_origin) * angle.signum();
+        for (step, facing) in [(0, -tangent), (segments, rotation(segments) * tangent)] {
+            let cap: Vec<Vec3> = outline.iter().map(|&p| revolve(p, step)).collect();
+            add_cap(&mut mesh, cap, facing);
+        }
+    }
+    mesh
+}
+
+/// How a swept tube turns at the directrix's interior points
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum JointStyle {
+    /// Cut both tube segments at the bisecting plane (sharp corner)
+    #[default]
+    Mitered,
+    /// Fill the bend with rings rotating around the joint (knuckle)
+    Rounded,
+}
+
+/// Bends sharper than this are rounded even when mitered joints are asked
+/// for (a miter's cross-section stretches by 1 / cos(bend / 2))
+const MAX_MITER_BEND: f32 = std::f32::consts::FRAC_PI_2;
+
+/// Tessellate an IFCSWEPTDISKSOLID(Directrix, Radius, InnerRadius, StartParam, EndParam)
+///
+/// The whole directrix polyline is swept; StartParam/EndParam are ignored.
+fn swept_disk_solid(ifc_file: &IfcFile, item: &IfcEntity) -> Option<Mesh> {
+    let directrix = ifc_file.get_entity(item.get_entity_ref(0)?)?;
+    let path: Vec<Vec3> = match directrix.entity_type.as_str() {
+        // IFCPOLYLINE(Points)
+        "IFCPOLYLINE" => directrix
+            .get_ref_list(0)
+            .into_iter()
+            .filter_map(|p| cartesian_point(ifc_file, p))
+            .map(|p| Vec3::new(p[0] as f32, p[1] as f32, p[2] as f32))
+            .collect(),
+        // IFCINDEXEDPOLYCURVE(Points, Segments, SelfIntersect): arcs are
+        // followed through their points as straight segments
+        "IFCINDEXEDPOLYCURVE" => {
+            let points = ifc_file.get_entity(directrix.get_entity_ref(0)?)?;
+            real_tuples(points.get_list(0)?).into_iter().map(Vec3::from_array).collect()
+        }
+        _ => return None,
+    };
+    let radius = item.get_real(1)? as f32;
+    let inner_radius = item.get_real(2).map(|r| r as f32);
+
+    let radial_segments = circle_segments(radius, tessellation_tolerance());
+    let mesh = sweep_disk(&path, radius, inner_radius, radial_segments, JointStyle::default());
+    (mesh.triangle_count() > 0).then_some(mesh)
+}
+
+/// Sweep a circular disk (or ring, with an inner radius) along a polyline
+///
+/// Cross-section frames are parallel-transported along the path so the tube
+/// does not twist. Ends are capped with disks (or annuli for hollow tubes).
+pub fn sweep_disk(
+    path: &[Vec3],
+    radius: f32,
+    inner_radius: Option<f32>,
+    radial_segments: u32,
+    joints: JointStyle,
+) -> Mesh {
+    let mut mesh = Mesh::new();
+    let mut path = path.to_vec();
+    path.dedup_by(|a, b| a.distance(*b) <= f32::EPSILON);
+    let inner_radius = inner_radius.filter(|&r| r > 0.0 && r < radius);
+    if path.len() < 2 || radius <= 0.0 || radial_segments < 3 {
+        return mesh;
+    }
+
+    let directions: Vec<Vec3> = path.windows(2).map(|w| (w[1] - w[0]).normalize()).collect();
+
+    // Frame (u, v) of each segment, transported by the rotation at each bend
+    let mut frames = Vec::with_capacity(directions.len());
+    let mut u = directions[0].any_orthonormal_vector();
+    for (i, &d) in directions.iter().enumerate() {
+        if i > 0 {
+            u = Quat::from_rotation_arc(directions[i - 1], d) * u;
+            u = u.reject_from_normalized(d).normalize_or_zero();
+        }
+        frames.push((u, d.cross(u)));
+    }
+
+    let around = |k: u32| TAU * k as f32 / radial_segments as f32;
+    let radial = |(u, v): (Vec3, Vec3), k: u32| u * around(k).cos() + v * around(k).sin();
+
+    for r in std::iter::once(radius).chain(inner_radius) {
+        // Inner walls face the tube's axis
+        let outward = if r == radius { 1.0 } else { -1.0 };
+
+        for (i, (&d, &frame)) in directions.iter().zip(&frames).enumerate() {
+            // Bend angle and bisecting plane normal at an interior path point
+            let bend_at = |joint: usize| -> Option<(f32, Vec3)> {
+                if joint == 0 || joint >= directions.len() {
+                    return None;
+                }
+                let (before, after) = (directions[joint - 1], directions[joint]);
+                let bend = before.angle_between(after);
+                (bend > 1e-4).then(|| (bend, (before + after).normalize_or_zero()))
+            };
+            // Plane the segment is cut at: the bisector for miters, else square
+            let miter = |joint: usize| -> Vec3 {
+                match (joints, bend_at(joint)) {
+                    (JointStyle::Mitered, Some((bend, bisector))) if bend <= MAX_MITER_BEND => bisector,
+                    _ => d,
+                }
+            };
+            // Ring point k on the plane through `center` with normal `plane`
+            let ring = |center: Vec3, plane: Vec3, k: u32| {
+                let offset = radial(frame, k) * r;
+                center + offset - d * offset.dot(plane) / d.dot(plane)
+            };
+            let (start_plane, end_plane) = (miter(i), miter(i + 1));
+
+            for k in 0..radial_segments {
+                let (n0, n1) = (radial(frame, k) * outward, radial(frame, k + 1) * outward);
+                add_quad(
+                    &mut mesh,
+                    [
+                        (ring(path[i], start_plane, k), n0),
+                        (ring(path[i], start_plane, k + 1), n1),
+                        (ring(path[i + 1], end_plane, k + 1), n1),
+                        (ring(path[i + 1], end_plane, k), n0),
+                    ],
+                );
+            }
+
+            // Knuckle from this segment's end frame to the next segment's
+            if i + 1 < directions.len() && end_plane == d && bend_at(i + 1).is_some() {
+                let next = directions[i + 1];
+                let turn = Quat::from_rotation_arc(d, next);
+                let steps = ((d.angle_between(next) / around(1)).ceil() as u32).max(1);
+                for step in 0..steps {
+                    let from = Quat::IDENTITY.slerp(turn, step as f32 / steps as f32);
+                    let to = Quat::IDENTITY.slerp(turn, (step + 1) as f32 / steps as f32);
+                    for k in 0..radial_segments {
+                        let corner = |q: Quat, k: u32| {
+                            let n = q * radial(frame, k);
+                            (path[i + 1] + n * r, n * outward)
+                        };
+                        add_quad(&mut mesh, [corner(from, k), corner(from, k + 1), corner(to, k + 1), corner(to, k)]);
+                    }
+                }
+            }
+        }
+    }
+
+    // End caps: disks, or annuli between the outer and inner walls
+    let last = directions.len() - 1;
+    for (center, d, frame) in [
+        (path[0], -directions[0], frames[0]),
+        (path[last + 1], directions[last], frames[last]),
+    ] {
+        let outer: Vec<Vec3> = (0..radial_segments).map(|k| center + radial(frame, k) * radius).collect();
+        match inner_radius {
+            None => add_cap(&mut mesh, outer, d),
+            Some(inner) => {
+                for k in 0..radial_segments {
+                    let point = |k: u32, r: f32| (center + radial(frame, k) * r, d);
+                    add_quad(&mut mesh, [point(k, radius), point(k + 1, radius), point(k + 1, inner), point(k, inner)]);
+                }
+            }
+        }
+    }
+    mesh
+}
+
+/// Add a planar cap polygon, wound to face along `facing`
+fn add_cap(mesh: &mut Mesh, mut points: Vec<Vec3>, facing: Vec3) {
+    if polygon_normal(&points).dot(facing) < 0.0 {
+        points.reverse();
+    }
+    add_polygon(mesh, &points);
+}
+
+/// Add a quad's two triangles, wound to face along the corner normals and
+/// skipping the degenerate half where an edge touches the revolution axis
+fn add_quad(mesh: &mut Mesh, corners: [(Vec3, Vec3); 4]) {
+    let base = mesh.vertex_count() as u32;
+    for (p, normal) in corners {
+        mesh.add_vertex(p.x, p.y, p.z);
+        mesh.add_normal(normal.x, normal.y, normal.z);
+        let [r, g, b, a] = TESSELLATION_COLOR;
+        mesh.add_color(r, g, b, a);
+    }
+
+    let facing: Vec3 = corners.iter().map(|c| c.1).sum();
+    for tri in [[0, 1, 2], [2, 3, 0]] {
+        let tri = tri.map(|k| base + k);
+        let Some(normal) = face_normal(&mesh.vertices, tri) else {
+            continue;
+        };
+        if normal.dot(facing) >= 0.0 {
+            mesh.add_triangle(tri[0], tri[1], tri[2]);
+        } else {
+            mesh.add_triangle(tri[0], tri[2], tri[1]);
+        }
+    }
+}
+
+/// Closed outline of a 2D profile definition, in its position's frame
+///
+/// Supports rectangle, circle and arbitrary closed (polyline) profiles.
+/// Circles are split into segments at the current tessellation tolerance.
+pub fn profile_outline(ifc_file: &IfcFile, profile: &IfcEntity) -> Option<Vec<Vec2>> {
+    let outline: Vec<Vec2> = match profile.entity_type.as_str() {
+        // IFCRECTANGLEPROFILEDEF(ProfileType, ProfileName, Position, XDim, YDim)
+        "IFCRECTANGLEPROFILEDEF" => {
+            let half = Vec2::new(profile.get_real(3)? as f32, profile.get_real(4)? as f32) / 2.0;
+            vec![
+                Vec2::new(-half.x, -half.y),
+                Vec2::new(half.x, -half.y),
+                Vec2::new(half.x, half.y),
+                Vec2::new(-half.x, half.y),
+            ]
+        }
+        // IFCCIRCLEPROFILEDEF(ProfileType, ProfileName, Position, Radius)
+        "IFCCIRCLEPROFILEDEF" => {
+            let radius = profile.get_real(3)? as f32;
+            let segments = circle_segments(radius, tessellation_tolerance());
+            (0..segments)
+                .map(|i| {
+                    let theta = TAU * i as f32 / segments as f32;
+                    Vec2::new(theta.cos(), theta.sin()) * radius
+                })
+                .collect()
+        }
+        // IFCARBITRARYCLOSEDPROFILEDEF(ProfileType, ProfileName, OuterCurve: IfcPolyline)
+        "IFCARBITRARYCLOSEDPROFILEDEF" => {
+            let curve = ifc_file.get_entity(profile.get_entity_ref(2)?)?;
+            if curve.entity_type != "IFCPOLYLINE" {
+                return None;
+            }
+            let mut points: Vec<Vec2> = curve
+                .get_ref_list(0)
+                .into_iter()
+                .filter_map(|p| cartesian_point(ifc_file, p))
+                .map(|p| Vec2::new(p[0] as f32, p[1] as f32))
+                .collect();
+            // Polylines repeat the first point to close
+            if points.len() > 1 && points.first() == points.last() {
+                points.pop();
+            }
+            return Some(points);
+        }
+        _ => return None,
+    };
+
+    // IFCAXIS2PLACEMENT2D(Location, RefDirection)
+    let Some(position) = profile.get_entity_ref(2).and_then(|p| ifc_file.get_entity(p)) else {
+        return Some(outline);
+    };
+    let location = position
+        .get_entity_ref(0)
+        .and_then(|p| cartesian_point(ifc_file, p))
+        .map_or(Vec2::ZERO, |p| Vec2::new(p[0] as f32, p[1] as f32));
+    let x_axis = position
+        .get_entity_ref(1)
+        .and_then(|d| direction(ifc_file, d))
+        .map_or(Vec2::X, |d| Vec2::new(d[0] as f32, d[1] as f32));
+    Some(outline.into_iter().map(|p| location + x_axis.rotate(p)).collect())
+}
+
+/// Move a mesh from its element's local coordinates into the model's
+/// world, relative to `origin` (see `LocalOrigin`)
+///
+/// `placement` is the element's resolved placement (`placement_matrix`),
+/// in f64 so georeferenced offsets cancel out before the f32 conversion.
+pub fn bake_placement(mesh: &mut Mesh, placement: &DAffine3, origin: WorldPoint) {
+    let relative = DAffine3::from_mat3_translation(placement.matrix3, placement.translation - DVec3::from(origin));
+    transform_mesh(mesh, &single_precision(&relative));
+}
+
+/// Single-precision copy of an f64 transform
+fn single_precision(transform: &DAffine3) -> Affine3A {
+    Affine3A::from_mat3_translation(transform.matrix3.as_mat3(), transform.translation.as_vec3())
+}
+
+/// Apply a rigid transform to a mesh's positions and normals
+fn transform_mesh(mesh: &mut Mesh, transform: &Affine3A) {
+    for p in mesh.vertices.chunks_exact_mut(3) {
+        let moved = transform.transform_point3a(Vec3A::new(p[0], p[1], p[2]));
+        p.copy_from_slice(&moved.to_array());
+    }
+    for n in mesh.normals.chunks_exact_mut(3) {
+        let turned = transform.transform_vector3a(Vec3A::new(n[0], n[1], n[2]));
+        n.copy_from_slice(&turned.to_array());
+    }
+    mesh.invalidate_topology();
+}
+
+/// Read a list of real triples ((x, y, z), ...)
+fn real_tuples(list: &[IfcValue]) -> Vec<[f32; 3]> {
+    list.iter()
+        .filter_map(|tuple| {
+            let IfcValue::List(values) = tuple else {
+                return None;
+            };
+            let mut out = [0.0; 3];
+            for (coord, value) in out.iter_mut().zip(values) {
+                *coord = match value {
+                    IfcValue::Real(v) => *v as f32,
+                    IfcValue::Integer(v) => *v as f32,
+                    _ => return None,
+                };
+            }
+            Some(out)
+        })
+        .collect()
+}
+
+/// Read a list of non-negative integers
+///
+/// The parser reads unsigned integers as reals, so integral reals count too.
+fn integers(list: &[IfcValue]) -> Vec<usize> {
+    list.iter()
+        .filter_map(|value| match value {
+            IfcValue::Integer(i) => usize::try_from(*i).ok(),
+            IfcValue::Real(r) if *r >= 0.0 && r.fract() == 0.0 => Some(*r as usize),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Outer boundary of an IFCFACE(Bounds) as points in winding order
+///
+/// Inner bounds (holes) are not cut out.
+fn face_outline(ifc_file: &IfcFile, face: &IfcEntity) -> Option<Vec<Vec3>> {
+    let bounds: Vec<&IfcEntity> = face
+        .get_ref_list(0)
+        .into_iter()
+        .filter_map(|b| ifc_file.get_entity(b))
+        .collect();
+    // IFCFACEOUTERBOUND / IFCFACEBOUND(Bound, Orientation)
+    let bound = bounds
+        .iter()
+        .find(|b| b.entity_type == "IFCFACEOUTERBOUND")
+        .or_else(|| bounds.first())?;
+
+    // IFCPOLYLOOP(Polygon)
+    let polyloop = ifc_file.get_entity(bound.get_entity_ref(0)?)?;
+    if polyloop.entity_type != "IFCPOLYLOOP" {
+        return None;
+    }
+    let mut points: Vec<Vec3> = polyloop
+        .get_ref_list(0)
+        .into_iter()
+        .filter_map(|p| cartesian_point(ifc_file, p))
+        .map(|p| Vec3::new(p[0] as f32, p[1] as f32, p[2] as f32))
+        .collect();
+    if matches!(bound.get_attr(1), Some(IfcValue::Boolean(false))) {
+        points.reverse();
+    }
+    Some(points)
+}
+
+/// Add a flat-shaded planar polygon (counter-clockwise around its normal)
+pub fn add_polygon(mesh: &mut Mesh, points: &[Vec3]) {
+    let normal = polygon_normal(points);
+    let triangles = triangulate_polygon(points);
+    if triangles.is_empty() {
+        return;
+    }
+
+    let base = mesh.vertex_count() as u32;
+    for p in points {
+        mesh.add_vertex(p.x, p.y, p.z);
+        mesh.add_normal(normal.x, normal.y, normal.z);
+        let [r, g, b, a] = TESSELLATION_COLOR;
+        mesh.add_color(r, g, b, a);
+    }
+    for [a, b, c] in triangles {
+        mesh.add_triangle(base + a as u32, base + b as u32, base + c as u32);
+    }
+}
+
+/// Unit normal of a polygon by Newell's method (robust for non-convex loops)
+pub fn polygon_normal(points: &[Vec3]) -> Vec3 {
+    let mut normal = Vec3::ZERO;
+    for (i, p) in points.iter().enumerate() {
+        let q = points[(i + 1) % points.len()];
+        normal += Vec3::new(
+            (p.y - q.y) * (p.z + q.z),
+            (p.z - q.z) * (p.x + q.x),
+            (p.x - q.x) * (p.y + q.y),
+        );
+    }
+    normal.normalize_or_zero()
+}
+
+/// Triangulate a simple planar polygon by ear clipping
+///
+/// Returns triangles as indices into `points`, wound like the polygon.
+/// Collinear and repeated points are tolerated; if no ear can be found (self
+/// intersecting input) the rest is fan-triangulated.
+pub fn triangulate_polygon(points: &[Vec3]) -> Vec<[usize; 3]> {
+    if points.len() < 3 {
+        return Vec::new();
+    }
+    let normal = polygon_normal(points);
+    if normal == Vec3::ZERO {
+        return Vec::new();
+    }
+
+    // Project onto the plane spanned by two axes orthogonal to the normal,
+    // oriented so the polygon is counter-clockwise in 2D
+    let u = normal.any_orthonormal_vector();
+    let v = normal.cross(u);
+    let projected: Vec<Vec2> = points.iter().map(|p| Vec2::new(p.dot(u), p.dot(v))).collect();
+
+    let cross = |a: Vec2, b: Vec2, c: Vec2| (b - a).perp_dot(c - a);
+    let mut remaining: Vec<usize> = (0..points.len()).collect();
+    let mut triangles = Vec::with_capacity(points.len() - 2);
+
+    while remaining.len() > 3 {
+        let n = remaining.len();
+        let ear = (0..n).find(|&i| {
+            let (a, b, c) = (remaining[(i + n - 1) % n], remaining[i], remaining[(i + 1) % n]);
+            let (pa, pb, pc) = (projected[a], projected[b], projected[c]);
+            if cross(pa, pb, pc) <= f32::EPSILON {
+                return false;
+            }
+            // No other vertex may lie inside (or on) the candidate ear
+            !remaining.iter().any(|&j| {
+                j != a
+                    && j != b
+                    && j != c
+                    && projected[j] != pa
+                    && projected[j] != pb
+                    && projected[j] != pc
+                    && cross(pa, pb, projected[j]) >= 0.0
+                    && cross(pb, pc, projected[j]) >= 0.0
+                    && cross(pc, pa, projected[j]) >= 0.0
+            })
+        });
+
+        match ear {
+            Some(i) => {
+                let (a, b, c) = (remaining[(i + n - 1) % n], remaining[i], remaining[(i + 1) % n]);
+                triangles.push([a, b, c]);
+                remaining.remove(i);
+            }
+            None => {
+                // Drop collinear vertices first; fan the rest if none are left
+                if let Some(i) = (0..n).find(|&i| {
+                    let (a, b, c) = (remaining[(i + n - 1) % n], remaining[i], remaining[(i + 1) % n]);
+                    cross(projected[a], projected[b], projected[c]).abs() <= f32::EPSILON
+                }) {
+                    remaining.remove(i);
+                } else {
+                    for k in 1..n - 1 {
+                        triangles.push([remaining[0], remaining[k], remaining[k + 1]]);
+                    }
+                    return triangles;
+                }
+            }
+        }
+    }
+
+    let [a, b, c] = [remaining[0], remaining[1], remaining[2]];
+    if cross(projected[a], projected[b], projected[c]).abs() > f32::EPSILON {
+        triangles.push([a, b, c]);
+    }
+    triangles
+}
+
+/// Rotate a mesh from its source axes to render axes (Y up)
+pub fn convert_to_y_up(mesh: &mut Mesh, up_axis: UpAxis) {
+    if up_axis == UpAxis::Y {
+        return;
+    }
+    for chunk in mesh.vertices.chunks_exact_mut(3).chain(mesh.normals.chunks_exact_mut(3)) {
+        let converted = up_axis.to_y_up([chunk[0], chunk[1], chunk[2]]);
+        chunk.copy_from_slice(&converted);
+    }
+    mesh.invalidate_topology();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ear_clipping_concave_polygon() {
+        // L-shape: 6 vertices, area 3, concave corner at (1, 1)
+        let points = [
+            Vec3::new(0.0, 0.0, 0.0),
+            Vec3::new(2.0, 0.0, 0.0),
+            Vec3::new(2.0, 1.0, 0.0),
+            Vec3::new(1.0, 1.0, 0.0),
+            Vec3::new(1.0, 2.0, 0.0),
+            Vec3::new(0.0, 2.0, 0.0),
+        ];
+        let triangles = triangulate_polygon(&points);
+        assert_eq!(triangles.len(), 4);
+
+        let area: f32 = triangles
+            .iter()
+            .map(|&[a, b, c]| (points[b] - points[a]).cross(points[c] - points[a]).z / 2.0)
+            .sum();
+        // All triangles wind counter-clockwise like the polygon
+        assert!((area - 3.0).abs() < 1e-6);
+        assert!(triangles
+            .iter()
+            .all(|&[a, b, c]| (points[b] - points[a]).cross(points[c] - points[a]).z > 0.0));
+    }
+
+    #[test]
+    fn test_faceted_shell_tessellation() {
+        // Unit cube as a closed shell of six polyloop faces
+        let content = r#"ISO-10303-21;
+HEADER;
+FILE_DESCRIPTION(('ViewDefinition'),'2;1');
+FILE_NAME('shell.ifc','2024-01-01',(''),(''),'','','');
+FILE_SCHEMA(('IFC2X3'));
+ENDSEC;
+DATA;
+#1=IFCCARTESIANPOINT((0.,0.,0.));
+#2=IFCCARTESIANPOINT((1.,0.,0.));
+#3=IFCCARTESIANPOINT((1.,1.,0.));
+#4=IFCCARTESIANPOINT((0.,1.,0.));
+#5=IFCCARTESIANPOINT((0.,0.,1.));
+#6=IFCCARTESIANPOINT((1.,0.,1.));
+#7=IFCCARTESIANPOINT((1.,1.,1.));
+#8=IFCCARTESIANPOINT((0.,1.,1.));
+#10=IFCPOLYLOOP((#1,#4,#3,#2));
+#11=IFCPOLYLOOP((#5,#6,#7,#8));
+#12=IFCPOLYLOOP((#1,#2,#6,#5));
+#13=IFCPOLYLOOP((#2,#3,#7,#6));
+#14=IFCPOLYLOOP((#3,#4,#8,#7));
+#15=IFCPOLYLOOP((#4,#1,#5,#8));
+#20=IFCFACEOUTERBOUND(#10,.T.);
+#21=IFCFACEOUTERBOUND(#11,.T.);
+#22=IFCFACEOUTERBOUND(#12,.T.);
+#23=IFCFACEOUTERBOUND(#13,.T.);
+#24=IFCFACEOUTERBOUND(#14,.T.);
+#25=IFCFACEOUTERBOUND(#15,.T.);
+#30=IFCFACE((#20));
+#31=IFCFACE((#21));
+#32=IFCFACE((#22));
+#33=IFCFACE((#23));
+#34=IFCFACE((#24));
+#35=IFCFACE((#25));
+#40=IFCCLOSEDSHELL((#30,#31,#32,#33,#34,#35));
+#41=IFCSHELLBASEDSURFACEMODEL((#40));
+#42=IFCCONNECTEDFACESET((#30,#31,#32,#33,#34,#35));
+#43=IFCFACEBASEDSURFACEMODEL((#42));
+ENDSEC;
+END-ISO-10303-21;
+"#;
+        let file = IfcFile::parse(content).unwrap();
+
+        for id in [41, 43] {
+            let mesh = tessellate_item(&file, file.get_entity(id).unwrap()).unwrap();
+            assert_eq!(mesh.vertex_count(), 24);
+            assert_eq!(mesh.triangle_count(), 12);
+            assert!((mesh.volume() - 1.0).abs() < 1e-6);
+            assert!(mesh.validate().is_clean());
+
+            let bounds = mesh.bounding_box().unwrap();
+            assert_eq!((bounds.min, bounds.max), ([0.0; 3], [1.0; 3]));
+        }
+        assert!(tessellate_item(&file, file.get_entity(1).unwrap()).is_none());
+    }
+
+    #[test]
+    fn test_triangulated_face_set() {
+        let content = r#"ISO-10303-21;
+HEADER;
+FILE_DESCRIPTION(('ViewDefinition'),'2;1');
+FILE_NAME('tfs.ifc','2024-01-01',(''),(''),'','','');
+FILE_SCHEMA(('IFC4'));
+ENDSEC;
+DATA;
+#1=IFCCARTESIANPOINTLIST3D(((0.,0.,0.),(1.,0.,0.),(1.,1.,0.),(0.,1.,0.)));
+#2=IFCTRIANGULATEDFACESET(#1,$,.F.,((1,2,3),(3,4,1)),$);
+#3=IFCTRIANGULATEDFACESET(#1,((0.,0.,1.),(0.,0.,1.),(0.,0.,1.)),.F.,((1,2,3)),(2,3,4));
+ENDSEC;
+END-ISO-10303-21;
+"#;
+        let file = IfcFile::parse(content).unwrap();
+
+        let mesh = tessellate_item(&file, file.get_entity(2).unwrap()).unwrap();
+        assert_eq!(
+            mesh.vertices,
+            vec![0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 1.0, 1.0, 0.0, 0.0, 1.0, 0.0]
+        );
+        assert_eq!(mesh.indices, vec![0, 1, 2, 2, 3, 0]);
+        assert!(mesh.normals.chunks_exact(3).all(|n| n == [0.0, 0.0, 1.0]));
+
+        // PnIndex remaps the indices into the point list
+        let mesh = tessellate_item(&file, file.get_entity(3).unwrap()).unwrap();
+        assert_eq!(mesh.vertices, vec![1.0, 0.0, 0.0, 1.0, 1.0, 0.0, 0.0, 1.0, 0.0]);
+        assert_eq!(mesh.indices, vec![0, 1, 2]);
+        assert_eq!(mesh.normals.len(), 9);
+    }
+
+    #[test]
+    fn test_segment_count_grows_with_radius() {
+        let tolerance = 0.005;
+        let quarter = TAU / 4.0;
+        let small = arc_segments(0.05, quarter, tolerance);
+        let large = arc_segments(5.0, quarter, tolerance);
+        assert!(large > small, "{} vs {}", large, small);
+
+        // Chords stay within the tolerance (unless clamped at the maximum)
+        for radius in [0.05, 0.5, 5.0] {
+            let step = TAU / circle_segments(radius, tolerance) as f32;
+            assert!(radius * (1.0 - (step / 2.0).cos()) <= tolerance + 1e-6);
+        }
+        assert_eq!(circle_segments(1e6, tolerance), MAX_CIRCLE_SEGMENTS);
+        assert_eq!(circle_segments(0.001, tolerance), MIN_CIRCLE_SEGMENTS);
+        assert_eq!(arc_segments(5.0, TAU, tolerance), circle_segments(5.0, tolerance));
+    }
+
+    #[test]
+    fn test_extruded_rectangle_is_closed_box() {
+        let content = r#"ISO-10303-21;
+HEADER;
+FILE_SCHEMA(('IFC4'));
+ENDSEC;
+DATA;
+#1=IFCRECTANGLEPROFILEDEF(.AREA.,$,$,4.,0.2);
+#2=IFCCARTESIANPOINT((1.,0.,0.5));
+#3=IFCAXIS2PLACEMENT3D(#2,$,$);
+#4=IFCEXTRUDEDAREASOLID(#1,#3,$,3.);
+ENDSEC;
+END-ISO-10303-21;
+"#;
+        let file = IfcFile::parse(content).unwrap();
+        let mesh = tessellate_item(&file, file.get_entity(4).unwrap()).unwrap();
+        let bounds = mesh.bounding_box().unwrap();
+        assert_eq!(bounds.min, [-1.0, -0.1, 0.5]);
+        assert_eq!(bounds.max, [3.0, 0.1, 3.5]);
+        assert!((mesh.volume() - 4.0 * 0.2 * 3.0).abs() < 1e-4);
+        assert!(mesh.validate().is_clean());
+    }
+
+    #[test]
+    fn test_revolved_rectangle() {
+        // 1 x 2 rectangle centered 3 units from the Y axis
+        let outline = [
+            Vec2::new(2.5, -1.0),
+            Vec2::new(3.5, -1.0),
+            Vec2::new(3.5, 1.0),
+            Vec2::new(2.5, 1.0),
+        ];
+
+        // Full turn: four side strips of 4-vertex quads, no caps
+        let segments = 32;
+        let mesh = revolve_profile(&outline, Vec3::ZERO, Vec3::Y, TAU, segments);
+        assert_eq!(mesh.vertex_count(), 4 * 4 * segments as usize);
+        assert!(mesh.validate().is_clean());
+        // Pappus: 2 pi r A, less a little for the polygonal approximation
+        let exact = TAU * 3.0 * 2.0;
+        assert!((mesh.volume() - exact).abs() / exact < 0.01, "volume {}", mesh.volume());
+
+        // Quarter turn: a quarter of the segments plus two 4-vertex caps
+        let mesh = revolve_profile(&outline, Vec3::ZERO, Vec3::Y, TAU / 4.0, segments);
+        assert_eq!(mesh.vertex_count(), 4 * 4 * 8 + 2 * 4);
+        assert!(mesh.volume() > 0.0);
+        assert!((mesh.volume() - exact / 4.0).abs() / exact < 0.01);
+    }
+
+    #[test]
+    fn test_revolve_angle_follows_plane_angle_unit() {
+        // 1 x 2 rectangle centered 3 units from the Y axis, revolved 90 and 6
+        let profile = "#1=IFCCARTESIANPOINT((2.5,-1.));
+#2=IFCCARTESIANPOINT((3.5,-1.));
+#3=IFCCARTESIANPOINT((3.5,1.));
+#4=IFCCARTESIANPOINT((2.5,1.));
+#5=IFCPOLYLINE((#1,#2,#3,#4,#1));
+#6=IFCARBITRARYCLOSEDPROFILEDEF(.AREA.,$,#5);
+#7=IFCCARTESIANPOINT((0.,0.,0.));
+#8=IFCDIRECTION((0.,1.,0.));
+#9=IFCAXIS1PLACEMENT(#7,#8);
+#10=IFCREVOLVEDAREASOLID(#6,$,#9,90.);
+#11=IFCREVOLVEDAREASOLID(#6,$,#9,6.);
+#12=IFCREVOLVEDAREASOLID(#6,$,#9,1.5707963267949);";
+        let degrees = "#20=IFCSIUNIT(*,.PLANEANGLEUNIT.,$,.RADIAN.);
+#21=IFCMEASUREWITHUNIT(IFCPLANEANGLEMEASURE(0.0174532925199433),#20);
+#22=IFCDIMENSIONALEXPONENTS(0,0,0,0,0,0,0);
+#23=IFCCONVERSIONBASEDUNIT(#22,.PLANEANGLEUNIT.,'DEGREE',#21);
+#24=IFCSIUNIT(*,.LENGTHUNIT.,$,.METRE.);
+#25=IFCUNITASSIGNMENT((#24,#23));";
+        let radians = "#20=IFCSIUNIT(*,.PLANEANGLEUNIT.,$,.RADIAN.);
+#25=IFCUNITASSIGNMENT((#20));";
+        let file = |units: &str| {
+            IfcFile::parse(&format!(
+                "ISO-10303-21;\nHEADER;\nFILE_SCHEMA(('IFC4'));\nENDSEC;\nDATA;\n{}\n{}\nENDSEC;\nEND-ISO-10303-21;",
+                profile, units
+            ))
+            .unwrap()
+        };
+        let exact = TAU * 3.0 * 2.0;
+        let volume = |file: &IfcFile, id| tessellate_item(file, file.get_entity(id).unwrap()).unwrap().volume();
+        let close = |volume: f32, share: f32| (volume - exact * share).abs() / (exact * share) < 0.02;
+
+        let file_in_degrees = file(degrees);
+        assert!((file_in_degrees.plane_angle_unit() - 1.0f64.to_radians()).abs() < 1e-12);
+        // 6 degrees, though it is less than a full turn in radians
+        assert!(close(volume(&file_in_degrees, 10), 0.25));
+        assert!(close(volume(&file_in_degrees, 11), 6.0 / 360.0));
+
+        for file in [file(radians), file("")] {
+            assert_eq!(file.plane_angle_unit(), 1.0);
+            assert!(close(volume(&file, 12), 0.25));
+        }
+    }
+
+    #[test]
+    fn test_swept_disk_along_line_is_cylinder() {
+        let path = [Vec3::ZERO, Vec3::new(0.0, 0.0, 4.0)];
+        let segments = 16;
+        let mesh = sweep_disk(&path, 0.5, None, segments, JointStyle::Mitered);
+
+        // Every side vertex lies on the cylinder of radius 0.5 around Z
+        let sides = 4 * segments as usize;
+        for p in mesh.vertices[..sides * 3].chunks_exact(3) {
+            assert!((Vec2::new(p[0], p[1]).length() - 0.5).abs() < 1e-5);
+        }
+        let bounds = mesh.bounding_box().unwrap();
+        assert!((bounds.min[2], bounds.max[2]) == (0.0, 4.0));
+        assert!((bounds.max[0] - 0.5).abs() < 1e-5);
+
+        // Closed: volume of the inscribed 16-gon prism
+        let polygon_area = 0.5 * segments as f32 * 0.25 * (TAU / segments as f32).sin();
+        assert!((mesh.volume() - polygon_area * 4.0).abs() < 1e-4);
+        assert!(mesh.validate().is_clean());
+
+        // A hollow pipe loses the inner bore's volume
+        let hollow = sweep_disk(&path, 0.5, Some(0.4), segments, JointStyle::Mitered);
+        let bore = polygon_area * (0.4 * 0.4) / (0.5 * 0.5) * 4.0;
+        assert!((hollow.volume() - (polygon_area * 4.0 - bore)).abs() < 1e-4);
+    }
+
+    #[test]
+    fn test_swept_disk_bend_joints() {
+        // L-shaped pipe with a 90 degree bend
+        let path = [Vec3::ZERO, Vec3::new(0.0, 0.0, 2.0), Vec3::new(2.0, 0.0, 2.0)];
+        let mitered = sweep_disk(&path, 0.1, None, 12, JointStyle::Mitered);
+        let rounded = sweep_disk(&path, 0.1, None, 12, JointStyle::Rounded);
+        assert!(rounded.triangle_count() > mitered.triangle_count());
+
+        // The mitered corner reaches exactly to the outer edge of both legs
+        let bounds = mitered.bounding_box().unwrap();
+        assert!((bounds.max[2] - 2.1).abs() < 1e-4);
+        assert!((bounds.min[0] + 0.1).abs() < 1e-4);
+    }
+}
@@ -0,0 +1,187 @@
+//! Mesh Topology
+//!
+//! Adjacency of a triangle mesh: which faces share each edge and which faces
+//! touch each vertex. Vertices are welded by position first, so flat-shaded
+//! meshes with split per-face vertices are still connected across edges.
+
+use std::collections::{HashMap, VecDeque};
+
+/// Positions closer than this (per axis) are welded into one vertex
+const WELD_EPSILON: f32 = 1e-5;
+
+/// Edge and vertex adjacency of a triangle mesh
+#[derive(Debug, Clone, Default)]
+pub struct MeshTopology {
+    /// Welded (canonical) vertex index for each vertex
+    canonical: Vec<u32>,
+    /// Welded edge (lower, higher canonical index) → adjacent triangles
+    edge_faces: HashMap<[u32; 2], Vec<u32>>,
+    /// Canonical vertex → triangles touching it (empty for non-canonical vertices)
+    vertex_faces: Vec<Vec<u32>>,
+}
+
+impl MeshTopology {
+    /// Build the adjacency maps of a triangle list
+    ///
+    /// Degenerate triangles (zero area or out-of-range indices) are left out.
+    pub fn new(positions: &[f32], indices: &[u32]) -> Self {
+        let vertex_count = positions.len() / 3;
+
+        // Weld vertices sharing a position to the first index seen there
+        let mut welded: HashMap<[i64; 3], u32> = HashMap::new();
+        let canonical: Vec<u32> = positions
+            .chunks_exact(3)
+            .enumerate()
+            .map(|(i, p)| {
+                let key = [
+                    (p[0] / WELD_EPSILON).round() as i64,
+                    (p[1] / WELD_EPSILON).round() as i64,
+                    (p[2] / WELD_EPSILON).round() as i64,
+                ];
+                *welded.entry(key).or_insert(i as u32)
+            })
+            .collect();
+
+        let mut topology = Self {
+            canonical,
+            edge_faces: HashMap::new(),
+            vertex_faces: vec![Vec::new(); vertex_count],
+        };
+
+        for (face, tri) in indices.chunks_exact(3).enumerate() {
+            let tri = [tri[0], tri[1], tri[2]];
+            if face_normal(positions, tri).is_none() {
+                continue;
+            }
+            let welded_tri = tri.map(|i| topology.canonical[i as usize]);
+            for k in 0..3 {
+                let (a, b) = (welded_tri[k], welded_tri[(k + 1) % 3]);
+                topology
+                    .edge_faces
+                    .entry([a.min(b), a.max(b)])
+                    .or_default()
+                    .push(face as u32);
+                topology.vertex_faces[welded_tri[k] as usize].push(face as u32);
+            }
+        }
+
+        topology
+    }
+
+    /// Welded vertex index that `vertex` shares its position with
+    pub fn canonical(&self, vertex: u32) -> Option<u32> {
+        self.canonical.get(vertex as usize).copied()
+    }
+
+    /// Triangles sharing the edge between two vertices
+    pub fn edge_faces(&self, a: u32, b: u32) -> &[u32] {
+        let (Some(a), Some(b)) = (self.canonical(a), self.canonical(b)) else {
+            return &[];
+        };
+        self.edge_faces
+            .get(&[a.min(b), a.max(b)])
+            .map(Vec::as_slice)
+            .unwrap_or(&[])
+    }
+
+    /// All welded edges with their adjacent triangles
+    pub fn edges(&self) -> impl Iterator<Item = ([u32; 2], &[u32])> {
+        self.edge_faces.iter().map(|(edge, faces)| (*edge, faces.as_slice()))
+    }
+
+    /// Triangles touching a vertex (at its welded position)
+    pub fn vertex_faces(&self, vertex: u32) -> &[u32] {
+        self.canonical(vertex)
+            .map(|v| self.vertex_faces[v as usize].as_slice())
+            .unwrap_or(&[])
+    }
+
+    /// Triangles sharing an edge with `triangle`
+    pub fn face_neighbors(&self, indices: &[u32], triangle: u32) -> Vec<u32> {
+        let Some(tri) = indices.get(triangle as usize * 3..triangle as usize * 3 + 3) else {
+            return Vec::new();
+        };
+        let mut neighbors: Vec<u32> = (0..3)
+            .flat_map(|k| self.edge_faces(tri[k], tri[(k + 1) % 3]))
+            .copied()
+            .filter(|&face| face != triangle)
+            .collect();
+        neighbors.sort_unstable();
+        neighbors.dedup();
+        neighbors
+    }
+
+    /// Triangles reachable from `seed` across shared edges, sorted
+    ///
+    /// Returns an empty list when `seed` is out of range.
+    pub fn connected_region(&self, indices: &[u32], seed: u32) -> Vec<u32> {
+        if seed as usize >= indices.len() / 3 {
+            return Vec::new();
+        }
+
+        let mut visited = vec![false; indices.len() / 3];
+        let mut queue = VecDeque::from([seed]);
+        visited[seed as usize] = true;
+        let mut region = Vec::new();
+
+        while let Some(face) = queue.pop_front() {
+            region.push(face);
+            for neighbor in self.face_neighbors(indices, face) {
+                if !visited[neighbor as usize] {
+                    visited[neighbor as usize] = true;
+                    queue.push_back(neighbor);
+                }
+            }
+        }
+
+        region.sort_unstable();
+        region
+    }
+}
+
+/// Unit normal of a triangle, or None if it is degenerate or out of range
+pub fn face_normal(positions: &[f32], tri: [u32; 3]) -> Option<glam::Vec3> {
+    let position = |i: u32| -> Option<glam::Vec3> {
+        let p = positions.get(i as usize * 3..i as usize * 3 + 3)?;
+        Some(glam::Vec3::new(p[0], p[1], p[2]))
+    };
+    let (p0, p1, p2) = (position(tri[0])?, position(tri[1])?, position(tri[2])?);
+    let normal = (p1 - p0).cross(p2 - p0);
+    (normal.length_squared() > f32::EPSILON * f32::EPSILON).then(|| normal.normalize())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::bim::geometry::{generate_box, generate_box_with_normals, merge_meshes};
+
+    #[test]
+    fn test_box_edges_shared_by_two_faces() {
+        let mesh = generate_box(2.0, 2.0, 2.0);
+        let topology = mesh.topology();
+
+        // 12 cube edges + 6 face diagonals, each between exactly two triangles
+        assert_eq!(topology.edges().count(), 18);
+        for (edge, faces) in topology.edges() {
+            assert_eq!(faces.len(), 2, "edge {:?} has faces {:?}", edge, faces);
+        }
+        // Each cube corner touches the triangles of its three faces
+        for v in 0..mesh.vertex_count() as u32 {
+            assert!(topology.vertex_faces(v).len() >= 3);
+        }
+
+        let all: Vec<u32> = (0..mesh.triangle_count() as u32).collect();
+        assert_eq!(mesh.connected_region(0), all);
+        assert_eq!(mesh.connected_region(99), Vec::<u32>::new());
+    }
+
+    #[test]
+    fn test_connected_region_welds_split_vertices() {
+        // Flat-shaded boxes have per-face vertices but are still one surface
+        let a = generate_box_with_normals([0.0, 0.0, 0.0], [1.0, 1.0, 1.0], [1.0; 4]);
+        let b = generate_box_with_normals([5.0, 0.0, 0.0], [1.0, 1.0, 1.0], [1.0; 4]);
+        let mesh = merge_meshes(vec![a, b]);
+
+        assert_eq!(mesh.connected_region(0), (0..12).collect::<Vec<u32>>());
+        assert_eq!(mesh.connected_region(20), (12..24).collect::<Vec<u32>>());
+    }
+}
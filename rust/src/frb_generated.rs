@@ -2562,11 +2562,13 @@ impl SseDecode for crate::bim::model::ModelInfo {
         let mut var_buildingName = <String>::sse_decode(deserializer);
         let mut var_siteName = <String>::sse_decode(deserializer);
         let mut var_stats = <crate::bim::model::ModelStats>::sse_decode(deserializer);
+        let mut var_hasGeometry = <bool>::sse_decode(deserializer);
         return crate::bim::model::ModelInfo {
             project_name: var_projectName,
             building_name: var_buildingName,
             site_name: var_siteName,
             stats: var_stats,
+            has_geometry: var_hasGeometry,
         };
     }
 }
@@ -2949,6 +2951,7 @@ impl flutter_rust_bridge::IntoDart for crate::bim::model::ModelInfo {
             self.building_name.into_into_dart().into_dart(),
             self.site_name.into_into_dart().into_dart(),
             self.stats.into_into_dart().into_dart(),
+            self.has_geometry.into_into_dart().into_dart(),
         ]
         .into_dart()
     }
@@ -3072,6 +3075,18 @@ impl SseEncode for crate::bim::model::ElementInfo {
     }
 }
 
+impl SseEncode for crate::bim::model::ElementMesh {
+    // Codec=Sse (Serialization based), see doc to use other codecs
+    fn sse_encode(self, serializer: &mut flutter_rust_bridge::for_generated::SseSerializer) {
+        <String>::sse_encode(self.global_id, serializer);
+        <Vec<f32>>::sse_encode(self.vertices, serializer);
+        <Vec<f32>>::sse_encode(self.normals, serializer);
+        <Vec<f32>>::sse_encode(self.colors, serializer);
+        <Vec<u32>>::sse_encode(self.indices, serializer);
+        <Option<crate::bim::geometry::BoundingBox>>::sse_encode(self.running_bounds, serializer);
+    }
+}
+
 impl SseEncode for f32 {
     // Codec=Sse (Serialization based), see doc to use other codecs
     fn sse_encode(self, serializer: &mut flutter_rust_bridge::for_generated::SseSerializer) {
@@ -3092,6 +3107,26 @@ impl SseEncode for [f32; 3] {
     }
 }
 
+impl SseEncode for [f32; 4] {
+    // Codec=Sse (Serialization based), see doc to use other codecs
+    fn sse_encode(self, serializer: &mut flutter_rust_bridge::for_generated::SseSerializer) {
+        <Vec<f32>>::sse_encode(
+            {
+                let boxed: Box<[_]> = Box::new(self);
+                boxed.into_vec()
+            },
+            serializer,
+        );
+    }
+}
+
+impl SseEncode for crate::bim::legend::Legend {
+    // Codec=Sse (Serialization based), see doc to use other codecs
+    fn sse_encode(self, serializer: &mut flutter_rust_bridge::for_generated::SseSerializer) {
+        <Vec<(String, [f32; 4])>>::sse_encode(self.entries, serializer);
+    }
+}
+
 impl SseEncode for f64 {
     // Codec=Sse (Serialization based), see doc to use other codecs
     fn sse_encode(self, serializer: &mut flutter_rust_bridge::for_generated::SseSerializer) {
@@ -3178,6 +3213,16 @@ impl SseEncode for Vec<f32> {
     }
 }
 
+impl SseEncode for Vec<u32> {
+    // Codec=Sse (Serialization based), see doc to use other codecs
+    fn sse_encode(self, serializer: &mut flutter_rust_bridge::for_generated::SseSerializer) {
+        <i32>::sse_encode(self.len() as _, serializer);
+        for item in self {
+            <u32>::sse_encode(item, serializer);
+        }
+    }
+}
+
 impl SseEncode for Vec<u8> {
     // Codec=Sse (Serialization based), see doc to use other codecs
     fn sse_encode(self, serializer: &mut flutter_rust_bridge::for_generated::SseSerializer) {
@@ -3188,6 +3233,16 @@ impl SseEncode for Vec<u8> {
     }
 }
 
+impl SseEncode for Vec<(String, [f32; 4])> {
+    // Codec=Sse (Serialization based), see doc to use other codecs
+    fn sse_encode(self, serializer: &mut flutter_rust_bridge::for_generated::SseSerializer) {
+        <i32>::sse_encode(self.len() as _, serializer);
+        for item in self {
+            <(String, [f32; 4])>::sse_encode(item, serializer);
+        }
+    }
+}
+
 impl SseEncode for Vec<(String, usize)> {
     // Codec=Sse (Serialization based), see doc to use other codecs
     fn sse_encode(self, serializer: &mut flutter_rust_bridge::for_generated::SseSerializer) {
@@ -3208,6 +3263,27 @@ impl SseEncode for Vec<crate::bim::model_registry::RegisteredModelInfo> {
     }
 }
 
+impl SseEncode for crate::api::LoadPhase {
+    // Codec=Sse (Serialization based), see doc to use other codecs
+    fn sse_encode(self, serializer: &mut flutter_rust_bridge::for_generated::SseSerializer) {
+        <i32>::sse_encode(
+            match self {
+                crate::api::LoadPhase::Parsing => 0,
+                crate::api::LoadPhase::Tessellating => 1,
+            },
+            serializer,
+        );
+    }
+}
+
+impl SseEncode for crate::api::LoadStage {
+    // Codec=Sse (Serialization based), see doc to use other codecs
+    fn sse_encode(self, serializer: &mut flutter_rust_bridge::for_generated::SseSerializer) {
+        <crate::api::LoadPhase>::sse_encode(self.stage, serializer);
+        <f64>::sse_encode(self.fraction, serializer);
+    }
+}
+
 impl SseEncode for crate::api::MeasurementPoint {
     // Codec=Sse (Serialization based), see doc to use other codecs
     fn sse_encode(self, serializer: &mut flutter_rust_bridge::for_generated::SseSerializer) {
@@ -3234,6 +3310,7 @@ impl SseEncode for crate::bim::model::ModelInfo {
         <String>::sse_encode(self.building_name, serializer);
         <String>::sse_encode(self.site_name, serializer);
         <crate::bim::model::ModelStats>::sse_encode(self.stats, serializer);
+        <bool>::sse_encode(self.has_geometry, serializer);
     }
 }
 
@@ -3261,6 +3338,16 @@ impl SseEncode for Option<String> {
     }
 }
 
+impl SseEncode for Option<crate::bim::geometry::BoundingBox> {
+    // Codec=Sse (Serialization based), see doc to use other codecs
+    fn sse_encode(self, serializer: &mut flutter_rust_bridge::for_generated::SseSerializer) {
+        <bool>::sse_encode(self.is_some(), serializer);
+        if let Some(value) = self {
+            <crate::bim::geometry::BoundingBox>::sse_encode(value, serializer);
+        }
+    }
+}
+
 impl SseEncode for Option<crate::bim::model::ElementInfo> {
     // Codec=Sse (Serialization based), see doc to use other codecs
     fn sse_encode(self, serializer: &mut flutter_rust_bridge::for_generated::SseSerializer) {
@@ -3291,6 +3378,14 @@ impl SseEncode for Option<i32> {
     }
 }
 
+impl SseEncode for (String, [f32; 4]) {
+    // Codec=Sse (Serialization based), see doc to use other codecs
+    fn sse_encode(self, serializer: &mut flutter_rust_bridge::for_generated::SseSerializer) {
+        <String>::sse_encode(self.0, serializer);
+        <[f32; 4]>::sse_encode(self.1, serializer);
+    }
+}
+
 impl SseEncode for (String, usize) {
     // Codec=Sse (Serialization based), see doc to use other codecs
     fn sse_encode(self, serializer: &mut flutter_rust_bridge::for_generated::SseSerializer) {
@@ -0,0 +1,184 @@
+//! Measurement Annotations
+//!
+//! Persistent 3D markup for measurements: each annotation is a polyline with
+//! a small cross at every point, drawn as lines on top of the model. Closed
+//! annotations (areas) are also filled with a translucent decal. Text labels
+//! are left to Flutter, positioned from projected points.
+
+use crate::bim::tessellation::triangulate_polygon;
+use glam::Vec3;
+
+/// Default annotation color (RGBA, sRGB): bright orange
+pub const DEFAULT_ANNOTATION_COLOR: [f32; 4] = [1.0, 0.55, 0.0, 1.0];
+
+/// Default half-size of the cross drawn at each point, in world units
+pub const DEFAULT_MARKER_SIZE: f32 = 0.1;
+
+/// Opacity of closed annotation fills, relative to the annotation color
+pub const FILL_OPACITY: f32 = 0.35;
+
+/// A measured polyline to draw in the scene
+#[derive(Debug, Clone, PartialEq)]
+pub struct Annotation {
+    pub points: Vec<[f32; 3]>,
+    /// Draw a segment from the last point back to the first
+    pub closed: bool,
+}
+
+impl Annotation {
+    /// Segments between consecutive points (plus the closing one)
+    pub fn segments(&self) -> Vec<[[f32; 3]; 2]> {
+        let mut segments: Vec<[[f32; 3]; 2]> = self.points.windows(2).map(|w| [w[0], w[1]]).collect();
+        if self.closed && self.points.len() > 2 {
+            segments.push([self.points[self.points.len() - 1], self.points[0]]);
+        }
+        segments
+    }
+}
+
+/// Geometry for annotations: flat arrays for a line list (outlines) or a
+/// triangle list (fills)
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct AnnotationMesh {
+    pub vertices: Vec<f32>,
+    pub colors: Vec<f32>,
+    pub indices: Vec<u32>,
+}
+
+impl AnnotationMesh {
+    fn add_line(&mut self, a: [f32; 3], b: [f32; 3], color: [f32; 4]) {
+        let base = (self.vertices.len() / 3) as u32;
+        self.vertices.extend(a);
+        self.vertices.extend(b);
+        self.colors.extend(color);
+        self.colors.extend(color);
+        self.indices.extend([base, base + 1]);
+    }
+
+    /// Number of line segments
+    pub fn line_count(&self) -> usize {
+        self.indices.len() / 2
+    }
+
+    /// Number of triangles, for a fill mesh
+    pub fn triangle_count(&self) -> usize {
+        self.indices.len() / 3
+    }
+}
+
+/// All annotations in the scene with their shared style
+#[derive(Debug, Clone)]
+pub struct AnnotationLayer {
+    annotations: Vec<Annotation>,
+    pub color: [f32; 4],
+    pub marker_size: f32,
+}
+
+impl Default for AnnotationLayer {
+    fn default() -> Self {
+        Self {
+            annotations: Vec::new(),
+            color: DEFAULT_ANNOTATION_COLOR,
+            marker_size: DEFAULT_MARKER_SIZE,
+        }
+    }
+}
+
+impl AnnotationLayer {
+    /// Add an annotation, returning its index
+    pub fn add(&mut self, annotation: Annotation) -> usize {
+        self.annotations.push(annotation);
+        self.annotations.len() - 1
+    }
+
+    /// Remove all annotations (style is kept)
+    pub fn clear(&mut self) {
+        self.annotations.clear();
+    }
+
+    pub fn annotations(&self) -> &[Annotation] {
+        &self.annotations
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.annotations.is_empty()
+    }
+
+    /// Line list of every segment plus a three-axis cross at every point
+    pub fn mesh(&self) -> AnnotationMesh {
+        let mut mesh = AnnotationMesh::default();
+        let s = self.marker_size;
+        for annotation in &self.annotations {
+            for [a, b] in annotation.segments() {
+                mesh.add_line(a, b, self.color);
+            }
+            for &[x, y, z] in &annotation.points {
+                mesh.add_line([x - s, y, z], [x + s, y, z], self.color);
+                mesh.add_line([x, y - s, z], [x, y + s, z], self.color);
+                mesh.add_line([x, y, z - s], [x, y, z + s], self.color);
+            }
+        }
+        mesh
+    }
+
+    /// Triangle list filling every closed annotation, in the annotation
+    /// color at `FILL_OPACITY`
+    pub fn fill_mesh(&self) -> AnnotationMesh {
+        let mut mesh = AnnotationMesh::default();
+        let [r, g, b, a] = self.color;
+        let color = [r, g, b, a * FILL_OPACITY];
+        for annotation in self.annotations.iter().filter(|a| a.closed) {
+            let points: Vec<Vec3> = annotation.points.iter().map(|&p| Vec3::from_array(p)).collect();
+            let triangles = triangulate_polygon(&points);
+            if triangles.is_empty() {
+                continue;
+            }
+            let base = (mesh.vertices.len() / 3) as u32;
+            for p in &annotation.points {
+                mesh.vertices.extend(p);
+                mesh.colors.extend(color);
+            }
+            mesh.indices.extend(triangles.iter().flatten().map(|&i| base + i as u32));
+        }
+        mesh
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_measurement_annotation_adds_segments_and_markers() {
+        let mut layer = AnnotationLayer::default();
+        assert_eq!(layer.mesh().line_count(), 0);
+
+        // Two-segment distance measurement
+        layer.add(Annotation {
+            points: vec![[0.0, 0.0, 0.0], [3.0, 0.0, 0.0], [3.0, 4.0, 0.0]],
+            closed: false,
+        });
+        let mesh = layer.mesh();
+        assert_eq!(mesh.line_count(), 2 + 3 * 3);
+        assert_eq!(&mesh.vertices[..6], &[0.0, 0.0, 0.0, 3.0, 0.0, 0.0]);
+        assert_eq!(&mesh.vertices[6..12], &[3.0, 0.0, 0.0, 3.0, 4.0, 0.0]);
+        assert_eq!(mesh.colors.len(), mesh.vertices.len() / 3 * 4);
+
+        // A closed area outline gets its closing segment
+        layer.add(Annotation {
+            points: vec![[0.0, 0.0, 1.0], [1.0, 0.0, 1.0], [1.0, 1.0, 1.0]],
+            closed: true,
+        });
+        assert_eq!(layer.annotations()[1].segments().len(), 3);
+        assert_eq!(layer.mesh().line_count(), 11 + 3 + 3 * 3);
+
+        // Only the closed one is filled, translucent
+        let fill = layer.fill_mesh();
+        assert_eq!(fill.triangle_count(), 1);
+        assert_eq!(fill.vertices.len(), 9);
+        assert_eq!(fill.colors[3], DEFAULT_ANNOTATION_COLOR[3] * FILL_OPACITY);
+
+        layer.clear();
+        assert!(layer.is_empty());
+    }
+}
@@ -0,0 +1,296 @@
+//! Bounding Volume Hierarchy
+//!
+//! A binary AABB tree over the triangles of an indexed mesh for ray casting.
+//! Nodes are split at the median triangle centroid along the longest axis,
+//! so the tree is balanced and a ray only visits the boxes it passes
+//! through, nearest child first.
+
+use super::camera::{ray_aabb_intersect, ray_triangle_intersect};
+use glam::Vec3;
+use std::ops::Range;
+
+/// Most triangles kept in a leaf
+const MAX_LEAF_TRIANGLES: usize = 4;
+
+/// Boxes grow by this share of their coordinates' magnitude, so rounding in
+/// the box test never rejects a hit on a (flat) box's boundary
+const BOX_PADDING: f32 = 1e-5;
+
+#[derive(Debug, Clone)]
+struct BvhNode {
+    min: Vec3,
+    max: Vec3,
+    /// Leaf: range into `Bvh::triangles`; inner node: empty, with children
+    triangles: Range<u32>,
+    /// Index of the second child (the first one follows this node)
+    right: u32,
+}
+
+impl BvhNode {
+    fn is_leaf(&self) -> bool {
+        !self.triangles.is_empty()
+    }
+}
+
+/// AABB tree over the triangles of a mesh
+#[derive(Debug, Clone, Default)]
+pub struct Bvh {
+    nodes: Vec<BvhNode>,
+    /// Triangle indices, grouped by leaf
+    triangles: Vec<u32>,
+}
+
+impl Bvh {
+    /// Build a tree over the triangles of an indexed mesh (x,y,z vertex
+    /// triplets, triangle list); triangles with out-of-range indices are left
+    /// out
+    pub fn build(vertices: &[f32], indices: &[u32]) -> Self {
+        let vertex = |i: u32| -> Option<Vec3> {
+            let i = i as usize * 3;
+            Some(Vec3::from_slice(vertices.get(i..i + 3)?))
+        };
+
+        // Bounds and centroid of every valid triangle
+        let mut boxes = Vec::new();
+        let mut triangles = Vec::new();
+        for (triangle, tri) in indices.chunks_exact(3).enumerate() {
+            let (Some(v0), Some(v1), Some(v2)) = (vertex(tri[0]), vertex(tri[1]), vertex(tri[2])) else {
+                continue;
+            };
+            boxes.push((v0.min(v1).min(v2), v0.max(v1).max(v2), (v0 + v1 + v2) / 3.0));
+            triangles.push(triangle as u32);
+        }
+
+        let mut bvh = Self {
+            nodes: Vec::with_capacity(2 * triangles.len() / MAX_LEAF_TRIANGLES + 1),
+            triangles,
+        };
+        if !bvh.triangles.is_empty() {
+            let mut order: Vec<usize> = (0..boxes.len()).collect();
+            bvh.build_node(&boxes, &mut order, 0);
+            bvh.triangles = order.into_iter().map(|k| bvh.triangles[k]).collect();
+        }
+        bvh
+    }
+
+    /// Add the node covering `order` (positions `offset..` of the final
+    /// triangle list) and its subtree, returning its index
+    fn build_node(&mut self, boxes: &[(Vec3, Vec3, Vec3)], order: &mut [usize], offset: usize) -> u32 {
+        let (min, max) = order
+            .iter()
+            .fold((Vec3::splat(f32::MAX), Vec3::splat(f32::MIN)), |(min, max), &k| {
+                (min.min(boxes[k].0), max.max(boxes[k].1))
+            });
+        let padding = BOX_PADDING * (1.0 + min.abs().max(max.abs()).max_element());
+        let index = self.nodes.len() as u32;
+        self.nodes.push(BvhNode {
+            min: min - padding,
+            max: max + padding,
+            triangles: 0..0,
+            right: 0,
+        });
+
+        if order.len() <= MAX_LEAF_TRIANGLES {
+            self.nodes[index as usize].triangles = offset as u32..(offset + order.len()) as u32;
+            return index;
+        }
+
+        // Median split along the longest axis of the centroids
+        let (centroid_min, centroid_max) = order
+            .iter()
+            .fold((Vec3::splat(f32::MAX), Vec3::splat(f32::MIN)), |(min, max), &k| {
+                (min.min(boxes[k].2), max.max(boxes[k].2))
+            });
+        let extent = centroid_max - centroid_min;
+        let axis = if extent.x >= extent.y && extent.x >= extent.z {
+            0
+        } else if extent.y >= extent.z {
+            1
+        } else {
+            2
+        };
+        let middle = order.len() / 2;
+        order.select_nth_unstable_by(middle, |&a, &b| boxes[a].2[axis].total_cmp(&boxes[b].2[axis]));
+
+        let (left, right) = order.split_at_mut(middle);
+        self.build_node(boxes, left, offset);
+        let right = self.build_node(boxes, right, offset + middle);
+        self.nodes[index as usize].right = right;
+        index
+    }
+
+    /// Bytes held by the nodes and triangle list
+    pub fn heap_bytes(&self) -> usize {
+        self.nodes.capacity() * size_of::<BvhNode>() + self.triangles.capacity() * size_of::<u32>()
+    }
+
+    /// Number of triangles in the tree
+    pub fn triangle_count(&self) -> usize {
+        self.triangles.len()
+    }
+
+    /// Closest triangle hit by a ray, as `ray_mesh_intersect` would find it
+    ///
+    /// `vertices` and `indices` must be the mesh the tree was built from.
+    /// Returns the distance and the triangle index.
+    pub fn intersect(&self, ray_origin: Vec3, ray_dir: Vec3, vertices: &[f32], indices: &[u32]) -> Option<(f32, usize)> {
+        self.intersect_counting(ray_origin, ray_dir, vertices, indices).0
+    }
+
+    /// `intersect`, also returning how many triangles were tested
+    fn intersect_counting(
+        &self,
+        ray_origin: Vec3,
+        ray_dir: Vec3,
+        vertices: &[f32],
+        indices: &[u32],
+    ) -> (Option<(f32, usize)>, usize) {
+        let vertex = |i: u32| -> Vec3 {
+            let i = i as usize * 3;
+            Vec3::from_slice(&vertices[i..i + 3])
+        };
+        let enter = |node: u32| -> Option<f32> {
+            let node = &self.nodes[node as usize];
+            ray_aabb_intersect(ray_origin, ray_dir, node.min, node.max).map(|t| {
+                // Inside the box: its far side is returned, so visit it now
+                let inside = ray_origin.cmpge(node.min).all() && ray_origin.cmple(node.max).all();
+                if inside { 0.0 } else { t }
+            })
+        };
+
+        let mut closest: Option<(f32, usize)> = None;
+        let mut tested = 0;
+        let mut stack: Vec<(u32, f32)> = Vec::new();
+        if let Some(t) = self.nodes.first().and_then(|_| enter(0)) {
+            stack.push((0, t));
+        }
+        while let Some((index, t_enter)) = stack.pop() {
+            if closest.is_some_and(|(closest_t, _)| t_enter > closest_t) {
+                continue;
+            }
+            let node = &self.nodes[index as usize];
+            if node.is_leaf() {
+                for &triangle in &self.triangles[node.triangles.start as usize..node.triangles.end as usize] {
+                    tested += 1;
+                    let tri = &indices[triangle as usize * 3..triangle as usize * 3 + 3];
+                    let Some(t) = ray_triangle_intersect(ray_origin, ray_dir, vertex(tri[0]), vertex(tri[1]), vertex(tri[2])) else {
+                        continue;
+                    };
+                    // Ties go to the lower triangle index, as in a linear scan
+                    if closest.is_none_or(|(closest_t, closest_tri)| {
+                        t < closest_t || (t == closest_t && (triangle as usize) < closest_tri)
+                    }) {
+                        closest = Some((t, triangle as usize));
+                    }
+                }
+                continue;
+            }
+
+            // Push the farther child first so the nearer one is visited next
+            match (enter(index + 1), enter(node.right)) {
+                (Some(left), Some(right)) if left <= right => stack.extend([(node.right, right), (index + 1, left)]),
+                (Some(left), Some(right)) => stack.extend([(index + 1, left), (node.right, right)]),
+                (Some(left), None) => stack.push((index + 1, left)),
+                (None, Some(right)) => stack.push((node.right, right)),
+                (None, None) => {}
+            }
+        }
+        (closest, tested)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::renderer::{generate_test_cube, ray_mesh_intersect};
+
+    /// Flat arrays of the test cube
+    fn test_cube() -> (Vec<f32>, Vec<u32>) {
+        let (vertices, indices) = generate_test_cube();
+        (vertices.iter().flat_map(|v| v.position).collect(), indices)
+    }
+
+    /// Rays from around a box of `radius` towards points near its center
+    fn rays(count: usize, radius: f32) -> Vec<(Vec3, Vec3)> {
+        (0..count)
+            .map(|i| {
+                let a = i as f32 * 2.399_963;
+                let origin = Vec3::new(a.cos(), ((i % 7) as f32 - 3.0) / 3.0, a.sin()) * radius * 3.0;
+                let target = Vec3::new((a * 3.0).sin(), (a * 5.0).cos(), (a * 7.0).sin()) * radius * 0.5;
+                (origin, (target - origin).normalize())
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_bvh_matches_brute_force_on_cube() {
+        let (vertices, indices) = test_cube();
+        let bvh = Bvh::build(&vertices, &indices);
+        assert_eq!(bvh.triangle_count(), 12);
+
+        // Straight on, hitting the front face at z = 1
+        let hit = bvh.intersect(Vec3::new(0.1, 0.2, 5.0), Vec3::NEG_Z, &vertices, &indices);
+        assert_eq!(hit, ray_mesh_intersect(Vec3::new(0.1, 0.2, 5.0), Vec3::NEG_Z, &vertices, &indices));
+        assert!((hit.unwrap().0 - 4.0).abs() < 1e-5);
+
+        // From inside and from all around, including misses
+        let mut rays = rays(200, 1.0);
+        rays.push((Vec3::ZERO, Vec3::X));
+        rays.push((Vec3::new(5.0, 5.0, 5.0), Vec3::X));
+        for (origin, dir) in rays {
+            assert_eq!(
+                bvh.intersect(origin, dir, &vertices, &indices),
+                ray_mesh_intersect(origin, dir, &vertices, &indices),
+                "ray from {} along {}",
+                origin,
+                dir
+            );
+        }
+        assert!(Bvh::build(&[], &[]).intersect(Vec3::ZERO, Vec3::X, &[], &[]).is_none());
+    }
+
+    #[test]
+    fn test_bvh_tests_few_triangles_on_large_mesh() {
+        // 256 x 256 grid of bumpy quads: 131k triangles
+        let n = 256u32;
+        let mut vertices = Vec::new();
+        for j in 0..=n {
+            for i in 0..=n {
+                let (x, z) = (i as f32 / n as f32 - 0.5, j as f32 / n as f32 - 0.5);
+                vertices.extend([x * 10.0, (x * 40.0).sin() * (z * 30.0).cos() * 0.2, z * 10.0]);
+            }
+        }
+        let mut indices = Vec::new();
+        for j in 0..n {
+            for i in 0..n {
+                let k = j * (n + 1) + i;
+                indices.extend([k, k + n + 1, k + 1, k + 1, k + n + 1, k + n + 2]);
+            }
+        }
+
+        let bvh = Bvh::build(&vertices, &indices);
+        assert_eq!(bvh.triangle_count(), indices.len() / 3);
+        let rays = rays(50, 5.0);
+
+        let expected: Vec<_> = rays
+            .iter()
+            .map(|&(o, d)| ray_mesh_intersect(o, d, &vertices, &indices))
+            .collect();
+        let (actual, tested): (Vec<_>, Vec<_>) = rays
+            .iter()
+            .map(|&(o, d)| bvh.intersect_counting(o, d, &vertices, &indices))
+            .unzip();
+
+        assert_eq!(actual, expected);
+        assert!(expected.iter().any(Option::is_some));
+        // A linear scan tests every triangle for every ray
+        let brute_force = rays.len() * bvh.triangle_count();
+        let with_bvh: usize = tested.iter().sum();
+        assert!(
+            with_bvh * 100 < brute_force,
+            "BVH tested {} triangles vs {} by brute force",
+            with_bvh,
+            brute_force
+        );
+    }
+}
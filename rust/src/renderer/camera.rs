@@ -1,8 +1,41 @@
 //! Camera System
 //!
-//! Implements perspective camera with orbit controls.
+//! Implements perspective and orthographic cameras with orbit controls, and
+//! first-person walk and fly navigation.
 
-use glam::{Mat4, Vec3};
+use glam::{Mat4, Quat, Vec3, Vec4};
+use serde::{Deserialize, Serialize};
+
+/// Camera projection type
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Projection {
+    #[default]
+    Perspective,
+    /// Parallel projection; the visible height matches what the perspective
+    /// view shows at the target distance, so zooming works the same way
+    Orthographic,
+}
+
+/// How drag gestures and movement steer the camera
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum NavMode {
+    /// Drags orbit around the target
+    #[default]
+    Orbit,
+    /// First person at a constant eye height: drags look around in place,
+    /// movement stays on the horizontal plane
+    Walk,
+    /// First person in free flight: drags look around in place, movement
+    /// follows the view direction
+    Fly,
+}
+
+/// Where a camera is and what it looks at (without projection settings)
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct CameraState {
+    pub position: [f32; 3],
+    pub target: [f32; 3],
+}
 
 /// Camera for 3D scene viewing
 #[derive(Debug, Clone)]
@@ -11,8 +44,11 @@ pub struct Camera {
     position: Vec3,
     /// Point the camera is looking at
     target: Vec3,
-    /// Up vector (usually [0, 1, 0])
+    /// World up vector (usually [0, 1, 0])
     up: Vec3,
+    /// Rotation of the view about its direction from a level horizon, in
+    /// radians
+    roll: f32,
     /// Field of view in degrees
     fov: f32,
     /// Aspect ratio (width / height)
@@ -21,6 +57,10 @@ pub struct Camera {
     near: f32,
     /// Far clipping plane
     far: f32,
+    /// Projection type
+    projection: Projection,
+    /// Navigation mode
+    nav_mode: NavMode,
 }
 
 impl Default for Camera {
@@ -29,10 +69,13 @@ impl Default for Camera {
             position: Vec3::new(10.0, 10.0, 10.0),
             target: Vec3::ZERO,
             up: Vec3::Y,
+            roll: 0.0,
             fov: 45.0,
             aspect_ratio: 16.0 / 9.0,
             near: 0.1,
             far: 1000.0,
+            projection: Projection::default(),
+            nav_mode: NavMode::default(),
         }
     }
 }
@@ -62,24 +105,161 @@ impl Camera {
         self.position.to_array()
     }
 
+    /// Get camera target as array
+    pub fn target(&self) -> [f32; 3] {
+        self.target.to_array()
+    }
+
+    /// Current position and target
+    pub fn state(&self) -> CameraState {
+        CameraState {
+            position: self.position(),
+            target: self.target(),
+        }
+    }
+
+    /// Move to a saved position and target, keeping projection settings
+    pub fn set_state(&mut self, state: CameraState) {
+        self.set_position(state.position);
+        self.set_target(state.target);
+    }
+
     /// Set aspect ratio
     pub fn set_aspect_ratio(&mut self, aspect_ratio: f32) {
         self.aspect_ratio = aspect_ratio;
     }
 
+    /// Set projection type
+    pub fn set_projection(&mut self, projection: Projection) {
+        self.projection = projection;
+    }
+
+    /// Get projection type
+    pub fn projection(&self) -> Projection {
+        self.projection
+    }
+
+    /// Set the navigation mode
+    pub fn set_nav_mode(&mut self, mode: NavMode) {
+        self.nav_mode = mode;
+    }
+
+    /// Current navigation mode
+    pub fn nav_mode(&self) -> NavMode {
+        self.nav_mode
+    }
+
+    /// Tilt the view about its direction by `radians` from a level horizon;
+    /// positive turns the camera clockwise, so the picture turns
+    /// counter-clockwise. Kept while orbiting, panning and zooming.
+    pub fn set_roll(&mut self, radians: f32) {
+        self.roll = radians;
+    }
+
+    /// Current roll in radians (0 = level horizon)
+    pub fn roll(&self) -> f32 {
+        self.roll
+    }
+
+    /// Remove any roll, so world up points up on screen again
+    pub fn level_horizon(&mut self) {
+        self.roll = 0.0;
+    }
+
+    /// Up direction of the view: world up projected onto the view plane,
+    /// rotated by the roll
+    pub fn up(&self) -> Vec3 {
+        let forward = (self.target - self.position).normalize_or_zero();
+        let level = self.up - forward * self.up.dot(forward);
+        // Looking straight along world up: no horizon to level to
+        let level = if level.length_squared() > 1e-8 { level.normalize() } else { self.up };
+        Quat::from_axis_angle(forward, self.roll) * level
+    }
+
+    /// Distance from camera position to target
+    pub fn distance(&self) -> f32 {
+        (self.position - self.target).length()
+    }
+
+    /// Height of the view in world units at the target distance
+    /// (everywhere, for orthographic)
+    fn view_height(&self) -> f32 {
+        2.0 * self.distance() * (self.fov.to_radians() / 2.0).tan()
+    }
+
+    /// World units covered by one pixel, for a viewport of the given height
+    /// in pixels. Exact for orthographic; for perspective it holds at the
+    /// target distance.
+    pub fn world_units_per_pixel(&self, viewport_height: f32) -> f32 {
+        if viewport_height <= 0.0 {
+            return 0.0;
+        }
+        self.view_height() / viewport_height
+    }
+
+    /// Diameter in pixels of a sphere on screen, for a viewport of the given
+    /// height (infinite with the camera inside it)
+    pub fn projected_size_px(&self, center: Vec3, radius: f32, viewport_height: f32) -> f32 {
+        let view_height = match self.projection {
+            Projection::Perspective => {
+                let distance = (center - self.position).length();
+                if distance <= radius {
+                    return f32::INFINITY;
+                }
+                2.0 * distance * (self.fov.to_radians() / 2.0).tan()
+            }
+            Projection::Orthographic => self.view_height(),
+        };
+        2.0 * radius / view_height * viewport_height
+    }
+
+    /// Length of a scale bar close to `target_pixels` long, rounded down to
+    /// a nice world length (1, 2 or 5 x 10^n).
+    /// Returns (world_length, pixel_length)
+    pub fn scale_bar_length(&self, target_pixels: f32, viewport_height: f32) -> (f32, f32) {
+        let units_per_pixel = self.world_units_per_pixel(viewport_height);
+        let raw = target_pixels * units_per_pixel;
+        if raw <= 0.0 || !raw.is_finite() {
+            return (0.0, 0.0);
+        }
+
+        let magnitude = 10f32.powf(raw.log10().floor());
+        let nice = [5.0, 2.0, 1.0]
+            .into_iter()
+            .map(|step| step * magnitude)
+            .find(|&length| length <= raw * 1.0001)
+            .unwrap_or(magnitude);
+
+        (nice, nice / units_per_pixel)
+    }
+
     /// Get view matrix (transforms world space to camera space)
     pub fn view_matrix(&self) -> Mat4 {
-        Mat4::look_at_rh(self.position, self.target, self.up)
+        Mat4::look_at_rh(self.position, self.target, self.up())
     }
 
-    /// Get projection matrix (perspective)
+    /// Get projection matrix (perspective or orthographic)
     pub fn projection_matrix(&self) -> Mat4 {
-        Mat4::perspective_rh(
-            self.fov.to_radians(),
-            self.aspect_ratio,
-            self.near,
-            self.far,
-        )
+        match self.projection {
+            Projection::Perspective => Mat4::perspective_rh(
+                self.fov.to_radians(),
+                self.aspect_ratio,
+                self.near,
+                self.far,
+            ),
+            Projection::Orthographic => {
+                let half_height = self.view_height() / 2.0;
+                let half_width = half_height * self.aspect_ratio;
+                Mat4::orthographic_rh(
+                    -half_width,
+                    half_width,
+                    -half_height,
+                    half_height,
+                    self.near,
+                    self.far,
+                )
+            }
+        }
     }
 
     /// Get combined view-projection matrix
@@ -87,6 +267,11 @@ impl Camera {
         self.projection_matrix() * self.view_matrix()
     }
 
+    /// The current view frustum
+    pub fn frustum(&self) -> Frustum {
+        Frustum::from_view_projection(self.view_projection_matrix())
+    }
+
     /// Orbit around target (rotate camera position)
     pub fn orbit(&mut self, delta_x: f32, delta_y: f32) {
         let radius = (self.position - self.target).length();
@@ -102,10 +287,71 @@ impl Camera {
         self.position.z = self.target.z + radius * phi.sin() * theta.sin();
     }
 
+    /// Orbit around `point` from now on: the target moves there and the
+    /// camera follows, keeping its view direction and distance
+    pub fn set_pivot(&mut self, point: [f32; 3]) {
+        let offset = self.position - self.target;
+        self.target = Vec3::from(point);
+        self.position = self.target + offset;
+    }
+
+    /// Turn the view direction in place, keeping the position (first-person
+    /// mouselook); same scale as `orbit`, positive deltas look right and up
+    pub fn look(&mut self, delta_x: f32, delta_y: f32) {
+        let offset = self.target - self.position;
+        let distance = offset.length();
+        let mut theta = offset.z.atan2(offset.x);
+        let mut phi = (offset.y / distance).clamp(-1.0, 1.0).acos();
+
+        theta += delta_x * 0.01;
+        phi = (phi - delta_y * 0.01).clamp(0.1, std::f32::consts::PI - 0.1);
+
+        let direction = Vec3::new(phi.sin() * theta.cos(), phi.cos(), phi.sin() * theta.sin());
+        self.target = self.position + direction * distance;
+    }
+
+    /// Forward direction of movement: the view direction, flattened onto
+    /// the horizontal plane when walking
+    fn movement_forward(&self) -> Vec3 {
+        let forward = (self.target - self.position).normalize_or_zero();
+        match self.nav_mode {
+            NavMode::Walk => (forward - self.up * forward.dot(self.up)).normalize_or_zero(),
+            NavMode::Orbit | NavMode::Fly => forward,
+        }
+    }
+
+    /// Move forward by `meters` (backward if negative), carrying the target
+    /// along; horizontal only when walking
+    pub fn move_forward(&mut self, meters: f32) {
+        let offset = self.movement_forward() * meters;
+        self.position += offset;
+        self.target += offset;
+    }
+
+    /// Move sideways by `meters` (right if positive), carrying the target
+    /// along; always horizontal
+    pub fn strafe(&mut self, meters: f32) {
+        let forward = (self.target - self.position).normalize_or_zero();
+        let offset = forward.cross(self.up).normalize_or_zero() * meters;
+        self.position += offset;
+        self.target += offset;
+    }
+
+    /// Move along world up by `meters`, carrying the target along; ignored
+    /// when walking, which keeps the eye height
+    pub fn move_up(&mut self, meters: f32) {
+        if self.nav_mode == NavMode::Walk {
+            return;
+        }
+        let offset = self.up * meters;
+        self.position += offset;
+        self.target += offset;
+    }
+
     /// Pan camera (move target and position together)
     pub fn pan(&mut self, delta_x: f32, delta_y: f32) {
         let forward = (self.target - self.position).normalize();
-        let right = forward.cross(self.up).normalize();
+        let right = forward.cross(self.up()).normalize();
         let up = right.cross(forward);
 
         let offset = right * delta_x * 0.01 + up * delta_y * 0.01;
@@ -143,6 +389,25 @@ impl Camera {
         }
     }
 
+    /// Project a world point to viewport pixels, e.g. to place 2D labels
+    /// Returns (x, y, depth) with y down from the top edge and depth 0-1
+    /// (near to far, as in the depth buffer), or None if the point is behind
+    /// the camera or outside the view frustum.
+    pub fn project_point(&self, world: Vec3, viewport: (f32, f32)) -> Option<(f32, f32, f32)> {
+        let clip = self.view_projection_matrix() * world.extend(1.0);
+        if clip.w <= f32::EPSILON {
+            return None;
+        }
+        let ndc = clip.truncate() / clip.w;
+        let inside = (-1.0..=1.0).contains(&ndc.x) && (-1.0..=1.0).contains(&ndc.y) && (0.0..=1.0).contains(&ndc.z);
+        if !inside {
+            return None;
+        }
+
+        let (width, height) = viewport;
+        Some(((ndc.x + 1.0) / 2.0 * width, (1.0 - ndc.y) / 2.0 * height, ndc.z))
+    }
+
     /// Convert screen coordinates (0-1 range) to a world-space ray
     /// Returns (origin, direction)
     pub fn screen_to_ray(&self, screen_x: f32, screen_y: f32) -> (Vec3, Vec3) {
@@ -157,13 +422,47 @@ impl Camera {
         let near_point = inv_view_proj.project_point3(Vec3::new(ndc_x, ndc_y, 0.0));
         let far_point = inv_view_proj.project_point3(Vec3::new(ndc_x, ndc_y, 1.0));
 
-        let origin = self.position;
+        // Orthographic rays are parallel, starting from the pixel on the near plane
+        let origin = match self.projection {
+            Projection::Perspective => self.position,
+            Projection::Orthographic => near_point,
+        };
         let direction = (far_point - near_point).normalize();
 
         (origin, direction)
     }
 }
 
+/// View frustum as six planes facing inwards, for culling bounding boxes
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Frustum {
+    /// (normal, distance) with normal·p + distance >= 0 inside
+    planes: [Vec4; 6],
+}
+
+impl Frustum {
+    /// Extract the planes of a view-projection matrix (depth 0-1, as wgpu)
+    pub fn from_view_projection(matrix: Mat4) -> Self {
+        let [x, y, z, w] = [matrix.row(0), matrix.row(1), matrix.row(2), matrix.row(3)];
+        let planes = [w + x, w - x, w + y, w - y, z, w - z].map(|plane| {
+            let length = plane.truncate().length();
+            if length > 0.0 { plane / length } else { plane }
+        });
+        Self { planes }
+    }
+
+    /// Whether a box is at least partly inside (conservative: boxes near a
+    /// frustum corner may pass while outside)
+    pub fn intersects_aabb(&self, min: Vec3, max: Vec3) -> bool {
+        self.planes.iter().all(|plane| {
+            // The box corner farthest along the plane normal
+            let normal = plane.truncate();
+            let corner = Vec3::select(normal.cmpge(Vec3::ZERO), max, min);
+            normal.dot(corner) + plane.w >= 0.0
+        })
+    }
+}
+
 /// Ray-AABB intersection test
 /// Returns the distance to intersection, or None if no hit
 pub fn ray_aabb_intersect(
@@ -194,3 +493,247 @@ pub fn ray_aabb_intersect(
         Some(if tmin < 0.0 { tmax } else { tmin })
     }
 }
+
+/// Ray-triangle intersection test (Möller–Trumbore)
+/// Returns the distance to intersection, or None if no hit (either winding)
+pub fn ray_triangle_intersect(ray_origin: Vec3, ray_dir: Vec3, v0: Vec3, v1: Vec3, v2: Vec3) -> Option<f32> {
+    const EPSILON: f32 = 1e-7;
+
+    let edge1 = v1 - v0;
+    let edge2 = v2 - v0;
+    let p = ray_dir.cross(edge2);
+    let det = edge1.dot(p);
+    if det.abs() < EPSILON {
+        return None; // Ray parallel to triangle
+    }
+
+    let inv_det = 1.0 / det;
+    let s = ray_origin - v0;
+    let u = s.dot(p) * inv_det;
+    if !(0.0..=1.0).contains(&u) {
+        return None;
+    }
+
+    let q = s.cross(edge1);
+    let v = ray_dir.dot(q) * inv_det;
+    if v < 0.0 || u + v > 1.0 {
+        return None;
+    }
+
+    let t = edge2.dot(q) * inv_det;
+    (t > EPSILON).then_some(t)
+}
+
+/// Closest triangle of an indexed mesh hit by a ray
+/// Returns the distance and the triangle index (skipping out-of-range indices)
+pub fn ray_mesh_intersect(ray_origin: Vec3, ray_dir: Vec3, vertices: &[f32], indices: &[u32]) -> Option<(f32, usize)> {
+    let vertex = |i: u32| -> Option<Vec3> {
+        let i = i as usize * 3;
+        Some(Vec3::from_slice(vertices.get(i..i + 3)?))
+    };
+
+    let mut closest: Option<(f32, usize)> = None;
+    for (triangle, tri) in indices.chunks_exact(3).enumerate() {
+        let (Some(v0), Some(v1), Some(v2)) = (vertex(tri[0]), vertex(tri[1]), vertex(tri[2])) else {
+            continue;
+        };
+        let Some(t) = ray_triangle_intersect(ray_origin, ray_dir, v0, v1, v2) else {
+            continue;
+        };
+        if closest.is_none_or(|(closest_t, _)| t < closest_t) {
+            closest = Some((t, triangle));
+        }
+    }
+    closest
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn orthographic_camera(distance: f32) -> Camera {
+        let mut camera = Camera::new(Vec3::new(0.0, 0.0, distance), Vec3::ZERO);
+        camera.set_projection(Projection::Orthographic);
+        camera.set_aspect_ratio(1.0);
+        camera
+    }
+
+    #[test]
+    fn test_orthographic_world_units_per_pixel() {
+        let camera = orthographic_camera(10.0);
+        let viewport_height = 800.0;
+        let units_per_pixel = camera.world_units_per_pixel(viewport_height);
+
+        // Project two points `units_per_pixel * 100` apart at different depths:
+        // in orthographic they are exactly 100 pixels apart regardless of depth
+        let view_proj = camera.view_projection_matrix();
+        for depth in [-5.0, 0.0, 5.0] {
+            let a = view_proj.project_point3(Vec3::new(0.0, 0.0, depth));
+            let b = view_proj.project_point3(Vec3::new(0.0, units_per_pixel * 100.0, depth));
+            let pixels = (b.y - a.y) / 2.0 * viewport_height;
+            assert!((pixels - 100.0).abs() < 1e-3, "{} px at depth {}", pixels, depth);
+        }
+    }
+
+    #[test]
+    fn test_scale_bar_rounds_to_nice_lengths() {
+        let camera = orthographic_camera(10.0);
+        let viewport_height = 1000.0;
+        let units_per_pixel = camera.world_units_per_pixel(viewport_height);
+
+        for (target_world, expected) in [(3.7, 2.0), (5.0, 5.0), (9.9, 5.0), (12.0, 10.0), (0.42, 0.2)] {
+            let target_pixels = target_world / units_per_pixel;
+            let (world, pixels) = camera.scale_bar_length(target_pixels, viewport_height);
+            assert!((world - expected).abs() < 1e-4, "{} -> {}", target_world, world);
+            assert!((pixels * units_per_pixel - world).abs() < 1e-4);
+            assert!(pixels <= target_pixels + 1e-3);
+        }
+
+        assert_eq!(camera.scale_bar_length(100.0, 0.0), (0.0, 0.0));
+    }
+
+    #[test]
+    fn test_orthographic_rays_are_parallel() {
+        let camera = orthographic_camera(10.0);
+        let (origin_a, dir_a) = camera.screen_to_ray(0.25, 0.5);
+        let (origin_b, dir_b) = camera.screen_to_ray(0.75, 0.5);
+        assert!((dir_a - dir_b).length() < 1e-5);
+        assert!((origin_a - origin_b).length() > 1.0);
+    }
+
+    #[test]
+    fn test_project_point_to_viewport() {
+        let mut camera = Camera::new(Vec3::new(0.0, 0.0, 10.0), Vec3::ZERO);
+        camera.set_aspect_ratio(800.0 / 600.0);
+        let viewport = (800.0, 600.0);
+
+        let (x, y, depth) = camera.project_point(Vec3::ZERO, viewport).unwrap();
+        assert!((x - 400.0).abs() < 1e-3 && (y - 300.0).abs() < 1e-3);
+        assert!(depth > 0.0 && depth < 1.0);
+
+        // Up in the world is up on screen (smaller y); farther is deeper
+        let (_, y_up, _) = camera.project_point(Vec3::new(0.0, 1.0, 0.0), viewport).unwrap();
+        assert!(y_up < 300.0);
+        let (_, _, far_depth) = camera.project_point(Vec3::new(0.0, 0.0, -5.0), viewport).unwrap();
+        assert!(far_depth > depth);
+
+        // Behind the camera, or off to the side
+        assert_eq!(camera.project_point(Vec3::new(0.0, 0.0, 20.0), viewport), None);
+        assert_eq!(camera.project_point(Vec3::new(100.0, 0.0, 0.0), viewport), None);
+
+        // Orthographic views project the target to the center too
+        camera.set_projection(Projection::Orthographic);
+        let (x, y, _) = camera.project_point(Vec3::ZERO, viewport).unwrap();
+        assert!((x - 400.0).abs() < 1e-3 && (y - 300.0).abs() < 1e-3);
+    }
+
+    #[test]
+    fn test_roll_then_level_restores_up() {
+        let mut camera = Camera::new(Vec3::new(0.0, 0.0, 10.0), Vec3::ZERO);
+        camera.set_aspect_ratio(1.0);
+        let viewport = (600.0, 600.0);
+        let (up, view) = (camera.up(), camera.view_matrix());
+        assert!(up.abs_diff_eq(Vec3::Y, 1e-6));
+
+        // Rolled a quarter turn, world up shows to the left
+        camera.set_roll(std::f32::consts::FRAC_PI_2);
+        assert!(camera.up().abs_diff_eq(Vec3::X, 1e-6));
+        let (x, y, _) = camera.project_point(Vec3::new(0.0, 1.0, 0.0), viewport).unwrap();
+        assert!(x < 300.0 && (y - 300.0).abs() < 1e-3);
+
+        // The roll follows the camera around
+        camera.orbit(50.0, 20.0);
+        assert!((camera.up().dot(Vec3::Y)).abs() < 1e-5);
+
+        camera.level_horizon();
+        assert_eq!(camera.roll(), 0.0);
+        camera.set_position([0.0, 0.0, 10.0]);
+        assert!(camera.up().abs_diff_eq(up, 1e-6));
+        assert!(camera.view_matrix().abs_diff_eq(view, 1e-6));
+
+        // Looking down, up is world up projected onto the view plane
+        camera.set_position([0.0, 10.0, 10.0]);
+        let expected = Vec3::new(0.0, 1.0, -1.0).normalize();
+        assert!(camera.up().abs_diff_eq(expected, 1e-6));
+    }
+
+    #[test]
+    fn test_pivot_moves_target_keeping_view_direction() {
+        let mut camera = Camera::new(Vec3::new(4.0, 3.0, 10.0), Vec3::new(1.0, 0.0, 0.0));
+        let forward = (Vec3::from(camera.target()) - Vec3::from(camera.position())).normalize();
+        let distance = camera.distance();
+
+        camera.set_pivot([5.0, 2.0, -3.0]);
+        assert_eq!(camera.target(), [5.0, 2.0, -3.0]);
+        let moved = (Vec3::from(camera.target()) - Vec3::from(camera.position())).normalize();
+        assert!(moved.abs_diff_eq(forward, 1e-6));
+        assert!((camera.distance() - distance).abs() < 1e-5);
+
+        // Orbiting now turns about the pivot
+        camera.orbit(40.0, -15.0);
+        assert_eq!(camera.target(), [5.0, 2.0, -3.0]);
+        assert!((camera.distance() - distance).abs() < 1e-4);
+    }
+
+    #[test]
+    fn test_walk_moves_horizontally_at_eye_height() {
+        // Standing at eye height, looking down and ahead
+        let mut camera = Camera::new(Vec3::new(0.0, 1.7, 0.0), Vec3::new(3.0, 0.0, -4.0));
+        camera.set_nav_mode(NavMode::Walk);
+        assert_eq!(camera.nav_mode(), NavMode::Walk);
+        let view = Vec3::from(camera.target()) - Vec3::from(camera.position());
+
+        camera.move_forward(5.0);
+        assert!(Vec3::from(camera.position()).abs_diff_eq(Vec3::new(3.0, 1.7, -4.0), 1e-5));
+        camera.strafe(2.0);
+        assert!(Vec3::from(camera.position()).abs_diff_eq(Vec3::new(4.6, 1.7, -2.8), 1e-5));
+        camera.move_up(1.0);
+        assert_eq!(camera.position()[1], 1.7);
+        let moved = Vec3::from(camera.target()) - Vec3::from(camera.position());
+        assert!(moved.abs_diff_eq(view, 1e-5));
+
+        // Looking around turns the view in place
+        let position = camera.position();
+        camera.look(100.0, 30.0);
+        assert_eq!(camera.position(), position);
+        let turned = Vec3::from(camera.target()) - Vec3::from(position);
+        assert!((turned.length() - view.length()).abs() < 1e-4);
+        assert!(turned.y > view.y);
+        camera.move_forward(1.0);
+        assert!((camera.position()[1] - 1.7).abs() < 1e-6);
+
+        // Flying follows the view direction, climbing included
+        camera.set_nav_mode(NavMode::Fly);
+        let start = Vec3::from(camera.position());
+        let forward = (Vec3::from(camera.target()) - start).normalize();
+        camera.move_forward(2.0);
+        assert!(Vec3::from(camera.position()).abs_diff_eq(start + forward * 2.0, 1e-5));
+        camera.move_up(1.0);
+        assert!((camera.position()[1] - (start.y + forward.y * 2.0 + 1.0)).abs() < 1e-5);
+    }
+
+    #[test]
+    fn test_frustum_culls_boxes_outside_the_view() {
+        let mut camera = Camera::new(Vec3::new(0.0, 0.0, 10.0), Vec3::ZERO);
+        camera.set_aspect_ratio(1.0);
+        let frustum = camera.frustum();
+        let unit = |center: Vec3| (center - 0.5, center + 0.5);
+
+        let (min, max) = unit(Vec3::ZERO);
+        assert!(frustum.intersects_aabb(min, max));
+        // Straddling the left edge of the view
+        let edge = 10.0 * (22.5f32).to_radians().tan();
+        let (min, max) = unit(Vec3::new(-edge, 0.0, 0.0));
+        assert!(frustum.intersects_aabb(min, max));
+
+        // Off to the side, behind the camera, beyond the far plane
+        for center in [Vec3::new(20.0, 0.0, 0.0), Vec3::new(0.0, 0.0, 20.0), Vec3::new(0.0, 0.0, -2000.0)] {
+            let (min, max) = unit(center);
+            assert!(!frustum.intersects_aabb(min, max), "{}", center);
+        }
+
+        camera.set_projection(Projection::Orthographic);
+        let (min, max) = unit(Vec3::new(20.0, 0.0, 0.0));
+        assert!(!camera.frustum().intersects_aabb(min, max));
+    }
+}
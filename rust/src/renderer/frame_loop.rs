@@ -0,0 +1,242 @@
+//! Frame Loop
+//!
+//! Non-blocking frame rendering for Flutter's render loop. A frame's readback
+//! is mapped asynchronously and callers pump the device until its pixels
+//! arrive, instead of blocking on it. Only one frame is in flight at a time:
+//! requests made meanwhile coalesce into a single follow-up frame (rendered
+//! with the camera as it is when that frame starts), so a burst of requests
+//! never queues up stale frames.
+
+use super::{Camera, Renderer, SceneRenderer};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// How long a waiting frame request sleeps between device polls
+pub const FRAME_POLL_INTERVAL: Duration = Duration::from_millis(1);
+
+/// Where the frame loop is
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum FrameState {
+    /// No frame on the GPU
+    #[default]
+    Idle,
+    /// A frame is rendering or being read back
+    InFlight,
+    /// A frame is in flight and another one was requested after it started
+    InFlightWithPending,
+}
+
+/// A frame request, resolved by the first frame numbered `frame` or later
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FrameTicket {
+    generation: u64,
+    frame: u64,
+}
+
+type MapResult = Arc<Mutex<Option<Result<(), wgpu::BufferAsyncError>>>>;
+
+/// The frame on the GPU and its readback buffer
+struct InFlightFrame {
+    number: u64,
+    buffer: wgpu::Buffer,
+    mapped: MapResult,
+}
+
+/// State of asynchronous frame rendering
+#[derive(Default)]
+pub struct FrameLoop {
+    /// Bumped by `cancel`; tickets from earlier generations are void
+    generation: u64,
+    /// Number of the most recently submitted frame
+    submitted: u64,
+    in_flight: Option<InFlightFrame>,
+    /// A frame was requested while another was in flight
+    pending: bool,
+    /// Readback buffer of the last finished frame, reused by the next one
+    spare_buffer: Option<wgpu::Buffer>,
+    /// Most recently read frame and its number
+    latest: Option<(u64, Arc<Vec<u8>>)>,
+}
+
+impl FrameLoop {
+    pub fn state(&self) -> FrameState {
+        match (&self.in_flight, self.pending) {
+            (None, _) => FrameState::Idle,
+            (Some(_), false) => FrameState::InFlight,
+            (Some(_), true) => FrameState::InFlightWithPending,
+        }
+    }
+
+    /// Size of the readback buffers held (in flight and spare)
+    pub fn buffer_bytes(&self) -> u64 {
+        let in_flight = self.in_flight.iter().map(|f| &f.buffer);
+        in_flight.chain(&self.spare_buffer).map(wgpu::Buffer::size).sum()
+    }
+
+    /// Request a frame, submitting one unless a frame is already in flight
+    pub fn request(
+        &mut self,
+        scene: &SceneRenderer,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        camera: &Camera,
+    ) -> FrameTicket {
+        let frame = if self.in_flight.is_some() {
+            self.pending = true;
+            self.submitted + 1
+        } else {
+            self.submit(scene, device, queue, camera);
+            self.submitted
+        };
+        FrameTicket {
+            generation: self.generation,
+            frame,
+        }
+    }
+
+    fn submit(&mut self, scene: &SceneRenderer, device: &wgpu::Device, queue: &wgpu::Queue, camera: &Camera) {
+        let buffer = self
+            .spare_buffer
+            .take()
+            .unwrap_or_else(|| scene.create_read_buffer(device, "Frame Loop Read Buffer"));
+        scene.submit_frame(device, queue, camera, &buffer);
+
+        let mapped: MapResult = Arc::new(Mutex::new(None));
+        let done = mapped.clone();
+        buffer.slice(..).map_async(wgpu::MapMode::Read, move |result| {
+            *done.lock().unwrap() = Some(result);
+        });
+        self.submitted += 1;
+        self.in_flight = Some(InFlightFrame {
+            number: self.submitted,
+            buffer,
+            mapped,
+        });
+    }
+
+    /// Advance the loop without blocking: read back the frame in flight if
+    /// it is done (starting the pending one, if any), then return the pixels
+    /// for `ticket` once a frame resolving it has been read
+    pub fn poll(
+        &mut self,
+        ticket: FrameTicket,
+        scene: &SceneRenderer,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        camera: &Camera,
+    ) -> Result<Option<Arc<Vec<u8>>>, String> {
+        if ticket.generation != self.generation {
+            return Err("Frame request canceled".to_string());
+        }
+
+        device.poll(wgpu::Maintain::Poll);
+        let mapped = self.in_flight.as_ref().and_then(|f| f.mapped.lock().unwrap().take());
+        if let Some(result) = mapped {
+            let frame = self.in_flight.take().expect("mapped frame is in flight");
+            if let Err(e) = result {
+                self.cancel();
+                return Err(format!("Frame readback failed: {}", e));
+            }
+            let pixels = scene.read_mapped_pixels(&frame.buffer);
+            // Visibility for the next frame
+            if let Some(occlusion) = &scene.occlusion {
+                occlusion.read_results(device);
+            }
+            self.spare_buffer = Some(frame.buffer);
+            self.latest = Some((frame.number, Arc::new(pixels)));
+            if std::mem::take(&mut self.pending) {
+                self.submit(scene, device, queue, camera);
+            }
+        }
+
+        Ok(self
+            .latest
+            .as_ref()
+            .filter(|(number, _)| *number >= ticket.frame)
+            .map(|(_, pixels)| pixels.clone()))
+    }
+
+    /// Void all outstanding requests and drop the frame in flight; the next
+    /// request starts the loop afresh (e.g. after the scene is resized)
+    pub fn cancel(&mut self) {
+        self.generation += 1;
+        self.in_flight = None;
+        self.pending = false;
+        self.spare_buffer = None;
+        self.latest = None;
+    }
+}
+
+/// Render a frame without blocking the thread, for a renderer shared behind
+/// a mutex (which is only held while submitting and polling)
+///
+/// Calls made while a frame is in flight share the next frame.
+pub async fn render_frame_async(renderer: &Mutex<Option<Renderer>>) -> Result<Vec<u8>, String> {
+    let ticket = {
+        let mut guard = renderer.lock().unwrap();
+        guard.as_mut().ok_or("Renderer not initialized")?.request_frame()?
+    };
+    loop {
+        {
+            let mut guard = renderer.lock().unwrap();
+            let r = guard.as_mut().ok_or("Renderer not initialized")?;
+            if let Some(pixels) = r.poll_frame(ticket)? {
+                return Ok(pixels);
+            }
+        }
+        tokio::time::sleep(FRAME_POLL_INTERVAL).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::renderer::test_renderer;
+
+    #[test]
+    fn test_rapid_frame_requests_coalesce_without_deadlock() {
+        let Some(renderer) = test_renderer(32, 32) else {
+            eprintln!("No GPU adapter available, skipping");
+            return;
+        };
+        let expected = renderer.render_frame().unwrap();
+        let renderer = Mutex::new(Some(renderer));
+
+        // Two overlapping requests both get a frame
+        let runtime = tokio::runtime::Builder::new_current_thread().enable_time().build().unwrap();
+        let (a, b) = runtime.block_on(async {
+            let both = async { tokio::join!(render_frame_async(&renderer), render_frame_async(&renderer)) };
+            tokio::time::timeout(Duration::from_secs(10), both)
+                .await
+                .expect("frame requests deadlocked")
+        });
+        assert_eq!(a.unwrap(), expected);
+        assert_eq!(b.unwrap(), expected);
+
+        // Requests made while a frame is in flight share one follow-up frame
+        let mut guard = renderer.lock().unwrap();
+        let r = guard.as_mut().unwrap();
+        assert_eq!(r.frame_state(), FrameState::Idle);
+        let first = r.request_frame().unwrap();
+        assert_eq!(r.frame_state(), FrameState::InFlight);
+        let second = r.request_frame().unwrap();
+        let third = r.request_frame().unwrap();
+        assert_ne!(first, second);
+        assert_eq!(second, third);
+        assert_eq!(r.frame_state(), FrameState::InFlightWithPending);
+
+        // Canceling voids them; the loop restarts on the next request
+        r.cancel_frames();
+        assert_eq!(r.frame_state(), FrameState::Idle);
+        assert!(r.poll_frame(second).is_err());
+        let restarted = r.request_frame().unwrap();
+        let pixels = (0..10_000)
+            .find_map(|_| {
+                std::thread::sleep(FRAME_POLL_INTERVAL);
+                r.poll_frame(restarted).unwrap()
+            })
+            .expect("restarted frame never finished");
+        assert_eq!(pixels, expected);
+        assert_eq!(r.frame_state(), FrameState::Idle);
+    }
+}
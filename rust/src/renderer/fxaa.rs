@@ -0,0 +1,219 @@
+//! FXAA
+//!
+//! Fast approximate antialiasing as a post-process: a fullscreen pass over
+//! the resolved color texture that finds high-contrast edges by luma and
+//! blends along them. Much cheaper than MSAA and independent of it, for
+//! devices where multisampling is slow or limited.
+
+use super::overlay::create_overlay_bind_group_layout;
+
+/// Fullscreen triangle running FXAA (after Lottes' FXAA 3.11 console
+/// version) over the source texture
+const FXAA_SHADER: &str = r#"
+struct VertexOutput {
+    @builtin(position) position: vec4<f32>,
+    @location(0) uv: vec2<f32>,
+};
+
+@vertex
+fn vs_main(@builtin(vertex_index) index: u32) -> VertexOutput {
+    let uv = vec2<f32>(f32((index << 1u) & 2u), f32(index & 2u));
+    var out: VertexOutput;
+    out.position = vec4<f32>(uv.x * 2.0 - 1.0, 1.0 - uv.y * 2.0, 0.0, 1.0);
+    out.uv = uv;
+    return out;
+}
+
+@group(0) @binding(0)
+var source: texture_2d<f32>;
+@group(0) @binding(1)
+var source_sampler: sampler;
+
+// Smallest direction reduction, share of luma reducing it, longest search
+const REDUCE_MIN: f32 = 0.0078125;
+const REDUCE_MUL: f32 = 0.125;
+const SPAN_MAX: f32 = 8.0;
+
+fn color_at(uv: vec2<f32>) -> vec3<f32> {
+    return textureSampleLevel(source, source_sampler, uv, 0.0).rgb;
+}
+
+// Perceptual luma of a linear color (square root approximates sRGB)
+fn luma(color: vec3<f32>) -> f32 {
+    return sqrt(dot(color, vec3<f32>(0.299, 0.587, 0.114)));
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    let texel = 1.0 / vec2<f32>(textureDimensions(source));
+    let center = textureSampleLevel(source, source_sampler, in.uv, 0.0);
+    let luma_m = luma(center.rgb);
+    let luma_nw = luma(color_at(in.uv + vec2<f32>(-1.0, -1.0) * texel));
+    let luma_ne = luma(color_at(in.uv + vec2<f32>(1.0, -1.0) * texel));
+    let luma_sw = luma(color_at(in.uv + vec2<f32>(-1.0, 1.0) * texel));
+    let luma_se = luma(color_at(in.uv + vec2<f32>(1.0, 1.0) * texel));
+    let luma_min = min(luma_m, min(min(luma_nw, luma_ne), min(luma_sw, luma_se)));
+    let luma_max = max(luma_m, max(max(luma_nw, luma_ne), max(luma_sw, luma_se)));
+
+    // Blend across the edge: perpendicular to the luma gradient
+    var dir = vec2<f32>(
+        -((luma_nw + luma_ne) - (luma_sw + luma_se)),
+        (luma_nw + luma_sw) - (luma_ne + luma_se),
+    );
+    let reduce = max((luma_nw + luma_ne + luma_sw + luma_se) * 0.25 * REDUCE_MUL, REDUCE_MIN);
+    let scale = 1.0 / (min(abs(dir.x), abs(dir.y)) + reduce);
+    dir = clamp(dir * scale, vec2<f32>(-SPAN_MAX), vec2<f32>(SPAN_MAX)) * texel;
+
+    let inner = 0.5 * (color_at(in.uv + dir * (1.0 / 3.0 - 0.5)) + color_at(in.uv + dir * (2.0 / 3.0 - 0.5)));
+    let outer = inner * 0.5 + 0.25 * (color_at(in.uv - dir * 0.5) + color_at(in.uv + dir * 0.5));
+
+    // The wider blend crossed another edge: keep the narrow one
+    let luma_outer = luma(outer);
+    if luma_outer < luma_min || luma_outer > luma_max {
+        return vec4<f32>(inner, center.a);
+    }
+    return vec4<f32>(outer, center.a);
+}
+"#;
+
+/// FXAA pass from a scene's color texture into a texture of its own
+pub struct FxaaPass {
+    pipeline: wgpu::RenderPipeline,
+    bind_group: wgpu::BindGroup,
+    output: wgpu::Texture,
+}
+
+impl FxaaPass {
+    /// Set up FXAA reading `source` (which needs TEXTURE_BINDING usage)
+    /// into a new texture of the same size and format
+    pub fn new(device: &wgpu::Device, source: &wgpu::Texture) -> Self {
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("FXAA Shader"),
+            source: wgpu::ShaderSource::Wgsl(FXAA_SHADER.into()),
+        });
+        let layout = create_overlay_bind_group_layout(device);
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("FXAA Pipeline Layout"),
+            bind_group_layouts: &[&layout],
+            push_constant_ranges: &[],
+        });
+        let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("FXAA Pipeline"),
+            layout: Some(&pipeline_layout),
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: "vs_main",
+                buffers: &[],
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &shader,
+                entry_point: "fs_main",
+                targets: &[Some(source.format().into())],
+            }),
+            primitive: wgpu::PrimitiveState::default(),
+            depth_stencil: None,
+            multisample: wgpu::MultisampleState::default(),
+            multiview: None,
+        });
+        let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            label: Some("FXAA Sampler"),
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Linear,
+            ..Default::default()
+        });
+
+        let source_view = source.create_view(&wgpu::TextureViewDescriptor::default());
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("FXAA Bind Group"),
+            layout: &layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureView(&source_view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::Sampler(&sampler),
+                },
+            ],
+        });
+        let output = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("FXAA Texture"),
+            size: source.size(),
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: source.format(),
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::COPY_SRC,
+            view_formats: &[],
+        });
+
+        Self {
+            pipeline,
+            bind_group,
+            output,
+        }
+    }
+
+    /// Antialiased frame, once `encode` has run
+    pub fn output(&self) -> &wgpu::Texture {
+        &self.output
+    }
+
+    /// Record the pass
+    pub fn encode(&self, encoder: &mut wgpu::CommandEncoder) {
+        let view = self.output.create_view(&wgpu::TextureViewDescriptor::default());
+        let mut pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("FXAA Pass"),
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                view: &view,
+                resolve_target: None,
+                ops: wgpu::Operations {
+                    load: wgpu::LoadOp::Clear(wgpu::Color::BLACK),
+                    store: wgpu::StoreOp::Store,
+                },
+            })],
+            depth_stencil_attachment: None,
+            timestamp_writes: None,
+            occlusion_query_set: None,
+        });
+        pass.set_pipeline(&self.pipeline);
+        pass.set_bind_group(0, &self.bind_group, &[]);
+        pass.draw(0..3, 0..1);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::renderer::test_renderer;
+
+    #[test]
+    fn test_fxaa_softens_hard_edge() {
+        let Some(mut renderer) = test_renderer(64, 64) else {
+            eprintln!("No GPU adapter available, skipping");
+            return;
+        };
+        // One flat triangle facing the camera with a diagonal edge
+        let positions = [-3.0, -3.0, 0.0, 3.0, -3.0, 0.0, -3.0, 3.0, 0.0];
+        let normals = [0.0, 0.0, 1.0].repeat(3);
+        let colors = [1.0, 1.0, 1.0, 1.0].repeat(3);
+        renderer.load_mesh(&positions, &normals, &colors, &[0, 1, 2]).unwrap();
+        renderer.update_camera([0.0, 0.0, 8.0], [0.0, 0.0, 0.0]);
+
+        let hard = renderer.render_frame().unwrap();
+        let hard_colors: std::collections::HashSet<&[u8]> = hard.chunks_exact(4).collect();
+        assert!(hard_colors.len() >= 2, "triangle not drawn");
+
+        renderer.set_fxaa(true).unwrap();
+        assert!(renderer.fxaa());
+        let smooth = renderer.render_frame().unwrap();
+        assert_eq!(smooth.len(), hard.len());
+        let blended = smooth.chunks_exact(4).filter(|p| !hard_colors.contains(p)).count();
+        assert!(blended > 0, "no intermediate pixels along the edge");
+        // Away from the edge nothing changes
+        assert_eq!(smooth[..4], hard[..4]);
+
+        renderer.set_fxaa(false).unwrap();
+        assert_eq!(renderer.render_frame().unwrap(), hard);
+    }
+}
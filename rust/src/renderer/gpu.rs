@@ -2,14 +2,106 @@
 //!
 //! Handles wgpu instance, adapter, device, and queue initialization.
 
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+
+/// Device limit tiers requested in order, best first
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LimitsTier {
+    /// `Limits::default()` - full WebGPU limits
+    Default,
+    /// `Limits::downlevel_defaults()` - GLES3 / DX11 class devices
+    Downlevel,
+    /// `Limits::downlevel_webgl2_defaults()` - the most conservative tier
+    WebGl2,
+}
+
+impl LimitsTier {
+    /// All tiers, in the order they are tried
+    pub const FALLBACK_ORDER: [LimitsTier; 3] = [LimitsTier::Default, LimitsTier::Downlevel, LimitsTier::WebGl2];
+
+    /// The limits requested for this tier, with texture sizes raised to what
+    /// the adapter supports
+    pub fn limits(&self, adapter_limits: &wgpu::Limits) -> wgpu::Limits {
+        let base = match self {
+            LimitsTier::Default => wgpu::Limits::default(),
+            LimitsTier::Downlevel => wgpu::Limits::downlevel_defaults(),
+            LimitsTier::WebGl2 => wgpu::Limits::downlevel_webgl2_defaults(),
+        };
+        base.using_resolution(adapter_limits.clone())
+    }
+
+    /// Tiers whose limits the adapter reports it can satisfy, best first
+    ///
+    /// The WebGL2 tier is always kept as a last resort.
+    pub fn candidates(adapter_limits: &wgpu::Limits) -> Vec<LimitsTier> {
+        Self::FALLBACK_ORDER
+            .into_iter()
+            .filter(|tier| *tier == LimitsTier::WebGl2 || tier.limits(adapter_limits).check_limits(adapter_limits))
+            .collect()
+    }
+}
+
+/// Try each tier in turn until a request succeeds
+///
+/// Returns the result with the tier that succeeded, or the last error.
+pub async fn request_with_fallback<T, E, F, Fut>(
+    tiers: &[LimitsTier],
+    mut request: F,
+) -> Result<(T, LimitsTier), String>
+where
+    F: FnMut(LimitsTier) -> Fut,
+    Fut: Future<Output = Result<T, E>>,
+    E: std::fmt::Display,
+{
+    let mut last_error = "No limit tiers to try".to_string();
+    for &tier in tiers {
+        match request(tier).await {
+            Ok(value) => return Ok((value, tier)),
+            Err(e) => {
+                tracing::warn!("Device request with {:?} limits failed: {}", tier, e);
+                last_error = e.to_string();
+            }
+        }
+    }
+    Err(last_error)
+}
+
+/// What the initialized GPU device supports
+#[derive(Debug, Clone)]
+pub struct GpuCapabilities {
+    pub adapter_name: String,
+    pub backend: String,
+    /// Limit tier the device was created with
+    pub limits_tier: LimitsTier,
+    pub max_texture_dimension_2d: u32,
+    pub max_buffer_size: u64,
+    pub wireframe_supported: bool,
+    pub anisotropic_filtering_supported: bool,
+}
+
 /// GPU context wrapping wgpu resources
 pub struct GpuContext {
     pub instance: Option<wgpu::Instance>,
     pub adapter: Option<wgpu::Adapter>,
     pub device: Option<wgpu::Device>,
     pub queue: Option<wgpu::Queue>,
+    /// Limit tier the device was created with
+    limits_tier: Option<LimitsTier>,
+    /// Most recent GPU error (from error scopes or the uncaptured-error handler)
+    last_error: Arc<Mutex<Option<String>>>,
 }
 
+impl Default for GpuContext {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Error reported by `GpuContext::initialize` when neither a hardware nor a
+/// fallback adapter exists
+pub(crate) const NO_ADAPTER_ERROR: &str = "Failed to find suitable GPU adapter";
+
 impl GpuContext {
     /// Create a new uninitialized GPU context
     pub fn new() -> Self {
@@ -18,6 +110,8 @@ impl GpuContext {
             adapter: None,
             device: None,
             queue: None,
+            limits_tier: None,
+            last_error: Arc::new(Mutex::new(None)),
         }
     }
 
@@ -31,15 +125,22 @@ impl GpuContext {
             ..Default::default()
         });
 
-        // Request adapter
-        let adapter = instance
-            .request_adapter(&wgpu::RequestAdapterOptions {
-                power_preference: wgpu::PowerPreference::HighPerformance,
-                compatible_surface: None,
-                force_fallback_adapter: false,
-            })
-            .await
-            .ok_or("Failed to find suitable GPU adapter")?;
+        // Request adapter, falling back to a software adapter when no
+        // hardware one is available
+        let mut adapter = None;
+        for force_fallback_adapter in [false, true] {
+            adapter = instance
+                .request_adapter(&wgpu::RequestAdapterOptions {
+                    power_preference: wgpu::PowerPreference::HighPerformance,
+                    compatible_surface: None,
+                    force_fallback_adapter,
+                })
+                .await;
+            if adapter.is_some() {
+                break;
+            }
+        }
+        let adapter = adapter.ok_or(NO_ADAPTER_ERROR)?;
 
         tracing::info!(
             "Selected adapter: {:?}",
@@ -58,30 +159,51 @@ impl GpuContext {
             tracing::warn!("POLYGON_MODE_LINE not supported - wireframe mode unavailable");
         }
 
-        // Request device and queue
-        let (device, queue) = adapter
-            .request_device(
-                &wgpu::DeviceDescriptor {
-                    label: Some("BIM Viewer Device"),
-                    required_features,
-                    required_limits: wgpu::Limits::downlevel_webgl2_defaults()
-                        .using_resolution(adapter.limits()),
-                },
-                None,
-            )
+        // Request device and queue, falling back to more conservative limits
+        let adapter_limits = adapter.limits();
+        let ((device, queue), limits_tier) =
+            request_with_fallback(&LimitsTier::candidates(&adapter_limits), |tier| {
+                adapter.request_device(
+                    &wgpu::DeviceDescriptor {
+                        label: Some("BIM Viewer Device"),
+                        required_features,
+                        required_limits: tier.limits(&adapter_limits),
+                    },
+                    None,
+                )
+            })
             .await
             .map_err(|e| format!("Failed to create device: {}", e))?;
 
-        tracing::info!("GPU device and queue created successfully");
+        tracing::info!("GPU device and queue created successfully ({:?} limits)", limits_tier);
+
+        // Record errors raised outside of any error scope instead of panicking
+        let last_error = self.last_error.clone();
+        device.on_uncaptured_error(Box::new(move |error| {
+            tracing::error!("Uncaptured GPU error: {}", error);
+            *last_error.lock().unwrap() = Some(error.to_string());
+        }));
 
         self.instance = Some(instance);
         self.adapter = Some(adapter);
         self.device = Some(device);
         self.queue = Some(queue);
+        self.limits_tier = Some(limits_tier);
 
         Ok(())
     }
 
+    /// Drop the queue, device, adapter and instance, releasing the GPU; the
+    /// context can be initialized again afterwards
+    pub fn release(&mut self) {
+        self.queue = None;
+        self.device = None;
+        self.adapter = None;
+        self.instance = None;
+        self.limits_tier = None;
+        *self.last_error.lock().unwrap() = None;
+    }
+
     /// Check if GPU is initialized
     pub fn is_initialized(&self) -> bool {
         self.device.is_some() && self.queue.is_some()
@@ -97,6 +219,103 @@ impl GpuContext {
         self.queue.as_ref()
     }
 
+    /// Get the limit tier the device was created with
+    pub fn limits_tier(&self) -> Option<LimitsTier> {
+        self.limits_tier
+    }
+
+    /// Get the limits granted to the device
+    pub fn limits(&self) -> Option<wgpu::Limits> {
+        self.device.as_ref().map(|d| d.limits())
+    }
+
+    /// Describe the initialized device's capabilities
+    pub fn capabilities(&self) -> Option<GpuCapabilities> {
+        let info = self.adapter.as_ref()?.get_info();
+        let limits = self.limits()?;
+        Some(GpuCapabilities {
+            adapter_name: info.name,
+            backend: format!("{:?}", info.backend),
+            limits_tier: self.limits_tier?,
+            max_texture_dimension_2d: limits.max_texture_dimension_2d,
+            max_buffer_size: limits.max_buffer_size,
+            wireframe_supported: self.wireframe_supported(),
+            anisotropic_filtering_supported: self.anisotropic_filtering_supported(),
+        })
+    }
+
+    /// Run device operations inside validation and out-of-memory error scopes
+    ///
+    /// Returns the first captured error (also recorded as the last GPU error)
+    /// instead of the operation's result.
+    pub fn with_error_scope<T>(
+        &self,
+        operation: &str,
+        f: impl FnOnce(&wgpu::Device) -> T,
+    ) -> Result<T, String> {
+        let device = self.device().ok_or("GPU not initialized")?;
+
+        device.push_error_scope(wgpu::ErrorFilter::OutOfMemory);
+        device.push_error_scope(wgpu::ErrorFilter::Validation);
+        let result = f(device);
+        let validation = resolve_now(device.pop_error_scope()).flatten();
+        let out_of_memory = resolve_now(device.pop_error_scope()).flatten();
+
+        match validation.or(out_of_memory) {
+            Some(error) => {
+                let message = format!("{} failed: {}", operation, error);
+                tracing::error!("{}", message);
+                *self.last_error.lock().unwrap() = Some(message.clone());
+                Err(message)
+            }
+            None => Ok(result),
+        }
+    }
+
+    /// Get the most recent GPU error, if any
+    pub fn last_error(&self) -> Option<String> {
+        self.last_error.lock().unwrap().clone()
+    }
+
+    /// Clear the recorded GPU error
+    pub fn clear_last_error(&self) {
+        *self.last_error.lock().unwrap() = None;
+    }
+
+    /// Check if anisotropic texture filtering is supported
+    pub fn anisotropic_filtering_supported(&self) -> bool {
+        self.adapter
+            .as_ref()
+            .map(|a| {
+                a.get_downlevel_capabilities()
+                    .flags
+                    .contains(wgpu::DownlevelFlags::ANISOTROPIC_FILTERING)
+            })
+            .unwrap_or(false)
+    }
+
+    /// Check if the scene's color and depth targets can be multisampled
+    /// with this many samples
+    pub fn msaa_supported(&self, samples: u32) -> bool {
+        self.adapter.as_ref().is_some_and(|a| {
+            [wgpu::TextureFormat::Rgba8UnormSrgb, wgpu::TextureFormat::Depth32Float]
+                .into_iter()
+                .all(|format| a.get_texture_format_features(format).flags.sample_count_supported(samples))
+        })
+    }
+
+    /// Check if occlusion queries can be created on this device
+    pub fn occlusion_queries_supported(&self) -> bool {
+        self.with_error_scope("Occlusion query probe", |device| {
+            device.create_query_set(&wgpu::QuerySetDescriptor {
+                label: Some("Occlusion Query Probe"),
+                ty: wgpu::QueryType::Occlusion,
+                count: 1,
+            });
+        })
+        .is_ok()
+    }
+
     /// Check if wireframe rendering is supported
     pub fn wireframe_supported(&self) -> bool {
         self.device
@@ -105,3 +324,67 @@ impl GpuContext {
             .unwrap_or(false)
     }
 }
+
+/// Poll a future once, returning its output if it is already complete
+///
+/// Error scopes resolve immediately on native backends, so this avoids
+/// blocking on an executor from inside synchronous render calls.
+fn resolve_now<F: Future>(future: F) -> Option<F::Output> {
+    let mut future = std::pin::pin!(future);
+    let mut context = std::task::Context::from_waker(std::task::Waker::noop());
+    match future.as_mut().poll(&mut context) {
+        std::task::Poll::Ready(output) => Some(output),
+        std::task::Poll::Pending => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_restricted_adapter_falls_back_to_webgl2_limits() {
+        // An adapter that only reaches WebGL2-level limits
+        let restricted = wgpu::Limits::downlevel_webgl2_defaults();
+        assert_eq!(LimitsTier::candidates(&restricted), vec![LimitsTier::WebGl2]);
+
+        // A capable adapter tries the best tier first
+        let capable = wgpu::Limits::default();
+        assert_eq!(LimitsTier::candidates(&capable)[0], LimitsTier::Default);
+
+        // Device creation failing for the better tiers lands on the next one
+        let runtime = tokio::runtime::Builder::new_current_thread().build().unwrap();
+        let mut attempts = Vec::new();
+        let (granted, tier) = runtime
+            .block_on(request_with_fallback(&LimitsTier::FALLBACK_ORDER, |tier| {
+                attempts.push(tier);
+                let result = match tier {
+                    LimitsTier::Default => Err("limits exceeded"),
+                    _ => Ok(tier.limits(&restricted).max_texture_dimension_2d),
+                };
+                std::future::ready(result)
+            }))
+            .unwrap();
+        assert_eq!(tier, LimitsTier::Downlevel);
+        assert_eq!(attempts, vec![LimitsTier::Default, LimitsTier::Downlevel]);
+        assert_eq!(granted, restricted.max_texture_dimension_2d);
+
+        let all_failed = runtime.block_on(request_with_fallback(&LimitsTier::FALLBACK_ORDER, |_| {
+            std::future::ready(Err::<(), _>("no device"))
+        }));
+        assert_eq!(all_failed.unwrap_err(), "no device");
+    }
+
+    #[test]
+    fn test_capabilities_report_granted_limits() {
+        let Some(renderer) = crate::renderer::test_renderer(8, 8) else {
+            eprintln!("No GPU adapter available, skipping");
+            return;
+        };
+        let capabilities = renderer.gpu.capabilities().unwrap();
+        let limits = renderer.gpu.limits().unwrap();
+        assert_eq!(capabilities.max_texture_dimension_2d, limits.max_texture_dimension_2d);
+        assert_eq!(capabilities.max_buffer_size, limits.max_buffer_size);
+        assert_eq!(Some(capabilities.limits_tier), renderer.gpu.limits_tier());
+    }
+}
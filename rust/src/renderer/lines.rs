@@ -0,0 +1,196 @@
+//! Thick Lines
+//!
+//! GPUs rasterize line primitives one pixel wide, which all but disappears on
+//! high-DPI screens. Lines are drawn instead as one instance per segment: the
+//! line shader expands each segment into a screen-space quad (two triangles)
+//! `line_width_px` wide, with square caps so joints between segments close.
+//! `expand_segment` is the same expansion on the CPU.
+
+use super::vertex::Vertex;
+use bytemuck::{Pod, Zeroable};
+use glam::{Vec2, Vec4};
+use std::collections::HashSet;
+
+/// Default line width in pixels
+pub const DEFAULT_LINE_WIDTH_PX: f32 = 1.0;
+
+/// Widest line accepted, in pixels
+pub const MAX_LINE_WIDTH_PX: f32 = 32.0;
+
+/// Vertices drawn per segment instance (two triangles)
+pub const VERTICES_PER_SEGMENT: u32 = 6;
+
+/// Quad corners in vertex order: x picks the end (0 start, 1 end), y the side
+/// of the line (-1 right, 1 left)
+pub const QUAD_CORNERS: [[f32; 2]; 6] = [
+    [0.0, -1.0],
+    [1.0, -1.0],
+    [1.0, 1.0],
+    [0.0, -1.0],
+    [1.0, 1.0],
+    [0.0, 1.0],
+];
+
+/// One line segment, drawn as an instance of the line quad
+#[repr(C)]
+#[derive(Copy, Clone, Debug, PartialEq, Pod, Zeroable)]
+pub struct LineSegment {
+    pub start: [f32; 3],
+    pub end: [f32; 3],
+    /// Normal of the surface the line lies on (for lit wireframes)
+    pub normal: [f32; 3],
+    /// Linear RGBA
+    pub color: [f32; 4],
+}
+
+impl LineSegment {
+    /// Segment between two vertices, with the first one's normal and color
+    pub fn between(a: &Vertex, b: &Vertex) -> Self {
+        Self {
+            start: a.position,
+            end: b.position,
+            normal: a.normal,
+            color: a.color,
+        }
+    }
+
+    /// Instance buffer layout description for wgpu
+    pub fn desc() -> wgpu::VertexBufferLayout<'static> {
+        const ATTRIBUTES: [wgpu::VertexAttribute; 4] = wgpu::vertex_attr_array![
+            0 => Float32x3,
+            1 => Float32x3,
+            2 => Float32x3,
+            3 => Float32x4,
+        ];
+        wgpu::VertexBufferLayout {
+            array_stride: std::mem::size_of::<LineSegment>() as wgpu::BufferAddress,
+            step_mode: wgpu::VertexStepMode::Instance,
+            attributes: &ATTRIBUTES,
+        }
+    }
+}
+
+/// Every triangle edge of an indexed mesh once (wireframe)
+pub fn triangle_edge_segments(vertices: &[Vertex], indices: &[u32]) -> Vec<LineSegment> {
+    let mut seen = HashSet::new();
+    let mut segments = Vec::new();
+    for tri in indices.chunks_exact(3) {
+        for (a, b) in [(tri[0], tri[1]), (tri[1], tri[2]), (tri[2], tri[0])] {
+            let (Some(va), Some(vb)) = (vertices.get(a as usize), vertices.get(b as usize)) else {
+                continue;
+            };
+            if seen.insert((a.min(b), a.max(b))) {
+                segments.push(LineSegment::between(va, vb));
+            }
+        }
+    }
+    segments
+}
+
+/// Segments of a line list (index pairs)
+pub fn line_list_segments(vertices: &[Vertex], indices: &[u32]) -> Vec<LineSegment> {
+    indices
+        .chunks_exact(2)
+        .filter_map(|pair| {
+            let a = vertices.get(pair[0] as usize)?;
+            let b = vertices.get(pair[1] as usize)?;
+            Some(LineSegment::between(a, b))
+        })
+        .collect()
+}
+
+/// Clip-space corners of the quad drawn for a segment, in `QUAD_CORNERS`
+/// order, as computed by the line shader
+///
+/// The segment is first clipped to the near plane (z >= 0); a segment wholly
+/// behind it yields a degenerate quad.
+pub fn expand_segment(start: Vec4, end: Vec4, width_px: f32, viewport: Vec2) -> [Vec4; 6] {
+    if start.z < 0.0 && end.z < 0.0 {
+        return [Vec4::ZERO; 6];
+    }
+    let start = if start.z < 0.0 { start.lerp(end, start.z / (start.z - end.z)) } else { start };
+    let end = if end.z < 0.0 { end.lerp(start, end.z / (end.z - start.z)) } else { end };
+
+    let half_viewport = viewport * 0.5;
+    let along = (end.truncate().truncate() / end.w - start.truncate().truncate() / start.w) * half_viewport;
+    let dir = along.try_normalize().unwrap_or(Vec2::X);
+    let half_width = width_px * 0.5;
+
+    QUAD_CORNERS.map(|[end_weight, side]| {
+        let mut clip = if end_weight > 0.5 { end } else { start };
+        // Sideways by half the width, outwards by half the width (square cap)
+        let offset = (dir.perp() * side + dir * (end_weight * 2.0 - 1.0)) * half_width;
+        let shift = offset / half_viewport * clip.w;
+        clip.x += shift.x;
+        clip.y += shift.y;
+        clip
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::renderer::{generate_test_cube, test_renderer, RenderMode};
+
+    /// Pixel position of a clip-space point
+    fn to_pixels(clip: Vec4, viewport: Vec2) -> Vec2 {
+        (clip.truncate().truncate() / clip.w + 1.0) * 0.5 * viewport
+    }
+
+    fn triangle_area(a: Vec2, b: Vec2, c: Vec2) -> f32 {
+        ((b - a).perp_dot(c - a) * 0.5).abs()
+    }
+
+    #[test]
+    fn test_quad_expansion_has_two_triangles_per_segment_at_width() {
+        // The test cube: 6 faces of 2 triangles sharing a diagonal
+        let (vertices, indices) = generate_test_cube();
+        let segments = triangle_edge_segments(&vertices, &indices);
+        assert_eq!(segments.len(), 6 * 5);
+        assert_eq!(line_list_segments(&vertices, &[0, 1, 2, 3]).len(), 2);
+
+        // A diagonal segment 100 px long on a 400 x 200 viewport
+        let viewport = Vec2::new(400.0, 200.0);
+        let from_pixels = |p: Vec2, w: f32| ((p / viewport * 2.0 - 1.0) * w).extend(0.5 * w).extend(w);
+        let (a, b) = (Vec2::new(100.0, 100.0), Vec2::new(160.0, 180.0));
+        for width in [1.0, 3.0, 8.0] {
+            let corners = expand_segment(from_pixels(a, 1.0), from_pixels(b, 2.0), width, viewport);
+            assert_eq!(corners.len(), VERTICES_PER_SEGMENT as usize);
+
+            let pixels: Vec<Vec2> = corners.iter().map(|&c| to_pixels(c, viewport)).collect();
+            let triangles: Vec<[Vec2; 3]> = pixels.chunks_exact(3).map(|t| [t[0], t[1], t[2]]).collect();
+            assert_eq!(triangles.len(), 2);
+
+            // The quad is `width` across, square-capped, and the two
+            // triangles cover it exactly
+            let dir = (b - a).normalize();
+            let across = pixels[5] - pixels[0];
+            assert!((across.length() - width).abs() < 1e-3, "width {}: {}", width, across.length());
+            assert!(across.dot(dir).abs() < 1e-3);
+            assert!(((pixels[1] - pixels[0]).length() - (100.0 + width)).abs() < 1e-3);
+            let area: f32 = triangles.iter().map(|t| triangle_area(t[0], t[1], t[2])).sum();
+            assert!((area - (100.0 + width) * width).abs() < 1e-2, "area {}", area);
+        }
+
+        // A segment behind the camera collapses
+        let behind = Vec4::new(0.0, 0.0, -1.0, 1.0);
+        assert_eq!(expand_segment(behind, behind, 4.0, viewport), [Vec4::ZERO; 6]);
+    }
+
+    #[test]
+    fn test_wider_lines_cover_more_pixels() {
+        let Some(mut renderer) = test_renderer(64, 64) else {
+            eprintln!("No GPU adapter available, skipping");
+            return;
+        };
+        renderer.set_render_mode(RenderMode::Wireframe).unwrap();
+
+        let background = |pixels: &[u8]| pixels.chunks_exact(4).filter(|p| p[..3] == pixels[..3]).count();
+        let thin = renderer.render_frame().unwrap();
+        renderer.set_line_width(4.0).unwrap();
+        let thick = renderer.render_frame().unwrap();
+        assert!(background(&thick) < background(&thin));
+        assert!(renderer.set_line_width(0.0).is_err());
+        assert_eq!(renderer.line_width(), 4.0);
+    }
+}
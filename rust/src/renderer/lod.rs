@@ -0,0 +1,185 @@
+//! Level of Detail
+//!
+//! Distance-based switching between decimated copies of each element. The
+//! index buffer holds the full-detail mesh followed by coarser levels of its
+//! elements; every frame the fill pass draws one level per element, picked
+//! by how large the element's bounding sphere appears on screen. Wireframes
+//! and edges always show full detail.
+
+use super::camera::Camera;
+use glam::Vec3;
+use std::ops::Range;
+
+/// Share of triangles kept by each coarse level, finest first
+pub const LOD_LEVEL_RATIOS: [f32; 2] = [0.25, 0.05];
+
+/// Default projected sizes in pixels below which each coarse level is used
+pub const DEFAULT_LOD_THRESHOLDS_PX: [f32; 2] = [200.0, 50.0];
+
+/// Bounds and detail levels of one element
+#[derive(Debug, Clone, PartialEq)]
+pub struct ElementLod {
+    pub center: Vec3,
+    pub radius: f32,
+    /// Triangle range of each level, full detail first
+    pub levels: Vec<Range<u32>>,
+}
+
+/// Per-element level selection for the current camera
+#[derive(Debug, Clone, PartialEq)]
+pub struct LodSelection {
+    elements: Vec<ElementLod>,
+    thresholds_px: Vec<f32>,
+}
+
+impl LodSelection {
+    /// Select between the levels of `elements`: level `k` is used below
+    /// `thresholds_px[k - 1]`, which must be positive and decreasing
+    pub fn new(elements: Vec<ElementLod>, thresholds_px: Vec<f32>) -> Result<Self, String> {
+        validate_thresholds(&thresholds_px)?;
+        Ok(Self { elements, thresholds_px })
+    }
+
+    /// Number of elements
+    pub fn element_count(&self) -> usize {
+        self.elements.len()
+    }
+
+    /// Level drawn for an element as seen by `camera`
+    pub fn level(&self, element: usize, camera: &Camera, viewport_height: f32) -> usize {
+        let lod = &self.elements[element];
+        let size = camera.projected_size_px(lod.center, lod.radius, viewport_height);
+        let coarser = self.thresholds_px.iter().take_while(|&&threshold| size < threshold).count();
+        coarser.min(lod.levels.len().saturating_sub(1))
+    }
+
+    /// Index buffer range to draw for each element as seen by `camera`
+    pub fn index_ranges(&self, camera: &Camera, viewport_height: f32) -> Vec<Range<u32>> {
+        (0..self.elements.len())
+            .map(|k| {
+                let levels = &self.elements[k].levels;
+                let triangles = levels.get(self.level(k, camera, viewport_height)).cloned().unwrap_or(0..0);
+                triangles.start * 3..triangles.end * 3
+            })
+            .collect()
+    }
+}
+
+/// Check LOD thresholds: at most one per coarse level, positive and
+/// decreasing
+pub fn validate_thresholds(thresholds_px: &[f32]) -> Result<(), String> {
+    if thresholds_px.len() > LOD_LEVEL_RATIOS.len() {
+        return Err(format!(
+            "At most {} LOD thresholds, got {}",
+            LOD_LEVEL_RATIOS.len(),
+            thresholds_px.len()
+        ));
+    }
+    let positive = thresholds_px.iter().all(|t| t.is_finite() && *t > 0.0);
+    if !positive || thresholds_px.windows(2).any(|w| w[1] >= w[0]) {
+        return Err(format!("Invalid LOD thresholds: {:?} (positive, decreasing)", thresholds_px));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bim::geometry::Mesh;
+    use crate::bim::{BoundingBox, ElementInfo, ModelMesh};
+    use crate::renderer::test_renderer;
+
+    #[test]
+    fn test_far_camera_switches_element_to_coarse_level() {
+        // A finely tessellated, bumpy 4 m panel as one element
+        let n = 32u32;
+        let mut panel = Mesh::new();
+        for j in 0..=n {
+            for i in 0..=n {
+                let (x, y) = (i as f32 / n as f32 * 4.0 - 2.0, j as f32 / n as f32 * 4.0 - 2.0);
+                panel.add_vertex(x, y, 0.05 * (x * 3.0).sin() * (y * 2.0).cos());
+                panel.add_normal(0.0, 0.0, 1.0);
+                panel.add_color(0.7, 0.7, 0.7, 1.0);
+            }
+        }
+        for j in 0..n {
+            for i in 0..n {
+                let k = j * (n + 1) + i;
+                panel.add_triangle(k, k + 1, k + n + 1);
+                panel.add_triangle(k + 1, k + n + 2, k + n + 1);
+            }
+        }
+        let bounds = panel.bounding_box().unwrap();
+        let mesh = ModelMesh {
+            elements: vec![ElementInfo {
+                id: 1,
+                element_type: "Wall".to_string(),
+                name: "Panel".to_string(),
+                global_id: "panel".to_string(),
+                bounds: BoundingBox { min: bounds.min, max: bounds.max },
+                triangle_start: 0,
+                triangle_count: panel.triangle_count() as u32,
+            }],
+            vertices: panel.vertices,
+            normals: panel.normals,
+            colors: panel.colors,
+            indices: panel.indices,
+            bounds: Some(bounds),
+        };
+
+        // Levels follow the mesh's own triangles, each coarser than the last
+        let lod = mesh.lod_levels(&LOD_LEVEL_RATIOS);
+        let levels: Vec<Range<u32>> = std::iter::once(0..mesh.elements[0].triangle_count)
+            .chain(lod.element_levels[0].iter().cloned())
+            .collect();
+        assert_eq!(levels[1].start as usize, mesh.indices.len() / 3);
+        assert_eq!(levels[2].end as usize, (mesh.indices.len() + lod.indices.len()) / 3);
+        assert!(levels.windows(2).all(|w| w[1].len() < w[0].len()), "{:?}", levels);
+
+        let (min, max) = (Vec3::from(bounds.min), Vec3::from(bounds.max));
+        let selection = LodSelection::new(
+            vec![ElementLod { center: (min + max) / 2.0, radius: (max - min).length() / 2.0, levels: levels.clone() }],
+            DEFAULT_LOD_THRESHOLDS_PX.to_vec(),
+        )
+        .unwrap();
+        let mut camera = Camera::default();
+        camera.set_target([0.0, 0.0, 0.0]);
+        let level_at = |camera: &mut Camera, distance: f32| {
+            camera.set_position([0.0, 0.0, distance]);
+            selection.level(0, camera, 512.0)
+        };
+        assert_eq!(level_at(&mut camera, 5.0), 0);
+        assert_eq!(level_at(&mut camera, 30.0), 1);
+        assert_eq!(level_at(&mut camera, 500.0), 2);
+        let far = selection.index_ranges(&camera, 512.0);
+        assert_eq!(far, vec![levels[2].start * 3..levels[2].end * 3]);
+
+        assert!(LodSelection::new(Vec::new(), vec![50.0, 200.0]).is_err());
+        assert!(LodSelection::new(Vec::new(), vec![300.0, 100.0, 10.0]).is_err());
+
+        // The renderer draws the panel at either distance
+        let Some(mut renderer) = test_renderer(64, 64) else {
+            eprintln!("No GPU adapter available, skipping");
+            return;
+        };
+        renderer
+            .load_mesh_with_lod(
+                &[mesh.vertices.as_slice(), &lod.vertices].concat(),
+                &[mesh.normals.as_slice(), &lod.normals].concat(),
+                &[mesh.colors.as_slice(), &lod.colors].concat(),
+                &[mesh.indices.as_slice(), &lod.indices].concat(),
+                mesh.indices.len() / 3,
+                selection,
+            )
+            .unwrap();
+        renderer.set_element_ranges(vec![levels[0].clone()]).unwrap();
+        // Up close and a few pixels across (coarsest level)
+        for distance in [5.0, 60.0] {
+            renderer.update_camera([0.0, 0.0, distance], [0.0, 0.0, 0.0]);
+            let pixels = renderer.render_frame().unwrap();
+            assert!(pixels.chunks_exact(4).any(|p| p[..3] != pixels[..3]), "panel not drawn at {}", distance);
+        }
+        let scene_lod = renderer.scene.as_ref().unwrap().lod.as_ref().unwrap();
+        assert_eq!(scene_lod.level(0, &renderer.camera, 64.0), 2);
+    }
+}
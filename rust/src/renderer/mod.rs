@@ -3,26 +3,123 @@
 //! High-performance 3D rendering using wgpu (WebGPU/Vulkan/Metal).
 //! Handles scene rendering, camera management, and GPU resource management.
 
+pub mod annotation;
+pub mod bvh;
 pub mod camera;
+pub mod frame_loop;
+pub mod fxaa;
 pub mod gpu;
+pub mod lines;
+pub mod lod;
+pub mod occlusion;
 pub mod overlay;
 pub mod pipeline;
+pub mod points;
 pub mod scene;
+pub mod section;
+pub mod small_objects;
 pub mod vertex;
+pub mod walkthrough;
 
-pub use camera::{Camera, ray_aabb_intersect};
-pub use gpu::GpuContext;
-pub use overlay::DrawingOverlay;
-pub use pipeline::{RenderMode, RenderPipeline};
-pub use scene::SceneRenderer;
-pub use vertex::{generate_test_cube, Vertex};
+pub use annotation::{Annotation, AnnotationLayer};
+pub use bvh::Bvh;
+pub use camera::{Camera, CameraState, Frustum, NavMode, Projection, ray_aabb_intersect, ray_mesh_intersect, ray_triangle_intersect};
+pub use frame_loop::{render_frame_async, FrameLoop, FrameState, FrameTicket};
+pub use fxaa::FxaaPass;
+pub use gpu::{GpuCapabilities, GpuContext, LimitsTier};
+pub use lines::{LineSegment, DEFAULT_LINE_WIDTH_PX, MAX_LINE_WIDTH_PX};
+pub use lod::{ElementLod, LodSelection, DEFAULT_LOD_THRESHOLDS_PX, LOD_LEVEL_RATIOS};
+pub use occlusion::OcclusionCulling;
+pub use overlay::{DrawingOverlay, OverlaySampling};
+pub use pipeline::{DepthBias, DrawPass, RenderMode, RenderPipeline, DEFAULT_OVERLAY_DEPTH_BIAS, MSAA_SAMPLE_COUNT};
+pub use points::{CloudPoint, PointCloud, DEFAULT_POINT_SIZE_PX, MAX_POINT_SIZE_PX};
+pub use scene::{LightingConfig, SceneRenderer, ToneMapping};
+pub use small_objects::SmallObjectCulling;
+pub use section::{box_fully_clipped, section_plane_at_hit, section_plane_axis, section_plane_from_ray, Axis, SectionSweep};
+pub use vertex::{generate_test_cube, vertices_from_arrays, Vertex};
+pub use walkthrough::{sequence_frame_name, CameraPath};
+
+use glam::Mat4;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::ops::Range;
+use std::sync::Mutex;
+
+/// A saved view: where the camera is and how the scene is lit
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Viewpoint {
+    pub camera: CameraState,
+    #[serde(default)]
+    pub lighting: LightingConfig,
+}
+
+/// Scene settings kept while the GPU is released (see `Renderer::release`)
+#[derive(Clone, Copy)]
+struct SceneSettings {
+    width: u32,
+    height: u32,
+    light: scene::LightUniform,
+    section_plane: scene::SectionPlaneUniform,
+    clip_box: scene::ClipBoxUniform,
+    render_mode: RenderMode,
+    srgb_colors: bool,
+}
 
 /// Renderer state and configuration
 pub struct Renderer {
     pub gpu: GpuContext,
     pub scene: Option<SceneRenderer>,
     pub camera: Camera,
+    /// Camera bookmark to return to (see `go_home`), kept across model
+    /// changes
+    home_view: Option<CameraState>,
     pub initialized: bool,
+    /// 2D drawing overlays by ID
+    pub overlays: HashMap<String, DrawingOverlay>,
+    overlay_bind_group_layout: Option<wgpu::BindGroupLayout>,
+    /// Skip elements hidden behind others (when the device supports queries)
+    occlusion_culling: bool,
+    /// Triangle range of each element in the loaded mesh
+    element_ranges: Vec<Range<u32>>,
+    /// Bounds (min, max) of each element in the loaded mesh
+    element_bounds: Vec<([f32; 3], [f32; 3])>,
+    /// Elements never culled for their size (selected, isolated)
+    pinned_elements: HashSet<usize>,
+    /// Projected size in pixels below which elements are skipped (None
+    /// draws every element)
+    small_object_min_px: Option<f32>,
+    /// Measurement markup drawn over the model
+    annotations: AnnotationLayer,
+    /// Depth bias of annotations and other geometry on model surfaces
+    overlay_depth_bias: DepthBias,
+    /// Width of wireframe, edge and annotation lines in pixels
+    line_width: f32,
+    /// Point cloud overlays by ID
+    point_clouds: HashMap<String, PointCloud>,
+    /// Size of point cloud points in pixels
+    point_size: f32,
+    /// Smooth edges with the FXAA post-process
+    fxaa: bool,
+    /// Samples per pixel of the scene pass (1 = no MSAA)
+    msaa_samples: u32,
+    /// Frames rendered without blocking (see `request_frame`)
+    frame_loop: FrameLoop,
+    /// Bumped by every change to what the scene shows (see `mark_dirty`)
+    scene_version: u64,
+    /// Scene version and view-projection of the frame in the scene's
+    /// persistent pixel buffer
+    rendered: Mutex<Option<(u64, Mat4)>>,
+    /// Render every frame asked for; when off, unchanged frames are served
+    /// from the last render
+    continuous_rendering: bool,
+    /// Settings of the scene dropped by `release`, restored by `reinitialize`
+    released: Option<SceneSettings>,
+}
+
+impl Default for Renderer {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl Renderer {
@@ -32,7 +129,27 @@ impl Renderer {
             gpu: GpuContext::new(),
             scene: None,
             camera: Camera::default(),
+            home_view: None,
             initialized: false,
+            overlays: HashMap::new(),
+            overlay_bind_group_layout: None,
+            occlusion_culling: true,
+            element_ranges: Vec::new(),
+            element_bounds: Vec::new(),
+            pinned_elements: HashSet::new(),
+            small_object_min_px: None,
+            annotations: AnnotationLayer::default(),
+            overlay_depth_bias: DepthBias::default(),
+            line_width: DEFAULT_LINE_WIDTH_PX,
+            point_clouds: HashMap::new(),
+            point_size: DEFAULT_POINT_SIZE_PX,
+            fxaa: false,
+            msaa_samples: MSAA_SAMPLE_COUNT,
+            frame_loop: FrameLoop::default(),
+            scene_version: 0,
+            rendered: Mutex::new(None),
+            continuous_rendering: true,
+            released: None,
         }
     }
 
@@ -48,31 +165,231 @@ impl Renderer {
 
     /// Initialize scene renderer with given dimensions
     pub fn init_scene(&mut self, width: u32, height: u32) -> Result<(), String> {
-        let device = self.gpu.device().ok_or("GPU not initialized")?;
-        let wireframe_supported = self.gpu.wireframe_supported();
+        let limits = self.gpu.limits().ok_or("GPU not initialized")?;
+        let max_dimension = limits.max_texture_dimension_2d;
+        if width == 0 || height == 0 || width > max_dimension || height > max_dimension {
+            return Err(format!(
+                "Invalid scene size: {}x{} (max {})",
+                width, height, max_dimension
+            ));
+        }
+        let overlay_depth_bias = self.overlay_depth_bias;
+        let line_width = self.line_width;
+        let point_size = self.point_size;
+        let fxaa = self.fxaa;
+        let msaa_samples = self.msaa_samples;
 
-        let mut scene = SceneRenderer::new(width, height);
-        scene.initialize_with_features(device, wireframe_supported);
+        let scene = self.gpu.with_error_scope("Scene initialization", |device| {
+            let mut scene = SceneRenderer::new(width, height);
+            scene.msaa_samples = msaa_samples;
+            scene.initialize(device);
+            scene.line_width_px = line_width;
+            scene.point_size_px = point_size;
+            scene.set_fxaa(device, fxaa);
+            if let Some(pipeline) = scene.pipeline.as_mut() {
+                if pipeline.overlay_depth_bias() != overlay_depth_bias {
+                    pipeline.set_overlay_depth_bias(device, overlay_depth_bias);
+                }
+            }
 
-        // Upload test cube
-        let (vertices, indices) = generate_test_cube();
-        scene.upload_mesh(device, &vertices, &indices);
+            // Upload test cube
+            let (vertices, indices) = generate_test_cube();
+            scene.upload_mesh(device, &vertices, &indices);
+            scene
+        })?;
 
         self.scene = Some(scene);
+        self.scene_version += 1;
+        self.frame_loop.cancel();
         self.camera.set_aspect_ratio(width as f32 / height as f32);
         self.initialized = true;
+        self.upload_annotations()?;
+        self.upload_point_clouds()?;
+        self.upload_overlays()?;
 
         Ok(())
     }
 
+    /// Release all GPU resources (e.g. while the app is in the background
+    /// and the OS reclaims GPU memory), keeping the camera and settings
+    ///
+    /// Rendering fails until `reinitialize`; the mesh and drawing overlays
+    /// must then be uploaded again (annotations and point clouds are kept).
+    pub fn release(&mut self) {
+        if let Some(scene) = self.scene.take() {
+            self.released = Some(SceneSettings {
+                width: scene.width,
+                height: scene.height,
+                light: scene.light_uniform,
+                section_plane: scene.section_plane_uniform,
+                clip_box: scene.clip_box_uniform,
+                render_mode: scene.render_mode,
+                srgb_colors: scene.srgb_colors,
+            });
+        }
+        self.frame_loop.cancel();
+        self.overlays.clear();
+        self.overlay_bind_group_layout = None;
+        self.element_ranges.clear();
+        self.gpu.release();
+        self.initialized = false;
+        self.scene_version += 1;
+    }
+
+    /// Whether GPU resources were released and not yet recreated
+    pub fn is_released(&self) -> bool {
+        self.released.is_some()
+    }
+
+    /// Recreate the GPU context and scene after `release`, with the size
+    /// and settings the scene had
+    pub async fn reinitialize(&mut self) -> Result<(), String> {
+        let settings = self.released.ok_or("Renderer was not released")?;
+        self.initialize().await?;
+        self.init_scene(settings.width, settings.height)?;
+
+        let scene = self.scene.as_mut().ok_or("Scene not initialized")?;
+        scene.light_uniform = settings.light;
+        scene.section_plane_uniform = settings.section_plane;
+        scene.clip_box_uniform = settings.clip_box;
+        scene.render_mode = settings.render_mode;
+        scene.srgb_colors = settings.srgb_colors;
+        if let Some(queue) = self.gpu.queue() {
+            scene.update_light(queue);
+            scene.update_section_plane(queue);
+            scene.update_clip_box(queue);
+        }
+        self.upload_annotations()?;
+        self.upload_point_clouds()?;
+        self.released = None;
+        Ok(())
+    }
+
+    /// The scene, or why there is none
+    fn live_scene(&self) -> Result<&SceneRenderer, String> {
+        match (&self.scene, &self.released) {
+            (Some(scene), _) => Ok(scene),
+            (None, Some(_)) => Err("Renderer GPU resources are released; reinitialize it first".to_string()),
+            (None, None) => Err("Scene not initialized".to_string()),
+        }
+    }
+
     /// Render a frame and return pixel data as RGBA
+    ///
+    /// Without continuous rendering, an unchanged frame is a copy of the last
+    /// one instead of a new render.
     pub fn render_frame(&self) -> Result<Vec<u8>, String> {
-        let device = self.gpu.device().ok_or("GPU not initialized")?;
+        self.live_scene()?;
+        if !self.continuous_rendering && !self.frame_changed() {
+            let scene = self.scene.as_ref().ok_or("Scene not initialized")?;
+            return Ok(scene.last_frame(<[u8]>::to_vec));
+        }
+        self.with_frame(<[u8]>::to_vec)
+    }
+
+    /// Render a frame unless continuous rendering is off and nothing changed
+    /// since the last one (None: keep showing the previous frame)
+    pub fn render_frame_if_changed(&self) -> Result<Option<Vec<u8>>, String> {
+        self.live_scene()?;
+        if !self.continuous_rendering && !self.frame_changed() {
+            return Ok(None);
+        }
+        self.with_frame(<[u8]>::to_vec).map(Some)
+    }
+
+    /// Whether the scene or camera changed since the last frame rendered
+    /// through `render_frame` or `with_frame`
+    pub fn frame_changed(&self) -> bool {
+        let current = (self.scene_version, self.camera.view_projection_matrix());
+        *self.rendered.lock().unwrap() != Some(current)
+    }
+
+    /// Force the next frame to render, for changes the renderer cannot see
+    pub fn mark_dirty(&mut self) {
+        self.scene_version += 1;
+    }
+
+    /// Render every frame (default), or only frames where something changed
+    pub fn set_continuous_rendering(&mut self, enabled: bool) {
+        self.continuous_rendering = enabled;
+    }
+
+    pub fn continuous_rendering(&self) -> bool {
+        self.continuous_rendering
+    }
+
+    /// Render a frame straight into a caller-owned RGBA buffer of
+    /// width * height * 4 bytes (e.g. one reused across frames)
+    pub fn render_frame_into(&self, out: &mut [u8]) -> Result<(), String> {
+        let scene = self.live_scene()?;
+        let queue = self.gpu.queue().ok_or("GPU queue not initialized")?;
+        if out.len() != scene.frame_len() {
+            return Err(format!(
+                "Frame buffer is {} bytes, expected {} for {}x{}",
+                out.len(),
+                scene.frame_len(),
+                scene.width,
+                scene.height
+            ));
+        }
+
+        self.gpu.with_error_scope("Render", |device| {
+            scene.render_frame_into(device, queue, &self.camera, out)
+        })
+    }
+
+    /// Render a frame into the scene's persistent pixel buffer and pass it
+    /// to `f` (no per-frame allocation)
+    pub fn with_frame<T>(&self, f: impl FnOnce(&[u8]) -> T) -> Result<T, String> {
+        let scene = self.live_scene()?;
+        let queue = self.gpu.queue().ok_or("GPU queue not initialized")?;
+
+        let result = self.gpu.with_error_scope("Render", |device| {
+            scene.with_frame(device, queue, &self.camera, f)
+        })?;
+        *self.rendered.lock().unwrap() = Some((self.scene_version, self.camera.view_projection_matrix()));
+        Ok(result)
+    }
+
+    /// Request a frame without waiting for it; poll the ticket with
+    /// `poll_frame` (or use `render_frame_async`)
+    pub fn request_frame(&mut self) -> Result<FrameTicket, String> {
+        self.live_scene()?;
         let queue = self.gpu.queue().ok_or("GPU queue not initialized")?;
         let scene = self.scene.as_ref().ok_or("Scene not initialized")?;
+        let (frame_loop, camera) = (&mut self.frame_loop, &self.camera);
 
-        let pixels = scene.render_frame(device, queue, &self.camera);
-        Ok(pixels)
+        self.gpu.with_error_scope("Render", |device| {
+            frame_loop.request(scene, device, queue, camera)
+        })
+    }
+
+    /// Pixels (RGBA) for a frame request, or None while it is still rendering
+    pub fn poll_frame(&mut self, ticket: FrameTicket) -> Result<Option<Vec<u8>>, String> {
+        self.live_scene()?;
+        let queue = self.gpu.queue().ok_or("GPU queue not initialized")?;
+        let scene = self.scene.as_ref().ok_or("Scene not initialized")?;
+        let (frame_loop, camera) = (&mut self.frame_loop, &self.camera);
+
+        let pixels = self.gpu.with_error_scope("Render", |device| {
+            frame_loop.poll(ticket, scene, device, queue, camera)
+        })??;
+        Ok(pixels.map(|p| p.to_vec()))
+    }
+
+    /// Cancel all outstanding frame requests
+    pub fn cancel_frames(&mut self) {
+        self.frame_loop.cancel();
+    }
+
+    /// GPU memory held by the scene and the frame loop, in bytes
+    pub fn gpu_buffer_bytes(&self) -> u64 {
+        self.scene.as_ref().map_or(0, SceneRenderer::gpu_bytes) + self.frame_loop.buffer_bytes()
+    }
+
+    /// Where the non-blocking frame loop is
+    pub fn frame_state(&self) -> FrameState {
+        self.frame_loop.state()
     }
 
     /// Update camera position/rotation
@@ -81,9 +398,13 @@ impl Renderer {
         self.camera.set_target(target);
     }
 
-    /// Orbit camera around target
+    /// Orbit camera around target, or look around in place in the walk
+    /// and fly navigation modes
     pub fn orbit_camera(&mut self, delta_x: f32, delta_y: f32) {
-        self.camera.orbit(delta_x, delta_y);
+        match self.camera.nav_mode() {
+            NavMode::Orbit => self.camera.orbit(delta_x, delta_y),
+            NavMode::Walk | NavMode::Fly => self.camera.look(delta_x, delta_y),
+        }
     }
 
     /// Zoom camera
@@ -104,44 +425,456 @@ impl Renderer {
         colors: &[f32],
         indices: &[u32],
     ) -> Result<(), String> {
-        let device = self.gpu.device().ok_or("GPU not initialized")?;
+        self.upload(vertices, normals, colors, indices, indices.len() / 3, None)
+    }
+
+    /// Load a mesh whose first `drawn_triangles` are the full-detail model,
+    /// followed by the coarse element levels `lod` switches between
+    pub fn load_mesh_with_lod(
+        &mut self,
+        vertices: &[f32],
+        normals: &[f32],
+        colors: &[f32],
+        indices: &[u32],
+        drawn_triangles: usize,
+        lod: LodSelection,
+    ) -> Result<(), String> {
+        if drawn_triangles * 3 > indices.len() {
+            return Err(format!("Mesh has fewer than {} triangles", drawn_triangles));
+        }
+        self.upload(vertices, normals, colors, indices, drawn_triangles, Some(lod))
+    }
+
+    fn upload(
+        &mut self,
+        vertices: &[f32],
+        normals: &[f32],
+        colors: &[f32],
+        indices: &[u32],
+        drawn_triangles: usize,
+        lod: Option<LodSelection>,
+    ) -> Result<(), String> {
+        let limits = self.gpu.limits().ok_or("GPU not initialized")?;
+        let vertex_bytes = (vertices.len() / 3 * std::mem::size_of::<Vertex>()) as u64;
+        let index_bytes = std::mem::size_of_val(indices) as u64;
+        let largest = vertex_bytes.max(index_bytes);
+        if largest > limits.max_buffer_size {
+            return Err(format!(
+                "Mesh too large for this device: {} byte buffer (max {})",
+                largest, limits.max_buffer_size
+            ));
+        }
+        let scene = self.scene.as_mut().ok_or("Scene not initialized")?;
+        self.scene_version += 1;
+
+        // Element ranges describe the previous mesh
+        scene.occlusion = None;
+        scene.small_objects = None;
+        self.element_ranges.clear();
+        self.element_bounds.clear();
+        self.pinned_elements.clear();
+        scene.lod = lod;
+
+        self.gpu.with_error_scope("Mesh upload", |device| {
+            let vertices = vertices_from_arrays(vertices, normals, colors, scene.srgb_colors);
+            scene.upload_mesh_with_levels(device, &vertices, indices, drawn_triangles * 3);
+        })
+    }
+
+    /// Set the triangle range of each element in the loaded mesh, enabling
+    /// per-element occlusion culling when it is on and supported
+    pub fn set_element_ranges(&mut self, ranges: Vec<Range<u32>>) -> Result<(), String> {
+        self.element_ranges = ranges;
+        self.update_occlusion()?;
+        self.update_small_objects()
+    }
+
+    /// Set the bounds (min, max) of each element in the loaded mesh, in the
+    /// order of the element ranges, for small object culling
+    pub fn set_element_bounds(&mut self, bounds: Vec<([f32; 3], [f32; 3])>) -> Result<(), String> {
+        self.element_bounds = bounds;
+        self.update_small_objects()
+    }
+
+    /// Set the elements (by index into the element ranges) that are drawn
+    /// however small they are, e.g. the selected one
+    pub fn set_pinned_elements(&mut self, elements: Vec<usize>) -> Result<(), String> {
+        self.pinned_elements = elements.into_iter().collect();
+        self.update_small_objects()
+    }
+
+    /// Skip elements smaller on screen than `min_pixels` across, or draw
+    /// them all with None (kept across mesh loads)
+    pub fn set_small_object_culling(&mut self, min_pixels: Option<f32>) -> Result<(), String> {
+        if let Some(min_pixels) = min_pixels {
+            small_objects::validate_min_pixels(min_pixels)?;
+        }
+        self.small_object_min_px = min_pixels;
+        self.update_small_objects()
+    }
+
+    /// Current small object threshold in pixels, if culling is on
+    pub fn small_object_culling(&self) -> Option<f32> {
+        self.small_object_min_px
+    }
+
+    /// Turn occlusion culling on or off; returns whether it is active
+    pub fn set_occlusion_culling(&mut self, enabled: bool) -> Result<bool, String> {
+        self.occlusion_culling = enabled;
+        self.update_occlusion()?;
+        Ok(self.scene.as_ref().is_some_and(|s| s.occlusion.is_some()))
+    }
+
+    /// Elements (by index into the element ranges) that were fully hidden in
+    /// the last rendered frame
+    pub fn occluded_elements(&self) -> Vec<usize> {
+        self.scene
+            .as_ref()
+            .and_then(|s| s.occlusion.as_ref())
+            .map(|o| o.occluded_elements())
+            .unwrap_or_default()
+    }
+
+    /// Add a measurement annotation (points in local render coordinates),
+    /// returning its index
+    pub fn add_annotation(&mut self, annotation: Annotation) -> Result<usize, String> {
+        let index = self.annotations.add(annotation);
+        self.upload_annotations()?;
+        Ok(index)
+    }
+
+    /// Remove all measurement annotations
+    pub fn clear_annotations(&mut self) -> Result<(), String> {
+        self.annotations.clear();
+        self.upload_annotations()
+    }
+
+    /// Set the color of all annotations (RGBA, 0.0-1.0)
+    pub fn set_annotation_color(&mut self, color: [f32; 4]) -> Result<(), String> {
+        self.annotations.color = color;
+        self.upload_annotations()
+    }
+
+    /// Current annotations
+    pub fn annotations(&self) -> &AnnotationLayer {
+        &self.annotations
+    }
+
+    /// Set the depth bias of annotations and fills drawn on model surfaces
+    /// (kept across scene re-initialization)
+    pub fn set_overlay_depth_bias(&mut self, bias: DepthBias) -> Result<(), String> {
+        if let Some(pipeline) = self.scene.as_mut().and_then(|s| s.pipeline.as_mut()) {
+            self.scene_version += 1;
+            self.gpu.with_error_scope("Overlay depth bias", |device| {
+                pipeline.set_overlay_depth_bias(device, bias);
+            })?;
+        }
+        self.overlay_depth_bias = bias;
+        Ok(())
+    }
+
+    /// Current depth bias of overlays
+    pub fn overlay_depth_bias(&self) -> DepthBias {
+        self.overlay_depth_bias
+    }
+
+    /// Set the width of wireframe, edge and annotation lines in pixels
+    /// (kept across scene re-initialization)
+    pub fn set_line_width(&mut self, px: f32) -> Result<(), String> {
+        if !(px.is_finite() && px > 0.0 && px <= MAX_LINE_WIDTH_PX) {
+            return Err(format!("Invalid line width: {} (0 < width <= {})", px, MAX_LINE_WIDTH_PX));
+        }
+        if let Some(scene) = self.scene.as_mut() {
+            scene.line_width_px = px;
+            self.scene_version += 1;
+        }
+        self.line_width = px;
+        Ok(())
+    }
+
+    /// Current line width in pixels
+    pub fn line_width(&self) -> f32 {
+        self.line_width
+    }
+
+    /// Turn FXAA antialiasing on or off (kept across scene
+    /// re-initialization)
+    pub fn set_fxaa(&mut self, enabled: bool) -> Result<(), String> {
+        if let Some(scene) = self.scene.as_mut() {
+            self.gpu.with_error_scope("FXAA setup", |device| scene.set_fxaa(device, enabled))?;
+            self.scene_version += 1;
+        }
+        self.fxaa = enabled;
+        Ok(())
+    }
+
+    /// Whether FXAA antialiasing is on
+    pub fn fxaa(&self) -> bool {
+        self.fxaa
+    }
+
+    /// Set the MSAA samples per pixel: 1 (off) or 4 (kept across scene
+    /// re-initialization); fails if the device cannot multisample the
+    /// scene's targets
+    pub fn set_msaa_samples(&mut self, samples: u32) -> Result<(), String> {
+        if samples != 1 && samples != 4 {
+            return Err(format!("Unsupported MSAA sample count: {} (1 or 4)", samples));
+        }
+        if self.gpu.is_initialized() && !self.gpu.msaa_supported(samples) {
+            return Err(format!("{}x MSAA is not supported on this device", samples));
+        }
+        if let Some(scene) = self.scene.as_mut() {
+            self.gpu
+                .with_error_scope("MSAA setup", |device| scene.set_msaa_samples(device, samples))?;
+            self.scene_version += 1;
+        }
+        self.msaa_samples = samples;
+        self.upload_overlays()
+    }
+
+    /// MSAA samples per pixel (1 = off)
+    pub fn msaa_samples(&self) -> u32 {
+        self.msaa_samples
+    }
+
+    /// Add or replace a point cloud overlay
+    pub fn add_point_cloud(&mut self, id: &str, cloud: PointCloud) -> Result<(), String> {
+        self.point_clouds.insert(id.to_string(), cloud);
+        self.upload_point_clouds()
+    }
+
+    /// Remove a point cloud overlay
+    pub fn remove_point_cloud(&mut self, id: &str) -> Result<(), String> {
+        self.point_clouds
+            .remove(id)
+            .ok_or_else(|| format!("Point cloud '{}' not found", id))?;
+        self.upload_point_clouds()
+    }
+
+    /// A point cloud overlay by ID
+    pub fn point_cloud(&self, id: &str) -> Option<&PointCloud> {
+        self.point_clouds.get(id)
+    }
+
+    /// IDs of the point cloud overlays
+    pub fn point_cloud_ids(&self) -> Vec<String> {
+        let mut ids: Vec<String> = self.point_clouds.keys().cloned().collect();
+        ids.sort();
+        ids
+    }
+
+    /// Align a point cloud with the model (column-major 4x4 matrix)
+    pub fn set_point_cloud_transform(&mut self, id: &str, transform: [f32; 16]) -> Result<(), String> {
+        if transform.iter().any(|v| !v.is_finite()) {
+            return Err("Point cloud transform must be finite".to_string());
+        }
+        let cloud = self
+            .point_clouds
+            .get_mut(id)
+            .ok_or_else(|| format!("Point cloud '{}' not found", id))?;
+        cloud.transform = transform;
+        self.upload_point_clouds()
+    }
+
+    /// Show or hide a point cloud
+    pub fn set_point_cloud_visible(&mut self, id: &str, visible: bool) -> Result<(), String> {
+        let cloud = self
+            .point_clouds
+            .get_mut(id)
+            .ok_or_else(|| format!("Point cloud '{}' not found", id))?;
+        cloud.visible = visible;
+        self.upload_point_clouds()
+    }
+
+    /// Set the size of point cloud points in pixels (kept across scene
+    /// re-initialization)
+    pub fn set_point_size(&mut self, px: f32) -> Result<(), String> {
+        if !(px.is_finite() && px > 0.0 && px <= MAX_POINT_SIZE_PX) {
+            return Err(format!("Invalid point size: {} (0 < size <= {})", px, MAX_POINT_SIZE_PX));
+        }
+        if let Some(scene) = self.scene.as_mut() {
+            scene.point_size_px = px;
+            self.scene_version += 1;
+        }
+        self.point_size = px;
+        Ok(())
+    }
+
+    /// Current point size in pixels
+    pub fn point_size(&self) -> f32 {
+        self.point_size
+    }
+
+    /// Rebuild the scene's point buffer from the visible point clouds, if
+    /// there is a scene
+    fn upload_point_clouds(&mut self) -> Result<(), String> {
+        let Some(scene) = self.scene.as_mut() else {
+            return Ok(());
+        };
+        self.scene_version += 1;
+        let mut ids: Vec<&String> = self.point_clouds.keys().collect();
+        ids.sort();
+        let points: Vec<CloudPoint> = ids
+            .into_iter()
+            .map(|id| &self.point_clouds[id])
+            .filter(|cloud| cloud.visible)
+            .flat_map(|cloud| cloud.points(scene.srgb_colors))
+            .collect();
+        self.gpu.with_error_scope("Point cloud upload", |device| {
+            scene.upload_points(device, &points);
+        })
+    }
+
+    /// Rebuild the quads of visible drawing overlays in the scene, if there
+    /// is one
+    fn upload_overlays(&mut self) -> Result<(), String> {
+        let Some(scene) = self.scene.as_mut() else {
+            return Ok(());
+        };
+        self.scene_version += 1;
+        let mut ids: Vec<&String> = self.overlays.keys().collect();
+        ids.sort();
+        let drawings: Vec<&DrawingOverlay> = ids
+            .into_iter()
+            .map(|id| &self.overlays[id])
+            .filter(|drawing| drawing.visible)
+            .collect();
+        self.gpu.with_error_scope("Overlay upload", |device| {
+            scene.upload_overlays(device, &drawings);
+        })
+    }
+
+    /// Rebuild the annotation lines and fills in the scene, if there is one
+    fn upload_annotations(&mut self) -> Result<(), String> {
+        let Some(scene) = self.scene.as_mut() else {
+            return Ok(());
+        };
+        self.scene_version += 1;
+        let lines = self.annotations.mesh();
+        let fill = self.annotations.fill_mesh();
+        self.gpu.with_error_scope("Annotation upload", |device| {
+            scene.upload_annotations(device, &lines, &fill);
+        })
+    }
+
+    /// Rebuild the scene's occlusion queries for the current settings
+    fn update_occlusion(&mut self) -> Result<(), String> {
+        let enabled = self.occlusion_culling
+            && !self.element_ranges.is_empty()
+            && self.gpu.occlusion_queries_supported();
         let scene = self.scene.as_mut().ok_or("Scene not initialized")?;
+        scene.occlusion = None;
+        if enabled {
+            let ranges = &self.element_ranges;
+            scene.occlusion = Some(self.gpu.with_error_scope("Occlusion query setup", |device| {
+                OcclusionCulling::new(device, ranges)
+            })?);
+        }
+        Ok(())
+    }
 
-        scene.upload_mesh_from_arrays(device, vertices, normals, colors, indices);
+    /// Rebuild the scene's small object culling for the current settings;
+    /// off until the element ranges and bounds describe the same elements
+    fn update_small_objects(&mut self) -> Result<(), String> {
+        let Some(scene) = self.scene.as_mut() else {
+            return Ok(());
+        };
+        self.scene_version += 1;
+        scene.small_objects = match self.small_object_min_px {
+            Some(min_pixels)
+                if !self.element_ranges.is_empty() && self.element_bounds.len() == self.element_ranges.len() =>
+            {
+                Some(SmallObjectCulling::new(
+                    &self.element_bounds,
+                    &self.element_ranges,
+                    min_pixels,
+                    self.pinned_elements.clone(),
+                )?)
+            }
+            _ => None,
+        };
         Ok(())
     }
 
     /// Fit camera to bounding box
     pub fn fit_camera_to_bounds(&mut self, min: [f32; 3], max: [f32; 3]) {
-        // Calculate center and size
-        let center = [
-            (min[0] + max[0]) / 2.0,
-            (min[1] + max[1]) / 2.0,
-            (min[2] + max[2]) / 2.0,
-        ];
+        fit_camera(&mut self.camera, min, max);
+    }
+
+    /// Remember the current camera as the home view
+    pub fn set_home_view(&mut self) {
+        self.home_view = Some(self.camera.state());
+    }
+
+    /// The home view, if one was set
+    pub fn home_view(&self) -> Option<CameraState> {
+        self.home_view
+    }
 
-        let size = [
-            max[0] - min[0],
-            max[1] - min[1],
-            max[2] - min[2],
-        ];
+    /// Move the camera back to the home view
+    pub fn go_home(&mut self) -> Result<(), String> {
+        let home = self.home_view.ok_or("No home view set")?;
+        self.camera.set_state(home);
+        Ok(())
+    }
 
-        // Find the largest dimension
-        let max_size = size[0].max(size[1]).max(size[2]);
+    /// Camera position and target that `fit_camera_to_bounds` would move to
+    pub fn camera_state_fitting(&self, min: [f32; 3], max: [f32; 3]) -> CameraState {
+        let mut camera = self.camera.clone();
+        fit_camera(&mut camera, min, max);
+        camera.state()
+    }
 
-        // Calculate camera distance (1.5x the max size, minimum of 10 units)
-        let distance = (max_size * 1.5).max(10.0);
+    /// Render a mesh into a square PNG thumbnail
+    ///
+    /// Uses a temporary offscreen scene on the same GPU device, so the live
+    /// scene and camera are left untouched. The camera looks at `bounds`
+    /// from the default viewpoint; lighting and render mode follow the live
+    /// scene when there is one, antialiasing the renderer's setting.
+    pub fn render_thumbnail(
+        &self,
+        size: u32,
+        vertices: &[f32],
+        normals: &[f32],
+        colors: &[f32],
+        indices: &[u32],
+        bounds: Option<([f32; 3], [f32; 3])>,
+    ) -> Result<Vec<u8>, String> {
+        let device = self.gpu.device().ok_or("GPU not initialized")?;
+        let queue = self.gpu.queue().ok_or("GPU queue not initialized")?;
+
+        let max_size = device.limits().max_texture_dimension_2d;
+        if size == 0 || size > max_size {
+            return Err(format!("Invalid thumbnail size: {} (max {})", size, max_size));
+        }
 
-        // Set camera target to center
-        self.camera.set_target(center);
+        let mut camera = Camera::default();
+        camera.set_aspect_ratio(1.0);
+        if let Some((min, max)) = bounds {
+            fit_camera(&mut camera, min, max);
+        }
 
-        // Set camera distance
-        self.camera.set_distance(distance);
+        let pixels = self.gpu.with_error_scope("Thumbnail render", |device| {
+            let mut scene = SceneRenderer::new(size, size);
+            if let Some(live) = &self.scene {
+                scene.light_uniform = live.light_uniform;
+                scene.render_mode = live.render_mode;
+                scene.srgb_colors = live.srgb_colors;
+                scene.line_width_px = live.line_width_px;
+            }
+            scene.msaa_samples = self.msaa_samples;
+            scene.initialize(device);
+            scene.set_fxaa(device, self.fxaa);
+            scene.upload_mesh_from_arrays(device, vertices, normals, colors, indices);
+            scene.render_frame(device, queue, &camera)
+        })?;
+        encode_png(size, size, pixels)
     }
 
     /// Set directional light direction (will be normalized)
     pub fn set_light_direction(&mut self, x: f32, y: f32, z: f32) -> Result<(), String> {
         let scene = self.scene.as_mut().ok_or("Scene not initialized")?;
+        self.scene_version += 1;
         scene.set_light_direction(x, y, z);
         if let Some(queue) = self.gpu.queue() {
             scene.update_light(queue);
@@ -152,6 +885,7 @@ impl Renderer {
     /// Set directional light color (RGB, 0.0-1.0)
     pub fn set_light_color(&mut self, r: f32, g: f32, b: f32) -> Result<(), String> {
         let scene = self.scene.as_mut().ok_or("Scene not initialized")?;
+        self.scene_version += 1;
         scene.set_light_color(r, g, b);
         if let Some(queue) = self.gpu.queue() {
             scene.update_light(queue);
@@ -162,6 +896,7 @@ impl Renderer {
     /// Set directional light intensity (0.0+)
     pub fn set_light_intensity(&mut self, intensity: f32) -> Result<(), String> {
         let scene = self.scene.as_mut().ok_or("Scene not initialized")?;
+        self.scene_version += 1;
         scene.set_light_intensity(intensity);
         if let Some(queue) = self.gpu.queue() {
             scene.update_light(queue);
@@ -172,6 +907,7 @@ impl Renderer {
     /// Set ambient light color (RGB, 0.0-1.0)
     pub fn set_ambient_color(&mut self, r: f32, g: f32, b: f32) -> Result<(), String> {
         let scene = self.scene.as_mut().ok_or("Scene not initialized")?;
+        self.scene_version += 1;
         scene.set_ambient_color(r, g, b);
         if let Some(queue) = self.gpu.queue() {
             scene.update_light(queue);
@@ -179,9 +915,75 @@ impl Renderer {
         Ok(())
     }
 
+    /// Set exposure (linear multiplier applied before tone mapping, 0.0+)
+    pub fn set_exposure(&mut self, exposure: f32) -> Result<(), String> {
+        let scene = self.scene.as_mut().ok_or("Scene not initialized")?;
+        self.scene_version += 1;
+        scene.set_exposure(exposure);
+        if let Some(queue) = self.gpu.queue() {
+            scene.update_light(queue);
+        }
+        Ok(())
+    }
+
+    /// Set the tone mapping applied to lit colors
+    pub fn set_tone_mapping(&mut self, mode: ToneMapping) -> Result<(), String> {
+        let scene = self.scene.as_mut().ok_or("Scene not initialized")?;
+        self.scene_version += 1;
+        scene.set_tone_mapping(mode);
+        if let Some(queue) = self.gpu.queue() {
+            scene.update_light(queue);
+        }
+        Ok(())
+    }
+
+    /// Choose whether mesh colors are sRGB (linearized on upload, the default)
+    /// or used as-is; takes effect on the next mesh load (annotations and
+    /// point clouds now)
+    pub fn set_srgb_vertex_colors(&mut self, enabled: bool) -> Result<(), String> {
+        let scene = self.scene.as_mut().ok_or("Scene not initialized")?;
+        self.scene_version += 1;
+        scene.srgb_colors = enabled;
+        self.upload_annotations()?;
+        self.upload_point_clouds()
+    }
+
+    /// Current light settings
+    pub fn lighting(&self) -> Result<LightingConfig, String> {
+        let scene = self.scene.as_ref().ok_or("Scene not initialized")?;
+        Ok(scene.lighting())
+    }
+
+    /// Replace all light settings and upload them to the GPU
+    pub fn set_lighting(&mut self, config: &LightingConfig) -> Result<(), String> {
+        let scene = self.scene.as_mut().ok_or("Scene not initialized")?;
+        self.scene_version += 1;
+        scene.set_lighting(config);
+        if let Some(queue) = self.gpu.queue() {
+            scene.update_light(queue);
+        }
+        Ok(())
+    }
+
+    /// Camera and lighting as a saved view
+    pub fn viewpoint(&self) -> Result<Viewpoint, String> {
+        Ok(Viewpoint {
+            camera: self.camera.state(),
+            lighting: self.lighting()?,
+        })
+    }
+
+    /// Restore a saved view
+    pub fn set_viewpoint(&mut self, viewpoint: &Viewpoint) -> Result<(), String> {
+        self.set_lighting(&viewpoint.lighting)?;
+        self.camera.set_state(viewpoint.camera);
+        Ok(())
+    }
+
     /// Set the render mode (shaded or wireframe)
     pub fn set_render_mode(&mut self, mode: RenderMode) -> Result<(), String> {
         let scene = self.scene.as_mut().ok_or("Scene not initialized")?;
+        self.scene_version += 1;
         scene.set_render_mode(mode);
         Ok(())
     }
@@ -197,6 +999,7 @@ impl Renderer {
     /// None to disable clipping
     pub fn set_section_plane(&mut self, plane: Option<([f32; 3], [f32; 3])>) -> Result<(), String> {
         let scene = self.scene.as_mut().ok_or("Scene not initialized")?;
+        self.scene_version += 1;
         scene.set_section_plane(plane);
         if let Some(queue) = self.gpu.queue() {
             scene.update_section_plane(queue);
@@ -204,6 +1007,157 @@ impl Renderer {
         Ok(())
     }
 
+    /// Draw an outline `width` pixels wide along where the section plane
+    /// cuts surfaces, antialiased towards the surface so the cut boundary
+    /// does not alias; 0 turns it off (the default)
+    pub fn set_section_outline(&mut self, width: f32, color: [f32; 4]) -> Result<(), String> {
+        let scene = self.scene.as_mut().ok_or("Scene not initialized")?;
+        self.scene_version += 1;
+        scene.section_plane_uniform.set_outline(width, color);
+        if let Some(queue) = self.gpu.queue() {
+            scene.update_section_plane(queue);
+        }
+        Ok(())
+    }
+
+    /// Section outline as (width in pixels, RGBA color)
+    pub fn section_outline(&self) -> Option<(f32, [f32; 4])> {
+        Some(self.scene.as_ref()?.section_plane_uniform.outline())
+    }
+
+    /// Set the clip box; fragments outside it are discarded
+    /// bounds: Option<(min: [f32; 3], max: [f32; 3])>
+    /// None to disable the clip box (section planes are unaffected)
+    pub fn set_clip_box(&mut self, bounds: Option<([f32; 3], [f32; 3])>) -> Result<(), String> {
+        let scene = self.scene.as_mut().ok_or("Scene not initialized")?;
+        self.scene_version += 1;
+        scene.set_clip_box(bounds);
+        if let Some(queue) = self.gpu.queue() {
+            scene.update_clip_box(queue);
+        }
+        Ok(())
+    }
+
+    /// Get the active clip box as (min, max)
+    pub fn clip_box(&self) -> Option<([f32; 3], [f32; 3])> {
+        self.scene.as_ref()?.clip_box_uniform.bounds()
+    }
+
+    /// Upload an RGBA image as a drawing overlay (replaces an existing ID)
+    pub fn add_overlay(&mut self, id: &str, width: u32, height: u32, rgba_data: &[u8]) -> Result<(), String> {
+        self.insert_overlay(id, |drawing, device, queue, layout| {
+            drawing.upload_texture(device, queue, width, height, rgba_data, layout)
+        })
+    }
+
+    /// Decode a PNG/JPEG image and add it as a drawing overlay (replaces an existing ID)
+    pub fn add_overlay_from_bytes(&mut self, id: &str, bytes: &[u8]) -> Result<(), String> {
+        self.insert_overlay(id, |drawing, device, queue, layout| {
+            drawing.upload_image_bytes(device, queue, bytes, layout)
+        })
+    }
+
+    fn insert_overlay(
+        &mut self,
+        id: &str,
+        upload: impl FnOnce(&mut DrawingOverlay, &wgpu::Device, &wgpu::Queue, &wgpu::BindGroupLayout) -> Result<(), String>,
+    ) -> Result<(), String> {
+        let device = self.gpu.device().ok_or("GPU not initialized")?;
+        let queue = self.gpu.queue().ok_or("GPU queue not initialized")?;
+        let layout = self
+            .overlay_bind_group_layout
+            .get_or_insert_with(|| overlay::create_overlay_bind_group_layout(device));
+
+        // Keep the sampling settings of an overlay being replaced
+        let mut drawing = DrawingOverlay::new(id.to_string());
+        if let Some(existing) = self.overlays.get(id) {
+            drawing.sampling = existing.sampling;
+        }
+        self.gpu
+            .with_error_scope("Overlay upload", |device| upload(&mut drawing, device, queue, layout))??;
+
+        self.overlays.insert(id.to_string(), drawing);
+        self.upload_overlays()
+    }
+
+    /// Remove a drawing overlay, returning whether it existed
+    pub fn remove_overlay(&mut self, id: &str) -> Result<bool, String> {
+        let removed = self.overlays.remove(id).is_some();
+        if removed {
+            self.upload_overlays()?;
+        }
+        Ok(removed)
+    }
+
+    /// Change a drawing overlay (placement, opacity, visibility) and redraw
+    /// the overlays, returning what `update` returns
+    pub fn update_overlay<T>(&mut self, id: &str, update: impl FnOnce(&mut DrawingOverlay) -> T) -> Result<T, String> {
+        let drawing = self
+            .overlays
+            .get_mut(id)
+            .ok_or_else(|| format!("Overlay not found: {}", id))?;
+        let result = update(drawing);
+        self.upload_overlays()?;
+        Ok(result)
+    }
+
+    /// Change how an overlay texture is sampled
+    /// Returns the settings actually applied (anisotropy depends on device support)
+    pub fn set_overlay_sampling(&mut self, id: &str, sampling: OverlaySampling) -> Result<OverlaySampling, String> {
+        let device = self.gpu.device().ok_or("GPU not initialized")?;
+        let queue = self.gpu.queue().ok_or("GPU queue not initialized")?;
+        let sampling = sampling.effective(self.gpu.anisotropic_filtering_supported());
+        let layout = self
+            .overlay_bind_group_layout
+            .get_or_insert_with(|| overlay::create_overlay_bind_group_layout(device));
+
+        let drawing = self
+            .overlays
+            .get_mut(id)
+            .ok_or_else(|| format!("Overlay not found: {}", id))?;
+        drawing.set_sampling(device, queue, sampling, layout);
+        self.upload_overlays()?;
+        Ok(sampling)
+    }
+
+    /// Get the active section plane as (origin, normal)
+    pub fn section_plane(&self) -> Option<([f32; 3], [f32; 3])> {
+        self.scene.as_ref()?.section_plane_uniform.plane()
+    }
+
+    /// Render frame `frame` of `frame_count` along a camera path as a PNG,
+    /// for exporting a sequence to assemble into video
+    ///
+    /// Each frame depends only on the scene, the path and its number: the
+    /// live camera is left where it was, and occlusion culling (which
+    /// reuses the previous frame's results) is off for the render.
+    pub fn render_sequence_frame(&mut self, path: &CameraPath, frame: u32, frame_count: u32) -> Result<Vec<u8>, String> {
+        path.validate()?;
+        if frame >= frame_count {
+            return Err(format!("Frame {} out of range for {} frames", frame, frame_count));
+        }
+        let state = path.sample_state(path.sequence_time(frame, frame_count)).ok_or("Invalid camera path")?;
+        let live_camera = self.camera.state();
+        let occlusion = self.scene.as_mut().and_then(|s| s.occlusion.take());
+        self.camera.set_state(state);
+
+        let pixels = self.with_frame(<[u8]>::to_vec);
+
+        self.camera.set_state(live_camera);
+        if let Some(scene) = self.scene.as_mut() {
+            scene.occlusion = occlusion;
+        }
+        let pixels = pixels?;
+        let (width, height) = self.get_dimensions().ok_or("Scene not initialized")?;
+        encode_png(width, height, pixels)
+    }
+
+    /// Move the section plane to a step of a sweep and render that frame
+    pub fn render_section_step(&mut self, sweep: &SectionSweep, step: u32) -> Result<Vec<u8>, String> {
+        self.set_section_plane(Some(sweep.plane(step)))?;
+        self.render_frame()
+    }
+
     /// Set the color of a specific element by index
     /// TODO: Implement per-element coloring in renderer
     pub fn set_element_color(&mut self, _element_index: usize, _r: f32, _g: f32, _b: f32) -> Result<(), String> {
@@ -218,3 +1172,452 @@ impl Renderer {
         Ok(())
     }
 }
+
+/// Point the camera at the center of a bounding box, backing off far enough
+/// to see all of it
+fn fit_camera(camera: &mut Camera, min: [f32; 3], max: [f32; 3]) {
+    // Calculate center and size
+    let center = [
+        (min[0] + max[0]) / 2.0,
+        (min[1] + max[1]) / 2.0,
+        (min[2] + max[2]) / 2.0,
+    ];
+
+    let size = [
+        max[0] - min[0],
+        max[1] - min[1],
+        max[2] - min[2],
+    ];
+
+    // Find the largest dimension
+    let max_size = size[0].max(size[1]).max(size[2]);
+
+    // Calculate camera distance (1.5x the max size, minimum of 10 units)
+    let distance = (max_size * 1.5).max(10.0);
+
+    // Set camera target to center
+    camera.set_target(center);
+
+    // Set camera distance
+    camera.set_distance(distance);
+}
+
+/// Encode RGBA pixel data as a PNG image
+pub fn encode_png(width: u32, height: u32, rgba: Vec<u8>) -> Result<Vec<u8>, String> {
+    let image = image::RgbaImage::from_raw(width, height, rgba)
+        .ok_or("Pixel data does not match image dimensions")?;
+
+    let mut png = std::io::Cursor::new(Vec::new());
+    image
+        .write_to(&mut png, image::ImageOutputFormat::Png)
+        .map_err(|e| format!("Failed to encode PNG: {}", e))?;
+    Ok(png.into_inner())
+}
+
+/// Create a headless renderer with an initialized scene for tests
+///
+/// Returns None only when the system exposes no adapter at all, not even a
+/// software one, so GPU tests can skip; any other initialization failure
+/// panics.
+#[cfg(test)]
+pub(crate) fn test_renderer(width: u32, height: u32) -> Option<Renderer> {
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .build()
+        .expect("Failed to build test runtime");
+    let mut renderer = Renderer::new();
+    if let Err(e) = runtime.block_on(renderer.initialize()) {
+        if e.contains(gpu::NO_ADAPTER_ERROR) {
+            return None;
+        }
+        panic!("{}", e);
+    }
+    renderer
+        .init_scene(width, height)
+        .expect("Failed to initialize scene");
+    Some(renderer)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bim::geometry::generate_box_with_normals;
+
+    #[test]
+    fn test_idle_renders_report_unchanged() {
+        let Some(mut renderer) = test_renderer(32, 32) else {
+            eprintln!("No GPU adapter available, skipping");
+            return;
+        };
+
+        // Continuous rendering (default) renders every time
+        assert!(renderer.render_frame_if_changed().unwrap().is_some());
+        assert!(renderer.render_frame_if_changed().unwrap().is_some());
+
+        renderer.set_continuous_rendering(false);
+        let frame = renderer.render_frame_if_changed().unwrap();
+        assert!(frame.is_none(), "nothing changed since the last render");
+        assert!(renderer.render_frame_if_changed().unwrap().is_none());
+        assert!(!renderer.frame_changed());
+
+        // The cached frame is served by render_frame
+        let cached = renderer.render_frame().unwrap();
+        assert_eq!(cached.len(), 32 * 32 * 4);
+
+        // Camera, light and explicit changes each trigger one render
+        renderer.orbit_camera(0.3, 0.0);
+        assert!(renderer.render_frame_if_changed().unwrap().is_some());
+        assert!(renderer.render_frame_if_changed().unwrap().is_none());
+        renderer.set_light_intensity(0.2).unwrap();
+        assert!(renderer.render_frame_if_changed().unwrap().is_some());
+        renderer.mark_dirty();
+        assert!(renderer.frame_changed());
+        assert!(renderer.render_frame_if_changed().unwrap().is_some());
+        assert!(renderer.render_frame_if_changed().unwrap().is_none());
+    }
+
+    #[test]
+    fn test_render_frame_into_matches_render_frame() {
+        let Some(renderer) = test_renderer(40, 24) else {
+            eprintln!("No GPU adapter available, skipping");
+            return;
+        };
+
+        let expected = renderer.render_frame().unwrap();
+        let mut buffer = vec![0u8; 40 * 24 * 4];
+        renderer.render_frame_into(&mut buffer).unwrap();
+        assert_eq!(buffer, expected);
+        // Reused without clearing, and through the persistent buffer
+        renderer.render_frame_into(&mut buffer).unwrap();
+        assert_eq!(buffer, expected);
+        assert_eq!(renderer.with_frame(|pixels| pixels == expected.as_slice()), Ok(true));
+
+        assert!(renderer.render_frame_into(&mut [0u8; 16]).is_err());
+    }
+
+    #[test]
+    fn test_render_thumbnail_png() {
+        let Some(renderer) = test_renderer(64, 48) else {
+            eprintln!("No GPU adapter available, skipping");
+            return;
+        };
+
+        let mesh = generate_box_with_normals([0.0, 1.0, 0.0], [4.0, 2.0, 3.0], [0.8, 0.3, 0.2, 1.0]);
+        let bounds = mesh.bounding_box().map(|b| (b.min, b.max));
+        let png = renderer
+            .render_thumbnail(32, &mesh.vertices, &mesh.normals, &mesh.colors, &mesh.indices, bounds)
+            .unwrap();
+
+        assert!(png.starts_with(b"\x89PNG"));
+        let image = image::load_from_memory(&png).unwrap();
+        assert_eq!((image.width(), image.height()), (32, 32));
+
+        // The live scene keeps its own size
+        assert_eq!(renderer.get_dimensions(), Some((64, 48)));
+        assert!(renderer.render_thumbnail(0, &[], &[], &[], &[], None).is_err());
+    }
+
+    #[test]
+    fn test_gpu_errors_are_captured() {
+        let Some(renderer) = test_renderer(16, 16) else {
+            eprintln!("No GPU adapter available, skipping");
+            return;
+        };
+        assert_eq!(renderer.gpu.last_error(), None);
+        assert!(renderer.render_frame().is_ok());
+
+        // MAP_READ and MAP_WRITE together is a validation error
+        let invalid = wgpu::BufferDescriptor {
+            label: Some("Invalid Buffer"),
+            size: 16,
+            usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::MAP_WRITE,
+            mapped_at_creation: false,
+        };
+        let result = renderer.gpu.with_error_scope("Buffer creation", |device| {
+            device.create_buffer(&invalid);
+        });
+        let error = result.unwrap_err();
+        assert!(error.starts_with("Buffer creation failed"));
+        assert_eq!(renderer.gpu.last_error(), Some(error));
+
+        // Errors outside any scope reach the uncaptured-error handler
+        renderer.gpu.clear_last_error();
+        renderer.gpu.device().unwrap().create_buffer(&invalid);
+        assert!(renderer.gpu.last_error().is_some());
+    }
+
+    #[test]
+    fn test_clip_box_discards_fragments_outside() {
+        let (width, height) = (64u32, 32u32);
+        let Some(mut renderer) = test_renderer(width, height) else {
+            eprintln!("No GPU adapter available, skipping");
+            return;
+        };
+
+        // Two boxes side by side, seen head-on
+        let left = generate_box_with_normals([-2.0, 0.0, 0.0], [2.0, 2.0, 2.0], [0.9, 0.2, 0.2, 1.0]);
+        let right = generate_box_with_normals([2.0, 0.0, 0.0], [2.0, 2.0, 2.0], [0.2, 0.9, 0.2, 1.0]);
+        let mesh = crate::bim::geometry::merge_meshes(vec![left, right]);
+        renderer
+            .load_mesh(&mesh.vertices, &mesh.normals, &mesh.colors, &mesh.indices)
+            .unwrap();
+        renderer.update_camera([0.0, 0.0, 10.0], [0.0, 0.0, 0.0]);
+
+        // Pixel under a world point (front face center of a box)
+        let view_projection = renderer.camera.view_projection_matrix();
+        let pixel = |frame: &[u8], x: f32| -> [u8; 4] {
+            let ndc = view_projection.project_point3(glam::Vec3::new(x, 0.0, 1.0));
+            let px = ((ndc.x + 1.0) / 2.0 * width as f32) as usize;
+            let py = ((1.0 - ndc.y) / 2.0 * height as f32) as usize;
+            let i = (py * width as usize + px) * 4;
+            frame[i..i + 4].try_into().unwrap()
+        };
+        let background = |frame: &[u8]| -> [u8; 4] { frame[0..4].try_into().unwrap() };
+
+        let before = renderer.render_frame().unwrap();
+        assert_ne!(pixel(&before, 2.0), background(&before));
+
+        // Keep only the left box
+        renderer
+            .set_clip_box(Some(([-3.5, -1.5, -1.5], [-0.5, 1.5, 1.5])))
+            .unwrap();
+        let clipped = renderer.render_frame().unwrap();
+        assert_eq!(pixel(&clipped, -2.0), pixel(&before, -2.0));
+        assert_eq!(pixel(&clipped, 2.0), background(&clipped));
+
+        renderer.set_clip_box(None).unwrap();
+        assert_eq!(renderer.clip_box(), None);
+        let restored = renderer.render_frame().unwrap();
+        assert_eq!(pixel(&restored, 2.0), pixel(&before, 2.0));
+    }
+
+    #[test]
+    fn test_lighting_round_trips_and_reaches_gpu() {
+        let Some(mut renderer) = test_renderer(32, 32) else {
+            eprintln!("No GPU adapter available, skipping");
+            return;
+        };
+        assert_eq!(renderer.lighting().unwrap(), LightingConfig::default());
+
+        let mesh = generate_box_with_normals([0.0, 0.0, 0.0], [2.0, 2.0, 2.0], [0.8, 0.8, 0.8, 1.0]);
+        renderer
+            .load_mesh(&mesh.vertices, &mesh.normals, &mesh.colors, &mesh.indices)
+            .unwrap();
+        renderer.update_camera([0.0, 0.0, 10.0], [0.0, 0.0, 0.0]);
+        let center = |frame: &[u8]| -> [u8; 4] {
+            let i = (16 * 32 + 16) * 4;
+            frame[i..i + 4].try_into().unwrap()
+        };
+        let lit = center(&renderer.render_frame().unwrap());
+
+        let config = LightingConfig {
+            direction: [0.0, 0.0, 1.0],
+            color: [1.0, 1.0, 1.0],
+            intensity: 0.0,
+            ambient: [0.0, 0.0, 0.0],
+        };
+        renderer.set_lighting(&config).unwrap();
+        assert_eq!(renderer.lighting().unwrap(), config);
+
+        // With no light the front face renders black
+        let dark = center(&renderer.render_frame().unwrap());
+        assert_ne!(dark, lit);
+        assert_eq!(&dark[..3], &[0, 0, 0]);
+
+        // Saved viewpoints carry lighting along with the camera
+        let json = serde_json::to_string(&renderer.viewpoint().unwrap()).unwrap();
+        renderer.set_lighting(&LightingConfig::default()).unwrap();
+        renderer.update_camera([5.0, 5.0, 5.0], [0.0, 0.0, 0.0]);
+        let viewpoint: Viewpoint = serde_json::from_str(&json).unwrap();
+        renderer.set_viewpoint(&viewpoint).unwrap();
+        assert_eq!(renderer.lighting().unwrap(), config);
+        assert_eq!(renderer.camera.position(), [0.0, 0.0, 10.0]);
+    }
+
+    #[test]
+    fn test_tone_mapping_keeps_bright_surfaces_below_white() {
+        let Some(mut renderer) = test_renderer(32, 32) else {
+            eprintln!("No GPU adapter available, skipping");
+            return;
+        };
+        let mesh = generate_box_with_normals([0.0, 0.0, 0.0], [2.0, 2.0, 2.0], [0.8, 0.6, 0.4, 1.0]);
+        renderer
+            .load_mesh(&mesh.vertices, &mesh.normals, &mesh.colors, &mesh.indices)
+            .unwrap();
+        renderer.update_camera([0.0, 0.0, 10.0], [0.0, 0.0, 0.0]);
+        renderer
+            .set_lighting(&LightingConfig {
+                direction: [0.0, 0.0, 1.0],
+                intensity: 8.0,
+                ..LightingConfig::default()
+            })
+            .unwrap();
+        let center = |renderer: &Renderer| -> [u8; 4] {
+            let frame = renderer.render_frame().unwrap();
+            let i = (16 * 32 + 16) * 4;
+            frame[i..i + 4].try_into().unwrap()
+        };
+
+        // Without tone mapping every channel clips
+        let clipped = center(&renderer);
+        assert_eq!(&clipped[..3], &[255, 255, 255]);
+
+        for mode in [ToneMapping::Reinhard, ToneMapping::Aces] {
+            renderer.set_tone_mapping(mode).unwrap();
+            let mapped = center(&renderer);
+            assert!(mapped[..3].iter().all(|&c| c < 255), "{:?}: {:?}", mode, mapped);
+            // Brighter base colors stay brighter
+            assert!(mapped[0] > mapped[1] && mapped[1] > mapped[2], "{:?}: {:?}", mode, mapped);
+        }
+
+        // Exposure scales the input to the curve
+        renderer.set_tone_mapping(ToneMapping::Reinhard).unwrap();
+        let bright = center(&renderer);
+        renderer.set_exposure(0.25).unwrap();
+        let dimmer = center(&renderer);
+        assert!(dimmer[0] < bright[0]);
+    }
+
+    #[test]
+    fn test_release_then_reinitialize_leaves_renderer_usable() {
+        let Some(mut renderer) = test_renderer(32, 32) else {
+            eprintln!("No GPU adapter available, skipping");
+            return;
+        };
+        let mesh = generate_box_with_normals([0.0, 0.0, 0.0], [2.0, 2.0, 2.0], [0.8, 0.6, 0.4, 1.0]);
+        let load = |renderer: &mut Renderer| {
+            renderer
+                .load_mesh(&mesh.vertices, &mesh.normals, &mesh.colors, &mesh.indices)
+                .unwrap()
+        };
+        load(&mut renderer);
+        renderer.update_camera([0.0, 0.0, 10.0], [0.0, 0.0, 0.0]);
+        renderer.set_light_intensity(0.6).unwrap();
+        renderer.set_section_plane(Some(([0.0, 0.5, 0.0], [0.0, -1.0, 0.0]))).unwrap();
+        let before = renderer.render_frame().unwrap();
+        let lighting = renderer.lighting().unwrap();
+
+        renderer.release();
+        assert!(renderer.is_released() && !renderer.gpu.is_initialized());
+        assert_eq!(renderer.gpu_buffer_bytes(), 0);
+        let error = renderer.render_frame().unwrap_err();
+        assert!(error.contains("released"), "{}", error);
+        assert!(renderer.request_frame().is_err());
+
+        let runtime = tokio::runtime::Builder::new_current_thread().build().unwrap();
+        runtime.block_on(renderer.reinitialize()).unwrap();
+        assert!(!renderer.is_released() && renderer.initialized);
+        assert_eq!(renderer.get_dimensions(), Some((32, 32)));
+        assert_eq!(renderer.lighting().unwrap(), lighting);
+        assert_eq!(renderer.section_plane(), Some(([0.0, 0.5, 0.0], [0.0, -1.0, 0.0])));
+
+        // The same mesh renders the same frame on the new device
+        load(&mut renderer);
+        assert_eq!(renderer.render_frame().unwrap(), before);
+        assert!(runtime.block_on(renderer.reinitialize()).is_err());
+    }
+
+    #[test]
+    fn test_fully_covered_element_is_flagged_occluded() {
+        let Some(mut renderer) = test_renderer(32, 32) else {
+            eprintln!("No GPU adapter available, skipping");
+            return;
+        };
+        // A wide box in front of a small one, seen head-on
+        let near = generate_box_with_normals([0.0, 0.0, 2.0], [6.0, 6.0, 1.0], [0.9, 0.2, 0.2, 1.0]);
+        let far = generate_box_with_normals([0.0, 0.0, -3.0], [1.0, 1.0, 1.0], [0.2, 0.9, 0.2, 1.0]);
+        let mesh = crate::bim::geometry::merge_meshes(vec![near, far]);
+        renderer
+            .load_mesh(&mesh.vertices, &mesh.normals, &mesh.colors, &mesh.indices)
+            .unwrap();
+        // Culling needs element ranges
+        assert!(!renderer.set_occlusion_culling(true).unwrap());
+        renderer.set_element_ranges(vec![0..12, 12..24]).unwrap();
+        if !renderer.gpu.occlusion_queries_supported() {
+            eprintln!("Occlusion queries unsupported, skipping");
+            return;
+        }
+        renderer.update_camera([0.0, 0.0, 10.0], [0.0, 0.0, 0.0]);
+
+        let first = renderer.render_frame().unwrap();
+        assert_eq!(renderer.occluded_elements(), vec![1]);
+
+        // Skipping the hidden element leaves the image unchanged
+        let second = renderer.render_frame().unwrap();
+        assert_eq!(first, second);
+        assert_eq!(renderer.occluded_elements(), vec![1]);
+
+        // From behind, the probe draw finds it visible again
+        renderer.update_camera([0.0, 0.0, -10.0], [0.0, 0.0, 0.0]);
+        renderer.render_frame().unwrap();
+        assert!(renderer.occluded_elements().is_empty());
+
+        // Reloading the mesh drops the stale ranges
+        renderer
+            .load_mesh(&mesh.vertices, &mesh.normals, &mesh.colors, &mesh.indices)
+            .unwrap();
+        assert!(renderer.scene.as_ref().unwrap().occlusion.is_none());
+    }
+
+    #[test]
+    fn test_annotations_draw_over_background() {
+        let Some(mut renderer) = test_renderer(32, 32) else {
+            eprintln!("No GPU adapter available, skipping");
+            return;
+        };
+        renderer.load_mesh(&[], &[], &[], &[]).unwrap();
+        renderer.update_camera([0.0, 0.0, 10.0], [0.0, 0.0, 0.0]);
+        let empty = renderer.render_frame().unwrap();
+
+        // A horizontal line through the middle of the view
+        renderer
+            .add_annotation(Annotation {
+                points: vec![[-5.0, 0.0, 0.0], [5.0, 0.0, 0.0]],
+                closed: false,
+            })
+            .unwrap();
+        renderer.set_annotation_color([1.0, 0.0, 0.0, 1.0]).unwrap();
+        let annotated = renderer.render_frame().unwrap();
+        let red = annotated.chunks(4).filter(|p| p[0] == 255 && p[1] == 0).count();
+        assert!(red > 0);
+
+        renderer.clear_annotations().unwrap();
+        assert_eq!(renderer.render_frame().unwrap(), empty);
+    }
+
+    #[test]
+    fn test_coplanar_annotation_fill_draws_over_surface() {
+        let Some(mut renderer) = test_renderer(64, 64) else {
+            eprintln!("No GPU adapter available, skipping");
+            return;
+        };
+        // Gray floor at y = 0 with a red area measurement lying on it
+        let vertices = [-5.0, 0.0, -5.0, 5.0, 0.0, -5.0, 5.0, 0.0, 5.0, -5.0, 0.0, 5.0];
+        let normals = [0.0, 1.0, 0.0].repeat(4);
+        let colors = [0.5, 0.5, 0.5, 1.0].repeat(4);
+        renderer.load_mesh(&vertices, &normals, &colors, &[0, 2, 1, 0, 3, 2]).unwrap();
+        renderer.set_annotation_color([1.0, 0.0, 0.0, 1.0]).unwrap();
+        renderer
+            .add_annotation(Annotation {
+                points: vec![[-2.0, 0.0, -2.0], [2.0, 0.0, -2.0], [2.0, 0.0, 2.0], [-2.0, 0.0, 2.0]],
+                closed: true,
+            })
+            .unwrap();
+        assert_eq!(renderer.overlay_depth_bias(), DEFAULT_OVERLAY_DEPTH_BIAS);
+
+        // From above down to grazing angles, the middle of the view is tinted
+        for position in [[0.0, 10.0, 1.0], [6.0, 4.0, 6.0], [10.0, 1.0, 2.0], [10.0, 0.3, 0.5]] {
+            renderer.update_camera(position, [0.0, 0.0, 0.0]);
+            let pixels = renderer.render_frame().unwrap();
+            for (x, y) in [(32, 32), (31, 32), (32, 31), (33, 32)] {
+                let p = &pixels[(y * 64 + x) * 4..][..4];
+                assert!(p[0] > p[1] + 20, "fill lost at {:?} ({}, {}): {:?}", position, x, y, p);
+            }
+        }
+
+        // The bias survives a scene re-initialization
+        let bias = DepthBias { constant: -8, slope_scale: -4.0 };
+        renderer.set_overlay_depth_bias(bias).unwrap();
+        renderer.init_scene(64, 64).unwrap();
+        assert_eq!(renderer.scene.as_ref().unwrap().pipeline.as_ref().unwrap().overlay_depth_bias(), bias);
+    }
+}
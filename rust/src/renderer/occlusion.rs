@@ -0,0 +1,174 @@
+//! Occlusion Culling
+//!
+//! Per-element occlusion queries around the fill draws. Elements whose draw
+//! produced no samples are skipped the next frame; they are still re-tested
+//! every frame by a probe draw that writes neither color nor depth, after all
+//! visible geometry, so they come back as soon as they are uncovered.
+
+use std::ops::Range;
+use std::sync::Mutex;
+
+/// Size of one resolved occlusion query result
+const QUERY_RESULT_SIZE: u64 = std::mem::size_of::<u64>() as u64;
+
+/// Occlusion queries and last-frame visibility for the elements of a mesh
+pub struct OcclusionCulling {
+    query_set: wgpu::QuerySet,
+    resolve_buffer: wgpu::Buffer,
+    read_buffer: wgpu::Buffer,
+    /// Index buffer range of each element; elements past the query set
+    /// capacity are always drawn
+    ranges: Vec<Range<u32>>,
+    /// Whether each queried element produced samples last frame
+    visible: Mutex<Vec<bool>>,
+}
+
+impl OcclusionCulling {
+    /// Create queries for elements given as triangle ranges
+    pub fn new(device: &wgpu::Device, triangle_ranges: &[Range<u32>]) -> Self {
+        let ranges: Vec<Range<u32>> = triangle_ranges.iter().map(|r| r.start * 3..r.end * 3).collect();
+        let count = (ranges.len() as u32).clamp(1, wgpu::QUERY_SET_MAX_QUERIES);
+
+        let query_set = device.create_query_set(&wgpu::QuerySetDescriptor {
+            label: Some("Occlusion Query Set"),
+            ty: wgpu::QueryType::Occlusion,
+            count,
+        });
+        let resolve_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Occlusion Resolve Buffer"),
+            size: count as u64 * QUERY_RESULT_SIZE,
+            usage: wgpu::BufferUsages::QUERY_RESOLVE | wgpu::BufferUsages::COPY_SRC,
+            mapped_at_creation: false,
+        });
+        let read_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Occlusion Read Buffer"),
+            size: count as u64 * QUERY_RESULT_SIZE,
+            usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        let queried = ranges.len().min(count as usize);
+        Self {
+            query_set,
+            resolve_buffer,
+            read_buffer,
+            ranges,
+            visible: Mutex::new(vec![true; queried]),
+        }
+    }
+
+    /// Size of the query result buffers
+    pub fn buffer_bytes(&self) -> u64 {
+        self.resolve_buffer.size() + self.read_buffer.size()
+    }
+
+    /// Query set to attach to the render pass
+    pub fn query_set(&self) -> &wgpu::QuerySet {
+        &self.query_set
+    }
+
+    /// Number of elements with a query
+    fn query_count(&self) -> u32 {
+        self.visible.lock().unwrap().len() as u32
+    }
+
+    /// Index buffer range of each element
+    pub fn ranges(&self) -> &[Range<u32>] {
+        &self.ranges
+    }
+
+    /// Draw the fill pass: elements visible last frame (and unqueried ones)
+    /// with `fill`, then probe the rest with `probe`
+    ///
+    /// `ranges` are the index ranges drawn per element: `ranges()`, or a
+    /// level of detail of each element. Elements marked in `skipped` (e.g.
+    /// too small on screen) are only probed, so their visibility is still
+    /// known when they are drawn again.
+    pub fn draw<'a>(
+        &self,
+        render_pass: &mut wgpu::RenderPass<'a>,
+        ranges: &[Range<u32>],
+        skipped: &[bool],
+        fill: &'a wgpu::RenderPipeline,
+        probe: &'a wgpu::RenderPipeline,
+    ) {
+        let visible = self.visible.lock().unwrap();
+        let query_count = visible.len();
+        let is_skipped = |i: usize| skipped.get(i).copied().unwrap_or(false);
+
+        render_pass.set_pipeline(fill);
+        for (i, range) in ranges.iter().enumerate() {
+            if is_skipped(i) {
+                continue;
+            }
+            if i >= query_count {
+                render_pass.draw_indexed(range.clone(), 0, 0..1);
+            } else if visible[i] {
+                render_pass.begin_occlusion_query(i as u32);
+                render_pass.draw_indexed(range.clone(), 0, 0..1);
+                render_pass.end_occlusion_query();
+            }
+        }
+
+        // Every query is written each frame, so no stale results are resolved
+        render_pass.set_pipeline(probe);
+        for (i, range) in ranges.iter().enumerate().take(query_count) {
+            if !visible[i] || is_skipped(i) {
+                render_pass.begin_occlusion_query(i as u32);
+                render_pass.draw_indexed(range.clone(), 0, 0..1);
+                render_pass.end_occlusion_query();
+            }
+        }
+    }
+
+    /// Record copying this frame's query results into the read buffer
+    pub fn resolve(&self, encoder: &mut wgpu::CommandEncoder) {
+        let count = self.query_count();
+        if count == 0 {
+            return;
+        }
+        encoder.resolve_query_set(&self.query_set, 0..count, &self.resolve_buffer, 0);
+        encoder.copy_buffer_to_buffer(
+            &self.resolve_buffer,
+            0,
+            &self.read_buffer,
+            0,
+            count as u64 * QUERY_RESULT_SIZE,
+        );
+    }
+
+    /// Read back the resolved results (after submitting the frame) to decide
+    /// which elements to draw next frame
+    pub fn read_results(&self, device: &wgpu::Device) {
+        let count = self.query_count();
+        if count == 0 {
+            return;
+        }
+        let slice = self.read_buffer.slice(..count as u64 * QUERY_RESULT_SIZE);
+        let (sender, receiver) = std::sync::mpsc::channel();
+        slice.map_async(wgpu::MapMode::Read, move |result| {
+            sender.send(result).unwrap();
+        });
+        device.poll(wgpu::Maintain::Wait);
+        if receiver.recv().map(|r| r.is_ok()).unwrap_or(false) {
+            let samples: Vec<u64> = bytemuck::cast_slice(&slice.get_mapped_range()).to_vec();
+            let mut visible = self.visible.lock().unwrap();
+            for (flag, count) in visible.iter_mut().zip(samples) {
+                *flag = count > 0;
+            }
+            self.read_buffer.unmap();
+        }
+    }
+
+    /// Elements that produced no samples in the last frame
+    pub fn occluded_elements(&self) -> Vec<usize> {
+        self.visible
+            .lock()
+            .unwrap()
+            .iter()
+            .enumerate()
+            .filter(|(_, visible)| !**visible)
+            .map(|(i, _)| i)
+            .collect()
+    }
+}
@@ -4,6 +4,252 @@
 //! for comparison and verification workflows.
 
 use super::vertex::Vertex;
+use wgpu::util::DeviceExt;
+
+/// Texture sampling settings for an overlay
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct OverlaySampling {
+    /// Linear filtering (false = nearest, for pixel-exact drawings)
+    pub linear: bool,
+    /// Sample from a generated mip chain (sharper at a distance, less shimmer)
+    pub mipmaps: bool,
+    /// Anisotropic filtering level (1 = off, up to 16)
+    pub anisotropy: u16,
+    /// Repeat the image outside 0..1 (tiling) instead of clamping to the edge
+    pub repeat: bool,
+}
+
+impl Default for OverlaySampling {
+    fn default() -> Self {
+        Self {
+            linear: true,
+            mipmaps: false,
+            anisotropy: 1,
+            repeat: false,
+        }
+    }
+}
+
+impl OverlaySampling {
+    /// Settings that are valid on the device
+    ///
+    /// Anisotropy is clamped to 1..=16 and disabled when unsupported or when
+    /// filtering is nearest (wgpu requires all-linear filters for it).
+    pub fn effective(self, anisotropy_supported: bool) -> Self {
+        let anisotropy = if anisotropy_supported && self.linear {
+            self.anisotropy.clamp(1, 16)
+        } else {
+            1
+        };
+        Self { anisotropy, ..self }
+    }
+
+    fn sampler_descriptor<'a>(&self, label: &'a str) -> wgpu::SamplerDescriptor<'a> {
+        let address_mode = if self.repeat {
+            wgpu::AddressMode::Repeat
+        } else {
+            wgpu::AddressMode::ClampToEdge
+        };
+        let filter = if self.linear {
+            wgpu::FilterMode::Linear
+        } else {
+            wgpu::FilterMode::Nearest
+        };
+
+        wgpu::SamplerDescriptor {
+            label: Some(label),
+            address_mode_u: address_mode,
+            address_mode_v: address_mode,
+            address_mode_w: address_mode,
+            mag_filter: filter,
+            min_filter: filter,
+            mipmap_filter: filter,
+            // Without mipmaps only the base level holds image data
+            lod_max_clamp: if self.mipmaps { 32.0 } else { 0.0 },
+            anisotropy_clamp: self.anisotropy,
+            ..Default::default()
+        }
+    }
+}
+
+/// Bind group layout for overlay textures (texture at 0, sampler at 1)
+pub fn create_overlay_bind_group_layout(device: &wgpu::Device) -> wgpu::BindGroupLayout {
+    device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+        label: Some("Overlay Bind Group Layout"),
+        entries: &[
+            wgpu::BindGroupLayoutEntry {
+                binding: 0,
+                visibility: wgpu::ShaderStages::FRAGMENT,
+                ty: wgpu::BindingType::Texture {
+                    sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                    view_dimension: wgpu::TextureViewDimension::D2,
+                    multisampled: false,
+                },
+                count: None,
+            },
+            wgpu::BindGroupLayoutEntry {
+                binding: 1,
+                visibility: wgpu::ShaderStages::FRAGMENT,
+                ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                count: None,
+            },
+        ],
+    })
+}
+
+/// Number of mip levels in a full chain for the given size
+pub fn mip_level_count(width: u32, height: u32) -> u32 {
+    32 - width.max(height).max(1).leading_zeros()
+}
+
+/// Blit shader: fullscreen triangle sampling the previous mip level
+const MIPMAP_SHADER: &str = r#"
+struct VertexOutput {
+    @builtin(position) position: vec4<f32>,
+    @location(0) uv: vec2<f32>,
+};
+
+@vertex
+fn vs_main(@builtin(vertex_index) index: u32) -> VertexOutput {
+    let uv = vec2<f32>(f32((index << 1u) & 2u), f32(index & 2u));
+    var out: VertexOutput;
+    out.position = vec4<f32>(uv.x * 2.0 - 1.0, 1.0 - uv.y * 2.0, 0.0, 1.0);
+    out.uv = uv;
+    return out;
+}
+
+@group(0) @binding(0)
+var source: texture_2d<f32>;
+@group(0) @binding(1)
+var source_sampler: sampler;
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    return textureSample(source, source_sampler, in.uv);
+}
+"#;
+
+/// Fill mip levels 1.. of a texture by repeatedly downsampling the level above
+pub fn generate_mipmaps(
+    device: &wgpu::Device,
+    queue: &wgpu::Queue,
+    texture: &wgpu::Texture,
+    mip_level_count: u32,
+) {
+    if mip_level_count < 2 {
+        return;
+    }
+
+    let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+        label: Some("Mipmap Shader"),
+        source: wgpu::ShaderSource::Wgsl(MIPMAP_SHADER.into()),
+    });
+    let layout = create_overlay_bind_group_layout(device);
+    let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+        label: Some("Mipmap Pipeline Layout"),
+        bind_group_layouts: &[&layout],
+        push_constant_ranges: &[],
+    });
+    let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+        label: Some("Mipmap Pipeline"),
+        layout: Some(&pipeline_layout),
+        vertex: wgpu::VertexState {
+            module: &shader,
+            entry_point: "vs_main",
+            buffers: &[],
+        },
+        fragment: Some(wgpu::FragmentState {
+            module: &shader,
+            entry_point: "fs_main",
+            targets: &[Some(texture.format().into())],
+        }),
+        primitive: wgpu::PrimitiveState::default(),
+        depth_stencil: None,
+        multisample: wgpu::MultisampleState::default(),
+        multiview: None,
+    });
+    let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+        label: Some("Mipmap Sampler"),
+        mag_filter: wgpu::FilterMode::Linear,
+        min_filter: wgpu::FilterMode::Linear,
+        ..Default::default()
+    });
+
+    let level_view = |level: u32| {
+        texture.create_view(&wgpu::TextureViewDescriptor {
+            base_mip_level: level,
+            mip_level_count: Some(1),
+            ..Default::default()
+        })
+    };
+
+    let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+        label: Some("Mipmap Encoder"),
+    });
+    for level in 1..mip_level_count {
+        let source = level_view(level - 1);
+        let target = level_view(level);
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            layout: &layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureView(&source),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::Sampler(&sampler),
+                },
+            ],
+            label: Some("Mipmap Bind Group"),
+        });
+
+        let mut pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("Mipmap Pass"),
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                view: &target,
+                resolve_target: None,
+                ops: wgpu::Operations {
+                    load: wgpu::LoadOp::Clear(wgpu::Color::TRANSPARENT),
+                    store: wgpu::StoreOp::Store,
+                },
+            })],
+            depth_stencil_attachment: None,
+            timestamp_writes: None,
+            occlusion_query_set: None,
+        });
+        pass.set_pipeline(&pipeline);
+        pass.set_bind_group(0, &bind_group, &[]);
+        pass.draw(0..3, 0..1);
+    }
+    queue.submit(std::iter::once(encoder.finish()));
+}
+
+/// Decode a PNG/JPEG image to RGBA, downscaling (aspect preserved) so that
+/// neither side exceeds `max_dimension`
+pub fn decode_overlay_image(bytes: &[u8], max_dimension: u32) -> Result<image::RgbaImage, String> {
+    let decoded = image::load_from_memory(bytes).map_err(|e| format!("Failed to decode image: {}", e))?;
+    let rgba = decoded.to_rgba8();
+    Ok(fit_to_max_dimension(rgba, max_dimension))
+}
+
+/// Downscale an image so that its larger side is at most `max_dimension`
+fn fit_to_max_dimension(rgba: image::RgbaImage, max_dimension: u32) -> image::RgbaImage {
+    let (width, height) = rgba.dimensions();
+    let largest = width.max(height);
+    if largest <= max_dimension || max_dimension == 0 {
+        return rgba;
+    }
+
+    let scale = max_dimension as f64 / largest as f64;
+    let new_width = ((width as f64 * scale).round() as u32).clamp(1, max_dimension);
+    let new_height = ((height as f64 * scale).round() as u32).clamp(1, max_dimension);
+    tracing::info!(
+        "Downscaling overlay image {}x{} -> {}x{} (device max {})",
+        width, height, new_width, new_height, max_dimension
+    );
+    image::imageops::resize(&rgba, new_width, new_height, image::imageops::FilterType::Triangle)
+}
 
 /// Drawing overlay representation
 pub struct DrawingOverlay {
@@ -19,6 +265,9 @@ pub struct DrawingOverlay {
     pub rotation: f32,       // Rotation around Z axis (radians)
     pub opacity: f32,        // 0.0 to 1.0
     pub visible: bool,
+    pub sampling: OverlaySampling,
+    pub mip_level_count: u32,
+    pub mipmaps_generated: bool,
 }
 
 impl DrawingOverlay {
@@ -36,6 +285,9 @@ impl DrawingOverlay {
             rotation: 0.0,
             opacity: 0.7,
             visible: true,
+            sampling: OverlaySampling::default(),
+            mip_level_count: 1,
+            mipmaps_generated: false,
         }
     }
 
@@ -57,7 +309,8 @@ impl DrawingOverlay {
             ));
         }
 
-        // Create texture
+        // Create texture (with room for a full mip chain, filled on demand)
+        let mip_level_count = mip_level_count(width, height);
         let texture = device.create_texture(&wgpu::TextureDescriptor {
             label: Some(&format!("Overlay Texture: {}", self.id)),
             size: wgpu::Extent3d {
@@ -65,11 +318,14 @@ impl DrawingOverlay {
                 height,
                 depth_or_array_layers: 1,
             },
-            mip_level_count: 1,
+            mip_level_count,
             sample_count: 1,
             dimension: wgpu::TextureDimension::D2,
             format: wgpu::TextureFormat::Rgba8UnormSrgb,
-            usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
+            usage: wgpu::TextureUsages::TEXTURE_BINDING
+                | wgpu::TextureUsages::COPY_DST
+                | wgpu::TextureUsages::COPY_SRC
+                | wgpu::TextureUsages::RENDER_ATTACHMENT,
             view_formats: &[],
         });
 
@@ -94,20 +350,69 @@ impl DrawingOverlay {
             },
         );
 
+        self.texture = Some(texture);
+        self.width = width;
+        self.height = height;
+        self.mip_level_count = mip_level_count;
+        self.mipmaps_generated = false;
+
+        self.apply_sampling(device, queue, bind_group_layout);
+        Ok(())
+    }
+
+    /// Decode an encoded image (PNG/JPEG) and upload it as the overlay texture
+    ///
+    /// Any size is accepted; images larger than the device's maximum texture
+    /// size are downscaled to fit.
+    pub fn upload_image_bytes(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        bytes: &[u8],
+        bind_group_layout: &wgpu::BindGroupLayout,
+    ) -> Result<(), String> {
+        let rgba = decode_overlay_image(bytes, device.limits().max_texture_dimension_2d)?;
+        let (width, height) = rgba.dimensions();
+        self.upload_texture(device, queue, width, height, rgba.as_raw(), bind_group_layout)
+    }
+
+    /// Change the sampling settings (already validated with
+    /// [`OverlaySampling::effective`]), generating mipmaps if newly needed
+    pub fn set_sampling(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        sampling: OverlaySampling,
+        bind_group_layout: &wgpu::BindGroupLayout,
+    ) {
+        self.sampling = sampling;
+        if self.texture.is_some() {
+            self.apply_sampling(device, queue, bind_group_layout);
+        }
+    }
+
+    /// Rebuild the view, sampler and bind group for the current settings
+    fn apply_sampling(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        bind_group_layout: &wgpu::BindGroupLayout,
+    ) {
+        let Some(texture) = &self.texture else {
+            return;
+        };
+
+        if self.sampling.mipmaps && !self.mipmaps_generated {
+            generate_mipmaps(device, queue, texture, self.mip_level_count);
+            self.mipmaps_generated = true;
+        }
+
         // Create texture view
         let texture_view = texture.create_view(&wgpu::TextureViewDescriptor::default());
 
         // Create sampler
-        let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
-            label: Some(&format!("Overlay Sampler: {}", self.id)),
-            address_mode_u: wgpu::AddressMode::ClampToEdge,
-            address_mode_v: wgpu::AddressMode::ClampToEdge,
-            address_mode_w: wgpu::AddressMode::ClampToEdge,
-            mag_filter: wgpu::FilterMode::Linear,
-            min_filter: wgpu::FilterMode::Linear,
-            mipmap_filter: wgpu::FilterMode::Nearest,
-            ..Default::default()
-        });
+        let sampler_label = format!("Overlay Sampler: {}", self.id);
+        let sampler = device.create_sampler(&self.sampling.sampler_descriptor(&sampler_label));
 
         // Create bind group
         let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
@@ -125,13 +430,46 @@ impl DrawingOverlay {
             label: Some(&format!("Overlay Bind Group: {}", self.id)),
         });
 
-        self.texture = Some(texture);
         self.texture_view = Some(texture_view);
         self.sampler = Some(sampler);
         self.bind_group = Some(bind_group);
-        self.width = width;
-        self.height = height;
+    }
 
+    /// Place the overlay so two pixels of its image land on two world points
+    /// (e.g. two grid intersections clicked on the plan and on the model)
+    ///
+    /// Image pixels count from the top-left corner, down the image; the
+    /// image spans the quad with its top toward +Y. Solves the similarity
+    /// transform (uniform scale, rotation about Z, translation) the two
+    /// pairs define; the overlay sits at the points' mean height. Fails
+    /// before an image is uploaded or when either pair of points coincides.
+    pub fn align_to_points(&mut self, image_points: [(f32, f32); 2], world_points: [[f32; 3]; 2]) -> Result<(), String> {
+        if self.width == 0 || self.height == 0 {
+            return Err(format!("Overlay {} has no image", self.id));
+        }
+        // Pixel to quad-local coordinates at one world unit per pixel
+        let (half_w, half_h) = (self.width as f32 / 2.0, self.height as f32 / 2.0);
+        let local = |(u, v): (f32, f32)| (u - half_w, half_h - v);
+        let (a, b) = (local(image_points[0]), local(image_points[1]));
+        let image_delta = (b.0 - a.0, b.1 - a.1);
+        let world_delta = (world_points[1][0] - world_points[0][0], world_points[1][1] - world_points[0][1]);
+        let image_length = image_delta.0.hypot(image_delta.1);
+        let world_length = world_delta.0.hypot(world_delta.1);
+        if image_length < f32::EPSILON || world_length < f32::EPSILON {
+            return Err("Alignment points must be distinct".to_string());
+        }
+
+        let scale = world_length / image_length;
+        let rotation = world_delta.1.atan2(world_delta.0) - image_delta.1.atan2(image_delta.0);
+        let (sin_r, cos_r) = rotation.sin_cos();
+        let placed = ((a.0 * cos_r - a.1 * sin_r) * scale, (a.0 * sin_r + a.1 * cos_r) * scale);
+        self.position = [
+            world_points[0][0] - placed.0,
+            world_points[0][1] - placed.1,
+            (world_points[0][2] + world_points[1][2]) / 2.0,
+        ];
+        self.scale = [self.width as f32 * scale, self.height as f32 * scale];
+        self.rotation = rotation.rem_euclid(std::f32::consts::TAU);
         Ok(())
     }
 
@@ -182,3 +520,274 @@ impl DrawingOverlay {
         (vertices, indices)
     }
 }
+
+/// A drawing overlay's quad and texture, ready for the scene to draw
+pub struct OverlayQuad {
+    pub vertex_buffer: wgpu::Buffer,
+    pub index_buffer: wgpu::Buffer,
+    pub bind_group: wgpu::BindGroup,
+}
+
+impl OverlayQuad {
+    /// Buffers and bind group for an overlay, or None before its image is
+    /// uploaded
+    ///
+    /// Overlays are placed in Z-up model axes (rotation about Z); the quad
+    /// is converted to the renderer's Y-up axes like model meshes.
+    pub fn new(device: &wgpu::Device, drawing: &DrawingOverlay, layout: &wgpu::BindGroupLayout) -> Option<Self> {
+        let (view, sampler) = (drawing.texture_view.as_ref()?, drawing.sampler.as_ref()?);
+        let (mut vertices, indices) = drawing.generate_quad_mesh();
+        for vertex in &mut vertices {
+            let [x, y, z] = vertex.position;
+            vertex.position = [x, z, -y];
+            vertex.normal = [0.0, 1.0, 0.0];
+        }
+
+        let vertex_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some(&format!("Overlay Vertex Buffer: {}", drawing.id)),
+            contents: bytemuck::cast_slice(&vertices),
+            usage: wgpu::BufferUsages::VERTEX,
+        });
+        let index_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some(&format!("Overlay Index Buffer: {}", drawing.id)),
+            contents: bytemuck::cast_slice(&indices),
+            usage: wgpu::BufferUsages::INDEX,
+        });
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureView(view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::Sampler(sampler),
+                },
+            ],
+            label: Some(&format!("Overlay Draw Bind Group: {}", drawing.id)),
+        });
+        Some(Self {
+            vertex_buffer,
+            index_buffer,
+            bind_group,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::renderer::test_renderer;
+
+    #[test]
+    fn test_two_point_alignment_solves_similarity() {
+        let mut drawing = DrawingOverlay::new("plan".to_string());
+        assert!(drawing.align_to_points([(0.0, 0.0), (1.0, 0.0)], [[0.0; 3], [1.0, 0.0, 0.0]]).is_err());
+        drawing.width = 200;
+        drawing.height = 100;
+
+        // 0.05 world units per pixel, turned a quarter turn, centered at
+        // (10, 20): the plan's right edge runs along +Y
+        let image_points = [(100.0, 50.0), (200.0, 50.0)];
+        let world_points = [[10.0, 20.0, 1.0], [10.0, 25.0, 3.0]];
+        drawing.align_to_points(image_points, world_points).unwrap();
+        let close = |a: f32, b: f32| (a - b).abs() < 1e-4;
+        assert!(close(drawing.position[0], 10.0) && close(drawing.position[1], 20.0), "{:?}", drawing.position);
+        assert!(close(drawing.position[2], 2.0));
+        assert!(close(drawing.scale[0], 10.0) && close(drawing.scale[1], 5.0), "{:?}", drawing.scale);
+        assert!(close(drawing.rotation, std::f32::consts::FRAC_PI_2), "{}", drawing.rotation);
+
+        // The quad's corners follow: the image's top-left pixel (vertex 3)
+        let (vertices, _) = drawing.generate_quad_mesh();
+        let top_left = vertices[3].position;
+        assert!(close(top_left[0], 7.5) && close(top_left[1], 15.0), "{:?}", top_left);
+
+        // Any pair of correspondences of the same transform agrees
+        let mut other = DrawingOverlay::new("other".to_string());
+        other.width = 200;
+        other.height = 100;
+        other
+            .align_to_points([(0.0, 0.0), (200.0, 100.0)], [[7.5, 15.0, 2.0], [12.5, 25.0, 2.0]])
+            .unwrap();
+        for k in 0..3 {
+            assert!(close(other.position[k], drawing.position[k]));
+        }
+        assert!(close(other.rotation, drawing.rotation) && close(other.scale[0], drawing.scale[0]));
+
+        assert!(drawing.align_to_points([(5.0, 5.0), (5.0, 5.0)], world_points).is_err());
+    }
+
+    #[test]
+    fn test_effective_sampling() {
+        let requested = OverlaySampling {
+            linear: true,
+            mipmaps: true,
+            anisotropy: 64,
+            repeat: true,
+        };
+        assert_eq!(requested.effective(true).anisotropy, 16);
+        assert_eq!(requested.effective(false).anisotropy, 1);
+
+        let nearest = OverlaySampling { linear: false, ..requested };
+        assert_eq!(nearest.effective(true).anisotropy, 1);
+        assert!(nearest.effective(true).repeat);
+
+        assert_eq!(mip_level_count(1, 1), 1);
+        assert_eq!(mip_level_count(8, 8), 4);
+        assert_eq!(mip_level_count(1000, 3), 10);
+    }
+
+    // 3x2 RGBA PNG: red, green, blue / white, black, half-transparent gray
+    const SMALL_PNG: &[u8] = &[
+        0x89, 0x50, 0x4e, 0x47, 0x0d, 0x0a, 0x1a, 0x0a, 0x00, 0x00, 0x00, 0x0d, 0x49, 0x48, 0x44, 0x52,
+        0x00, 0x00, 0x00, 0x03, 0x00, 0x00, 0x00, 0x02, 0x08, 0x06, 0x00, 0x00, 0x00, 0x9d, 0x74, 0x66,
+        0x1a, 0x00, 0x00, 0x00, 0x19, 0x49, 0x44, 0x41, 0x54, 0x78, 0x9c, 0x63, 0xf8, 0xcf, 0xc0, 0xf0,
+        0x1f, 0x0c, 0x19, 0xfe, 0x03, 0x49, 0x20, 0x60, 0x00, 0xb2, 0x1a, 0x80, 0x00, 0x00, 0xa5, 0x79,
+        0x0c, 0xf6, 0xf8, 0x1d, 0x9d, 0x7b, 0x00, 0x00, 0x00, 0x00, 0x49, 0x45, 0x4e, 0x44, 0xae, 0x42,
+        0x60, 0x82,
+    ];
+
+    #[test]
+    fn test_decode_embedded_png() {
+        let rgba = decode_overlay_image(SMALL_PNG, 8192).unwrap();
+        assert_eq!(rgba.dimensions(), (3, 2));
+        assert_eq!(rgba.get_pixel(0, 0).0, [255, 0, 0, 255]);
+        assert_eq!(rgba.get_pixel(2, 0).0, [0, 0, 255, 255]);
+        assert_eq!(rgba.get_pixel(2, 1).0, [128, 128, 128, 128]);
+
+        // Larger than the device limit: downscaled, aspect kept
+        let rgba = decode_overlay_image(SMALL_PNG, 2).unwrap();
+        assert_eq!(rgba.dimensions(), (2, 1));
+
+        assert!(decode_overlay_image(b"not an image", 8192).is_err());
+    }
+
+    #[test]
+    fn test_upload_image_bytes_non_power_of_two() {
+        let Some(mut renderer) = test_renderer(16, 16) else {
+            eprintln!("No GPU adapter available, skipping");
+            return;
+        };
+
+        renderer.add_overlay_from_bytes("png", SMALL_PNG).unwrap();
+        let drawing = &renderer.overlays["png"];
+        assert_eq!((drawing.width, drawing.height), (3, 2));
+        assert_eq!(drawing.mip_level_count, 2);
+        assert!(drawing.bind_group.is_some());
+    }
+
+    #[test]
+    fn test_visible_overlays_are_drawn() {
+        let Some(mut renderer) = test_renderer(64, 64) else {
+            eprintln!("No GPU adapter available, skipping");
+            return;
+        };
+        let without = renderer.render_frame().unwrap();
+        let bottom_left = |frame: &[u8]| {
+            let k = (63 * 64) * 4;
+            [frame[k], frame[k + 1], frame[k + 2], frame[k + 3]]
+        };
+
+        // A large opaque red plan just below the test cube
+        renderer.add_overlay("plan", 2, 2, &[255, 0, 0, 255].repeat(4)).unwrap();
+        renderer
+            .update_overlay("plan", |drawing| {
+                drawing.position = [0.0, 0.0, -1.5];
+                drawing.scale = [100.0, 100.0];
+                drawing.opacity = 1.0;
+            })
+            .unwrap();
+        let with = renderer.render_frame().unwrap();
+        assert_eq!(bottom_left(&with), [255, 0, 0, 255]);
+
+        // Half faded: between the background and the plan
+        renderer.update_overlay("plan", |drawing| drawing.opacity = 0.5).unwrap();
+        let faded = bottom_left(&renderer.render_frame().unwrap());
+        assert!(faded[0] > bottom_left(&without)[0] && faded[0] < 255, "{:?}", faded);
+
+        renderer.update_overlay("plan", |drawing| drawing.visible = false).unwrap();
+        assert_eq!(renderer.render_frame().unwrap(), without);
+        renderer.update_overlay("plan", |drawing| drawing.visible = true).unwrap();
+        assert!(renderer.remove_overlay("plan").unwrap());
+        assert_eq!(renderer.render_frame().unwrap(), without);
+        assert!(!renderer.remove_overlay("plan").unwrap());
+        assert!(renderer.update_overlay("plan", |_| ()).is_err());
+    }
+
+    /// Read back the single pixel of a 1x1 mip level
+    fn read_pixel(device: &wgpu::Device, queue: &wgpu::Queue, texture: &wgpu::Texture, level: u32) -> [u8; 4] {
+        let buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: None,
+            size: wgpu::COPY_BYTES_PER_ROW_ALIGNMENT as u64,
+            usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+            mapped_at_creation: false,
+        });
+        let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor::default());
+        encoder.copy_texture_to_buffer(
+            wgpu::ImageCopyTexture {
+                texture,
+                mip_level: level,
+                origin: wgpu::Origin3d::ZERO,
+                aspect: wgpu::TextureAspect::All,
+            },
+            wgpu::ImageCopyBuffer {
+                buffer: &buffer,
+                layout: wgpu::ImageDataLayout {
+                    offset: 0,
+                    bytes_per_row: Some(wgpu::COPY_BYTES_PER_ROW_ALIGNMENT),
+                    rows_per_image: Some(1),
+                },
+            },
+            wgpu::Extent3d { width: 1, height: 1, depth_or_array_layers: 1 },
+        );
+        queue.submit(std::iter::once(encoder.finish()));
+
+        let slice = buffer.slice(..);
+        slice.map_async(wgpu::MapMode::Read, |_| {});
+        device.poll(wgpu::Maintain::Wait);
+        let data = slice.get_mapped_range();
+        [data[0], data[1], data[2], data[3]]
+    }
+
+    #[test]
+    fn test_overlay_mipmaps_generated_on_demand() {
+        let Some(mut renderer) = test_renderer(16, 16) else {
+            eprintln!("No GPU adapter available, skipping");
+            return;
+        };
+
+        // Solid 8x8 red image: every mip level averages to the same red
+        let rgba: Vec<u8> = [200u8, 0, 0, 255].repeat(64);
+        renderer.add_overlay("plan", 8, 8, &rgba).unwrap();
+        {
+            let drawing = &renderer.overlays["plan"];
+            assert_eq!(drawing.mip_level_count, 4);
+            assert!(!drawing.mipmaps_generated);
+        }
+
+        let applied = renderer
+            .set_overlay_sampling(
+                "plan",
+                OverlaySampling {
+                    mipmaps: true,
+                    anisotropy: 8,
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+        assert!(applied.anisotropy == 1 || applied.anisotropy == 8);
+
+        let drawing = &renderer.overlays["plan"];
+        assert!(drawing.mipmaps_generated);
+        assert_eq!(drawing.sampling, applied);
+
+        let device = renderer.gpu.device().unwrap();
+        let queue = renderer.gpu.queue().unwrap();
+        let pixel = read_pixel(device, queue, drawing.texture.as_ref().unwrap(), 3);
+        assert!((pixel[0] as i32 - 200).abs() <= 2, "smallest mip level: {:?}", pixel);
+        assert_eq!(pixel[3], 255);
+
+        assert!(renderer.set_overlay_sampling("missing", OverlaySampling::default()).is_err());
+    }
+}
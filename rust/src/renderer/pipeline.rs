@@ -2,7 +2,11 @@
 //!
 //! Manages shader compilation and render pipeline configuration.
 
+use super::lines::LineSegment;
+use super::overlay::create_overlay_bind_group_layout;
+use super::points::CloudPoint;
 use super::vertex::Vertex;
+use serde::{Deserialize, Serialize};
 
 /// Vertex shader (WGSL)
 const VERTEX_SHADER: &str = r#"
@@ -10,15 +14,18 @@ struct CameraUniform {
     view_proj: mat4x4<f32>,
     camera_pos: vec3<f32>,
     _padding: f32,
+    viewport: vec2<f32>,
+    line_width: f32,
+    _padding2: f32,
 };
 
 struct LightUniform {
     direction: vec3<f32>,
-    _padding1: f32,
+    exposure: f32,
     color: vec3<f32>,
     intensity: f32,
     ambient: vec3<f32>,
-    _padding2: f32,
+    tone_mapping: f32,
 };
 
 @group(0) @binding(0)
@@ -37,11 +44,10 @@ struct VertexOutput {
     @builtin(position) clip_position: vec4<f32>,
     @location(0) color: vec4<f32>,
     @location(1) normal: vec3<f32>,
-    @location(2) world_pos: vec3<f32>,
+    @location(2) @interpolate(perspective, centroid) world_pos: vec3<f32>,
 };
 
-@vertex
-fn vs_main(model: VertexInput) -> VertexOutput {
+fn transform(model: VertexInput) -> VertexOutput {
     var out: VertexOutput;
     out.clip_position = camera.view_proj * vec4<f32>(model.position, 1.0);
     out.color = model.color;
@@ -49,17 +55,161 @@ fn vs_main(model: VertexInput) -> VertexOutput {
     out.world_pos = model.position;
     return out;
 }
+
+@vertex
+fn vs_main(model: VertexInput) -> VertexOutput {
+    return transform(model);
+}
+"#;
+
+/// Line vertex shader (WGSL): expands each segment instance into a
+/// screen-space quad `line_width` pixels wide (see `lines::expand_segment`)
+const LINE_VERTEX_SHADER: &str = r#"
+struct CameraUniform {
+    view_proj: mat4x4<f32>,
+    camera_pos: vec3<f32>,
+    _padding: f32,
+    viewport: vec2<f32>,
+    line_width: f32,
+    _padding2: f32,
+};
+
+@group(0) @binding(0)
+var<uniform> camera: CameraUniform;
+
+struct SegmentInput {
+    @location(0) start: vec3<f32>,
+    @location(1) end: vec3<f32>,
+    @location(2) normal: vec3<f32>,
+    @location(3) color: vec4<f32>,
+};
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) color: vec4<f32>,
+    @location(1) normal: vec3<f32>,
+    @location(2) @interpolate(perspective, centroid) world_pos: vec3<f32>,
+};
+
+@vertex
+fn vs_line_quad(@builtin(vertex_index) index: u32, segment: SegmentInput) -> VertexOutput {
+    // x picks the end of the segment, y the side of the line
+    var corners = array<vec2<f32>, 6>(
+        vec2<f32>(0.0, -1.0), vec2<f32>(1.0, -1.0), vec2<f32>(1.0, 1.0),
+        vec2<f32>(0.0, -1.0), vec2<f32>(1.0, 1.0), vec2<f32>(0.0, 1.0),
+    );
+    let corner = corners[index % 6u];
+
+    var out: VertexOutput;
+    out.color = segment.color;
+    out.normal = segment.normal;
+    var start = camera.view_proj * vec4<f32>(segment.start, 1.0);
+    var end = camera.view_proj * vec4<f32>(segment.end, 1.0);
+    var world_start = segment.start;
+    var world_end = segment.end;
+
+    // Clip to the near plane so both ends project in front of the camera
+    if (start.z < 0.0 && end.z < 0.0) {
+        out.clip_position = vec4<f32>(0.0, 0.0, 0.0, 0.0);
+        out.world_pos = world_start;
+        return out;
+    }
+    if (start.z < 0.0) {
+        let t = start.z / (start.z - end.z);
+        start = mix(start, end, t);
+        world_start = mix(world_start, world_end, t);
+    }
+    if (end.z < 0.0) {
+        let t = end.z / (end.z - start.z);
+        end = mix(end, start, t);
+        world_end = mix(world_end, world_start, t);
+    }
+
+    let half_viewport = camera.viewport * 0.5;
+    let along = (end.xy / end.w - start.xy / start.w) * half_viewport;
+    var dir = vec2<f32>(1.0, 0.0);
+    if (length(along) > 1e-6) {
+        dir = normalize(along);
+    }
+
+    // Sideways by half the width, outwards by half the width (square cap)
+    let offset = (vec2<f32>(-dir.y, dir.x) * corner.y + dir * (corner.x * 2.0 - 1.0)) * camera.line_width * 0.5;
+    var clip = select(start, end, corner.x > 0.5);
+    clip = vec4<f32>(clip.xy + offset / half_viewport * clip.w, clip.zw);
+    // Nudged towards the camera so lines on a face win the depth test
+    // against that face
+    clip.z = clip.z - 0.0005 * clip.w;
+
+    out.clip_position = clip;
+    out.world_pos = select(world_start, world_end, corner.x > 0.5);
+    return out;
+}
+"#;
+
+/// Point cloud vertex shader (WGSL): points as a point list, or expanded
+/// from instances into screen-space squares `point_size` pixels across
+const POINT_VERTEX_SHADER: &str = r#"
+struct CameraUniform {
+    view_proj: mat4x4<f32>,
+    camera_pos: vec3<f32>,
+    _padding: f32,
+    viewport: vec2<f32>,
+    line_width: f32,
+    point_size: f32,
+};
+
+@group(0) @binding(0)
+var<uniform> camera: CameraUniform;
+
+struct PointInput {
+    @location(0) position: vec3<f32>,
+    @location(1) color: vec4<f32>,
+};
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) color: vec4<f32>,
+    @location(1) normal: vec3<f32>,
+    @location(2) @interpolate(perspective, centroid) world_pos: vec3<f32>,
+};
+
+fn project_point(point: PointInput) -> VertexOutput {
+    var out: VertexOutput;
+    out.clip_position = camera.view_proj * vec4<f32>(point.position, 1.0);
+    out.color = point.color;
+    out.normal = vec3<f32>(0.0, 1.0, 0.0);
+    out.world_pos = point.position;
+    return out;
+}
+
+@vertex
+fn vs_point(point: PointInput) -> VertexOutput {
+    return project_point(point);
+}
+
+@vertex
+fn vs_point_quad(@builtin(vertex_index) index: u32, point: PointInput) -> VertexOutput {
+    var corners = array<vec2<f32>, 6>(
+        vec2<f32>(-1.0, -1.0), vec2<f32>(1.0, -1.0), vec2<f32>(1.0, 1.0),
+        vec2<f32>(-1.0, -1.0), vec2<f32>(1.0, 1.0), vec2<f32>(-1.0, 1.0),
+    );
+    var out = project_point(point);
+    let offset = corners[index % 6u] * camera.point_size * 0.5;
+    let clip = out.clip_position;
+    out.clip_position = vec4<f32>(clip.xy + offset / (camera.viewport * 0.5) * clip.w, clip.zw);
+    return out;
+}
 "#;
 
 /// Fragment shader (WGSL) - optimized for mobile
 const FRAGMENT_SHADER: &str = r#"
 struct LightUniform {
     direction: vec3<f32>,
-    _padding1: f32,
+    exposure: f32,
     color: vec3<f32>,
     intensity: f32,
     ambient: vec3<f32>,
-    _padding2: f32,
+    tone_mapping: f32,
 };
 
 @group(0) @binding(1)
@@ -69,29 +219,84 @@ struct SectionPlaneUniform {
     origin: vec3<f32>,
     enabled: f32,
     normal: vec3<f32>,
-    _padding: f32,
+    outline_width: f32,
+    outline_color: vec4<f32>,
 };
 
 @group(0) @binding(2)
 var<uniform> section_plane: SectionPlaneUniform;
 
+struct ClipBoxUniform {
+    min: vec3<f32>,
+    enabled: f32,
+    max: vec3<f32>,
+    _padding: f32,
+};
+
+@group(0) @binding(3)
+var<uniform> clip_box: ClipBoxUniform;
+
 struct VertexOutput {
     @builtin(position) clip_position: vec4<f32>,
     @location(0) color: vec4<f32>,
     @location(1) normal: vec3<f32>,
-    @location(2) world_pos: vec3<f32>,
+    @location(2) @interpolate(perspective, centroid) world_pos: vec3<f32>,
 };
 
-@fragment
-fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
-    // Section plane clipping
+// world_pos is interpolated at the centroid of the covered samples, so with
+// MSAA partly covered edge pixels are clipped where they are covered rather
+// than at an extrapolated pixel center
+fn is_clipped(world_pos: vec3<f32>) -> bool {
     if (section_plane.enabled > 0.5) {
-        let to_point = in.world_pos - section_plane.origin;
-        let distance = dot(to_point, section_plane.normal);
-        if (distance < 0.0) {
-            discard;
+        let to_point = world_pos - section_plane.origin;
+        if (dot(to_point, section_plane.normal) < 0.0) {
+            return true;
+        }
+    }
+    if (clip_box.enabled > 0.5) {
+        if (any(world_pos < clip_box.min) || any(world_pos > clip_box.max)) {
+            return true;
         }
     }
+    return false;
+}
+
+// How much of the section outline covers a fragment: 1 within
+// outline_width pixels of the cut, falling off over one pixel at the inner
+// edge so the boundary with the surface is antialiased
+fn section_outline(world_pos: vec3<f32>) -> f32 {
+    // Derivatives before any discard, so control flow is still uniform
+    let distance = dot(world_pos - section_plane.origin, section_plane.normal);
+    let pixels = distance / max(fwidth(distance), 1e-6);
+    if (section_plane.enabled < 0.5 || section_plane.outline_width <= 0.0) {
+        return 0.0;
+    }
+    let width = section_plane.outline_width;
+    return 1.0 - smoothstep(width - 1.0, width, pixels);
+}
+
+// Map linear HDR color into 0-1; stays linear, the sRGB target encodes gamma
+fn tone_map(color: vec3<f32>) -> vec3<f32> {
+    if (light.tone_mapping > 1.5) {
+        // ACES filmic curve fit (Narkowicz 2015)
+        let a = color * (2.51 * color + 0.03);
+        let b = color * (2.43 * color + 0.59) + 0.14;
+        return clamp(a / b, vec3<f32>(0.0), vec3<f32>(1.0));
+    }
+    if (light.tone_mapping > 0.5) {
+        return color / (vec3<f32>(1.0) + color);
+    }
+    return color;
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    let outline = section_outline(in.world_pos);
+
+    // Section plane and clip box clipping
+    if (is_clipped(in.world_pos)) {
+        discard;
+    }
 
     // Simple diffuse + ambient lighting (fast)
     let normal = normalize(in.normal);
@@ -100,8 +305,122 @@ fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
     let ambient = light.ambient * in.color.rgb;
     let diffuse = diff * light.color * light.intensity * in.color.rgb;
 
-    let result = ambient + diffuse;
-    return vec4<f32>(result, in.color.a);
+    let result = tone_map((ambient + diffuse) * light.exposure);
+    let outlined = mix(result, section_plane.outline_color.rgb, outline * section_plane.outline_color.a);
+    return vec4<f32>(outlined, in.color.a);
+}
+
+// Edge overlay for shaded-with-edges mode: a darkened, unlit element color
+@fragment
+fn fs_edges(in: VertexOutput) -> @location(0) vec4<f32> {
+    if (is_clipped(in.world_pos)) {
+        discard;
+    }
+
+    return vec4<f32>(in.color.rgb * 0.25, 1.0);
+}
+
+// Point clouds: unlit, clipped like the model
+@fragment
+fn fs_points(in: VertexOutput) -> @location(0) vec4<f32> {
+    if (is_clipped(in.world_pos)) {
+        discard;
+    }
+
+    return in.color;
+}
+
+// Measurement annotations: unlit and never clipped, so they stay readable
+@fragment
+fn fs_annotation(in: VertexOutput) -> @location(0) vec4<f32> {
+    return in.color;
+}
+"#;
+
+/// Drawing overlay shader (WGSL): a quad textured with its image, faded by
+/// the vertex alpha and clipped like the model
+const DRAWING_SHADER: &str = r#"
+struct CameraUniform {
+    view_proj: mat4x4<f32>,
+    camera_pos: vec3<f32>,
+    _padding: f32,
+    viewport: vec2<f32>,
+    line_width: f32,
+    _padding2: f32,
+};
+
+@group(0) @binding(0)
+var<uniform> camera: CameraUniform;
+
+struct SectionPlaneUniform {
+    origin: vec3<f32>,
+    enabled: f32,
+    normal: vec3<f32>,
+    outline_width: f32,
+    outline_color: vec4<f32>,
+};
+
+@group(0) @binding(2)
+var<uniform> section_plane: SectionPlaneUniform;
+
+struct ClipBoxUniform {
+    min: vec3<f32>,
+    enabled: f32,
+    max: vec3<f32>,
+    _padding: f32,
+};
+
+@group(0) @binding(3)
+var<uniform> clip_box: ClipBoxUniform;
+
+@group(1) @binding(0)
+var drawing: texture_2d<f32>;
+@group(1) @binding(1)
+var drawing_sampler: sampler;
+
+struct VertexInput {
+    @location(0) position: vec3<f32>,
+    @location(1) normal: vec3<f32>,
+    @location(2) color: vec4<f32>,
+};
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) color: vec4<f32>,
+    @location(1) uv: vec2<f32>,
+    @location(2) world_pos: vec3<f32>,
+};
+
+@vertex
+fn vs_drawing(@builtin(vertex_index) index: u32, model: VertexInput) -> VertexOutput {
+    // Quad corners in order: bottom-left, bottom-right, top-right, top-left;
+    // image rows run from the top
+    var uvs = array<vec2<f32>, 4>(
+        vec2<f32>(0.0, 1.0), vec2<f32>(1.0, 1.0), vec2<f32>(1.0, 0.0), vec2<f32>(0.0, 0.0),
+    );
+    var out: VertexOutput;
+    out.clip_position = camera.view_proj * vec4<f32>(model.position, 1.0);
+    out.color = model.color;
+    out.uv = uvs[index % 4u];
+    out.world_pos = model.position;
+    return out;
+}
+
+fn is_clipped(world_pos: vec3<f32>) -> bool {
+    if (section_plane.enabled > 0.5 && dot(world_pos - section_plane.origin, section_plane.normal) < 0.0) {
+        return true;
+    }
+    return clip_box.enabled > 0.5 && (any(world_pos < clip_box.min) || any(world_pos > clip_box.max));
+}
+
+@fragment
+fn fs_drawing(in: VertexOutput) -> @location(0) vec4<f32> {
+    // Sampled before any discard, so control flow is still uniform
+    let texel = textureSample(drawing, drawing_sampler, in.uv);
+    if (is_clipped(in.world_pos)) {
+        discard;
+    }
+    return vec4<f32>(texel.rgb, texel.a * in.color.a);
 }
 "#;
 
@@ -111,32 +430,120 @@ pub enum RenderMode {
     #[default]
     Shaded,
     Wireframe,
+    /// Shaded fill with element edges drawn on top
+    ShadedWithEdges,
+    /// Shaded fill with only feature edges (creases, outlines) drawn on top
+    ShadedWithFeatureEdges,
+}
+
+/// A single draw of the scene geometry within a frame
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DrawPass {
+    /// Lit, filled triangles
+    Fill,
+    /// Triangle outlines only (wireframe mode)
+    Lines,
+    /// Outlines over an existing fill, depth-biased towards the camera
+    Edges,
+    /// Feature edge line list over an existing fill
+    FeatureEdges,
+}
+
+impl RenderMode {
+    /// Draws issued per frame for this mode, in order
+    pub fn draw_passes(self) -> &'static [DrawPass] {
+        match self {
+            RenderMode::Shaded => &[DrawPass::Fill],
+            RenderMode::Wireframe => &[DrawPass::Lines],
+            RenderMode::ShadedWithEdges => &[DrawPass::Fill, DrawPass::Edges],
+            RenderMode::ShadedWithFeatureEdges => &[DrawPass::Fill, DrawPass::FeatureEdges],
+        }
+    }
+}
+
+/// Depth bias pulling edge lines towards the camera so they win the depth
+/// test against the fill they were drawn from (no z-fighting)
+const EDGE_DEPTH_BIAS: wgpu::DepthBiasState = wgpu::DepthBiasState {
+    constant: -2,
+    slope_scale: -1.0,
+    clamp: 0.0,
+};
+
+/// Depth bias of geometry drawn on model surfaces (annotation fills and lines)
+///
+/// Negative values pull it towards the camera: `constant` in depth buffer
+/// units, `slope_scale` times the surface's depth slope, so coplanar overlays
+/// stay on top at grazing angles too.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct DepthBias {
+    pub constant: i32,
+    pub slope_scale: f32,
 }
 
-/// MSAA sample count (1 = disabled, 4 = 4x MSAA)
-/// Using 1 for mobile performance - can increase on desktop
+/// Default overlay bias: enough to keep coplanar decals stable at any angle
+pub const DEFAULT_OVERLAY_DEPTH_BIAS: DepthBias = DepthBias {
+    constant: -4,
+    slope_scale: -2.0,
+};
+
+impl Default for DepthBias {
+    fn default() -> Self {
+        DEFAULT_OVERLAY_DEPTH_BIAS
+    }
+}
+
+impl DepthBias {
+    fn state(self) -> wgpu::DepthBiasState {
+        wgpu::DepthBiasState {
+            constant: self.constant,
+            slope_scale: self.slope_scale,
+            clamp: 0.0,
+        }
+    }
+}
+
+/// Default MSAA sample count (1 = disabled, 4 = 4x MSAA)
+/// Using 1 for mobile performance - can be raised at runtime with
+/// `Renderer::set_msaa_samples`
 pub const MSAA_SAMPLE_COUNT: u32 = 1;
 
 /// Render pipeline wrapper
 pub struct RenderPipeline {
     pub pipeline: wgpu::RenderPipeline,
-    pub wireframe_pipeline: Option<wgpu::RenderPipeline>,
+    /// Lit line quads over every triangle edge
+    pub wireframe_pipeline: wgpu::RenderPipeline,
+    /// Unlit line quads over an existing fill (element and feature edges)
+    pub edges_pipeline: wgpu::RenderPipeline,
+    /// Depth-tested fill that writes nothing (re-testing occluded elements)
+    pub occlusion_probe_pipeline: wgpu::RenderPipeline,
+    /// Unlit line quads for measurement annotations
+    pub annotation_pipeline: wgpu::RenderPipeline,
+    /// Unlit translucent triangles on model surfaces (annotation area fills)
+    pub decal_pipeline: wgpu::RenderPipeline,
+    /// Point clouds as one-pixel points
+    pub point_pipeline: wgpu::RenderPipeline,
+    /// Point clouds as squares expanded from point instances
+    pub point_quad_pipeline: wgpu::RenderPipeline,
+    /// Textured translucent quads of drawing overlays
+    pub drawing_pipeline: wgpu::RenderPipeline,
     pub camera_bind_group_layout: wgpu::BindGroupLayout,
+    /// Layout of a drawing overlay's texture and sampler (group 1)
+    pub drawing_bind_group_layout: wgpu::BindGroupLayout,
+    // Kept to rebuild the overlay pipelines when their depth bias changes
+    pipeline_layout: wgpu::PipelineLayout,
+    drawing_pipeline_layout: wgpu::PipelineLayout,
+    drawing_shader: wgpu::ShaderModule,
+    vertex_shader: wgpu::ShaderModule,
+    line_vertex_shader: wgpu::ShaderModule,
+    fragment_shader: wgpu::ShaderModule,
+    surface_format: wgpu::TextureFormat,
+    sample_count: u32,
+    overlay_depth_bias: DepthBias,
 }
 
 impl RenderPipeline {
-    /// Create a new render pipeline
-    /// If wireframe_supported is true, creates a wireframe pipeline as well
-    pub fn new(device: &wgpu::Device, surface_format: wgpu::TextureFormat) -> Self {
-        Self::new_with_features(device, surface_format, false)
-    }
-
-    /// Create a new render pipeline with optional wireframe support
-    pub fn new_with_features(
-        device: &wgpu::Device,
-        surface_format: wgpu::TextureFormat,
-        wireframe_supported: bool,
-    ) -> Self {
+    /// Create a new render pipeline drawing `sample_count` samples per pixel
+    pub fn new(device: &wgpu::Device, surface_format: wgpu::TextureFormat, sample_count: u32) -> Self {
         // Create shader modules
         let vertex_shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
             label: Some("Vertex Shader"),
@@ -148,7 +555,7 @@ impl RenderPipeline {
             source: wgpu::ShaderSource::Wgsl(FRAGMENT_SHADER.into()),
         });
 
-        // Create bind group layout for camera, light, and section plane uniforms
+        // Create bind group layout for camera, light, section plane and clip box uniforms
         let camera_bind_group_layout =
             device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
                 entries: &[
@@ -185,6 +592,17 @@ impl RenderPipeline {
                         },
                         count: None,
                     },
+                    // Clip box uniform
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 3,
+                        visibility: wgpu::ShaderStages::FRAGMENT,
+                        ty: wgpu::BindingType::Buffer {
+                            ty: wgpu::BufferBindingType::Uniform,
+                            has_dynamic_offset: false,
+                            min_binding_size: None,
+                        },
+                        count: None,
+                    },
                 ],
                 label: Some("Camera Bind Group Layout"),
             });
@@ -231,26 +649,106 @@ impl RenderPipeline {
                 bias: wgpu::DepthBiasState::default(),
             }),
             multisample: wgpu::MultisampleState {
-                count: MSAA_SAMPLE_COUNT,
+                count: sample_count,
                 mask: !0,
                 alpha_to_coverage_enabled: false,
             },
             multiview: None,
         });
 
-        // Create wireframe pipeline only if the feature is supported
-        let wireframe_pipeline = if wireframe_supported {
-            Some(device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
-                label: Some("Wireframe Pipeline"),
+        // Occlusion probes: fill geometry depth-tested against the frame so
+        // far, writing nothing, so only the query sample count is affected
+        let occlusion_probe_pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Occlusion Probe Pipeline"),
+            layout: Some(&pipeline_layout),
+            vertex: wgpu::VertexState {
+                module: &vertex_shader,
+                entry_point: "vs_main",
+                buffers: &[Vertex::desc()],
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &fragment_shader,
+                entry_point: "fs_main",
+                targets: &[Some(wgpu::ColorTargetState {
+                    format: surface_format,
+                    blend: None,
+                    write_mask: wgpu::ColorWrites::empty(),
+                })],
+            }),
+            primitive: wgpu::PrimitiveState {
+                topology: wgpu::PrimitiveTopology::TriangleList,
+                strip_index_format: None,
+                front_face: wgpu::FrontFace::Ccw,
+                cull_mode: Some(wgpu::Face::Back),
+                polygon_mode: wgpu::PolygonMode::Fill,
+                unclipped_depth: false,
+                conservative: false,
+            },
+            depth_stencil: Some(wgpu::DepthStencilState {
+                format: wgpu::TextureFormat::Depth32Float,
+                depth_write_enabled: false,
+                depth_compare: wgpu::CompareFunction::Less,
+                stencil: wgpu::StencilState::default(),
+                bias: wgpu::DepthBiasState::default(),
+            }),
+            multisample: wgpu::MultisampleState {
+                count: sample_count,
+                mask: !0,
+                alpha_to_coverage_enabled: false,
+            },
+            multiview: None,
+        });
+
+        let line_vertex_shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Line Vertex Shader"),
+            source: wgpu::ShaderSource::Wgsl(LINE_VERTEX_SHADER.into()),
+        });
+
+        let overlay_depth_bias = DepthBias::default();
+        let (annotation_pipeline, decal_pipeline) = create_overlay_pipelines(
+            device,
+            &pipeline_layout,
+            &vertex_shader,
+            &line_vertex_shader,
+            &fragment_shader,
+            surface_format,
+            sample_count,
+            overlay_depth_bias,
+        );
+
+        let drawing_shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Drawing Shader"),
+            source: wgpu::ShaderSource::Wgsl(DRAWING_SHADER.into()),
+        });
+        let drawing_bind_group_layout = create_overlay_bind_group_layout(device);
+        let drawing_pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Drawing Pipeline Layout"),
+            bind_group_layouts: &[&camera_bind_group_layout, &drawing_bind_group_layout],
+            push_constant_ranges: &[],
+        });
+        let drawing_pipeline = create_drawing_pipeline(
+            device,
+            &drawing_pipeline_layout,
+            &drawing_shader,
+            surface_format,
+            sample_count,
+            overlay_depth_bias,
+        );
+
+        // Lines are segment instances expanded into quads, so any device
+        // draws them at any width
+        let line_pipeline = |label: &str, entry_point: &str, depth: wgpu::DepthStencilState| {
+            device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+                label: Some(label),
                 layout: Some(&pipeline_layout),
                 vertex: wgpu::VertexState {
-                    module: &vertex_shader,
-                    entry_point: "vs_main",
-                    buffers: &[Vertex::desc()],
+                    module: &line_vertex_shader,
+                    entry_point: "vs_line_quad",
+                    buffers: &[LineSegment::desc()],
                 },
                 fragment: Some(wgpu::FragmentState {
                     module: &fragment_shader,
-                    entry_point: "fs_main",
+                    entry_point,
                     targets: &[Some(wgpu::ColorTargetState {
                         format: surface_format,
                         blend: Some(wgpu::BlendState::REPLACE),
@@ -261,8 +759,69 @@ impl RenderPipeline {
                     topology: wgpu::PrimitiveTopology::TriangleList,
                     strip_index_format: None,
                     front_face: wgpu::FrontFace::Ccw,
-                    cull_mode: None, // No culling for wireframe
-                    polygon_mode: wgpu::PolygonMode::Line,
+                    cull_mode: None, // Quads face either way
+                    polygon_mode: wgpu::PolygonMode::Fill,
+                    unclipped_depth: false,
+                    conservative: false,
+                },
+                depth_stencil: Some(depth),
+                multisample: wgpu::MultisampleState {
+                    count: sample_count,
+                    mask: !0,
+                    alpha_to_coverage_enabled: false,
+                },
+                multiview: None,
+            })
+        };
+
+        let wireframe_pipeline = line_pipeline(
+            "Wireframe Pipeline",
+            "fs_main",
+            wgpu::DepthStencilState {
+                format: wgpu::TextureFormat::Depth32Float,
+                depth_write_enabled: true,
+                depth_compare: wgpu::CompareFunction::Less,
+                stencil: wgpu::StencilState::default(),
+                bias: wgpu::DepthBiasState::default(),
+            },
+        );
+        // Edges test against the shaded depth but leave it untouched
+        let edges_pipeline = line_pipeline(
+            "Edges Pipeline",
+            "fs_edges",
+            wgpu::DepthStencilState {
+                format: wgpu::TextureFormat::Depth32Float,
+                depth_write_enabled: false,
+                depth_compare: wgpu::CompareFunction::LessEqual,
+                stencil: wgpu::StencilState::default(),
+                bias: EDGE_DEPTH_BIAS,
+            },
+        );
+
+        let point_vertex_shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Point Vertex Shader"),
+            source: wgpu::ShaderSource::Wgsl(POINT_VERTEX_SHADER.into()),
+        });
+        let point_pipeline = |label: &str, vertex: wgpu::VertexState, topology: wgpu::PrimitiveTopology| {
+            device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+                label: Some(label),
+                layout: Some(&pipeline_layout),
+                vertex,
+                fragment: Some(wgpu::FragmentState {
+                    module: &fragment_shader,
+                    entry_point: "fs_points",
+                    targets: &[Some(wgpu::ColorTargetState {
+                        format: surface_format,
+                        blend: Some(wgpu::BlendState::REPLACE),
+                        write_mask: wgpu::ColorWrites::ALL,
+                    })],
+                }),
+                primitive: wgpu::PrimitiveState {
+                    topology,
+                    strip_index_format: None,
+                    front_face: wgpu::FrontFace::Ccw,
+                    cull_mode: None,
+                    polygon_mode: wgpu::PolygonMode::Fill,
                     unclipped_depth: false,
                     conservative: false,
                 },
@@ -274,28 +833,299 @@ impl RenderPipeline {
                     bias: wgpu::DepthBiasState::default(),
                 }),
                 multisample: wgpu::MultisampleState {
-                    count: MSAA_SAMPLE_COUNT,
+                    count: sample_count,
                     mask: !0,
                     alpha_to_coverage_enabled: false,
                 },
                 multiview: None,
-            }))
-        } else {
-            None
+            })
         };
+        let point_quad_pipeline = point_pipeline(
+            "Point Quad Pipeline",
+            wgpu::VertexState {
+                module: &point_vertex_shader,
+                entry_point: "vs_point_quad",
+                buffers: &[CloudPoint::desc(wgpu::VertexStepMode::Instance)],
+            },
+            wgpu::PrimitiveTopology::TriangleList,
+        );
+        let point_pipeline = point_pipeline(
+            "Point Pipeline",
+            wgpu::VertexState {
+                module: &point_vertex_shader,
+                entry_point: "vs_point",
+                buffers: &[CloudPoint::desc(wgpu::VertexStepMode::Vertex)],
+            },
+            wgpu::PrimitiveTopology::PointList,
+        );
 
         Self {
             pipeline,
             wireframe_pipeline,
+            edges_pipeline,
+            occlusion_probe_pipeline,
+            annotation_pipeline,
+            decal_pipeline,
+            point_pipeline,
+            point_quad_pipeline,
+            drawing_pipeline,
             camera_bind_group_layout,
+            drawing_bind_group_layout,
+            pipeline_layout,
+            drawing_pipeline_layout,
+            drawing_shader,
+            vertex_shader,
+            line_vertex_shader,
+            fragment_shader,
+            surface_format,
+            sample_count,
+            overlay_depth_bias,
         }
     }
 
+    /// Samples per pixel the pipelines draw
+    pub fn sample_count(&self) -> u32 {
+        self.sample_count
+    }
+
+    /// Depth bias of the annotation, decal and drawing pipelines
+    pub fn overlay_depth_bias(&self) -> DepthBias {
+        self.overlay_depth_bias
+    }
+
+    /// Rebuild the annotation, decal and drawing pipelines with a new depth bias
+    pub fn set_overlay_depth_bias(&mut self, device: &wgpu::Device, bias: DepthBias) {
+        (self.annotation_pipeline, self.decal_pipeline) = create_overlay_pipelines(
+            device,
+            &self.pipeline_layout,
+            &self.vertex_shader,
+            &self.line_vertex_shader,
+            &self.fragment_shader,
+            self.surface_format,
+            self.sample_count,
+            bias,
+        );
+        self.drawing_pipeline = create_drawing_pipeline(
+            device,
+            &self.drawing_pipeline_layout,
+            &self.drawing_shader,
+            self.surface_format,
+            self.sample_count,
+            bias,
+        );
+        self.overlay_depth_bias = bias;
+    }
+
     /// Get the appropriate pipeline for the render mode
     pub fn get_pipeline(&self, mode: RenderMode) -> &wgpu::RenderPipeline {
         match mode {
             RenderMode::Shaded => &self.pipeline,
-            RenderMode::Wireframe => self.wireframe_pipeline.as_ref().unwrap_or(&self.pipeline),
+            RenderMode::Wireframe => &self.wireframe_pipeline,
+            RenderMode::ShadedWithEdges | RenderMode::ShadedWithFeatureEdges => &self.pipeline,
         }
     }
+
+    /// Get the pipelines to draw with for the render mode, in order
+    pub fn draw_pipelines(&self, mode: RenderMode) -> Vec<(DrawPass, &wgpu::RenderPipeline)> {
+        mode.draw_passes()
+            .iter()
+            .map(|pass| {
+                let pipeline = match pass {
+                    DrawPass::Fill => &self.pipeline,
+                    DrawPass::Lines => &self.wireframe_pipeline,
+                    DrawPass::Edges | DrawPass::FeatureEdges => &self.edges_pipeline,
+                };
+                (*pass, pipeline)
+            })
+            .collect()
+    }
+}
+
+/// Create the pipelines for geometry drawn over model surfaces: annotation
+/// lines and decal triangles
+///
+/// Both are unlit, alpha blended and depth tested without writing depth.
+/// Line quads also get the clip-space offset of the line shader.
+#[allow(clippy::too_many_arguments)]
+fn create_overlay_pipelines(
+    device: &wgpu::Device,
+    layout: &wgpu::PipelineLayout,
+    vertex_shader: &wgpu::ShaderModule,
+    line_vertex_shader: &wgpu::ShaderModule,
+    fragment_shader: &wgpu::ShaderModule,
+    surface_format: wgpu::TextureFormat,
+    sample_count: u32,
+    bias: DepthBias,
+) -> (wgpu::RenderPipeline, wgpu::RenderPipeline) {
+    let create = |label: &str, vertex: wgpu::VertexState| {
+        device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some(label),
+            layout: Some(layout),
+            vertex,
+            fragment: Some(wgpu::FragmentState {
+                module: fragment_shader,
+                entry_point: "fs_annotation",
+                targets: &[Some(wgpu::ColorTargetState {
+                    format: surface_format,
+                    blend: Some(wgpu::BlendState::ALPHA_BLENDING),
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+            }),
+            primitive: wgpu::PrimitiveState {
+                topology: wgpu::PrimitiveTopology::TriangleList,
+                strip_index_format: None,
+                front_face: wgpu::FrontFace::Ccw,
+                cull_mode: None,
+                polygon_mode: wgpu::PolygonMode::Fill,
+                unclipped_depth: false,
+                conservative: false,
+            },
+            depth_stencil: Some(wgpu::DepthStencilState {
+                format: wgpu::TextureFormat::Depth32Float,
+                depth_write_enabled: false,
+                depth_compare: wgpu::CompareFunction::LessEqual,
+                stencil: wgpu::StencilState::default(),
+                bias: bias.state(),
+            }),
+            multisample: wgpu::MultisampleState {
+                count: sample_count,
+                mask: !0,
+                alpha_to_coverage_enabled: false,
+            },
+            multiview: None,
+        })
+    };
+
+    (
+        create(
+            "Annotation Pipeline",
+            wgpu::VertexState {
+                module: line_vertex_shader,
+                entry_point: "vs_line_quad",
+                buffers: &[LineSegment::desc()],
+            },
+        ),
+        create(
+            "Decal Pipeline",
+            wgpu::VertexState {
+                module: vertex_shader,
+                entry_point: "vs_main",
+                buffers: &[Vertex::desc()],
+            },
+        ),
+    )
+}
+
+/// Create the pipeline for drawing overlay quads
+///
+/// Alpha blended and depth tested without writing depth, like the other
+/// overlays, so a plan laid on a slab stays visible on top of it.
+fn create_drawing_pipeline(
+    device: &wgpu::Device,
+    layout: &wgpu::PipelineLayout,
+    shader: &wgpu::ShaderModule,
+    surface_format: wgpu::TextureFormat,
+    sample_count: u32,
+    bias: DepthBias,
+) -> wgpu::RenderPipeline {
+    device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+        label: Some("Drawing Pipeline"),
+        layout: Some(layout),
+        vertex: wgpu::VertexState {
+            module: shader,
+            entry_point: "vs_drawing",
+            buffers: &[Vertex::desc()],
+        },
+        fragment: Some(wgpu::FragmentState {
+            module: shader,
+            entry_point: "fs_drawing",
+            targets: &[Some(wgpu::ColorTargetState {
+                format: surface_format,
+                blend: Some(wgpu::BlendState::ALPHA_BLENDING),
+                write_mask: wgpu::ColorWrites::ALL,
+            })],
+        }),
+        primitive: wgpu::PrimitiveState {
+            topology: wgpu::PrimitiveTopology::TriangleList,
+            strip_index_format: None,
+            front_face: wgpu::FrontFace::Ccw,
+            cull_mode: None, // Seen from above and below
+            polygon_mode: wgpu::PolygonMode::Fill,
+            unclipped_depth: false,
+            conservative: false,
+        },
+        depth_stencil: Some(wgpu::DepthStencilState {
+            format: wgpu::TextureFormat::Depth32Float,
+            depth_write_enabled: false,
+            depth_compare: wgpu::CompareFunction::LessEqual,
+            stencil: wgpu::StencilState::default(),
+            bias: bias.state(),
+        }),
+        multisample: wgpu::MultisampleState {
+            count: sample_count,
+            mask: !0,
+            alpha_to_coverage_enabled: false,
+        },
+        multiview: None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::renderer::test_renderer;
+
+    #[test]
+    fn test_draw_passes() {
+        assert_eq!(RenderMode::Shaded.draw_passes(), &[DrawPass::Fill]);
+        assert_eq!(RenderMode::Wireframe.draw_passes(), &[DrawPass::Lines]);
+        assert_eq!(
+            RenderMode::ShadedWithEdges.draw_passes(),
+            &[DrawPass::Fill, DrawPass::Edges]
+        );
+        assert_eq!(
+            RenderMode::ShadedWithFeatureEdges.draw_passes(),
+            &[DrawPass::Fill, DrawPass::FeatureEdges]
+        );
+    }
+
+    #[test]
+    fn test_shaded_with_edges_draws_fill_and_lines() {
+        let Some(mut renderer) = test_renderer(64, 64) else {
+            eprintln!("No GPU adapter available, skipping");
+            return;
+        };
+        let pipeline = renderer.scene.as_ref().unwrap().pipeline.as_ref().unwrap();
+        let passes: Vec<DrawPass> = pipeline
+            .draw_pipelines(RenderMode::ShadedWithEdges)
+            .into_iter()
+            .map(|(pass, _)| pass)
+            .collect();
+        assert_eq!(passes, vec![DrawPass::Fill, DrawPass::Edges]);
+
+        // The edge draw must actually change the shaded image of the test cube
+        renderer.set_render_mode(RenderMode::Shaded).unwrap();
+        let shaded = renderer.render_frame().unwrap();
+        renderer.set_render_mode(RenderMode::ShadedWithEdges).unwrap();
+        let with_edges = renderer.render_frame().unwrap();
+        assert_eq!(shaded.len(), with_edges.len());
+        assert_ne!(shaded, with_edges);
+    }
+
+    #[test]
+    fn test_feature_edges_draw_over_fill() {
+        let Some(mut renderer) = test_renderer(64, 64) else {
+            eprintln!("No GPU adapter available, skipping");
+            return;
+        };
+
+        // The test cube's 12 edges are uploaded as line segments
+        assert_eq!(renderer.scene.as_ref().unwrap().num_edge_segments, 12);
+
+        renderer.set_render_mode(RenderMode::Shaded).unwrap();
+        let shaded = renderer.render_frame().unwrap();
+        renderer.set_render_mode(RenderMode::ShadedWithFeatureEdges).unwrap();
+        let with_edges = renderer.render_frame().unwrap();
+        assert_ne!(shaded, with_edges);
+    }
 }
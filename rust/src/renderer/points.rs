@@ -0,0 +1,375 @@
+//! Point Clouds
+//!
+//! Laser-scan points drawn with the model to check it against reality.
+//! Clouds are read from XYZ text or PLY (ASCII or binary) files and drawn
+//! unlit in their own colors. One-pixel points use a point list; GPUs
+//! rasterize point primitives a single pixel wide, so larger points are
+//! drawn as one instance per point that the point shader expands into a
+//! screen-space square `point_size_px` across, as lines are expanded into
+//! quads.
+
+use super::vertex::srgb_to_linear;
+use bytemuck::{Pod, Zeroable};
+use glam::{Mat4, Vec3};
+use std::path::Path;
+
+/// Default point size in pixels
+pub const DEFAULT_POINT_SIZE_PX: f32 = 1.0;
+
+/// Largest point accepted, in pixels
+pub const MAX_POINT_SIZE_PX: f32 = 32.0;
+
+/// Vertices drawn per point instance (two triangles)
+pub const VERTICES_PER_POINT: u32 = 6;
+
+/// Color of points whose file gives none
+const DEFAULT_POINT_COLOR: [f32; 4] = [0.8, 0.8, 0.8, 1.0];
+
+/// One point as uploaded to the GPU
+#[repr(C)]
+#[derive(Copy, Clone, Debug, PartialEq, Pod, Zeroable)]
+pub struct CloudPoint {
+    pub position: [f32; 3],
+    /// Linear RGBA
+    pub color: [f32; 4],
+}
+
+impl CloudPoint {
+    /// Buffer layout description for wgpu: per vertex for point lists, per
+    /// instance for expanded points
+    pub fn desc(step_mode: wgpu::VertexStepMode) -> wgpu::VertexBufferLayout<'static> {
+        const ATTRIBUTES: [wgpu::VertexAttribute; 2] = wgpu::vertex_attr_array![
+            0 => Float32x3,
+            1 => Float32x4,
+        ];
+        wgpu::VertexBufferLayout {
+            array_stride: std::mem::size_of::<CloudPoint>() as wgpu::BufferAddress,
+            step_mode,
+            attributes: &ATTRIBUTES,
+        }
+    }
+}
+
+/// A point cloud overlay
+#[derive(Debug, Clone, PartialEq)]
+pub struct PointCloud {
+    /// Positions (x, y, z triplets) in render coordinates (Y up, meters)
+    pub positions: Vec<f32>,
+    /// sRGB colors (r, g, b, a), 0.0-1.0
+    pub colors: Vec<f32>,
+    /// Alignment with the model: column-major 4x4 matrix, as model
+    /// transforms in the registry
+    pub transform: [f32; 16],
+    pub visible: bool,
+}
+
+impl PointCloud {
+    /// A cloud of points with colors (`colors` may be empty for the default
+    /// color)
+    pub fn new(positions: Vec<f32>, colors: Vec<f32>) -> Self {
+        let colors = if colors.len() / 4 == positions.len() / 3 {
+            colors
+        } else {
+            DEFAULT_POINT_COLOR.repeat(positions.len() / 3)
+        };
+        Self {
+            positions,
+            colors,
+            transform: Mat4::IDENTITY.to_cols_array(),
+            visible: true,
+        }
+    }
+
+    /// Read a `.ply` file, or any other file as XYZ text
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let data = std::fs::read(path).map_err(|e| format!("Failed to read point cloud: {}", e))?;
+        if path.extension().is_some_and(|e| e.eq_ignore_ascii_case("ply")) {
+            Self::parse_ply(&data)
+        } else {
+            Self::parse_xyz(&String::from_utf8_lossy(&data))
+        }
+    }
+
+    /// Parse XYZ text: one `x y z [r g b]` point per line, separated by
+    /// spaces or commas
+    ///
+    /// Colors are 0-255 unless every channel in the file is at most 1.
+    /// Blank lines and lines starting with `#` or `//` are skipped.
+    pub fn parse_xyz(text: &str) -> Result<Self, String> {
+        let mut positions = Vec::new();
+        let mut rgb = Vec::new();
+        for (line_number, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') || line.starts_with("//") {
+                continue;
+            }
+            let values = line
+                .split(|c: char| c == ',' || c.is_whitespace())
+                .filter(|v| !v.is_empty())
+                .map(str::parse::<f32>)
+                .collect::<Result<Vec<f32>, _>>()
+                .map_err(|_| format!("XYZ line {}: invalid number", line_number + 1))?;
+            if values.len() < 3 {
+                return Err(format!("XYZ line {}: expected x y z", line_number + 1));
+            }
+            positions.extend_from_slice(&values[..3]);
+            if values.len() >= 6 {
+                rgb.extend_from_slice(&values[3..6]);
+            }
+        }
+
+        let colors = if rgb.len() == positions.len() {
+            let scale = if rgb.iter().all(|&c| c <= 1.0) { 1.0 } else { 255.0 };
+            rgb.chunks_exact(3)
+                .flat_map(|c| [c[0] / scale, c[1] / scale, c[2] / scale, 1.0])
+                .collect()
+        } else {
+            Vec::new()
+        };
+        Ok(Self::new(positions, colors))
+    }
+
+    /// Parse a PLY file's vertices (ASCII or binary), with their `red`,
+    /// `green` and `blue` properties if present
+    pub fn parse_ply(data: &[u8]) -> Result<Self, String> {
+        let header_end = data
+            .windows(11)
+            .position(|w| w == b"end_header\n" || w == b"end_header\r")
+            .ok_or("PLY has no end_header")?;
+        let header = String::from_utf8_lossy(&data[..header_end]);
+        let mut body = &data[header_end + "end_header".len()..];
+        // The header line ends with \n or \r\n
+        body = body.strip_prefix(b"\r").unwrap_or(body);
+        body = body.strip_prefix(b"\n").unwrap_or(body);
+
+        let mut lines = header.lines().map(str::trim);
+        if lines.next() != Some("ply") {
+            return Err("Not a PLY file".to_string());
+        }
+        let mut format = None;
+        let mut vertex_count = None;
+        let mut properties: Vec<(String, PlyType)> = Vec::new();
+        // Reading the vertex element's properties
+        let mut in_vertex = false;
+        for line in lines {
+            let words: Vec<&str> = line.split_whitespace().collect();
+            match words.as_slice() {
+                ["format", kind, ..] => format = Some(kind.to_string()),
+                ["element", "vertex", count] if vertex_count.is_none() => {
+                    vertex_count = Some(count.parse::<usize>().map_err(|_| "Invalid PLY vertex count")?);
+                    in_vertex = true;
+                }
+                // Vertices must come first; later elements (faces) are ignored
+                ["element", ..] if vertex_count.is_none() => {
+                    return Err("PLY elements before the vertices are not supported".to_string());
+                }
+                ["element", ..] => in_vertex = false,
+                ["property", "list", ..] if in_vertex => {
+                    return Err("PLY vertex list properties are not supported".to_string());
+                }
+                ["property", kind, name] if in_vertex => properties.push((name.to_string(), PlyType::parse(kind)?)),
+                _ => {}
+            }
+        }
+        let count = vertex_count.ok_or("PLY has no vertex element")?;
+        let column = |name: &str| properties.iter().position(|(n, _)| n == name);
+        let (Some(x), Some(y), Some(z)) = (column("x"), column("y"), column("z")) else {
+            return Err("PLY vertices have no x, y, z".to_string());
+        };
+        let color_columns = [column("red"), column("green"), column("blue")];
+
+        let rows: Vec<Vec<f64>> = match format.as_deref() {
+            Some("ascii") => String::from_utf8_lossy(body)
+                .lines()
+                .filter(|l| !l.trim().is_empty())
+                .take(count)
+                .map(|l| l.split_whitespace().map(str::parse::<f64>).collect::<Result<Vec<f64>, _>>())
+                .collect::<Result<_, _>>()
+                .map_err(|_| "Invalid PLY vertex".to_string())?,
+            Some(kind @ ("binary_little_endian" | "binary_big_endian")) => {
+                let little = kind == "binary_little_endian";
+                let stride: usize = properties.iter().map(|(_, t)| t.size()).sum();
+                let data = body.get(..count * stride).ok_or("PLY vertex data is truncated")?;
+                data.chunks_exact(stride)
+                    .map(|row| {
+                        let mut at = 0;
+                        properties
+                            .iter()
+                            .map(|(_, t)| {
+                                let value = t.read(&row[at..at + t.size()], little);
+                                at += t.size();
+                                value
+                            })
+                            .collect()
+                    })
+                    .collect()
+            }
+            other => return Err(format!("Unsupported PLY format {:?}", other)),
+        };
+        if rows.len() < count || rows.iter().any(|r| r.len() < properties.len()) {
+            return Err("PLY vertex data is truncated".to_string());
+        }
+
+        let positions = rows.iter().flat_map(|r| [r[x] as f32, r[y] as f32, r[z] as f32]).collect();
+        let colors = match color_columns {
+            [Some(r), Some(g), Some(b)] => {
+                let scale = |k: usize| if properties[k].1.is_float() { 1.0 } else { 255.0 };
+                rows.iter()
+                    .flat_map(|row| [row[r] / scale(r), row[g] / scale(g), row[b] / scale(b), 1.0].map(|c| c as f32))
+                    .collect()
+            }
+            _ => Vec::new(),
+        };
+        Ok(Self::new(positions, colors))
+    }
+
+    /// Number of points
+    pub fn point_count(&self) -> usize {
+        self.positions.len() / 3
+    }
+
+    /// Points as uploaded: transformed, with colors converted to linear
+    /// light when the scene takes sRGB colors
+    pub fn points(&self, srgb_colors: bool) -> Vec<CloudPoint> {
+        let transform = Mat4::from_cols_array(&self.transform);
+        self.positions
+            .chunks_exact(3)
+            .zip(self.colors.chunks_exact(4))
+            .map(|(p, c)| {
+                let color = if srgb_colors {
+                    [srgb_to_linear(c[0]), srgb_to_linear(c[1]), srgb_to_linear(c[2]), c[3]]
+                } else {
+                    [c[0], c[1], c[2], c[3]]
+                };
+                CloudPoint {
+                    position: transform.transform_point3(Vec3::from_slice(p)).to_array(),
+                    color,
+                }
+            })
+            .collect()
+    }
+}
+
+/// Scalar type of a PLY property
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum PlyType {
+    I8,
+    U8,
+    I16,
+    U16,
+    I32,
+    U32,
+    F32,
+    F64,
+}
+
+impl PlyType {
+    fn parse(name: &str) -> Result<Self, String> {
+        Ok(match name {
+            "char" | "int8" => Self::I8,
+            "uchar" | "uint8" => Self::U8,
+            "short" | "int16" => Self::I16,
+            "ushort" | "uint16" => Self::U16,
+            "int" | "int32" => Self::I32,
+            "uint" | "uint32" => Self::U32,
+            "float" | "float32" => Self::F32,
+            "double" | "float64" => Self::F64,
+            other => return Err(format!("Unknown PLY property type '{}'", other)),
+        })
+    }
+
+    fn size(self) -> usize {
+        match self {
+            Self::I8 | Self::U8 => 1,
+            Self::I16 | Self::U16 => 2,
+            Self::I32 | Self::U32 | Self::F32 => 4,
+            Self::F64 => 8,
+        }
+    }
+
+    fn is_float(self) -> bool {
+        matches!(self, Self::F32 | Self::F64)
+    }
+
+    fn read(self, bytes: &[u8], little_endian: bool) -> f64 {
+        let mut buf = [0u8; 8];
+        buf[..bytes.len()].copy_from_slice(bytes);
+        if !little_endian {
+            buf[..bytes.len()].reverse();
+        }
+        match self {
+            Self::I8 => buf[0] as i8 as f64,
+            Self::U8 => buf[0] as f64,
+            Self::I16 => i16::from_le_bytes([buf[0], buf[1]]) as f64,
+            Self::U16 => u16::from_le_bytes([buf[0], buf[1]]) as f64,
+            Self::I32 => i32::from_le_bytes([buf[0], buf[1], buf[2], buf[3]]) as f64,
+            Self::U32 => u32::from_le_bytes([buf[0], buf[1], buf[2], buf[3]]) as f64,
+            Self::F32 => f32::from_le_bytes([buf[0], buf[1], buf[2], buf[3]]) as f64,
+            Self::F64 => f64::from_le_bytes(buf),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::renderer::test_renderer;
+
+    #[test]
+    fn test_xyz_cloud_loads_every_point() {
+        let path = std::env::temp_dir().join(format!("scan_{}.xyz", std::process::id()));
+        std::fs::write(&path, "# x y z r g b\n0 0 0 255 0 0\n1,0,0,0,255,0\n\n0 1 0 0 0 255\n0 0 1 255 255 255\n").unwrap();
+        let cloud = PointCloud::load(&path);
+        std::fs::remove_file(&path).unwrap();
+        let cloud = cloud.unwrap();
+        assert_eq!(cloud.point_count(), 4);
+        assert_eq!(&cloud.colors[4..8], &[0.0, 1.0, 0.0, 1.0]);
+        assert!(PointCloud::parse_xyz("0 0\n").is_err());
+        // No colors: the default one
+        assert_eq!(PointCloud::parse_xyz("0 0 0\n").unwrap().colors, DEFAULT_POINT_COLOR);
+
+        // PLY in ASCII and binary, vertices followed by faces
+        let ascii = "ply\nformat ascii 1.0\nelement vertex 2\nproperty float x\nproperty float y\nproperty float z\n\
+                     property uchar red\nproperty uchar green\nproperty uchar blue\nelement face 0\n\
+                     property list uchar int vertex_indices\nend_header\n1 2 3 255 0 0\n4 5 6 0 0 255\n";
+        let ply = PointCloud::parse_ply(ascii.as_bytes()).unwrap();
+        assert_eq!(ply.positions, [1.0, 2.0, 3.0, 4.0, 5.0, 6.0]);
+        assert_eq!(&ply.colors[..4], &[1.0, 0.0, 0.0, 1.0]);
+        let mut binary = b"ply\nformat binary_big_endian 1.0\nelement vertex 2\nproperty double x\n\
+                           property float y\nproperty short z\nend_header\n"
+            .to_vec();
+        for (x, y, z) in [(1.5f64, 2.5f32, -3i16), (4.0, 5.0, 6)] {
+            binary.extend(x.to_be_bytes());
+            binary.extend(y.to_be_bytes());
+            binary.extend(z.to_be_bytes());
+        }
+        assert_eq!(PointCloud::parse_ply(&binary).unwrap().positions, [1.5, 2.5, -3.0, 4.0, 5.0, 6.0]);
+        assert!(PointCloud::parse_ply(&binary[..binary.len() - 1]).is_err());
+
+        // The transform moves the points as uploaded
+        let mut moved = cloud.clone();
+        moved.transform = Mat4::from_translation(Vec3::new(10.0, 0.0, 0.0)).to_cols_array();
+        assert_eq!(moved.points(true)[1].position, [11.0, 0.0, 0.0]);
+        assert_eq!(moved.points(true)[1].color, [0.0, 1.0, 0.0, 1.0]);
+
+        // Larger points cover more of the frame
+        let Some(mut renderer) = test_renderer(64, 64) else {
+            eprintln!("No GPU adapter available, skipping");
+            return;
+        };
+        renderer.load_mesh(&[], &[], &[], &[]).unwrap();
+        renderer.add_point_cloud("scan", cloud).unwrap();
+        renderer.update_camera([2.0, 2.0, 4.0], [0.0, 0.0, 0.0]);
+        let covered = |renderer: &crate::renderer::Renderer| {
+            let pixels = renderer.render_frame().unwrap();
+            pixels.chunks_exact(4).filter(|p| p[..3] != pixels[..3]).count()
+        };
+        let small = covered(&renderer);
+        assert!(small > 0, "points not drawn");
+        renderer.set_point_size(5.0).unwrap();
+        assert!(covered(&renderer) > small * 4);
+        assert!(renderer.set_point_size(0.0).is_err());
+        renderer.set_point_cloud_visible("scan", false).unwrap();
+        assert_eq!(covered(&renderer), 0);
+    }
+}
@@ -2,9 +2,12 @@
 //!
 //! Manages offscreen rendering and frame generation.
 
-use super::{camera::Camera, pipeline::{RenderPipeline, RenderMode, MSAA_SAMPLE_COUNT}, vertex::Vertex};
+use super::{annotation::AnnotationMesh, camera::Camera, lines::{line_list_segments, triangle_edge_segments, LineSegment, DEFAULT_LINE_WIDTH_PX, VERTICES_PER_SEGMENT}, fxaa::FxaaPass, lod::LodSelection, occlusion::OcclusionCulling, overlay::{DrawingOverlay, OverlayQuad}, small_objects::SmallObjectCulling, points::{CloudPoint, DEFAULT_POINT_SIZE_PX, VERTICES_PER_POINT}, pipeline::{DrawPass, RenderPipeline, RenderMode, MSAA_SAMPLE_COUNT}, vertex::{vertices_from_arrays, Vertex}};
+use crate::bim::geometry::{feature_edges, DEFAULT_FEATURE_ANGLE_DEG};
 use bytemuck;
 use glam::Mat4;
+use serde::{Deserialize, Serialize};
+use std::sync::Mutex;
 
 /// Uniform buffer for camera matrices
 #[repr(C)]
@@ -13,6 +16,16 @@ pub struct CameraUniform {
     view_proj: [[f32; 4]; 4],
     camera_pos: [f32; 3],
     _padding: f32,
+    /// Render target size in pixels
+    viewport: [f32; 2],
+    line_width_px: f32,
+    point_size_px: f32,
+}
+
+impl Default for CameraUniform {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl CameraUniform {
@@ -21,6 +34,9 @@ impl CameraUniform {
             view_proj: Mat4::IDENTITY.to_cols_array_2d(),
             camera_pos: [0.0, 0.0, 0.0],
             _padding: 0.0,
+            viewport: [1.0, 1.0],
+            line_width_px: DEFAULT_LINE_WIDTH_PX,
+            point_size_px: DEFAULT_POINT_SIZE_PX,
         }
     }
 
@@ -28,6 +44,43 @@ impl CameraUniform {
         self.view_proj = camera.view_projection_matrix().to_cols_array_2d();
         self.camera_pos = camera.position();
     }
+
+    /// Set the render target size and the width lines are expanded to
+    pub fn set_lines(&mut self, width: u32, height: u32, line_width_px: f32) {
+        self.viewport = [width as f32, height as f32];
+        self.line_width_px = line_width_px;
+    }
+
+    /// Set the size point clouds are expanded to
+    pub fn set_point_size(&mut self, point_size_px: f32) {
+        self.point_size_px = point_size_px;
+    }
+}
+
+/// Tone mapping applied to lit colors before they are written out
+///
+/// Colors are mapped in linear space; the sRGB render target applies the
+/// gamma encoding afterwards, so the shader never gamma-corrects itself.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum ToneMapping {
+    /// Exposure only; values above 1.0 clip to white
+    #[default]
+    None,
+    /// c / (1 + c): soft roll-off, slightly desaturated highlights
+    Reinhard,
+    /// Narkowicz's ACES filmic fit: more contrast, brighter mid-tones
+    Aces,
+}
+
+impl ToneMapping {
+    /// Mode index as read by the fragment shader
+    fn shader_index(self) -> f32 {
+        match self {
+            ToneMapping::None => 0.0,
+            ToneMapping::Reinhard => 1.0,
+            ToneMapping::Aces => 2.0,
+        }
+    }
 }
 
 /// Uniform buffer for lighting
@@ -35,11 +88,19 @@ impl CameraUniform {
 #[derive(Debug, Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
 pub struct LightUniform {
     direction: [f32; 3],
-    _padding1: f32,
+    /// Linear multiplier on the lit color before tone mapping
+    exposure: f32,
     color: [f32; 3],
     intensity: f32,
     ambient: [f32; 3],
-    _padding2: f32,
+    /// ToneMapping::shader_index
+    tone_mapping: f32,
+}
+
+impl Default for LightUniform {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl LightUniform {
@@ -47,13 +108,13 @@ impl LightUniform {
         Self {
             // Light coming from upper-right-front
             direction: [0.5, 0.8, 0.3],
-            _padding1: 0.0,
+            exposure: 1.0,
             // Warm white light
             color: [1.0, 0.98, 0.95],
             intensity: 1.0,
             // Soft ambient
             ambient: [0.15, 0.17, 0.2],
-            _padding2: 0.0,
+            tone_mapping: ToneMapping::None.shader_index(),
         }
     }
 
@@ -76,6 +137,54 @@ impl LightUniform {
     pub fn set_ambient(&mut self, r: f32, g: f32, b: f32) {
         self.ambient = [r, g, b];
     }
+
+    pub fn set_exposure(&mut self, exposure: f32) {
+        self.exposure = exposure.max(0.0);
+    }
+
+    pub fn set_tone_mapping(&mut self, mode: ToneMapping) {
+        self.tone_mapping = mode.shader_index();
+    }
+
+    /// Current settings as a serializable config
+    pub fn config(&self) -> LightingConfig {
+        LightingConfig {
+            direction: self.direction,
+            color: self.color,
+            intensity: self.intensity,
+            ambient: self.ambient,
+        }
+    }
+
+    /// Apply all settings from a config (direction normalized, intensity clamped)
+    pub fn apply(&mut self, config: &LightingConfig) {
+        let [x, y, z] = config.direction;
+        self.set_direction(x, y, z);
+        let [r, g, b] = config.color;
+        self.set_color(r, g, b);
+        self.set_intensity(config.intensity);
+        let [r, g, b] = config.ambient;
+        self.set_ambient(r, g, b);
+    }
+}
+
+/// Directional and ambient light settings
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct LightingConfig {
+    /// Direction towards the light (normalized when applied)
+    pub direction: [f32; 3],
+    /// Light color (RGB, 0.0-1.0)
+    pub color: [f32; 3],
+    /// Light intensity (0.0+)
+    pub intensity: f32,
+    /// Ambient color (RGB, 0.0-1.0)
+    pub ambient: [f32; 3],
+}
+
+impl Default for LightingConfig {
+    fn default() -> Self {
+        LightUniform::new().config()
+    }
 }
 
 /// Uniform buffer for section plane
@@ -85,7 +194,14 @@ pub struct SectionPlaneUniform {
     origin: [f32; 3],
     enabled: f32, // 0.0 = disabled, 1.0 = enabled
     normal: [f32; 3],
-    _padding: f32,
+    outline_width: f32, // Pixels, 0.0 = no outline along the cut
+    outline_color: [f32; 4],
+}
+
+impl Default for SectionPlaneUniform {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl SectionPlaneUniform {
@@ -94,7 +210,8 @@ impl SectionPlaneUniform {
             origin: [0.0, 0.0, 0.0],
             enabled: 0.0,
             normal: [0.0, 1.0, 0.0],
-            _padding: 0.0,
+            outline_width: 0.0,
+            outline_color: [0.0, 0.0, 0.0, 1.0],
         }
     }
 
@@ -107,6 +224,65 @@ impl SectionPlaneUniform {
     pub fn disable(&mut self) {
         self.enabled = 0.0;
     }
+
+    /// Get the active plane as (origin, normal), or None when disabled
+    pub fn plane(&self) -> Option<([f32; 3], [f32; 3])> {
+        (self.enabled > 0.5).then_some((self.origin, self.normal))
+    }
+
+    /// Outline along the cut as (width in pixels, RGBA color); kept while
+    /// the plane is moved or disabled
+    pub fn set_outline(&mut self, width: f32, color: [f32; 4]) {
+        self.outline_width = width.max(0.0);
+        self.outline_color = color;
+    }
+
+    /// Outline along the cut as (width in pixels, RGBA color)
+    pub fn outline(&self) -> (f32, [f32; 4]) {
+        (self.outline_width, self.outline_color)
+    }
+}
+
+/// Uniform buffer for the clip box (fragments outside it are discarded)
+#[repr(C)]
+#[derive(Debug, Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct ClipBoxUniform {
+    min: [f32; 3],
+    enabled: f32, // 0.0 = disabled, 1.0 = enabled
+    max: [f32; 3],
+    _padding: f32,
+}
+
+impl Default for ClipBoxUniform {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ClipBoxUniform {
+    pub fn new() -> Self {
+        Self {
+            min: [0.0, 0.0, 0.0],
+            enabled: 0.0,
+            max: [0.0, 0.0, 0.0],
+            _padding: 0.0,
+        }
+    }
+
+    pub fn set(&mut self, min: [f32; 3], max: [f32; 3]) {
+        self.min = min;
+        self.max = max;
+        self.enabled = 1.0;
+    }
+
+    pub fn disable(&mut self) {
+        self.enabled = 0.0;
+    }
+
+    /// Get the active box as (min, max), or None when disabled
+    pub fn bounds(&self) -> Option<([f32; 3], [f32; 3])> {
+        (self.enabled > 0.5).then_some((self.min, self.max))
+    }
 }
 
 /// Scene renderer for offscreen rendering
@@ -119,17 +295,57 @@ pub struct SceneRenderer {
     pub light_uniform: LightUniform,
     pub section_plane_buffer: Option<wgpu::Buffer>,
     pub section_plane_uniform: SectionPlaneUniform,
+    pub clip_box_buffer: Option<wgpu::Buffer>,
+    pub clip_box_uniform: ClipBoxUniform,
     pub bind_group: Option<wgpu::BindGroup>,
+    /// Samples per pixel of the scene pass (1 = no MSAA)
+    pub msaa_samples: u32,
     pub msaa_texture: Option<wgpu::Texture>,    // MSAA render target
     pub color_texture: Option<wgpu::Texture>,   // Resolve target (for reading)
     pub depth_texture: Option<wgpu::Texture>,
     pub vertex_buffer: Option<wgpu::Buffer>,
     pub index_buffer: Option<wgpu::Buffer>,
     pub num_indices: u32,
+    // Every triangle edge of the uploaded mesh (wireframe and edge modes)
+    pub wire_segment_buffer: Option<wgpu::Buffer>,
+    pub num_wire_segments: u32,
+    // Feature edges of the uploaded mesh
+    pub edge_segment_buffer: Option<wgpu::Buffer>,
+    pub num_edge_segments: u32,
     pub render_mode: RenderMode,
+    /// Width of wireframe, edge and annotation lines in pixels
+    pub line_width_px: f32,
+    // Measurement annotation lines
+    pub annotation_segment_buffer: Option<wgpu::Buffer>,
+    pub num_annotation_segments: u32,
+    // Fills of closed annotations (triangle list)
+    pub annotation_fill_vertex_buffer: Option<wgpu::Buffer>,
+    pub annotation_fill_index_buffer: Option<wgpu::Buffer>,
+    pub num_annotation_fill_indices: u32,
+    // Visible drawing overlays, in draw order
+    pub overlay_quads: Vec<OverlayQuad>,
+    // Point cloud points (vertices of a point list, or instances)
+    pub point_buffer: Option<wgpu::Buffer>,
+    pub num_points: u32,
+    /// Size of point cloud points in pixels
+    pub point_size_px: f32,
+    /// Per-element occlusion queries for the fill pass (None draws everything)
+    pub occlusion: Option<OcclusionCulling>,
+    /// Per-element detail levels for the fill pass (None draws full detail)
+    pub lod: Option<LodSelection>,
+    /// Skipping of elements too small on screen (None draws them all)
+    pub small_objects: Option<SmallObjectCulling>,
+    /// Antialiasing post-process over the color texture (None reads the
+    /// color texture as rendered)
+    pub fxaa: Option<FxaaPass>,
+    /// Treat uploaded vertex colors as sRGB and linearize them (default);
+    /// false passes them to the shader unchanged
+    pub srgb_colors: bool,
     // Persistent read buffer to avoid allocation each frame
     pub read_buffer: Option<wgpu::Buffer>,
     pub padded_bytes_per_row: u32,
+    // Persistent de-padded pixels, reused by `render_frame` and `with_frame`
+    pixels: Mutex<Vec<u8>>,
 }
 
 impl SceneRenderer {
@@ -143,20 +359,43 @@ impl SceneRenderer {
             light_uniform: LightUniform::new(),
             section_plane_buffer: None,
             section_plane_uniform: SectionPlaneUniform::new(),
+            clip_box_buffer: None,
+            clip_box_uniform: ClipBoxUniform::new(),
             bind_group: None,
+            msaa_samples: MSAA_SAMPLE_COUNT,
             msaa_texture: None,
             color_texture: None,
             depth_texture: None,
             vertex_buffer: None,
             index_buffer: None,
             num_indices: 0,
+            wire_segment_buffer: None,
+            num_wire_segments: 0,
+            edge_segment_buffer: None,
+            num_edge_segments: 0,
             render_mode: RenderMode::default(),
+            line_width_px: DEFAULT_LINE_WIDTH_PX,
+            annotation_segment_buffer: None,
+            num_annotation_segments: 0,
+            annotation_fill_vertex_buffer: None,
+            annotation_fill_index_buffer: None,
+            num_annotation_fill_indices: 0,
+            overlay_quads: Vec::new(),
+            point_buffer: None,
+            num_points: 0,
+            point_size_px: DEFAULT_POINT_SIZE_PX,
+            occlusion: None,
+            lod: None,
+            small_objects: None,
+            fxaa: None,
+            srgb_colors: true,
             read_buffer: None,
             padded_bytes_per_row: 0,
+            pixels: Mutex::new(Vec::new()),
         }
     }
 
-    /// Set the render mode (shaded or wireframe)
+    /// Set the render mode (shaded, wireframe or shaded with edges)
     pub fn set_render_mode(&mut self, mode: RenderMode) {
         self.render_mode = mode;
     }
@@ -168,17 +407,8 @@ impl SceneRenderer {
 
     /// Initialize rendering resources
     pub fn initialize(&mut self, device: &wgpu::Device) {
-        self.initialize_with_features(device, false);
-    }
-
-    /// Initialize rendering resources with optional wireframe support
-    pub fn initialize_with_features(&mut self, device: &wgpu::Device, wireframe_supported: bool) {
         // Create render pipeline
-        let pipeline = RenderPipeline::new_with_features(
-            device,
-            wgpu::TextureFormat::Rgba8UnormSrgb,
-            wireframe_supported,
-        );
+        let pipeline = RenderPipeline::new(device, wgpu::TextureFormat::Rgba8UnormSrgb, self.msaa_samples);
 
         // Create camera uniform buffer
         let camera_uniform = CameraUniform::new();
@@ -202,7 +432,14 @@ impl SceneRenderer {
             usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
         });
 
-        // Create bind group with camera, light, and section plane
+        // Create clip box uniform buffer
+        let clip_box_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Clip Box Buffer"),
+            contents: bytemuck::cast_slice(&[self.clip_box_uniform]),
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+        });
+
+        // Create bind group with camera, light, section plane and clip box
         let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
             layout: &pipeline.camera_bind_group_layout,
             entries: &[
@@ -218,12 +455,16 @@ impl SceneRenderer {
                     binding: 2,
                     resource: section_plane_buffer.as_entire_binding(),
                 },
+                wgpu::BindGroupEntry {
+                    binding: 3,
+                    resource: clip_box_buffer.as_entire_binding(),
+                },
             ],
             label: Some("Scene Bind Group"),
         });
 
         // Create MSAA render target texture (only if MSAA enabled)
-        let msaa_texture = if MSAA_SAMPLE_COUNT > 1 {
+        let msaa_texture = if self.msaa_samples > 1 {
             Some(device.create_texture(&wgpu::TextureDescriptor {
                 label: Some("MSAA Texture"),
                 size: wgpu::Extent3d {
@@ -232,7 +473,7 @@ impl SceneRenderer {
                     depth_or_array_layers: 1,
                 },
                 mip_level_count: 1,
-                sample_count: MSAA_SAMPLE_COUNT,
+                sample_count: self.msaa_samples,
                 dimension: wgpu::TextureDimension::D2,
                 format: wgpu::TextureFormat::Rgba8UnormSrgb,
                 usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
@@ -254,7 +495,9 @@ impl SceneRenderer {
             sample_count: 1,
             dimension: wgpu::TextureDimension::D2,
             format: wgpu::TextureFormat::Rgba8UnormSrgb,
-            usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::COPY_SRC,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT
+                | wgpu::TextureUsages::COPY_SRC
+                | wgpu::TextureUsages::TEXTURE_BINDING,
             view_formats: &[],
         });
 
@@ -267,7 +510,7 @@ impl SceneRenderer {
                 depth_or_array_layers: 1,
             },
             mip_level_count: 1,
-            sample_count: if MSAA_SAMPLE_COUNT > 1 { MSAA_SAMPLE_COUNT } else { 1 },
+            sample_count: self.msaa_samples,
             dimension: wgpu::TextureDimension::D2,
             format: wgpu::TextureFormat::Depth32Float,
             usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
@@ -278,26 +521,58 @@ impl SceneRenderer {
         let bytes_per_pixel = 4u32;
         let unpadded_bytes_per_row = self.width * bytes_per_pixel;
         let align = wgpu::COPY_BYTES_PER_ROW_ALIGNMENT;
-        let padded_bytes_per_row = (unpadded_bytes_per_row + align - 1) / align * align;
-        let buffer_size = (padded_bytes_per_row * self.height) as u64;
-
-        let read_buffer = device.create_buffer(&wgpu::BufferDescriptor {
-            label: Some("Persistent Read Buffer"),
-            size: buffer_size,
-            usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
-            mapped_at_creation: false,
-        });
+        let padded_bytes_per_row = unpadded_bytes_per_row.div_ceil(align) * align;
+        self.padded_bytes_per_row = padded_bytes_per_row;
+        let read_buffer = self.create_read_buffer(device, "Persistent Read Buffer");
 
         self.pipeline = Some(pipeline);
         self.camera_buffer = Some(camera_buffer);
         self.light_buffer = Some(light_buffer);
         self.section_plane_buffer = Some(section_plane_buffer);
+        self.clip_box_buffer = Some(clip_box_buffer);
         self.bind_group = Some(bind_group);
         self.msaa_texture = msaa_texture;
         self.color_texture = Some(color_texture);
         self.depth_texture = Some(depth_texture);
         self.read_buffer = Some(read_buffer);
-        self.padded_bytes_per_row = padded_bytes_per_row;
+        self.fxaa = None;
+    }
+
+    /// Change the samples per pixel (after `initialize`), rebuilding the
+    /// pipelines and render targets; the mesh and settings are kept
+    ///
+    /// Every draw of the pass uses the multisampled depth target, and only
+    /// color is resolved: the frame read back, FXAA and overlays see the
+    /// single-sample color texture, and nothing reads depth back.
+    pub fn set_msaa_samples(&mut self, device: &wgpu::Device, samples: u32) {
+        let fxaa = self.fxaa.is_some();
+        let overlay_depth_bias = self.pipeline.as_ref().map(RenderPipeline::overlay_depth_bias);
+        self.msaa_samples = samples;
+        self.initialize(device);
+        self.set_fxaa(device, fxaa);
+        if let (Some(pipeline), Some(bias)) = (self.pipeline.as_mut(), overlay_depth_bias) {
+            if pipeline.overlay_depth_bias() != bias {
+                pipeline.set_overlay_depth_bias(device, bias);
+            }
+        }
+    }
+
+    /// Turn the FXAA post-process on or off (after `initialize`)
+    pub fn set_fxaa(&mut self, device: &wgpu::Device, enabled: bool) {
+        self.fxaa = match (enabled, &self.color_texture) {
+            (true, Some(color_texture)) => Some(FxaaPass::new(device, color_texture)),
+            _ => None,
+        };
+    }
+
+    /// Create a buffer frames can be copied into for readback
+    pub fn create_read_buffer(&self, device: &wgpu::Device, label: &str) -> wgpu::Buffer {
+        device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some(label),
+            size: (self.padded_bytes_per_row * self.height) as u64,
+            usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+            mapped_at_creation: false,
+        })
     }
 
     /// Upload mesh data to GPU from flat arrays (from ModelMesh)
@@ -309,20 +584,7 @@ impl SceneRenderer {
         colors: &[f32],      // r,g,b,a quads
         indices: &[u32],
     ) {
-        let vertex_count = vertices.len() / 3;
-        let mut vertex_data = Vec::with_capacity(vertex_count);
-
-        for i in 0..vertex_count {
-            let pos_idx = i * 3;
-            let col_idx = i * 4;
-
-            vertex_data.push(Vertex::new(
-                [vertices[pos_idx], vertices[pos_idx + 1], vertices[pos_idx + 2]],
-                [normals[pos_idx], normals[pos_idx + 1], normals[pos_idx + 2]],
-                [colors[col_idx], colors[col_idx + 1], colors[col_idx + 2], colors[col_idx + 3]],
-            ));
-        }
-
+        let vertex_data = vertices_from_arrays(vertices, normals, colors, self.srgb_colors);
         self.upload_mesh(device, &vertex_data, indices);
     }
 
@@ -353,6 +615,26 @@ impl SceneRenderer {
         self.light_uniform.set_ambient(r, g, b);
     }
 
+    /// Set exposure (linear multiplier, 0.0+)
+    pub fn set_exposure(&mut self, exposure: f32) {
+        self.light_uniform.set_exposure(exposure);
+    }
+
+    /// Set tone mapping mode
+    pub fn set_tone_mapping(&mut self, mode: ToneMapping) {
+        self.light_uniform.set_tone_mapping(mode);
+    }
+
+    /// Current light settings
+    pub fn lighting(&self) -> LightingConfig {
+        self.light_uniform.config()
+    }
+
+    /// Replace all light settings
+    pub fn set_lighting(&mut self, config: &LightingConfig) {
+        self.light_uniform.apply(config);
+    }
+
     /// Set section plane (or None to disable)
     pub fn set_section_plane(&mut self, plane: Option<([f32; 3], [f32; 3])>) {
         if let Some((origin, normal)) = plane {
@@ -369,8 +651,36 @@ impl SceneRenderer {
         }
     }
 
+    /// Set clip box as (min, max) corners (or None to disable)
+    pub fn set_clip_box(&mut self, bounds: Option<([f32; 3], [f32; 3])>) {
+        if let Some((min, max)) = bounds {
+            self.clip_box_uniform.set(min, max);
+        } else {
+            self.clip_box_uniform.disable();
+        }
+    }
+
+    /// Update clip box uniform buffer with current settings
+    pub fn update_clip_box(&self, queue: &wgpu::Queue) {
+        if let Some(buffer) = &self.clip_box_buffer {
+            queue.write_buffer(buffer, 0, bytemuck::cast_slice(&[self.clip_box_uniform]));
+        }
+    }
+
     /// Upload mesh data to GPU
     pub fn upload_mesh(&mut self, device: &wgpu::Device, vertices: &[Vertex], indices: &[u32]) {
+        self.upload_mesh_with_levels(device, vertices, indices, indices.len());
+    }
+
+    /// Upload mesh data whose first `drawn_indices` indices are the mesh
+    /// and the rest coarse element levels, only drawn through `lod`
+    pub fn upload_mesh_with_levels(
+        &mut self,
+        device: &wgpu::Device,
+        vertices: &[Vertex],
+        indices: &[u32],
+        drawn_indices: usize,
+    ) {
         let vertex_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
             label: Some("Vertex Buffer"),
             contents: bytemuck::cast_slice(vertices),
@@ -383,9 +693,133 @@ impl SceneRenderer {
             usage: wgpu::BufferUsages::INDEX,
         });
 
+        // Triangle edges for wireframe and edge modes, feature edges for
+        // the shaded-with-feature-edges mode
+        let indices = &indices[..drawn_indices];
+        let positions: Vec<f32> = vertices.iter().flat_map(|v| v.position).collect();
+        let edge_indices: Vec<u32> = feature_edges(&positions, indices, DEFAULT_FEATURE_ANGLE_DEG)
+            .into_iter()
+            .flatten()
+            .collect();
+        let wire_segments = triangle_edge_segments(vertices, indices);
+        let edge_segments = line_list_segments(vertices, &edge_indices);
+
         self.vertex_buffer = Some(vertex_buffer);
         self.index_buffer = Some(index_buffer);
         self.num_indices = indices.len() as u32;
+        (self.wire_segment_buffer, self.num_wire_segments) =
+            create_segment_buffer(device, &wire_segments, "Wireframe Segment Buffer");
+        (self.edge_segment_buffer, self.num_edge_segments) =
+            create_segment_buffer(device, &edge_segments, "Edge Segment Buffer");
+    }
+
+    /// Upload annotation lines and fills (replacing any previous ones)
+    pub fn upload_annotations(&mut self, device: &wgpu::Device, lines: &AnnotationMesh, fill: &AnnotationMesh) {
+        let normals = vec![0.0; lines.vertices.len()];
+        let vertices = vertices_from_arrays(&lines.vertices, &normals, &lines.colors, self.srgb_colors);
+        (self.annotation_segment_buffer, self.num_annotation_segments) = create_segment_buffer(
+            device,
+            &line_list_segments(&vertices, &lines.indices),
+            "Annotation Segment Buffer",
+        );
+        (
+            self.annotation_fill_vertex_buffer,
+            self.annotation_fill_index_buffer,
+            self.num_annotation_fill_indices,
+        ) = self.create_annotation_buffers(device, fill, "Annotation Fill");
+    }
+
+    /// Build the quads of drawing overlays (replacing any previous ones);
+    /// overlays without an image are skipped
+    pub fn upload_overlays(&mut self, device: &wgpu::Device, drawings: &[&DrawingOverlay]) {
+        self.overlay_quads = match &self.pipeline {
+            Some(pipeline) => drawings
+                .iter()
+                .filter_map(|drawing| OverlayQuad::new(device, drawing, &pipeline.drawing_bind_group_layout))
+                .collect(),
+            None => Vec::new(),
+        };
+    }
+
+    /// Upload point cloud points (replacing any previous ones)
+    pub fn upload_points(&mut self, device: &wgpu::Device, points: &[CloudPoint]) {
+        self.num_points = points.len() as u32;
+        self.point_buffer = (!points.is_empty()).then(|| {
+            device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                label: Some("Point Cloud Buffer"),
+                contents: bytemuck::cast_slice(points),
+                usage: wgpu::BufferUsages::VERTEX,
+            })
+        });
+    }
+
+    fn create_annotation_buffers(
+        &self,
+        device: &wgpu::Device,
+        mesh: &AnnotationMesh,
+        label: &str,
+    ) -> (Option<wgpu::Buffer>, Option<wgpu::Buffer>, u32) {
+        if mesh.indices.is_empty() {
+            return (None, None, 0);
+        }
+
+        let normals = vec![0.0; mesh.vertices.len()];
+        let vertices = vertices_from_arrays(&mesh.vertices, &normals, &mesh.colors, self.srgb_colors);
+        let vertex_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some(&format!("{} Vertex Buffer", label)),
+            contents: bytemuck::cast_slice(&vertices),
+            usage: wgpu::BufferUsages::VERTEX,
+        });
+        let index_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some(&format!("{} Index Buffer", label)),
+            contents: bytemuck::cast_slice(&mesh.indices),
+            usage: wgpu::BufferUsages::INDEX,
+        });
+        (Some(vertex_buffer), Some(index_buffer), mesh.indices.len() as u32)
+    }
+
+    /// GPU memory held by the scene's buffers and render targets, in bytes
+    pub fn gpu_bytes(&self) -> u64 {
+        let buffers = [
+            &self.camera_buffer,
+            &self.light_buffer,
+            &self.section_plane_buffer,
+            &self.clip_box_buffer,
+            &self.vertex_buffer,
+            &self.index_buffer,
+            &self.wire_segment_buffer,
+            &self.edge_segment_buffer,
+            &self.annotation_segment_buffer,
+            &self.annotation_fill_vertex_buffer,
+            &self.annotation_fill_index_buffer,
+            &self.point_buffer,
+            &self.read_buffer,
+        ];
+        let textures = [
+            self.msaa_texture.as_ref(),
+            self.color_texture.as_ref(),
+            self.depth_texture.as_ref(),
+            self.fxaa.as_ref().map(FxaaPass::output),
+        ];
+        let texture_bytes = |t: &wgpu::Texture| {
+            let texel = t.format().block_copy_size(Some(wgpu::TextureAspect::All)).unwrap_or(4);
+            let size = t.size();
+            (size.width * size.height * size.depth_or_array_layers * t.sample_count()) as u64 * texel as u64
+        };
+        let overlay_bytes: u64 = self
+            .overlay_quads
+            .iter()
+            .map(|quad| quad.vertex_buffer.size() + quad.index_buffer.size())
+            .sum();
+        buffers.into_iter().flatten().map(wgpu::Buffer::size).sum::<u64>()
+            + overlay_bytes
+            + textures.into_iter().flatten().map(texture_bytes).sum::<u64>()
+            + self.occlusion.as_ref().map_or(0, OcclusionCulling::buffer_bytes)
+    }
+
+    /// Size of a frame's RGBA pixel data in bytes
+    pub fn frame_len(&self) -> usize {
+        (self.width * self.height * 4) as usize
     }
 
     /// Render a frame and return pixel data
@@ -395,9 +829,74 @@ impl SceneRenderer {
         queue: &wgpu::Queue,
         camera: &Camera,
     ) -> Vec<u8> {
+        self.with_frame(device, queue, camera, <[u8]>::to_vec)
+    }
+
+    /// Render a frame into the persistent pixel buffer and lend it to `f`,
+    /// so nothing is allocated per frame
+    pub fn with_frame<T>(
+        &self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        camera: &Camera,
+        f: impl FnOnce(&[u8]) -> T,
+    ) -> T {
+        let mut pixels = self.pixels.lock().unwrap();
+        pixels.resize(self.frame_len(), 0);
+        self.render_frame_into(device, queue, camera, &mut pixels);
+        f(&pixels)
+    }
+
+    /// Lend the persistent pixel buffer to `f` as the last `with_frame` left
+    /// it, without rendering
+    pub fn last_frame<T>(&self, f: impl FnOnce(&[u8]) -> T) -> T {
+        f(&self.pixels.lock().unwrap())
+    }
+
+    /// Render a frame straight into `out`, which must be `frame_len()` bytes
+    pub fn render_frame_into(
+        &self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        camera: &Camera,
+        out: &mut [u8],
+    ) {
+        assert_eq!(out.len(), self.frame_len(), "frame buffer size");
+
+        // Use persistent read buffer
+        let read_buffer = self.read_buffer.as_ref().unwrap();
+        self.submit_frame(device, queue, camera, read_buffer);
+
+        // Wait for the pixels
+        let buffer_slice = read_buffer.slice(..);
+        let (sender, receiver) = std::sync::mpsc::channel();
+        buffer_slice.map_async(wgpu::MapMode::Read, move |result| {
+            sender.send(result).unwrap();
+        });
+        device.poll(wgpu::Maintain::Wait);
+        receiver.recv().unwrap().unwrap();
+        self.read_mapped_pixels_into(read_buffer, out);
+
+        // Visibility for the next frame
+        if let Some(occlusion) = &self.occlusion {
+            occlusion.read_results(device);
+        }
+    }
+
+    /// Render a frame and copy it into `read_buffer` (from
+    /// `create_read_buffer`), without waiting for the GPU
+    pub fn submit_frame(
+        &self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        camera: &Camera,
+        read_buffer: &wgpu::Buffer,
+    ) {
         // Update camera uniform
         let mut camera_uniform = CameraUniform::new();
         camera_uniform.update(camera);
+        camera_uniform.set_lines(self.width, self.height, self.line_width_px);
+        camera_uniform.set_point_size(self.point_size_px);
         queue.write_buffer(
             self.camera_buffer.as_ref().unwrap(),
             0,
@@ -456,7 +955,7 @@ impl SceneRenderer {
                     stencil_ops: None,
                 }),
                 timestamp_writes: None,
-                occlusion_query_set: None,
+                occlusion_query_set: self.occlusion.as_ref().map(|o| o.query_set()),
             });
 
             if let (Some(pipeline), Some(vb), Some(ib), Some(bg)) = (
@@ -465,24 +964,120 @@ impl SceneRenderer {
                 &self.index_buffer,
                 &self.bind_group,
             ) {
-                // Use the appropriate pipeline based on render mode
-                render_pass.set_pipeline(pipeline.get_pipeline(self.render_mode));
                 render_pass.set_bind_group(0, bg, &[]);
+
+                // One draw per pass of the render mode (e.g. fill, then edges)
+                for (pass, draw_pipeline) in pipeline.draw_pipelines(self.render_mode) {
+                    let segments = match pass {
+                        DrawPass::Fill => {
+                            render_pass.set_vertex_buffer(0, vb.slice(..));
+                            render_pass.set_index_buffer(ib.slice(..), wgpu::IndexFormat::Uint32);
+                            let viewport_height = self.height as f32;
+                            let mut lod_ranges = self.lod.as_ref().map(|lod| lod.index_ranges(camera, viewport_height));
+                            let skipped = match &self.small_objects {
+                                Some(small) => {
+                                    lod_ranges.get_or_insert_with(|| small.ranges().to_vec());
+                                    small.culled(camera, viewport_height)
+                                }
+                                None => Vec::new(),
+                            };
+                            match (&self.occlusion, &lod_ranges) {
+                                (Some(occlusion), _) => {
+                                    let ranges = lod_ranges.as_deref().unwrap_or(occlusion.ranges());
+                                    occlusion.draw(&mut render_pass, ranges, &skipped, draw_pipeline, &pipeline.occlusion_probe_pipeline);
+                                }
+                                (None, Some(ranges)) => {
+                                    render_pass.set_pipeline(draw_pipeline);
+                                    for (i, range) in ranges.iter().enumerate() {
+                                        if !skipped.get(i).copied().unwrap_or(false) {
+                                            render_pass.draw_indexed(range.clone(), 0, 0..1);
+                                        }
+                                    }
+                                }
+                                (None, None) => {
+                                    render_pass.set_pipeline(draw_pipeline);
+                                    render_pass.draw_indexed(0..self.num_indices, 0, 0..1);
+                                }
+                            }
+                            continue;
+                        }
+                        DrawPass::Lines | DrawPass::Edges => (&self.wire_segment_buffer, self.num_wire_segments),
+                        DrawPass::FeatureEdges => (&self.edge_segment_buffer, self.num_edge_segments),
+                    };
+                    if let (Some(buffer), count) = segments {
+                        render_pass.set_pipeline(draw_pipeline);
+                        render_pass.set_vertex_buffer(0, buffer.slice(..));
+                        render_pass.draw(0..VERTICES_PER_SEGMENT, 0..count);
+                    }
+                }
+            }
+
+            // Point clouds are depth tested against the model like it
+            if let (Some(pipeline), Some(points), Some(bg)) = (&self.pipeline, &self.point_buffer, &self.bind_group) {
+                render_pass.set_bind_group(0, bg, &[]);
+                render_pass.set_vertex_buffer(0, points.slice(..));
+                if self.point_size_px > 1.0 {
+                    render_pass.set_pipeline(&pipeline.point_quad_pipeline);
+                    render_pass.draw(0..VERTICES_PER_POINT, 0..self.num_points);
+                } else {
+                    render_pass.set_pipeline(&pipeline.point_pipeline);
+                    render_pass.draw(0..self.num_points, 0..1);
+                }
+            }
+
+            // Drawing overlays blend over the model and point clouds
+            if let (Some(pipeline), Some(bg)) = (&self.pipeline, &self.bind_group) {
+                render_pass.set_pipeline(&pipeline.drawing_pipeline);
+                render_pass.set_bind_group(0, bg, &[]);
+                for quad in &self.overlay_quads {
+                    render_pass.set_bind_group(1, &quad.bind_group, &[]);
+                    render_pass.set_vertex_buffer(0, quad.vertex_buffer.slice(..));
+                    render_pass.set_index_buffer(quad.index_buffer.slice(..), wgpu::IndexFormat::Uint32);
+                    render_pass.draw_indexed(0..6, 0, 0..1);
+                }
+            }
+
+            // Annotations go last so they are tested against the whole model;
+            // fills first so outlines stay crisp on top of them
+            if let (Some(pipeline), Some(vb), Some(ib), Some(bg)) = (
+                &self.pipeline,
+                &self.annotation_fill_vertex_buffer,
+                &self.annotation_fill_index_buffer,
+                &self.bind_group,
+            ) {
+                render_pass.set_bind_group(0, bg, &[]);
+                render_pass.set_pipeline(&pipeline.decal_pipeline);
                 render_pass.set_vertex_buffer(0, vb.slice(..));
                 render_pass.set_index_buffer(ib.slice(..), wgpu::IndexFormat::Uint32);
-                render_pass.draw_indexed(0..self.num_indices, 0, 0..1);
+                render_pass.draw_indexed(0..self.num_annotation_fill_indices, 0, 0..1);
+            }
+            if let (Some(pipeline), Some(segments), Some(bg)) = (
+                &self.pipeline,
+                &self.annotation_segment_buffer,
+                &self.bind_group,
+            ) {
+                render_pass.set_bind_group(0, bg, &[]);
+                render_pass.set_pipeline(&pipeline.annotation_pipeline);
+                render_pass.set_vertex_buffer(0, segments.slice(..));
+                render_pass.draw(0..VERTICES_PER_SEGMENT, 0..self.num_annotation_segments);
             }
         }
 
-        // Use persistent read buffer
-        let read_buffer = self.read_buffer.as_ref().unwrap();
-        let padded_bytes_per_row = self.padded_bytes_per_row;
-        let bytes_per_pixel = 4u32;
+        if let Some(occlusion) = &self.occlusion {
+            occlusion.resolve(&mut encoder);
+        }
+        if let Some(fxaa) = &self.fxaa {
+            fxaa.encode(&mut encoder);
+        }
 
         // Copy texture to buffer
+        let frame_texture = match &self.fxaa {
+            Some(fxaa) => fxaa.output(),
+            None => self.color_texture.as_ref().unwrap(),
+        };
         encoder.copy_texture_to_buffer(
             wgpu::ImageCopyTexture {
-                texture: self.color_texture.as_ref().unwrap(),
+                texture: frame_texture,
                 mip_level: 0,
                 origin: wgpu::Origin3d::ZERO,
                 aspect: wgpu::TextureAspect::All,
@@ -491,7 +1086,7 @@ impl SceneRenderer {
                 buffer: read_buffer,
                 layout: wgpu::ImageDataLayout {
                     offset: 0,
-                    bytes_per_row: Some(padded_bytes_per_row),
+                    bytes_per_row: Some(self.padded_bytes_per_row),
                     rows_per_image: Some(self.height),
                 },
             },
@@ -502,35 +1097,45 @@ impl SceneRenderer {
             },
         );
 
-        // Submit and wait
         queue.submit(std::iter::once(encoder.finish()));
+    }
 
-        // Read pixels from persistent buffer
-        let buffer_slice = read_buffer.slice(..);
-        let (sender, receiver) = std::sync::mpsc::channel();
-        buffer_slice.map_async(wgpu::MapMode::Read, move |result| {
-            sender.send(result).unwrap();
-        });
-        device.poll(wgpu::Maintain::Wait);
-        receiver.recv().unwrap().unwrap();
-
-        let data = buffer_slice.get_mapped_range();
+    /// Pixels (RGBA) of a frame in a mapped read buffer, which is unmapped
+    /// afterwards
+    pub fn read_mapped_pixels(&self, read_buffer: &wgpu::Buffer) -> Vec<u8> {
+        let mut pixels = vec![0; self.frame_len()];
+        self.read_mapped_pixels_into(read_buffer, &mut pixels);
+        pixels
+    }
 
-        // Remove padding and return pixel data
-        let mut pixels = Vec::with_capacity((self.width * self.height * 4) as usize);
-        for y in 0..self.height {
-            let start = (y * padded_bytes_per_row) as usize;
-            let end = start + (self.width * bytes_per_pixel) as usize;
-            pixels.extend_from_slice(&data[start..end]);
+    /// Copy the rows of a frame in a mapped read buffer into `out` without
+    /// their padding, then unmap the buffer
+    fn read_mapped_pixels_into(&self, read_buffer: &wgpu::Buffer, out: &mut [u8]) {
+        let row_len = (self.width * 4) as usize;
+        let data = read_buffer.slice(..).get_mapped_range();
+        for (y, row) in out.chunks_exact_mut(row_len).enumerate() {
+            let start = y * self.padded_bytes_per_row as usize;
+            row.copy_from_slice(&data[start..start + row_len]);
         }
 
         // Must drop the mapped range before unmapping
         drop(data);
         read_buffer.unmap();
-
-        pixels
     }
 }
 
 // Need to add buffer init descriptor
 use wgpu::util::DeviceExt;
+
+/// Instance buffer of line segments (None when there are none)
+fn create_segment_buffer(device: &wgpu::Device, segments: &[LineSegment], label: &str) -> (Option<wgpu::Buffer>, u32) {
+    if segments.is_empty() {
+        return (None, 0);
+    }
+    let buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+        label: Some(label),
+        contents: bytemuck::cast_slice(segments),
+        usage: wgpu::BufferUsages::VERTEX,
+    });
+    (Some(buffer), segments.len() as u32)
+}
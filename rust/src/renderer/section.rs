@@ -0,0 +1,271 @@
+//! Section Plane Helpers
+//!
+//! Builds section planes (origin + normal) from axis positions or picked
+//! surfaces, for use with `Renderer::set_section_plane`.
+
+use super::camera::ray_mesh_intersect;
+use glam::Vec3;
+
+/// World axis for axis-aligned section cuts
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Axis {
+    X,
+    Y,
+    Z,
+}
+
+impl Axis {
+    /// Axis from index (X=0, Y=1, Z=2)
+    pub fn from_index(index: i32) -> Option<Axis> {
+        match index {
+            0 => Some(Axis::X),
+            1 => Some(Axis::Y),
+            2 => Some(Axis::Z),
+            _ => None,
+        }
+    }
+
+    /// Component index of this axis
+    pub fn index(self) -> usize {
+        match self {
+            Axis::X => 0,
+            Axis::Y => 1,
+            Axis::Z => 2,
+        }
+    }
+
+    /// Unit vector along the positive axis
+    pub fn unit(self) -> [f32; 3] {
+        let mut unit = [0.0; 3];
+        unit[self.index()] = 1.0;
+        unit
+    }
+}
+
+/// Axis-aligned section plane at `position` along `axis`
+/// Returns (origin, normal); geometry on the positive side is kept
+pub fn section_plane_axis(axis: Axis, position: f32) -> ([f32; 3], [f32; 3]) {
+    let mut origin = [0.0; 3];
+    origin[axis.index()] = position;
+    (origin, axis.unit())
+}
+
+/// Section plane through the first surface hit by a ray
+///
+/// The plane passes through the hit point, aligned with the hit face and
+/// oriented away from the viewer, so the picked face and everything behind
+/// it stay visible. `vertices` are x,y,z triplets, `indices` a triangle list.
+/// Returns (origin, normal), or None if the ray hits nothing.
+pub fn section_plane_from_ray(
+    ray_origin: Vec3,
+    ray_dir: Vec3,
+    vertices: &[f32],
+    indices: &[u32],
+) -> Option<([f32; 3], [f32; 3])> {
+    let hit = ray_mesh_intersect(ray_origin, ray_dir, vertices, indices)?;
+    section_plane_at_hit(ray_origin, ray_dir, vertices, indices, hit)
+}
+
+/// Section plane through a known ray hit, given as (distance, triangle index)
+/// into the mesh, e.g. from a BVH; see `section_plane_from_ray`
+pub fn section_plane_at_hit(
+    ray_origin: Vec3,
+    ray_dir: Vec3,
+    vertices: &[f32],
+    indices: &[u32],
+    (t, triangle): (f32, usize),
+) -> Option<([f32; 3], [f32; 3])> {
+    let corner = |k: usize| -> Vec3 {
+        let i = indices[triangle * 3 + k] as usize * 3;
+        Vec3::from_slice(&vertices[i..i + 3])
+    };
+    let face_normal = (corner(1) - corner(0)).cross(corner(2) - corner(0));
+
+    let mut normal = face_normal.normalize_or_zero();
+    if normal == Vec3::ZERO {
+        return None;
+    }
+    if normal.dot(ray_dir) < 0.0 {
+        normal = -normal;
+    }
+
+    let hit = ray_origin + ray_dir * t;
+    Some((hit.to_array(), normal.to_array()))
+}
+
+/// Whether the section plane and clip box cut away all of a bounding box,
+/// as the fragment shader would (the plane keeps its positive side, the box
+/// its inside)
+pub fn box_fully_clipped(
+    min: Vec3,
+    max: Vec3,
+    section_plane: Option<([f32; 3], [f32; 3])>,
+    clip_box: Option<([f32; 3], [f32; 3])>,
+) -> bool {
+    if let Some((origin, normal)) = section_plane {
+        // The corner farthest along the normal is the last one kept
+        let normal = Vec3::from_array(normal);
+        let corner = Vec3::select(normal.cmpge(Vec3::ZERO), max, min);
+        if (corner - Vec3::from_array(origin)).dot(normal) < 0.0 {
+            return true;
+        }
+    }
+    if let Some((box_min, box_max)) = clip_box {
+        if max.cmplt(Vec3::from_array(box_min)).any() || min.cmpgt(Vec3::from_array(box_max)).any() {
+            return true;
+        }
+    }
+    false
+}
+
+/// A section plane moving along an axis in fixed steps
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SectionSweep {
+    pub axis: Axis,
+    pub from: f32,
+    pub to: f32,
+    /// Number of frames, including both ends (at least 1)
+    pub steps: u32,
+}
+
+impl SectionSweep {
+    pub fn new(axis: Axis, from: f32, to: f32, steps: u32) -> Self {
+        Self {
+            axis,
+            from,
+            to,
+            steps: steps.max(1),
+        }
+    }
+
+    /// Sweep across a bounding box, with `from`/`to` defaulting to its
+    /// extent along the axis
+    pub fn across_bounds(
+        axis: Axis,
+        min: [f32; 3],
+        max: [f32; 3],
+        from: Option<f32>,
+        to: Option<f32>,
+        steps: u32,
+    ) -> Self {
+        let i = axis.index();
+        Self::new(axis, from.unwrap_or(min[i]), to.unwrap_or(max[i]), steps)
+    }
+
+    /// Plane position at a step (the last step lands exactly on `to`)
+    pub fn position(&self, step: u32) -> f32 {
+        if step + 1 >= self.steps {
+            return self.to;
+        }
+        let t = step as f32 / (self.steps - 1) as f32;
+        self.from + (self.to - self.from) * t
+    }
+
+    /// Section plane (origin, normal) at a step
+    pub fn plane(&self, step: u32) -> ([f32; 3], [f32; 3]) {
+        section_plane_axis(self.axis, self.position(step))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bim::geometry::generate_box_with_normals;
+
+    #[test]
+    fn test_section_plane_axis() {
+        assert_eq!(section_plane_axis(Axis::X, 2.5), ([2.5, 0.0, 0.0], [1.0, 0.0, 0.0]));
+        assert_eq!(section_plane_axis(Axis::Y, 3.0), ([0.0, 3.0, 0.0], [0.0, 1.0, 0.0]));
+        assert_eq!(section_plane_axis(Axis::Z, -1.0), ([0.0, 0.0, -1.0], [0.0, 0.0, 1.0]));
+        assert_eq!(Axis::from_index(1), Some(Axis::Y));
+        assert_eq!(Axis::from_index(3), None);
+    }
+
+    #[test]
+    fn test_section_sweep_reaches_end() {
+        let sweep = SectionSweep::new(Axis::Y, -1.0, 7.3, 10);
+        assert_eq!(sweep.position(0), -1.0);
+        assert_eq!(sweep.plane(9), ([0.0, 7.3, 0.0], [0.0, 1.0, 0.0]));
+
+        // Positions advance monotonically
+        for step in 1..10 {
+            assert!(sweep.position(step) > sweep.position(step - 1));
+        }
+
+        // Defaults come from the bounds; a single step jumps to the end
+        let sweep = SectionSweep::across_bounds(Axis::X, [2.0, 0.0, 0.0], [5.0, 1.0, 1.0], None, None, 0);
+        assert_eq!((sweep.from, sweep.to, sweep.steps), (2.0, 5.0, 1));
+        assert_eq!(sweep.position(0), 5.0);
+    }
+
+    #[test]
+    fn test_render_section_sweep_step() {
+        let Some(mut renderer) = crate::renderer::test_renderer(32, 32) else {
+            eprintln!("No GPU adapter available, skipping");
+            return;
+        };
+
+        let sweep = SectionSweep::new(Axis::Z, -2.0, 0.5, 4);
+        for step in 0..sweep.steps {
+            let frame = renderer.render_section_step(&sweep, step).unwrap();
+            assert_eq!(frame.len(), 32 * 32 * 4);
+        }
+        assert_eq!(renderer.section_plane(), Some(([0.0, 0.0, 0.5], [0.0, 0.0, 1.0])));
+    }
+
+    #[test]
+    fn test_section_outline_antialiases_cut_boundary() {
+        let Some(mut renderer) = crate::renderer::test_renderer(64, 64) else {
+            eprintln!("No GPU adapter available, skipping");
+            return;
+        };
+        let mesh = generate_box_with_normals([0.0, 0.0, 0.0], [2.0, 2.0, 2.0], [0.8, 0.6, 0.4, 1.0]);
+        renderer.load_mesh(&mesh.vertices, &mesh.normals, &mesh.colors, &mesh.indices).unwrap();
+        renderer.update_camera([4.0, 3.0, 6.0], [0.0, 0.0, 0.0]);
+        // Keep the part below a slightly tilted cut, so it crosses pixel rows
+        renderer.set_section_plane(Some(([0.0, 0.3, 0.0], [0.1, -1.0, 0.0]))).unwrap();
+
+        // Without an outline the cut is a hard edge: flat faces and background
+        let hard = renderer.render_frame().unwrap();
+        let hard_colors: std::collections::HashSet<&[u8]> = hard.chunks_exact(4).collect();
+        assert!(hard_colors.len() <= 4, "{} colors", hard_colors.len());
+
+        renderer.set_section_outline(2.0, [0.0, 0.0, 0.0, 1.0]).unwrap();
+        assert_eq!(renderer.section_outline(), Some((2.0, [0.0, 0.0, 0.0, 1.0])));
+        let outlined = renderer.render_frame().unwrap();
+        let black = outlined.chunks_exact(4).filter(|p| p[..3] == [0, 0, 0]).count();
+        assert!(black > 0, "outline not drawn");
+        let blended = outlined
+            .chunks_exact(4)
+            .filter(|p| p[..3] != [0, 0, 0] && !hard_colors.contains(p))
+            .count();
+        assert!(blended > 0, "no intermediate pixels along the cut");
+
+        // Moving the plane keeps the outline; width 0 turns it off
+        renderer.set_section_plane(None).unwrap();
+        renderer.set_section_plane(Some(([0.0, 0.3, 0.0], [0.1, -1.0, 0.0]))).unwrap();
+        assert_eq!(renderer.render_frame().unwrap(), outlined);
+        renderer.set_section_outline(0.0, [0.0, 0.0, 0.0, 1.0]).unwrap();
+        assert_eq!(renderer.render_frame().unwrap(), hard);
+    }
+
+    #[test]
+    fn test_section_plane_from_ray() {
+        // Slab with its top face at y = 1
+        let slab = generate_box_with_normals([0.0, 0.5, 0.0], [4.0, 1.0, 4.0], [1.0; 4]);
+
+        // Looking straight down onto the top face
+        let (origin, normal) = section_plane_from_ray(
+            Vec3::new(0.5, 10.0, 0.5),
+            Vec3::NEG_Y,
+            &slab.vertices,
+            &slab.indices,
+        )
+        .unwrap();
+        assert!((origin[1] - 1.0).abs() < 1e-5);
+        assert_eq!(normal, [0.0, -1.0, 0.0]);
+
+        // Missing the slab entirely
+        assert!(section_plane_from_ray(Vec3::new(10.0, 10.0, 0.0), Vec3::NEG_Y, &slab.vertices, &slab.indices).is_none());
+    }
+}
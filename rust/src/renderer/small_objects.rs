@@ -0,0 +1,145 @@
+//! Small Object Culling
+//!
+//! Skips elements too small on screen to matter: bolts, fixtures and other
+//! details that cover less than a few pixels. Every frame each element's
+//! bounding sphere is projected with the camera, as for LOD selection, and
+//! elements below the threshold are left out of the fill pass. Pinned
+//! elements (selected or isolated ones) are always drawn.
+
+use super::camera::Camera;
+use glam::Vec3;
+use std::collections::HashSet;
+use std::ops::Range;
+
+/// Per-element size culling for the current camera
+#[derive(Debug, Clone, PartialEq)]
+pub struct SmallObjectCulling {
+    /// Bounding sphere of each element (center, radius)
+    spheres: Vec<(Vec3, f32)>,
+    /// Index buffer range of each element
+    ranges: Vec<Range<u32>>,
+    /// Projected size in pixels below which elements are skipped
+    min_pixels: f32,
+    /// Elements drawn whatever their size
+    pinned: HashSet<usize>,
+}
+
+impl SmallObjectCulling {
+    /// Cull elements given by bounds and triangle ranges (one each) below
+    /// `min_pixels` across, except the `pinned` ones
+    pub fn new(
+        bounds: &[([f32; 3], [f32; 3])],
+        triangle_ranges: &[Range<u32>],
+        min_pixels: f32,
+        pinned: HashSet<usize>,
+    ) -> Result<Self, String> {
+        if bounds.len() != triangle_ranges.len() {
+            return Err(format!(
+                "{} element bounds for {} element ranges",
+                bounds.len(),
+                triangle_ranges.len()
+            ));
+        }
+        validate_min_pixels(min_pixels)?;
+        let spheres = bounds
+            .iter()
+            .map(|&(min, max)| {
+                let (min, max) = (Vec3::from(min), Vec3::from(max));
+                ((min + max) / 2.0, (max - min).max(Vec3::ZERO).length() / 2.0)
+            })
+            .collect();
+        Ok(Self {
+            spheres,
+            ranges: triangle_ranges.iter().map(|r| r.start * 3..r.end * 3).collect(),
+            min_pixels,
+            pinned,
+        })
+    }
+
+    /// Index buffer range of each element
+    pub fn ranges(&self) -> &[Range<u32>] {
+        &self.ranges
+    }
+
+    /// Whether an element is skipped as seen by `camera`
+    pub fn is_culled(&self, element: usize, camera: &Camera, viewport_height: f32) -> bool {
+        let (center, radius) = self.spheres[element];
+        !self.pinned.contains(&element) && camera.projected_size_px(center, radius, viewport_height) < self.min_pixels
+    }
+
+    /// Whether each element is skipped as seen by `camera`
+    pub fn culled(&self, camera: &Camera, viewport_height: f32) -> Vec<bool> {
+        (0..self.spheres.len()).map(|k| self.is_culled(k, camera, viewport_height)).collect()
+    }
+}
+
+/// Check a small object threshold: a positive number of pixels
+pub fn validate_min_pixels(min_pixels: f32) -> Result<(), String> {
+    if !(min_pixels.is_finite() && min_pixels > 0.0) {
+        return Err(format!("Invalid small object threshold: {} px (must be positive)", min_pixels));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::renderer::{generate_test_cube, test_renderer};
+
+    #[test]
+    fn test_small_element_culled_far_away_and_drawn_up_close() {
+        // A 10 cm bolt and a 4 m slab, 12 triangles each
+        let bounds = [([-0.05; 3], [0.05; 3]), ([-2.0, -0.1, -2.0], [2.0, 0.1, 2.0])];
+        let ranges = [0..12, 12..24];
+        let culling = SmallObjectCulling::new(&bounds, &ranges, 4.0, HashSet::new()).unwrap();
+        let mut camera = Camera::default();
+        camera.set_target([0.0, 0.0, 0.0]);
+
+        camera.set_position([0.0, 0.0, 100.0]);
+        assert!(culling.is_culled(0, &camera, 512.0));
+        assert!(!culling.is_culled(1, &camera, 512.0));
+        assert_eq!(culling.culled(&camera, 512.0), [true, false]);
+        assert_eq!(culling.ranges(), [0..36, 36..72]);
+
+        // Zoomed in, the bolt is back
+        camera.set_position([0.0, 0.0, 3.0]);
+        assert!(!culling.is_culled(0, &camera, 512.0));
+        assert_eq!(culling.culled(&camera, 512.0), [false, false]);
+
+        // A pinned element is drawn at any distance
+        let pinned = SmallObjectCulling::new(&bounds, &ranges, 4.0, HashSet::from([0])).unwrap();
+        camera.set_position([0.0, 0.0, 1000.0]);
+        assert!(!pinned.is_culled(0, &camera, 512.0));
+
+        assert!(SmallObjectCulling::new(&bounds, &ranges[..1], 4.0, HashSet::new()).is_err());
+        assert!(SmallObjectCulling::new(&bounds, &ranges, 0.0, HashSet::new()).is_err());
+
+        // The renderer leaves the culled cube out of the frame
+        let Some(mut renderer) = test_renderer(64, 64) else {
+            eprintln!("No GPU adapter available, skipping");
+            return;
+        };
+        let (vertices, indices) = generate_test_cube();
+        let positions: Vec<f32> = vertices.iter().flat_map(|v| v.position).collect();
+        let normals: Vec<f32> = vertices.iter().flat_map(|v| v.normal).collect();
+        let colors: Vec<f32> = vertices.iter().flat_map(|v| v.color).collect();
+        renderer.load_mesh(&positions, &normals, &colors, &indices).unwrap();
+        let triangles = 0..indices.len() as u32 / 3;
+        renderer.set_element_ranges(vec![triangles]).unwrap();
+        renderer.set_element_bounds(vec![([-1.0; 3], [1.0; 3])]).unwrap();
+        let drawn = |renderer: &crate::renderer::Renderer| {
+            let pixels = renderer.render_frame().unwrap();
+            pixels.chunks_exact(4).any(|p| p[..3] != pixels[..3])
+        };
+        // A few pixels across
+        renderer.update_camera([0.0, 0.0, 100.0], [0.0, 0.0, 0.0]);
+        assert!(drawn(&renderer), "cube not drawn without culling");
+        renderer.set_small_object_culling(Some(16.0)).unwrap();
+        assert!(!drawn(&renderer), "small cube drawn");
+        renderer.update_camera([0.0, 0.0, 5.0], [0.0, 0.0, 0.0]);
+        assert!(drawn(&renderer), "cube not drawn up close");
+        renderer.update_camera([0.0, 0.0, 100.0], [0.0, 0.0, 0.0]);
+        renderer.set_pinned_elements(vec![0]).unwrap();
+        assert!(drawn(&renderer), "pinned cube culled");
+    }
+}
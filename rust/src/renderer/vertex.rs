@@ -55,6 +55,37 @@ impl Vertex {
     }
 }
 
+/// Convert an sRGB-encoded color channel (0.0-1.0) to linear light
+pub fn srgb_to_linear(c: f32) -> f32 {
+    if c <= 0.04045 {
+        c / 12.92
+    } else {
+        ((c + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+/// Interleave flat position/normal/color arrays into vertices
+///
+/// With `srgb_colors`, RGB is treated as sRGB-encoded and converted to linear
+/// so lighting happens in linear space (the sRGB render target re-encodes
+/// it). Alpha is always linear.
+pub fn vertices_from_arrays(
+    vertices: &[f32], // x,y,z triplets
+    normals: &[f32],  // x,y,z triplets
+    colors: &[f32],   // r,g,b,a quads
+    srgb_colors: bool,
+) -> Vec<Vertex> {
+    vertices
+        .chunks_exact(3)
+        .zip(normals.chunks_exact(3))
+        .zip(colors.chunks_exact(4))
+        .map(|((p, n), c)| {
+            let rgb = |i: usize| if srgb_colors { srgb_to_linear(c[i]) } else { c[i] };
+            Vertex::new([p[0], p[1], p[2]], [n[0], n[1], n[2]], [rgb(0), rgb(1), rgb(2), c[3]])
+        })
+        .collect()
+}
+
 /// Generate a test cube mesh
 pub fn generate_test_cube() -> (Vec<Vertex>, Vec<u32>) {
     let vertices = vec![
@@ -101,3 +132,31 @@ pub fn generate_test_cube() -> (Vec<Vertex>, Vec<u32>) {
 
     (vertices, indices)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_srgb_gray_is_linearized_in_vertex_data() {
+        let positions = [0.0, 0.0, 0.0];
+        let normals = [0.0, 1.0, 0.0];
+        let colors = [0.5, 0.5, 0.5, 0.5];
+
+        // sRGB 0.5 (128/255-ish mid gray) is about 21.4% linear light
+        let linear = vertices_from_arrays(&positions, &normals, &colors, true);
+        for channel in &linear[0].color[..3] {
+            assert!((channel - 0.21404).abs() < 1e-4, "{}", channel);
+        }
+        assert_eq!(linear[0].color[3], 0.5);
+
+        // The toggle keeps colors as authored
+        let raw = vertices_from_arrays(&positions, &normals, &colors, false);
+        assert_eq!(raw[0].color, [0.5, 0.5, 0.5, 0.5]);
+
+        // Endpoints and the linear toe
+        assert_eq!(srgb_to_linear(0.0), 0.0);
+        assert!((srgb_to_linear(1.0) - 1.0).abs() < 1e-6);
+        assert!((srgb_to_linear(0.04) - 0.04 / 12.92).abs() < 1e-7);
+    }
+}
@@ -0,0 +1,228 @@
+//! Camera Walkthroughs
+//!
+//! Flythrough paths through camera waypoints: positions follow a Catmull-Rom
+//! spline through every waypoint, and the look-at target eases between
+//! consecutive waypoint targets.
+
+use super::camera::{Camera, CameraState};
+use glam::Vec3;
+
+/// A camera flythrough along waypoints
+#[derive(Debug, Clone, PartialEq)]
+pub struct CameraPath {
+    pub waypoints: Vec<CameraState>,
+    /// Seconds from each waypoint to the next (one fewer than waypoints)
+    pub durations: Vec<f32>,
+}
+
+impl CameraPath {
+    /// Check the path has waypoints and one positive duration per leg
+    pub fn validate(&self) -> Result<(), String> {
+        if self.waypoints.is_empty() {
+            return Err("Camera path has no waypoints".to_string());
+        }
+        if self.durations.len() + 1 != self.waypoints.len() {
+            return Err(format!(
+                "Camera path needs {} durations for {} waypoints, got {}",
+                self.waypoints.len() - 1,
+                self.waypoints.len(),
+                self.durations.len()
+            ));
+        }
+        if let Some(d) = self.durations.iter().find(|d| !d.is_finite() || **d <= 0.0) {
+            return Err(format!("Invalid leg duration: {}", d));
+        }
+        Ok(())
+    }
+
+    /// Total playback time in seconds
+    pub fn total_duration(&self) -> f32 {
+        self.durations.iter().sum()
+    }
+
+    /// Camera position and target at a time (clamped to the path)
+    ///
+    /// Returns None for an invalid path.
+    pub fn sample_state(&self, t_seconds: f32) -> Option<CameraState> {
+        self.validate().ok()?;
+        if self.waypoints.len() == 1 {
+            return Some(self.waypoints[0]);
+        }
+
+        // Find the leg containing t and the fraction along it
+        let mut remaining = t_seconds.max(0.0);
+        let mut leg = 0;
+        while leg + 1 < self.durations.len() && remaining >= self.durations[leg] {
+            remaining -= self.durations[leg];
+            leg += 1;
+        }
+        let u = (remaining / self.durations[leg]).clamp(0.0, 1.0);
+
+        // End waypoints are repeated so the spline passes through them
+        let last = self.waypoints.len() - 1;
+        let point = |i: isize| -> Vec3 {
+            Vec3::from_array(self.waypoints[i.clamp(0, last as isize) as usize].position)
+        };
+        let i = leg as isize;
+        let position = catmull_rom(point(i - 1), point(i), point(i + 1), point(i + 2), u);
+
+        let from = Vec3::from_array(self.waypoints[leg].target);
+        let to = Vec3::from_array(self.waypoints[leg + 1].target);
+        let eased = u * u * (3.0 - 2.0 * u);
+        let target = from.lerp(to, eased);
+
+        Some(CameraState {
+            position: position.to_array(),
+            target: target.to_array(),
+        })
+    }
+
+    /// Camera at a time along the path, with default projection settings
+    pub fn sample(&self, t_seconds: f32) -> Option<Camera> {
+        let state = self.sample_state(t_seconds)?;
+        Some(Camera::new(
+            Vec3::from_array(state.position),
+            Vec3::from_array(state.target),
+        ))
+    }
+
+    /// Sample times for playback at `fps`, ending exactly at the last waypoint
+    pub fn frame_times(&self, fps: u32) -> Vec<f32> {
+        let total = self.total_duration();
+        let frames = (total * fps.max(1) as f32).ceil() as u32;
+        (0..frames)
+            .map(|frame| frame as f32 / fps.max(1) as f32)
+            .chain(std::iter::once(total))
+            .collect()
+    }
+
+    /// Path time of frame `frame` of `frame_count` spread evenly from the
+    /// first waypoint to the last (both included), so any frame of a
+    /// sequence can be rendered on its own
+    pub fn sequence_time(&self, frame: u32, frame_count: u32) -> f32 {
+        if frame_count <= 1 {
+            return 0.0;
+        }
+        self.total_duration() * frame.min(frame_count - 1) as f32 / (frame_count - 1) as f32
+    }
+}
+
+/// File name of a frame of an exported sequence (`frame_00042.png`), so the
+/// files sort in playback order
+pub fn sequence_frame_name(frame: u32) -> String {
+    format!("frame_{:05}.png", frame)
+}
+
+/// Uniform Catmull-Rom spline between p1 (u = 0) and p2 (u = 1)
+fn catmull_rom(p0: Vec3, p1: Vec3, p2: Vec3, p3: Vec3, u: f32) -> Vec3 {
+    let u2 = u * u;
+    let u3 = u2 * u;
+    0.5 * ((2.0 * p1)
+        + (p2 - p0) * u
+        + (2.0 * p0 - 5.0 * p1 + 4.0 * p2 - p3) * u2
+        + (3.0 * p1 - p0 - 3.0 * p2 + p3) * u3)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn state(position: [f32; 3], target: [f32; 3]) -> CameraState {
+        CameraState { position, target }
+    }
+
+    #[test]
+    fn test_sampling_at_waypoint_times_returns_waypoints() {
+        let path = CameraPath {
+            waypoints: vec![
+                state([0.0, 2.0, 10.0], [0.0, 1.0, 0.0]),
+                state([10.0, 2.0, 10.0], [5.0, 1.0, 0.0]),
+                state([10.0, 5.0, 0.0], [5.0, 1.0, -5.0]),
+            ],
+            durations: vec![2.0, 3.0],
+        };
+        assert_eq!(path.total_duration(), 5.0);
+
+        for (time, waypoint) in [0.0, 2.0, 5.0].into_iter().zip(&path.waypoints) {
+            let sampled = path.sample_state(time).unwrap();
+            for k in 0..3 {
+                assert!((sampled.position[k] - waypoint.position[k]).abs() < 1e-4);
+                assert!((sampled.target[k] - waypoint.target[k]).abs() < 1e-4);
+            }
+        }
+
+        // Halfway along the first leg, between its waypoints
+        let camera = path.sample(1.0).unwrap();
+        assert!(camera.position()[0] > 0.0 && camera.position()[0] < 10.0);
+
+        // Clamped outside the path
+        assert_eq!(path.sample_state(-1.0), path.sample_state(0.0));
+        assert_eq!(path.sample_state(99.0), path.sample_state(5.0));
+
+        let times = path.frame_times(10);
+        assert_eq!(times.len(), 51);
+        assert_eq!(*times.last().unwrap(), 5.0);
+    }
+
+    #[test]
+    fn test_sequence_writes_requested_png_frames() {
+        let path = CameraPath {
+            waypoints: vec![state([0.0, 2.0, 8.0], [0.0; 3]), state([8.0, 2.0, 0.0], [0.0; 3])],
+            durations: vec![4.0],
+        };
+        assert_eq!([0, 2, 4].map(|frame| path.sequence_time(frame, 5)), [0.0, 2.0, 4.0]);
+        assert_eq!(path.sequence_time(0, 1), 0.0);
+        assert_eq!(sequence_frame_name(7), "frame_00007.png");
+
+        let Some(mut renderer) = crate::renderer::test_renderer(48, 32) else {
+            eprintln!("No GPU adapter available, skipping");
+            return;
+        };
+        let (vertices, indices) = crate::renderer::generate_test_cube();
+        let positions: Vec<f32> = vertices.iter().flat_map(|v| v.position).collect();
+        let normals: Vec<f32> = vertices.iter().flat_map(|v| v.normal).collect();
+        let colors: Vec<f32> = vertices.iter().flat_map(|v| v.color).collect();
+        renderer.load_mesh(&positions, &normals, &colors, &indices).unwrap();
+        let camera = renderer.camera.state();
+
+        let dir = std::env::temp_dir().join(format!("sequence_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        for frame in 0..5 {
+            let png = renderer.render_sequence_frame(&path, frame, 5).unwrap();
+            std::fs::write(dir.join(sequence_frame_name(frame)), png).unwrap();
+        }
+        let mut files: Vec<_> = std::fs::read_dir(&dir).unwrap().map(|e| e.unwrap().path()).collect();
+        files.sort();
+        assert_eq!(files.len(), 5);
+        for file in &files {
+            let image = image::open(file).unwrap();
+            assert_eq!((image.width(), image.height()), (48, 32));
+        }
+
+        // Frames render the same out of order and leave the camera alone
+        let again = renderer.render_sequence_frame(&path, 2, 5).unwrap();
+        assert_eq!(again, std::fs::read(&files[2]).unwrap());
+        assert_ne!(std::fs::read(&files[0]).unwrap(), std::fs::read(&files[4]).unwrap());
+        assert_eq!(renderer.camera.state(), camera);
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert!(renderer.render_sequence_frame(&path, 0, 0).is_err());
+    }
+
+    #[test]
+    fn test_invalid_paths_are_rejected() {
+        let one = state([0.0; 3], [1.0; 3]);
+        let path = CameraPath {
+            waypoints: vec![one, one],
+            durations: vec![],
+        };
+        assert!(path.validate().is_err());
+        assert!(path.sample_state(0.0).is_none());
+
+        let path = CameraPath {
+            waypoints: vec![one, one],
+            durations: vec![0.0],
+        };
+        assert!(path.validate().is_err());
+        assert!(CameraPath { waypoints: vec![], durations: vec![] }.validate().is_err());
+    }
+}